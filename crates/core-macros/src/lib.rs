@@ -1,26 +1,49 @@
 extern crate proc_macro;
 use proc_macro::TokenStream;
 use quote::quote;
+use syn::parse::Parse;
+use syn::parse::ParseStream;
 use syn::parse_macro_input;
+use syn::LitInt;
 use syn::LitStr;
+use syn::Token;
 
-#[proc_macro]
-pub fn sc(input: TokenStream) -> TokenStream {
-  let input_lit = parse_macro_input!(input as LitStr);
-  let s = input_lit.value();
+struct ScInput {
+  text: LitStr,
+  width: Option<LitInt>,
+}
 
-  // Check each character
-  for c in s.chars() {
-    if !c.is_ascii() {
-      let msg = format!(
-        "Unsupported character: '{}'. Only ASCII characters are known to have a Unicode width of 1. Don't use this macro in this case.",
-        c
-      );
-      return syn::Error::new(input_lit.span(), msg).to_compile_error().into();
-    }
+impl Parse for ScInput {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let text = input.parse()?;
+    let width = if input.is_empty() {
+      None
+    } else {
+      input.parse::<Token![,]>()?;
+      Some(input.parse()?)
+    };
+    Ok(ScInput { text, width })
   }
+}
+
+/// Creates a `&'static StringContainer` with its width computed at compile time.
+///
+/// Use `sc!("text", width)` to provide the display width explicitly for text
+/// where the computed width differs from how it's displayed (ex. emoji sequences).
+#[proc_macro]
+pub fn sc(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as ScInput);
+  let s = input.text.value();
 
-  let char_count = s.len() as u32;
+  let char_count = match &input.width {
+    Some(width) => match width.base10_parse::<u32>() {
+      Ok(width) => width,
+      Err(err) => return err.to_compile_error().into(),
+    },
+    // this should match how dprint_core measures strings at runtime
+    None if s.is_ascii() => s.len() as u32,
+    None => unicode_width::UnicodeWidthStr::width(s.as_str()) as u32,
+  };
 
   TokenStream::from(quote! {
     {
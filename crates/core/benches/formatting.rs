@@ -0,0 +1,73 @@
+//! Measures generating and printing a large number of print items, which
+//! is dominated by allocation for large files.
+//!
+//! Run with `cargo bench -p dprint-core --bench formatting`.
+
+use criterion::black_box;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+use criterion::Throughput;
+use dprint_core::formatting::condition_resolvers;
+use dprint_core::formatting::conditions::if_true_or;
+use dprint_core::formatting::ir_helpers::new_line_group;
+use dprint_core::formatting::ir_helpers::with_indent;
+use dprint_core::formatting::PrintItems;
+use dprint_core::formatting::PrintOptions;
+use dprint_core::formatting::Signal;
+
+const STATEMENT_COUNT: usize = 50_000;
+
+fn bench_formatting(c: &mut Criterion) {
+  let mut group = c.benchmark_group("formatting");
+  group.throughput(Throughput::Elements(STATEMENT_COUNT as u64));
+  group.bench_function("static strings", |b| {
+    b.iter(|| format(|| gen_statements(STATEMENT_COUNT, |i| if i % 2 == 0 { "value".into() } else { "other".into() })))
+  });
+  group.bench_function("owned strings", |b| {
+    b.iter(|| format(|| gen_statements(STATEMENT_COUNT, |i| format!("value{}", i).into())))
+  });
+  group.finish();
+}
+
+fn format(get_print_items: impl FnOnce() -> PrintItems) -> String {
+  black_box(dprint_core::formatting::format(
+    get_print_items,
+    PrintOptions {
+      max_width: 80,
+      indent_width: 2,
+      use_tabs: false,
+      smart_tabs: false,
+      new_line_text: "\n",
+    },
+  ))
+}
+
+fn gen_statements(count: usize, get_text: impl Fn(usize) -> PrintItems) -> PrintItems {
+  let mut items = PrintItems::new();
+  for i in 0..count {
+    let mut statement = PrintItems::new();
+    statement.push_str_runtime_width_computed("const a = [");
+    let mut elements = PrintItems::new();
+    for j in 0..5 {
+      if j > 0 {
+        elements.push_str_runtime_width_computed(",");
+        elements.push_signal(Signal::SpaceOrNewLine);
+      }
+      elements.extend(get_text(i + j));
+    }
+    statement.extend(new_line_group(with_indent(elements)));
+    statement.push_condition(if_true_or(
+      "closeBracketOnNewLine",
+      condition_resolvers::is_start_of_line_indented(),
+      "]".into(),
+      "];".into(),
+    ));
+    items.extend(statement);
+    items.push_signal(Signal::NewLine);
+  }
+  items
+}
+
+criterion_group!(benches, bench_formatting);
+criterion_main!(benches);
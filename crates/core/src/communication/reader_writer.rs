@@ -4,14 +4,31 @@ use std::io::Result;
 use std::io::Write;
 
 const SUCCESS_BYTES: &[u8; 4] = &[255, 255, 255, 255];
+/// Set on the size of sized bytes that are lz4 compressed.
+const COMPRESSED_SIZE_FLAG: u32 = 1 << 31;
+
+/// The default size in bytes at which sized bytes are compressed
+/// once compression has been negotiated.
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 64 * 1024;
 
 pub struct MessageReader<TRead: Read + Unpin> {
   reader: TRead,
+  is_compression_enabled: bool,
 }
 
 impl<TRead: Read + Unpin> MessageReader<TRead> {
   pub fn new(reader: TRead) -> Self {
-    Self { reader }
+    Self {
+      reader,
+      is_compression_enabled: false,
+    }
+  }
+
+  /// Allows reading sized bytes compressed by a `MessageWriter` with compression enabled.
+  ///
+  /// This should only be done once both sides have agreed to use compression.
+  pub fn enable_compression(&mut self) {
+    self.is_compression_enabled = true;
   }
 
   /// Reads a u32 value.
@@ -23,8 +40,13 @@ impl<TRead: Read + Unpin> MessageReader<TRead> {
 
   /// Reads a u32 value followed by a buffer.
   pub fn read_sized_bytes(&mut self) -> Result<Vec<u8>> {
-    let size = self.read_u32()? as usize;
-    self.read_bytes(size)
+    let size = self.read_u32()?;
+    if self.is_compression_enabled && size & COMPRESSED_SIZE_FLAG != 0 {
+      let compressed_bytes = self.read_bytes((size & !COMPRESSED_SIZE_FLAG) as usize)?;
+      lz4_flex::decompress_size_prepended(&compressed_bytes).map_err(|err| std::io::Error::new(ErrorKind::InvalidData, err))
+    } else {
+      self.read_bytes(size as usize)
+    }
   }
 
   #[allow(clippy::read_zero_byte_vec)]
@@ -61,11 +83,22 @@ impl<TRead: Read + Unpin> MessageReader<TRead> {
 
 pub struct MessageWriter<TWrite: Write + Unpin> {
   writer: TWrite,
+  compression_threshold: Option<usize>,
 }
 
 impl<TWrite: Write + Unpin> MessageWriter<TWrite> {
   pub fn new(writer: TWrite) -> Self {
-    Self { writer }
+    Self {
+      writer,
+      compression_threshold: None,
+    }
+  }
+
+  /// Compresses sized bytes that are at least the provided number of bytes.
+  ///
+  /// This should only be done once both sides have agreed to use compression.
+  pub fn enable_compression(&mut self, threshold: usize) {
+    self.compression_threshold = Some(threshold);
   }
 
   pub fn send_u32(&mut self, value: u32) -> Result<()> {
@@ -74,6 +107,18 @@ impl<TWrite: Write + Unpin> MessageWriter<TWrite> {
   }
 
   pub fn send_sized_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+    if let Some(threshold) = self.compression_threshold {
+      if bytes.len() >= threshold {
+        let compressed_bytes = lz4_flex::compress_prepend_size(bytes);
+        // don't bother when it doesn't help (ex. already compressed data)
+        if compressed_bytes.len() < bytes.len() {
+          self.send_u32(compressed_bytes.len() as u32 | COMPRESSED_SIZE_FLAG)?;
+          self.writer.write_all(&compressed_bytes)?;
+          return Ok(());
+        }
+      }
+    }
+
     self.send_u32(bytes.len() as u32)?;
     if !bytes.is_empty() {
       self.writer.write_all(bytes)?;
@@ -91,3 +136,38 @@ impl<TWrite: Write + Unpin> MessageWriter<TWrite> {
     self.writer.flush()
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_compress_sized_bytes_above_threshold() {
+    let large_bytes = "const value = 5;\n".repeat(1_000).into_bytes();
+    let small_bytes = b"small".to_vec();
+    let mut writer = MessageWriter::new(Vec::new());
+    writer.enable_compression(1024);
+    writer.send_sized_bytes(&large_bytes).unwrap();
+    writer.send_sized_bytes(&small_bytes).unwrap();
+    writer.send_success_bytes().unwrap();
+    let written_bytes = writer.writer;
+    assert!(written_bytes.len() < large_bytes.len());
+
+    let mut reader = MessageReader::new(written_bytes.as_slice());
+    reader.enable_compression();
+    assert_eq!(reader.read_sized_bytes().unwrap(), large_bytes);
+    assert_eq!(reader.read_sized_bytes().unwrap(), small_bytes);
+    reader.read_success_bytes().unwrap();
+  }
+
+  #[test]
+  fn should_not_compress_when_not_enabled() {
+    let large_bytes = "a".repeat(2048).into_bytes();
+    let mut writer = MessageWriter::new(Vec::new());
+    writer.send_sized_bytes(&large_bytes).unwrap();
+    assert_eq!(writer.writer.len(), large_bytes.len() + 4);
+
+    let mut reader = MessageReader::new(writer.writer.as_slice());
+    assert_eq!(reader.read_sized_bytes().unwrap(), large_bytes);
+  }
+}
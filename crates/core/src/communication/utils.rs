@@ -3,6 +3,12 @@ use std::collections::HashMap;
 use std::rc::Rc;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+use tokio::time::MissedTickBehavior;
+
+use crate::async_runtime::JoinHandle;
 
 #[derive(Default)]
 pub struct AtomicFlag(AtomicBool);
@@ -51,12 +57,33 @@ impl<T> Drop for RcIdStoreGuard<'_, T> {
   }
 }
 
+struct RcIdStoreItem<T> {
+  data: T,
+  expires_at: Option<Instant>,
+}
+
+impl<T> RcIdStoreItem<T> {
+  fn is_expired(&self, now: Instant) -> bool {
+    self.expires_at.map(|expires_at| expires_at <= now).unwrap_or(false)
+  }
+}
+
 /// A store keyed by id.
-pub struct RcIdStore<T>(Rc<RefCell<HashMap<u32, T>>>);
+///
+/// Entries may optionally expire so that ids the other side never
+/// responds to don't accumulate in long running processes. Expired
+/// entries are no longer returned and are removed by `sweep_expired`.
+pub struct RcIdStore<T> {
+  items: Rc<RefCell<HashMap<u32, RcIdStoreItem<T>>>>,
+  ttl: Option<Duration>,
+}
 
 impl<T> Default for RcIdStore<T> {
   fn default() -> Self {
-    Self(Default::default())
+    Self {
+      items: Default::default(),
+      ttl: None,
+    }
   }
 }
 
@@ -65,8 +92,26 @@ impl<T> RcIdStore<T> {
     Default::default()
   }
 
+  /// Creates a store where entries expire after the provided duration.
+  pub fn with_ttl(ttl: Duration) -> Self {
+    Self {
+      items: Default::default(),
+      ttl: Some(ttl),
+    }
+  }
+
   pub fn store(&self, message_id: u32, data: T) {
-    self.0.borrow_mut().insert(message_id, data);
+    self.store_item(message_id, data, self.ttl);
+  }
+
+  /// Stores the data with an expiry that overrides the store's time to live.
+  pub fn store_with_ttl(&self, message_id: u32, data: T, ttl: Duration) {
+    self.store_item(message_id, data, Some(ttl));
+  }
+
+  fn store_item(&self, message_id: u32, data: T, ttl: Option<Duration>) {
+    let expires_at = ttl.map(|ttl| Instant::now() + ttl);
+    self.items.borrow_mut().insert(message_id, RcIdStoreItem { data, expires_at });
   }
 
   pub fn store_with_guard(&self, message_id: u32, data: T) -> RcIdStoreGuard<'_, T> {
@@ -83,18 +128,65 @@ impl<T> RcIdStore<T> {
   }
 
   pub fn take(&self, message_id: u32) -> Option<T> {
-    self.0.borrow_mut().remove(&message_id)
+    let item = self.items.borrow_mut().remove(&message_id)?;
+    if item.is_expired(Instant::now()) {
+      None
+    } else {
+      Some(item.data)
+    }
+  }
+
+  /// Gets if the store has no entries, including ones that expired but haven't been swept.
+  pub fn is_empty(&self) -> bool {
+    self.items.borrow().is_empty()
   }
 
   pub fn take_all(&self) -> HashMap<u32, T> {
-    let mut map = self.0.borrow_mut();
-    std::mem::take(&mut *map)
+    let mut map = self.items.borrow_mut();
+    std::mem::take(&mut *map).into_iter().map(|(id, item)| (id, item.data)).collect()
+  }
+
+  /// Removes and returns the expired entries.
+  pub fn sweep_expired(&self) -> Vec<(u32, T)> {
+    sweep_expired_items(&self.items)
+  }
+
+  /// Periodically removes expired entries until the store is dropped.
+  pub fn start_sweep_task(&self, interval: Duration) -> JoinHandle<()>
+  where
+    T: 'static,
+  {
+    let items = Rc::downgrade(&self.items);
+    crate::async_runtime::spawn(async move {
+      let mut interval = tokio::time::interval(interval);
+      interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+      loop {
+        interval.tick().await;
+        let Some(items) = items.upgrade() else {
+          return; // store was dropped
+        };
+        sweep_expired_items(&items);
+      }
+    })
   }
 }
 
+fn sweep_expired_items<T>(items: &RefCell<HashMap<u32, RcIdStoreItem<T>>>) -> Vec<(u32, T)> {
+  let now = Instant::now();
+  let mut items = items.borrow_mut();
+  let expired_ids = items.iter().filter(|(_, item)| item.is_expired(now)).map(|(id, _)| *id).collect::<Vec<_>>();
+  expired_ids.into_iter().filter_map(|id| items.remove(&id).map(|item| (id, item.data))).collect()
+}
+
 impl<T: Clone> RcIdStore<T> {
   pub fn get_cloned(&self, message_id: u32) -> Option<T> {
-    self.0.borrow().get(&message_id).cloned()
+    let items = self.items.borrow();
+    let item = items.get(&message_id)?;
+    if item.is_expired(Instant::now()) {
+      None
+    } else {
+      Some(item.data.clone())
+    }
   }
 }
 
@@ -102,6 +194,40 @@ impl<T: Clone> RcIdStore<T> {
 // https://github.com/rust-lang/rust/issues/26925
 impl<T> Clone for RcIdStore<T> {
   fn clone(&self) -> Self {
-    Self(self.0.clone())
+    Self {
+      items: self.items.clone(),
+      ttl: self.ttl,
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_expire_entries() {
+    let store = RcIdStore::with_ttl(Duration::from_secs(60));
+    store.store(1, "first");
+    store.store_with_ttl(2, "second", Duration::ZERO);
+    store.store_with_ttl(3, "third", Duration::ZERO);
+
+    assert_eq!(store.get_cloned(1), Some("first"));
+    assert_eq!(store.get_cloned(2), None);
+    assert_eq!(store.take(3), None);
+
+    let mut expired = store.sweep_expired();
+    expired.sort();
+    assert_eq!(expired, vec![(2, "second")]);
+    assert!(store.sweep_expired().is_empty());
+    assert_eq!(store.take(1), Some("first"));
+  }
+
+  #[test]
+  fn should_not_expire_entries_without_ttl() {
+    let store = RcIdStore::new();
+    store.store(1, "first");
+    assert!(store.sweep_expired().is_empty());
+    assert_eq!(store.take_all().len(), 1);
   }
 }
@@ -34,9 +34,113 @@ macro_rules! generate_str_to_from {
                 }
             }
         }
+
+        impl $crate::configuration::ConfigurationEnum for $enum_name {
+            const STRING_VALUES: &'static [&'static str] = &[$($string_value),*];
+        }
     };
 }
 
+/// An enum that may be parsed from one of a known set of strings.
+///
+/// This is implemented by `generate_str_to_from!`.
+pub trait ConfigurationEnum: std::str::FromStr {
+  /// The strings the enum may be parsed from.
+  const STRING_VALUES: &'static [&'static str];
+}
+
+/// A size in bytes that may be parsed from text like "80kb" or "1.5mb".
+///
+/// Units are case insensitive and multiples of 1024 bytes (b, kb, mb, gb).
+/// Numbers without a unit are bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+  const UNITS: [(&'static str, u64); 4] = [("gb", 1024 * 1024 * 1024), ("mb", 1024 * 1024), ("kb", 1024), ("b", 1)];
+
+  pub fn as_u64(&self) -> u64 {
+    self.0
+  }
+}
+
+impl std::str::FromStr for ByteSize {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (value, unit) = split_number_and_unit(s).ok_or_else(|| format!("Found invalid size '{}'. Expected a value like 80kb or 2mb.", s))?;
+    let multiplier = if unit.is_empty() {
+      1
+    } else {
+      match ByteSize::UNITS.iter().find(|(name, _)| unit.eq_ignore_ascii_case(name)) {
+        Some((_, multiplier)) => *multiplier,
+        None => return Err(format!("Found invalid size unit '{}'. Expected one of: b, kb, mb, gb.", unit)),
+      }
+    };
+    Ok(ByteSize((value * multiplier as f64).round() as u64))
+  }
+}
+
+impl std::fmt::Display for ByteSize {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for (name, multiplier) in ByteSize::UNITS {
+      let count = self.0 / multiplier;
+      if count > 0 && count * multiplier == self.0 {
+        return write!(f, "{}{}", count, name);
+      }
+    }
+    write!(f, "{}b", self.0)
+  }
+}
+
+/// A duration that may be parsed from text like "30s" or "500ms".
+///
+/// Supported units are ms, s, m, and h. A unit is required.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ConfigDuration(pub std::time::Duration);
+
+impl ConfigDuration {
+  const UNITS: [(&'static str, u64); 4] = [("h", 60 * 60 * 1000), ("m", 60 * 1000), ("s", 1000), ("ms", 1)];
+
+  pub fn as_duration(&self) -> std::time::Duration {
+    self.0
+  }
+}
+
+impl std::str::FromStr for ConfigDuration {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (value, unit) = split_number_and_unit(s).ok_or_else(|| format!("Found invalid duration '{}'. Expected a value like 30s or 500ms.", s))?;
+    match ConfigDuration::UNITS.iter().find(|(name, _)| unit.eq_ignore_ascii_case(name)) {
+      Some((_, millis)) => Ok(ConfigDuration(std::time::Duration::from_millis((value * *millis as f64).round() as u64))),
+      None if unit.is_empty() => Err(format!("Found invalid duration '{}'. Expected a unit (ms, s, m, or h).", s)),
+      None => Err(format!("Found invalid duration unit '{}'. Expected one of: ms, s, m, h.", unit)),
+    }
+  }
+}
+
+impl std::fmt::Display for ConfigDuration {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let millis = self.0.as_millis() as u64;
+    for (name, multiplier) in ConfigDuration::UNITS {
+      let count = millis / multiplier;
+      if count > 0 && count * multiplier == millis {
+        return write!(f, "{}{}", count, name);
+      }
+    }
+    write!(f, "{}ms", millis)
+  }
+}
+
+/// Splits text like "1.5mb" into its non-negative number and unit.
+fn split_number_and_unit(text: &str) -> Option<(f64, &str)> {
+  let text = text.trim();
+  let unit_start = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+  let value = text[..unit_start].parse::<f64>().ok()?;
+  Some((value, text[unit_start..].trim_start()))
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Copy, Serialize, Deserialize, Hash)]
 pub enum RawNewLineKind {
   /// Decide which newline kind to use based on the last newline in the file.
@@ -76,14 +180,94 @@ pub enum NewLineKind {
 
 generate_str_to_from![NewLineKind, [Auto, "auto"], [LineFeed, "lf"], [CarriageReturnLineFeed, "crlf"]];
 
+/// How severe a configuration diagnostic is.
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug, Default, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigurationDiagnosticSeverity {
+  /// The configuration is invalid and formatting should not occur.
+  #[default]
+  Error,
+  /// The configuration can be used, but the user should be notified.
+  Warning,
+  /// The property is deprecated, but still works.
+  Deprecation,
+}
+
+impl ConfigurationDiagnosticSeverity {
+  pub fn is_error(&self) -> bool {
+    matches!(self, ConfigurationDiagnosticSeverity::Error)
+  }
+}
+
+impl std::fmt::Display for ConfigurationDiagnosticSeverity {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ConfigurationDiagnosticSeverity::Error => write!(f, "error"),
+      ConfigurationDiagnosticSeverity::Warning => write!(f, "warning"),
+      ConfigurationDiagnosticSeverity::Deprecation => write!(f, "deprecation"),
+    }
+  }
+}
+
+/// Stable codes for the diagnostics created by the helpers in this module.
+pub mod diagnostic_codes {
+  pub const UNKNOWN_PROPERTY: &str = "unknown-property";
+  pub const INVALID_VALUE: &str = "invalid-value";
+  pub const RENAMED_PROPERTY: &str = "renamed-property";
+  pub const UNUSED_GLOBAL_OPTION: &str = "unused-global-option";
+}
+
 /// Represents a problem within the configuration.
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+///
+/// Create this with `ConfigurationDiagnostic::error`, `warning`, or `deprecation`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug, Default)]
 #[serde(rename_all = "camelCase")]
+#[non_exhaustive]
 pub struct ConfigurationDiagnostic {
   /// The property name the problem occurred on.
   pub property_name: String,
   /// The diagnostic message that should be displayed to the user
   pub message: String,
+  /// How severe the problem is. Only errors prevent formatting.
+  ///
+  /// Defaults to an error for plugins that don't provide this.
+  #[serde(default)]
+  pub severity: ConfigurationDiagnosticSeverity,
+  /// A stable code identifying the kind of problem (ex. "unknown-property").
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub code: Option<String>,
+}
+
+impl ConfigurationDiagnostic {
+  /// Creates an error diagnostic without a code.
+  pub fn error(property_name: impl Into<String>, message: impl Into<String>) -> Self {
+    Self {
+      property_name: property_name.into(),
+      message: message.into(),
+      severity: ConfigurationDiagnosticSeverity::Error,
+      code: None,
+    }
+  }
+
+  /// Creates a warning diagnostic without a code.
+  pub fn warning(property_name: impl Into<String>, message: impl Into<String>) -> Self {
+    Self::error(property_name, message).with_severity(ConfigurationDiagnosticSeverity::Warning)
+  }
+
+  /// Creates a deprecation diagnostic without a code.
+  pub fn deprecation(property_name: impl Into<String>, message: impl Into<String>) -> Self {
+    Self::error(property_name, message).with_severity(ConfigurationDiagnosticSeverity::Deprecation)
+  }
+
+  pub fn with_severity(mut self, severity: ConfigurationDiagnosticSeverity) -> Self {
+    self.severity = severity;
+    self
+  }
+
+  pub fn with_code(mut self, code: impl Into<String>) -> Self {
+    self.code = Some(code.into());
+    self
+  }
 }
 
 impl std::fmt::Display for ConfigurationDiagnostic {
@@ -302,16 +486,67 @@ where
   pub config: T,
 }
 
+/// Information about an option in the global configuration, which is shared by plugins.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GlobalOptionInfo {
+  /// The key of the option in the configuration file.
+  pub name: &'static str,
+  pub description: &'static str,
+}
+
+/// The options of the global configuration.
+///
+/// A new option shared by plugins should be defined here, added to `GLOBAL_OPTIONS`,
+/// and resolved in `resolve_global_config`.
+pub mod global_options {
+  use super::GlobalOptionInfo;
+
+  pub const LINE_WIDTH: GlobalOptionInfo = GlobalOptionInfo {
+    name: "lineWidth",
+    description: "The width of a line the printer will try to stay under. Note that the printer may exceed this width in certain cases.",
+  };
+  pub const INDENT_WIDTH: GlobalOptionInfo = GlobalOptionInfo {
+    name: "indentWidth",
+    description: "The number of characters for an indent.",
+  };
+  pub const USE_TABS: GlobalOptionInfo = GlobalOptionInfo {
+    name: "useTabs",
+    description: "Whether to use tabs (true) or spaces (false) for indentation.",
+  };
+  pub const NEW_LINE_KIND: GlobalOptionInfo = GlobalOptionInfo {
+    name: "newLineKind",
+    description: "The kind of newline to use.",
+  };
+}
+
+/// All the options of the global configuration.
+pub const GLOBAL_OPTIONS: &[GlobalOptionInfo] = &[
+  global_options::LINE_WIDTH,
+  global_options::INDENT_WIDTH,
+  global_options::USE_TABS,
+  global_options::NEW_LINE_KIND,
+];
+
+/// Gets the global option with the provided name.
+pub fn get_global_option(name: &str) -> Option<&'static GlobalOptionInfo> {
+  GLOBAL_OPTIONS.iter().find(|option| option.name == name)
+}
+
+/// Gets the names of the global options.
+pub fn get_global_option_names() -> Vec<&'static str> {
+  GLOBAL_OPTIONS.iter().map(|option| option.name).collect()
+}
+
 /// Resolves a collection of key value pairs to a GlobalConfiguration.
 pub fn resolve_global_config(config: &mut ConfigKeyMap) -> ResolveConfigurationResult<GlobalConfiguration> {
   let mut diagnostics = Vec::new();
 
-  let raw_new_line_kind = get_nullable_value::<RawNewLineKind>(config, "newLineKind", &mut diagnostics);
+  let raw_new_line_kind = get_nullable_enum_value::<RawNewLineKind>(config, global_options::NEW_LINE_KIND.name, &mut diagnostics);
 
   let resolved_config = GlobalConfiguration {
-    line_width: get_nullable_value(config, "lineWidth", &mut diagnostics),
-    use_tabs: get_nullable_value(config, "useTabs", &mut diagnostics),
-    indent_width: get_nullable_value(config, "indentWidth", &mut diagnostics),
+    line_width: get_nullable_value(config, global_options::LINE_WIDTH.name, &mut diagnostics),
+    use_tabs: get_nullable_value(config, global_options::USE_TABS.name, &mut diagnostics),
+    indent_width: get_nullable_value(config, global_options::INDENT_WIDTH.name, &mut diagnostics),
     new_line_kind: raw_new_line_kind.map(|kind| match kind {
       RawNewLineKind::Auto => NewLineKind::Auto,
       RawNewLineKind::LineFeed => NewLineKind::LineFeed,
@@ -343,6 +578,49 @@ where
   get_nullable_value(config, key, diagnostics).unwrap_or(default_value)
 }
 
+/// If the provided key exists, takes its value from the provided config and parses it to the enum.
+/// If the provided key does not exist, it returns the default value.
+/// Adds a diagnostic listing the allowed values if the value is not one of them.
+pub fn get_enum_value<T: ConfigurationEnum>(config: &mut ConfigKeyMap, key: &str, default_value: T, diagnostics: &mut Vec<ConfigurationDiagnostic>) -> T {
+  get_nullable_enum_value(config, key, diagnostics).unwrap_or(default_value)
+}
+
+/// If the provided key exists, takes its value from the provided config and parses it to the enum.
+/// If the provided key does not exist, it returns None.
+/// Adds a diagnostic listing the allowed values if the value is not one of them.
+pub fn get_nullable_enum_value<T: ConfigurationEnum>(config: &mut ConfigKeyMap, key: &str, diagnostics: &mut Vec<ConfigurationDiagnostic>) -> Option<T> {
+  let text = match config.shift_remove(key)? {
+    ConfigKeyValue::String(value) => value,
+    ConfigKeyValue::Number(value) => value.to_string(),
+    ConfigKeyValue::Bool(value) => value.to_string(),
+    ConfigKeyValue::Null => return None,
+    ConfigKeyValue::Object(_) | ConfigKeyValue::Array(_) => {
+      diagnostics.push(
+        ConfigurationDiagnostic::error(key, format!("Expected one of: {}.", display_string_values(T::STRING_VALUES)))
+          .with_code(diagnostic_codes::INVALID_VALUE),
+      );
+      return None;
+    }
+  };
+  match text.parse::<T>() {
+    Ok(value) => Some(value),
+    Err(_) => {
+      diagnostics.push(
+        ConfigurationDiagnostic::error(
+          key,
+          format!("Found invalid value '{}'. Expected one of: {}.", text, display_string_values(T::STRING_VALUES)),
+        )
+        .with_code(diagnostic_codes::INVALID_VALUE),
+      );
+      None
+    }
+  }
+}
+
+fn display_string_values(values: &[&str]) -> String {
+  values.iter().map(|value| format!("'{}'", value)).collect::<Vec<_>>().join(", ")
+}
+
 /// If the provided key exists, takes its value from the provided config and returns it.
 /// If the provided key does not exist, it returns None.
 /// Adds a diagnostic if there is any problem deserializing the value.
@@ -363,10 +641,7 @@ where
     match parsed_value {
       Ok(parsed_value) => Some(parsed_value),
       Err(message) => {
-        diagnostics.push(ConfigurationDiagnostic {
-          property_name: key.to_string(),
-          message,
-        });
+        diagnostics.push(ConfigurationDiagnostic::error(key, message).with_code(diagnostic_codes::INVALID_VALUE));
         None
       }
     }
@@ -393,10 +668,7 @@ pub fn get_nullable_vec<T: std::str::FromStr>(
         Some(result)
       }
       _ => {
-        diagnostics.push(ConfigurationDiagnostic {
-          property_name: key.to_string(),
-          message: "Expected an array.".to_string(),
-        });
+        diagnostics.push(ConfigurationDiagnostic::error(key, "Expected an array.").with_code(diagnostic_codes::INVALID_VALUE));
         None
       }
     },
@@ -404,17 +676,102 @@ pub fn get_nullable_vec<T: std::str::FromStr>(
   }
 }
 
+/// If the provided key exists, takes its object value from the provided config and returns it.
+/// Adds a diagnostic if the value is not an object.
+pub fn get_nullable_object(config: &mut ConfigKeyMap, key: &str, diagnostics: &mut Vec<ConfigurationDiagnostic>) -> Option<ConfigKeyMap> {
+  match config.shift_remove(key)? {
+    ConfigKeyValue::Object(value) => Some(value),
+    ConfigKeyValue::Null => None,
+    _ => {
+      diagnostics.push(ConfigurationDiagnostic::error(key, "Expected an object.").with_code(diagnostic_codes::INVALID_VALUE));
+      None
+    }
+  }
+}
+
+/// If the provided key exists, takes its object value from the provided config and returns it.
+/// If the provided key does not exist, it returns an empty object.
+/// Adds a diagnostic if the value is not an object.
+pub fn get_object(config: &mut ConfigKeyMap, key: &str, diagnostics: &mut Vec<ConfigurationDiagnostic>) -> ConfigKeyMap {
+  get_nullable_object(config, key, diagnostics).unwrap_or_default()
+}
+
+/// Takes the value at the provided path of keys (ex. `&["arrowFunction", "useParentheses"]`)
+/// from the nested objects in the provided config and returns it.
+///
+/// Objects along the path are removed from the config once they are empty so
+/// they don't show up in the unknown property diagnostics. Diagnostics use the
+/// dot separated path as the property name.
+pub fn get_nested_value<T>(config: &mut ConfigKeyMap, path: &[&str], diagnostics: &mut Vec<ConfigurationDiagnostic>) -> Option<T>
+where
+  T: std::str::FromStr,
+  <T as std::str::FromStr>::Err: std::fmt::Display,
+{
+  take_nested(config, path, diagnostics, get_nullable_value)
+}
+
+/// Takes the object at the provided path of keys from the nested objects in the provided config.
+pub fn get_nested_object(config: &mut ConfigKeyMap, path: &[&str], diagnostics: &mut Vec<ConfigurationDiagnostic>) -> Option<ConfigKeyMap> {
+  take_nested(config, path, diagnostics, get_nullable_object)
+}
+
+/// Takes the array at the provided path of keys from the nested objects in the provided config.
+pub fn get_nested_vec<T: std::str::FromStr>(
+  config: &mut ConfigKeyMap,
+  path: &[&str],
+  get_nullable_value: impl Fn(ConfigKeyValue, usize, &mut Vec<ConfigurationDiagnostic>) -> Option<T>,
+  diagnostics: &mut Vec<ConfigurationDiagnostic>,
+) -> Option<Vec<T>> {
+  take_nested(config, path, diagnostics, |config, key, diagnostics| {
+    get_nullable_vec(config, key, get_nullable_value, diagnostics)
+  })
+}
+
+fn take_nested<T>(
+  config: &mut ConfigKeyMap,
+  path: &[&str],
+  diagnostics: &mut Vec<ConfigurationDiagnostic>,
+  take: impl FnOnce(&mut ConfigKeyMap, &str, &mut Vec<ConfigurationDiagnostic>) -> Option<T>,
+) -> Option<T> {
+  let (key, rest) = path.split_first()?;
+  if rest.is_empty() {
+    return take(config, key, diagnostics);
+  }
+
+  let (result, is_empty) = match config.get_mut(*key) {
+    Some(ConfigKeyValue::Object(nested_config)) => {
+      let mut nested_diagnostics = Vec::new();
+      let result = take_nested(nested_config, rest, &mut nested_diagnostics, take);
+      for mut diagnostic in nested_diagnostics {
+        diagnostic.property_name = format!("{}.{}", key, diagnostic.property_name);
+        diagnostics.push(diagnostic);
+      }
+      (result, nested_config.is_empty())
+    }
+    Some(ConfigKeyValue::Null) => (None, true),
+    Some(_) => {
+      diagnostics.push(ConfigurationDiagnostic::error(*key, "Expected an object.").with_code(diagnostic_codes::INVALID_VALUE));
+      (None, true)
+    }
+    None => (None, false),
+  };
+  if is_empty {
+    config.shift_remove(*key);
+  }
+  result
+}
+
 /// If it exists, moves over the configuration value over from the old key
-/// to the new key and adds a diagnostic.
+/// to the new key and adds a deprecation diagnostic.
 pub fn handle_renamed_config_property(config: &mut ConfigKeyMap, old_key: &str, new_key: &str, diagnostics: &mut Vec<ConfigurationDiagnostic>) {
   if let Some(raw_value) = config.shift_remove(old_key) {
     if !config.contains_key(new_key) {
       config.insert(new_key.to_string(), raw_value);
     }
-    diagnostics.push(ConfigurationDiagnostic {
-      property_name: old_key.to_string(),
-      message: format!("The configuration key was renamed to '{}'", new_key),
-    });
+    diagnostics.push(
+      ConfigurationDiagnostic::deprecation(old_key, format!("The configuration key was renamed to '{}'", new_key))
+        .with_code(diagnostic_codes::RENAMED_PROPERTY),
+    );
   }
 }
 
@@ -448,16 +805,56 @@ pub fn resolve_new_line_kind(file_text: &str, new_line_kind: NewLineKind) -> &'s
 ///
 /// This should be done last, so it swallows the hashmap.
 pub fn get_unknown_property_diagnostics(config: ConfigKeyMap) -> Vec<ConfigurationDiagnostic> {
+  get_unknown_property_diagnostics_with_known_keys(config, &[])
+}
+
+/// Gets a diagnostic for each remaining key value pair in the hash map,
+/// suggesting the closest of the provided known keys when one is similar.
+///
+/// This should be done last, so it swallows the hashmap.
+pub fn get_unknown_property_diagnostics_with_known_keys(config: ConfigKeyMap, known_keys: &[&str]) -> Vec<ConfigurationDiagnostic> {
   let mut diagnostics = Vec::new();
   for (key, _) in config {
-    diagnostics.push(ConfigurationDiagnostic {
-      property_name: key.to_string(),
-      message: "Unknown property in configuration".to_string(),
-    });
+    let message = match get_similar_key(&key, known_keys) {
+      Some(similar_key) => format!("Unknown property in configuration. Did you mean '{}'?", similar_key),
+      None => "Unknown property in configuration".to_string(),
+    };
+    diagnostics.push(ConfigurationDiagnostic::error(key, message).with_code(diagnostic_codes::UNKNOWN_PROPERTY));
   }
   diagnostics
 }
 
+/// Gets the known key closest to the provided key by edit distance
+/// or `None` when none are similar enough to be a likely typo.
+pub fn get_similar_key<'a>(key: &str, known_keys: &[&'a str]) -> Option<&'a str> {
+  let key = key.to_lowercase();
+  let max_distance = std::cmp::max(1, key.chars().count() / 3);
+  let mut best_match = None;
+  for known_key in known_keys {
+    let distance = get_edit_distance(&key, &known_key.to_lowercase());
+    if distance <= max_distance && best_match.map(|(best_distance, _)| distance < best_distance).unwrap_or(true) {
+      best_match = Some((distance, *known_key));
+    }
+  }
+  best_match.map(|(_, known_key)| known_key)
+}
+
+/// Levenshtein distance between the two strings.
+fn get_edit_distance(a: &str, b: &str) -> usize {
+  let b = b.chars().collect::<Vec<_>>();
+  let mut previous_row = (0..=b.len()).collect::<Vec<_>>();
+  let mut current_row = vec![0; b.len() + 1];
+  for (i, a_char) in a.chars().enumerate() {
+    current_row[0] = i + 1;
+    for (j, b_char) in b.iter().enumerate() {
+      let substitution_cost = if a_char == *b_char { 0 } else { 1 };
+      current_row[j + 1] = (previous_row[j] + substitution_cost).min(previous_row[j + 1] + 1).min(current_row[j] + 1);
+    }
+    std::mem::swap(&mut previous_row, &mut current_row);
+  }
+  previous_row[b.len()]
+}
+
 #[cfg(test)]
 mod test {
   use super::*;
@@ -490,12 +887,29 @@ mod test {
     assert_eq!(config.use_tabs, Some(true));
   }
 
+  #[test]
+  fn get_global_options() {
+    assert_eq!(get_global_option_names(), vec!["lineWidth", "indentWidth", "useTabs", "newLineKind"]);
+    assert_eq!(get_global_option("useTabs"), Some(&global_options::USE_TABS));
+    assert_eq!(get_global_option("unknown"), None);
+    // all the options should be resolved
+    let mut config = GLOBAL_OPTIONS
+      .iter()
+      .map(|option| (option.name.to_string(), ConfigKeyValue::Null))
+      .collect::<ConfigKeyMap>();
+    resolve_global_config(&mut config);
+    assert!(config.is_empty());
+  }
+
   #[test]
   fn get_diagnostic_for_invalid_enum_config() {
     let mut global_config = ConfigKeyMap::from([(String::from("newLineKind"), ConfigKeyValue::from_str("something"))]);
     let diagnostics = resolve_global_config(&mut global_config).diagnostics;
     assert_eq!(diagnostics.len(), 1);
-    assert_eq!(diagnostics[0].message, "Found invalid value 'something'.");
+    assert_eq!(
+      diagnostics[0].message,
+      "Found invalid value 'something'. Expected one of: 'auto', 'lf', 'crlf', 'system'."
+    );
     assert_eq!(diagnostics[0].property_name, "newLineKind");
   }
 
@@ -506,6 +920,8 @@ mod test {
     assert_eq!(diagnostics.len(), 1);
     assert_eq!(diagnostics[0].message, "provided string was not `true` or `false`");
     assert_eq!(diagnostics[0].property_name, "useTabs");
+    assert_eq!(diagnostics[0].severity, ConfigurationDiagnosticSeverity::Error);
+    assert_eq!(diagnostics[0].code.as_deref(), Some(diagnostic_codes::INVALID_VALUE));
   }
 
   #[test]
@@ -517,6 +933,170 @@ mod test {
     assert_eq!(diagnostics[0].property_name, "something");
   }
 
+  #[test]
+  fn get_diagnostic_for_excess_property_with_suggestion() {
+    let config = ConfigKeyMap::from([
+      (String::from("lineWdth"), ConfigKeyValue::from_i32(80)),
+      (String::from("usetabs"), ConfigKeyValue::from_bool(true)),
+      (String::from("something"), ConfigKeyValue::from_str("value")),
+    ]);
+    let diagnostics = get_unknown_property_diagnostics_with_known_keys(config, &get_global_option_names());
+    assert_eq!(
+      diagnostics,
+      vec![
+        ConfigurationDiagnostic::error("lineWdth", "Unknown property in configuration. Did you mean 'lineWidth'?")
+          .with_code(diagnostic_codes::UNKNOWN_PROPERTY),
+        ConfigurationDiagnostic::error("usetabs", "Unknown property in configuration. Did you mean 'useTabs'?").with_code(diagnostic_codes::UNKNOWN_PROPERTY),
+        ConfigurationDiagnostic::error("something", "Unknown property in configuration").with_code(diagnostic_codes::UNKNOWN_PROPERTY),
+      ]
+    );
+  }
+
+  #[test]
+  fn get_similar_key_picks_closest() {
+    assert_eq!(get_similar_key("indentWith", &["indentWidth", "lineWidth"]), Some("indentWidth"));
+    assert_eq!(get_similar_key("quoteStyle", &["quoteProps", "semiColons"]), None);
+    assert_eq!(get_similar_key("a", &[]), None);
+  }
+
+  #[test]
+  fn get_nested_values() {
+    let mut config = ConfigKeyMap::from([
+      (
+        String::from("arrowFunction"),
+        ConfigKeyValue::Object(ConfigKeyMap::from([
+          (String::from("useParentheses"), ConfigKeyValue::from_str("force")),
+          (String::from("indentWidth"), ConfigKeyValue::from_str("wide")),
+          (
+            String::from("nested"),
+            ConfigKeyValue::Object(ConfigKeyMap::from([(
+              String::from("values"),
+              ConfigKeyValue::Array(vec![ConfigKeyValue::from_i32(1), ConfigKeyValue::from_i32(2)]),
+            )])),
+          ),
+        ])),
+      ),
+      (String::from("other"), ConfigKeyValue::from_bool(true)),
+    ]);
+    let mut diagnostics = Vec::new();
+    assert_eq!(
+      get_nested_value::<String>(&mut config, &["arrowFunction", "useParentheses"], &mut diagnostics),
+      Some("force".to_string())
+    );
+    assert_eq!(get_nested_value::<u8>(&mut config, &["arrowFunction", "indentWidth"], &mut diagnostics), None);
+    assert_eq!(get_nested_value::<u8>(&mut config, &["arrowFunction", "missing"], &mut diagnostics), None);
+    assert_eq!(get_nested_value::<u8>(&mut config, &["other", "value"], &mut diagnostics), None);
+    assert_eq!(
+      get_nested_vec(
+        &mut config,
+        &["arrowFunction", "nested", "values"],
+        |value, _, _| value.as_number(),
+        &mut diagnostics
+      ),
+      Some(vec![1, 2])
+    );
+    assert_eq!(
+      diagnostics,
+      vec![
+        ConfigurationDiagnostic::error("arrowFunction.indentWidth", "invalid digit found in string").with_code(diagnostic_codes::INVALID_VALUE),
+        ConfigurationDiagnostic::error("other", "Expected an object.").with_code(diagnostic_codes::INVALID_VALUE),
+      ]
+    );
+    // the emptied objects are removed
+    assert!(config.is_empty());
+  }
+
+  #[test]
+  fn get_object_values() {
+    let mut config = ConfigKeyMap::from([
+      (
+        String::from("object"),
+        ConfigKeyValue::Object(ConfigKeyMap::from([(String::from("value"), ConfigKeyValue::from_bool(true))])),
+      ),
+      (String::from("notObject"), ConfigKeyValue::from_i32(5)),
+    ]);
+    let mut diagnostics = Vec::new();
+    let mut object = get_object(&mut config, "object", &mut diagnostics);
+    assert_eq!(get_value(&mut object, "value", false, &mut diagnostics), true);
+    assert_eq!(get_object(&mut config, "missing", &mut diagnostics), ConfigKeyMap::new());
+    assert_eq!(get_nullable_object(&mut config, "notObject", &mut diagnostics), None);
+    assert_eq!(
+      diagnostics,
+      vec![ConfigurationDiagnostic::error("notObject", "Expected an object.").with_code(diagnostic_codes::INVALID_VALUE)]
+    );
+    assert!(config.is_empty());
+  }
+
+  #[test]
+  fn parse_byte_sizes() {
+    assert_eq!("80kb".parse::<ByteSize>(), Ok(ByteSize(80 * 1024)));
+    assert_eq!("1.5 MB".parse::<ByteSize>(), Ok(ByteSize(1536 * 1024)));
+    assert_eq!("2gb".parse::<ByteSize>(), Ok(ByteSize(2 * 1024 * 1024 * 1024)));
+    assert_eq!("100".parse::<ByteSize>(), Ok(ByteSize(100)));
+    assert_eq!(
+      "80tb".parse::<ByteSize>(),
+      Err("Found invalid size unit 'tb'. Expected one of: b, kb, mb, gb.".to_string())
+    );
+    assert_eq!(
+      "kb".parse::<ByteSize>(),
+      Err("Found invalid size 'kb'. Expected a value like 80kb or 2mb.".to_string())
+    );
+    assert_eq!(ByteSize(80 * 1024).to_string(), "80kb");
+    assert_eq!(ByteSize(1536).to_string(), "1536b");
+  }
+
+  #[test]
+  fn parse_durations() {
+    assert_eq!("30s".parse::<ConfigDuration>(), Ok(ConfigDuration(std::time::Duration::from_secs(30))));
+    assert_eq!("500ms".parse::<ConfigDuration>(), Ok(ConfigDuration(std::time::Duration::from_millis(500))));
+    assert_eq!("1.5m".parse::<ConfigDuration>(), Ok(ConfigDuration(std::time::Duration::from_secs(90))));
+    assert_eq!("2H".parse::<ConfigDuration>(), Ok(ConfigDuration(std::time::Duration::from_secs(2 * 60 * 60))));
+    assert_eq!(
+      "30".parse::<ConfigDuration>(),
+      Err("Found invalid duration '30'. Expected a unit (ms, s, m, or h).".to_string())
+    );
+    assert_eq!(
+      "30d".parse::<ConfigDuration>(),
+      Err("Found invalid duration unit 'd'. Expected one of: ms, s, m, h.".to_string())
+    );
+    assert_eq!(ConfigDuration(std::time::Duration::from_secs(90)).to_string(), "90s");
+    assert_eq!(ConfigDuration(std::time::Duration::from_secs(120)).to_string(), "2m");
+  }
+
+  #[test]
+  fn get_typed_values() {
+    let mut config = ConfigKeyMap::from([
+      (String::from("maxSize"), ConfigKeyValue::from_str("80kb")),
+      (String::from("timeout"), ConfigKeyValue::from_str("30s")),
+      (String::from("newLineKind"), ConfigKeyValue::from_str("crlf")),
+      (String::from("otherNewLineKind"), ConfigKeyValue::from_i32(5)),
+    ]);
+    let mut diagnostics = Vec::new();
+    assert_eq!(
+      get_nullable_value::<ByteSize>(&mut config, "maxSize", &mut diagnostics),
+      Some(ByteSize(80 * 1024))
+    );
+    assert_eq!(
+      get_value(&mut config, "timeout", ConfigDuration::default(), &mut diagnostics).as_duration(),
+      std::time::Duration::from_secs(30)
+    );
+    assert_eq!(
+      get_enum_value(&mut config, "newLineKind", NewLineKind::Auto, &mut diagnostics),
+      NewLineKind::CarriageReturnLineFeed
+    );
+    assert_eq!(
+      get_enum_value(&mut config, "otherNewLineKind", NewLineKind::Auto, &mut diagnostics),
+      NewLineKind::Auto
+    );
+    assert_eq!(
+      diagnostics,
+      vec![
+        ConfigurationDiagnostic::error("otherNewLineKind", "Found invalid value '5'. Expected one of: 'auto', 'lf', 'crlf'.")
+          .with_code(diagnostic_codes::INVALID_VALUE)
+      ]
+    );
+  }
+
   #[test]
   fn add_diagnostic_for_renamed_property() {
     let mut config = ConfigKeyMap::new();
@@ -528,6 +1108,7 @@ mod test {
     assert_eq!(diagnostics.len(), 1);
     assert_eq!(diagnostics[0].message, "The configuration key was renamed to 'newProp'");
     assert_eq!(diagnostics[0].property_name, "oldProp");
+    assert_eq!(diagnostics[0].severity, ConfigurationDiagnosticSeverity::Deprecation);
   }
 
   #[test]
@@ -543,4 +1124,14 @@ mod test {
     assert_eq!(diagnostics[0].message, "The configuration key was renamed to 'newProp'");
     assert_eq!(diagnostics[0].property_name, "oldProp");
   }
+  #[test]
+  fn deserialize_diagnostic_without_severity() {
+    let diagnostic: ConfigurationDiagnostic = serde_json::from_str(r#"{ "propertyName": "prop", "message": "Message." }"#).unwrap();
+    assert_eq!(diagnostic, ConfigurationDiagnostic::error("prop", "Message."));
+    let diagnostic = ConfigurationDiagnostic::warning("prop", "Message.").with_code("some-code");
+    assert_eq!(
+      serde_json::to_string(&diagnostic).unwrap(),
+      r#"{"propertyName":"prop","message":"Message.","severity":"warning","code":"some-code"}"#
+    );
+  }
 }
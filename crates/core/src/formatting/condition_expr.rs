@@ -0,0 +1,229 @@
+use std::rc::Rc;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::Condition;
+use super::ConditionProperties;
+use super::ConditionReference;
+use super::ConditionResolverContext;
+use super::Info;
+use super::PrintItems;
+
+/// A condition described as data instead of a closure.
+///
+/// Conditions created from one of these with `Condition::new_serializable` can
+/// be serialized along with the rest of the print items (see `ir_serialization`).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "content", rename_all = "camelCase")]
+pub enum ConditionExpr {
+  Bool(bool),
+  Not(Box<ConditionExpr>),
+  /// True when all the expressions are true.
+  And(Vec<ConditionExpr>),
+  /// True when any of the expressions are true.
+  Or(Vec<ConditionExpr>),
+  /// The resolved value of the condition with the provided id.
+  Condition(u32),
+  /// If the condition is at the start of a line.
+  IsStartOfLine,
+  /// If the start of the line the condition is on is indented.
+  IsStartOfLineIndented,
+  IsForcingNoNewLines,
+  Compare(Box<ValueExpr>, CompareOp, Box<ValueExpr>),
+}
+
+impl ConditionExpr {
+  /// Creates an expression that resolves to the value of the referenced condition.
+  pub fn condition(condition_reference: &ConditionReference) -> Self {
+    ConditionExpr::Condition(condition_reference.id)
+  }
+
+  /// Creates an expression that compares two values.
+  pub fn compare(left: ValueExpr, op: CompareOp, right: ValueExpr) -> Self {
+    ConditionExpr::Compare(Box::new(left), op, Box::new(right))
+  }
+
+  pub(super) fn resolve(&self, context: &mut ConditionResolverContext) -> Option<bool> {
+    match self {
+      ConditionExpr::Bool(value) => Some(*value),
+      ConditionExpr::Not(expr) => expr.resolve(context).map(|value| !value),
+      ConditionExpr::And(exprs) => {
+        let mut is_resolved = true;
+        for expr in exprs {
+          match expr.resolve(context) {
+            Some(false) => return Some(false),
+            Some(true) => {}
+            None => is_resolved = false,
+          }
+        }
+        if is_resolved {
+          Some(true)
+        } else {
+          None
+        }
+      }
+      ConditionExpr::Or(exprs) => {
+        let mut is_resolved = true;
+        for expr in exprs {
+          match expr.resolve(context) {
+            Some(true) => return Some(true),
+            Some(false) => {}
+            None => is_resolved = false,
+          }
+        }
+        if is_resolved {
+          Some(false)
+        } else {
+          None
+        }
+      }
+      ConditionExpr::Condition(id) => context.resolved_condition(&ConditionReference::new("serializedCondition", *id)),
+      ConditionExpr::IsStartOfLine => Some(context.writer_info.is_start_of_line()),
+      ConditionExpr::IsStartOfLineIndented => Some(context.writer_info.is_start_of_line_indented()),
+      ConditionExpr::IsForcingNoNewLines => Some(context.is_forcing_no_newlines()),
+      ConditionExpr::Compare(left, op, right) => {
+        let left = left.resolve(context)?;
+        let right = right.resolve(context)?;
+        Some(match op {
+          CompareOp::Equal => left == right,
+          CompareOp::NotEqual => left != right,
+          CompareOp::LessThan => left < right,
+          CompareOp::LessThanOrEqual => left <= right,
+          CompareOp::GreaterThan => left > right,
+          CompareOp::GreaterThanOrEqual => left >= right,
+        })
+      }
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CompareOp {
+  Equal,
+  NotEqual,
+  LessThan,
+  LessThanOrEqual,
+  GreaterThan,
+  GreaterThanOrEqual,
+}
+
+/// A number used in a `ConditionExpr`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "content", rename_all = "camelCase")]
+pub enum ValueExpr {
+  Number(u32),
+  /// A value of the writer at the condition's location.
+  Writer(WriterValue),
+  /// The resolved value of an info. Is start of line infos are 1 when true and 0 when false.
+  Info(InfoRef),
+  Add(Box<ValueExpr>, Box<ValueExpr>),
+  /// Subtracts the right value from the left value, stopping at zero.
+  Subtract(Box<ValueExpr>, Box<ValueExpr>),
+}
+
+impl ValueExpr {
+  /// Creates an expression that resolves to the value of the info.
+  pub fn info(info: impl Into<Info>) -> Self {
+    let info = info.into();
+    ValueExpr::Info(InfoRef {
+      kind: info.kind(),
+      id: info.unique_id(),
+    })
+  }
+
+  fn resolve(&self, context: &mut ConditionResolverContext) -> Option<u32> {
+    match self {
+      ValueExpr::Number(value) => Some(*value),
+      ValueExpr::Writer(value) => Some(match value {
+        WriterValue::LineNumber => context.writer_info.line_number,
+        WriterValue::ColumnNumber => context.writer_info.column_number,
+        WriterValue::IndentLevel => context.writer_info.indent_level as u32,
+        WriterValue::LineStartColumnNumber => context.writer_info.line_start_column_number(),
+        WriterValue::LineStartIndentLevel => context.writer_info.line_start_indent_level as u32,
+      }),
+      ValueExpr::Info(info) => match Info::from_kind_and_id(info.kind, info.id) {
+        Info::LineNumber(info) => context.resolved_line_number(info),
+        Info::ColumnNumber(info) => context.resolved_column_number(info),
+        Info::IsStartOfLine(info) => context.resolved_is_start_of_line(info).map(|value| value as u32),
+        Info::IndentLevel(info) => context.resolved_indent_level(info).map(|value| value as u32),
+        Info::LineStartColumnNumber(info) => context.resolved_line_start_column_number(info),
+        Info::LineStartIndentLevel(info) => context.resolved_line_start_indent_level(info).map(|value| value as u32),
+      },
+      ValueExpr::Add(left, right) => Some(left.resolve(context)? + right.resolve(context)?),
+      ValueExpr::Subtract(left, right) => Some(left.resolve(context)?.saturating_sub(right.resolve(context)?)),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WriterValue {
+  LineNumber,
+  ColumnNumber,
+  IndentLevel,
+  LineStartColumnNumber,
+  LineStartIndentLevel,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InfoKind {
+  LineNumber,
+  ColumnNumber,
+  IsStartOfLine,
+  IndentLevel,
+  LineStartColumnNumber,
+  LineStartIndentLevel,
+}
+
+/// The kind and unique id of an info.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InfoRef {
+  pub kind: InfoKind,
+  pub id: u32,
+}
+
+/// Properties for a condition that can be serialized.
+pub struct SerializableConditionProperties {
+  /// The condition to resolve.
+  pub condition: ConditionExpr,
+  /// The items to print when the condition is true.
+  pub true_path: Option<PrintItems>,
+  /// The items to print when the condition is false or undefined (not yet resolved).
+  pub false_path: Option<PrintItems>,
+}
+
+impl Condition {
+  /// Creates a condition from data rather than a closure so that it may be serialized.
+  pub fn new_serializable(name: &'static str, properties: SerializableConditionProperties) -> Self {
+    let expr = Rc::new(properties.condition);
+    let mut condition = Condition::new(
+      name,
+      ConditionProperties {
+        condition: create_resolver(expr.clone()),
+        true_path: properties.true_path,
+        false_path: properties.false_path,
+      },
+    );
+    condition.expr = Some(expr);
+    condition
+  }
+
+  /// Gets the data the condition was created from when it was created with `new_serializable`.
+  pub fn expr(&self) -> Option<&ConditionExpr> {
+    self.expr.as_deref()
+  }
+
+  pub(super) fn set_expr(&mut self, expr: ConditionExpr) {
+    let expr = Rc::new(expr);
+    self.condition = create_resolver(expr.clone());
+    self.expr = Some(expr);
+  }
+}
+
+fn create_resolver(expr: Rc<ConditionExpr>) -> super::ConditionResolver {
+  Rc::new(move |context| expr.resolve(context))
+}
@@ -88,6 +88,19 @@ pub fn single_indent_if_start_of_line() -> Condition {
   )
 }
 
+/// Aligns the continuation lines of the items to the current column (ex. the column
+/// after an operator or open paren) or indents them instead when the current relative
+/// column number is above the specified width, which keeps deeply hanging code readable.
+pub fn align_or_indent_if_above_width(width: u8, items: PrintItems) -> Condition {
+  let rc_path = items.into_rc_path();
+  if_true_or(
+    "alignOrIndentIfAboveWidth",
+    above_width_resolver(width),
+    ir_helpers::with_indent(rc_path.into()),
+    ir_helpers::with_alignment(rc_path.into()),
+  )
+}
+
 /// Prints the provided items when the current relative column number is above
 /// the specified width.
 pub fn if_above_width(width: u8, items: PrintItems) -> Condition {
@@ -100,17 +113,21 @@ pub fn if_above_width_or(width: u8, true_items: PrintItems, false_items: PrintIt
   Condition::new(
     "ifAboveWidth",
     ConditionProperties {
-      condition: Rc::new(move |context| {
-        let writer_info = &context.writer_info;
-        let first_indent_col = writer_info.line_start_column_number() + (width as u32);
-        Some(writer_info.column_number > first_indent_col)
-      }),
+      condition: above_width_resolver(width),
       true_path: Some(true_items),
       false_path: if false_items.is_empty() { None } else { Some(false_items) },
     },
   )
 }
 
+fn above_width_resolver(width: u8) -> ConditionResolver {
+  Rc::new(move |context| {
+    let writer_info = &context.writer_info;
+    let first_indent_col = writer_info.line_start_column_number() + (width as u32);
+    Some(writer_info.column_number > first_indent_col)
+  })
+}
+
 pub fn if_true(name: &'static str, resolver: ConditionResolver, true_path: PrintItems) -> Condition {
   Condition::new(
     name,
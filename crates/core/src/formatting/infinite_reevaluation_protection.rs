@@ -1,27 +1,60 @@
 use super::collections::VecU32MapWithDefault;
+use super::ConditionReference;
+use super::Info;
+use super::WriterInfo;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 
 static LOGGED: AtomicBool = AtomicBool::new(false);
 
+const MAX_COUNT: u16 = 1_000;
+/// The number of the last writer states to keep for the message.
+const RECENT_WRITER_INFOS_COUNT: u16 = 5;
+
+/// Something a condition used when it was resolved.
+#[derive(Clone, Copy, Debug)]
+pub enum ReevaluationDependency {
+  Condition(ConditionReference),
+  Info(Info),
+}
+
+/// Details about the condition being re-evaluated, which are used in the
+/// message when it exceeds the re-evaluation count.
+pub struct ReevaluationDetails<'a> {
+  pub condition_name: &'static str,
+  pub condition_id: u32,
+  pub dependencies: &'a [ReevaluationDependency],
+  pub writer_info: WriterInfo,
+}
+
 /// This provides some protection if a condition re-evaluation keeps
 /// flipping back and forth over and over.
 pub struct InfiniteReevaluationProtector {
   reevaluation_count: VecU32MapWithDefault<u16>,
+  recent_writer_infos: Vec<(u32, WriterInfo)>,
 }
 
 impl InfiniteReevaluationProtector {
   pub fn with_capacity(capacity: u32) -> Self {
     Self {
       reevaluation_count: VecU32MapWithDefault::with_capacity(capacity),
+      recent_writer_infos: Vec::new(),
     }
   }
 
-  pub fn should_reevaluate(&mut self, reevaluation_id: u32, current_value: Option<bool>, last_value: bool) -> bool {
-    const MAX_COUNT: u16 = 1_000;
+  pub fn should_reevaluate<'a>(
+    &mut self,
+    reevaluation_id: u32,
+    current_value: Option<bool>,
+    last_value: bool,
+    get_details: impl FnOnce() -> ReevaluationDetails<'a>,
+  ) -> bool {
     let current_value = current_value.unwrap_or(false);
     if current_value == last_value {
       self.reevaluation_count.set(reevaluation_id, 0); // reset
+      if !self.recent_writer_infos.is_empty() {
+        self.recent_writer_infos.retain(|(id, _)| *id != reevaluation_id);
+      }
       true
     } else {
       // re-evaluation flipped
@@ -31,39 +64,97 @@ impl InfiniteReevaluationProtector {
       }
 
       self.reevaluation_count.set(reevaluation_id, next_count);
-      if next_count == MAX_COUNT {
-        // only ever log this once per execution
-        if !LOGGED.swap(true, Ordering::SeqCst) {
-          // todo: use awasm logging here instead
-          #[allow(clippy::print_stderr)]
-          {
-            eprintln!(
-              "[dprint-core] A file exceeded the re-evaluation count and formatting stabilized at a random condition value. Please report this as a bug."
-            );
-          }
+      if next_count <= MAX_COUNT - RECENT_WRITER_INFOS_COUNT {
+        return true;
+      }
+
+      let details = get_details();
+      if next_count < MAX_COUNT {
+        self.recent_writer_infos.push((reevaluation_id, details.writer_info));
+        return true;
+      }
+
+      // only ever log this once per execution
+      if !LOGGED.swap(true, Ordering::SeqCst) {
+        let message = self.get_exceeded_message(reevaluation_id, &details);
+        // todo: use awasm logging here instead
+        #[allow(clippy::print_stderr)]
+        {
+          eprintln!("{}", message);
         }
-        false
-      } else {
-        true
+      }
+      self.recent_writer_infos.retain(|(id, _)| *id != reevaluation_id);
+      false
+    }
+  }
+
+  fn get_exceeded_message(&self, reevaluation_id: u32, details: &ReevaluationDetails) -> String {
+    let mut message = format!(
+      concat!(
+        "[dprint-core] A file exceeded the re-evaluation count and formatting stabilized at a random condition value. Please report this as a bug.\n",
+        "  Condition: {} (id: {}, re-evaluation id: {})"
+      ),
+      details.condition_name, details.condition_id, reevaluation_id,
+    );
+    if details.dependencies.is_empty() {
+      message.push_str("\n  Depends on: nothing");
+    } else {
+      message.push_str("\n  Depends on:");
+      for dependency in details.dependencies {
+        message.push_str(&format!("\n    {}", describe_dependency(dependency)));
       }
     }
+    message.push_str("\n  Last writer states (line:column, indent level):");
+    let writer_infos = self
+      .recent_writer_infos
+      .iter()
+      .filter(|(id, _)| *id == reevaluation_id)
+      .map(|(_, writer_info)| writer_info)
+      .chain(std::iter::once(&details.writer_info));
+    for writer_info in writer_infos {
+      // line and column numbers are zero-indexed in the printer
+      message.push_str(&format!(
+        "\n    {}:{}, {}",
+        writer_info.line_number + 1,
+        writer_info.column_number + 1,
+        writer_info.indent_level
+      ));
+    }
+    message
   }
 }
 
+fn describe_dependency(dependency: &ReevaluationDependency) -> String {
+  let (kind, name, id) = match dependency {
+    ReevaluationDependency::Condition(condition) => ("condition", condition.name(), condition.id),
+    ReevaluationDependency::Info(info) => match info {
+      Info::LineNumber(info) => ("line number", info.name(), info.unique_id()),
+      Info::ColumnNumber(info) => ("column number", info.name(), info.unique_id()),
+      Info::IsStartOfLine(info) => ("is start of line", info.name(), info.unique_id()),
+      Info::IndentLevel(info) => ("indent level", info.name(), info.unique_id()),
+      Info::LineStartColumnNumber(info) => ("line start column number", info.name(), info.unique_id()),
+      Info::LineStartIndentLevel(info) => ("line start indent level", info.name(), info.unique_id()),
+    },
+  };
+  format!("{} {} (id: {})", kind, name, id)
+}
+
 #[cfg(test)]
 mod test {
+  use super::super::Condition;
+  use super::super::LineNumber;
   use super::*;
 
   #[test]
   fn should_keep_track_flipping_reevaluation() {
     let mut protector = InfiniteReevaluationProtector::with_capacity(1);
     for _ in 0..999 {
-      assert!(protector.should_reevaluate(0, Some(true), false));
+      assert!(protector.should_reevaluate(0, Some(true), false, get_details));
     }
 
-    assert!(!protector.should_reevaluate(0, Some(true), false));
-    assert!(!protector.should_reevaluate(0, Some(true), false));
-    assert!(protector.should_reevaluate(0, Some(true), true));
+    assert!(!protector.should_reevaluate(0, Some(true), false, get_details));
+    assert!(!protector.should_reevaluate(0, Some(true), false, get_details));
+    assert!(protector.should_reevaluate(0, Some(true), true, get_details));
   }
 
   #[test]
@@ -72,14 +163,93 @@ mod test {
     let mut value = false;
     for _ in 0..998 {
       value = !value;
-      assert!(protector.should_reevaluate(0, Some(true), false));
+      assert!(protector.should_reevaluate(0, Some(true), false, get_details));
     }
-    assert!(protector.should_reevaluate(0, None, false));
+    assert!(protector.should_reevaluate(0, None, false, get_details));
 
     for _ in 0..999 {
-      assert!(protector.should_reevaluate(0, Some(false), true));
+      assert!(protector.should_reevaluate(0, Some(false), true, get_details));
+    }
+
+    assert!(!protector.should_reevaluate(0, Some(true), false, get_details));
+  }
+
+  #[test]
+  fn should_describe_exceeded_reevaluation() {
+    let mut protector = InfiniteReevaluationProtector::with_capacity(1);
+    for line_number in 0..999 {
+      let details = || ReevaluationDetails {
+        writer_info: create_writer_info(line_number),
+        ..get_details()
+      };
+      assert!(protector.should_reevaluate(0, Some(true), false, details));
+    }
+    let dependencies = [
+      ReevaluationDependency::Info(Info::LineNumber(LineNumber::new("start"))),
+      ReevaluationDependency::Condition(Condition::new_true().create_reference()),
+    ];
+    let details = ReevaluationDetails {
+      dependencies: &dependencies,
+      writer_info: create_writer_info(999),
+      ..get_details()
+    };
+    let message = protector.get_exceeded_message(0, &details);
+    let expected_names = if cfg!(debug_assertions) {
+      ["start", "trueCondition"]
+    } else {
+      ["line_number", "conditionRef"]
+    };
+    assert_eq!(
+      message,
+      format!(
+        concat!(
+          "[dprint-core] A file exceeded the re-evaluation count and formatting stabilized at a random condition value. Please report this as a bug.\n",
+          "  Condition: test (id: 5, re-evaluation id: 0)\n",
+          "  Depends on:\n",
+          "    line number {} (id: {})\n",
+          "    condition {} (id: {})\n",
+          "  Last writer states (line:column, indent level):\n",
+          "    996:2, 0\n",
+          "    997:2, 0\n",
+          "    998:2, 0\n",
+          "    999:2, 0\n",
+          "    1000:2, 0",
+        ),
+        expected_names[0],
+        dependencies_id(&dependencies[0]),
+        expected_names[1],
+        dependencies_id(&dependencies[1]),
+      )
+    );
+  }
+
+  fn get_details() -> ReevaluationDetails<'static> {
+    ReevaluationDetails {
+      condition_name: "test",
+      condition_id: 5,
+      dependencies: &[],
+      writer_info: create_writer_info(0),
+    }
+  }
+
+  fn create_writer_info(line_number: u32) -> WriterInfo {
+    WriterInfo {
+      line_number,
+      column_number: 1,
+      indent_level: 0,
+      line_start_indent_level: 0,
+      indent_width: 2,
+      expect_newline_next: false,
     }
+  }
 
-    assert!(!protector.should_reevaluate(0, Some(true), false));
+  fn dependencies_id(dependency: &ReevaluationDependency) -> u32 {
+    match dependency {
+      ReevaluationDependency::Condition(condition) => condition.id,
+      ReevaluationDependency::Info(info) => match info {
+        Info::LineNumber(info) => info.unique_id(),
+        _ => unreachable!(),
+      },
+    }
   }
 }
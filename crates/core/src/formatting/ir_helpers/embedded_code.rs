@@ -0,0 +1,78 @@
+use anyhow::Result;
+
+use super::super::print_items::*;
+use super::gen_from_string;
+use super::with_indent_times;
+
+/// Code in another language that's embedded within a file, such as the
+/// code of a markdown code fence or an HTML script tag.
+pub struct EmbeddedCodeBlock<'a> {
+  /// Hint for the language of the code, such as a code fence's info string or a file extension.
+  pub language: &'a str,
+  /// The code as written in the file, without the indentation of the block.
+  pub text: &'a str,
+  /// The number of times the code should be indented.
+  pub indent_level: u8,
+}
+
+/// Generates IR for an embedded code block by formatting its text with `format_text`,
+/// which usually delegates to the host to format with another plugin.
+///
+/// `format_text` should return `None` when the code should be kept as is, such as
+/// when no plugin formats the language.
+pub fn gen_embedded_code_block(block: &EmbeddedCodeBlock, format_text: impl FnOnce(&EmbeddedCodeBlock) -> Result<Option<String>>) -> Result<PrintItems> {
+  let formatted_text = format_text(block)?;
+  let text = formatted_text.as_deref().unwrap_or(block.text);
+  // the surrounding IR decides what comes after the block
+  let text = text.trim_end_matches(['\r', '\n']);
+  Ok(with_indent_times(gen_from_string(text), block.indent_level as u32))
+}
+
+#[cfg(test)]
+mod test {
+  use super::super::super::format;
+  use super::super::super::PrintOptions;
+  use super::*;
+
+  #[test]
+  fn should_gen_embedded_code_block() {
+    let block = EmbeddedCodeBlock {
+      language: "ts",
+      text: "const  a=1;\n\nif (a) {\n\tb();\n}\n",
+      indent_level: 1,
+    };
+    assert_eq!(
+      format_block(&block, |block| {
+        assert_eq!(block.language, "ts");
+        Ok(Some("const a = 1;\r\n\r\nif (a) {\r\n  b();\r\n}\r\n".to_string()))
+      }),
+      "```ts\n  const a = 1;\n\n  if (a) {\n    b();\n  }\n```"
+    );
+    // keeps the text as is when not formatted
+    assert_eq!(format_block(&block, |_| Ok(None)), "```ts\n  const  a=1;\n\n  if (a) {\n  \tb();\n  }\n```");
+    // surfaces errors from formatting
+    let err = gen_embedded_code_block(&block, |_| Err(anyhow::anyhow!("Failed."))).err().unwrap();
+    assert_eq!(err.to_string(), "Failed.");
+  }
+
+  fn format_block(block: &EmbeddedCodeBlock, format_text: impl FnOnce(&EmbeddedCodeBlock) -> Result<Option<String>>) -> String {
+    format(
+      || {
+        let mut items = PrintItems::new();
+        items.push_string(format!("```{}", block.language));
+        items.push_signal(Signal::NewLine);
+        items.extend(gen_embedded_code_block(block, format_text).unwrap());
+        items.push_signal(Signal::NewLine);
+        items.push_str_runtime_width_computed("```");
+        items
+      },
+      PrintOptions {
+        indent_width: 2,
+        max_width: 80,
+        use_tabs: false,
+        smart_tabs: false,
+        new_line_text: "\n",
+      },
+    )
+  }
+}
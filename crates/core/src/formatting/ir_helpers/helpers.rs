@@ -1,3 +1,4 @@
+use std::ops::Range;
 use std::rc::Rc;
 
 use crate::formatting::actions;
@@ -50,6 +51,20 @@ pub fn with_indent_times(item: PrintItems, times: u32) -> PrintItems {
   items
 }
 
+/// Aligns the lines after the first line to the current column, such as for
+/// hanging continuation lines after an operator or open paren.
+pub fn with_alignment(item: PrintItems) -> PrintItems {
+  if item.is_empty() {
+    return item;
+  }
+
+  let mut items = PrintItems::new();
+  items.push_signal(Signal::StartAlignment);
+  items.extend(item);
+  items.push_signal(Signal::FinishAlignment);
+  items
+}
+
 pub fn with_no_new_lines(item: PrintItems) -> PrintItems {
   if item.is_empty() {
     return item;
@@ -97,6 +112,30 @@ fn gen_from_raw_string_lines(text: &str, gen_line: impl Fn(&str) -> PrintItems)
   items
 }
 
+/// Generates IR that prints the text in the range of the file text exactly as
+/// it's written, such as for a node ignored with a `dprint-ignore` comment.
+///
+/// The first line is printed at the current position and the following lines keep
+/// their original indentation rather than being indented by the printer.
+pub fn gen_verbatim(file_text: &str, range: Range<usize>) -> PrintItems {
+  let mut items = PrintItems::new();
+  let mut lines = file_text[range].split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line));
+  // the first line is indented by the printer since it starts at the current position
+  if let Some(first_line) = lines.next() {
+    items.extend(gen_from_string_line(first_line));
+  }
+  let mut remaining_lines = lines.peekable();
+  if remaining_lines.peek().is_some() {
+    items.push_signal(Signal::StartIgnoringIndent);
+    for line in remaining_lines {
+      items.push_signal(Signal::NewLine);
+      items.extend(gen_from_string_line(line));
+    }
+    items.push_signal(Signal::FinishIgnoringIndent);
+  }
+  items
+}
+
 /// Generates IR from a string to a series of PrintItems.
 pub fn gen_from_string(text: &str) -> PrintItems {
   gen_string_lines(text, gen_from_string_line)
@@ -277,3 +316,36 @@ pub fn text_has_dprint_ignore(text: &str, searching_text: &str) -> bool {
     false
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::super::super::format;
+  use super::super::super::PrintOptions;
+  use super::*;
+
+  #[test]
+  fn should_gen_verbatim() {
+    let file_text = "function a() {\n    const   x =\t[1,\n  2];  \n}\n";
+    let start = file_text.find("const").unwrap();
+    let end = file_text.find(";").unwrap() + 1;
+    let result = format(
+      || {
+        let mut items = PrintItems::new();
+        items.push_str_runtime_width_computed("{");
+        items.push_signal(Signal::NewLine);
+        items.extend(with_indent(gen_verbatim(file_text, start..end)));
+        items.push_signal(Signal::NewLine);
+        items.push_str_runtime_width_computed("}");
+        items
+      },
+      PrintOptions {
+        indent_width: 2,
+        max_width: 10,
+        use_tabs: false,
+        smart_tabs: false,
+        new_line_text: "\n",
+      },
+    );
+    assert_eq!(result, "{\n  const   x =\t[1,\n  2];\n}");
+  }
+}
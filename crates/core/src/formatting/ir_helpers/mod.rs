@@ -1,5 +1,7 @@
+mod embedded_code;
 mod gen_separated_values;
 mod helpers;
 
+pub use embedded_code::*;
 pub use gen_separated_values::*;
 pub use helpers::*;
@@ -0,0 +1,469 @@
+//! Serialization of print items, which allows generating the IR in one
+//! process or wasm module and printing it in another.
+//!
+//! Only conditions created with `Condition::new_serializable` can be serialized
+//! because the resolvers of other conditions are closures.
+
+use std::collections::HashMap;
+
+use anyhow::bail;
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::condition_resolvers;
+use super::thread_state;
+use super::AlignmentGroup;
+use super::Anchor;
+use super::ColumnNumber;
+use super::Condition;
+use super::ConditionExpr;
+use super::ConditionProperties;
+use super::ConditionReevaluation;
+use super::IndentLevel;
+use super::Info;
+use super::InfoKind;
+use super::InfoRef;
+use super::IsStartOfLine;
+use super::LineNumber;
+use super::LineNumberAnchor;
+use super::LineStartColumnNumber;
+use super::LineStartIndentLevel;
+use super::PrintItem;
+use super::PrintItemPath;
+use super::PrintItems;
+use super::PrintNodeCell;
+use super::Signal;
+use super::ValueExpr;
+
+/// Print items in a form that can be serialized.
+///
+/// The print items are a graph of nodes because paths may be shared, so
+/// each node is stored once and referenced by its index in `nodes`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedPrintItems {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub first_node: Option<u32>,
+  pub nodes: Vec<SerializedPrintNode>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedPrintNode {
+  pub item: SerializedPrintItem,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub next: Option<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "content", rename_all = "camelCase")]
+pub enum SerializedPrintItem {
+  String(String),
+  Signal(Signal),
+  Condition(SerializedCondition),
+  Info(InfoRef),
+  Anchor(SerializedLineNumberAnchor),
+  /// Index of the node the path starts at.
+  RcPath(u32),
+  ConditionReevaluation(SerializedConditionReevaluation),
+  /// Identifier of the alignment group.
+  AlignmentAnchor(u32),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedCondition {
+  pub id: u32,
+  /// Name for debugging purposes.
+  pub name: String,
+  pub is_stored: bool,
+  pub store_save_point: bool,
+  pub condition: ConditionExpr,
+  /// Index of the node the true path starts at.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub true_path: Option<u32>,
+  /// Index of the node the false path starts at.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub false_path: Option<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedLineNumberAnchor {
+  pub anchor_id: u32,
+  pub line_number_id: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedConditionReevaluation {
+  pub condition_reevaluation_id: u32,
+  pub condition_id: u32,
+}
+
+impl SerializedPrintItems {
+  /// Converts the print items to a serializable form.
+  ///
+  /// Errors when the print items contain a condition that wasn't
+  /// created with `Condition::new_serializable`.
+  pub fn from_print_items(print_items: &PrintItems) -> Result<Self> {
+    let mut builder = SerializedNodesBuilder::default();
+    let first_node = print_items.first_node.map(|node| builder.get_node_index(node));
+    while let Some((index, node)) = builder.pending.pop() {
+      let item = builder.serialize_item(node.get_item())?;
+      let next = node.get_next().map(|next| builder.get_node_index(next));
+      builder.nodes[index as usize] = Some(SerializedPrintNode { item, next });
+    }
+    Ok(Self {
+      first_node,
+      nodes: builder.nodes.into_iter().map(|node| node.unwrap()).collect(),
+    })
+  }
+
+  /// Creates print items from the serialized print items.
+  ///
+  /// The identifiers of the infos and conditions are replaced with new
+  /// ones so they don't collide with any print items created in this thread.
+  ///
+  /// Note: This should only be called within the closure provided to `format`.
+  pub fn into_print_items(self) -> Result<PrintItems> {
+    let node_count = self.nodes.len() as u32;
+    let get_index = |index: u32| -> Result<usize> {
+      if index >= node_count {
+        bail!("Node index {} was out of bounds of the {} nodes.", index, node_count);
+      }
+      Ok(index as usize)
+    };
+
+    let nodes = thread_state::with_bump_allocator(|bump| {
+      (0..node_count)
+        .map(|_| bump.alloc_print_node_cell(PrintNodeCell::new(PrintItem::Signal(Signal::NewLine))))
+        .collect::<Vec<_>>()
+    });
+    for (node, serialized_node) in nodes.iter().zip(&self.nodes) {
+      if let Some(next) = serialized_node.next {
+        node.set_next(Some(nodes[get_index(next)?]));
+      }
+    }
+
+    // create the conditions first so that expressions may reference any condition
+    let mut ids = IdRemapper::default();
+    let mut conditions = HashMap::new();
+    let mut allocated_conditions = HashMap::new();
+    for serialized_node in &self.nodes {
+      if let SerializedPrintItem::Condition(serialized_condition) = &serialized_node.item {
+        let condition = Condition::new(
+          "serializedCondition",
+          ConditionProperties {
+            condition: condition_resolvers::false_resolver(),
+            true_path: None,
+            false_path: None,
+          },
+        );
+        ids.conditions.insert(serialized_condition.id, condition.unique_id());
+        conditions.insert(serialized_condition.id, condition);
+      }
+    }
+
+    for (node, serialized_node) in nodes.iter().zip(self.nodes) {
+      let item = match serialized_node.item {
+        SerializedPrintItem::String(text) => {
+          let text = thread_state::with_bump_allocator(|bump| bump.alloc_string(text.into()));
+          PrintItem::String(text)
+        }
+        SerializedPrintItem::Signal(signal) => PrintItem::Signal(signal),
+        SerializedPrintItem::Condition(serialized_condition) => {
+          // a condition may be in the print items more than once when it was cloned
+          let condition = match conditions.remove(&serialized_condition.id) {
+            Some(mut condition) => {
+              let mut expr = serialized_condition.condition;
+              ids.remap_condition_expr(&mut expr)?;
+              condition.set_expr(expr);
+              condition.is_stored = serialized_condition.is_stored;
+              condition.store_save_point = serialized_condition.store_save_point;
+              condition.true_path = serialized_condition
+                .true_path
+                .map(|index| get_index(index).map(|index| nodes[index]))
+                .transpose()?;
+              condition.false_path = serialized_condition
+                .false_path
+                .map(|index| get_index(index).map(|index| nodes[index]))
+                .transpose()?;
+              let condition = thread_state::with_bump_allocator(|bump| bump.alloc_condition(condition));
+              allocated_conditions.insert(serialized_condition.id, condition);
+              condition
+            }
+            None => allocated_conditions[&serialized_condition.id],
+          };
+          PrintItem::Condition(condition)
+        }
+        SerializedPrintItem::Info(info) => PrintItem::Info(ids.get_info(info)),
+        SerializedPrintItem::Anchor(anchor) => PrintItem::Anchor(Anchor::LineNumber(ids.get_line_number_anchor(anchor))),
+        SerializedPrintItem::RcPath(index) => PrintItem::RcPath(nodes[get_index(index)?]),
+        SerializedPrintItem::ConditionReevaluation(reevaluation) => PrintItem::ConditionReevaluation(ids.get_condition_reevaluation(reevaluation)?),
+        SerializedPrintItem::AlignmentAnchor(group_id) => PrintItem::AlignmentAnchor(ids.get_alignment_group(group_id)),
+      };
+      node.set_item(item);
+    }
+
+    let mut print_items = PrintItems::new();
+    if let Some(first_node) = self.first_node {
+      print_items.push_path(nodes[get_index(first_node)?]);
+    }
+    Ok(print_items)
+  }
+}
+
+#[derive(Default)]
+struct SerializedNodesBuilder {
+  node_indexes: HashMap<*const PrintNodeCell, u32>,
+  nodes: Vec<Option<SerializedPrintNode>>,
+  pending: Vec<(u32, PrintItemPath)>,
+}
+
+impl SerializedNodesBuilder {
+  fn get_node_index(&mut self, node: PrintItemPath) -> u32 {
+    let key = node as *const PrintNodeCell;
+    if let Some(index) = self.node_indexes.get(&key) {
+      return *index;
+    }
+    let index = self.nodes.len() as u32;
+    self.nodes.push(None);
+    self.node_indexes.insert(key, index);
+    self.pending.push((index, node));
+    index
+  }
+
+  fn serialize_item(&mut self, item: PrintItem) -> Result<SerializedPrintItem> {
+    Ok(match item {
+      PrintItem::String(text) => SerializedPrintItem::String(text.text.to_string()),
+      PrintItem::Signal(signal) => SerializedPrintItem::Signal(signal),
+      PrintItem::Condition(condition) => {
+        let expr = match condition.expr() {
+          Some(expr) => expr.clone(),
+          None => bail!(
+            "Condition '{}' could not be serialized because it was not created with Condition::new_serializable.",
+            condition.name()
+          ),
+        };
+        SerializedPrintItem::Condition(SerializedCondition {
+          id: condition.unique_id(),
+          name: condition.name().to_string(),
+          is_stored: condition.is_stored,
+          store_save_point: condition.store_save_point,
+          condition: expr,
+          true_path: condition.true_path.map(|node| self.get_node_index(node)),
+          false_path: condition.false_path.map(|node| self.get_node_index(node)),
+        })
+      }
+      PrintItem::Info(info) => SerializedPrintItem::Info(InfoRef {
+        kind: info.kind(),
+        id: info.unique_id(),
+      }),
+      PrintItem::Anchor(Anchor::LineNumber(anchor)) => SerializedPrintItem::Anchor(SerializedLineNumberAnchor {
+        anchor_id: anchor.unique_id(),
+        line_number_id: anchor.line_number_id(),
+      }),
+      PrintItem::RcPath(node) => SerializedPrintItem::RcPath(self.get_node_index(node)),
+      PrintItem::ConditionReevaluation(reevaluation) => SerializedPrintItem::ConditionReevaluation(SerializedConditionReevaluation {
+        condition_reevaluation_id: reevaluation.condition_reevaluation_id,
+        condition_id: reevaluation.condition_id,
+      }),
+      PrintItem::AlignmentAnchor(group) => SerializedPrintItem::AlignmentAnchor(group.unique_id()),
+    })
+  }
+}
+
+/// Maps the identifiers of the serialized print items to new identifiers.
+#[derive(Default)]
+struct IdRemapper {
+  conditions: HashMap<u32, u32>,
+  infos: HashMap<InfoRef, Info>,
+  line_number_anchors: HashMap<u32, LineNumberAnchor>,
+  condition_reevaluations: HashMap<u32, ConditionReevaluation>,
+  alignment_groups: HashMap<u32, AlignmentGroup>,
+}
+
+impl IdRemapper {
+  fn get_condition_id(&self, id: u32) -> Result<u32> {
+    match self.conditions.get(&id) {
+      Some(id) => Ok(*id),
+      None => bail!("Condition {} was referenced, but was not in the print items.", id),
+    }
+  }
+
+  fn get_info(&mut self, info: InfoRef) -> Info {
+    *self.infos.entry(info).or_insert_with(|| {
+      let name = "serializedInfo";
+      match info.kind {
+        InfoKind::LineNumber => LineNumber::new(name).into(),
+        InfoKind::ColumnNumber => ColumnNumber::new(name).into(),
+        InfoKind::IsStartOfLine => IsStartOfLine::new(name).into(),
+        InfoKind::IndentLevel => IndentLevel::new(name).into(),
+        InfoKind::LineStartColumnNumber => LineStartColumnNumber::new(name).into(),
+        InfoKind::LineStartIndentLevel => LineStartIndentLevel::new(name).into(),
+      }
+    })
+  }
+
+  fn get_line_number_anchor(&mut self, anchor: SerializedLineNumberAnchor) -> LineNumberAnchor {
+    if let Some(line_number_anchor) = self.line_number_anchors.get(&anchor.anchor_id) {
+      return line_number_anchor.clone();
+    }
+    let line_number = match self.get_info(InfoRef {
+      kind: InfoKind::LineNumber,
+      id: anchor.line_number_id,
+    }) {
+      Info::LineNumber(line_number) => line_number,
+      _ => unreachable!(),
+    };
+    let line_number_anchor = LineNumberAnchor::new(line_number);
+    self.line_number_anchors.insert(anchor.anchor_id, line_number_anchor.clone());
+    line_number_anchor
+  }
+
+  fn get_condition_reevaluation(&mut self, reevaluation: SerializedConditionReevaluation) -> Result<ConditionReevaluation> {
+    if let Some(condition_reevaluation) = self.condition_reevaluations.get(&reevaluation.condition_reevaluation_id) {
+      return Ok(*condition_reevaluation);
+    }
+    let condition_reevaluation = ConditionReevaluation::new("serializedConditionReevaluation", self.get_condition_id(reevaluation.condition_id)?);
+    self
+      .condition_reevaluations
+      .insert(reevaluation.condition_reevaluation_id, condition_reevaluation);
+    Ok(condition_reevaluation)
+  }
+
+  fn get_alignment_group(&mut self, id: u32) -> AlignmentGroup {
+    *self
+      .alignment_groups
+      .entry(id)
+      .or_insert_with(|| AlignmentGroup::new("serializedAlignmentGroup"))
+  }
+
+  fn remap_condition_expr(&mut self, expr: &mut ConditionExpr) -> Result<()> {
+    match expr {
+      ConditionExpr::Bool(_) | ConditionExpr::IsStartOfLine | ConditionExpr::IsStartOfLineIndented | ConditionExpr::IsForcingNoNewLines => {}
+      ConditionExpr::Not(expr) => self.remap_condition_expr(expr)?,
+      ConditionExpr::And(exprs) | ConditionExpr::Or(exprs) => {
+        for expr in exprs {
+          self.remap_condition_expr(expr)?;
+        }
+      }
+      ConditionExpr::Condition(id) => *id = self.get_condition_id(*id)?,
+      ConditionExpr::Compare(left, _, right) => {
+        self.remap_value_expr(left);
+        self.remap_value_expr(right);
+      }
+    }
+    Ok(())
+  }
+
+  fn remap_value_expr(&mut self, expr: &mut ValueExpr) {
+    match expr {
+      ValueExpr::Number(_) | ValueExpr::Writer(_) => {}
+      ValueExpr::Info(info) => info.id = self.get_info(*info).unique_id(),
+      ValueExpr::Add(left, right) | ValueExpr::Subtract(left, right) => {
+        self.remap_value_expr(left);
+        self.remap_value_expr(right);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::super::*;
+  use super::*;
+
+  #[test]
+  fn should_round_trip_print_items() {
+    let get_print_items = || {
+      let end_line_number = LineNumber::new("end");
+      let mut items = PrintItems::new();
+      items.push_str_runtime_width_computed("start");
+      // look ahead to an info that's printed later
+      let mut is_multi_line = Condition::new_serializable(
+        "isMultiLine",
+        SerializableConditionProperties {
+          condition: ConditionExpr::compare(
+            ValueExpr::info(end_line_number),
+            CompareOp::GreaterThan,
+            ValueExpr::Writer(WriterValue::LineNumber),
+          ),
+          true_path: Some(" {multi}".into()),
+          false_path: Some(" {single}".into()),
+        },
+      );
+      let is_multi_line_reference = is_multi_line.create_reference();
+      items.push_condition(is_multi_line);
+      items.push_signal(Signal::NewLine);
+      let shared_path = PrintItems::from("shared").into_rc_path();
+      items.push_optional_path(shared_path);
+      items.push_condition(Condition::new_serializable(
+        "isNotMultiLine",
+        SerializableConditionProperties {
+          condition: ConditionExpr::Not(Box::new(ConditionExpr::condition(&is_multi_line_reference))),
+          true_path: Some("!".into()),
+          false_path: Some("?".into()),
+        },
+      ));
+      items.push_signal(Signal::NewLine);
+      items.push_optional_path(shared_path);
+      items.push_info(end_line_number);
+      items
+    };
+
+    let expected = format(get_print_items, get_print_options());
+    assert_eq!(expected, "start {multi}\nshared?\nshared");
+
+    let mut json = String::new();
+    format(
+      || {
+        let print_items = get_print_items();
+        let serialized = SerializedPrintItems::from_print_items(&print_items).unwrap();
+        json = serde_json::to_string(&serialized).unwrap();
+        print_items
+      },
+      get_print_options(),
+    );
+    let text = format(
+      || {
+        let serialized: SerializedPrintItems = serde_json::from_str(&json).unwrap();
+        serialized.into_print_items().unwrap()
+      },
+      get_print_options(),
+    );
+    assert_eq!(text, expected);
+  }
+
+  #[test]
+  fn should_error_for_closure_conditions() {
+    format(
+      || {
+        let mut items = PrintItems::new();
+        items.push_condition(conditions::if_true("closureCondition", condition_resolvers::true_resolver(), "a".into()));
+        let err = SerializedPrintItems::from_print_items(&items).err().unwrap();
+        assert_eq!(
+          err.to_string(),
+          format!(
+            "Condition '{}' could not be serialized because it was not created with Condition::new_serializable.",
+            if cfg!(debug_assertions) { "closureCondition" } else { "condition" }
+          )
+        );
+        items
+      },
+      get_print_options(),
+    );
+  }
+
+  fn get_print_options() -> PrintOptions {
+    PrintOptions {
+      indent_width: 2,
+      max_width: 40,
+      use_tabs: false,
+      smart_tabs: false,
+      new_line_text: "\n",
+    }
+  }
+}
@@ -3,8 +3,10 @@ pub mod condition_helpers;
 pub mod condition_resolvers;
 pub mod conditions;
 pub mod ir_helpers;
+pub mod ir_serialization;
 
 mod collections;
+mod condition_expr;
 mod infinite_reevaluation_protection;
 mod print;
 mod print_items;
@@ -18,15 +20,26 @@ mod writer;
 pub mod tokens;
 pub mod utils;
 
+pub use condition_expr::*;
 pub use print::format;
+pub use print::format_range;
+pub use print::format_to_io_writer;
+pub use print::format_to_writer;
+pub use print::format_with_report;
+pub use print::measure;
 pub use print::print;
 #[cfg(feature = "tracing")]
 pub use print::trace_printing;
+pub use print::ExceededWidth;
+pub use print::FormatReport;
+pub use print::Measurement;
 pub use print::PrintOptions;
 #[cfg(feature = "tracing")]
 pub use print::TracingResult;
 pub use print_items::*;
+pub use printer::PrintRange;
 use printer::*;
+pub use thread_state::release_thread_memory;
 #[cfg(feature = "tracing")]
 use tracing::*;
 use write_items::*;
@@ -1,10 +1,15 @@
 use std::cell::RefCell;
+use std::fmt;
+use std::io;
 
 use self::thread_state::BumpAllocator;
 
 use super::*;
 
 /// Options for printing the print items.
+///
+/// Use `..Default::default()` when constructing this so that
+/// options added in the future don't cause compile errors.
 pub struct PrintOptions {
   /// The width the printer will attempt to keep the line under.
   pub max_width: u32,
@@ -12,10 +17,26 @@ pub struct PrintOptions {
   pub indent_width: u8,
   /// Whether to use tabs for indenting.
   pub use_tabs: bool,
+  /// When using tabs, whether to use spaces for alignment beyond the
+  /// indentation level instead of filling it with as many tabs as possible.
+  pub smart_tabs: bool,
   /// The newline character to use when doing a new line.
   pub new_line_text: &'static str,
 }
 
+impl Default for PrintOptions {
+  /// The recommended global configuration (ex. a max width of 120).
+  fn default() -> Self {
+    Self {
+      max_width: 120,
+      indent_width: 2,
+      use_tabs: false,
+      smart_tabs: false,
+      new_line_text: "\n",
+    }
+  }
+}
+
 impl PrintOptions {
   pub(super) fn to_printer_options(&self) -> PrinterOptions {
     PrinterOptions {
@@ -33,12 +54,45 @@ impl PrintOptions {
 /// outside of the closure, since they are created with a thread local allocator
 /// that is reset once this function returns.
 pub fn format(get_print_items: impl FnOnce() -> PrintItems, options: PrintOptions) -> String {
+  let mut text = String::new();
+  // writing to a string never fails
+  format_to_writer(get_print_items, options, &mut text).unwrap();
+  text
+}
+
+/// Same as `format`, but writes the output to the writer chunk by chunk
+/// instead of collecting it into a string.
+pub fn format_to_writer(get_print_items: impl FnOnce() -> PrintItems, options: PrintOptions, writer: &mut impl fmt::Write) -> fmt::Result {
   increment_formatting_count();
   let old_counts = thread_state::take_counts();
   let print_items = get_print_items();
 
   let result = thread_state::with_bump_allocator(|bump| {
-    let result = print_with_allocator(bump, &print_items, &options);
+    let result = write_with_allocator(bump, &print_items, &options, writer);
+    if decrement_formatting_count() {
+      bump.reset();
+    }
+    result
+  });
+  thread_state::set_counts(old_counts);
+  result
+}
+
+/// Same as `format`, but only outputs the text printed between the infos of the
+/// range returned by `get_print_items`, which is useful for range formatting.
+///
+/// The whole file is still printed in order to know how the range is laid out, but
+/// the text outside the range is never collected. Returns `None` when the start
+/// or end info wasn't printed or the end info was printed before the start info.
+pub fn format_range(get_print_items: impl FnOnce() -> (PrintItems, PrintRange), options: PrintOptions) -> Option<String> {
+  increment_formatting_count();
+  let old_counts = thread_state::take_counts();
+  let (print_items, range) = get_print_items();
+
+  let result = thread_state::with_bump_allocator(|bump| {
+    let result = Printer::new(bump, print_items.first_node, options.to_printer_options())
+      .print_range(range)
+      .map(|write_items| WriteItemsPrinter::from(&options).print(write_items));
     if decrement_formatting_count() {
       bump.reset();
     }
@@ -48,6 +102,183 @@ pub fn format(get_print_items: impl FnOnce() -> PrintItems, options: PrintOption
   result
 }
 
+/// The result of `format_with_report`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatReport {
+  pub text: String,
+  /// The lines that couldn't be kept under the max width.
+  pub exceeded_widths: Vec<ExceededWidth>,
+}
+
+/// A line of the output that exceeded the max width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExceededWidth {
+  /// The zero-based line number.
+  pub line_number: u32,
+  /// The column number at the end of the line.
+  pub column_number: u32,
+}
+
+/// Same as `format`, but also reports the lines the printer couldn't keep under
+/// the max width, which is useful for warning about lines that couldn't be wrapped.
+pub fn format_with_report(get_print_items: impl FnOnce() -> PrintItems, options: PrintOptions) -> FormatReport {
+  increment_formatting_count();
+  let old_counts = thread_state::take_counts();
+  let print_items = get_print_items();
+
+  let mut exceeded_widths = Vec::new();
+  let text = thread_state::with_bump_allocator(|bump| {
+    let text = match Printer::new(bump, print_items.first_node, options.to_printer_options()).print() {
+      Some(write_items) => {
+        let mut collector = ExceededWidthCollector::new(&options);
+        let text = WriteItemsPrinter::from(&options).print(write_items.inspect(|item| collector.add(item)));
+        exceeded_widths = collector.finish();
+        text
+      }
+      None => String::new(),
+    };
+    if decrement_formatting_count() {
+      bump.reset();
+    }
+    text
+  });
+  thread_state::set_counts(old_counts);
+  FormatReport { text, exceeded_widths }
+}
+
+/// Tracks the width of each line from the write items.
+struct ExceededWidthCollector {
+  max_width: u32,
+  indent_width: u32,
+  line_number: u32,
+  column_number: u32,
+  exceeded_widths: Vec<ExceededWidth>,
+}
+
+impl ExceededWidthCollector {
+  pub fn new(options: &PrintOptions) -> Self {
+    Self {
+      max_width: options.max_width,
+      indent_width: options.indent_width as u32,
+      line_number: 0,
+      column_number: 0,
+      exceeded_widths: Vec::new(),
+    }
+  }
+
+  pub fn add(&mut self, item: &WriteItem) {
+    match item {
+      WriteItem::String(text) => self.column_number += text.char_count,
+      WriteItem::Indent(times) => self.column_number += *times as u32 * self.indent_width,
+      WriteItem::Alignment(columns) => self.column_number += columns,
+      WriteItem::Tab => self.column_number += self.indent_width,
+      WriteItem::Space => self.column_number += 1,
+      WriteItem::NewLine => {
+        self.finish_line();
+        self.line_number += 1;
+        self.column_number = 0;
+      }
+    }
+  }
+
+  pub fn finish(mut self) -> Vec<ExceededWidth> {
+    self.finish_line();
+    self.exceeded_widths
+  }
+
+  fn finish_line(&mut self) {
+    if self.column_number > self.max_width {
+      self.exceeded_widths.push(ExceededWidth {
+        line_number: self.line_number,
+        column_number: self.column_number,
+      });
+    }
+  }
+}
+
+/// The space print items would take up when printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measurement {
+  /// The number of lines.
+  pub line_count: u32,
+  /// The width of the widest line, including its indentation.
+  pub max_width: u32,
+  /// The width of the last line, including its indentation.
+  pub last_line_width: u32,
+}
+
+impl Measurement {
+  /// Gets if the print items were printed on one line within the provided width.
+  pub fn fits_on_one_line(&self, width: u32) -> bool {
+    self.line_count == 1 && self.max_width <= width
+  }
+}
+
+/// Measures the width and line count the print items would take up when
+/// printed at the provided indent level, without producing any output.
+///
+/// This is useful for deciding how to lay out a construct based on whether
+/// another layout fits. The print items may still be used afterwards.
+///
+/// Note: This should only be called within the closure provided to `format`.
+pub fn measure(print_items: &PrintItems, indent_level: u8, options: &PrintOptions) -> Measurement {
+  // This shouldn't be called without calling `format` because it doesn't
+  // reset the allocator.
+  panic_if_not_formatting();
+
+  let indent_width = options.indent_width as u32;
+  let mut measurement = Measurement {
+    line_count: 1,
+    max_width: 0,
+    last_line_width: 0,
+  };
+  thread_state::with_bump_allocator(|bump| {
+    let printer = Printer::new(bump, print_items.first_node, options.to_printer_options()).with_indent_level(indent_level);
+    for item in printer.print().into_iter().flatten() {
+      match item {
+        WriteItem::String(text) => measurement.last_line_width += text.char_count,
+        WriteItem::Indent(times) => measurement.last_line_width += times as u32 * indent_width,
+        WriteItem::Alignment(columns) => measurement.last_line_width += columns,
+        WriteItem::Tab => measurement.last_line_width += indent_width,
+        WriteItem::Space => measurement.last_line_width += 1,
+        WriteItem::NewLine => {
+          measurement.max_width = std::cmp::max(measurement.max_width, measurement.last_line_width);
+          measurement.line_count += 1;
+          measurement.last_line_width = 0;
+        }
+      }
+    }
+  });
+  measurement.max_width = std::cmp::max(measurement.max_width, measurement.last_line_width);
+  measurement
+}
+
+/// Same as `format_to_writer`, but for an `io::Write`.
+///
+/// Note: Many small writes are done, so provide a buffered writer.
+pub fn format_to_io_writer(get_print_items: impl FnOnce() -> PrintItems, options: PrintOptions, writer: &mut impl io::Write) -> io::Result<()> {
+  let mut writer = IoWriteAdapter { writer, error: None };
+  match format_to_writer(get_print_items, options, &mut writer) {
+    Ok(()) => Ok(()),
+    Err(_) => Err(writer.error.unwrap_or_else(|| io::Error::other("Failed writing the formatted output."))),
+  }
+}
+
+/// Writes to an `io::Write` while keeping the error that occurred.
+struct IoWriteAdapter<'a, TWrite: io::Write> {
+  writer: &'a mut TWrite,
+  error: Option<io::Error>,
+}
+
+impl<'a, TWrite: io::Write> fmt::Write for IoWriteAdapter<'a, TWrite> {
+  fn write_str(&mut self, text: &str) -> fmt::Result {
+    self.writer.write_all(text.as_bytes()).map_err(|err| {
+      self.error = Some(err);
+      fmt::Error
+    })
+  }
+}
+
 /// Prints out the print items using the provided options.
 ///
 /// Note: This should only be used in rare scenarios. In most cases,
@@ -58,15 +289,17 @@ pub fn print(print_items: PrintItems, options: PrintOptions) -> String {
   panic_if_not_formatting();
 
   let old_counts = thread_state::take_counts();
-  let result = thread_state::with_bump_allocator(|bump| print_with_allocator(bump, &print_items, &options));
+  let mut text = String::new();
+  // writing to a string never fails
+  thread_state::with_bump_allocator(|bump| write_with_allocator(bump, &print_items, &options, &mut text)).unwrap();
   thread_state::set_counts(old_counts);
-  result
+  text
 }
 
-fn print_with_allocator(bump: &mut BumpAllocator, print_items: &PrintItems, options: &PrintOptions) -> String {
+fn write_with_allocator(bump: &mut BumpAllocator, print_items: &PrintItems, options: &PrintOptions, writer: &mut impl fmt::Write) -> fmt::Result {
   match Printer::new(bump, print_items.first_node, options.to_printer_options()).print() {
-    Some(write_items) => WriteItemsPrinter::from(options).print(write_items),
-    None => String::new(),
+    Some(write_items) => WriteItemsPrinter::from(options).write_to(write_items, writer),
+    None => Ok(()),
   }
 }
 
@@ -139,6 +372,10 @@ fn decrement_formatting_count() -> bool {
   })
 }
 
+pub(super) fn is_formatting() -> bool {
+  FORMATTING_COUNT.with(|formatting_count_cell| *formatting_count_cell.borrow() > 0)
+}
+
 fn panic_if_not_formatting() {
   FORMATTING_COUNT.with(|formatting_count_cell| {
     if *formatting_count_cell.borrow() == 0 {
@@ -152,7 +389,16 @@ mod test {
   use crate::formatting::LineNumber;
 
   use super::super::PrintItems;
+  use super::super::PrintRange;
+  use super::super::Signal;
   use super::format;
+  use super::format_range;
+  use super::format_to_io_writer;
+  use super::format_to_writer;
+  use super::format_with_report;
+  use super::measure;
+  use super::ExceededWidth;
+  use super::Measurement;
   use super::PrintOptions;
 
   #[test]
@@ -187,11 +433,197 @@ mod test {
     );
   }
 
+  #[test]
+  fn test_format_to_writer() {
+    fn get_print_items() -> PrintItems {
+      let mut items = PrintItems::new();
+      items.push_str_runtime_width_computed("a");
+      items.push_signal(Signal::StartIndent);
+      items.push_signal(Signal::NewLine);
+      items.push_str_runtime_width_computed("b");
+      items.push_signal(Signal::Tab);
+      items.push_str_runtime_width_computed("c");
+      items.push_signal(Signal::FinishIndent);
+      items
+    }
+
+    let expected = format(get_print_items, get_print_options());
+    assert_eq!(expected, "a\n  b\tc");
+
+    let mut text = String::new();
+    format_to_writer(get_print_items, get_print_options(), &mut text).unwrap();
+    assert_eq!(text, expected);
+
+    let mut bytes = Vec::new();
+    format_to_io_writer(get_print_items, get_print_options(), &mut bytes).unwrap();
+    assert_eq!(String::from_utf8(bytes).unwrap(), expected);
+
+    // surfaces the error of the writer
+    struct FailingWriter;
+    impl std::io::Write for FailingWriter {
+      fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "broken"))
+      }
+
+      fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+      }
+    }
+    let err = format_to_io_writer(get_print_items, get_print_options(), &mut FailingWriter).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+  }
+
+  #[test]
+  fn test_format_range() {
+    fn get_print_items(print_end: bool) -> (PrintItems, PrintRange) {
+      let range = PrintRange {
+        start: LineNumber::new("start").into(),
+        end: LineNumber::new("end").into(),
+      };
+      let mut items = PrintItems::new();
+      items.push_str_runtime_width_computed("a");
+      items.push_signal(Signal::NewLine);
+      items.push_signal(Signal::StartIndent);
+      items.push_info(range.start);
+      items.push_str_runtime_width_computed("b");
+      items.push_signal(Signal::NewLine);
+      items.push_str_runtime_width_computed("c");
+      if print_end {
+        items.push_info(range.end);
+      }
+      items.push_str_runtime_width_computed("d");
+      items.push_signal(Signal::FinishIndent);
+      (items, range)
+    }
+
+    assert_eq!(format_range(|| get_print_items(true), get_print_options()), Some("  b\n  c".to_string()));
+    assert_eq!(format_range(|| get_print_items(false), get_print_options()), None);
+  }
+
+  #[test]
+  fn test_measure() {
+    let options = || PrintOptions {
+      max_width: 10,
+      ..get_print_options()
+    };
+    let text = format(
+      || {
+        let mut items = PrintItems::new();
+        items.push_str_runtime_width_computed("call(");
+        items.push_str_runtime_width_computed("arg");
+        items.push_str_runtime_width_computed(")");
+
+        let measurement = measure(&items, 0, &options());
+        assert_eq!(
+          measurement,
+          Measurement {
+            line_count: 1,
+            max_width: 9,
+            last_line_width: 9,
+          }
+        );
+        assert!(measurement.fits_on_one_line(10));
+        // indented twice
+        let measurement = measure(&items, 2, &options());
+        assert_eq!(measurement.max_width, 13);
+        assert!(!measurement.fits_on_one_line(10));
+
+        let mut multi_line = PrintItems::new();
+        multi_line.push_str_runtime_width_computed("first_word");
+        multi_line.push_signal(Signal::SpaceOrNewLine);
+        multi_line.push_str_runtime_width_computed("second");
+        assert_eq!(
+          measure(&multi_line, 1, &options()),
+          Measurement {
+            line_count: 2,
+            max_width: 12,
+            last_line_width: 8,
+          }
+        );
+
+        // the measured items can still be printed
+        items
+      },
+      options(),
+    );
+    assert_eq!(text, "call(arg)");
+  }
+
+  #[test]
+  fn test_format_with_report() {
+    fn get_print_items() -> PrintItems {
+      let mut items = PrintItems::new();
+      items.push_str_runtime_width_computed("short");
+      items.push_signal(Signal::NewLine);
+      items.push_signal(Signal::StartIndent);
+      items.push_str_runtime_width_computed("this_text_is_too_long_to_fit");
+      items.push_signal(Signal::SpaceOrNewLine);
+      items.push_str_runtime_width_computed("a");
+      items.push_signal(Signal::FinishIndent);
+      items.push_signal(Signal::NewLine);
+      items.push_str_runtime_width_computed("also_way_too_long_for_the_line");
+      items
+    }
+    let options = |max_width: u32| PrintOptions {
+      max_width,
+      ..get_print_options()
+    };
+
+    let report = format_with_report(get_print_items, options(40));
+    assert_eq!(report.text, "short\n  this_text_is_too_long_to_fit a\nalso_way_too_long_for_the_line");
+    assert_eq!(report.exceeded_widths, Vec::new());
+
+    let report = format_with_report(get_print_items, options(20));
+    assert_eq!(report.text, "short\n  this_text_is_too_long_to_fit\n  a\nalso_way_too_long_for_the_line");
+    assert_eq!(
+      report.exceeded_widths,
+      vec![
+        ExceededWidth {
+          line_number: 1,
+          column_number: 30,
+        },
+        ExceededWidth {
+          line_number: 3,
+          column_number: 30,
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_smart_tabs() {
+    fn get_print_items() -> PrintItems {
+      let mut items = PrintItems::new();
+      items.push_signal(Signal::StartIndent);
+      items.push_str_runtime_width_computed("call(");
+      items.push_signal(Signal::StartAlignment);
+      items.push_str_runtime_width_computed("a,");
+      items.push_signal(Signal::NewLine);
+      items.push_str_runtime_width_computed("b)");
+      items.push_signal(Signal::FinishAlignment);
+      items.push_signal(Signal::FinishIndent);
+      items
+    }
+    fn get_options(use_tabs: bool, smart_tabs: bool) -> PrintOptions {
+      PrintOptions {
+        indent_width: 4,
+        use_tabs,
+        smart_tabs,
+        ..get_print_options()
+      }
+    }
+
+    assert_eq!(format(get_print_items, get_options(false, false)), "    call(a,\n         b)");
+    assert_eq!(format(get_print_items, get_options(true, false)), "\tcall(a,\n\t\t b)");
+    assert_eq!(format(get_print_items, get_options(true, true)), "\tcall(a,\n\t     b)");
+  }
+
   fn get_print_options() -> PrintOptions {
     PrintOptions {
       max_width: 40,
       indent_width: 2,
       use_tabs: false,
+      smart_tabs: false,
       new_line_text: "\n",
     }
   }
@@ -6,6 +6,8 @@ use std::rc::Rc;
 use super::condition_resolvers;
 use super::printer::Printer;
 use super::thread_state;
+use super::ConditionExpr;
+use super::InfoKind;
 
 #[derive(Default)]
 pub struct PrintItems {
@@ -111,6 +113,12 @@ impl PrintItems {
     self.push_item_internal(PrintItem::Anchor(anchor.into()));
   }
 
+  /// Pushes an anchor that will be padded with spaces so that it's at the
+  /// same column as all the other anchors of the alignment group.
+  pub fn push_alignment_anchor(&mut self, group: AlignmentGroup) {
+    self.push_item_internal(PrintItem::AlignmentAnchor(group));
+  }
+
   pub fn push_reevaluation(&mut self, condition_reevaluation: ConditionReevaluation) {
     self.push_item_internal(PrintItem::ConditionReevaluation(condition_reevaluation));
   }
@@ -175,6 +183,7 @@ impl PrintItems {
             text.push_str(&get_line(format!("{}: {}", desc, name), &indent_text))
           }
           PrintItem::ConditionReevaluation(reevaluation) => text.push_str(&get_line(format!("Condition reevaluation: {}", reevaluation.name()), &indent_text)),
+          PrintItem::AlignmentAnchor(group) => text.push_str(&get_line(format!("Alignment anchor: {}", group.name()), &indent_text)),
         }
       }
 
@@ -277,6 +286,28 @@ pub struct Trace {
   pub print_node_id: u32,
   #[serde(skip_serializing_if = "Option::is_none")]
   pub writer_node_id: Option<u32>,
+  /// Snapshot of the writer after the print node was handled.
+  pub writer_info: WriterInfo,
+  /// What the condition or info of the print node resolved to.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub resolution: Option<TraceResolution>,
+}
+
+#[cfg(feature = "tracing")]
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", content = "content", rename_all = "camelCase")]
+pub enum TraceResolution {
+  /// The value a condition resolved to or `None` when it couldn't be resolved yet.
+  Condition(Option<bool>),
+  Info(TraceInfoValue),
+}
+
+#[cfg(feature = "tracing")]
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum TraceInfoValue {
+  Number(u32),
+  Bool(bool),
 }
 
 #[cfg(feature = "tracing")]
@@ -311,6 +342,7 @@ pub enum TracePrintItem {
   RcPath(u32),
   Anchor(TraceLineNumberAnchor),
   ConditionReevaluation(TraceConditionReevaluation),
+  AlignmentAnchor(TraceAlignmentAnchor),
 }
 
 #[cfg(feature = "tracing")]
@@ -359,6 +391,14 @@ pub struct TraceConditionReevaluation {
   pub name: String,
 }
 
+#[cfg(feature = "tracing")]
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceAlignmentAnchor {
+  pub alignment_group_id: u32,
+  pub name: String,
+}
+
 #[cfg(feature = "tracing")]
 #[derive(serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -420,6 +460,13 @@ impl PrintNodeCell {
     unsafe { (*self.value.get()).item.clone() }
   }
 
+  #[inline]
+  pub(super) fn set_item(&self, item: PrintItem) {
+    unsafe {
+      (*self.value.get()).item = item;
+    }
+  }
+
   #[inline]
   pub(super) fn get_next(&self) -> Option<PrintItemPath> {
     unsafe { (*self.value.get()).next }
@@ -482,6 +529,7 @@ pub(super) type UnsafePrintLifetime<T> = &'static T;
 
 /// The different items the printer could encounter.
 #[derive(Clone)]
+#[non_exhaustive]
 pub enum PrintItem {
   String(UnsafePrintLifetime<StringContainer>),
   Condition(UnsafePrintLifetime<Condition>),
@@ -490,9 +538,11 @@ pub enum PrintItem {
   Anchor(Anchor),
   Info(Info),
   ConditionReevaluation(ConditionReevaluation),
+  AlignmentAnchor(AlignmentGroup),
 }
 
-#[derive(Clone, PartialEq, Eq, Copy, Debug, serde::Serialize)]
+#[derive(Clone, PartialEq, Eq, Copy, Debug, serde::Serialize, serde::Deserialize)]
+#[non_exhaustive]
 pub enum Signal {
   /// Signal that a new line should occur based on the printer settings.
   NewLine,
@@ -529,6 +579,12 @@ pub enum Signal {
   FinishForceNoNewLines,
   /// Signal that a space should occur if not trailing.
   SpaceIfNotTrailing,
+  /// Signal the start of a section where new lines are aligned to the current
+  /// column. This is written as alignment after the indentation, which will be
+  /// spaces when using smart tabs.
+  StartAlignment,
+  /// Signal the end of an aligned section.
+  FinishAlignment,
 }
 
 #[derive(Clone)]
@@ -584,6 +640,68 @@ pub enum Info {
   LineStartIndentLevel(LineStartIndentLevel),
 }
 
+impl Info {
+  /// Creates an info of the provided kind for an existing unique id.
+  pub(super) fn from_kind_and_id(kind: InfoKind, id: u32) -> Self {
+    #[cfg(debug_assertions)]
+    let name = "serializedInfo";
+    match kind {
+      InfoKind::LineNumber => Info::LineNumber(LineNumber {
+        id,
+        #[cfg(debug_assertions)]
+        name,
+      }),
+      InfoKind::ColumnNumber => Info::ColumnNumber(ColumnNumber {
+        id,
+        #[cfg(debug_assertions)]
+        name,
+      }),
+      InfoKind::IsStartOfLine => Info::IsStartOfLine(IsStartOfLine {
+        id,
+        #[cfg(debug_assertions)]
+        name,
+      }),
+      InfoKind::IndentLevel => Info::IndentLevel(IndentLevel {
+        id,
+        #[cfg(debug_assertions)]
+        name,
+      }),
+      InfoKind::LineStartColumnNumber => Info::LineStartColumnNumber(LineStartColumnNumber {
+        id,
+        #[cfg(debug_assertions)]
+        name,
+      }),
+      InfoKind::LineStartIndentLevel => Info::LineStartIndentLevel(LineStartIndentLevel {
+        id,
+        #[cfg(debug_assertions)]
+        name,
+      }),
+    }
+  }
+
+  pub fn kind(&self) -> InfoKind {
+    match self {
+      Info::LineNumber(_) => InfoKind::LineNumber,
+      Info::ColumnNumber(_) => InfoKind::ColumnNumber,
+      Info::IsStartOfLine(_) => InfoKind::IsStartOfLine,
+      Info::IndentLevel(_) => InfoKind::IndentLevel,
+      Info::LineStartColumnNumber(_) => InfoKind::LineStartColumnNumber,
+      Info::LineStartIndentLevel(_) => InfoKind::LineStartIndentLevel,
+    }
+  }
+
+  pub fn unique_id(&self) -> u32 {
+    match self {
+      Info::LineNumber(info) => info.unique_id(),
+      Info::ColumnNumber(info) => info.unique_id(),
+      Info::IsStartOfLine(info) => info.unique_id(),
+      Info::IndentLevel(info) => info.unique_id(),
+      Info::LineStartColumnNumber(info) => info.unique_id(),
+      Info::LineStartIndentLevel(info) => info.unique_id(),
+    }
+  }
+}
+
 impl From<LineNumber> for Info {
   fn from(info: LineNumber) -> Self {
     Info::LineNumber(info)
@@ -850,6 +968,42 @@ impl ConditionReevaluation {
   }
 }
 
+/// A group of anchors that the printer will align to the same column.
+///
+/// Each anchor is padded with spaces to the furthest column any anchor of the
+/// group was printed at, which is useful for aligning trailing comments,
+/// table cells, or the values of assignments across lines.
+#[derive(Clone, PartialEq, Eq, Copy, Debug)]
+pub struct AlignmentGroup {
+  id: u32,
+  /// Name for debugging purposes.
+  #[cfg(debug_assertions)]
+  name: &'static str,
+}
+
+impl AlignmentGroup {
+  pub fn new(_name: &'static str) -> Self {
+    Self {
+      id: thread_state::next_alignment_group_id(),
+      #[cfg(debug_assertions)]
+      name: _name,
+    }
+  }
+
+  #[inline]
+  pub fn unique_id(&self) -> u32 {
+    self.id
+  }
+
+  #[inline]
+  pub fn name(&self) -> &'static str {
+    #[cfg(debug_assertions)]
+    return self.name;
+    #[cfg(not(debug_assertions))]
+    return "alignment_group";
+  }
+}
+
 /// Conditionally print items based on a condition.
 ///
 /// These conditions are extremely flexible and can even be resolved based on
@@ -871,6 +1025,8 @@ pub struct Condition {
   pub(super) true_path: Option<PrintItemPath>,
   /// The items to print when the condition is false or undefined (not yet resolved).
   pub(super) false_path: Option<PrintItemPath>,
+  /// The data the condition was created from when it's serializable.
+  pub(super) expr: Option<Rc<ConditionExpr>>,
 }
 
 impl Condition {
@@ -910,6 +1066,7 @@ impl Condition {
       condition: properties.condition,
       true_path: properties.true_path.and_then(|x| x.first_node),
       false_path: properties.false_path.and_then(|x| x.first_node),
+      expr: None,
     }
   }
 
@@ -1082,7 +1239,7 @@ pub struct StringContainer {
 impl StringContainer {
   /// Creates a new string container.
   pub fn new(text: UnsafePrintLifetime<str>) -> Self {
-    let char_count = unicode_width::UnicodeWidthStr::width(text) as u32;
+    let char_count = super::utils::str_width::get_str_width(text) as u32;
     Self { text, char_count }
   }
 
@@ -1095,6 +1252,8 @@ impl StringContainer {
 
 /// Information about a certain location being printed.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "tracing", derive(serde::Serialize))]
+#[cfg_attr(feature = "tracing", serde(rename_all = "camelCase"))]
 pub struct WriterInfo {
   pub line_number: u32,
   pub column_number: u32,
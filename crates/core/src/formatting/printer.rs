@@ -4,6 +4,8 @@ type BumpHashMap<'a, K, V> = hashbrown::HashMap<K, V, BuildHasherDefault<rustc_h
 
 use super::collections::*;
 use super::infinite_reevaluation_protection::InfiniteReevaluationProtector;
+use super::infinite_reevaluation_protection::ReevaluationDependency;
+use super::infinite_reevaluation_protection::ReevaluationDetails;
 use super::print_items::*;
 use super::thread_state;
 use super::thread_state::BumpAllocator;
@@ -26,6 +28,7 @@ pub struct SavePoint<'a> {
   pub look_ahead_indent_level_save_points: BumpHashMap<'a, u32, &'a SavePoint<'a>>,
   pub look_ahead_line_start_column_number_save_points: BumpHashMap<'a, u32, &'a SavePoint<'a>>,
   pub look_ahead_line_start_indent_level_save_points: BumpHashMap<'a, u32, &'a SavePoint<'a>>,
+  pub alignment_group_save_points: BumpHashMap<'a, u32, &'a SavePoint<'a>>,
   pub next_node_stack: NodeStack<'a>,
 }
 
@@ -45,6 +48,13 @@ pub struct PrinterOptions {
   pub enable_tracing: bool,
 }
 
+/// The infos marking the start and end of the output to print.
+#[derive(Clone, Copy, Debug)]
+pub struct PrintRange {
+  pub start: Info,
+  pub end: Info,
+}
+
 pub struct Printer<'a> {
   bump: &'a BumpAllocator,
   possible_new_line_save_point: Option<&'a SavePoint<'a>>,
@@ -65,6 +75,9 @@ pub struct Printer<'a> {
   resolved_indent_levels: VecU32U8Map,
   resolved_line_start_column_numbers: VecU32U32Map,
   resolved_line_start_indent_levels: VecU32U8Map,
+  // The column each alignment group is aligned to. This only ever increases
+  // so that printing stabilizes when re-printing for a wider anchor.
+  resolved_alignment_columns: VecU32U32Map,
   look_ahead_condition_save_points: BumpHashMap<'a, u32, &'a SavePoint<'a>>,
   look_ahead_line_number_save_points: BumpHashMap<'a, u32, &'a SavePoint<'a>>,
   look_ahead_column_number_save_points: BumpHashMap<'a, u32, &'a SavePoint<'a>>,
@@ -72,15 +85,26 @@ pub struct Printer<'a> {
   look_ahead_indent_level_save_points: BumpHashMap<'a, u32, &'a SavePoint<'a>>,
   look_ahead_line_start_column_number_save_points: BumpHashMap<'a, u32, &'a SavePoint<'a>>,
   look_ahead_line_start_indent_level_save_points: BumpHashMap<'a, u32, &'a SavePoint<'a>>,
+  alignment_group_save_points: BumpHashMap<'a, u32, &'a SavePoint<'a>>,
   infinite_reevaluation_protector: InfiniteReevaluationProtector,
+  /// What the condition being re-evaluated used when it was resolved.
+  reevaluation_dependencies: Vec<ReevaluationDependency>,
   next_node_stack: NodeStack<'a>,
   stored_condition_save_points: BumpHashMap<'a, u32, (&'a Condition, &'a SavePoint<'a>)>,
   max_width: u32,
+  /// The indent level printing started at.
+  initial_indent_level: u8,
   skip_moving_next: bool,
   resolving_save_point: Option<&'a SavePoint<'a>>,
+  range: Option<PrintRange>,
+  /// The writer's last item when the start and end infos of the range were printed.
+  range_start_item: Option<Option<&'a GraphNode<'a, WriteItem<'a>>>>,
+  range_end_item: Option<Option<&'a GraphNode<'a, WriteItem<'a>>>>,
   #[cfg(feature = "tracing")]
   traces: Option<Vec<Trace>>,
   #[cfg(feature = "tracing")]
+  trace_resolution: Option<TraceResolution>,
+  #[cfg(feature = "tracing")]
   start_time: std::time::Instant,
 }
 
@@ -108,6 +132,7 @@ impl<'a> Printer<'a> {
       resolved_indent_levels: VecU32U8Map::with_capacity(thread_state::next_indent_level_id()),
       resolved_line_start_column_numbers: VecU32U32Map::with_capacity(thread_state::next_line_start_column_number_id()),
       resolved_line_start_indent_levels: VecU32U8Map::with_capacity(thread_state::next_line_start_indent_level_id()),
+      resolved_alignment_columns: VecU32U32Map::with_capacity(thread_state::next_alignment_group_id()),
       look_ahead_condition_save_points: BumpHashMap::with_hasher_in(Default::default(), bump.inner()),
       look_ahead_line_number_save_points: BumpHashMap::with_hasher_in(Default::default(), bump.inner()),
       look_ahead_column_number_save_points: BumpHashMap::with_hasher_in(Default::default(), bump.inner()),
@@ -115,25 +140,70 @@ impl<'a> Printer<'a> {
       look_ahead_indent_level_save_points: BumpHashMap::with_hasher_in(Default::default(), bump.inner()),
       look_ahead_line_start_column_number_save_points: BumpHashMap::with_hasher_in(Default::default(), bump.inner()),
       look_ahead_line_start_indent_level_save_points: BumpHashMap::with_hasher_in(Default::default(), bump.inner()),
+      alignment_group_save_points: BumpHashMap::with_hasher_in(Default::default(), bump.inner()),
       infinite_reevaluation_protector: InfiniteReevaluationProtector::with_capacity(thread_state::next_condition_reevaluation_id()),
       stored_condition_save_points: BumpHashMap::with_hasher_in(Default::default(), bump.inner()),
+      reevaluation_dependencies: Vec::new(),
       next_node_stack: NodeStack::default(),
       max_width: options.max_width,
+      initial_indent_level: 0,
       skip_moving_next: false,
       resolving_save_point: None,
+      range: None,
+      range_start_item: None,
+      range_end_item: None,
       #[cfg(feature = "tracing")]
       traces: if options.enable_tracing { Some(Vec::new()) } else { None },
       #[cfg(feature = "tracing")]
+      trace_resolution: None,
+      #[cfg(feature = "tracing")]
       start_time: std::time::Instant::now(),
     }
   }
 
+  /// Starts printing at the provided indent level.
+  pub fn with_indent_level(mut self, indent_level: u8) -> Self {
+    self.initial_indent_level = indent_level;
+    for _ in 0..indent_level {
+      self.writer.start_indent();
+    }
+    self
+  }
+
   /// Turns the print items into a collection of writer items according to the options.
   pub fn print(mut self) -> Option<impl Iterator<Item = WriteItem<'a>>> {
     self.inner_print();
     self.writer.items()
   }
 
+  /// Turns the print items into a collection of writer items, but only for
+  /// the items written between the range's start and end infos.
+  ///
+  /// Returns `None` when either info wasn't printed.
+  pub fn print_range(mut self, range: PrintRange) -> Option<impl Iterator<Item = WriteItem<'a>>> {
+    self.range = Some(range);
+    self.inner_print();
+
+    let start_item = self.range_start_item?;
+    let mut current_item = self.range_end_item?;
+    let mut items = Vec::new();
+    loop {
+      match (current_item, start_item) {
+        (Some(item), Some(start_item)) if std::ptr::eq(item, start_item) => break,
+        // the start item may have been a trailing space that was removed
+        (Some(item), Some(start_item)) if start_item.previous.is_some_and(|previous| std::ptr::eq(item, previous)) => break,
+        (Some(item), _) => {
+          items.push(item.item);
+          current_item = item.previous;
+        }
+        (None, None) => break,
+        // the end info was printed before the start info
+        (None, Some(_)) => return None,
+      }
+    }
+    Some(items.into_iter().rev())
+  }
+
   /// Turns the print items into a collection of writer items according to the options along with traces.
   #[cfg(feature = "tracing")]
   pub fn print_for_tracing(mut self) -> PrintTracingResult<'a> {
@@ -174,21 +244,32 @@ impl<'a> Printer<'a> {
 
   #[cfg(feature = "tracing")]
   fn create_trace(&mut self, current_node: &PrintNode) {
+    let resolution = self.trace_resolution.take();
     if let Some(traces) = self.traces.as_mut() {
       traces.push(Trace {
         nanos: (std::time::Instant::now() - self.start_time).as_nanos(),
         print_node_id: current_node.print_node_id,
         writer_node_id: self.writer.current_node_id(),
+        writer_info: self.writer.writer_info(),
+        resolution,
       });
     }
   }
 
+  #[cfg(feature = "tracing")]
+  fn set_trace_resolution(&mut self, resolution: TraceResolution) {
+    if self.traces.is_some() {
+      self.trace_resolution = Some(resolution);
+    }
+  }
+
   #[inline]
   pub fn get_writer_info(&self) -> WriterInfo {
     self.writer.writer_info()
   }
 
   pub fn resolved_line_number(&mut self, line_number: LineNumber) -> Option<u32> {
+    self.add_reevaluation_dependency(ReevaluationDependency::Info(line_number.into()));
     let resolved_number = self.resolved_line_numbers.get(line_number.unique_id());
     if resolved_number.is_none() && !self.look_ahead_line_number_save_points.contains_key(&line_number.unique_id()) {
       let save_point = self.get_save_point_for_restoring_condition(line_number.name());
@@ -199,6 +280,7 @@ impl<'a> Printer<'a> {
   }
 
   pub fn resolved_column_number(&mut self, column_number: ColumnNumber) -> Option<u32> {
+    self.add_reevaluation_dependency(ReevaluationDependency::Info(column_number.into()));
     let resolved_number = self.resolved_column_numbers.get(column_number.unique_id());
     if resolved_number.is_none() && !self.look_ahead_column_number_save_points.contains_key(&column_number.unique_id()) {
       let save_point = self.get_save_point_for_restoring_condition(column_number.name());
@@ -209,6 +291,7 @@ impl<'a> Printer<'a> {
   }
 
   pub fn resolved_is_start_of_line(&mut self, is_start_of_line: IsStartOfLine) -> Option<bool> {
+    self.add_reevaluation_dependency(ReevaluationDependency::Info(is_start_of_line.into()));
     let resolved_is_start_of_line = self.resolved_is_start_of_lines.get(is_start_of_line.unique_id());
     if resolved_is_start_of_line.is_none() && !self.look_ahead_is_start_of_line_save_points.contains_key(&is_start_of_line.unique_id()) {
       let save_point = self.get_save_point_for_restoring_condition(is_start_of_line.name());
@@ -219,6 +302,7 @@ impl<'a> Printer<'a> {
   }
 
   pub fn resolved_indent_level(&mut self, indent_level: IndentLevel) -> Option<u8> {
+    self.add_reevaluation_dependency(ReevaluationDependency::Info(indent_level.into()));
     let resolved_indent_level = self.resolved_indent_levels.get(indent_level.unique_id());
     if resolved_indent_level.is_none() && !self.look_ahead_indent_level_save_points.contains_key(&indent_level.unique_id()) {
       let save_point = self.get_save_point_for_restoring_condition(indent_level.name());
@@ -229,6 +313,7 @@ impl<'a> Printer<'a> {
   }
 
   pub fn resolved_line_start_column_number(&mut self, line_start_column_number: LineStartColumnNumber) -> Option<u32> {
+    self.add_reevaluation_dependency(ReevaluationDependency::Info(line_start_column_number.into()));
     let resolved_line_start_column_number = self.resolved_line_start_column_numbers.get(line_start_column_number.unique_id());
     if resolved_line_start_column_number.is_none()
       && !self
@@ -245,6 +330,7 @@ impl<'a> Printer<'a> {
   }
 
   pub fn resolved_line_start_indent_level(&mut self, line_start_indent_level: LineStartIndentLevel) -> Option<u8> {
+    self.add_reevaluation_dependency(ReevaluationDependency::Info(line_start_indent_level.into()));
     let resolved_line_start_indent_level = self.resolved_line_start_indent_levels.get(line_start_indent_level.unique_id());
     if resolved_line_start_indent_level.is_none()
       && !self
@@ -260,6 +346,13 @@ impl<'a> Printer<'a> {
     resolved_line_start_indent_level
   }
 
+  #[inline]
+  fn add_reevaluation_dependency(&mut self, dependency: ReevaluationDependency) {
+    if self.resolving_save_point.is_some() {
+      self.reevaluation_dependencies.push(dependency);
+    }
+  }
+
   pub fn clear_info(&mut self, info: Info) {
     match info {
       Info::LineNumber(info) => self.resolved_line_numbers.remove(info.unique_id()),
@@ -272,6 +365,7 @@ impl<'a> Printer<'a> {
   }
 
   pub fn resolved_condition(&mut self, condition_reference: &ConditionReference) -> Option<bool> {
+    self.add_reevaluation_dependency(ReevaluationDependency::Condition(*condition_reference));
     if !self.resolved_conditions.contains_key(&condition_reference.id) && !self.look_ahead_condition_save_points.contains_key(&condition_reference.id) {
       let save_point = self.get_save_point_for_restoring_condition(condition_reference.name());
       self.look_ahead_condition_save_points.insert(condition_reference.id, save_point);
@@ -295,6 +389,7 @@ impl<'a> Printer<'a> {
       PrintItem::Anchor(anchor) => self.handle_anchor(anchor),
       PrintItem::Info(info) => self.handle_targeted_info(info),
       PrintItem::ConditionReevaluation(reevaluation) => self.handle_condition_reevaluation(reevaluation),
+      PrintItem::AlignmentAnchor(group) => self.handle_alignment_anchor(group),
     }
   }
 
@@ -319,6 +414,7 @@ impl<'a> Printer<'a> {
       look_ahead_indent_level_save_points: self.look_ahead_indent_level_save_points.clone(),
       look_ahead_line_start_column_number_save_points: self.look_ahead_line_start_column_number_save_points.clone(),
       look_ahead_line_start_indent_level_save_points: self.look_ahead_line_start_indent_level_save_points.clone(),
+      alignment_group_save_points: self.alignment_group_save_points.clone(),
       next_node_stack: self.next_node_stack.clone(),
     })
   }
@@ -373,6 +469,7 @@ impl<'a> Printer<'a> {
     self
       .look_ahead_line_start_indent_level_save_points
       .clone_from(&save_point.look_ahead_line_start_indent_level_save_points);
+    self.alignment_group_save_points.clone_from(&save_point.alignment_group_save_points);
     self.next_node_stack = save_point.next_node_stack.clone();
 
     if is_for_new_line {
@@ -433,6 +530,8 @@ impl<'a> Printer<'a> {
       Signal::StartForceNoNewLines => self.force_no_newlines_depth += 1,
       Signal::FinishForceNoNewLines => self.force_no_newlines_depth -= 1,
       Signal::SpaceIfNotTrailing => self.writer.space_if_not_trailing(),
+      Signal::StartAlignment => self.writer.start_alignment(),
+      Signal::FinishAlignment => self.writer.finish_alignment(),
     }
   }
 
@@ -459,6 +558,26 @@ impl<'a> Printer<'a> {
 
   #[inline]
   fn handle_targeted_info(&mut self, info: &Info) {
+    if let Some(range) = &self.range {
+      // store these every time since the infos are printed again when restoring a save point
+      if *info == range.start {
+        self.range_start_item = Some(self.writer.last_item());
+      }
+      if *info == range.end {
+        self.range_end_item = Some(self.writer.last_item());
+      }
+    }
+
+    #[cfg(feature = "tracing")]
+    self.set_trace_resolution(TraceResolution::Info(match info {
+      Info::LineNumber(_) => TraceInfoValue::Number(self.writer.line_number()),
+      Info::ColumnNumber(_) => TraceInfoValue::Number(self.writer.column_number()),
+      Info::IsStartOfLine(_) => TraceInfoValue::Bool(self.writer.is_start_of_line()),
+      Info::IndentLevel(_) => TraceInfoValue::Number(self.writer.indent_level() as u32),
+      Info::LineStartColumnNumber(_) => TraceInfoValue::Number(self.writer.line_start_column_number()),
+      Info::LineStartIndentLevel(_) => TraceInfoValue::Number(self.writer.line_start_indent_level() as u32),
+    }));
+
     match info {
       Info::LineNumber(line_number) => {
         let line_number_id = line_number.unique_id();
@@ -521,9 +640,12 @@ impl<'a> Printer<'a> {
     if let Some((condition, save_point)) = self.stored_condition_save_points.get(&condition_id).cloned() {
       if let Some(past_condition_value) = self.resolved_conditions.get(&condition_id).and_then(|x| x.to_owned()) {
         self.resolving_save_point.replace(save_point);
+        self.reevaluation_dependencies.clear();
         let mut context = ConditionResolverContext::new(self, save_point.writer_state.writer_info(self.writer.indent_width()));
         let latest_condition_value = condition.resolve(&mut context);
         self.resolving_save_point.take();
+        #[cfg(feature = "tracing")]
+        self.set_trace_resolution(TraceResolution::Condition(latest_condition_value));
 
         // Do not re-evaluate the condition if it's flipped back and forth a decent number of times.
         // If it hits the max number of times it can flip then an error will be logged.
@@ -531,6 +653,12 @@ impl<'a> Printer<'a> {
           condition_reevaluation.condition_reevaluation_id,
           latest_condition_value,
           past_condition_value,
+          || ReevaluationDetails {
+            condition_name: condition.name(),
+            condition_id,
+            dependencies: &self.reevaluation_dependencies,
+            writer_info: self.writer.writer_info(),
+          },
         );
         if should_reevaluate {
           if let Some(latest_condition_value) = latest_condition_value {
@@ -545,6 +673,32 @@ impl<'a> Printer<'a> {
     }
   }
 
+  #[inline]
+  fn handle_alignment_anchor(&mut self, group: &AlignmentGroup) {
+    let group_id = group.unique_id();
+    let column = self.writer.column_number();
+    // store where the group starts so it can be re-printed when a later anchor is further along
+    let group_save_point = match self.alignment_group_save_points.get(&group_id) {
+      Some(save_point) => Some(*save_point),
+      None => {
+        let save_point = self.create_save_point(group.name(), self.current_node);
+        self.alignment_group_save_points.insert(group_id, save_point);
+        None
+      }
+    };
+
+    match self.resolved_alignment_columns.get(group_id) {
+      Some(aligned_column) if aligned_column >= column => self.writer.spaces(aligned_column - column),
+      _ => {
+        self.resolved_alignment_columns.insert(group_id, column);
+        // the previous anchors were aligned to a column that's now too small
+        if let Some(save_point) = group_save_point {
+          self.update_state_to_save_point(save_point, false);
+        }
+      }
+    }
+  }
+
   #[inline]
   fn handle_condition(&mut self, condition: &'a Condition, next_node: &Option<PrintItemPath>) {
     let condition_id = condition.unique_id();
@@ -555,6 +709,8 @@ impl<'a> Printer<'a> {
     }
 
     let condition_value = condition.resolve(&mut ConditionResolverContext::new(self, self.get_writer_info()));
+    #[cfg(feature = "tracing")]
+    self.set_trace_resolution(TraceResolution::Condition(condition_value));
     if condition.is_stored {
       self.resolved_conditions.insert(condition_id, condition_value);
     }
@@ -671,9 +827,10 @@ impl<'a> Printer<'a> {
         self.force_no_newlines_depth
       );
     }
-    if self.writer.indentation_level() != 0 {
+    if self.writer.indentation_level() != self.initial_indent_level {
       panic!(
-        "Debug panic! The writer indentation level was not zero after printing. {0}",
+        "Debug panic! The writer indentation level was not {0} after printing. {1}",
+        self.initial_indent_level,
         self.writer.indentation_level()
       );
     }
@@ -683,5 +840,8 @@ impl<'a> Printer<'a> {
         self.writer.ignore_indent_count()
       );
     }
+    if self.writer.is_aligning() {
+      panic!("Debug panic! The writer was still aligning after printing.");
+    }
   }
 }
@@ -1,5 +1,7 @@
 use std::borrow::Cow;
+use std::cell::RefCell;
 use std::cell::UnsafeCell;
+use std::hash::BuildHasherDefault;
 use std::rc::Rc;
 
 use super::collections::GraphNode;
@@ -23,6 +25,7 @@ pub struct Counts {
   line_start_indent_level_id_count: u32,
   condition_id_count: u32,
   condition_reevaluation_id_count: u32,
+  alignment_group_id_count: u32,
   #[cfg(feature = "tracing")]
   print_node_id_count: u32,
   #[cfg(feature = "tracing")]
@@ -37,13 +40,21 @@ pub struct Counts {
 /// make the API safe.
 pub struct BumpAllocator {
   condition_resolvers: Vec<ConditionResolver>,
+  /// Short strings keyed by their text so that strings that are
+  /// repeated many times in a file (ex. `,` or `(`) are only stored once.
+  interned_strings: RefCell<hashbrown::HashMap<UnsafePrintLifetime<str>, UnsafePrintLifetime<StringContainer>, BuildHasherDefault<rustc_hash::FxHasher>>>,
   bump: bumpalo::Bump,
 }
 
+/// The maximum byte length of a string to intern. Longer strings are
+/// unlikely to be repeated and would be slower to hash.
+const MAX_INTERNED_STRING_LEN: usize = 16;
+
 impl BumpAllocator {
   fn new() -> Self {
     Self {
       condition_resolvers: Default::default(),
+      interned_strings: Default::default(),
       bump: bumpalo::Bump::new(),
     }
   }
@@ -67,11 +78,25 @@ impl BumpAllocator {
   }
 
   pub fn alloc_string(&self, item: Cow<'static, str>) -> UnsafePrintLifetime<StringContainer> {
+    if item.len() > MAX_INTERNED_STRING_LEN {
+      return self.alloc_string_container(item);
+    }
+    let mut interned_strings = self.interned_strings.borrow_mut();
+    if let Some(string_container) = interned_strings.get(item.as_ref()) {
+      return string_container;
+    }
+    let string_container = self.alloc_string_container(item);
+    interned_strings.insert(string_container.text, string_container);
+    string_container
+  }
+
+  fn alloc_string_container(&self, item: Cow<'static, str>) -> UnsafePrintLifetime<StringContainer> {
     let string = match item {
       Cow::Borrowed(item) => item,
       Cow::Owned(item) => {
-        let string = self.bump.alloc(bumpalo::collections::String::from_str_in(&item, &self.bump));
-        unsafe { std::mem::transmute::<&bumpalo::collections::String, UnsafePrintLifetime<bumpalo::collections::String>>(string) }
+        // copy only the text into the arena rather than also allocating a string header
+        let string: &str = self.bump.alloc_str(&item);
+        unsafe { std::mem::transmute::<&str, UnsafePrintLifetime<str>>(string) }
       }
     };
     let string = StringContainer::new(string);
@@ -99,6 +124,7 @@ impl BumpAllocator {
   pub fn reset(&mut self) {
     self.bump.reset();
     self.condition_resolvers.clear();
+    self.interned_strings.get_mut().clear();
   }
 }
 
@@ -114,6 +140,27 @@ pub fn with_bump_allocator<TReturn>(action: impl FnOnce(&mut BumpAllocator) -> T
   })
 }
 
+/// Frees the memory held by the thread local allocator.
+///
+/// The allocator keeps its largest chunk of memory and the capacity of its
+/// collections after formatting so that they can be reused for the next file,
+/// which means a long running process (ex. a process plugin) holds on to the
+/// peak memory used for the largest file it formatted on each thread. Call
+/// this on a thread that's idle to give that memory back.
+///
+/// This does nothing and returns `false` when called while formatting.
+pub fn release_thread_memory() -> bool {
+  if super::print::is_formatting() {
+    return false;
+  }
+  with_bump_allocator(|bump| {
+    bump.condition_resolvers = Vec::new();
+    bump.interned_strings = Default::default();
+    bump.bump = bumpalo::Bump::new();
+  });
+  true
+}
+
 pub fn take_counts() -> Counts {
   COUNTS.with(|cell| unsafe { std::mem::take(&mut (*cell.get())) })
 }
@@ -205,6 +252,15 @@ pub fn next_condition_reevaluation_id() -> u32 {
   })
 }
 
+pub fn next_alignment_group_id() -> u32 {
+  COUNTS.with(|cell| unsafe {
+    let counts = &mut *cell.get();
+    let value = counts.alignment_group_id_count;
+    counts.alignment_group_id_count += 1;
+    value
+  })
+}
+
 #[cfg(feature = "tracing")]
 pub fn next_print_node_id() -> u32 {
   COUNTS.with(|cell| unsafe {
@@ -224,3 +280,61 @@ pub fn next_graph_node_id() -> u32 {
     value
   })
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_intern_short_strings() {
+    let mut bump = BumpAllocator::new();
+    let first = bump.alloc_string(Cow::Owned(",".to_string()));
+    let second = bump.alloc_string(Cow::Borrowed(","));
+    assert!(std::ptr::eq(first, second));
+    assert!(!std::ptr::eq(first, bump.alloc_string(Cow::Borrowed("("))));
+
+    let long_text = "a".repeat(MAX_INTERNED_STRING_LEN + 1);
+    let first = bump.alloc_string(Cow::Owned(long_text.clone()));
+    let second = bump.alloc_string(Cow::Owned(long_text));
+    assert!(!std::ptr::eq(first, second));
+    assert_eq!(first.text, second.text);
+
+    bump.reset();
+    assert!(bump.interned_strings.borrow().is_empty());
+  }
+
+  #[test]
+  fn should_release_thread_memory() {
+    let text = super::super::format(
+      || {
+        assert!(!release_thread_memory());
+        let mut items = super::super::PrintItems::new();
+        items.push_str_runtime_width_computed("text");
+        items.push_string(",".to_string());
+        items.push_condition(super::super::conditions::if_true("condition", Rc::new(|_| Some(true)), "!".into()));
+        items
+      },
+      super::super::PrintOptions {
+        indent_width: 2,
+        max_width: 40,
+        use_tabs: false,
+        smart_tabs: false,
+        new_line_text: "\n",
+      },
+    );
+    assert_eq!(text, "text,!");
+    // formatting resets the allocator, but keeps its memory for the next format
+    with_bump_allocator(|bump| {
+      assert!(bump.bump.allocated_bytes() > 0);
+      assert!(bump.interned_strings.borrow().capacity() > 0);
+      assert!(bump.condition_resolvers.capacity() > 0);
+    });
+
+    assert!(release_thread_memory());
+    with_bump_allocator(|bump| {
+      assert_eq!(bump.bump.allocated_bytes(), 0);
+      assert_eq!(bump.interned_strings.borrow().capacity(), 0);
+      assert_eq!(bump.condition_resolvers.capacity(), 0);
+    });
+  }
+}
@@ -1,4 +1,6 @@
 mod token_finder;
+mod token_source;
 
 pub use token_finder::TokenCollection;
 pub use token_finder::TokenFinder;
+pub use token_source::Token;
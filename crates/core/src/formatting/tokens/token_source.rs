@@ -0,0 +1,134 @@
+use super::TokenCollection;
+use super::TokenFinder;
+
+/// A token of a parser, which can be implemented for the tokens of any
+/// parser in order to use a slice of them with the `TokenFinder`.
+pub trait Token {
+  /// The position type the token uses.
+  type TPos: PartialOrd + Copy;
+
+  /// Gets the start position of the token.
+  fn start(&self) -> Self::TPos;
+  /// Gets the end position of the token.
+  fn end(&self) -> Self::TPos;
+  /// Gets if the token is a comment, which is used to find
+  /// the comments that belong to a node.
+  fn is_comment(&self) -> bool;
+}
+
+impl<'a, TToken: Token> TokenCollection<'a> for &'a [TToken] {
+  type TPos = TToken::TPos;
+  type TToken = TToken;
+
+  #[inline]
+  fn get_start_at_index(&self, index: usize) -> Self::TPos {
+    self[index].start()
+  }
+
+  #[inline]
+  fn get_end_at_index(&self, index: usize) -> Self::TPos {
+    self[index].end()
+  }
+
+  #[inline]
+  fn get_token_at_index(&self, index: usize) -> &'a Self::TToken {
+    &self[index]
+  }
+
+  #[inline]
+  fn len(&self) -> usize {
+    <[TToken]>::len(self)
+  }
+
+  #[inline]
+  fn is_empty(&self) -> bool {
+    <[TToken]>::is_empty(self)
+  }
+}
+
+impl<'a, TTokenCollection> TokenFinder<TTokenCollection>
+where
+  TTokenCollection: TokenCollection<'a>,
+  TTokenCollection::TToken: Token<TPos = TTokenCollection::TPos>,
+{
+  /// Gets the comments directly before the provided start position, which are
+  /// the comments after the previous token that isn't a comment.
+  pub fn get_leading_comments(&mut self, start: TTokenCollection::TPos) -> Vec<&'a TTokenCollection::TToken> {
+    let mut comments = Vec::new();
+    let mut next_token = self.get_previous_token(start);
+    while let Some(token) = next_token.filter(|token| token.is_comment()) {
+      comments.push(token);
+      next_token = self.get_previous_token(token.start());
+    }
+    comments.reverse();
+    comments
+  }
+
+  /// Gets the comments directly after the provided end position, which are
+  /// the comments before the next token that isn't a comment.
+  pub fn get_trailing_comments(&mut self, end: TTokenCollection::TPos) -> Vec<&'a TTokenCollection::TToken> {
+    let mut comments = Vec::new();
+    let mut next_token = self.get_next_token(end);
+    while let Some(token) = next_token.filter(|token| token.is_comment()) {
+      comments.push(token);
+      next_token = self.get_next_token(token.end());
+    }
+    comments
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[derive(Debug, PartialEq)]
+  struct TestToken {
+    start: usize,
+    end: usize,
+    is_comment: bool,
+  }
+
+  impl Token for TestToken {
+    type TPos = usize;
+
+    fn start(&self) -> usize {
+      self.start
+    }
+
+    fn end(&self) -> usize {
+      self.end
+    }
+
+    fn is_comment(&self) -> bool {
+      self.is_comment
+    }
+  }
+
+  #[test]
+  fn should_find_comments_around_node() {
+    // a /* 1 */ /* 2 */ b c /* 3 */ d
+    let tokens = [
+      create_token(0, 1, false),
+      create_token(2, 9, true),
+      create_token(10, 17, true),
+      create_token(18, 19, false),
+      create_token(20, 21, false),
+      create_token(22, 29, true),
+      create_token(30, 31, false),
+    ];
+    let mut finder = TokenFinder::new(tokens.as_slice());
+    // node is `b c`
+    assert_eq!(finder.get_leading_comments(18), vec![&tokens[1], &tokens[2]]);
+    assert_eq!(finder.get_trailing_comments(21), vec![&tokens[5]]);
+    // node is `a`
+    assert_eq!(finder.get_leading_comments(0), Vec::<&TestToken>::new());
+    assert_eq!(finder.get_trailing_comments(1), vec![&tokens[1], &tokens[2]]);
+    // node is `d`
+    assert_eq!(finder.get_trailing_comments(31), Vec::<&TestToken>::new());
+    assert_eq!(finder.get_next_token(21), Some(&tokens[5]));
+  }
+
+  fn create_token(start: usize, end: usize, is_comment: bool) -> TestToken {
+    TestToken { start, end, is_comment }
+  }
+}
@@ -61,6 +61,10 @@ pub fn get_trace_print_nodes(start_node: Option<PrintItemPath>) -> Vec<TracePrin
         condition_id: reevaluation.condition_id,
         name: reevaluation.name().to_string(),
       }),
+      PrintItem::AlignmentAnchor(group) => TracePrintItem::AlignmentAnchor(TraceAlignmentAnchor {
+        alignment_group_id: group.unique_id(),
+        name: group.name().to_string(),
+      }),
     };
 
     // create and store the trace print node
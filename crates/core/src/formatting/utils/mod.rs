@@ -1 +1,2 @@
+pub mod str_width;
 pub mod string_utils;
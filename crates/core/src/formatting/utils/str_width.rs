@@ -0,0 +1,86 @@
+#[cfg(feature = "unicode_graphemes")]
+use unicode_segmentation::UnicodeSegmentation;
+#[cfg(feature = "unicode_graphemes")]
+use unicode_width::UnicodeWidthChar;
+
+/// Gets the number of columns the text takes up when displayed.
+///
+/// By default this is the sum of the East Asian width of each character. When
+/// the `unicode_graphemes` feature is enabled, the text is measured per grapheme
+/// cluster instead so that emoji sequences (ex. flags or emoji joined by a zero
+/// width joiner) and characters followed by an emoji presentation selector are
+/// measured as they're displayed.
+pub fn get_str_width(text: &str) -> usize {
+  if text.is_ascii() {
+    return text.len();
+  }
+
+  #[cfg(feature = "unicode_graphemes")]
+  {
+    text.graphemes(true).map(get_grapheme_width).sum()
+  }
+  #[cfg(not(feature = "unicode_graphemes"))]
+  {
+    unicode_width::UnicodeWidthStr::width(text)
+  }
+}
+
+#[cfg(feature = "unicode_graphemes")]
+fn get_grapheme_width(grapheme: &str) -> usize {
+  const EMOJI_PRESENTATION_SELECTOR: char = '\u{FE0F}';
+
+  let mut chars = grapheme.chars();
+  let first_char = match chars.next() {
+    Some(c) => c,
+    None => return 0,
+  };
+  if chars.next().is_none() {
+    return first_char.width().unwrap_or(0);
+  }
+
+  if is_regional_indicator(first_char) || grapheme.contains(EMOJI_PRESENTATION_SELECTOR) {
+    return 2;
+  }
+  // for zero width joiner sequences and combining characters, the
+  // grapheme is displayed as wide as its widest character
+  grapheme.chars().map(|c| c.width().unwrap_or(0)).max().unwrap_or(0)
+}
+
+#[cfg(feature = "unicode_graphemes")]
+fn is_regional_indicator(c: char) -> bool {
+  ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_get_width_of_ascii() {
+    assert_eq!(get_str_width(""), 0);
+    assert_eq!(get_str_width("testing"), 7);
+  }
+
+  #[test]
+  fn should_get_width_of_east_asian_wide_chars() {
+    assert_eq!(get_str_width("テスト"), 6);
+    assert_eq!(get_str_width("a中b"), 4);
+  }
+
+  #[test]
+  fn should_get_width_of_combining_chars() {
+    assert_eq!(get_str_width("e\u{0301}"), 1);
+  }
+
+  #[cfg(feature = "unicode_graphemes")]
+  #[test]
+  fn should_get_width_of_grapheme_clusters() {
+    // family emoji joined by zero width joiners
+    assert_eq!(get_str_width("👨\u{200D}👩\u{200D}👧"), 2);
+    // flag
+    assert_eq!(get_str_width("🇨🇦"), 2);
+    // heart with emoji presentation selector
+    assert_eq!(get_str_width("❤\u{FE0F}"), 2);
+    assert_eq!(get_str_width("a👍🏽b"), 4);
+  }
+}
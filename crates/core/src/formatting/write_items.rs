@@ -5,14 +5,19 @@ use super::StringContainer;
 pub enum WriteItem<'a> {
   String(&'a StringContainer),
   Indent(u8),
+  /// Columns to align to after the indentation.
+  Alignment(u32),
   NewLine,
   Tab,
   Space,
 }
 
 pub enum Indentation {
-  /// Indent with tabs.
-  Tabs,
+  /// Indent and align with tabs, aligning with spaces only for the remainder that
+  /// doesn't fill a tab. Specifies the number of columns that make up a tab.
+  Tabs(usize),
+  /// Indent with tabs, but align with spaces.
+  SmartTabs,
   /// Indent with spaces. Specifies the number of spaces that make up each indentation level.
   Spaces(usize),
 }
@@ -25,32 +30,64 @@ pub struct WriteItemsPrinter {
 }
 
 impl WriteItemsPrinter {
-  pub fn new(indent_width: u8, use_tabs: bool, newline: &'static str) -> Self {
+  pub fn new(indent_width: u8, use_tabs: bool, smart_tabs: bool, newline: &'static str) -> Self {
     Self {
-      indent: match use_tabs {
-        true => Indentation::Tabs,
-        false => Indentation::Spaces(indent_width as usize),
+      indent: match (use_tabs, smart_tabs) {
+        (true, true) => Indentation::SmartTabs,
+        (true, false) => Indentation::Tabs(indent_width as usize),
+        (false, _) => Indentation::Spaces(indent_width as usize),
       },
       newline,
     }
   }
 
   pub fn print<'a>(&self, items: impl Iterator<Item = WriteItem<'a>>) -> String {
-    items.fold(String::new(), |acc, item| match item {
-      WriteItem::Indent(n) => match self.indent {
-        Indentation::Tabs => acc + &str::repeat("\t", n as usize),
-        Indentation::Spaces(width) => acc + &str::repeat(" ", width * n as usize),
-      },
-      WriteItem::NewLine => acc + self.newline,
-      WriteItem::Tab => acc + "\t",
-      WriteItem::Space => acc + " ",
-      WriteItem::String(StringContainer { text, .. }) => acc + text,
-    })
+    let mut text = String::new();
+    // writing to a string never fails
+    self.write_to(items, &mut text).unwrap();
+    text
+  }
+
+  /// Writes the items to the writer one at a time.
+  pub fn write_to<'a>(&self, items: impl Iterator<Item = WriteItem<'a>>, writer: &mut impl std::fmt::Write) -> std::fmt::Result {
+    for item in items {
+      match item {
+        WriteItem::Indent(n) => match self.indent {
+          Indentation::Tabs(_) | Indentation::SmartTabs => {
+            for _ in 0..n {
+              writer.write_char('\t')?;
+            }
+          }
+          Indentation::Spaces(width) => {
+            for _ in 0..(width * n as usize) {
+              writer.write_char(' ')?;
+            }
+          }
+        },
+        WriteItem::Alignment(columns) => {
+          let (tabs, spaces) = match self.indent {
+            Indentation::Tabs(width) if width > 0 => (columns as usize / width, columns as usize % width),
+            _ => (0, columns as usize),
+          };
+          for _ in 0..tabs {
+            writer.write_char('\t')?;
+          }
+          for _ in 0..spaces {
+            writer.write_char(' ')?;
+          }
+        }
+        WriteItem::NewLine => writer.write_str(self.newline)?,
+        WriteItem::Tab => writer.write_char('\t')?,
+        WriteItem::Space => writer.write_char(' ')?,
+        WriteItem::String(StringContainer { text, .. }) => writer.write_str(text)?,
+      }
+    }
+    Ok(())
   }
 }
 
 impl From<&PrintOptions> for WriteItemsPrinter {
   fn from(value: &PrintOptions) -> Self {
-    Self::new(value.indent_width, value.use_tabs, value.new_line_text)
+    Self::new(value.indent_width, value.use_tabs, value.smart_tabs, value.new_line_text)
   }
 }
@@ -14,9 +14,17 @@ pub struct WriterState<'a> {
   indent_queue_count: u8,
   last_was_not_trailing_space: bool,
   ignore_indent_count: u8,
+  alignment: Option<&'a Alignment<'a>>,
   items: Option<&'a GraphNode<'a, WriteItem<'a>>>,
 }
 
+/// Columns beyond the indentation that new lines are aligned to.
+#[derive(Clone, Copy)]
+pub struct Alignment<'a> {
+  columns: u32,
+  previous: Option<&'a Alignment<'a>>,
+}
+
 impl WriterState<'_> {
   pub fn writer_info(&self, indent_width: u8) -> WriterInfo {
     WriterInfo {
@@ -32,11 +40,16 @@ impl WriterState<'_> {
   #[inline]
   pub fn column_number(&self, indent_width: u8) -> u32 {
     if self.current_line_column == 0 {
-      (indent_width as u32) * (self.indent_level as u32)
+      (indent_width as u32) * (self.indent_level as u32) + self.alignment_columns()
     } else {
       self.current_line_column
     }
   }
+
+  #[inline]
+  fn alignment_columns(&self) -> u32 {
+    self.alignment.map(|alignment| alignment.columns).unwrap_or(0)
+  }
 }
 
 pub struct WriterOptions {
@@ -67,6 +80,7 @@ impl<'a> Writer<'a> {
         indent_queue_count: 0,
         last_was_not_trailing_space: false,
         ignore_indent_count: 0,
+        alignment: None,
         items: None,
       },
       #[cfg(feature = "tracing")]
@@ -120,6 +134,23 @@ impl<'a> Writer<'a> {
     self.state.ignore_indent_count -= 1;
   }
 
+  /// Aligns the following lines to the current column.
+  pub fn start_alignment(&mut self) {
+    let indent_columns = self.state.indent_level as u32 * self.indent_width as u32;
+    let alignment = self.bump.inner().alloc(Alignment {
+      columns: self.column_number().saturating_sub(indent_columns),
+      previous: self.state.alignment,
+    });
+    self.state.alignment = Some(alignment);
+  }
+
+  pub fn finish_alignment(&mut self) {
+    match self.state.alignment {
+      Some(alignment) => self.state.alignment = alignment.previous,
+      None => panic!("For some reason finish_alignment was called without a corresponding start_alignment."),
+    }
+  }
+
   pub fn mark_expect_new_line(&mut self) {
     self.state.expect_newline_next = true;
   }
@@ -151,6 +182,11 @@ impl<'a> Writer<'a> {
     self.state.ignore_indent_count
   }
 
+  #[cfg(debug_assertions)]
+  pub fn is_aligning(&self) -> bool {
+    self.state.alignment.is_some()
+  }
+
   #[inline]
   pub fn column_number(&self) -> u32 {
     self.state.column_number(self.indent_width)
@@ -206,6 +242,12 @@ impl<'a> Writer<'a> {
     self.push_item(WriteItem::Tab);
   }
 
+  pub fn spaces(&mut self, count: u32) {
+    for _ in 0..count {
+      self.space();
+    }
+  }
+
   fn space(&mut self) {
     self.handle_first_column();
     self.state.current_line_column += 1;
@@ -225,8 +267,13 @@ impl<'a> Writer<'a> {
 
     self.state.last_was_not_trailing_space = false;
 
+    if self.state.current_line_column != 0 || self.state.ignore_indent_count > 0 {
+      return;
+    }
+
     // add the indentation if necessary
-    if self.state.current_line_column == 0 && self.state.indent_level > 0 && self.state.ignore_indent_count == 0 {
+    let alignment_columns = self.state.alignment_columns();
+    if self.state.indent_level > 0 {
       // update the indent level again since on the first column
       self.state.last_line_indent_level = self.state.indent_level;
 
@@ -234,10 +281,14 @@ impl<'a> Writer<'a> {
       self.state.current_line_column = self.state.indent_level as u32 * self.indent_width as u32;
 
       // finally, push the indent level
-      if self.state.indent_level > 0 {
-        // this might update the indent_level based on the queued indentation, so do this last
-        self.push_item(WriteItem::Indent(self.state.indent_level));
-      }
+      // this might update the indent_level based on the queued indentation, so do this last
+      self.push_item(WriteItem::Indent(self.state.indent_level));
+    }
+
+    // then align past the indentation
+    if alignment_columns > 0 {
+      self.state.current_line_column += alignment_columns;
+      self.push_item(WriteItem::Alignment(alignment_columns));
     }
   }
 
@@ -264,6 +315,11 @@ impl<'a> Writer<'a> {
     }
   }
 
+  #[inline]
+  pub fn last_item(&self) -> Option<&'a GraphNode<'a, WriteItem<'a>>> {
+    self.state.items
+  }
+
   pub fn items(self) -> Option<impl Iterator<Item = WriteItem<'a>>> {
     self.state.items.map(|items| items.iter().rev())
   }
@@ -283,7 +339,7 @@ impl<'a> Writer<'a> {
   pub fn to_string_for_debugging(&self) -> String {
     use super::WriteItemsPrinter;
     let write_items = self.items_cloned();
-    WriteItemsPrinter::new(self.indent_width, false, "\n").print(write_items.into_iter())
+    WriteItemsPrinter::new(self.indent_width, false, false, "\n").print(write_items.into_iter())
   }
 
   #[cfg(debug_assertions)]
@@ -238,6 +238,9 @@ pub trait AsyncPluginHandler: 'static {
     request: FormatRequest<Self::Configuration>,
     format_with_host: impl FnMut(HostFormatRequest) -> LocalBoxFuture<'static, FormatResult> + 'static,
   ) -> FormatResult;
+  /// Called when the CLI asks the plugin to shut down once the in-flight
+  /// requests have completed, allowing the plugin to flush any state.
+  async fn shutdown(&self) {}
 }
 
 /// Trait for implementing a Wasm plugin.
@@ -23,6 +23,11 @@ pub struct PluginInfo {
   /// Generally in the format: https://plugins.dprint.dev/<org-or-user>/<repo>/latest.json
   /// For example: https://plugins.dprint.dev/dprint/dprint-plugin-typescript/latest.json
   pub update_url: Option<String>,
+  /// The names of the global configuration options the plugin uses (ex. `["lineWidth", "useTabs"]`).
+  ///
+  /// When `None`, the plugin is assumed to use all of them.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub global_options: Option<Vec<String>>,
 }
 
 /// The plugin file matching information based on the configuration.
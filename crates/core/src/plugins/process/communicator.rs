@@ -6,8 +6,6 @@ use serde::de::DeserializeOwned;
 use std::cell::RefCell;
 use std::io::BufRead;
 use std::io::ErrorKind;
-use std::io::Read;
-use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Child;
@@ -17,9 +15,16 @@ use std::process::Stdio;
 use std::rc::Rc;
 use std::sync::Arc;
 use std::time::Duration;
+use std::time::Instant;
 use tokio::sync::oneshot;
+use tokio::sync::OwnedSemaphorePermit;
+use tokio::sync::Semaphore;
 use tokio_util::sync::CancellationToken;
 
+use super::handshake::establish_plugin_protocol;
+use super::handshake::protocol_extensions;
+use super::handshake::ProtocolInfo;
+use super::handshake::PROTOCOL_EXTENSIONS_ARG;
 use super::messages::CheckConfigUpdatesMessageBody;
 use super::messages::CheckConfigUpdatesResponseBody;
 use super::messages::FormatMessageBody;
@@ -52,6 +57,9 @@ use crate::plugins::PluginInfo;
 
 type DprintCancellationToken = Arc<dyn super::super::CancellationToken>;
 
+/// Time given to the plugin to finish its in-flight requests when shutting down.
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
 pub type HostFormatCallback = Rc<dyn Fn(HostFormatRequest) -> LocalBoxFuture<'static, FormatResult>>;
 
 pub struct ProcessPluginCommunicatorFormatRequest {
@@ -62,8 +70,23 @@ pub struct ProcessPluginCommunicatorFormatRequest {
   pub override_config: ConfigKeyMap,
   pub on_host_format: HostFormatCallback,
   pub token: DprintCancellationToken,
+  /// When provided, the format is cancelled and a `RequestTimeoutError`
+  /// is returned if the plugin hasn't responded by this time.
+  pub deadline: Option<Instant>,
+}
+
+/// Error returned when a process plugin doesn't respond to a request before its deadline.
+#[derive(Debug)]
+pub struct RequestTimeoutError;
+
+impl std::fmt::Display for RequestTimeoutError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "Timed out waiting for the plugin to respond.")
+  }
 }
 
+impl std::error::Error for RequestTimeoutError {}
+
 enum MessageResponseChannel {
   Acknowledgement(oneshot::Sender<Result<()>>),
   Data(oneshot::Sender<Result<Vec<u8>>>),
@@ -77,12 +100,16 @@ struct Context {
   messages: RcIdStore<MessageResponseChannel>,
   format_request_tokens: RcIdStore<Arc<CancellationToken>>,
   host_format_callbacks: RcIdStore<HostFormatCallback>,
+  /// Permits of format requests counting towards the in-flight limit.
+  format_permits: RcIdStore<OwnedSemaphorePermit>,
 }
 
 /// Communicates with a process plugin.
 pub struct ProcessPluginCommunicator {
   child: RefCell<Option<Child>>,
   context: Rc<Context>,
+  protocol_info: ProtocolInfo,
+  format_limiter: Option<Arc<Semaphore>>,
 }
 
 impl Drop for ProcessPluginCommunicator {
@@ -102,7 +129,7 @@ impl ProcessPluginCommunicator {
   }
 
   async fn new_internal(executable_file_path: &Path, is_init: bool, on_std_err: impl Fn(String) + Clone + Send + Sync + 'static) -> Result<Self> {
-    let mut args = vec!["--parent-pid".to_string(), std::process::id().to_string()];
+    let mut args = vec!["--parent-pid".to_string(), std::process::id().to_string(), PROTOCOL_EXTENSIONS_ARG.to_string()];
     if is_init {
       args.push("--init".to_string());
     }
@@ -130,12 +157,13 @@ impl ProcessPluginCommunicator {
     let mut stdout_reader = MessageReader::new(child.stdout.take().unwrap());
     let mut stdin_writer = MessageWriter::new(child.stdin.take().unwrap());
 
-    let (mut stdout_reader, stdin_writer, schema_version) = crate::async_runtime::spawn_blocking(move || {
-      let schema_version = get_plugin_schema_version(&mut stdout_reader, &mut stdin_writer)
+    let (mut stdout_reader, stdin_writer, protocol_info) = crate::async_runtime::spawn_blocking(move || {
+      let protocol_info = establish_plugin_protocol(&mut stdout_reader, &mut stdin_writer)
         .context("Failed plugin schema verification. This may indicate you are using an old version of the dprint CLI or plugin and should upgrade")?;
-      Ok::<_, anyhow::Error>((stdout_reader, stdin_writer, schema_version))
+      Ok::<_, anyhow::Error>((stdout_reader, stdin_writer, protocol_info))
     })
     .await??;
+    let schema_version = protocol_info.schema_version;
 
     if schema_version != PLUGIN_SCHEMA_VERSION {
       // kill the child to prevent it from ouputting to stderr
@@ -163,6 +191,7 @@ impl ProcessPluginCommunicator {
       messages: Default::default(),
       format_request_tokens: Default::default(),
       host_format_callbacks: Default::default(),
+      format_permits: Default::default(),
     });
 
     // read from stdout
@@ -210,22 +239,53 @@ impl ProcessPluginCommunicator {
     Ok(Self {
       child: RefCell::new(Some(child)),
       context,
+      protocol_info,
+      format_limiter: None,
     })
   }
 
+  /// Limits the number of format requests sent to the plugin at the same time.
+  /// Requests beyond this limit are queued until an earlier request completes.
+  ///
+  /// A request that is waiting on the host to format text for it does not count
+  /// towards the limit so that host formatting with the same plugin can't deadlock.
+  pub fn with_max_in_flight_formats(mut self, max: usize) -> Self {
+    self.format_limiter = Some(Arc::new(Semaphore::new(std::cmp::max(1, max))));
+    self
+  }
+
+  /// Gets the schema version and protocol extensions negotiated with the plugin.
+  pub fn protocol_info(&self) -> &ProtocolInfo {
+    &self.protocol_info
+  }
+
   /// Perform a graceful shutdown.
   pub async fn shutdown(&self) {
+    self.shutdown_with_timeout(DEFAULT_SHUTDOWN_TIMEOUT).await
+  }
+
+  /// Perform a graceful shutdown, giving the plugin up to the provided
+  /// amount of time to finish its in-flight requests when it supports that.
+  pub async fn shutdown_with_timeout(&self, timeout: Duration) {
     if self.context.shutdown_flag.raise() {
+      let (message, wait_duration) = if self.protocol_info.has_extension(protocol_extensions::GRACEFUL_SHUTDOWN) {
+        let timeout_ms = std::cmp::min(timeout.as_millis(), u32::MAX as u128) as u32;
+        (MessageBody::Shutdown(timeout_ms), timeout + Duration::from_millis(250))
+      } else {
+        (MessageBody::Close, Duration::from_millis(250))
+      };
       // attempt to exit nicely
       tokio::select! {
         // we wait for acknowledgement in order to give the process
         // plugin a chance to clean up (ex. in case it has spawned
         // any processes it needs to kill or something like that)
-        _ = self.send_with_acknowledgement(MessageBody::Close) => {}
-        _ = tokio::time::sleep(Duration::from_millis(250)) => {
+        _ = self.send_with_acknowledgement(message) => {}
+        _ = tokio::time::sleep(wait_duration) => {
           self.kill();
         }
       }
+      // any remaining requests were cancelled by the plugin and won't receive a response
+      self.context.messages.take_all();
     } else {
       self.kill();
     }
@@ -290,6 +350,17 @@ impl ProcessPluginCommunicator {
     let (tx, rx) = oneshot::channel::<Result<Option<Vec<u8>>>>();
 
     let message_id = self.context.id_generator.next();
+    let _permit_guard = match &self.format_limiter {
+      Some(limiter) => {
+        let permit = tokio::select! {
+          _ = request.token.wait_cancellation() => return Ok(None),
+          _ = wait_deadline(request.deadline) => return Err(RequestTimeoutError.into()),
+          permit = limiter.clone().acquire_owned() => permit?,
+        };
+        Some(self.context.format_permits.store_with_guard(message_id, permit))
+      }
+      None => None,
+    };
     let store_guard = self.context.host_format_callbacks.store_with_guard(message_id, request.on_host_format);
     let maybe_result = self
       .send_message_with_id(
@@ -304,6 +375,7 @@ impl ProcessPluginCommunicator {
         MessageResponseChannel::Format(tx),
         rx,
         request.token.clone(),
+        request.deadline,
       )
       .await;
 
@@ -314,6 +386,8 @@ impl ProcessPluginCommunicator {
     } else {
       match maybe_result {
         Ok(result) => result,
+        // the plugin may still recover, so don't surface this as critical
+        Err(err) if err.is::<RequestTimeoutError>() => Err(err),
         Err(err) => Err(CriticalFormatError(err).into()),
       }
     }
@@ -331,7 +405,7 @@ impl ProcessPluginCommunicator {
   async fn send_with_acknowledgement(&self, body: MessageBody) -> Result<()> {
     let (tx, rx) = oneshot::channel::<Result<()>>();
     self
-      .send_message(body, MessageResponseChannel::Acknowledgement(tx), rx, Arc::new(NullCancellationToken))
+      .send_message(body, MessageResponseChannel::Acknowledgement(tx), rx, Arc::new(NullCancellationToken), None)
       .await?
   }
 
@@ -348,7 +422,7 @@ impl ProcessPluginCommunicator {
   async fn send_receiving_bytes(&self, body: MessageBody) -> Result<Result<Vec<u8>>> {
     let (tx, rx) = oneshot::channel::<Result<Vec<u8>>>();
     self
-      .send_message(body, MessageResponseChannel::Data(tx), rx, Arc::new(NullCancellationToken))
+      .send_message(body, MessageResponseChannel::Data(tx), rx, Arc::new(NullCancellationToken), None)
       .await
   }
 
@@ -358,9 +432,10 @@ impl ProcessPluginCommunicator {
     response_channel: MessageResponseChannel,
     receiver: oneshot::Receiver<Result<T>>,
     token: Arc<dyn super::super::CancellationToken>,
+    deadline: Option<Instant>,
   ) -> Result<Result<T>> {
     let message_id = self.context.id_generator.next();
-    self.send_message_with_id(message_id, body, response_channel, receiver, token).await
+    self.send_message_with_id(message_id, body, response_channel, receiver, token, deadline).await
   }
 
   async fn send_message_with_id<T: Default>(
@@ -370,6 +445,7 @@ impl ProcessPluginCommunicator {
     response_channel: MessageResponseChannel,
     receiver: oneshot::Receiver<Result<T>>,
     token: Arc<dyn super::super::CancellationToken>,
+    deadline: Option<Instant>,
   ) -> Result<Result<T>> {
     let mut drop_guard = DropGuardAction::new(|| {
       // clear up memory
@@ -388,6 +464,10 @@ impl ProcessPluginCommunicator {
         drop(drop_guard); // explicit
         Ok(Ok(Default::default()))
       }
+      _ = wait_deadline(deadline) => {
+        drop(drop_guard); // explicit, cancels the request in the plugin
+        Err(RequestTimeoutError.into())
+      }
       response = receiver => {
         drop_guard.forget(); // we completed, so don't run the drop guard
         match response {
@@ -401,15 +481,11 @@ impl ProcessPluginCommunicator {
   }
 }
 
-fn get_plugin_schema_version<TRead: Read + Unpin, TWrite: Write + Unpin>(reader: &mut MessageReader<TRead>, writer: &mut MessageWriter<TWrite>) -> Result<u32> {
-  // since this is the setup, use a lot of contexts to find exactly where it failed
-  writer.send_u32(0).context("Failed asking for schema version.")?; // ask for schema version
-  writer.flush().context("Failed flushing schema version request.")?;
-  let acknowledgement_response = reader.read_u32().context("Could not read success response.")?;
-  if acknowledgement_response != 0 {
-    bail!("Plugin response was unexpected ({acknowledgement_response}).");
+async fn wait_deadline(deadline: Option<Instant>) {
+  match deadline {
+    Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+    None => std::future::pending().await,
   }
-  reader.read_u32().context("Could not read schema version.")
 }
 
 fn std_err_redirect(shutdown_flag: Arc<AtomicFlag>, stderr: ChildStderr, on_std_err: impl Fn(String) + Send + Sync + 'static) {
@@ -521,6 +597,7 @@ fn handle_stdout_message(message: ProcessPluginMessage, context: &Rc<Context>) -
     }
     MessageBody::Format(_)
     | MessageBody::Close
+    | MessageBody::Shutdown(_)
     | MessageBody::GetPluginInfo
     | MessageBody::GetLicenseText
     | MessageBody::RegisterConfig(_)
@@ -552,6 +629,9 @@ async fn host_format(context: Rc<Context>, message_id: u32, body: HostFormatMess
     return FormatResult::Err(anyhow!("Could not find host format callback for message id: {}", body.original_message_id));
   };
 
+  // the original request is now waiting on the host, so let another request through
+  context.format_permits.take(body.original_message_id);
+
   let token = Arc::new(CancellationToken::new());
   let store_guard = context.format_request_tokens.store_with_guard(message_id, token.clone());
   let result = callback(HostFormatRequest {
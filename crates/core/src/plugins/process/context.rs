@@ -1,5 +1,6 @@
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
 use serde::Serialize;
 use tokio_util::sync::CancellationToken;
@@ -17,6 +18,11 @@ use super::messages::ProcessPluginMessage;
 
 pub type FormatHostSender = tokio::sync::oneshot::Sender<FormatResult>;
 
+/// How long to wait for the host to respond to a host format request before giving up on it.
+const FORMAT_HOST_SENDER_TTL: Duration = Duration::from_secs(10 * 60);
+/// How often to clean up host format requests the host never responded to.
+const FORMAT_HOST_SENDER_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
 pub struct StoredConfig<TConfiguration: Serialize + Clone> {
   pub config: Arc<TConfiguration>,
   pub diagnostics: Rc<Vec<ConfigurationDiagnostic>>,
@@ -39,8 +45,13 @@ impl<TConfiguration: Serialize + Clone> ProcessContext<TConfiguration> {
       id_generator: Default::default(),
       configs: Default::default(),
       cancellation_tokens: Default::default(),
-      format_host_senders: Default::default(),
+      format_host_senders: RcIdStore::with_ttl(FORMAT_HOST_SENDER_TTL),
       stdout_writer: Rc::new(stdout_writer),
     }
   }
+
+  /// Starts the task that cleans up expired entries. Must be run within the async runtime.
+  pub fn start_sweep_tasks(&self) {
+    self.format_host_senders.start_sweep_task(FORMAT_HOST_SENDER_SWEEP_INTERVAL);
+  }
 }
@@ -0,0 +1,182 @@
+use std::io::Read;
+use std::io::Write;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::PLUGIN_SCHEMA_VERSION;
+use crate::communication::MessageReader;
+use crate::communication::MessageWriter;
+use crate::communication::DEFAULT_COMPRESSION_THRESHOLD;
+
+/// CLI flag provided to the process plugin when the host supports
+/// exchanging protocol extensions while establishing the schema version.
+pub const PROTOCOL_EXTENSIONS_ARG: &str = "--protocol-extensions";
+
+/// Optional additions to the process plugin protocol that are
+/// only used when both the host and plugin support them.
+pub mod protocol_extensions {
+  /// Sized bytes at or above a threshold may be lz4 compressed.
+  pub const LZ4_COMPRESSION: &str = "lz4Compression";
+  /// The host may send a shutdown message that allows the plugin to finish in-flight requests.
+  pub const GRACEFUL_SHUTDOWN: &str = "gracefulShutdown";
+}
+
+/// The protocol extensions supported by this version of dprint-core.
+pub const SUPPORTED_PROTOCOL_EXTENSIONS: &[&str] = &[protocol_extensions::LZ4_COMPRESSION, protocol_extensions::GRACEFUL_SHUTDOWN];
+
+/// Result of establishing the schema version with the other side.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ProtocolInfo {
+  pub schema_version: u32,
+  /// Protocol extensions both sides agreed to use.
+  pub extensions: Vec<String>,
+}
+
+impl ProtocolInfo {
+  pub fn has_extension(&self, name: &str) -> bool {
+    self.extensions.iter().any(|e| e == name)
+  }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HelloMessage {
+  extensions: Vec<String>,
+}
+
+/// Asks the plugin for its schema version and negotiates the protocol extensions to use.
+pub(super) fn establish_plugin_protocol<TRead: Read + Unpin, TWrite: Write + Unpin>(
+  reader: &mut MessageReader<TRead>,
+  writer: &mut MessageWriter<TWrite>,
+) -> Result<ProtocolInfo> {
+  // since this is the setup, use a lot of contexts to find exactly where it failed
+  writer.send_u32(0).context("Failed asking for schema version.")?; // ask for schema version
+  writer.flush().context("Failed flushing schema version request.")?;
+  let acknowledgement_response = reader.read_u32().context("Could not read success response.")?;
+  // plugins that don't understand the protocol extensions flag respond with `0`
+  let has_hello = match acknowledgement_response {
+    0 => false,
+    1 => true,
+    _ => bail!("Plugin response was unexpected ({acknowledgement_response})."),
+  };
+  let schema_version = reader.read_u32().context("Could not read schema version.")?;
+  let mut extensions = Vec::new();
+  if has_hello {
+    let plugin_hello = read_hello(reader).context("Could not read plugin protocol extensions.")?;
+    extensions = plugin_hello
+      .extensions
+      .into_iter()
+      .filter(|extension| SUPPORTED_PROTOCOL_EXTENSIONS.contains(&extension.as_str()))
+      .collect();
+    send_hello(writer, &extensions).context("Failed sending protocol extensions.")?;
+  }
+  let protocol_info = ProtocolInfo { schema_version, extensions };
+  apply_protocol_extensions(&protocol_info, reader, writer);
+  Ok(protocol_info)
+}
+
+/// Responds to the host's schema version request.
+///
+/// When the host supports protocol extensions, the plugin sends the ones it
+/// supports and the host responds with the ones that will be used.
+pub(super) fn establish_host_protocol<TRead: Read + Unpin, TWrite: Write + Unpin>(
+  reader: &mut MessageReader<TRead>,
+  writer: &mut MessageWriter<TWrite>,
+  host_supports_extensions: bool,
+) -> Result<ProtocolInfo> {
+  // 1. An initial `0` (4 bytes) is sent asking for the schema version.
+  if reader.read_u32()? != 0 {
+    bail!("Expected a schema version request of `0`.");
+  }
+
+  // 2. The client responds with `0` (4 bytes) for success or `1` when
+  //    protocol extensions will be exchanged (see step 4)
+  writer.send_u32(if host_supports_extensions { 1 } else { 0 })?;
+  // 3. Then 4 bytes for the schema version
+  writer.send_u32(PLUGIN_SCHEMA_VERSION)?;
+  let mut extensions = Vec::new();
+  if host_supports_extensions {
+    // 4. Then the supported extensions, after which the host responds with the ones to use
+    let supported_extensions = SUPPORTED_PROTOCOL_EXTENSIONS.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    send_hello(writer, &supported_extensions)?;
+    extensions = read_hello(reader)?
+      .extensions
+      .into_iter()
+      .filter(|extension| SUPPORTED_PROTOCOL_EXTENSIONS.contains(&extension.as_str()))
+      .collect();
+  } else {
+    writer.flush()?;
+  }
+
+  let protocol_info = ProtocolInfo {
+    schema_version: PLUGIN_SCHEMA_VERSION,
+    extensions,
+  };
+  apply_protocol_extensions(&protocol_info, reader, writer);
+  Ok(protocol_info)
+}
+
+fn read_hello<TRead: Read + Unpin>(reader: &mut MessageReader<TRead>) -> Result<HelloMessage> {
+  let bytes = reader.read_sized_bytes()?;
+  Ok(serde_json::from_slice(&bytes)?)
+}
+
+fn send_hello<TWrite: Write + Unpin>(writer: &mut MessageWriter<TWrite>, extensions: &[String]) -> Result<()> {
+  let bytes = serde_json::to_vec(&HelloMessage {
+    extensions: extensions.to_vec(),
+  })?;
+  writer.send_sized_bytes(&bytes)?;
+  writer.flush()?;
+  Ok(())
+}
+
+fn apply_protocol_extensions<TRead: Read + Unpin, TWrite: Write + Unpin>(
+  protocol_info: &ProtocolInfo,
+  reader: &mut MessageReader<TRead>,
+  writer: &mut MessageWriter<TWrite>,
+) {
+  if protocol_info.has_extension(protocol_extensions::LZ4_COMPRESSION) {
+    reader.enable_compression();
+    writer.enable_compression(DEFAULT_COMPRESSION_THRESHOLD);
+  }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+  use std::os::unix::net::UnixStream;
+
+  use super::*;
+
+  fn run_handshake(host_supports_extensions: bool) -> (ProtocolInfo, ProtocolInfo) {
+    let (host_stream, plugin_stream) = UnixStream::pair().unwrap();
+    let plugin_thread = std::thread::spawn(move || {
+      let mut reader = MessageReader::new(plugin_stream.try_clone().unwrap());
+      let mut writer = MessageWriter::new(plugin_stream);
+      establish_host_protocol(&mut reader, &mut writer, host_supports_extensions).unwrap()
+    });
+    let mut reader = MessageReader::new(host_stream.try_clone().unwrap());
+    let mut writer = MessageWriter::new(host_stream);
+    let host_info = establish_plugin_protocol(&mut reader, &mut writer).unwrap();
+    (host_info, plugin_thread.join().unwrap())
+  }
+
+  #[test]
+  fn should_negotiate_protocol_extensions() {
+    let (host_info, plugin_info) = run_handshake(true);
+    assert_eq!(host_info, plugin_info);
+    assert_eq!(host_info.schema_version, PLUGIN_SCHEMA_VERSION);
+    assert!(host_info.has_extension(protocol_extensions::LZ4_COMPRESSION));
+    assert!(host_info.has_extension(protocol_extensions::GRACEFUL_SHUTDOWN));
+  }
+
+  #[test]
+  fn should_not_use_extensions_when_host_does_not_support_them() {
+    let (host_info, plugin_info) = run_handshake(false);
+    assert_eq!(host_info, plugin_info);
+    assert!(host_info.extensions.is_empty());
+  }
+}
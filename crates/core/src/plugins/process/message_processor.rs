@@ -3,14 +3,16 @@ use anyhow::bail;
 use anyhow::Context;
 use anyhow::Result;
 use serde::Serialize;
-use std::io::Read;
-use std::io::Write;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
 use tokio_util::sync::CancellationToken;
 
 use super::context::ProcessContext;
 use super::context::StoredConfig;
+use super::handshake::establish_host_protocol;
+use super::handshake::PROTOCOL_EXTENSIONS_ARG;
 use super::messages::CheckConfigUpdatesMessageBody;
 use super::messages::CheckConfigUpdatesResponseBody;
 use super::messages::HostFormatMessageBody;
@@ -18,7 +20,6 @@ use super::messages::MessageBody;
 use super::messages::ProcessPluginMessage;
 use super::messages::ResponseBody;
 use super::utils::setup_exit_process_panic_hook;
-use super::PLUGIN_SCHEMA_VERSION;
 
 use crate::async_runtime::FutureExt;
 use crate::async_runtime::LocalBoxFuture;
@@ -42,7 +43,8 @@ pub async fn handle_process_stdio_messages<THandler: AsyncPluginHandler>(handler
     let mut stdin_reader = MessageReader::new(std::io::stdin());
     let mut stdout_writer = MessageWriter::new(std::io::stdout());
 
-    schema_establishment_phase(&mut stdin_reader, &mut stdout_writer).context("Failed estabilishing schema.")?;
+    let host_supports_extensions = std::env::args().any(|arg| arg == PROTOCOL_EXTENSIONS_ARG);
+    establish_host_protocol(&mut stdin_reader, &mut stdout_writer, host_supports_extensions).context("Failed estabilishing schema.")?;
     Ok::<_, anyhow::Error>((stdin_reader, stdout_writer))
   })
   .await??;
@@ -64,12 +66,18 @@ pub async fn handle_process_stdio_messages<THandler: AsyncPluginHandler>(handler
     let handler = Rc::new(handler);
     let stdout_message_writer = SingleThreadMessageWriter::for_stdout(stdout_writer);
     let context: Rc<ProcessContext<THandler::Configuration>> = Rc::new(ProcessContext::new(stdout_message_writer));
+    context.start_sweep_tasks();
+    let (shutdown_tx, mut shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+    let mut shutdown_tx = Some(shutdown_tx);
 
     // read messages over stdin
     loop {
-      let message = match rx.recv().await {
-        Some(message_result) => message_result?,
-        None => return Ok(()), // disconnected
+      let message = tokio::select! {
+        message = rx.recv() => match message {
+          Some(message_result) => message_result?,
+          None => return Ok(()), // disconnected
+        },
+        _ = &mut shutdown_rx => return Ok(()),
       };
 
       match message.body {
@@ -77,6 +85,20 @@ pub async fn handle_process_stdio_messages<THandler: AsyncPluginHandler>(handler
           handle_message(&context, message.id, || Ok(MessageBody::Success(message.id)));
           return Ok(());
         }
+        MessageBody::Shutdown(timeout_ms) => {
+          let Some(shutdown_tx) = shutdown_tx.take() else {
+            continue; // already shutting down
+          };
+          // keep handling messages (ex. host format responses) while the in-flight requests finish
+          let context = context.clone();
+          let handler = handler.clone();
+          crate::async_runtime::spawn(async move {
+            wait_in_flight_formats(&context, Duration::from_millis(timeout_ms as u64)).await;
+            handler.shutdown().await;
+            handle_message(&context, message.id, || Ok(MessageBody::Success(message.id)));
+            let _ = shutdown_tx.send(());
+          });
+        }
         MessageBody::IsAlive => {
           handle_message(&context, message.id, || Ok(MessageBody::Success(message.id)));
         }
@@ -169,6 +191,11 @@ pub async fn handle_process_stdio_messages<THandler: AsyncPluginHandler>(handler
           .await;
         }
         MessageBody::Format(body) => {
+          if shutdown_tx.is_none() {
+            send_error_response(&context, message.id, anyhow!("Plugin is shutting down."));
+            continue;
+          }
+
           // now parse
           let token = Arc::new(CancellationToken::new());
           let request = FormatRequest {
@@ -261,6 +288,18 @@ pub async fn handle_process_stdio_messages<THandler: AsyncPluginHandler>(handler
   .unwrap()
 }
 
+/// Waits for the in-flight formats to complete, cancelling any remaining after the timeout.
+async fn wait_in_flight_formats<TConfiguration: Serialize + Clone + Send + Sync>(context: &ProcessContext<TConfiguration>, timeout: Duration) {
+  let deadline = Instant::now() + timeout;
+  // each format stores a cancellation token until it completes
+  while !context.cancellation_tokens.is_empty() && Instant::now() < deadline {
+    tokio::time::sleep(Duration::from_millis(10)).await;
+  }
+  for token in context.cancellation_tokens.take_all().into_values() {
+    token.cancel();
+  }
+}
+
 fn host_format<TConfiguration: Serialize + Clone + Send + Sync>(
   context: &ProcessContext<TConfiguration>,
   original_message_id: u32,
@@ -287,11 +326,14 @@ fn host_format<TConfiguration: Serialize + Clone + Send + Sync>(
   let token = request.token;
   let stdout_writer = context.stdout_writer.clone();
   let id_generator = context.id_generator.clone();
+  let format_host_senders = context.format_host_senders.clone();
   let original_message_id = id;
 
   async move {
     tokio::select! {
       _ = token.wait_cancellation() => {
+        // the host might never respond, so don't keep the sender around
+        format_host_senders.take(original_message_id);
         // send a cancellation to the host
         stdout_writer.send(ProcessPluginMessage {
           id: id_generator.next(),
@@ -358,19 +400,3 @@ fn send_response_body<TConfiguration: Serialize + Clone + Send + Sync>(context:
     panic!("Receiver dropped. {:#}", err);
   }
 }
-
-/// For backwards compatibility asking for the schema version.
-fn schema_establishment_phase<TRead: Read + Unpin, TWrite: Write + Unpin>(stdin: &mut MessageReader<TRead>, stdout: &mut MessageWriter<TWrite>) -> Result<()> {
-  // 1. An initial `0` (4 bytes) is sent asking for the schema version.
-  if stdin.read_u32()? != 0 {
-    bail!("Expected a schema version request of `0`.");
-  }
-
-  // 2. The client responds with `0` (4 bytes) for success
-  stdout.send_u32(0)?;
-  // 3. Then 4 bytes for the schema version
-  stdout.send_u32(PLUGIN_SCHEMA_VERSION)?;
-  stdout.flush()?;
-
-  Ok(())
-}
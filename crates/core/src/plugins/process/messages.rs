@@ -37,6 +37,7 @@ mod message_ids {
   pub const FORMAT_RESPONSE_ID: MessageId = 14;
   pub const CANCEL_FORMAT_ID: MessageId = 15;
   pub const HOST_FORMAT_ID: MessageId = 16;
+  pub const SHUTDOWN_ID: MessageId = 17;
 }
 
 #[derive(Debug)]
@@ -143,6 +144,7 @@ impl ProcessPluginMessage {
           override_config,
         })
       }
+      message_ids::SHUTDOWN_ID => MessageBody::Shutdown(reader.read_u32()?),
       _ => {
         // don't read success bytes... receiving this means that
         // the plugin should exit the process after returning an
@@ -249,6 +251,10 @@ impl Message for ProcessPluginMessage {
         writer.send_sized_bytes(&body.override_config)?;
         writer.send_sized_bytes(&body.file_text)?;
       }
+      MessageBody::Shutdown(timeout_ms) => {
+        writer.send_u32(message_ids::SHUTDOWN_ID)?;
+        writer.send_u32(*timeout_ms)?;
+      }
       MessageBody::Unknown(_) => unreachable!(), // should never be written
     }
     writer.send_success_bytes()?;
@@ -275,6 +281,9 @@ pub enum MessageBody {
   FormatResponse(ResponseBody<Option<Vec<u8>>>),
   CancelFormat(MessageId),
   HostFormat(HostFormatMessageBody),
+  /// Shuts down after finishing the in-flight requests within
+  /// the provided number of milliseconds.
+  Shutdown(u32),
   /// If encountered, process plugin should panic and
   /// the CLI should kill the process plugin.
   Unknown(u32),
@@ -1,5 +1,6 @@
 mod communicator;
 mod context;
+mod handshake;
 mod message_processor;
 mod messages;
 mod parent_process_checker;
@@ -7,6 +8,10 @@ mod shared_types;
 mod utils;
 
 pub use communicator::*;
+pub use handshake::protocol_extensions;
+pub use handshake::ProtocolInfo;
+pub use handshake::PROTOCOL_EXTENSIONS_ARG;
+pub use handshake::SUPPORTED_PROTOCOL_EXTENSIONS;
 pub use message_processor::*;
 pub use parent_process_checker::*;
 use shared_types::*;
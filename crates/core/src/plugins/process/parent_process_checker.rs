@@ -1,10 +1,50 @@
 use std::time::Duration;
 
+use crate::async_runtime::LocalBoxFuture;
+
+/// Options for `start_parent_process_checker_task_with_options`.
+pub struct ParentProcessCheckerOptions {
+  /// How often to check for the existence of the parent process.
+  pub poll_interval: Duration,
+  /// How long `on_shutdown` is given to complete before the process exits.
+  pub grace_period: Duration,
+  /// Called when the parent process no longer exists, before exiting the current process.
+  pub on_shutdown: Option<Box<dyn FnOnce() -> LocalBoxFuture<'static, ()>>>,
+}
+
+impl Default for ParentProcessCheckerOptions {
+  fn default() -> Self {
+    Self {
+      poll_interval: Duration::from_secs(5),
+      grace_period: Duration::from_secs(2),
+      on_shutdown: None,
+    }
+  }
+}
+
 /// Starts a task that polls for the existence of the parent process.
 /// If the parent process no longer exists, then it will exit the current process.
 ///
 /// Note: This must be called from a tokio runtime.
 pub fn start_parent_process_checker_task(parent_process_id: u32) {
+  start_parent_process_checker_task_with_options(parent_process_id, Default::default())
+}
+
+/// Same as `start_parent_process_checker_task`, but allows configuring how often
+/// to poll and providing a callback to clean up before the process exits.
+///
+/// The process exits once the callback completes or the grace period elapses,
+/// whichever happens first.
+///
+/// Note: This must be called from a tokio runtime.
+pub fn start_parent_process_checker_task_with_options(parent_process_id: u32, options: ParentProcessCheckerOptions) {
+  let ParentProcessCheckerOptions {
+    poll_interval,
+    grace_period,
+    on_shutdown,
+  } = options;
+  let (parent_exited_tx, parent_exited_rx) = tokio::sync::oneshot::channel::<()>();
+
   crate::async_runtime::spawn(async move {
     // wait cheaply for 2 seconds
     tokio::time::sleep(Duration::from_secs(2)).await;
@@ -14,11 +54,23 @@ pub fn start_parent_process_checker_task(parent_process_id: u32) {
     // use spawn_blocking from tokio here because it will keep
     // the process alive
     std::thread::spawn(move || loop {
-      std::thread::sleep(Duration::from_secs(5));
+      std::thread::sleep(poll_interval);
       if !is_process_active(parent_process_id) {
+        if parent_exited_tx.send(()).is_ok() {
+          // give the runtime a chance to shut down gracefully, but still
+          // exit here in case the runtime is blocked
+          std::thread::sleep(grace_period);
+        }
         std::process::exit(1);
       }
     });
+
+    if parent_exited_rx.await.is_ok() {
+      if let Some(on_shutdown) = on_shutdown {
+        let _ = tokio::time::timeout(grace_period, on_shutdown()).await;
+      }
+      std::process::exit(1);
+    }
   });
 }
 
@@ -0,0 +1,58 @@
+use dprint_core::formatting::AlignmentGroup;
+use dprint_core::formatting::PrintItems;
+use dprint_core::formatting::PrintOptions;
+use dprint_core::formatting::Signal;
+
+#[test]
+fn should_align_anchors_across_lines() {
+  let result = format(|| {
+    let group = AlignmentGroup::new("comments");
+    let mut items = PrintItems::new();
+    for (code, comment) in [("a = 1;", "// first"), ("longer = 2;", "// second"), ("b = 3;", "// third")] {
+      items.push_string(code.to_string());
+      items.push_space();
+      items.push_alignment_anchor(group);
+      items.push_string(comment.to_string());
+      items.push_signal(Signal::NewLine);
+    }
+    items
+  });
+  assert_eq!(result, concat!("a = 1;      // first\n", "longer = 2; // second\n", "b = 3;      // third\n",));
+}
+
+#[test]
+fn should_align_groups_independently() {
+  let result = format(|| {
+    let mut items = PrintItems::new();
+    for (code, value) in [("a", "1"), ("bb", "2")] {
+      let group = AlignmentGroup::new("unused");
+      items.push_alignment_anchor(group);
+      items.push_string(format!("{} = {}", code, value));
+      items.push_signal(Signal::NewLine);
+    }
+    let group = AlignmentGroup::new("values");
+    for (name, value) in [("a", "1"), ("bbb", "2")] {
+      items.push_signal(Signal::StartIndent);
+      items.push_string(name.to_string());
+      items.push_alignment_anchor(group);
+      items.push_string(format!(" = {}", value));
+      items.push_signal(Signal::FinishIndent);
+      items.push_signal(Signal::NewLine);
+    }
+    items
+  });
+  assert_eq!(result, concat!("a = 1\n", "bb = 2\n", "  a   = 1\n", "  bbb = 2\n",));
+}
+
+fn format(get_print_items: impl FnOnce() -> PrintItems) -> String {
+  dprint_core::formatting::format(
+    get_print_items,
+    PrintOptions {
+      indent_width: 2,
+      max_width: 40,
+      use_tabs: false,
+      smart_tabs: false,
+      new_line_text: "\n",
+    },
+  )
+}
@@ -0,0 +1,88 @@
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use dprint_core::formatting::ir_helpers::new_line_group;
+use dprint_core::formatting::ir_helpers::with_indent;
+use dprint_core::formatting::PrintItems;
+use dprint_core::formatting::PrintOptions;
+use dprint_core::formatting::Signal;
+
+struct CountingAllocator;
+
+static ALLOCATION_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    ALLOCATION_COUNT.fetch_add(1, Ordering::Relaxed);
+    System.alloc(layout)
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    System.dealloc(ptr, layout)
+  }
+
+  unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    ALLOCATION_COUNT.fetch_add(1, Ordering::Relaxed);
+    System.realloc(ptr, layout, new_size)
+  }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const STATEMENT_COUNT: usize = 10_000;
+
+#[test]
+fn it_allocates_print_items_in_the_arena() {
+  // the first format grows the arena, which is then reused by later formats on this thread
+  format(gen_statements(STATEMENT_COUNT));
+
+  let texts = (0..STATEMENT_COUNT * 2).map(|i| format!("value{}", i)).collect::<Vec<_>>();
+  let allocation_count_before = ALLOCATION_COUNT.load(Ordering::Relaxed);
+  let mut texts = texts.into_iter();
+  let output = format(gen_statements_with_text(STATEMENT_COUNT, move || texts.next().unwrap()));
+  let allocation_count = ALLOCATION_COUNT.load(Ordering::Relaxed) - allocation_count_before;
+
+  assert!(output.starts_with("const a = [value0, value1];\n"));
+  // each statement has 8 print items, so this would be in the tens of
+  // thousands if the items or their text were individually heap allocated
+  assert!(allocation_count < 100, "{} allocations", allocation_count);
+}
+
+fn format(get_print_items: impl FnOnce() -> PrintItems) -> String {
+  dprint_core::formatting::format(
+    get_print_items,
+    PrintOptions {
+      max_width: 80,
+      indent_width: 2,
+      use_tabs: false,
+      smart_tabs: false,
+      new_line_text: "\n",
+    },
+  )
+}
+
+fn gen_statements(count: usize) -> impl FnOnce() -> PrintItems {
+  gen_statements_with_text(count, || "value".to_string())
+}
+
+fn gen_statements_with_text(count: usize, mut get_text: impl FnMut() -> String) -> impl FnOnce() -> PrintItems {
+  move || {
+    let mut items = PrintItems::new();
+    for _ in 0..count {
+      items.push_str_runtime_width_computed("const a = [");
+      let mut elements = PrintItems::new();
+      elements.push_string(get_text());
+      elements.push_str_runtime_width_computed(",");
+      elements.push_signal(Signal::SpaceOrNewLine);
+      elements.push_string(get_text());
+      items.extend(new_line_group(with_indent(elements)));
+      items.push_str_runtime_width_computed("];");
+      items.push_signal(Signal::NewLine);
+    }
+    items
+  }
+}
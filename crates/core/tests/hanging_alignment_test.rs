@@ -0,0 +1,54 @@
+use dprint_core::formatting::conditions::align_or_indent_if_above_width;
+use dprint_core::formatting::ir_helpers::with_alignment;
+use dprint_core::formatting::PrintItems;
+use dprint_core::formatting::PrintOptions;
+use dprint_core::formatting::Signal;
+
+#[test]
+fn should_align_continuation_lines_after_open_paren() {
+  let result = format(|| {
+    let mut items = PrintItems::new();
+    items.push_str_runtime_width_computed("call(");
+    items.extend(with_alignment(gen_args()));
+    items.push_str_runtime_width_computed(");");
+    items
+  });
+  assert_eq!(result, "call(first,\n     second);");
+}
+
+#[test]
+fn should_align_or_indent_based_on_width() {
+  let gen = |prefix: &'static str| {
+    format(move || {
+      let mut items = PrintItems::new();
+      items.push_signal(Signal::StartIndent);
+      items.push_str_runtime_width_computed(prefix);
+      items.push_condition(align_or_indent_if_above_width(8, gen_args()));
+      items.push_signal(Signal::FinishIndent);
+      items
+    })
+  };
+  assert_eq!(gen("x = "), "  x = first,\n      second");
+  assert_eq!(gen("longName = "), "  longName = first,\n    second");
+}
+
+fn gen_args() -> PrintItems {
+  let mut items = PrintItems::new();
+  items.push_str_runtime_width_computed("first,");
+  items.push_signal(Signal::NewLine);
+  items.push_str_runtime_width_computed("second");
+  items
+}
+
+fn format(get_print_items: impl FnOnce() -> PrintItems) -> String {
+  dprint_core::formatting::format(
+    get_print_items,
+    PrintOptions {
+      indent_width: 2,
+      max_width: 40,
+      use_tabs: false,
+      smart_tabs: false,
+      new_line_text: "\n",
+    },
+  )
+}
@@ -32,6 +32,7 @@ fn should_stabilize_after_reevaluation_flipping() {
       indent_width: 2,
       max_width: 40,
       use_tabs: false,
+      smart_tabs: false,
       new_line_text: "\n",
     },
   );
@@ -132,6 +132,7 @@ fn do_test(expr: &ArrayLiteralExpression, expected_text: &str) {
       indent_width: 2,
       max_width: 40,
       use_tabs: false,
+      smart_tabs: false,
       new_line_text: "\n",
     },
   );
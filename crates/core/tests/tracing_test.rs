@@ -25,6 +25,7 @@ fn test_tracing() {
     PrintOptions {
       indent_width: 4,
       use_tabs: false,
+      smart_tabs: false,
       max_width: 80,
       new_line_text: "\n",
     },
@@ -34,4 +35,13 @@ fn test_tracing() {
   assert_eq!(trace_result.print_nodes.len(), 8);
   assert_eq!(trace_result.traces.len(), 7);
   assert_eq!(trace_result.writer_nodes.len(), 4);
+
+  // the resolved values are included for infos and conditions
+  let resolutions = trace_result.traces.iter().filter_map(|trace| trace.resolution).collect::<Vec<_>>();
+  assert_eq!(
+    resolutions,
+    vec![TraceResolution::Info(TraceInfoValue::Number(0)), TraceResolution::Condition(Some(true))]
+  );
+  let last_trace = trace_result.traces.last().unwrap();
+  assert_eq!(last_trace.writer_info.line_and_column(), (1, 19));
 }
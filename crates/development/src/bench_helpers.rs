@@ -0,0 +1,143 @@
+use anyhow::Result;
+use criterion::black_box;
+use criterion::Criterion;
+use criterion::Throughput;
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+static ALLOCATION_COUNT: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Allocator that counts allocations so that [`bench_corpus`] can report them.
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: dprint_development::CountingAllocator = dprint_development::CountingAllocator;
+/// ```
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    ALLOCATION_COUNT.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+    System.alloc(layout)
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    System.dealloc(ptr, layout)
+  }
+
+  unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    ALLOCATION_COUNT.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+    System.realloc(ptr, layout, new_size)
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocationStats {
+  pub count: u64,
+  pub bytes: u64,
+}
+
+/// Gets the allocations made by the action when the [`CountingAllocator`]
+/// is the global allocator. Otherwise, this is always zero.
+pub fn measure_allocations<T>(action: impl FnOnce() -> T) -> (T, AllocationStats) {
+  let start_count = ALLOCATION_COUNT.load(Ordering::Relaxed);
+  let start_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+  let result = action();
+  let stats = AllocationStats {
+    count: ALLOCATION_COUNT.load(Ordering::Relaxed) - start_count,
+    bytes: ALLOCATED_BYTES.load(Ordering::Relaxed) - start_bytes,
+  };
+  (result, stats)
+}
+
+/// Files to benchmark formatting.
+#[derive(Debug, Clone)]
+pub struct BenchCorpus {
+  pub files: Vec<(PathBuf, String)>,
+}
+
+impl BenchCorpus {
+  /// Reads the files in the directory and its descendants, only including
+  /// the files with the provided extensions when any are provided.
+  pub fn from_dir(dir_path: &Path, extensions: &[&str]) -> Self {
+    let mut files = Vec::new();
+    read_dir_files(dir_path, extensions, &mut files);
+    if files.is_empty() {
+      panic!("Could not find any files to benchmark in {}", dir_path.display());
+    }
+    // keep the order the same between runs
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    Self { files }
+  }
+
+  pub fn total_bytes(&self) -> u64 {
+    self.files.iter().map(|(_, file_text)| file_text.len() as u64).sum()
+  }
+
+  /// Formats every file in the corpus, panicking on an error.
+  pub fn format_all(&self, format_text: &impl Fn(&Path, &str) -> Result<Option<String>>) {
+    for (file_path, file_text) in &self.files {
+      let result = format_text(file_path, file_text).unwrap_or_else(|err| panic!("Failed formatting {}: {:#}", file_path.display(), err));
+      black_box(result);
+    }
+  }
+}
+
+fn read_dir_files(dir_path: &Path, extensions: &[&str], files: &mut Vec<(PathBuf, String)>) {
+  let entries = fs::read_dir(dir_path).unwrap_or_else(|err| panic!("Could not read directory {}: {:#}", dir_path.display(), err));
+  for entry in entries {
+    let path = entry.unwrap().path();
+    if path.is_dir() {
+      read_dir_files(&path, extensions, files);
+    } else if extensions.is_empty()
+      || path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| extensions.contains(&ext))
+        .unwrap_or(false)
+    {
+      let file_text = fs::read_to_string(&path).unwrap_or_else(|err| panic!("Could not read file {}: {:#}", path.display(), err));
+      files.push((path, file_text));
+    }
+  }
+}
+
+/// Benchmarks formatting the corpus, reporting the throughput in bytes
+/// and the allocations per run when using the [`CountingAllocator`].
+///
+/// ```ignore
+/// fn bench(c: &mut Criterion) {
+///   let corpus = BenchCorpus::from_dir(Path::new("benches/corpus"), &["ts"]);
+///   bench_corpus(c, "typescript", &corpus, |file_path, file_text| format_text(file_path, file_text, &config));
+/// }
+///
+/// criterion_group!(benches, bench);
+/// criterion_main!(benches);
+/// ```
+pub fn bench_corpus(c: &mut Criterion, name: &str, corpus: &BenchCorpus, format_text: impl Fn(&Path, &str) -> Result<Option<String>>) {
+  // format once outside the measurements to fail fast and to count the allocations
+  let ((), allocations) = measure_allocations(|| corpus.format_all(&format_text));
+  if allocations.count > 0 {
+    eprintln!(
+      "{}: {} allocations ({} bytes) per run of {} files",
+      name,
+      allocations.count,
+      allocations.bytes,
+      corpus.files.len()
+    );
+  }
+
+  let mut group = c.benchmark_group(name);
+  group.throughput(Throughput::Bytes(corpus.total_bytes()));
+  group.bench_function("format", |b| b.iter(|| corpus.format_all(&format_text)));
+  group.finish();
+}
@@ -0,0 +1,210 @@
+use anyhow::Result;
+use std::panic::catch_unwind;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+
+/// Text that commonly causes issues when inserted into code (ex. unclosed
+/// strings, comments, and brackets like when someone is typing in an IDE).
+const INTERESTING_TEXTS: [&str; 21] = [
+  "{", "}", "(", ")", "[", "]", "<", ">", "\"", "'", "`", "/*", "//", "*/", "\n", "\r\n", " ", "\t", ";", ",", "\u{FEFF}",
+];
+
+#[derive(Debug, Clone)]
+pub struct FuzzOptions {
+  /// Number of randomly mutated inputs to format for each seed text.
+  pub mutations: usize,
+  /// Seed for generating the mutations, which should be changed to find other failures.
+  pub seed: u64,
+  /// Also format every prefix of the seed text (ex. half written code).
+  pub truncate: bool,
+}
+
+impl Default for FuzzOptions {
+  fn default() -> Self {
+    Self {
+      mutations: 1_000,
+      seed: 1,
+      truncate: true,
+    }
+  }
+}
+
+/// Formats truncated and mutated versions of the seed text and panics with
+/// the input to reproduce it when formatting:
+///
+/// - Panics.
+/// - Gives a different result when formatting the same input twice.
+/// - Changes or fails on its own output (ex. the output isn't idempotent).
+///
+/// Returning an error for invalid input is allowed.
+pub fn fuzz_format_text(file_path: &Path, seed_text: &str, options: &FuzzOptions, format_text: impl Fn(&Path, &str) -> Result<Option<String>>) {
+  if options.truncate {
+    for (index, _) in seed_text.char_indices().skip(1) {
+      check_format_invariants(file_path, &seed_text[..index], &format_text);
+    }
+  }
+
+  let mut rng = XorShiftRng::new(options.seed);
+  for _ in 0..options.mutations {
+    let input = mutate_text(seed_text, &mut rng);
+    check_format_invariants(file_path, &input, &format_text);
+  }
+}
+
+/// Checks the invariants of [`fuzz_format_text`] for the input provided by a fuzzer.
+///
+/// For example, in a cargo-fuzz target:
+///
+/// ```ignore
+/// fuzz_target!(|data: &[u8]| {
+///   dprint_development::fuzz_format_bytes(Path::new("file.ts"), data, |file_path, file_text| {
+///     format_text(file_path, file_text, &config)
+///   });
+/// });
+/// ```
+pub fn fuzz_format_bytes(file_path: &Path, data: &[u8], format_text: impl Fn(&Path, &str) -> Result<Option<String>>) {
+  // only text is formatted
+  if let Ok(file_text) = std::str::from_utf8(data) {
+    check_format_invariants(file_path, file_text, &format_text);
+  }
+}
+
+fn check_format_invariants(file_path: &Path, file_text: &str, format_text: &impl Fn(&Path, &str) -> Result<Option<String>>) {
+  let format = |file_text: &str| match catch_unwind(AssertUnwindSafe(|| format_text(file_path, file_text))) {
+    Ok(result) => result.map_err(|err| format!("{:#}", err)),
+    Err(_) => panic!("Panicked formatting {}.\nInput: {:?}", file_path.display(), file_text),
+  };
+
+  let result = format(file_text);
+  let second_result = format(file_text);
+  if result != second_result {
+    panic!(
+      "Formatting {} gave a different result the second time.\nInput: {:?}\nFirst: {:?}\nSecond: {:?}",
+      file_path.display(),
+      file_text,
+      result,
+      second_result,
+    );
+  }
+
+  if let Ok(Some(formatted_text)) = result {
+    match format(&formatted_text) {
+      Ok(None) => {}
+      Ok(Some(reformatted_text)) if reformatted_text == formatted_text => {}
+      reformatted_result => panic!(
+        "Formatting the output of {} changed it.\nInput: {:?}\nOutput: {:?}\nOutput formatted: {:?}",
+        file_path.display(),
+        file_text,
+        formatted_text,
+        reformatted_result,
+      ),
+    }
+  }
+}
+
+fn mutate_text(text: &str, rng: &mut XorShiftRng) -> String {
+  let mut text = text.to_string();
+  // apply a few mutations so that they may interact
+  for _ in 0..rng.next_range(1, 4) {
+    let start = rng.char_boundary(&text);
+    match rng.next_range(0, 4) {
+      // insert
+      0 => text.insert_str(start, INTERESTING_TEXTS[rng.next_range(0, INTERESTING_TEXTS.len())]),
+      // remove
+      1 => {
+        let end = rng.char_boundary(&text[start..]) + start;
+        text.replace_range(start..end, "");
+      }
+      // duplicate
+      2 => {
+        let end = rng.char_boundary(&text[start..]) + start;
+        let duplicate = text[start..end].to_string();
+        text.insert_str(end, &duplicate);
+      }
+      // truncate
+      _ => text.truncate(start),
+    }
+  }
+  text
+}
+
+/// Deterministic random number generator so failures are reproducible
+/// and so this crate doesn't need a dependency for it.
+struct XorShiftRng(u64);
+
+impl XorShiftRng {
+  pub fn new(seed: u64) -> Self {
+    // the state must not be zero
+    Self(seed.max(1))
+  }
+
+  pub fn next(&mut self) -> u64 {
+    self.0 ^= self.0 << 13;
+    self.0 ^= self.0 >> 7;
+    self.0 ^= self.0 << 17;
+    self.0
+  }
+
+  /// Gets a number from the start (inclusive) to the end (exclusive).
+  pub fn next_range(&mut self, start: usize, end: usize) -> usize {
+    start + (self.next() % (end - start) as u64) as usize
+  }
+
+  /// Gets a random char boundary in the text, including its end.
+  pub fn char_boundary(&mut self, text: &str) -> usize {
+    let mut index = self.next_range(0, text.len() + 1);
+    while !text.is_char_boundary(index) {
+      index -= 1;
+    }
+    index
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn format_spaces(_: &Path, file_text: &str) -> Result<Option<String>> {
+    let formatted_text = file_text.split(' ').filter(|text| !text.is_empty()).collect::<Vec<_>>().join(" ");
+    Ok(if formatted_text == file_text { None } else { Some(formatted_text) })
+  }
+
+  #[test]
+  fn it_fuzzes_stable_formatter() {
+    let options = FuzzOptions {
+      mutations: 200,
+      ..Default::default()
+    };
+    fuzz_format_text(Path::new("file.txt"), "let  a = 1; \n/* ü */ \n", &options, format_spaces);
+    fuzz_format_bytes(Path::new("file.txt"), b"text \n", format_spaces);
+    fuzz_format_bytes(Path::new("file.txt"), &[0xFF, 0xFE], format_spaces);
+  }
+
+  #[test]
+  #[should_panic(expected = "Panicked formatting file.txt.")]
+  fn it_panics_on_formatter_panic() {
+    fuzz_format_text(Path::new("file.txt"), "a{b}", &Default::default(), |_, file_text| {
+      if file_text.matches('{').count() != file_text.matches('}').count() {
+        panic!("unbalanced");
+      }
+      Ok(None)
+    });
+  }
+
+  #[test]
+  #[should_panic(expected = "Formatting the output of file.txt changed it.")]
+  fn it_panics_on_unstable_output() {
+    fuzz_format_text(Path::new("file.txt"), "a", &Default::default(), |_, file_text| {
+      Ok(Some(format!("{} ", file_text)))
+    });
+  }
+
+  #[test]
+  fn it_mutates_at_char_boundaries() {
+    let mut rng = XorShiftRng::new(0);
+    for _ in 0..1_000 {
+      // would panic when not on a char boundary
+      mutate_text("üñí©ødé", &mut rng);
+    }
+  }
+}
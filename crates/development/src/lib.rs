@@ -1,7 +1,13 @@
+#[cfg(feature = "bench")]
+mod bench_helpers;
 mod config_helpers;
+mod fuzz_helpers;
 mod spec_helpers;
 mod spec_parser;
 
+#[cfg(feature = "bench")]
+pub use bench_helpers::*;
 pub use config_helpers::*;
+pub use fuzz_helpers::*;
 pub use spec_helpers::*;
 pub use spec_parser::*;
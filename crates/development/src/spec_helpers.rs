@@ -6,8 +6,10 @@ use file_test_runner::SubTestResult;
 use file_test_runner::TestResult;
 use similar::ChangeTag;
 use similar::TextDiff;
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::fs;
+use std::ops::Range;
 use std::panic::catch_unwind;
 use std::panic::AssertUnwindSafe;
 use std::path::Path;
@@ -47,11 +49,15 @@ impl Display for DiffFailedMessage<'_> {
 }
 
 type FormatTextFunc = dyn (Fn(&Path, &str, &SpecConfigMap) -> Result<Option<String>>) + Send + Sync;
+type FormatTextWithRangeFunc = dyn (Fn(&Path, &str, &SpecConfigMap, Option<Range<usize>>) -> Result<Option<String>>) + Send + Sync;
 type GetTraceJsonFunc = dyn (Fn(&Path, &str, &SpecConfigMap) -> String) + Send + Sync;
 
 #[derive(Debug, Clone)]
 pub struct RunSpecsOptions {
   /// Set to true to overwrite the failing tests with the actual result.
+  ///
+  /// This may also be done without changing the code by setting the
+  /// `DPRINT_UPDATE_SPECS` environment variable to `1`.
   pub fix_failures: bool,
   pub format_twice: bool,
 }
@@ -62,12 +68,36 @@ pub fn run_specs(
   run_spec_options: &RunSpecsOptions,
   format_text: Arc<FormatTextFunc>,
   get_trace_json: Arc<GetTraceJsonFunc>,
+) {
+  run_specs_with_range(
+    directory_path,
+    parse_spec_options,
+    run_spec_options,
+    Arc::new(move |file_path, file_text, config, range| {
+      if range.is_some() {
+        panic!("Specs with a range must be run with `run_specs_with_range`.");
+      }
+      format_text(file_path, file_text, config)
+    }),
+    get_trace_json,
+  )
+}
+
+/// Runs the specs providing the range to format of specs with range markers
+/// (`〚` and `〛`) for plugins that support range formatting.
+pub fn run_specs_with_range(
+  directory_path: &Path,
+  parse_spec_options: &ParseSpecOptions,
+  run_spec_options: &RunSpecsOptions,
+  format_text: Arc<FormatTextWithRangeFunc>,
+  get_trace_json: Arc<GetTraceJsonFunc>,
 ) {
   #[cfg(not(debug_assertions))]
   assert_not_fix_failures(run_spec_options);
 
   let parse_spec_options = parse_spec_options.clone();
   let run_spec_options = run_spec_options.clone();
+  let update_specs = run_spec_options.fix_failures || is_update_specs_env_var_set();
   file_test_runner::collect_and_run_tests(
     CollectOptions {
       base: directory_path.to_path_buf(),
@@ -77,30 +107,38 @@ pub fn run_specs(
     RunOptions { parallel: true },
     Arc::new(move |test| {
       let file_text = test.read_to_string().unwrap();
-      let specs = parse_specs(file_text, &parse_spec_options);
-      let specs = if specs.iter().any(|s| s.is_only) {
-        specs.into_iter().filter(|s| s.is_only).collect()
+      let specs = parse_specs(file_text.clone(), &parse_spec_options).into_iter().enumerate().collect::<Vec<_>>();
+      let specs = if specs.iter().any(|(_, s)| s.is_only) {
+        specs.into_iter().filter(|(_, s)| s.is_only).collect()
       } else {
         specs
       };
       let mut sub_tests = Vec::new();
-      for spec in specs {
+      let mut updated_expected_texts = HashMap::new();
+      for (spec_index, spec) in specs {
         #[cfg(not(debug_assertions))]
         assert_spec_not_only_or_trace(&spec);
 
         if spec.skip {
           sub_tests.push(SubTestResult {
-            name: spec.message.clone(),
+            name: spec.name(),
             result: TestResult::Ignored,
           });
           continue;
         }
 
         let test_file_path = &test.path;
-        let maybe_failed_result = run_spec(&spec, test_file_path, &run_spec_options, &format_text, &get_trace_json);
+        let maybe_failed_result = match run_spec(&spec, test_file_path, &run_spec_options, &format_text, &get_trace_json) {
+          // only update the expected text when the output is stable
+          Some(failed_test) if update_specs && failed_test.actual_second.is_none() => {
+            updated_expected_texts.insert(spec_index, failed_test.actual);
+            None
+          }
+          maybe_failed_result => maybe_failed_result,
+        };
 
         sub_tests.push(SubTestResult {
-          name: spec.message.clone(),
+          name: spec.name(),
           result: if let Some(failed_test) = maybe_failed_result {
             let mut output = Vec::<u8>::new();
             let mut failed_message = format!(
@@ -132,6 +170,11 @@ pub fn run_specs(
         });
       }
 
+      if !updated_expected_texts.is_empty() {
+        let file_text = replace_spec_expected_texts(&file_text, &parse_spec_options, &updated_expected_texts);
+        fs::write(&test.path, file_text).expect("Expected to write to file.");
+      }
+
       TestResult::SubTests(sub_tests)
     }),
   );
@@ -140,17 +183,17 @@ pub fn run_specs(
     spec: &Spec,
     test_file_path: &Path,
     run_spec_options: &RunSpecsOptions,
-    format_text: &Arc<FormatTextFunc>,
+    format_text: &Arc<FormatTextWithRangeFunc>,
     get_trace_json: &Arc<GetTraceJsonFunc>,
   ) -> Option<FailedTestResult> {
     let spec_file_path_buf = PathBuf::from(&spec.file_name);
-    let format = |file_text: &str| {
-      let result = catch_unwind(AssertUnwindSafe(|| format_text(&spec_file_path_buf, file_text, &spec.config)));
+    let format = |file_text: &str, range: Option<Range<usize>>| {
+      let result = catch_unwind(AssertUnwindSafe(|| format_text(&spec_file_path_buf, file_text, &spec.config, range)));
       if result.is_err() {
-        eprintln!("Panic in spec '{}' in {}\n", spec.message, test_file_path.display());
+        eprintln!("Panic in spec '{}' in {}\n", spec.name(), test_file_path.display());
       }
       let result = result.unwrap();
-      result.unwrap_or_else(|err| panic!("Could not parse spec '{}' in {}\nMessage: {:#}", spec.message, test_file_path.display(), err,))
+      result.unwrap_or_else(|err| panic!("Could not parse spec '{}' in {}\nMessage: {:#}", spec.name(), test_file_path.display(), err,))
     };
 
     if spec.is_trace {
@@ -158,31 +201,24 @@ pub fn run_specs(
       handle_trace(spec, &trace_json);
       None
     } else {
-      let result = format(&spec.file_text).unwrap_or_else(|| spec.file_text.to_string());
+      let result = format(&spec.file_text, spec.range.clone()).unwrap_or_else(|| spec.file_text.to_string());
       if result != spec.expected_text {
-        if run_spec_options.fix_failures {
-          // very rough, but good enough
-          let file_text = fs::read_to_string(test_file_path).expect("Expected to read the file.");
-          let file_text = file_text.replace(&spec.expected_text, &result);
-          fs::write(test_file_path, file_text).expect("Expected to write to file.");
-          None
-        } else {
-          Some(FailedTestResult {
-            expected: spec.expected_text.clone(),
-            actual: result,
-            actual_second: None,
-            message: spec.message.clone(),
-          })
-        }
-      } else if run_spec_options.format_twice && !spec.skip_format_twice {
-        // ensure no changes when formatting twice
-        let twice_result = format(&result).unwrap_or_else(|| result.to_string());
+        Some(FailedTestResult {
+          expected: spec.expected_text.clone(),
+          actual: result,
+          actual_second: None,
+          message: spec.name(),
+        })
+      } else if run_spec_options.format_twice && !spec.skip_format_twice && spec.range.is_none() {
+        // ensure no changes when formatting twice (the range would be
+        // out of date, so this is only done when formatting the whole file)
+        let twice_result = format(&result, None).unwrap_or_else(|| result.to_string());
         if twice_result != spec.expected_text {
           Some(FailedTestResult {
             expected: spec.expected_text.clone(),
             actual: result,
             actual_second: Some(twice_result),
-            message: spec.message.clone(),
+            message: spec.name(),
           })
         } else {
           None
@@ -238,6 +274,10 @@ pub fn run_specs(
     panic!("\n==============\nTrace output ready! Please open your browser to: {}\n==============\n", url);
   }
 
+  fn is_update_specs_env_var_set() -> bool {
+    matches!(std::env::var("DPRINT_UPDATE_SPECS").as_deref(), Ok("1") | Ok("true"))
+  }
+
   #[cfg(not(debug_assertions))]
   fn assert_spec_not_only_or_trace(spec: &Spec) {
     if spec.is_trace {
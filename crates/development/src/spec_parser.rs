@@ -1,14 +1,38 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Marks the start of the range to format in a spec's file text.
+pub const SPEC_RANGE_START: &str = "\u{301A}";
+/// Marks the end of the range to format in a spec's file text.
+pub const SPEC_RANGE_END: &str = "\u{301B}";
+
 #[derive(PartialEq, Eq, Debug)]
 pub struct Spec {
   pub file_name: String,
   pub message: String,
   pub file_text: String,
+  /// The byte range of the file text to format (the same as a
+  /// `FormatRange` in dprint-core) when the spec has range markers.
+  pub range: Option<Range<usize>>,
   pub expected_text: String,
   pub is_only: bool,
   pub is_trace: bool,
   pub skip: bool,
   pub skip_format_twice: bool,
   pub config: SpecConfigMap,
+  /// The name of the config variant from the file's header that was
+  /// merged into the config (ex. `~~ (tabs) useTabs: true ~~`).
+  pub config_variant: Option<String>,
+}
+
+impl Spec {
+  /// Gets the message with the name of the config variant, if any.
+  pub fn name(&self) -> String {
+    match &self.config_variant {
+      Some(config_variant) => format!("{} ({})", self.message, config_variant),
+      None => self.message.clone(),
+    }
+  }
 }
 
 pub type SpecConfigMap = serde_json::Map<String, serde_json::Value>;
@@ -23,7 +47,7 @@ pub fn parse_specs(file_text: String, options: &ParseSpecOptions) -> Vec<Spec> {
   // this function needs a rewrite
   let file_text = file_text.replace("\r\n", "\n");
   let (file_path, file_text) = parse_file_path(file_text, options);
-  let (config, file_text) = parse_config(file_text);
+  let (config, config_variants, file_text) = parse_configs(file_text);
   let lines = file_text.split('\n').collect::<Vec<_>>();
   let spec_starts = get_spec_starts(&file_path, &lines);
   let mut specs = Vec::new();
@@ -32,9 +56,23 @@ pub fn parse_specs(file_text: String, options: &ParseSpecOptions) -> Vec<Spec> {
     let start_index = spec_starts[i];
     let end_index = if spec_starts.len() == i + 1 { lines.len() } else { spec_starts[i + 1] };
     let message_line = lines[start_index];
-    let spec = parse_single_spec(&file_path, message_line, &lines[(start_index + 1)..end_index], &config);
-
-    specs.push(spec);
+    let spec_lines = &lines[(start_index + 1)..end_index];
+    for (name, _) in split_expect_sections(spec_lines).1 {
+      if let Some(name) = name {
+        if !config_variants.iter().any(|(variant_name, _)| *variant_name == name) {
+          panic!("Could not find config variant '{}' used in: {}", name, message_line);
+        }
+      }
+    }
+    if config_variants.is_empty() {
+      specs.push(parse_single_spec(&file_path, message_line, spec_lines, &config, None));
+    } else {
+      for (name, variant_config) in &config_variants {
+        let mut config = config.clone();
+        config.extend(variant_config.clone());
+        specs.push(parse_single_spec(&file_path, message_line, spec_lines, &config, Some(name)));
+      }
+    }
   }
 
   return specs;
@@ -48,14 +86,35 @@ pub fn parse_specs(file_text: String, options: &ParseSpecOptions) -> Vec<Spec> {
     (file_text["--".len()..last_index].trim().into(), file_text[(last_index + "--\n".len())..].into())
   }
 
-  fn parse_config(file_text: String) -> (SpecConfigMap, String) {
-    if !file_text.starts_with("~~") {
-      return (Default::default(), file_text);
+  fn parse_configs(mut file_text: String) -> (SpecConfigMap, Vec<(String, SpecConfigMap)>, String) {
+    let mut config = SpecConfigMap::new();
+    let mut config_variants = Vec::new();
+    while file_text.starts_with("~~") {
+      let (config_text, remaining_text) = parse_config(file_text);
+      match parse_config_variant_name(&config_text) {
+        Some((name, config_text)) => config_variants.push((name.to_string(), parse_config_text(config_text))),
+        None => config.extend(parse_config_text(&config_text)),
+      }
+      file_text = remaining_text;
     }
+    (config, config_variants, file_text)
+  }
+
+  fn parse_config(file_text: String) -> (String, String) {
     let last_index = file_text.find("~~\n").expect("Could not find final ~~\\n");
 
     let config_text = file_text["~~".len()..last_index].replace('\n', "");
-    let config_text = config_text.trim();
+    (config_text.trim().to_string(), file_text[(last_index + "~~\n".len())..].into())
+  }
+
+  /// Gets the name of a config variant (ex. `~~ (tabs) useTabs: true ~~`).
+  fn parse_config_variant_name(config_text: &str) -> Option<(&str, &str)> {
+    let config_text = config_text.strip_prefix('(')?;
+    let end_index = config_text.find(')').expect("Could not find closing parenthesis of config variant name.");
+    Some((config_text[..end_index].trim(), config_text[end_index + ")".len()..].trim()))
+  }
+
+  fn parse_config_text(config_text: &str) -> SpecConfigMap {
     let mut config: SpecConfigMap = Default::default();
 
     if config_text.starts_with('{') {
@@ -79,7 +138,7 @@ pub fn parse_specs(file_text: String, options: &ParseSpecOptions) -> Vec<Spec> {
       }
     }
 
-    (config, file_text[(last_index + "~~\n".len())..].into())
+    config
   }
 
   fn get_spec_starts(file_name: &str, lines: &[&str]) -> Vec<usize> {
@@ -99,37 +158,166 @@ pub fn parse_specs(file_text: String, options: &ParseSpecOptions) -> Vec<Spec> {
     result
   }
 
-  fn parse_single_spec(file_name: &str, message_line: &str, lines: &[&str], config: &SpecConfigMap) -> Spec {
-    let file_text = lines.join("\n");
-    let parts = file_text.split("[expect]").collect::<Vec<&str>>();
-    let start_text = parts[0][0..parts[0].len() - "\n".len()].into(); // remove last newline
-    let expected_text = parts[1]["\n".len()..].into(); // remove first newline
+  fn parse_single_spec(file_name: &str, message_line: &str, lines: &[&str], config: &SpecConfigMap, config_variant: Option<&str>) -> Spec {
+    let (start_lines, expect_sections) = split_expect_sections(lines);
+    let (start_text, range) = parse_range(&start_lines.join("\n"));
     let lower_case_message_line = message_line.to_ascii_lowercase();
     let message_separator = get_message_separator(file_name);
+    let message = message_line[message_separator.len()..message_line.len() - message_separator.len()].trim();
     let is_trace = lower_case_message_line.contains("(trace)");
+    // use the expected text of the config variant when it differs from the others
+    let expected_lines = expect_sections
+      .iter()
+      .find(|(name, _)| name.is_some() && name.as_deref() == config_variant)
+      .or_else(|| expect_sections.iter().find(|(name, _)| name.is_none()))
+      .map(|(_, lines)| lines)
+      .unwrap_or_else(|| panic!("Could not find [expect] in spec '{}'.", message));
 
     Spec {
       file_name: String::from(file_name),
-      message: message_line[message_separator.len()..message_line.len() - message_separator.len()]
-        .trim()
-        .into(),
+      message: message.into(),
       file_text: start_text,
-      expected_text,
+      range,
+      expected_text: expected_lines.join("\n"),
       is_only: lower_case_message_line.contains("(only)") || is_trace,
       is_trace,
       skip: lower_case_message_line.contains("(skip)"),
       skip_format_twice: lower_case_message_line.contains("(skip-format-twice)"),
       config: config.clone(),
+      config_variant: config_variant.map(|name| name.to_string()),
     }
   }
 
-  fn get_message_separator(file_name: &str) -> &'static str {
-    if file_name.ends_with(".md") {
-      "!!"
+  fn parse_range(file_text: &str) -> (String, Option<Range<usize>>) {
+    let start_count = file_text.matches(SPEC_RANGE_START).count();
+    let end_count = file_text.matches(SPEC_RANGE_END).count();
+    if start_count == 0 && end_count == 0 {
+      return (file_text.to_string(), None);
+    }
+    if start_count != 1 || end_count != 1 {
+      panic!(
+        "Specs must have one range start ({}) and one range end ({}) marker.",
+        SPEC_RANGE_START, SPEC_RANGE_END
+      );
+    }
+    let start = file_text.find(SPEC_RANGE_START).unwrap();
+    let end = file_text.find(SPEC_RANGE_END).unwrap();
+    if end < start {
+      panic!(
+        "The range end marker ({}) must come after the range start marker ({}).",
+        SPEC_RANGE_END, SPEC_RANGE_START
+      );
+    }
+    let text = format!(
+      "{}{}{}",
+      &file_text[..start],
+      &file_text[start + SPEC_RANGE_START.len()..end],
+      &file_text[end + SPEC_RANGE_END.len()..]
+    );
+    (text, Some(start..end - SPEC_RANGE_START.len()))
+  }
+}
+
+/// The name of the config variant, if any, and the lines of an `[expect]` section.
+type ExpectSection<'a> = (Option<String>, Vec<&'a str>);
+
+/// Splits the lines of a spec into the lines of the input and the lines of each
+/// `[expect]` section, which may be for a config variant (ex. `[expect (tabs)]`).
+fn split_expect_sections<'a>(lines: &[&'a str]) -> (Vec<&'a str>, Vec<ExpectSection<'a>>) {
+  let mut start_lines = Vec::new();
+  let mut expect_sections: Vec<ExpectSection> = Vec::new();
+  for line in lines {
+    if let Some(name) = parse_expect_line(line) {
+      expect_sections.push((name, Vec::new()));
+    } else if let Some((_, section_lines)) = expect_sections.last_mut() {
+      section_lines.push(line);
     } else {
-      "=="
+      start_lines.push(*line);
+    }
+  }
+  (start_lines, expect_sections)
+}
+
+/// Parses `[expect]` or `[expect (<config variant name>)]`.
+fn parse_expect_line(line: &str) -> Option<Option<String>> {
+  let line = line.trim_end();
+  if line == "[expect]" {
+    return Some(None);
+  }
+  let name = line.strip_prefix("[expect (")?.strip_suffix(")]")?;
+  Some(Some(name.trim().to_string()))
+}
+
+/// Replaces the expected text of the specs at the provided indexes (in the
+/// order returned by `parse_specs`), keeping the rest of the file text as-is.
+///
+/// When a spec of a config variant uses the shared `[expect]` section, an
+/// `[expect (<config variant name>)]` section is added for it instead.
+pub fn replace_spec_expected_texts(file_text: &str, options: &ParseSpecOptions, expected_texts: &HashMap<usize, String>) -> String {
+  let specs = parse_specs(file_text.to_string(), options);
+  let message_separator = get_message_separator(&specs[0].file_name);
+  let is_crlf = file_text.contains("\r\n");
+  let file_text = file_text.replace("\r\n", "\n");
+  let lines = file_text.split('\n').collect::<Vec<_>>();
+  let spec_starts = lines
+    .iter()
+    .enumerate()
+    .filter(|(_, line)| line.starts_with(message_separator))
+    .map(|(i, _)| i)
+    .collect::<Vec<_>>();
+  // each spec in the file is parsed once per config variant
+  let specs_per_section = specs.len() / spec_starts.len();
+  let mut output_lines = lines[..spec_starts[0]].iter().map(|line| line.to_string()).collect::<Vec<_>>();
+
+  for (i, start_index) in spec_starts.iter().enumerate() {
+    let end_index = spec_starts.get(i + 1).copied().unwrap_or(lines.len());
+    output_lines.push(lines[*start_index].to_string());
+    let (start_lines, mut expect_sections) = split_expect_sections(&lines[(start_index + 1)..end_index]);
+    let mut added_sections = Vec::new();
+    for (spec_index, spec) in specs.iter().enumerate().skip(i * specs_per_section).take(specs_per_section) {
+      let Some(expected_text) = expected_texts.get(&spec_index) else {
+        continue;
+      };
+      let config_variant = &spec.config_variant;
+      match expect_sections.iter_mut().find(|(name, _)| name == config_variant) {
+        Some((_, section_lines)) => *section_lines = expected_text.split('\n').collect(),
+        None => added_sections.push((config_variant.clone(), expected_text.split('\n').collect())),
+      }
+    }
+    if !added_sections.is_empty() {
+      // ensure the new sections start on a new line
+      if let Some((_, section_lines)) = expect_sections.last_mut() {
+        if section_lines.last() != Some(&"") {
+          section_lines.push("");
+        }
+      }
+      expect_sections.extend(added_sections);
+    }
+
+    output_lines.extend(start_lines.iter().map(|line| line.to_string()));
+    for (name, section_lines) in expect_sections {
+      output_lines.push(match name {
+        Some(name) => format!("[expect ({})]", name),
+        None => "[expect]".to_string(),
+      });
+      output_lines.extend(section_lines.iter().map(|line| line.to_string()));
     }
   }
+
+  let output = output_lines.join("\n");
+  if is_crlf {
+    output.replace('\n', "\r\n")
+  } else {
+    output
+  }
+}
+
+fn get_message_separator(file_name: &str) -> &'static str {
+  if file_name.ends_with(".md") {
+    "!!"
+  } else {
+    "=="
+  }
 }
 
 #[cfg(test)]
@@ -171,6 +359,7 @@ mod tests {
       Spec {
         file_name: "test.ts".into(),
         file_text: "start\nmultiple\n".into(),
+        range: None,
         expected_text: "expected\nmultiple\n".into(),
         message: "message 1".into(),
         is_only: false,
@@ -178,6 +367,7 @@ mod tests {
         skip: false,
         skip_format_twice: false,
         config: Default::default(),
+        config_variant: None,
       }
     );
     assert_eq!(
@@ -185,6 +375,7 @@ mod tests {
       Spec {
         file_name: "test.ts".into(),
         file_text: "start2\n".into(),
+        range: None,
         expected_text: "expected2\n".into(),
         message: "message 2 (only) (skip) (skip-format-twice)".into(),
         is_only: true,
@@ -192,6 +383,7 @@ mod tests {
         skip: true,
         skip_format_twice: true,
         config: Default::default(),
+        config_variant: None,
       }
     );
     assert_eq!(
@@ -199,6 +391,7 @@ mod tests {
       Spec {
         file_name: "test.ts".into(),
         file_text: "test\n".into(),
+        range: None,
         expected_text: "test\n".into(),
         message: "message 3 (trace)".into(),
         is_only: true,
@@ -206,6 +399,7 @@ mod tests {
         skip: false,
         skip_format_twice: false,
         config: Default::default(),
+        config_variant: None,
       }
     );
   }
@@ -213,7 +407,7 @@ mod tests {
   #[test]
   fn it_parses_with_file_name() {
     let specs = parse_specs(
-      vec!["-- asdf.ts --", "== message ==", "start", "[expect]", "expected"].join("\n"),
+      ["-- asdf.ts --", "== message ==", "start", "[expect]", "expected"].join("\n"),
       &ParseSpecOptions { default_file_name: "test.ts" },
     );
 
@@ -223,6 +417,7 @@ mod tests {
       Spec {
         file_name: "asdf.ts".into(),
         file_text: "start".into(),
+        range: None,
         expected_text: "expected".into(),
         message: "message".into(),
         is_only: false,
@@ -230,6 +425,7 @@ mod tests {
         skip: false,
         skip_format_twice: false,
         config: Default::default(),
+        config_variant: None,
       }
     );
   }
@@ -237,7 +433,7 @@ mod tests {
   #[test]
   fn it_parses_with_config() {
     let specs = parse_specs(
-      vec![
+      [
         "-- asdf.ts --",
         "~~ test.test: other, lineWidth: 40 ~~",
         "== message ==",
@@ -255,6 +451,7 @@ mod tests {
       Spec {
         file_name: "asdf.ts".into(),
         file_text: "start".into(),
+        range: None,
         expected_text: "expected".into(),
         message: "message".into(),
         is_only: false,
@@ -265,6 +462,7 @@ mod tests {
           .iter()
           .cloned()
           .collect(),
+        config_variant: None,
       }
     );
   }
@@ -298,6 +496,7 @@ mod tests {
       Spec {
         file_name: "test.md".into(),
         file_text: "start\nmultiple\n".into(),
+        range: None,
         expected_text: "expected\nmultiple\n".into(),
         message: "message 1".into(),
         is_only: false,
@@ -305,6 +504,7 @@ mod tests {
         skip: false,
         skip_format_twice: false,
         config: Default::default(),
+        config_variant: None,
       }
     );
     assert_eq!(
@@ -312,6 +512,7 @@ mod tests {
       Spec {
         file_name: "test.md".into(),
         file_text: "start2\n".into(),
+        range: None,
         expected_text: "expected2\n".into(),
         message: "message 2 (only) (skip) (skip-format-twice)".into(),
         is_only: true,
@@ -319,7 +520,204 @@ mod tests {
         skip: true,
         skip_format_twice: true,
         config: Default::default(),
+        config_variant: None,
       }
     );
   }
+
+  #[test]
+  fn it_parses_range() {
+    let specs = parse_specs(
+      [
+        "== message ==",
+        "let a = 1;",
+        "\u{301A}let  b =  2;\u{301B}",
+        "",
+        "[expect]",
+        "let a = 1;",
+        "let b = 2;",
+        "",
+      ]
+      .join("\n"),
+      &ParseSpecOptions { default_file_name: "test.ts" },
+    );
+
+    assert_eq!(specs.len(), 1);
+    assert_eq!(specs[0].file_text, "let a = 1;\nlet  b =  2;\n");
+    assert_eq!(specs[0].range, Some(11..23));
+    assert_eq!(specs[0].expected_text, "let a = 1;\nlet b = 2;\n");
+  }
+
+  #[test]
+  #[should_panic(expected = "Specs must have one range start")]
+  fn it_panics_range_without_end() {
+    parse_specs(
+      ["== message ==", "\u{301A}let a = 1;", "[expect]", "let a = 1;"].join("\n"),
+      &ParseSpecOptions { default_file_name: "test.ts" },
+    );
+  }
+
+  #[test]
+  fn it_replaces_expected_texts() {
+    let file_text = [
+      "-- file.md --",
+      "~~ lineWidth: 40 ~~",
+      "!! message 1 !!",
+      "start",
+      "[expect]",
+      "start",
+      "",
+      "!! message 2 !!",
+      "start",
+      "",
+      "[expect]",
+      "expected",
+      "",
+      "!! message 3 !!",
+      "start",
+      "[expect]",
+      "other",
+    ]
+    .join("\r\n");
+    let options = ParseSpecOptions { default_file_name: "test.ts" };
+    let expected_texts = HashMap::from([(1, "start\nformatted\n".to_string()), (2, "start".to_string())]);
+    let output = replace_spec_expected_texts(&file_text, &options, &expected_texts);
+
+    assert_eq!(
+      output,
+      [
+        "-- file.md --",
+        "~~ lineWidth: 40 ~~",
+        "!! message 1 !!",
+        "start",
+        "[expect]",
+        "start",
+        "",
+        "!! message 2 !!",
+        "start",
+        "",
+        "[expect]",
+        "start",
+        "formatted",
+        "",
+        "!! message 3 !!",
+        "start",
+        "[expect]",
+        "start",
+      ]
+      .join("\r\n")
+    );
+    let specs = parse_specs(output, &options);
+    assert_eq!(specs[1].expected_text, "start\nformatted\n");
+    assert_eq!(specs[2].expected_text, "start");
+  }
+
+  #[test]
+  fn it_parses_config_variants() {
+    let specs = parse_specs(
+      [
+        "~~ lineWidth: 40 ~~",
+        "~~ (spaces) useTabs: false ~~",
+        "~~ (tabs) useTabs: true, lineWidth: 80 ~~",
+        "== message ==",
+        "start",
+        "[expect]",
+        "  expected",
+        "",
+        "[expect (tabs)]",
+        "\texpected",
+        "",
+      ]
+      .join("\n"),
+      &ParseSpecOptions { default_file_name: "test.ts" },
+    );
+
+    assert_eq!(specs.len(), 2);
+    assert_eq!(specs[0].name(), "message (spaces)");
+    assert_eq!(specs[0].file_text, "start");
+    assert_eq!(specs[0].expected_text, "  expected\n");
+    assert_eq!(
+      specs[0].config,
+      [("lineWidth".into(), 40.into()), ("useTabs".into(), false.into())].into_iter().collect()
+    );
+    assert_eq!(specs[1].name(), "message (tabs)");
+    assert_eq!(specs[1].expected_text, "\texpected\n");
+    assert_eq!(
+      specs[1].config,
+      [("lineWidth".into(), 80.into()), ("useTabs".into(), true.into())].into_iter().collect()
+    );
+  }
+
+  #[test]
+  #[should_panic(expected = "Could not find config variant 'other' used in: == message ==")]
+  fn it_panics_unknown_config_variant() {
+    parse_specs(
+      [
+        "~~ (tabs) useTabs: true ~~",
+        "== message ==",
+        "start",
+        "[expect]",
+        "start",
+        "[expect (other)]",
+        "start",
+      ]
+      .join("\n"),
+      &ParseSpecOptions { default_file_name: "test.ts" },
+    );
+  }
+
+  #[test]
+  fn it_replaces_config_variant_expected_texts() {
+    let file_text = [
+      "~~ (spaces) useTabs: false ~~",
+      "~~ (tabs) useTabs: true ~~",
+      "== message 1 ==",
+      "start",
+      "[expect]",
+      "start",
+      "",
+      "== message 2 ==",
+      "start",
+      "[expect]",
+      "start",
+      "",
+      "[expect (tabs)]",
+      "other",
+      "",
+    ]
+    .join("\n");
+    let options = ParseSpecOptions { default_file_name: "test.ts" };
+    let expected_texts = HashMap::from([(1, "\tstart\n".to_string()), (3, "\tstart\n".to_string())]);
+    let output = replace_spec_expected_texts(&file_text, &options, &expected_texts);
+
+    assert_eq!(
+      output,
+      [
+        "~~ (spaces) useTabs: false ~~",
+        "~~ (tabs) useTabs: true ~~",
+        "== message 1 ==",
+        "start",
+        "[expect]",
+        "start",
+        "",
+        "[expect (tabs)]",
+        "\tstart",
+        "",
+        "== message 2 ==",
+        "start",
+        "[expect]",
+        "start",
+        "",
+        "[expect (tabs)]",
+        "\tstart",
+        "",
+      ]
+      .join("\n")
+    );
+    let specs = parse_specs(output, &options);
+    assert_eq!(specs[0].expected_text, "start\n");
+    assert_eq!(specs[1].expected_text, "\tstart\n");
+    assert_eq!(specs[2].expected_text, "start\n");
+    assert_eq!(specs[3].expected_text, "\tstart\n");
+  }
 }
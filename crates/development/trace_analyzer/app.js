@@ -110,6 +110,8 @@ function getNodeHoverText(node) {
       return `Anchor: ${printItem.content.name} (${node.printNodeId})`;
     case "conditionReevaluation":
       return `Condition reevaluation: ${printItem.content.name} (${printItem.content.conditionId}) (${node.printNodeId})`;
+    case "alignmentAnchor":
+      return `Alignment anchor: ${printItem.content.name} (${printItem.content.alignmentGroupId}) (${node.printNodeId})`;
   }
 }
 
@@ -430,6 +432,16 @@ function createInfoArea() {
   mainElement.appendChild(currentTimeLabel);
   const timeSpan = document.createElement("span");
   mainElement.appendChild(timeSpan);
+  const positionLabel = document.createElement("label");
+  positionLabel.textContent = "Position:";
+  mainElement.appendChild(positionLabel);
+  const positionSpan = document.createElement("span");
+  mainElement.appendChild(positionSpan);
+  const resolutionLabel = document.createElement("label");
+  resolutionLabel.textContent = "Resolved:";
+  mainElement.appendChild(resolutionLabel);
+  const resolutionSpan = document.createElement("span");
+  mainElement.appendChild(resolutionSpan);
 
   return {
     element: mainElement,
@@ -437,6 +449,8 @@ function createInfoArea() {
     setTraceIndex(index) {
       const trace = traceResult.traces[index];
       timeSpan.textContent = formatNanos(trace.nanos);
+      positionSpan.textContent = `${trace.writerInfo.lineNumber}:${trace.writerInfo.columnNumber} (indent ${trace.writerInfo.indentLevel})`;
+      resolutionSpan.textContent = trace.resolution == null ? "-" : `${trace.resolution.content ?? "unresolved"}`;
     },
   };
 }
@@ -622,6 +636,8 @@ function getNodeColor(node) {
       return "pink";
     case "conditionReevaluation":
       return "purple";
+    case "alignmentAnchor":
+      return "teal";
   }
 }
 
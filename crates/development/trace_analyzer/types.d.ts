@@ -22,6 +22,29 @@ export interface Trace {
   nanos: number;
   printNodeId: number;
   writerNodeId: number | undefined;
+  writerInfo: WriterInfo;
+  resolution: TraceResolution | undefined;
+}
+
+export interface WriterInfo {
+  lineNumber: number;
+  columnNumber: number;
+  indentLevel: number;
+  lineStartIndentLevel: number;
+  indentWidth: number;
+  expectNewlineNext: boolean;
+}
+
+export type TraceResolution = ConditionResolution | InfoResolution;
+
+export interface ConditionResolution {
+  kind: "condition";
+  content: boolean | null;
+}
+
+export interface InfoResolution {
+  kind: "info";
+  content: number | boolean;
 }
 
 export interface WriterNode {
@@ -36,7 +59,7 @@ export interface PrintNode {
   printItem: PrintItem;
 }
 
-export type PrintItem = InfoItem | SignalItem | StringItem | ConditionItem | RcPathItem | AnchorItem | ConditionReevaluationItem;
+export type PrintItem = InfoItem | SignalItem | StringItem | ConditionItem | RcPathItem | AnchorItem | ConditionReevaluationItem | AlignmentAnchorItem;
 
 export interface InfoItem {
   kind: "info";
@@ -101,7 +124,9 @@ export type Signal =
   | "FinishIgnoringIndent"
   | "StartForceNoNewLines"
   | "FinishForceNoNewLines"
-  | "SpaceIfNotTrailing";
+  | "SpaceIfNotTrailing"
+  | "StartAlignment"
+  | "FinishAlignment";
 
 export interface StringItem {
   kind: "string";
@@ -142,6 +167,16 @@ export interface ConditionReevaluation {
   name: string;
 }
 
+export interface AlignmentAnchorItem {
+  kind: "alignmentAnchor";
+  content: AlignmentAnchor;
+}
+
+export interface AlignmentAnchor {
+  alignmentGroupId: number;
+  name: string;
+}
+
 export interface RcPathItem {
   kind: "rcPath";
   content: number;
@@ -11,6 +11,10 @@ pub struct CliArgs {
   pub log_level: LogLevel,
   pub plugins: Vec<String>,
   pub config: Option<String>,
+  /// Errors instead of accessing the network (ex. downloading plugins).
+  pub no_network: bool,
+  /// Trusts new process plugins without prompting.
+  pub trust_process_plugins: bool,
 }
 
 impl CliArgs {
@@ -21,6 +25,20 @@ impl CliArgs {
       log_level: LogLevel::Info,
       plugins: vec![],
       config: None,
+      no_network: false,
+      trust_process_plugins: false,
+    }
+  }
+
+  /// Args for only resolving the configuration outside of a command (ex. in the engine).
+  pub fn for_config(config: Option<String>, plugins: Vec<String>) -> Self {
+    Self {
+      sub_command: SubCommand::Help(String::new()),
+      log_level: LogLevel::Info,
+      plugins,
+      config,
+      no_network: false,
+      trust_process_plugins: false,
     }
   }
 
@@ -28,7 +46,12 @@ impl CliArgs {
     // these output json or other text that's read by stdout
     matches!(
       self.sub_command,
-      SubCommand::StdInFmt(..) | SubCommand::EditorInfo | SubCommand::OutputResolvedConfig | SubCommand::Completions(..)
+      SubCommand::StdInFmt(..)
+        | SubCommand::EditorInfo
+        | SubCommand::OutputResolvedConfig
+        | SubCommand::Completions(..)
+        | SubCommand::Config(ConfigSubCommand::Diagnostics { json: true })
+        | SubCommand::Check(CheckSubCommand { json: true, .. })
     )
   }
 
@@ -38,6 +61,8 @@ impl CliArgs {
       log_level: LogLevel::Info,
       config: None,
       plugins: Vec::new(),
+      no_network: false,
+      trust_process_plugins: false,
     }
   }
 }
@@ -48,6 +73,7 @@ pub enum SubCommand {
   Fmt(FmtSubCommand),
   Config(ConfigSubCommand),
   ClearCache,
+  Cache(CacheSubCommand),
   OutputFilePaths(OutputFilePathsSubCommand),
   OutputResolvedConfig,
   OutputFormatTimes(OutputFormatTimesSubCommand),
@@ -56,7 +82,8 @@ pub enum SubCommand {
   Help(String),
   EditorInfo,
   EditorService(EditorServiceSubCommand),
-  Lsp,
+  Daemon(DaemonSubCommand),
+  Lsp(LspSubCommand),
   StdInFmt(StdInFmtSubCommand),
   Completions(clap_complete::Shell),
   Upgrade,
@@ -73,6 +100,16 @@ impl SubCommand {
     }
   }
 
+  /// Gets the daemon to forward the command to, if any.
+  pub fn daemon(&self) -> Option<&DaemonClientArgs> {
+    match self {
+      SubCommand::Check(a) => a.daemon.as_ref(),
+      SubCommand::Fmt(a) => a.daemon.as_ref(),
+      SubCommand::StdInFmt(a) => a.daemon.as_ref(),
+      _ => None,
+    }
+  }
+
   pub fn file_patterns(&self) -> Option<&FilePatternArgs> {
     match self {
       SubCommand::Check(a) => Some(&a.patterns),
@@ -82,13 +119,15 @@ impl SubCommand {
       SubCommand::OutputFormatTimes(a) => Some(&a.patterns),
       SubCommand::Config(_)
       | SubCommand::ClearCache
+      | SubCommand::Cache(_)
       | SubCommand::OutputResolvedConfig
       | SubCommand::Version
       | SubCommand::License
       | SubCommand::Help(_)
-      | SubCommand::Lsp
+      | SubCommand::Lsp(_)
       | SubCommand::EditorInfo
       | SubCommand::EditorService(_)
+      | SubCommand::Daemon(_)
       | SubCommand::Completions(_)
       | SubCommand::Upgrade => None,
       #[cfg(target_os = "windows")]
@@ -101,9 +140,16 @@ impl SubCommand {
 pub struct CheckSubCommand {
   pub patterns: FilePatternArgs,
   pub incremental: Option<bool>,
+  pub cache_file: Option<String>,
+  pub slow_format: SlowFormatArgs,
+  pub profile: Option<String>,
+  pub otlp_endpoint: Option<String>,
   pub list_different: bool,
+  pub json: bool,
   pub allow_no_files: bool,
   pub only_staged: bool,
+  pub no_lock: bool,
+  pub daemon: Option<DaemonClientArgs>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -111,9 +157,16 @@ pub struct FmtSubCommand {
   pub diff: bool,
   pub patterns: FilePatternArgs,
   pub incremental: Option<bool>,
+  pub cache_file: Option<String>,
+  pub slow_format: SlowFormatArgs,
+  pub profile: Option<String>,
+  pub otlp_endpoint: Option<String>,
+  pub read_only_files: Option<ReadOnlyFilesPolicy>,
   pub enable_stable_format: bool,
   pub allow_no_files: bool,
   pub only_staged: bool,
+  pub no_lock: bool,
+  pub daemon: Option<DaemonClientArgs>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -121,6 +174,13 @@ pub enum ConfigSubCommand {
   Init,
   Update { yes: bool },
   Add(Option<String>),
+  Diagnostics { json: bool },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CacheSubCommand {
+  IncrementalStatus,
+  IncrementalInvalidate(Vec<String>),
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -136,7 +196,30 @@ pub struct OutputFormatTimesSubCommand {
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct EditorServiceSubCommand {
-  pub parent_pid: u32,
+  pub parent_pid: Option<u32>,
+  /// Listen for clients on this unix socket or windows named pipe instead of using stdio.
+  pub socket: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DaemonSubCommand {
+  /// Listen for clients on this unix socket or windows named pipe instead of the default one.
+  pub socket: Option<String>,
+}
+
+/// Forwards a command to a running daemon.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DaemonClientArgs {
+  /// Connect to this unix socket or windows named pipe instead of the default one.
+  pub socket: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct LspSubCommand {
+  /// Listen for a client on this TCP port instead of using stdio.
+  pub port: Option<u16>,
+  /// Connect to the client's pipe (unix socket path or windows named pipe) instead of using stdio.
+  pub pipe: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -144,6 +227,7 @@ pub struct StdInFmtSubCommand {
   pub file_name_or_path: String,
   pub file_bytes: Vec<u8>,
   pub patterns: FilePatternArgs,
+  pub daemon: Option<DaemonClientArgs>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -165,6 +249,25 @@ pub struct FilePatternArgs {
   pub only_staged: bool,
 }
 
+/// Overrides of the slow format configuration.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SlowFormatArgs {
+  pub warning_secs: Option<u32>,
+  pub timeout_secs: Option<u32>,
+}
+
+/// How to handle formatting a file that's read-only.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ReadOnlyFilesPolicy {
+  /// Report the file as an error.
+  #[default]
+  Error,
+  /// Don't format the file and output a warning.
+  Skip,
+  /// Temporarily make the file writable, then restore its permissions.
+  MakeWritable,
+}
+
 #[derive(Debug, Error)]
 #[error(transparent)]
 pub struct ParseArgsError(#[from] anyhow::Error);
@@ -203,12 +306,23 @@ fn inner_parse_args<TStdInReader: StdInReader>(args: Vec<String>, std_in_reader:
           file_name_or_path,
           file_bytes: std_in_reader.read()?,
           patterns: parse_file_patterns(matches)?,
+          daemon: parse_daemon_client_args(matches),
         })
       } else {
         SubCommand::Fmt(FmtSubCommand {
           diff: matches.get_flag("diff"),
           patterns: parse_file_patterns(matches)?,
           incremental: parse_incremental(matches),
+          cache_file: matches.get_one::<String>("cache-file").map(ToOwned::to_owned),
+          slow_format: parse_slow_format(matches),
+          profile: matches.get_one::<String>("profile").map(ToOwned::to_owned),
+          otlp_endpoint: matches.get_one::<String>("otlp-endpoint").map(ToOwned::to_owned),
+          read_only_files: matches.get_one::<String>("read-only-files").map(|value| match value.as_str() {
+            "error" => ReadOnlyFilesPolicy::Error,
+            "skip" => ReadOnlyFilesPolicy::Skip,
+            "make-writable" => ReadOnlyFilesPolicy::MakeWritable,
+            _ => unreachable!(),
+          }),
           enable_stable_format: !matches.get_flag("skip-stable-format"),
           allow_no_files: if matches.get_flag("staged") {
             true
@@ -216,26 +330,44 @@ fn inner_parse_args<TStdInReader: StdInReader>(args: Vec<String>, std_in_reader:
             matches.get_flag("allow-no-files")
           },
           only_staged: matches.get_flag("staged"),
+          no_lock: matches.get_flag("no-lock"),
+          daemon: parse_daemon_client_args(matches),
         })
       }
     }
     ("check", matches) => SubCommand::Check(CheckSubCommand {
       patterns: parse_file_patterns(matches)?,
       incremental: parse_incremental(matches),
+      cache_file: matches.get_one::<String>("cache-file").map(ToOwned::to_owned),
+      slow_format: parse_slow_format(matches),
+      profile: matches.get_one::<String>("profile").map(ToOwned::to_owned),
+      otlp_endpoint: matches.get_one::<String>("otlp-endpoint").map(ToOwned::to_owned),
       only_staged: matches.get_flag("staged"),
       list_different: matches.get_flag("list-different"),
+      json: matches.get_flag("json"),
       allow_no_files: matches.get_flag("allow-no-files"),
+      no_lock: matches.get_flag("no-lock"),
+      daemon: parse_daemon_client_args(matches),
     }),
     ("init", _) => SubCommand::Config(ConfigSubCommand::Init),
     ("config", matches) => SubCommand::Config(match matches.subcommand().unwrap() {
       ("init", _) => ConfigSubCommand::Init,
       ("add", matches) => ConfigSubCommand::Add(matches.get_one::<String>("url-or-plugin-name").map(String::from)),
-      ("update", matches) => ConfigSubCommand::Update {
-        yes: *matches.get_one::<bool>("yes").unwrap(),
+      ("update", matches) => ConfigSubCommand::Update { yes: matches.get_flag("yes") },
+      ("diagnostics", matches) => ConfigSubCommand::Diagnostics {
+        json: *matches.get_one::<bool>("json").unwrap(),
       },
       _ => unreachable!(),
     }),
     ("clear-cache", _) => SubCommand::ClearCache,
+    ("cache", matches) => SubCommand::Cache(match matches.subcommand().unwrap() {
+      ("incremental", matches) => match matches.subcommand().unwrap() {
+        ("status", _) => CacheSubCommand::IncrementalStatus,
+        ("invalidate", matches) => CacheSubCommand::IncrementalInvalidate(maybe_values_to_vec(matches.get_many("files"))),
+        _ => unreachable!(),
+      },
+      _ => unreachable!(),
+    }),
     ("output-file-paths", matches) => SubCommand::OutputFilePaths(OutputFilePathsSubCommand {
       patterns: parse_file_patterns(matches)?,
     }),
@@ -248,9 +380,16 @@ fn inner_parse_args<TStdInReader: StdInReader>(args: Vec<String>, std_in_reader:
     ("license", _) => SubCommand::License,
     ("editor-info", _) => SubCommand::EditorInfo,
     ("editor-service", matches) => SubCommand::EditorService(EditorServiceSubCommand {
-      parent_pid: matches.get_one::<String>("parent-pid").and_then(|v| v.parse::<u32>().ok()).unwrap(),
+      parent_pid: matches.get_one::<String>("parent-pid").and_then(|v| v.parse::<u32>().ok()),
+      socket: matches.get_one::<String>("socket").map(String::from),
+    }),
+    ("daemon", matches) => SubCommand::Daemon(DaemonSubCommand {
+      socket: matches.get_one::<String>("socket").map(String::from),
+    }),
+    ("lsp", matches) => SubCommand::Lsp(LspSubCommand {
+      port: matches.get_one::<u16>("port").copied(),
+      pipe: matches.get_one::<String>("pipe").map(String::from),
     }),
-    ("lsp", _) => SubCommand::Lsp,
     ("completions", matches) => SubCommand::Completions(matches.get_one::<clap_complete::Shell>("shell").unwrap().to_owned()),
     ("upgrade", _) => SubCommand::Upgrade,
     #[cfg(target_os = "windows")]
@@ -282,6 +421,8 @@ fn inner_parse_args<TStdInReader: StdInReader>(args: Vec<String>, std_in_reader:
     },
     config: matches.get_one::<String>("config").map(String::from),
     plugins: maybe_values_to_vec(matches.get_many("plugins")),
+    no_network: matches.get_flag("no-network"),
+    trust_process_plugins: matches.get_flag("yes"),
   })
 }
 
@@ -303,6 +444,13 @@ fn parse_file_patterns(matches: &ArgMatches) -> Result<FilePatternArgs> {
   })
 }
 
+fn parse_slow_format(matches: &ArgMatches) -> SlowFormatArgs {
+  SlowFormatArgs {
+    warning_secs: matches.get_one::<u32>("slow-format-warning-seconds").copied(),
+    timeout_secs: matches.get_one::<u32>("format-timeout-seconds").copied(),
+  }
+}
+
 fn parse_incremental(matches: &ArgMatches) -> Option<bool> {
   if let Some(incremental) = matches.get_one::<String>("incremental") {
     Some(incremental != "false")
@@ -313,6 +461,16 @@ fn parse_incremental(matches: &ArgMatches) -> Option<bool> {
   }
 }
 
+fn parse_daemon_client_args(matches: &ArgMatches) -> Option<DaemonClientArgs> {
+  if matches.contains_id("daemon") {
+    Some(DaemonClientArgs {
+      socket: matches.get_one::<String>("daemon").map(String::from),
+    })
+  } else {
+    None
+  }
+}
+
 fn maybe_values_to_vec(values: Option<clap::parser::ValuesRef<String>>) -> Vec<String> {
   values.map(values_to_vec).unwrap_or_default()
 }
@@ -391,16 +549,20 @@ OPTIONS:
 {options}
 
 ENVIRONMENT VARIABLES:
-  DPRINT_CACHE_DIR     Directory to store the dprint cache. Note that this
-                       directory may be periodically deleted by the CLI.
-  DPRINT_MAX_THREADS   Limit the number of threads dprint uses for
-                       formatting (ex. DPRINT_MAX_THREADS=4).
-  DPRINT_CERT          Load certificate authority from PEM encoded file.
-  DPRINT_TLS_CA_STORE  Comma-separated list of order dependent certificate stores.
-                       Possible values: "mozilla" and "system".
-                       Defaults to "mozilla,system".
-  HTTPS_PROXY          Proxy to use when downloading plugins or configuration
-                       files (set HTTP_PROXY for HTTP).{after-help}"#)
+  DPRINT_CACHE_DIR      Directory to store the dprint cache. Note that this
+                        directory may be periodically deleted by the CLI.
+  DPRINT_MAX_THREADS    Limit the number of threads dprint uses for
+                        formatting (ex. DPRINT_MAX_THREADS=4).
+  DPRINT_CERT           Load certificate authority from PEM encoded file.
+  DPRINT_TLS_CA_STORE   Comma-separated list of order dependent certificate stores.
+                        Possible values: "mozilla" and "system".
+                        Defaults to "mozilla,system".
+  DPRINT_NO_NETWORK     Set to 1 to error instead of accessing the network
+                        (same as --no-network).
+  DPRINT_TRUST_PLUGINS  Set to 1 to trust new process plugins without prompting
+                        (same as --yes).
+  HTTPS_PROXY           Proxy to use when downloading plugins or configuration
+                        files (set HTTP_PROXY for HTTP).{after-help}"#)
     .after_help(
             r#"GETTING STARTED:
   1. Navigate to the root directory of a code repository.
@@ -434,6 +596,10 @@ EXAMPLES:
         .about("Formats the source files and writes the result to the file system.")
         .add_resolve_file_path_args()
         .add_incremental_arg()
+        .add_cache_file_arg()
+        .add_slow_format_args()
+        .add_profile_arg()
+        .add_otlp_endpoint_arg()
         .arg(
           Arg::new("stdin")
             .long("stdin")
@@ -449,8 +615,18 @@ EXAMPLES:
             .num_args(0)
             .required(false)
         )
+        .arg(
+          Arg::new("read-only-files")
+            .long("read-only-files")
+            .value_name("policy")
+            .help("How to handle formatting read-only files. This may alternatively be specified in the configuration file. [default: error]")
+            .value_parser(["error", "skip", "make-writable"])
+            .num_args(1)
+        )
         .add_only_staged_arg()
         .add_allow_no_files_arg()
+        .add_no_lock_arg()
+        .add_daemon_arg()
         .arg(
           Arg::new("skip-stable-format")
             .long("skip-stable-format")
@@ -466,14 +642,27 @@ EXAMPLES:
         .about("Checks for any files that haven't been formatted.")
         .add_resolve_file_path_args()
         .add_incremental_arg()
+        .add_cache_file_arg()
+        .add_slow_format_args()
+        .add_profile_arg()
+        .add_otlp_endpoint_arg()
         .add_allow_no_files_arg()
         .add_only_staged_arg()
+        .add_no_lock_arg()
+        .add_daemon_arg()
         .arg(
           Arg::new("list-different")
             .long("list-different")
             .help("Only outputs file paths that aren't formatted and doesn't output diffs.")
             .num_args(0)
         )
+        .arg(
+          Arg::new("json")
+            .long("json")
+            .help("Outputs the files that aren't formatted along with the edits to format them as JSON to stdout.")
+            .num_args(0)
+            .conflicts_with("list-different")
+        )
     )
     .subcommand(
       Command::new("config")
@@ -486,7 +675,6 @@ EXAMPLES:
         .subcommand(
           Command::new("update")
             .about("Updates the plugins in the configuration file.")
-            .arg(Arg::new("yes").help("Upgrade process plugins without prompting to confirm checksums.").short('y').long("yes").action(clap::ArgAction::SetTrue))
         )
         .subcommand(
           Command::new("add")
@@ -497,6 +685,11 @@ EXAMPLES:
                 .num_args(1)
           )
         )
+        .subcommand(
+          Command::new("diagnostics")
+            .about("Outputs the diagnostics for the global and plugin configuration.")
+            .arg(Arg::new("json").help("Outputs the diagnostics as JSON to stdout.").long("json").action(clap::ArgAction::SetTrue))
+        )
     )
     .subcommand(
       Command::new("output-file-paths")
@@ -519,6 +712,29 @@ EXAMPLES:
       Command::new("clear-cache")
         .about("Deletes the plugin cache directory.")
     )
+    .subcommand(
+      Command::new("cache")
+        .about("Functionality related to the incremental formatting cache.")
+        .subcommand_required(true)
+        .subcommand(
+          Command::new("incremental")
+            .about("Functionality related to the incremental formatting cache of the configuration file.")
+            .subcommand_required(true)
+            .subcommand(
+              Command::new("status")
+                .about("Outputs how many formatted files are tracked, the cache hits of the last runs, and why the cache is stale.")
+            )
+            .subcommand(
+              Command::new("invalidate")
+                .about("Removes files from the cache so they're formatted on the next run. Removes all files when none are provided.")
+                .arg(
+                  Arg::new("files")
+                    .help("List of file paths to remove from the cache.")
+                    .num_args(1..)
+                )
+            )
+        )
+    )
     .subcommand(
       Command::new("upgrade")
         .about("Upgrades the dprint executable.")
@@ -545,13 +761,43 @@ EXAMPLES:
         .arg(
           Arg::new("parent-pid")
             .long("parent-pid")
-            .required(true)
+            .required_unless_present("socket")
+            .num_args(1)
+        )
+        .arg(
+          Arg::new("socket")
+            .long("socket")
+            .help("Listens for clients on the provided unix socket path or windows named pipe instead of communicating over stdio.")
+            .num_args(1)
+        )
+    )
+    .subcommand(
+      Command::new("daemon")
+        .about("Starts a daemon that keeps plugins loaded between `fmt` and `check` commands run with `--daemon`.")
+        .arg(
+          Arg::new("socket")
+            .long("socket")
+            .help("Listens for clients on the provided unix socket path or windows named pipe instead of the default one.")
             .num_args(1)
         )
     )
     .subcommand(
       Command::new("lsp")
       .about("Starts up a language server for formatting files.")
+      .arg(
+        Arg::new("port")
+          .long("port")
+          .help("Listens for a client on the provided TCP port instead of communicating over stdio.")
+          .value_parser(clap::value_parser!(u16))
+          .num_args(1)
+      )
+      .arg(
+        Arg::new("pipe")
+          .long("pipe")
+          .help("Connects to the client's pipe (a unix socket path or a windows named pipe) instead of communicating over stdio.")
+          .conflicts_with("port")
+          .num_args(1)
+      )
     )
     .arg(
       Arg::new("config")
@@ -578,6 +824,21 @@ EXAMPLES:
         .default_value("info")
         .global(true),
     )
+    .arg(
+      Arg::new("no-network")
+        .long("no-network")
+        .help("Errors instead of accessing the network (ex. downloading plugins).")
+        .global(true)
+        .num_args(0)
+    )
+    .arg(
+      Arg::new("yes")
+        .short('y')
+        .long("yes")
+        .help("Trusts new process plugins and upgrades them without prompting (ex. on CI).")
+        .global(true)
+        .num_args(0)
+    )
     .arg(
       Arg::new("verbose")
         .long("verbose")
@@ -604,8 +865,14 @@ EXAMPLES:
 trait ClapExtensions {
   fn add_resolve_file_path_args(self) -> Self;
   fn add_incremental_arg(self) -> Self;
+  fn add_cache_file_arg(self) -> Self;
+  fn add_slow_format_args(self) -> Self;
+  fn add_profile_arg(self) -> Self;
+  fn add_otlp_endpoint_arg(self) -> Self;
   fn add_allow_no_files_arg(self) -> Self;
   fn add_only_staged_arg(self) -> Self;
+  fn add_no_lock_arg(self) -> Self;
+  fn add_daemon_arg(self) -> Self;
 }
 
 impl ClapExtensions for clap::Command {
@@ -658,6 +925,60 @@ impl ClapExtensions for clap::Command {
     )
   }
 
+  fn add_cache_file_arg(self) -> Self {
+    use clap::Arg;
+    self.arg(
+      Arg::new("cache-file")
+        .long("cache-file")
+        .value_name("path")
+        .help("Path to a file that caches which file contents are known to be formatted so they are skipped in future runs. Unlike the incremental file, this may be stored anywhere (ex. persisted between CI runs).")
+        .num_args(1),
+    )
+  }
+
+  fn add_slow_format_args(self) -> Self {
+    use clap::Arg;
+    self
+      .arg(
+        Arg::new("slow-format-warning-seconds")
+          .long("slow-format-warning-seconds")
+          .value_name("seconds")
+          .help("Seconds after which to warn about a file that's still being formatted. This may alternatively be specified in the configuration file. [default: 10]")
+          .value_parser(clap::value_parser!(u32).range(1..))
+          .num_args(1),
+      )
+      .arg(
+        Arg::new("format-timeout-seconds")
+          .long("format-timeout-seconds")
+          .value_name("seconds")
+          .help("Seconds after which formatting a file is cancelled and the file is skipped and reported. This may alternatively be specified in the configuration file.")
+          .value_parser(clap::value_parser!(u32).range(1..))
+          .num_args(1),
+      )
+  }
+
+  fn add_profile_arg(self) -> Self {
+    use clap::Arg;
+    self.arg(
+      Arg::new("profile")
+        .long("profile")
+        .value_name("path")
+        .help("Writes a profile of the run to the provided path in the Chrome trace event format, which can be viewed in chrome://tracing, Perfetto, or speedscope. Attach this to performance issues.")
+        .num_args(1),
+    )
+  }
+
+  fn add_otlp_endpoint_arg(self) -> Self {
+    use clap::Arg;
+    self.arg(
+      Arg::new("otlp-endpoint")
+        .long("otlp-endpoint")
+        .value_name("url")
+        .help("Exports a trace and metrics of the run (ex. files formatted, plugin durations, and cache hit rates) to the provided OpenTelemetry collector url using OTLP/HTTP.")
+        .num_args(1),
+    )
+  }
+
   fn add_allow_no_files_arg(self) -> Self {
     use clap::Arg;
     self.arg(
@@ -679,6 +1000,29 @@ impl ClapExtensions for clap::Command {
         .required(false),
     )
   }
+
+  fn add_no_lock_arg(self) -> Self {
+    use clap::Arg;
+    self.arg(
+      Arg::new("no-lock")
+        .long("no-lock")
+        .help("Doesn't wait for other dprint processes using the same configuration to finish before formatting the files.")
+        .num_args(0)
+        .required(false),
+    )
+  }
+
+  fn add_daemon_arg(self) -> Self {
+    use clap::Arg;
+    self.arg(
+      Arg::new("daemon")
+        .long("daemon")
+        .value_name("socket")
+        .help("Runs the command in the daemon started by `dprint daemon`, optionally at the provided unix socket path or windows named pipe. Falls back to running the command in this process when no daemon is running.")
+        .num_args(0..=1)
+        .require_equals(true),
+    )
+  }
 }
 
 #[cfg(test)]
@@ -735,6 +1079,65 @@ mod test {
     assert_eq!(fmt_cmd.incremental, Some(true));
   }
 
+  #[test]
+  fn cache_file_arg() {
+    let fmt_cmd = parse_fmt_sub_command(vec!["fmt"]).unwrap();
+    assert_eq!(fmt_cmd.cache_file, None);
+    let fmt_cmd = parse_fmt_sub_command(vec!["fmt", "--cache-file", ".dprint-cache.json"]).unwrap();
+    assert_eq!(fmt_cmd.cache_file, Some(".dprint-cache.json".to_string()));
+  }
+
+  #[test]
+  fn slow_format_args() {
+    let fmt_cmd = parse_fmt_sub_command(vec!["fmt"]).unwrap();
+    assert_eq!(fmt_cmd.slow_format, SlowFormatArgs::default());
+    let fmt_cmd = parse_fmt_sub_command(vec!["fmt", "--slow-format-warning-seconds", "5", "--format-timeout-seconds", "60"]).unwrap();
+    assert_eq!(
+      fmt_cmd.slow_format,
+      SlowFormatArgs {
+        warning_secs: Some(5),
+        timeout_secs: Some(60),
+      }
+    );
+    assert!(parse_fmt_sub_command(vec!["fmt", "--format-timeout-seconds", "0"]).is_err());
+  }
+
+  #[test]
+  fn profile_arg() {
+    let fmt_cmd = parse_fmt_sub_command(vec!["fmt"]).unwrap();
+    assert_eq!(fmt_cmd.profile, None);
+    let fmt_cmd = parse_fmt_sub_command(vec!["fmt", "--profile", "profile.json"]).unwrap();
+    assert_eq!(fmt_cmd.profile, Some("profile.json".to_string()));
+  }
+
+  #[test]
+  fn otlp_endpoint_arg() {
+    let fmt_cmd = parse_fmt_sub_command(vec!["fmt"]).unwrap();
+    assert_eq!(fmt_cmd.otlp_endpoint, None);
+    let fmt_cmd = parse_fmt_sub_command(vec!["fmt", "--otlp-endpoint", "http://localhost:4318"]).unwrap();
+    assert_eq!(fmt_cmd.otlp_endpoint, Some("http://localhost:4318".to_string()));
+  }
+
+  #[test]
+  fn read_only_files_arg() {
+    let fmt_cmd = parse_fmt_sub_command(vec!["fmt"]).unwrap();
+    assert_eq!(fmt_cmd.read_only_files, None);
+    let fmt_cmd = parse_fmt_sub_command(vec!["fmt", "--read-only-files", "skip"]).unwrap();
+    assert_eq!(fmt_cmd.read_only_files, Some(ReadOnlyFilesPolicy::Skip));
+    let fmt_cmd = parse_fmt_sub_command(vec!["fmt", "--read-only-files", "make-writable"]).unwrap();
+    assert_eq!(fmt_cmd.read_only_files, Some(ReadOnlyFilesPolicy::MakeWritable));
+    assert!(parse_fmt_sub_command(vec!["fmt", "--read-only-files", "other"]).is_err());
+  }
+
+  #[test]
+  fn check_json_arg() {
+    let args = test_args(vec!["check"]).unwrap();
+    assert!(!args.is_stdout_machine_readable());
+    let args = test_args(vec!["check", "--json"]).unwrap();
+    assert!(args.is_stdout_machine_readable());
+    assert!(test_args(vec!["check", "--json", "--list-different"]).is_err());
+  }
+
   #[test]
   fn staged_arg() {
     let fmt_cmd = parse_fmt_sub_command(vec!["fmt"]).unwrap();
@@ -743,6 +1146,39 @@ mod test {
     assert_eq!(fmt_cmd.only_staged, true);
   }
 
+  #[test]
+  fn no_network_arg() {
+    let args = test_args(vec!["fmt"]).unwrap();
+    assert!(!args.no_network);
+    let args = test_args(vec!["fmt", "--no-network"]).unwrap();
+    assert!(args.no_network);
+    let args = test_args(vec!["--no-network", "check"]).unwrap();
+    assert!(args.no_network);
+  }
+
+  #[test]
+  fn yes_arg() {
+    let args = test_args(vec!["fmt"]).unwrap();
+    assert!(!args.trust_process_plugins);
+    let args = test_args(vec!["fmt", "--yes"]).unwrap();
+    assert!(args.trust_process_plugins);
+    let args = test_args(vec!["-y", "check"]).unwrap();
+    assert!(args.trust_process_plugins);
+    let args = test_args(vec!["config", "update", "-y"]).unwrap();
+    assert!(args.trust_process_plugins);
+    assert_eq!(args.sub_command, SubCommand::Config(ConfigSubCommand::Update { yes: true }));
+  }
+
+  #[test]
+  fn no_lock_arg() {
+    let fmt_cmd = parse_fmt_sub_command(vec!["fmt"]).unwrap();
+    assert!(!fmt_cmd.no_lock);
+    let fmt_cmd = parse_fmt_sub_command(vec!["fmt", "--no-lock"]).unwrap();
+    assert!(fmt_cmd.no_lock);
+    let args = test_args(vec!["check", "--no-lock"]).unwrap();
+    assert!(matches!(args.sub_command, SubCommand::Check(CheckSubCommand { no_lock: true, .. })));
+  }
+
   #[test]
   fn no_files_arg() {
     let fmt_cmd = parse_fmt_sub_command(vec!["fmt", "--staged"]).unwrap();
@@ -750,6 +1186,102 @@ mod test {
     assert_eq!(fmt_cmd.allow_no_files, true);
   }
 
+  #[test]
+  fn lsp_transport_args() {
+    let lsp_cmd = parse_lsp_sub_command(vec!["lsp"]).unwrap();
+    assert_eq!(lsp_cmd, LspSubCommand { port: None, pipe: None });
+    let lsp_cmd = parse_lsp_sub_command(vec!["lsp", "--port", "9257"]).unwrap();
+    assert_eq!(lsp_cmd.port, Some(9257));
+    let lsp_cmd = parse_lsp_sub_command(vec!["lsp", "--pipe", "/tmp/dprint.sock"]).unwrap();
+    assert_eq!(lsp_cmd.pipe, Some("/tmp/dprint.sock".to_string()));
+    assert!(test_args(vec!["lsp", "--port", "9257", "--pipe", "/tmp/dprint.sock"]).is_err());
+  }
+
+  #[test]
+  fn editor_service_transport_args() {
+    let cmd = parse_editor_service_sub_command(vec!["editor-service", "--parent-pid", "123"]).unwrap();
+    assert_eq!(
+      cmd,
+      EditorServiceSubCommand {
+        parent_pid: Some(123),
+        socket: None
+      }
+    );
+    let cmd = parse_editor_service_sub_command(vec!["editor-service", "--socket", "/tmp/dprint.sock"]).unwrap();
+    assert_eq!(
+      cmd,
+      EditorServiceSubCommand {
+        parent_pid: None,
+        socket: Some("/tmp/dprint.sock".to_string())
+      }
+    );
+    assert!(test_args(vec!["editor-service"]).is_err());
+  }
+
+  #[test]
+  fn cache_incremental_args() {
+    let args = test_args(vec!["cache", "incremental", "status"]).unwrap();
+    assert_eq!(args.sub_command, SubCommand::Cache(CacheSubCommand::IncrementalStatus));
+    let args = test_args(vec!["cache", "incremental", "invalidate"]).unwrap();
+    assert_eq!(args.sub_command, SubCommand::Cache(CacheSubCommand::IncrementalInvalidate(Vec::new())));
+    let args = test_args(vec!["cache", "incremental", "invalidate", "a.ts", "b.ts"]).unwrap();
+    assert_eq!(
+      args.sub_command,
+      SubCommand::Cache(CacheSubCommand::IncrementalInvalidate(vec!["a.ts".to_string(), "b.ts".to_string()]))
+    );
+    assert!(test_args(vec!["cache", "incremental"]).is_err());
+  }
+
+  #[test]
+  fn daemon_args() {
+    let args = test_args(vec!["daemon"]).unwrap();
+    assert_eq!(args.sub_command, SubCommand::Daemon(DaemonSubCommand { socket: None }));
+    let args = test_args(vec!["daemon", "--socket", "/tmp/dprint.sock"]).unwrap();
+    assert_eq!(
+      args.sub_command,
+      SubCommand::Daemon(DaemonSubCommand {
+        socket: Some("/tmp/dprint.sock".to_string())
+      })
+    );
+
+    let fmt_cmd = parse_fmt_sub_command(vec!["fmt"]).unwrap();
+    assert_eq!(fmt_cmd.daemon, None);
+    let fmt_cmd = parse_fmt_sub_command(vec!["fmt", "--daemon", "file.ts"]).unwrap();
+    assert_eq!(fmt_cmd.daemon, Some(DaemonClientArgs { socket: None }));
+    assert_eq!(fmt_cmd.patterns.include_patterns, vec!["file.ts".to_string()]);
+    let fmt_cmd = parse_fmt_sub_command(vec!["fmt", "--daemon=/tmp/dprint.sock"]).unwrap();
+    assert_eq!(
+      fmt_cmd.daemon,
+      Some(DaemonClientArgs {
+        socket: Some("/tmp/dprint.sock".to_string())
+      })
+    );
+    let args = test_args(vec!["check", "--daemon"]).unwrap();
+    assert_eq!(args.sub_command.daemon(), Some(&DaemonClientArgs { socket: None }));
+    let args = parse_args(
+      ["", "fmt", "--stdin", "ts", "--daemon"].into_iter().map(String::from).collect(),
+      TestStdInReader::from("text"),
+    )
+    .unwrap();
+    assert_eq!(args.sub_command.daemon(), Some(&DaemonClientArgs { socket: None }));
+  }
+
+  fn parse_editor_service_sub_command(args: Vec<&str>) -> Result<EditorServiceSubCommand, ParseArgsError> {
+    let args = test_args(args)?;
+    match args.sub_command {
+      SubCommand::EditorService(cmd) => Ok(cmd),
+      _ => unreachable!(),
+    }
+  }
+
+  fn parse_lsp_sub_command(args: Vec<&str>) -> Result<LspSubCommand, ParseArgsError> {
+    let args = test_args(args)?;
+    match args.sub_command {
+      SubCommand::Lsp(cmd) => Ok(cmd),
+      _ => unreachable!(),
+    }
+  }
+
   fn parse_fmt_sub_command(args: Vec<&str>) -> Result<FmtSubCommand, ParseArgsError> {
     let args = test_args(args)?;
     match args.sub_command {
@@ -0,0 +1,166 @@
+use std::rc::Rc;
+
+use anyhow::Context;
+use anyhow::Result;
+
+use crate::arg_parser::CliArgs;
+use crate::configuration::resolve_config_from_args;
+use crate::environment::Environment;
+use crate::incremental::get_incremental_file_path;
+use crate::incremental::get_incremental_file_status;
+use crate::incremental::get_incremental_state;
+use crate::incremental::invalidate_incremental_file_texts;
+use crate::incremental::IncrementalRunStats;
+use crate::plugins::PluginResolver;
+use crate::resolution::resolve_plugins_scope;
+
+pub async fn output_incremental_status<TEnvironment: Environment>(
+  args: &CliArgs,
+  environment: &TEnvironment,
+  plugin_resolver: &Rc<PluginResolver<TEnvironment>>,
+) -> Result<()> {
+  let config = Rc::new(resolve_config_from_args(args, environment).await?);
+  let plugins_scope = resolve_plugins_scope(config.clone(), environment, plugin_resolver).await?;
+  plugins_scope.ensure_no_global_config_diagnostics()?;
+
+  if config.incremental == Some(false) {
+    log_stdout_info!(environment, "Incremental formatting is disabled in the configuration file.");
+  }
+  let file_path = get_incremental_file_path(&config, environment);
+  let Some(status) = get_incremental_file_status(&file_path, &get_incremental_state(&plugins_scope), environment) else {
+    log_stdout_info!(
+      environment,
+      "No incremental cache for {}. Run `dprint fmt` or `dprint check` to create it.",
+      config.base_path.display()
+    );
+    return Ok(());
+  };
+
+  let mut text = format!("Incremental cache for {}\n", config.base_path.display());
+  text.push_str(&format!("  File: {}\n", file_path.display()));
+  text.push_str(&format!("  Tracked files: {}\n", status.tracked_file_count));
+  if status.stale_reasons.is_empty() {
+    text.push_str("  Status: Up to date\n");
+  } else {
+    text.push_str("  Status: Stale (the tracked files will be formatted on the next run)\n");
+    for reason in &status.stale_reasons {
+      text.push_str(&format!("    {}\n", reason));
+    }
+  }
+  if let Some(reset_reason) = &status.reset_reason {
+    text.push_str(&format!("  Last reset: {}\n", reset_reason));
+  }
+  if !status.runs.is_empty() {
+    text.push_str("Last runs:\n");
+    let now_secs = environment.get_time_secs();
+    for run in status.runs.iter().rev() {
+      text.push_str(&format!(
+        "  {}: {}\n",
+        get_elapsed_text(now_secs.saturating_sub(run.time_secs)),
+        get_run_stats_text(run)
+      ));
+    }
+  }
+  log_stdout_info!(environment, text.trim_end());
+  Ok(())
+}
+
+pub async fn invalidate_incremental_cache<TEnvironment: Environment>(file_paths: &[String], args: &CliArgs, environment: &TEnvironment) -> Result<()> {
+  let config = resolve_config_from_args(args, environment).await?;
+  let incremental_file_path = get_incremental_file_path(&config, environment);
+  if file_paths.is_empty() {
+    if environment.path_exists(&incremental_file_path) {
+      environment.remove_file(&incremental_file_path)?;
+    }
+    log_stdout_info!(environment, "Removed all files from the incremental cache.");
+    return Ok(());
+  }
+
+  let cwd = environment.cwd();
+  let mut file_texts = Vec::with_capacity(file_paths.len());
+  for file_path in file_paths {
+    let file_path = cwd.join(file_path);
+    let file_text = environment
+      .read_file_bytes(&file_path)
+      .with_context(|| format!("Error reading {}.", file_path.display()))?;
+    file_texts.push(file_text);
+  }
+  let removed_count = invalidate_incremental_file_texts(&incremental_file_path, file_texts.iter().map(|text| text.as_slice()), environment)?;
+  log_stdout_info!(
+    environment,
+    "Removed {} of {} file(s) from the incremental cache.",
+    removed_count,
+    file_paths.len()
+  );
+  Ok(())
+}
+
+fn get_run_stats_text(run: &IncrementalRunStats) -> String {
+  let total = run.hits + run.misses;
+  if total == 0 {
+    return "no files".to_string();
+  }
+  format!("{} hit(s), {} miss(es) ({}% hit rate)", run.hits, run.misses, run.hits * 100 / total)
+}
+
+fn get_elapsed_text(secs: u64) -> String {
+  match secs {
+    0..=59 => format!("{}s ago", secs),
+    60..=3599 => format!("{}m ago", secs / 60),
+    3600..=86399 => format!("{}h ago", secs / 3600),
+    _ => format!("{}d ago", secs / 86400),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::environment::TestEnvironmentBuilder;
+  use crate::test_helpers::run_test_cli;
+
+  #[test]
+  fn should_output_incremental_status() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file1.txt", "text1")
+      .write_file("/file2.txt", "text2_formatted")
+      .build();
+    run_test_cli(vec!["cache", "incremental", "status"], &environment).unwrap();
+    assert_eq!(
+      environment.take_stdout_messages(),
+      vec!["No incremental cache for /. Run `dprint fmt` or `dprint check` to create it."]
+    );
+
+    run_test_cli(vec!["fmt"], &environment).unwrap();
+    run_test_cli(vec!["fmt"], &environment).unwrap();
+    environment.clear_logs();
+    run_test_cli(vec!["cache", "incremental", "status"], &environment).unwrap();
+    let messages = environment.take_stdout_messages();
+    assert_eq!(messages.len(), 1);
+    assert!(
+      messages[0].contains("  Tracked files: 2\n  Status: Up to date\nLast runs:\n"),
+      "{}",
+      messages[0]
+    );
+    assert!(messages[0].ends_with("  0s ago: 2 hit(s), 0 miss(es) (100% hit rate)\n  0s ago: 0 hit(s), 2 miss(es) (0% hit rate)"));
+  }
+
+  #[test]
+  fn should_invalidate_incremental_cache() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file1.txt", "text1_formatted")
+      .write_file("/file2.txt", "text2_formatted")
+      .build();
+    run_test_cli(vec!["fmt"], &environment).unwrap();
+    run_test_cli(vec!["cache", "incremental", "invalidate", "/file1.txt"], &environment).unwrap();
+    assert_eq!(environment.take_stdout_messages(), vec!["Removed 1 of 1 file(s) from the incremental cache."]);
+    run_test_cli(vec!["cache", "incremental", "status"], &environment).unwrap();
+    assert!(environment.take_stdout_messages()[0].contains("  Tracked files: 1\n"));
+
+    run_test_cli(vec!["cache", "incremental", "invalidate"], &environment).unwrap();
+    assert_eq!(environment.take_stdout_messages(), vec!["Removed all files from the incremental cache."]);
+    run_test_cli(vec!["cache", "incremental", "status"], &environment).unwrap();
+    assert_eq!(
+      environment.take_stdout_messages(),
+      vec!["No incremental cache for /. Run `dprint fmt` or `dprint check` to create it."]
+    );
+  }
+}
@@ -4,7 +4,9 @@ use anyhow::Context;
 use anyhow::Error;
 use anyhow::Result;
 use dprint_core::async_runtime::future;
+use dprint_core::configuration::ConfigurationDiagnostic;
 use dprint_core::plugins;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::path::PathBuf;
@@ -17,6 +19,7 @@ use crate::configuration::get_init_config_file_text;
 use crate::configuration::*;
 use crate::environment::CanonicalizedPathBuf;
 use crate::environment::Environment;
+use crate::plugins::display_config_diagnostic;
 use crate::plugins::read_info_file;
 use crate::plugins::read_update_url;
 use crate::plugins::InfoFilePluginInfo;
@@ -468,6 +471,60 @@ pub async fn output_resolved_config<TEnvironment: Environment>(
   Ok(())
 }
 
+pub async fn output_config_diagnostics<TEnvironment: Environment>(
+  args: &CliArgs,
+  environment: &TEnvironment,
+  plugin_resolver: &Rc<PluginResolver<TEnvironment>>,
+  json: bool,
+) -> Result<()> {
+  #[derive(Serialize)]
+  struct JsonConfigDiagnostic<'a> {
+    /// The plugin name or `None` for the global configuration.
+    plugin: Option<&'a str>,
+    #[serde(flatten)]
+    diagnostic: &'a ConfigurationDiagnostic,
+  }
+
+  let config = Rc::new(resolve_config_from_args(args, environment).await?);
+  let plugins_scope = resolve_plugins_scope(config, environment, plugin_resolver).await?;
+
+  let mut diagnostics = plugins_scope
+    .global_config_diagnostics()
+    .iter()
+    .map(|d| (None, d.diagnostic().clone()))
+    .collect::<Vec<_>>();
+  for plugin in plugins_scope.plugins.values() {
+    let plugin = plugin.initialize().await?;
+    for diagnostic in plugin.config_diagnostics().await? {
+      diagnostics.push((Some(plugin.info().name.clone()), diagnostic));
+    }
+  }
+
+  if json {
+    let json_diagnostics = diagnostics
+      .iter()
+      .map(|(plugin_name, diagnostic)| JsonConfigDiagnostic {
+        plugin: plugin_name.as_deref(),
+        diagnostic,
+      })
+      .collect::<Vec<_>>();
+    environment.log_machine_readable(serde_json::to_string_pretty(&json_diagnostics)?.as_bytes());
+  } else {
+    for (plugin_name, diagnostic) in &diagnostics {
+      match plugin_name {
+        Some(plugin_name) => log_warn!(environment, "[{}]: {}", plugin_name, display_config_diagnostic(diagnostic)),
+        None => log_warn!(environment, "{}", display_config_diagnostic(diagnostic)),
+      }
+    }
+  }
+
+  let error_count = diagnostics.iter().filter(|(_, d)| d.severity.is_error()).count();
+  if error_count > 0 {
+    bail!("Had {} config error(s).", error_count);
+  }
+  Ok(())
+}
+
 async fn get_config_file_plugins<TEnvironment: Environment>(
   plugin_resolver: &Rc<PluginResolver<TEnvironment>>,
   current_plugins: Vec<PluginSourceReference>,
@@ -1368,6 +1425,31 @@ mod test {
     );
   }
 
+  #[test]
+  fn should_output_config_diagnostics_as_json() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_process_plugin()
+      .with_default_config(|c| {
+        c.add_config_section("testProcessPlugin", r#"{ "non-existent": 25 }"#);
+      })
+      .build();
+    let err = run_test_cli(vec!["config", "diagnostics", "--json"], &environment).err().unwrap();
+    assert_eq!(err.to_string(), "Had 1 config error(s).");
+    assert_eq!(
+      environment.take_stdout_messages(),
+      vec![concat!(
+        "[\n",
+        "  {\n",
+        "    \"plugin\": \"test-process-plugin\",\n",
+        "    \"propertyName\": \"non-existent\",\n",
+        "    \"message\": \"Unknown property in configuration\",\n",
+        "    \"severity\": \"error\",\n",
+        "    \"code\": \"unknown-property\"\n",
+        "  }\n",
+        "]",
+      )]
+    );
+  }
+
   #[test]
   fn should_output_resolved_config_no_plugins() {
     let environment = TestEnvironmentBuilder::new().with_default_config(|_| {}).build();
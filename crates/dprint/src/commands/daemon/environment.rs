@@ -0,0 +1,395 @@
+use anyhow::bail;
+use anyhow::Result;
+use dprint_core::communication::SingleThreadMessageWriter;
+use parking_lot::Mutex;
+use std::borrow::Cow;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use dprint_core::async_runtime::async_trait;
+
+use crate::environment::ensure_network_allowed;
+use crate::environment::CanonicalizedPathBuf;
+use crate::environment::DirEntry;
+use crate::environment::Environment;
+use crate::environment::FileBytes;
+use crate::environment::FileLock;
+use crate::environment::FilePermissions;
+use crate::environment::ProcessPluginTrustPolicy;
+use crate::environment::UrlDownloader;
+use crate::plugins::CompilationResult;
+use crate::utils::log_action_with_progress;
+use crate::utils::LogLevel;
+use crate::utils::ProgressBars;
+
+use super::messages::DaemonResponse;
+
+/// The client's state for the request being run by the daemon.
+pub struct DaemonRequestContext {
+  cwd: CanonicalizedPathBuf,
+  log_level: LogLevel,
+  is_stdout_machine_readable: bool,
+  no_network: bool,
+  trust_process_plugins: bool,
+  writer: Arc<SingleThreadMessageWriter<DaemonResponse>>,
+  last_context_name: Mutex<String>,
+}
+
+impl DaemonRequestContext {
+  pub fn new(
+    cwd: CanonicalizedPathBuf,
+    log_level: LogLevel,
+    is_stdout_machine_readable: bool,
+    no_network: bool,
+    trust_process_plugins: bool,
+    writer: Arc<SingleThreadMessageWriter<DaemonResponse>>,
+  ) -> Self {
+    Self {
+      cwd,
+      log_level,
+      is_stdout_machine_readable,
+      no_network,
+      trust_process_plugins,
+      writer,
+      last_context_name: Mutex::new("dprint".to_string()),
+    }
+  }
+
+  /// Sends the text to the client the same way the logger outputs it.
+  fn log(&self, is_std_out: bool, text: &str, context_name: &str) {
+    let mut last_context_name = self.last_context_name.lock();
+    let mut output_text = String::new();
+    if *last_context_name != context_name {
+      // don't output this if stdout is machine readable
+      if !is_std_out || !self.is_stdout_machine_readable {
+        output_text.push_str(&format!("[{}]\n", context_name));
+      }
+      *last_context_name = context_name.to_string();
+    }
+    output_text.push_str(text);
+    // only add a newline if the logged text does not end with one
+    if !output_text.ends_with('\n') {
+      output_text.push('\n');
+    }
+    // send while locked so the output stays in order
+    let bytes = output_text.into_bytes();
+    let _ = self.writer.send(if is_std_out {
+      DaemonResponse::Stdout(bytes)
+    } else {
+      DaemonResponse::Stderr(bytes)
+    });
+  }
+}
+
+/// An environment that runs requests from the daemon's clients as if
+/// they were run in the client's process.
+///
+/// While a request is being run, output is sent to the client, relative
+/// paths are resolved from the client's cwd, and nothing is prompted.
+/// Otherwise, everything is delegated to the inner environment.
+#[derive(Clone)]
+pub struct DaemonEnvironment<TEnvironment: Environment> {
+  inner: TEnvironment,
+  request: Arc<Mutex<Option<Arc<DaemonRequestContext>>>>,
+}
+
+impl<TEnvironment: Environment> DaemonEnvironment<TEnvironment> {
+  pub fn new(inner: TEnvironment) -> Self {
+    Self {
+      inner,
+      request: Default::default(),
+    }
+  }
+
+  pub fn inner(&self) -> &TEnvironment {
+    &self.inner
+  }
+
+  pub fn start_request(&self, request: DaemonRequestContext) {
+    *self.request.lock() = Some(Arc::new(request));
+  }
+
+  pub fn end_request(&self) {
+    self.request.lock().take();
+  }
+
+  fn request(&self) -> Option<Arc<DaemonRequestContext>> {
+    self.request.lock().clone()
+  }
+
+  fn resolve_path<'a>(&self, path: &'a Path) -> Cow<'a, Path> {
+    match self.request() {
+      Some(request) if !path.is_absolute() => Cow::Owned(request.cwd.join(path)),
+      _ => Cow::Borrowed(path),
+    }
+  }
+
+  /// The client may disable network access even though the daemon didn't.
+  fn ensure_network_allowed(&self, url: &str) -> Result<()> {
+    ensure_network_allowed(self.request().is_some_and(|request| request.no_network), url)
+  }
+
+  fn ensure_can_prompt(&self) -> Result<()> {
+    if self.request().is_some() {
+      bail!("Cannot prompt when running in the daemon. Run the command without --daemon instead.");
+    }
+    Ok(())
+  }
+}
+
+#[async_trait(?Send)]
+impl<TEnvironment: Environment> UrlDownloader for DaemonEnvironment<TEnvironment> {
+  async fn download_file(&self, url: &str) -> Result<Option<Vec<u8>>> {
+    self.ensure_network_allowed(url)?;
+    self.inner.download_file(url).await
+  }
+
+  async fn download_file_with_checksum(&self, url: &str, checksum: &str) -> Result<Option<Vec<u8>>> {
+    self.ensure_network_allowed(url)?;
+    self.inner.download_file_with_checksum(url, checksum).await
+  }
+
+  async fn post_json(&self, url: &str, headers: &[(String, String)], body: Vec<u8>) -> Result<()> {
+    self.ensure_network_allowed(url)?;
+    self.inner.post_json(url, headers, body).await
+  }
+}
+
+#[async_trait]
+impl<TEnvironment: Environment> Environment for DaemonEnvironment<TEnvironment> {
+  fn is_real(&self) -> bool {
+    self.inner.is_real()
+  }
+
+  fn get_staged_files(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+    self.inner.get_staged_files(&self.resolve_path(dir))
+  }
+
+  fn read_file(&self, file_path: impl AsRef<Path>) -> Result<String> {
+    self.inner.read_file(self.resolve_path(file_path.as_ref()))
+  }
+
+  fn read_file_bytes(&self, file_path: impl AsRef<Path>) -> Result<Vec<u8>> {
+    self.inner.read_file_bytes(self.resolve_path(file_path.as_ref()))
+  }
+
+  fn read_file_bytes_mapped(&self, file_path: impl AsRef<Path>) -> Result<FileBytes> {
+    self.inner.read_file_bytes_mapped(self.resolve_path(file_path.as_ref()))
+  }
+
+  fn write_file_bytes(&self, file_path: impl AsRef<Path>, bytes: &[u8]) -> Result<()> {
+    self.inner.write_file_bytes(self.resolve_path(file_path.as_ref()), bytes)
+  }
+
+  fn atomic_write_file_bytes(&self, file_path: impl AsRef<Path>, bytes: &[u8]) -> Result<()> {
+    self.inner.atomic_write_file_bytes(self.resolve_path(file_path.as_ref()), bytes)
+  }
+
+  fn replace_file_bytes(&self, file_path: impl AsRef<Path>, bytes: &[u8]) -> Result<()> {
+    self.inner.replace_file_bytes(self.resolve_path(file_path.as_ref()), bytes)
+  }
+
+  fn rename(&self, path_from: impl AsRef<Path>, path_to: impl AsRef<Path>) -> Result<()> {
+    self.inner.rename(self.resolve_path(path_from.as_ref()), self.resolve_path(path_to.as_ref()))
+  }
+
+  fn remove_file(&self, file_path: impl AsRef<Path>) -> Result<()> {
+    self.inner.remove_file(self.resolve_path(file_path.as_ref()))
+  }
+
+  fn remove_dir_all(&self, dir_path: impl AsRef<Path>) -> Result<()> {
+    self.inner.remove_dir_all(self.resolve_path(dir_path.as_ref()))
+  }
+
+  fn dir_info(&self, dir_path: impl AsRef<Path>) -> std::io::Result<Vec<DirEntry>> {
+    self.inner.dir_info(self.resolve_path(dir_path.as_ref()))
+  }
+
+  fn path_exists(&self, file_path: impl AsRef<Path>) -> bool {
+    self.inner.path_exists(self.resolve_path(file_path.as_ref()))
+  }
+
+  fn canonicalize(&self, path: impl AsRef<Path>) -> Result<CanonicalizedPathBuf> {
+    self.inner.canonicalize(self.resolve_path(path.as_ref()))
+  }
+
+  fn is_absolute_path(&self, path: impl AsRef<Path>) -> bool {
+    self.inner.is_absolute_path(path)
+  }
+
+  fn file_permissions(&self, path: impl AsRef<Path>) -> Result<FilePermissions> {
+    self.inner.file_permissions(self.resolve_path(path.as_ref()))
+  }
+
+  fn file_size(&self, path: impl AsRef<Path>) -> Result<u64> {
+    self.inner.file_size(self.resolve_path(path.as_ref()))
+  }
+
+  fn set_file_permissions(&self, path: impl AsRef<Path>, permissions: FilePermissions) -> Result<()> {
+    self.inner.set_file_permissions(self.resolve_path(path.as_ref()), permissions)
+  }
+
+  fn mk_dir_all(&self, path: impl AsRef<Path>) -> Result<()> {
+    self.inner.mk_dir_all(self.resolve_path(path.as_ref()))
+  }
+
+  fn try_lock_file(&self, file_path: &Path) -> std::io::Result<Option<FileLock>> {
+    self.inner.try_lock_file(&self.resolve_path(file_path))
+  }
+
+  fn cwd(&self) -> CanonicalizedPathBuf {
+    match self.request() {
+      Some(request) => request.cwd.clone(),
+      None => self.inner.cwd(),
+    }
+  }
+
+  fn current_exe(&self) -> Result<PathBuf> {
+    self.inner.current_exe()
+  }
+
+  fn __log__(&self, text: &str) {
+    match self.request() {
+      Some(request) => {
+        if !request.is_stdout_machine_readable {
+          request.log(true, text, "dprint");
+        }
+      }
+      None => self.inner.__log__(text),
+    }
+  }
+
+  fn log_stderr_with_context(&self, text: &str, context_name: &str) {
+    match self.request() {
+      Some(request) => request.log(false, text, context_name),
+      None => self.inner.log_stderr_with_context(text, context_name),
+    }
+  }
+
+  fn log_machine_readable(&self, bytes: &[u8]) {
+    match self.request() {
+      Some(request) => {
+        let _ = request.writer.send(DaemonResponse::Stdout(bytes.to_vec()));
+      }
+      None => self.inner.log_machine_readable(bytes),
+    }
+  }
+
+  fn log_action_with_progress<TResult: Send + Sync, TCreate: FnOnce(Box<dyn Fn(usize)>) -> TResult + Send + Sync>(
+    &self,
+    message: &str,
+    action: TCreate,
+    total_size: usize,
+  ) -> TResult {
+    match self.request() {
+      // progress bars would be drawn in the daemon's terminal
+      Some(_) => log_action_with_progress(None, message, action, total_size),
+      None => self.inner.log_action_with_progress(message, action, total_size),
+    }
+  }
+
+  fn get_cache_dir(&self) -> CanonicalizedPathBuf {
+    self.inner.get_cache_dir()
+  }
+
+  fn cpu_arch(&self) -> String {
+    self.inner.cpu_arch()
+  }
+
+  fn os(&self) -> String {
+    self.inner.os()
+  }
+
+  fn max_threads(&self) -> usize {
+    self.inner.max_threads()
+  }
+
+  fn cli_version(&self) -> String {
+    self.inner.cli_version()
+  }
+
+  fn get_time_secs(&self) -> u64 {
+    self.inner.get_time_secs()
+  }
+
+  fn get_selection(&self, prompt_message: &str, item_indent_width: u16, items: &[String]) -> Result<usize> {
+    self.ensure_can_prompt()?;
+    self.inner.get_selection(prompt_message, item_indent_width, items)
+  }
+
+  fn get_multi_selection(&self, prompt_message: &str, item_indent_width: u16, items: &[(bool, String)]) -> Result<Vec<usize>> {
+    self.ensure_can_prompt()?;
+    self.inner.get_multi_selection(prompt_message, item_indent_width, items)
+  }
+
+  fn confirm(&self, prompt_message: &str, default_value: bool) -> Result<bool> {
+    self.ensure_can_prompt()?;
+    self.inner.confirm(prompt_message, default_value)
+  }
+
+  fn is_ci(&self) -> bool {
+    self.inner.is_ci()
+  }
+
+  fn process_plugin_trust_policy(&self) -> ProcessPluginTrustPolicy {
+    match self.request() {
+      // the client can't be prompted from the daemon
+      Some(request) if request.trust_process_plugins => ProcessPluginTrustPolicy::Trust,
+      Some(_) => ProcessPluginTrustPolicy::Deny,
+      None => self.inner.process_plugin_trust_policy(),
+    }
+  }
+
+  #[inline]
+  fn log_level(&self) -> LogLevel {
+    match self.request() {
+      Some(request) => request.log_level,
+      None => self.inner.log_level(),
+    }
+  }
+
+  fn compile_wasm(&self, wasm_bytes: &[u8]) -> Result<CompilationResult> {
+    self.inner.compile_wasm(wasm_bytes)
+  }
+
+  fn wasm_cache_key(&self) -> String {
+    self.inner.wasm_cache_key()
+  }
+
+  async fn cpu_usage(&self) -> u8 {
+    self.inner.cpu_usage().await
+  }
+
+  fn memory_usage(&self) -> Option<u64> {
+    self.inner.memory_usage()
+  }
+
+  fn stdout(&self) -> Box<dyn Write + Send> {
+    self.inner.stdout()
+  }
+
+  fn stdin(&self) -> Box<dyn Read + Send> {
+    self.inner.stdin()
+  }
+
+  fn progress_bars(&self) -> Option<&Arc<ProgressBars>> {
+    if self.request().is_some() {
+      None
+    } else {
+      self.inner.progress_bars()
+    }
+  }
+
+  #[cfg(windows)]
+  fn ensure_system_path(&self, directory_path: &str) -> Result<()> {
+    self.inner.ensure_system_path(directory_path)
+  }
+
+  #[cfg(windows)]
+  fn remove_system_path(&self, directory_path: &str) -> Result<()> {
+    self.inner.remove_system_path(directory_path)
+  }
+}
@@ -0,0 +1,135 @@
+use dprint_core::communication::Message;
+use dprint_core::communication::MessageReader;
+use dprint_core::communication::MessageWriter;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Result;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// A request from a client to run the CLI as if it were run in the client's process.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DaemonRequest {
+  pub cli_version: String,
+  pub cwd: PathBuf,
+  /// The arguments the client was run with, including the executable.
+  pub args: Vec<String>,
+  pub stdin: Vec<u8>,
+}
+
+impl DaemonRequest {
+  pub fn read<TRead: Read + Unpin>(reader: &mut MessageReader<TRead>) -> Result<DaemonRequest> {
+    let cli_version = read_string(reader)?;
+    let cwd = PathBuf::from(read_string(reader)?);
+    let arg_count = reader.read_u32()?;
+    let mut args = Vec::with_capacity(arg_count as usize);
+    for _ in 0..arg_count {
+      args.push(read_string(reader)?);
+    }
+    let stdin = reader.read_sized_bytes()?;
+    reader.read_success_bytes()?;
+    Ok(DaemonRequest { cli_version, cwd, args, stdin })
+  }
+
+  pub fn write<TWrite: Write + Unpin>(&self, writer: &mut MessageWriter<TWrite>) -> Result<()> {
+    writer.send_sized_bytes(self.cli_version.as_bytes())?;
+    writer.send_sized_bytes(self.cwd.to_string_lossy().as_bytes())?;
+    writer.send_u32(self.args.len() as u32)?;
+    for arg in &self.args {
+      writer.send_sized_bytes(arg.as_bytes())?;
+    }
+    writer.send_sized_bytes(&self.stdin)?;
+    writer.send_success_bytes()
+  }
+}
+
+/// Output of the request sent to the client as it happens.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DaemonResponse {
+  Stdout(Vec<u8>),
+  Stderr(Vec<u8>),
+  /// The request finished with this exit code.
+  Exit(i32),
+  /// The daemon is running a different version of the CLI than the client, so
+  /// the client should run the command itself. Contains the daemon's version.
+  VersionMismatch(String),
+}
+
+impl DaemonResponse {
+  pub fn read<TRead: Read + Unpin>(reader: &mut MessageReader<TRead>) -> Result<DaemonResponse> {
+    let kind = reader.read_u32()?;
+    let response = match kind {
+      0 => DaemonResponse::Stdout(reader.read_sized_bytes()?),
+      1 => DaemonResponse::Stderr(reader.read_sized_bytes()?),
+      2 => DaemonResponse::Exit(reader.read_u32()? as i32),
+      3 => DaemonResponse::VersionMismatch(read_string(reader)?),
+      _ => return Err(std::io::Error::new(ErrorKind::InvalidData, format!("Unknown daemon response kind: {}", kind))),
+    };
+    reader.read_success_bytes()?;
+    Ok(response)
+  }
+}
+
+impl Message for DaemonResponse {
+  fn write<TWrite: Write + Unpin>(&self, writer: &mut MessageWriter<TWrite>) -> Result<()> {
+    match self {
+      DaemonResponse::Stdout(bytes) => {
+        writer.send_u32(0)?;
+        writer.send_sized_bytes(bytes)?;
+      }
+      DaemonResponse::Stderr(bytes) => {
+        writer.send_u32(1)?;
+        writer.send_sized_bytes(bytes)?;
+      }
+      DaemonResponse::Exit(exit_code) => {
+        writer.send_u32(2)?;
+        writer.send_u32(*exit_code as u32)?;
+      }
+      DaemonResponse::VersionMismatch(version) => {
+        writer.send_u32(3)?;
+        writer.send_sized_bytes(version.as_bytes())?;
+      }
+    }
+    writer.send_success_bytes()
+  }
+}
+
+fn read_string<TRead: Read + Unpin>(reader: &mut MessageReader<TRead>) -> Result<String> {
+  String::from_utf8(reader.read_sized_bytes()?).map_err(|err| std::io::Error::new(ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_round_trip_messages() {
+    let request = DaemonRequest {
+      cli_version: "0.1.0".to_string(),
+      cwd: PathBuf::from("/project"),
+      args: vec!["dprint".to_string(), "fmt".to_string(), "--daemon".to_string()],
+      stdin: b"text".to_vec(),
+    };
+    let responses = vec![
+      DaemonResponse::Stdout(b"out".to_vec()),
+      DaemonResponse::Stderr(b"err".to_vec()),
+      DaemonResponse::VersionMismatch("0.2.0".to_string()),
+      DaemonResponse::Exit(-1),
+    ];
+
+    let mut bytes = Vec::new();
+    {
+      let mut writer = MessageWriter::new(&mut bytes);
+      request.write(&mut writer).unwrap();
+      for response in &responses {
+        response.write(&mut writer).unwrap();
+      }
+    }
+
+    let mut reader = MessageReader::new(bytes.as_slice());
+    assert_eq!(DaemonRequest::read(&mut reader).unwrap(), request);
+    for response in responses {
+      assert_eq!(DaemonResponse::read(&mut reader).unwrap(), response);
+    }
+  }
+}
@@ -0,0 +1,255 @@
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use dprint_core::communication::MessageReader;
+use dprint_core::communication::MessageWriter;
+use dprint_core::communication::SingleThreadMessageWriter;
+use std::io::ErrorKind;
+use std::io::Write;
+use std::rc::Rc;
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+
+mod environment;
+mod messages;
+
+use crate::arg_parser::parse_args;
+use crate::arg_parser::CliArgs;
+use crate::arg_parser::DaemonClientArgs;
+use crate::arg_parser::DaemonSubCommand;
+use crate::arg_parser::SubCommand;
+use crate::commands;
+use crate::environment::Environment;
+use crate::plugins::PluginCache;
+use crate::plugins::PluginResolver;
+use crate::run_cli::AppError;
+use crate::utils::cleanup_local_socket;
+use crate::utils::connect_local_socket;
+use crate::utils::LocalSocketListener;
+use crate::utils::LocalSocketStreams;
+use crate::utils::LogLevel;
+use crate::utils::StdInReader;
+
+use self::environment::DaemonEnvironment;
+use self::environment::DaemonRequestContext;
+use self::messages::DaemonRequest;
+use self::messages::DaemonResponse;
+
+/// Runs the commands forwarded by clients one at a time, keeping the
+/// resolved plugins initialized between them.
+pub async fn run_daemon<TEnvironment: Environment>(environment: &TEnvironment, cmd: &DaemonSubCommand) -> Result<()> {
+  let socket_path = match &cmd.socket {
+    Some(socket_path) => socket_path.clone(),
+    None => get_default_socket_path(environment),
+  };
+  remove_stale_socket(&socket_path, environment)?;
+  let mut listener = LocalSocketListener::bind(&socket_path).with_context(|| format!("Failed listening on {}.", socket_path))?;
+  log_stderr_info!(environment, "Listening on {}", socket_path);
+
+  let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+  let listener_environment = environment.clone();
+  dprint_core::async_runtime::spawn(async move {
+    loop {
+      let streams = match listener.accept().await {
+        Ok(streams) => streams,
+        Err(err) => {
+          log_error!(listener_environment, "Daemon failed accepting a connection: {:#}", err);
+          return;
+        }
+      };
+      read_request(streams, &listener_environment, tx.clone());
+    }
+  });
+
+  let environment = DaemonEnvironment::new(environment.clone());
+  let plugin_cache = PluginCache::new(environment.clone());
+  let plugin_resolver = Rc::new(PluginResolver::new(environment.clone(), plugin_cache));
+  while let Some((request, writer)) = rx.recv().await {
+    run_request(request, writer, &environment, &plugin_resolver).await;
+  }
+
+  plugin_resolver.clear_and_shutdown_initialized().await;
+  cleanup_local_socket(&socket_path, environment.inner()).await;
+  Ok(())
+}
+
+type RequestWriter = Arc<SingleThreadMessageWriter<DaemonResponse>>;
+
+fn read_request<TEnvironment: Environment>(streams: LocalSocketStreams, environment: &TEnvironment, tx: UnboundedSender<(DaemonRequest, RequestWriter)>) {
+  let environment = environment.clone();
+  dprint_core::async_runtime::spawn_blocking(move || {
+    let (reader, writer) = streams;
+    let request = match DaemonRequest::read(&mut MessageReader::new(reader)) {
+      Ok(request) => request,
+      // ex. a client checking if the daemon is running
+      Err(err) if matches!(err.kind(), ErrorKind::BrokenPipe | ErrorKind::UnexpectedEof) => return,
+      Err(err) => {
+        log_error!(environment, "Daemon failed reading from client: {:#}", err);
+        return;
+      }
+    };
+    // failing to write only means the client went away
+    let writer = Arc::new(SingleThreadMessageWriter::for_stdin(MessageWriter::new(writer)));
+    let _ = tx.send((request, writer));
+  });
+}
+
+async fn run_request<TEnvironment: Environment>(
+  request: DaemonRequest,
+  writer: RequestWriter,
+  environment: &DaemonEnvironment<TEnvironment>,
+  plugin_resolver: &Rc<PluginResolver<DaemonEnvironment<TEnvironment>>>,
+) {
+  let cli_version = environment.cli_version();
+  if request.cli_version != cli_version {
+    let _ = writer.send(DaemonResponse::VersionMismatch(cli_version));
+    return;
+  }
+
+  let args = match parse_args(request.args, RequestStdInReader(request.stdin)) {
+    Ok(args) => args,
+    Err(err) => {
+      send_exit(&writer, Err(err.into()), LogLevel::Info);
+      return;
+    }
+  };
+  let result = run_request_cli(&args, &request.cwd, &writer, environment, plugin_resolver).await;
+  environment.end_request();
+  send_exit(&writer, result.map_err(|err| err.into()), args.log_level);
+}
+
+async fn run_request_cli<TEnvironment: Environment>(
+  args: &CliArgs,
+  cwd: &std::path::Path,
+  writer: &RequestWriter,
+  environment: &DaemonEnvironment<TEnvironment>,
+  plugin_resolver: &Rc<PluginResolver<DaemonEnvironment<TEnvironment>>>,
+) -> Result<()> {
+  let cwd = environment.inner().canonicalize(cwd)?;
+  environment.start_request(DaemonRequestContext::new(
+    cwd,
+    args.log_level,
+    args.is_stdout_machine_readable(),
+    args.no_network,
+    args.trust_process_plugins,
+    writer.clone(),
+  ));
+  match &args.sub_command {
+    SubCommand::Fmt(cmd) => commands::format(cmd, args, environment, plugin_resolver).await,
+    SubCommand::Check(cmd) => commands::check(cmd, args, environment, plugin_resolver).await,
+    SubCommand::StdInFmt(cmd) => commands::stdin_fmt(cmd, args, environment, plugin_resolver).await,
+    _ => bail!("Only the fmt and check commands can be run in the daemon."),
+  }
+}
+
+/// Sends the error the same way the CLI outputs it, then the exit code.
+fn send_exit(writer: &RequestWriter, result: Result<(), AppError>, log_level: LogLevel) {
+  let exit_code = match result {
+    Ok(()) => 0,
+    Err(err) => {
+      let message = format!("{:#}", err.inner);
+      if log_level != LogLevel::Silent && !message.is_empty() {
+        let _ = writer.send(DaemonResponse::Stderr(format!("{}\n", message).into_bytes()));
+      }
+      err.exit_code
+    }
+  };
+  let _ = writer.send(DaemonResponse::Exit(exit_code));
+}
+
+#[derive(Clone)]
+struct RequestStdInReader(Vec<u8>);
+
+impl StdInReader for RequestStdInReader {
+  fn read(&self) -> Result<Vec<u8>> {
+    Ok(self.0.clone())
+  }
+}
+
+/// Runs the command in the daemon, outputting what it outputs and returning
+/// its exit code, or `None` when the daemon isn't available.
+pub fn run_in_daemon<TEnvironment: Environment>(
+  raw_args: Vec<String>,
+  args: &CliArgs,
+  daemon_args: &DaemonClientArgs,
+  environment: &TEnvironment,
+) -> Result<Option<i32>> {
+  let socket_path = match &daemon_args.socket {
+    Some(socket_path) => socket_path.clone(),
+    None => get_default_socket_path(environment),
+  };
+  let (reader, writer) = match connect_local_socket(&socket_path) {
+    Ok(streams) => streams,
+    Err(err) => {
+      log_warn!(
+        environment,
+        "Running in this process because no daemon is listening on {} ({}). Start one with `dprint daemon`.",
+        socket_path,
+        err
+      );
+      return Ok(None);
+    }
+  };
+
+  let request = DaemonRequest {
+    cli_version: environment.cli_version(),
+    cwd: environment.cwd().into_path_buf(),
+    args: raw_args,
+    stdin: match &args.sub_command {
+      SubCommand::StdInFmt(cmd) => cmd.file_bytes.clone(),
+      _ => Vec::new(),
+    },
+  };
+  request
+    .write(&mut MessageWriter::new(writer))
+    .context("Failed sending the command to the daemon.")?;
+
+  let mut reader = MessageReader::new(reader);
+  loop {
+    let response = DaemonResponse::read(&mut reader).context("Failed reading the output of the daemon.")?;
+    match response {
+      DaemonResponse::Stdout(bytes) => {
+        let mut stdout = std::io::stdout();
+        stdout.write_all(&bytes)?;
+        stdout.flush()?;
+      }
+      DaemonResponse::Stderr(bytes) => {
+        let mut stderr = std::io::stderr();
+        stderr.write_all(&bytes)?;
+        stderr.flush()?;
+      }
+      DaemonResponse::Exit(exit_code) => return Ok(Some(exit_code)),
+      DaemonResponse::VersionMismatch(daemon_version) => {
+        log_warn!(
+          environment,
+          "Running in this process because the daemon is running dprint {} instead of {}.",
+          daemon_version,
+          environment.cli_version(),
+        );
+        return Ok(None);
+      }
+    }
+  }
+}
+
+fn get_default_socket_path<TEnvironment: Environment>(environment: &TEnvironment) -> String {
+  if cfg!(windows) {
+    // named pipes are global, so use one per cache directory
+    let cache_dir = environment.get_cache_dir();
+    let hash = crate::utils::get_bytes_hash(cache_dir.to_string_lossy().as_bytes());
+    format!(r"\\.\pipe\dprint-daemon-{:x}", hash)
+  } else {
+    environment.get_cache_dir().join("daemon.sock").to_string_lossy().into_owned()
+  }
+}
+
+/// Removes the socket file left behind by a daemon that didn't exit cleanly.
+fn remove_stale_socket<TEnvironment: Environment>(socket_path: &str, environment: &TEnvironment) -> Result<()> {
+  if cfg!(unix) && environment.path_exists(socket_path) {
+    if connect_local_socket(socket_path).is_ok() {
+      bail!("A daemon is already listening on {}.", socket_path);
+    }
+    environment.remove_file(socket_path)?;
+  }
+  Ok(())
+}
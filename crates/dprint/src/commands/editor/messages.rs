@@ -38,26 +38,7 @@ impl EditorMessage {
         let can_format = reader.read_u32()?;
         EditorMessageBody::CanFormatResponse(message_id, can_format)
       }
-      6 => {
-        let file_path = reader.read_sized_bytes()?;
-        let start_byte_index = reader.read_u32()?;
-        let end_byte_index = reader.read_u32()?;
-        let override_config = reader.read_sized_bytes()?;
-        let file_text = reader.read_sized_bytes()?;
-        EditorMessageBody::Format(FormatEditorMessageBody {
-          file_path: PathBuf::from(String::from_utf8_lossy(&file_path).to_string()),
-          range: if start_byte_index == 0 && end_byte_index == file_text.len() as u32 {
-            None
-          } else {
-            Some(std::ops::Range {
-              start: start_byte_index as usize,
-              end: end_byte_index as usize,
-            })
-          },
-          file_bytes: file_text,
-          override_config,
-        })
-      }
+      6 => EditorMessageBody::Format(FormatEditorMessageBody::read(reader)?),
       7 => {
         let message_id = reader.read_u32()?;
         let response_kind = reader.read_u32()?;
@@ -74,6 +55,73 @@ impl EditorMessage {
         EditorMessageBody::FormatResponse(message_id, data)
       }
       8 => EditorMessageBody::CancelFormat(reader.read_u32()?),
+      9 => {
+        let file_count = reader.read_u32()?;
+        let mut files = Vec::with_capacity(file_count as usize);
+        for _ in 0..file_count {
+          files.push(FormatEditorMessageBody::read(reader)?);
+        }
+        EditorMessageBody::FormatBatch(files)
+      }
+      10 => {
+        let message_id = reader.read_u32()?;
+        let file_index = reader.read_u32()?;
+        let response_kind = reader.read_u32()?;
+        let result = match response_kind {
+          0 => FormatBatchItemResult::NoChange,
+          1 => FormatBatchItemResult::Change(reader.read_sized_bytes()?),
+          2 => FormatBatchItemResult::Error(reader.read_sized_bytes()?),
+          _ => {
+            return Err(std::io::Error::new(
+              ErrorKind::InvalidData,
+              format!("Unknown format batch item response kind: {}", response_kind),
+            ))
+          }
+        };
+        EditorMessageBody::FormatBatchItemResponse(message_id, file_index, result)
+      }
+      11 => {
+        let file_path = reader.read_sized_bytes()?;
+        EditorMessageBody::ResolvedConfig(PathBuf::from(String::from_utf8_lossy(&file_path).to_string()))
+      }
+      12 => {
+        let message_id = reader.read_u32()?;
+        let data = reader.read_sized_bytes()?;
+        EditorMessageBody::ResolvedConfigResponse(message_id, data)
+      }
+      13 => EditorMessageBody::ConfigChanged,
+      14 => EditorMessageBody::Handshake(HandshakeMessageBody::read(reader)?),
+      15 => {
+        let message_id = reader.read_u32()?;
+        EditorMessageBody::HandshakeResponse(message_id, HandshakeMessageBody::read(reader)?)
+      }
+      16 => EditorMessageBody::FileAssociations,
+      17 => {
+        let message_id = reader.read_u32()?;
+        let data = reader.read_sized_bytes()?;
+        EditorMessageBody::FileAssociationsResponse(message_id, data)
+      }
+      18 => {
+        let config_path = reader.read_sized_bytes()?;
+        let file_path = reader.read_sized_bytes()?;
+        EditorMessageBody::CanFormatWithConfig(
+          PathBuf::from(String::from_utf8_lossy(&config_path).to_string()),
+          PathBuf::from(String::from_utf8_lossy(&file_path).to_string()),
+        )
+      }
+      19 => {
+        let config_path = reader.read_sized_bytes()?;
+        EditorMessageBody::FormatWithConfig(
+          PathBuf::from(String::from_utf8_lossy(&config_path).to_string()),
+          FormatEditorMessageBody::read(reader)?,
+        )
+      }
+      20 => EditorMessageBody::Status,
+      21 => {
+        let message_id = reader.read_u32()?;
+        let data = reader.read_sized_bytes()?;
+        EditorMessageBody::StatusResponse(message_id, data)
+      }
       _ => {
         let data = reader.read_bytes(body_length as usize)?;
         EditorMessageBody::Unknown(message_kind, data)
@@ -106,12 +154,7 @@ impl Message for EditorMessage {
         builder.add_number(*can_format);
       }
       EditorMessageBody::Format(body) => {
-        let path = body.file_path.to_string_lossy().to_string();
-        builder.add_owned_bytes(path.into_bytes());
-        builder.add_number(body.range.as_ref().map(|r| r.start as u32).unwrap_or(0));
-        builder.add_number(body.range.as_ref().map(|r| r.end as u32).unwrap_or_else(|| body.file_bytes.len() as u32));
-        builder.add_bytes(&body.override_config);
-        builder.add_bytes(&body.file_bytes);
+        body.add_to_builder(&mut builder);
       }
       EditorMessageBody::FormatResponse(message_id, data) => {
         builder.add_number(*message_id);
@@ -128,6 +171,63 @@ impl Message for EditorMessage {
       EditorMessageBody::CancelFormat(message_id) => {
         builder.add_number(*message_id);
       }
+      EditorMessageBody::FormatBatch(files) => {
+        builder.add_number(files.len() as u32);
+        for body in files {
+          body.add_to_builder(&mut builder);
+        }
+      }
+      EditorMessageBody::FormatBatchItemResponse(message_id, file_index, result) => {
+        builder.add_number(*message_id);
+        builder.add_number(*file_index);
+        match result {
+          FormatBatchItemResult::NoChange => {
+            builder.add_number(0);
+          }
+          FormatBatchItemResult::Change(data) => {
+            builder.add_number(1);
+            builder.add_bytes(data);
+          }
+          FormatBatchItemResult::Error(data) => {
+            builder.add_number(2);
+            builder.add_bytes(data);
+          }
+        }
+      }
+      EditorMessageBody::ResolvedConfig(path_buf) => {
+        let path = path_buf.to_string_lossy().to_string();
+        builder.add_owned_bytes(path.into_bytes());
+      }
+      EditorMessageBody::ResolvedConfigResponse(message_id, data) => {
+        builder.add_number(*message_id);
+        builder.add_bytes(data);
+      }
+      EditorMessageBody::ConfigChanged => {}
+      EditorMessageBody::Handshake(body) => {
+        body.add_to_builder(&mut builder)?;
+      }
+      EditorMessageBody::HandshakeResponse(message_id, body) => {
+        builder.add_number(*message_id);
+        body.add_to_builder(&mut builder)?;
+      }
+      EditorMessageBody::FileAssociations => {}
+      EditorMessageBody::FileAssociationsResponse(message_id, data) => {
+        builder.add_number(*message_id);
+        builder.add_bytes(data);
+      }
+      EditorMessageBody::CanFormatWithConfig(config_path, file_path) => {
+        builder.add_owned_bytes(config_path.to_string_lossy().to_string().into_bytes());
+        builder.add_owned_bytes(file_path.to_string_lossy().to_string().into_bytes());
+      }
+      EditorMessageBody::FormatWithConfig(config_path, body) => {
+        builder.add_owned_bytes(config_path.to_string_lossy().to_string().into_bytes());
+        body.add_to_builder(&mut builder);
+      }
+      EditorMessageBody::Status => {}
+      EditorMessageBody::StatusResponse(message_id, data) => {
+        builder.add_number(*message_id);
+        builder.add_bytes(data);
+      }
       EditorMessageBody::Unknown(_, _) => unreachable!(), // should never be written
     }
     builder.write(writer)?;
@@ -207,6 +307,28 @@ pub enum EditorMessageBody {
   Format(FormatEditorMessageBody),
   FormatResponse(u32, Option<Vec<u8>>),
   CancelFormat(u32),
+  /// Formats several files, responding with a `FormatBatchItemResponse`
+  /// for each file as it completes followed by a `Success` message.
+  FormatBatch(Vec<FormatEditorMessageBody>),
+  FormatBatchItemResponse(u32, u32, FormatBatchItemResult),
+  /// Gets the resolved configuration of the plugins that format a file.
+  ResolvedConfig(PathBuf),
+  ResolvedConfigResponse(u32, Vec<u8>),
+  /// Notifies the client that the configuration file changed.
+  ConfigChanged,
+  /// Exchanges the schema version and supported features.
+  Handshake(HandshakeMessageBody),
+  HandshakeResponse(u32, HandshakeMessageBody),
+  /// Gets the file patterns of the configuration and each plugin.
+  FileAssociations,
+  FileAssociationsResponse(u32, Vec<u8>),
+  /// Same as `CanFormat`, but using the provided configuration file.
+  CanFormatWithConfig(PathBuf, PathBuf),
+  /// Same as `Format`, but using the provided configuration file.
+  FormatWithConfig(PathBuf, FormatEditorMessageBody),
+  /// Gets information about the state of the service for diagnosing issues.
+  Status,
+  StatusResponse(u32, Vec<u8>),
   #[allow(dead_code)]
   Unknown(u32, Vec<u8>),
 }
@@ -223,6 +345,19 @@ impl EditorMessageBody {
       EditorMessageBody::Format(_) => 6,
       EditorMessageBody::FormatResponse(_, _) => 7,
       EditorMessageBody::CancelFormat(_) => 8,
+      EditorMessageBody::FormatBatch(_) => 9,
+      EditorMessageBody::FormatBatchItemResponse(_, _, _) => 10,
+      EditorMessageBody::ResolvedConfig(_) => 11,
+      EditorMessageBody::ResolvedConfigResponse(_, _) => 12,
+      EditorMessageBody::ConfigChanged => 13,
+      EditorMessageBody::Handshake(_) => 14,
+      EditorMessageBody::HandshakeResponse(_, _) => 15,
+      EditorMessageBody::FileAssociations => 16,
+      EditorMessageBody::FileAssociationsResponse(_, _) => 17,
+      EditorMessageBody::CanFormatWithConfig(_, _) => 18,
+      EditorMessageBody::FormatWithConfig(_, _) => 19,
+      EditorMessageBody::Status => 20,
+      EditorMessageBody::StatusResponse(_, _) => 21,
       EditorMessageBody::Unknown(_, _) => unreachable!(),
     }
   }
@@ -235,3 +370,65 @@ pub struct FormatEditorMessageBody {
   pub override_config: Vec<u8>,
   pub file_bytes: Vec<u8>,
 }
+
+impl FormatEditorMessageBody {
+  fn read<TRead: Read + Unpin>(reader: &mut MessageReader<TRead>) -> Result<FormatEditorMessageBody> {
+    let file_path = reader.read_sized_bytes()?;
+    let start_byte_index = reader.read_u32()?;
+    let end_byte_index = reader.read_u32()?;
+    let override_config = reader.read_sized_bytes()?;
+    let file_text = reader.read_sized_bytes()?;
+    Ok(FormatEditorMessageBody {
+      file_path: PathBuf::from(String::from_utf8_lossy(&file_path).to_string()),
+      range: if start_byte_index == 0 && end_byte_index == file_text.len() as u32 {
+        None
+      } else {
+        Some(std::ops::Range {
+          start: start_byte_index as usize,
+          end: end_byte_index as usize,
+        })
+      },
+      file_bytes: file_text,
+      override_config,
+    })
+  }
+
+  fn add_to_builder<'a>(&'a self, builder: &mut MessageBuilder<'a>) {
+    let path = self.file_path.to_string_lossy().to_string();
+    builder.add_owned_bytes(path.into_bytes());
+    builder.add_number(self.range.as_ref().map(|r| r.start as u32).unwrap_or(0));
+    builder.add_number(self.range.as_ref().map(|r| r.end as u32).unwrap_or_else(|| self.file_bytes.len() as u32));
+    builder.add_bytes(&self.override_config);
+    builder.add_bytes(&self.file_bytes);
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatBatchItemResult {
+  NoChange,
+  Change(Vec<u8>),
+  Error(Vec<u8>),
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HandshakeMessageBody {
+  pub schema_version: u32,
+  pub features: Vec<String>,
+}
+
+impl HandshakeMessageBody {
+  fn read<TRead: Read + Unpin>(reader: &mut MessageReader<TRead>) -> Result<HandshakeMessageBody> {
+    let schema_version = reader.read_u32()?;
+    let features = reader.read_sized_bytes()?;
+    Ok(HandshakeMessageBody {
+      schema_version,
+      features: serde_json::from_slice(&features).map_err(|err| std::io::Error::new(ErrorKind::InvalidData, err))?,
+    })
+  }
+
+  fn add_to_builder(&self, builder: &mut MessageBuilder) -> Result<()> {
+    builder.add_number(self.schema_version);
+    builder.add_owned_bytes(serde_json::to_vec(&self.features)?);
+    Ok(())
+  }
+}
@@ -1,34 +1,61 @@
 use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::Context;
 use anyhow::Result;
+use dprint_core::async_runtime::future;
+use dprint_core::async_runtime::FutureExt;
 use dprint_core::communication::IdGenerator;
-use dprint_core::communication::MessageReader;
-use dprint_core::communication::MessageWriter;
 use dprint_core::communication::RcIdStore;
 use dprint_core::communication::SingleThreadMessageWriter;
+use dprint_core::configuration::ConfigKeyMap;
 use dprint_core::plugins::HostFormatRequest;
-use std::io::ErrorKind;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::hash::Hasher;
 use std::path::Path;
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::time::MissedTickBehavior;
 use tokio_util::sync::CancellationToken;
 
-use dprint_core::plugins::process::start_parent_process_checker_task;
+use dprint_core::plugins::process::start_parent_process_checker_task_with_options;
+use dprint_core::plugins::process::ParentProcessCheckerOptions;
 
 mod messages;
+mod transport;
 
 use crate::arg_parser::CliArgs;
 use crate::arg_parser::EditorServiceSubCommand;
 use crate::configuration::resolve_config_from_args;
+use crate::configuration::resolve_config_from_path;
 use crate::configuration::ResolvedConfig;
+use crate::configuration::ResolvedConfigPath;
+use crate::environment::CanonicalizedPathBuf;
 use crate::environment::Environment;
 use crate::plugins::PluginResolver;
-use crate::resolution::get_plugins_scope_from_args;
+use crate::resolution::get_plugins_config_from_args;
 use crate::resolution::resolve_plugins_scope;
+use crate::resolution::GetPluginResult;
 use crate::resolution::PluginsScope;
+use crate::resolution::ResolvedPluginsConfig;
+use crate::utils::cleanup_local_socket;
+use crate::utils::get_bytes_hash;
+use crate::utils::FastInsecureHasher;
+use crate::utils::PathSource;
+use crate::utils::ResolvedPath;
 use crate::utils::Semaphore;
 
 use self::messages::EditorMessage;
 use self::messages::EditorMessageBody;
+use self::messages::FormatBatchItemResult;
+use self::messages::FormatEditorMessageBody;
+use self::messages::HandshakeMessageBody;
+use self::transport::start_socket_transport;
+use self::transport::start_stdio_transport;
+use self::transport::ConnectionEvent;
 
 pub async fn output_editor_info<TEnvironment: Environment>(
   args: &CliArgs,
@@ -57,20 +84,28 @@ pub async fn output_editor_info<TEnvironment: Environment>(
     help_url: String,
   }
 
-  let mut plugins = Vec::new();
-  let scope = get_plugins_scope_from_args(args, environment, plugin_resolver).await?;
+  let plugins_config = get_plugins_config_from_args(args, environment, plugin_resolver).await?;
+
+  plugins_config.ensure_no_global_config_diagnostics()?;
 
-  scope.ensure_no_global_config_diagnostics()?;
+  // the editor extensions call this often, so avoid initializing the plugins when nothing changed
+  let cache_file_path = get_editor_info_cache_file_path(&plugins_config, environment);
+  if let Some(cache_file_path) = &cache_file_path {
+    if let Ok(bytes) = environment.read_file_bytes(cache_file_path) {
+      environment.log_machine_readable(&bytes);
+      return Ok(());
+    }
+  }
 
+  let scope = plugins_config.initialize(plugin_resolver).await?;
+  let mut plugins = Vec::new();
   for plugin in scope.plugins.values() {
-    let initialized_plugin = plugin.initialize().await?;
-    let file_matching = initialized_plugin.file_matching_info().await?;
     plugins.push(EditorPluginInfo {
       name: plugin.info().name.to_string(),
       version: plugin.info().version.to_string(),
       config_key: plugin.info().config_key.to_string(),
-      file_extensions: file_matching.file_extensions,
-      file_names: file_matching.file_names,
+      file_extensions: plugin.file_matching.file_extensions.clone(),
+      file_names: plugin.file_matching.file_names.clone(),
       config_schema_url: if plugin.info().config_schema_url.trim().is_empty() {
         None
       } else {
@@ -80,33 +115,149 @@ pub async fn output_editor_info<TEnvironment: Environment>(
     });
   }
 
-  environment.log_machine_readable(&serde_json::to_vec(&EditorInfo {
+  let bytes = serde_json::to_vec(&EditorInfo {
     schema_version: 5,
     cli_version: environment.cli_version(),
     config_schema_url: "https://dprint.dev/schemas/v0.json".to_string(),
     plugins,
-  })?);
+  })?;
+  if let Some(cache_file_path) = &cache_file_path {
+    let result = environment
+      .mk_dir_all(cache_file_path.parent().unwrap())
+      .and_then(|_| environment.atomic_write_file_bytes(cache_file_path, &bytes));
+    if let Err(err) = result {
+      log_debug!(environment, "Failed caching editor info. {:#}", err);
+    }
+  }
+  environment.log_machine_readable(&bytes);
 
   Ok(())
 }
 
+/// Gets the path to the cached editor info based on a hash of the configuration,
+/// or `None` when it shouldn't be cached.
+fn get_editor_info_cache_file_path<TEnvironment: Environment>(
+  plugins_config: &ResolvedPluginsConfig<TEnvironment>,
+  environment: &TEnvironment,
+) -> Option<PathBuf> {
+  let config = plugins_config.config.as_ref()?;
+  // local plugins may change without their version changing
+  if config.plugins.iter().any(|p| !matches!(p.path_source, PathSource::Remote(_))) {
+    return None;
+  }
+  let mut hasher = FastInsecureHasher::default();
+  hasher.write(environment.cli_version().as_bytes());
+  for plugin in &config.plugins {
+    hasher.write(plugin.to_full_string().as_bytes());
+  }
+  plugins_config.plugins_hash(&mut hasher);
+  Some(environment.get_cache_dir().join("editor-info").join(format!("{}.json", hasher.finish())))
+}
+
 pub async fn run_editor_service<TEnvironment: Environment>(
   args: &CliArgs,
   environment: &TEnvironment,
   plugin_resolver: &Rc<PluginResolver<TEnvironment>>,
   editor_service_cmd: &EditorServiceSubCommand,
 ) -> Result<()> {
-  // poll for the existence of the parent process and terminate this process when that process no longer exists
-  start_parent_process_checker_task(editor_service_cmd.parent_pid);
+  if let Some(parent_pid) = editor_service_cmd.parent_pid {
+    // poll for the existence of the parent process and terminate this process when that process no longer exists
+    start_parent_process_checker_task_with_options(
+      parent_pid,
+      ParentProcessCheckerOptions {
+        on_shutdown: Some({
+          let environment = environment.clone();
+          let plugin_resolver = plugin_resolver.clone();
+          let socket_path = editor_service_cmd.socket.clone();
+          Box::new(move || {
+            async move {
+              // give the process plugins a chance to exit gracefully
+              plugin_resolver.clear_and_shutdown_initialized().await;
+              if let Some(socket_path) = &socket_path {
+                cleanup_local_socket(socket_path, &environment).await;
+              }
+            }
+            .boxed_local()
+          })
+        }),
+        ..Default::default()
+      },
+    );
+  }
+
+  let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+  match &editor_service_cmd.socket {
+    Some(socket_path) => start_socket_transport(socket_path, environment, tx).with_context(|| format!("Failed listening on socket {}.", socket_path))?,
+    None => start_stdio_transport(environment, tx),
+  }
 
   let mut editor_service = EditorService::new(args, environment, plugin_resolver);
-  editor_service.run().await
+  let result = editor_service.run(rx).await;
+  if let Some(socket_path) = &editor_service_cmd.socket {
+    drop(editor_service);
+    cleanup_local_socket(socket_path, environment).await;
+  }
+  result
 }
 
+/// Version of the protocol negotiated with the handshake message. The `editor-info`
+/// command still reports version 5 so that older editor extensions keep working.
+const SCHEMA_VERSION: u32 = 6;
+const FORMAT_BATCH_FEATURE: &str = "formatBatch";
+const RESOLVED_CONFIG_FEATURE: &str = "resolvedConfig";
+const CONFIG_CHANGED_FEATURE: &str = "configChangedNotification";
+const FILE_ASSOCIATIONS_FEATURE: &str = "fileAssociations";
+const CONFIG_PATH_FEATURE: &str = "configPath";
+const STATUS_FEATURE: &str = "status";
+
+/// How often to check if the configuration file changed.
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// State of a single client connected to the editor service.
 struct EditorContext {
   pub id_generator: IdGenerator,
   pub writer: SingleThreadMessageWriter<EditorMessage>,
   pub cancellation_tokens: RcIdStore<Arc<CancellationToken>>,
+  pub in_flight_formats: Rc<Cell<usize>>,
+  pub in_flight_format_batches: Rc<Cell<usize>>,
+  /// Whether the client opted into the config changed notification in the handshake.
+  pub notify_config_changed: Cell<bool>,
+}
+
+impl EditorContext {
+  pub fn new(writer: SingleThreadMessageWriter<EditorMessage>) -> Self {
+    Self {
+      id_generator: Default::default(),
+      writer,
+      cancellation_tokens: Default::default(),
+      in_flight_formats: Default::default(),
+      in_flight_format_batches: Default::default(),
+      notify_config_changed: Default::default(),
+    }
+  }
+
+  pub fn cancel_all(&self) {
+    let tokens = self.cancellation_tokens.take_all();
+    for token in tokens.values() {
+      token.cancel();
+    }
+  }
+}
+
+/// Counts a request as in flight until dropped.
+struct InFlightGuard(Rc<Cell<usize>>);
+
+impl InFlightGuard {
+  pub fn new(count: &Rc<Cell<usize>>) -> Self {
+    count.set(count.get() + 1);
+    Self(count.clone())
+  }
+}
+
+impl Drop for InFlightGuard {
+  fn drop(&mut self) {
+    self.0.set(self.0.get() - 1);
+  }
 }
 
 struct EditorService<'a, TEnvironment: Environment> {
@@ -114,15 +265,17 @@ struct EditorService<'a, TEnvironment: Environment> {
   environment: &'a TEnvironment,
   plugin_resolver: &'a Rc<PluginResolver<TEnvironment>>,
   plugins_scope: Option<Rc<PluginsScope<TEnvironment>>>,
-  context: Rc<EditorContext>,
+  /// Plugin scopes of config files explicitly provided in messages.
+  plugins_scope_by_config: HashMap<CanonicalizedPathBuf, Rc<PluginsScope<TEnvironment>>>,
+  /// Local config file of the plugins scope along with a hash of its contents.
+  config_file_hash: Option<(PathBuf, u64)>,
+  connections: HashMap<u32, Rc<EditorContext>>,
   concurrency_limiter: Rc<Semaphore>,
   config_semaphore: Rc<Semaphore>,
 }
 
 impl<'a, TEnvironment: Environment> EditorService<'a, TEnvironment> {
   pub fn new(args: &'a CliArgs, environment: &'a TEnvironment, plugin_resolver: &'a Rc<PluginResolver<TEnvironment>>) -> Self {
-    let stdout = environment.stdout();
-    let writer = SingleThreadMessageWriter::for_stdout(MessageWriter::new(stdout));
     let max_cores = environment.max_threads();
     let concurrency_limiter = Rc::new(Semaphore::new(std::cmp::max(1, max_cores - 1)));
 
@@ -131,121 +284,251 @@ impl<'a, TEnvironment: Environment> EditorService<'a, TEnvironment> {
       environment,
       plugin_resolver,
       plugins_scope: None,
-      context: Rc::new(EditorContext {
-        id_generator: Default::default(),
-        cancellation_tokens: Default::default(),
-        writer,
-      }),
+      plugins_scope_by_config: Default::default(),
+      config_file_hash: None,
+      connections: Default::default(),
       concurrency_limiter,
       config_semaphore: Rc::new(Semaphore::new(1)),
     }
   }
 
-  pub async fn run(&mut self) -> Result<()> {
-    let environment = self.environment.clone();
-    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<EditorMessage>();
-    dprint_core::async_runtime::spawn_blocking(move || {
-      let stdin = environment.stdin();
-      let mut reader = MessageReader::new(stdin);
-      loop {
-        let read_message = match EditorMessage::read(&mut reader) {
-          Ok(message) => message,
-          Err(err) if err.kind() == ErrorKind::BrokenPipe => {
-            return;
-          }
-          Err(err) => {
-            log_error!(environment, "Editor service failed reading from stdin: {:#}", err);
-            return;
+  /// Handles the messages of the connected clients until the last one disconnects.
+  pub async fn run(&mut self, mut rx: UnboundedReceiver<ConnectionEvent>) -> Result<()> {
+    let mut config_poll_interval = tokio::time::interval(CONFIG_POLL_INTERVAL);
+    config_poll_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+      let event = tokio::select! {
+        event = rx.recv() => event,
+        _ = config_poll_interval.tick() => {
+          self.check_config_changed();
+          continue;
+        }
+      };
+      let Some(event) = event else { return Ok(()) };
+      let (connection_id, message) = match event {
+        ConnectionEvent::Connected(connection_id, writer) => {
+          self.connections.insert(connection_id, Rc::new(EditorContext::new(writer)));
+          continue;
+        }
+        ConnectionEvent::Disconnected(connection_id) => {
+          if self.remove_connection(connection_id) {
+            return Ok(());
           }
-        };
-        if tx.send(read_message).is_err() {
-          return; // channel disconnected
+          continue;
         }
-      }
-    });
-    loop {
-      let Some(message) = rx.recv().await else { return Ok(()) };
+        ConnectionEvent::Message(connection_id, message) => (connection_id, message),
+      };
+      let Some(context) = self.connections.get(&connection_id).cloned() else {
+        continue; // already closed
+      };
       match message.body {
         EditorMessageBody::Success(_message_id) => {}
         EditorMessageBody::Error(_message_id, _data) => {}
         EditorMessageBody::Close => {
-          handle_message(&self.context, message.id, || Ok(EditorMessageBody::Success(message.id)));
-          return Ok(());
+          handle_message(&context, message.id, || Ok(EditorMessageBody::Success(message.id)));
+          if self.remove_connection(connection_id) {
+            return Ok(());
+          }
         }
         EditorMessageBody::IsAlive => {
-          handle_message(&self.context, message.id, || Ok(EditorMessageBody::Success(message.id)));
+          handle_message(&context, message.id, || Ok(EditorMessageBody::Success(message.id)));
         }
         EditorMessageBody::CanFormat(path) => {
           // do this on the same thread
           let result = self.can_format(&path).await;
-          handle_message(&self.context, message.id, || {
+          handle_message(&context, message.id, || {
             result.map(|val| EditorMessageBody::CanFormatResponse(message.id, if val { 1 } else { 0 }))
           });
         }
         EditorMessageBody::CanFormatResponse(_, _) => {
-          send_error_response(&self.context, message.id, anyhow!("CLI cannot handle a CanFormatResponse message."));
+          send_error_response(&context, message.id, anyhow!("CLI cannot handle a CanFormatResponse message."));
         }
         EditorMessageBody::Format(body) => {
+          if self.plugins_scope.is_none() {
+            self.ensure_latest_config().await?;
+          }
+          let scope = self.plugins_scope.clone().unwrap();
+          self.format(&context, message.id, scope, body);
+        }
+        EditorMessageBody::CanFormatWithConfig(config_path, file_path) => {
+          let result = self.can_format_with_config(&config_path, &file_path).await;
+          handle_message(&context, message.id, || {
+            result.map(|val| EditorMessageBody::CanFormatResponse(message.id, if val { 1 } else { 0 }))
+          });
+        }
+        EditorMessageBody::FormatWithConfig(config_path, body) => match self.get_scope_for_config(&config_path).await {
+          Ok(scope) => self.format(&context, message.id, scope, body),
+          Err(err) => send_error_response(&context, message.id, err),
+        },
+        EditorMessageBody::FormatResponse(_, _) => {
+          send_error_response(&context, message.id, anyhow!("CLI cannot handle a FormatResponse message."));
+        }
+        EditorMessageBody::FormatBatch(files) => {
           if self.plugins_scope.is_none() {
             self.ensure_latest_config().await?;
           }
           let token = Arc::new(CancellationToken::new());
-          let request = HostFormatRequest {
-            file_path: body.file_path,
-            range: body.range,
-            override_config: if body.override_config.is_empty() {
-              Default::default()
-            } else {
-              match serde_json::from_slice(&body.override_config) {
-                Ok(config) => config,
-                Err(err) => {
-                  send_error_response(&self.context, message.id, anyhow!("Error deserializing override config. {:#}", err));
-                  continue;
-                }
-              }
-            },
-            file_bytes: body.file_bytes,
-            token: token.clone(),
-          };
-
-          let token_storage_guard = self.context.cancellation_tokens.store_with_owned_guard(message.id, token.clone());
-          let context = self.context.clone();
+          let token_storage_guard = context.cancellation_tokens.store_with_owned_guard(message.id, token.clone());
+          let in_flight_guard = InFlightGuard::new(&context.in_flight_format_batches);
+          let context = context.clone();
           let concurrency_limiter = self.concurrency_limiter.clone();
           let scope = self.plugins_scope.clone().unwrap();
           let _ignore = dprint_core::async_runtime::spawn(async move {
-            let _permit = concurrency_limiter.acquire().await;
-            if token.is_cancelled() {
-              return;
-            }
-
-            let result = scope.format(request).await;
+            let _in_flight_guard = in_flight_guard;
+            let futures = files.into_iter().enumerate().map(|(index, body)| {
+              let context = context.clone();
+              let concurrency_limiter = concurrency_limiter.clone();
+              let scope = scope.clone();
+              let token = token.clone();
+              async move {
+                let result = match parse_override_config(&body.override_config) {
+                  Ok(override_config) => {
+                    let _permit = concurrency_limiter.acquire().await;
+                    if token.is_cancelled() {
+                      return;
+                    }
+                    scope
+                      .format(HostFormatRequest {
+                        file_path: body.file_path,
+                        range: body.range,
+                        override_config,
+                        file_bytes: body.file_bytes,
+                        token: token.clone(),
+                      })
+                      .await
+                  }
+                  Err(err) => Err(err),
+                };
+                if token.is_cancelled() {
+                  return;
+                }
+                let result = match result {
+                  Ok(Some(text)) => FormatBatchItemResult::Change(text),
+                  Ok(None) => FormatBatchItemResult::NoChange,
+                  Err(err) => FormatBatchItemResult::Error(format!("{:#}", err).into_bytes()),
+                };
+                send_response_body(&context, EditorMessageBody::FormatBatchItemResponse(message.id, index as u32, result));
+              }
+            });
+            future::join_all(futures).await;
             drop(token_storage_guard);
-            if token.is_cancelled() {
-              return;
+            if !token.is_cancelled() {
+              send_response_body(&context, EditorMessageBody::Success(message.id));
             }
-
-            let body = match result {
-              Ok(text) => EditorMessageBody::FormatResponse(message.id, text),
-              Err(err) => EditorMessageBody::Error(message.id, format!("{:#}", err).into_bytes()),
-            };
-            send_response_body(&context, body);
           });
         }
-        EditorMessageBody::FormatResponse(_, _) => {
-          send_error_response(&self.context, message.id, anyhow!("CLI cannot handle a FormatResponse message."));
+        EditorMessageBody::FormatBatchItemResponse(_, _, _) => {
+          send_error_response(&context, message.id, anyhow!("CLI cannot handle a FormatBatchItemResponse message."));
+        }
+        EditorMessageBody::ResolvedConfig(path) => {
+          let result = self.resolved_config(&path).await;
+          handle_message(&context, message.id, || {
+            result.map(|data| EditorMessageBody::ResolvedConfigResponse(message.id, data))
+          });
+        }
+        EditorMessageBody::ResolvedConfigResponse(_, _) => {
+          send_error_response(&context, message.id, anyhow!("CLI cannot handle a ResolvedConfigResponse message."));
+        }
+        EditorMessageBody::Handshake(body) => {
+          context
+            .notify_config_changed
+            .set(body.features.iter().any(|feature| feature == CONFIG_CHANGED_FEATURE));
+          handle_message(&context, message.id, || {
+            Ok(EditorMessageBody::HandshakeResponse(
+              message.id,
+              HandshakeMessageBody {
+                schema_version: SCHEMA_VERSION,
+                features: [
+                  FORMAT_BATCH_FEATURE,
+                  RESOLVED_CONFIG_FEATURE,
+                  CONFIG_CHANGED_FEATURE,
+                  FILE_ASSOCIATIONS_FEATURE,
+                  CONFIG_PATH_FEATURE,
+                  STATUS_FEATURE,
+                ]
+                .iter()
+                .map(|feature| feature.to_string())
+                .collect(),
+              },
+            ))
+          });
+        }
+        EditorMessageBody::HandshakeResponse(_, _) => {
+          send_error_response(&context, message.id, anyhow!("CLI cannot handle a HandshakeResponse message."));
+        }
+        EditorMessageBody::FileAssociations => {
+          let result = self.file_associations().await;
+          handle_message(&context, message.id, || {
+            result.map(|data| EditorMessageBody::FileAssociationsResponse(message.id, data))
+          });
+        }
+        EditorMessageBody::FileAssociationsResponse(_, _) => {
+          send_error_response(&context, message.id, anyhow!("CLI cannot handle a FileAssociationsResponse message."));
+        }
+        EditorMessageBody::ConfigChanged => {
+          send_error_response(&context, message.id, anyhow!("CLI cannot handle a ConfigChanged message."));
+        }
+        EditorMessageBody::Status => {
+          handle_message(&context, message.id, || {
+            self.status().map(|data| EditorMessageBody::StatusResponse(message.id, data))
+          });
+        }
+        EditorMessageBody::StatusResponse(_, _) => {
+          send_error_response(&context, message.id, anyhow!("CLI cannot handle a StatusResponse message."));
         }
         EditorMessageBody::CancelFormat(message_id) => {
-          if let Some(token) = self.context.cancellation_tokens.take(message_id) {
+          if let Some(token) = context.cancellation_tokens.take(message_id) {
             token.cancel();
           }
         }
         EditorMessageBody::Unknown(message_kind, _) => {
-          send_error_response(&self.context, message.id, anyhow!("Unknown message with kind: {}", message_kind));
+          send_error_response(&context, message.id, anyhow!("Unknown message with kind: {}", message_kind));
         }
       }
     }
   }
 
+  fn format(&self, context: &Rc<EditorContext>, message_id: u32, scope: Rc<PluginsScope<TEnvironment>>, body: FormatEditorMessageBody) {
+    let token = Arc::new(CancellationToken::new());
+    let request = HostFormatRequest {
+      file_path: body.file_path,
+      range: body.range,
+      override_config: match parse_override_config(&body.override_config) {
+        Ok(config) => config,
+        Err(err) => {
+          send_error_response(context, message_id, err);
+          return;
+        }
+      },
+      file_bytes: body.file_bytes,
+      token: token.clone(),
+    };
+
+    let token_storage_guard = context.cancellation_tokens.store_with_owned_guard(message_id, token.clone());
+    let in_flight_guard = InFlightGuard::new(&context.in_flight_formats);
+    let context = context.clone();
+    let concurrency_limiter = self.concurrency_limiter.clone();
+    let _ignore = dprint_core::async_runtime::spawn(async move {
+      let _in_flight_guard = in_flight_guard;
+      let _permit = concurrency_limiter.acquire().await;
+      if token.is_cancelled() {
+        return;
+      }
+
+      let result = scope.format(request).await;
+      drop(token_storage_guard);
+      if token.is_cancelled() {
+        return;
+      }
+
+      let body = match result {
+        Ok(text) => EditorMessageBody::FormatResponse(message_id, text),
+        Err(err) => EditorMessageBody::Error(message_id, format!("{:#}", err).into_bytes()),
+      };
+      send_response_body(&context, body);
+    });
+  }
+
   async fn can_format(&mut self, file_path: &Path) -> Result<bool> {
     self.ensure_latest_config().await?;
     // canonicalize the file path, then check if it's in the list of file paths.
@@ -258,6 +541,161 @@ impl<'a, TEnvironment: Environment> EditorService<'a, TEnvironment> {
     Ok(self.plugins_scope.as_ref().map(|s| s.can_format_for_editor(&file_path)).unwrap_or(false))
   }
 
+  async fn can_format_with_config(&mut self, config_path: &Path, file_path: &Path) -> Result<bool> {
+    let scope = self.get_scope_for_config(config_path).await?;
+    let file_path = self
+      .environment
+      .canonicalize(file_path)
+      .map(|p| p.into_path_buf())
+      .unwrap_or(file_path.to_path_buf());
+    log_debug!(self.environment, "Checking can format with {}: {}", config_path.display(), file_path.display());
+    Ok(scope.can_format_for_editor(&file_path))
+  }
+
+  /// Gets the resolved configuration of the plugins that will format the file, in order.
+  async fn resolved_config(&mut self, file_path: &Path) -> Result<Vec<u8>> {
+    #[derive(serde::Serialize)]
+    struct ResolvedFileConfig {
+      plugins: Vec<ResolvedPluginConfig>,
+    }
+
+    #[derive(serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ResolvedPluginConfig {
+      name: String,
+      version: String,
+      config_key: String,
+      config: serde_json::Value,
+    }
+
+    self.ensure_latest_config().await?;
+    let file_path = self
+      .environment
+      .canonicalize(file_path)
+      .map(|p| p.into_path_buf())
+      .unwrap_or(file_path.to_path_buf());
+    let scope = self.plugins_scope.clone().unwrap();
+    let plugin_names = if scope.can_format_for_editor(&file_path) {
+      scope.plugin_name_maps.get_plugin_names_from_file_path(&file_path)
+    } else {
+      Vec::new()
+    };
+    let mut plugins = Vec::with_capacity(plugin_names.len());
+    for plugin_name in plugin_names {
+      let plugin = scope.get_plugin(&plugin_name);
+      let initialized_plugin = match plugin.get_or_create_checking_config_diagnostics(self.environment).await? {
+        GetPluginResult::HadDiagnostics(count) => bail!("Plugin {} had {} configuration error(s).", plugin_name, count),
+        GetPluginResult::Success(plugin) => plugin,
+      };
+      let config_text = initialized_plugin.resolved_config().await?;
+      plugins.push(ResolvedPluginConfig {
+        name: plugin.info().name.to_string(),
+        version: plugin.info().version.to_string(),
+        config_key: plugin.info().config_key.to_string(),
+        config: serde_json::from_str(&config_text)?,
+      });
+    }
+    Ok(serde_json::to_vec(&ResolvedFileConfig { plugins })?)
+  }
+
+  /// Gets the file patterns of the config file and the files each plugin matches.
+  async fn file_associations(&mut self) -> Result<Vec<u8>> {
+    #[derive(serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct FileAssociations {
+      base_path: String,
+      includes: Option<Vec<String>>,
+      excludes: Option<Vec<String>>,
+      plugins: Vec<PluginFileAssociations>,
+    }
+
+    #[derive(serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct PluginFileAssociations {
+      name: String,
+      config_key: String,
+      file_extensions: Vec<String>,
+      file_names: Vec<String>,
+      associations: Option<Vec<String>>,
+    }
+
+    let config = self.ensure_latest_config().await?;
+    let scope = self.plugins_scope.clone().unwrap();
+    Ok(serde_json::to_vec(&FileAssociations {
+      base_path: config.base_path.display().to_string(),
+      includes: config.includes.clone(),
+      excludes: config.excludes.clone(),
+      plugins: scope
+        .plugins
+        .values()
+        .map(|plugin| PluginFileAssociations {
+          name: plugin.info().name.to_string(),
+          config_key: plugin.info().config_key.to_string(),
+          file_extensions: plugin.file_matching.file_extensions.clone(),
+          file_names: plugin.file_matching.file_names.clone(),
+          associations: plugin.associations.clone(),
+        })
+        .collect(),
+    })?)
+  }
+
+  /// Gets the state of the service without resolving anything so that
+  /// it still responds when the plugins are stuck resolving or formatting.
+  fn status(&self) -> Result<Vec<u8>> {
+    #[derive(serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct EditorServiceStatus {
+      cli_version: String,
+      config_path: Option<String>,
+      plugins: Vec<EditorServicePluginStatus>,
+      memory_usage: Option<u64>,
+      clients: usize,
+      in_flight: EditorServiceInFlightStatus,
+    }
+
+    #[derive(serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct EditorServicePluginStatus {
+      name: String,
+      version: String,
+      config_key: String,
+    }
+
+    #[derive(serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct EditorServiceInFlightStatus {
+      formats: usize,
+      format_batches: usize,
+    }
+
+    let scope = self.plugins_scope.as_ref();
+    Ok(serde_json::to_vec(&EditorServiceStatus {
+      cli_version: self.environment.cli_version(),
+      config_path: scope
+        .and_then(|scope| scope.config.as_ref())
+        .map(|config| config.resolved_path.source.display()),
+      plugins: scope
+        .map(|scope| {
+          scope
+            .plugins
+            .values()
+            .map(|plugin| EditorServicePluginStatus {
+              name: plugin.info().name.to_string(),
+              version: plugin.info().version.to_string(),
+              config_key: plugin.info().config_key.to_string(),
+            })
+            .collect()
+        })
+        .unwrap_or_default(),
+      memory_usage: self.environment.memory_usage(),
+      clients: self.connections.len(),
+      in_flight: EditorServiceInFlightStatus {
+        formats: self.connections.values().map(|context| context.in_flight_formats.get()).sum(),
+        format_batches: self.connections.values().map(|context| context.in_flight_format_batches.get()).sum(),
+      },
+    })?)
+  }
+
   async fn ensure_latest_config(&mut self) -> Result<Rc<ResolvedConfig>> {
     let _update_permit = self.config_semaphore.acquire().await;
     let config = Rc::new(resolve_config_from_args(self.args, self.environment).await?);
@@ -265,20 +703,108 @@ impl<'a, TEnvironment: Environment> EditorService<'a, TEnvironment> {
     let last_config = self.plugins_scope.as_ref().and_then(|scope| scope.config.as_ref());
     let has_config_changed = last_config.is_none() || last_config.unwrap() != &config || self.plugins_scope.is_none();
     if has_config_changed {
-      self.plugins_scope.take();
-      let tokens = self.context.cancellation_tokens.take_all();
-      for token in tokens.values() {
-        token.cancel();
-      }
-      self.plugin_resolver.clear_and_shutdown_initialized().await;
+      self.clear_plugins_scopes().await;
 
       let scope = resolve_plugins_scope(config.clone(), self.environment, self.plugin_resolver).await?;
       scope.ensure_no_global_config_diagnostics()?;
       self.plugins_scope = Some(Rc::new(scope));
+      self.config_file_hash = self.get_config_file_hash(&config);
     }
 
     Ok(self.plugins_scope.as_ref().unwrap().config.clone().unwrap())
   }
+
+  /// Gets the plugins scope for an explicitly provided config file, which is
+  /// cached separately from the main plugins scope until the config changes.
+  async fn get_scope_for_config(&mut self, config_path: &Path) -> Result<Rc<PluginsScope<TEnvironment>>> {
+    let _update_permit = self.config_semaphore.acquire().await;
+    let config_path = self.environment.canonicalize(config_path)?;
+    let Some(base_path) = config_path.parent() else {
+      bail!("Config file path did not have a parent directory: {}", config_path.display());
+    };
+    let config = resolve_config_from_path(
+      &ResolvedConfigPath {
+        resolved_path: ResolvedPath::local(config_path.clone()),
+        base_path,
+      },
+      self.environment,
+    )
+    .await?;
+
+    if let Some(scope) = self.plugins_scope_by_config.get(&config_path) {
+      if scope.config.as_deref() == Some(&config) {
+        return Ok(scope.clone());
+      }
+      // shutting down the plugins invalidates every scope
+      self.clear_plugins_scopes().await;
+    }
+
+    let scope = resolve_plugins_scope(Rc::new(config), self.environment, self.plugin_resolver).await?;
+    scope.ensure_no_global_config_diagnostics()?;
+    let scope = Rc::new(scope);
+    self.plugins_scope_by_config.insert(config_path, scope.clone());
+    Ok(scope)
+  }
+
+  /// Cancels any in progress formatting and shuts down the plugins, which
+  /// causes all the plugin scopes to be resolved again on their next use.
+  async fn clear_plugins_scopes(&mut self) {
+    self.plugins_scope.take();
+    self.plugins_scope_by_config.clear();
+    for context in self.connections.values() {
+      context.cancel_all();
+    }
+    self.plugin_resolver.clear_and_shutdown_initialized().await;
+  }
+
+  fn get_config_file_hash(&self, config: &ResolvedConfig) -> Option<(PathBuf, u64)> {
+    if !config.resolved_path.is_local() {
+      return None;
+    }
+    let file_path = config.resolved_path.file_path.clone().into_path_buf();
+    let bytes = self.environment.read_file_bytes(&file_path).ok()?;
+    Some((file_path, get_bytes_hash(&bytes)))
+  }
+
+  /// Notifies the clients that opted in when the config file changed since it
+  /// was last checked so that they can drop any cached state. The plugins are
+  /// resolved again with the new config on the next "can format" message.
+  fn check_config_changed(&mut self) {
+    if !self.connections.values().any(|context| context.notify_config_changed.get()) {
+      return;
+    }
+    let Some((file_path, hash)) = &self.config_file_hash else {
+      return;
+    };
+    let current_hash = self.environment.read_file_bytes(file_path).ok().map(|bytes| get_bytes_hash(&bytes));
+    if current_hash == Some(*hash) {
+      return;
+    }
+    log_debug!(self.environment, "Config file changed: {}", file_path.display());
+    self.config_file_hash = current_hash.map(|hash| (file_path.clone(), hash));
+    for context in self.connections.values() {
+      if context.notify_config_changed.get() {
+        send_response_body(context, EditorMessageBody::ConfigChanged);
+      }
+    }
+  }
+
+  /// Removes the client's connection, cancelling its in progress formatting.
+  /// Returns true when no clients remain connected.
+  fn remove_connection(&mut self, connection_id: u32) -> bool {
+    if let Some(context) = self.connections.remove(&connection_id) {
+      context.cancel_all();
+    }
+    self.connections.is_empty()
+  }
+}
+
+fn parse_override_config(bytes: &[u8]) -> Result<ConfigKeyMap> {
+  if bytes.is_empty() {
+    Ok(Default::default())
+  } else {
+    serde_json::from_slice(bytes).map_err(|err| anyhow!("Error deserializing override config. {:#}", err))
+  }
 }
 
 fn handle_message(context: &EditorContext, original_message_id: u32, action: impl FnOnce() -> Result<EditorMessageBody>) {
@@ -298,9 +824,8 @@ fn send_response_body(context: &EditorContext, body: EditorMessageBody) {
     id: context.id_generator.next(),
     body,
   };
-  if let Err(err) = context.writer.send(message) {
-    panic!("Receiver dropped. {:#}", err);
-  }
+  // this only fails when the client disconnected
+  let _ = context.writer.send(message);
 }
 
 #[cfg(test)]
@@ -318,6 +843,7 @@ mod test {
   use dprint_core::plugins::FormatRange;
   use dprint_core::plugins::FormatResult;
   use pretty_assertions::assert_eq;
+  use std::cell::Cell;
   use std::io::Read;
   use std::io::Write;
   use std::path::Path;
@@ -335,7 +861,9 @@ mod test {
 
   use super::messages::EditorMessage;
   use super::messages::EditorMessageBody;
+  use super::messages::FormatBatchItemResult;
   use super::messages::FormatEditorMessageBody;
+  use super::messages::HandshakeMessageBody;
 
   #[test]
   fn should_output_editor_plugin_info() {
@@ -354,7 +882,7 @@ mod test {
     final_output
       .push_str(r#"{"name":"test-plugin","version":"0.2.0","configKey":"test-plugin","fileExtensions":["txt"],"fileNames":[],"configSchemaUrl":"https://plugins.dprint.dev/test/schema.json","helpUrl":"https://dprint.dev/plugins/test"},"#);
     final_output.push_str(r#"{"name":"test-process-plugin","version":"0.1.0","configKey":"testProcessPlugin","fileExtensions":["txt_ps"],"fileNames":["test-process-plugin-exact-file"],"helpUrl":"https://dprint.dev/plugins/test-process"}]}"#);
-    assert_eq!(environment.take_stdout_messages(), vec![final_output]);
+    assert_eq!(environment.take_stdout_messages(), vec![final_output.clone()]);
     let mut stderr_messages = environment.take_stderr_messages();
     stderr_messages.sort();
     assert_eq!(
@@ -364,12 +892,23 @@ mod test {
         "Extracting zip for test-process-plugin"
       ]
     );
+
+    // should output the same from the cache the second time
+    assert!(environment.path_exists(environment.get_cache_dir().join("editor-info")));
+    run_test_cli(vec!["editor-info"], &environment).unwrap();
+    assert_eq!(environment.take_stdout_messages(), vec![final_output]);
+    assert!(environment.take_stderr_messages().is_empty());
   }
 
   enum MessageResponseChannel {
     Success(oneshot::Sender<Result<()>>),
     Format(oneshot::Sender<Result<Option<Vec<u8>>>>),
     CanFormat(oneshot::Sender<Result<bool>>),
+    FormatBatch(tokio::sync::mpsc::UnboundedSender<Result<(u32, FormatBatchItemResult)>>),
+    ResolvedConfig(oneshot::Sender<Result<serde_json::Value>>),
+    Handshake(oneshot::Sender<Result<HandshakeMessageBody>>),
+    FileAssociations(oneshot::Sender<Result<serde_json::Value>>),
+    Status(oneshot::Sender<Result<serde_json::Value>>),
   }
 
   #[derive(Clone)]
@@ -377,6 +916,7 @@ mod test {
     writer: Rc<SingleThreadMessageWriter<EditorMessage>>,
     id_generator: Rc<IdGenerator>,
     messages: RcIdStore<MessageResponseChannel>,
+    config_changed_count: Rc<Cell<usize>>,
   }
 
   impl EditorServiceCommunicator {
@@ -388,6 +928,7 @@ mod test {
         writer,
         id_generator: Default::default(),
         messages: Default::default(),
+        config_changed_count: Default::default(),
       };
 
       let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
@@ -402,9 +943,10 @@ mod test {
       });
 
       let messages = communicator.messages.clone();
+      let config_changed_count = communicator.config_changed_count.clone();
       dprint_core::async_runtime::spawn(async move {
         while let Some(Ok(message)) = rx.recv().await {
-          if let Err(_) = handle_stdout_message(message, &messages) {
+          if let Err(_) = handle_stdout_message(message, &messages, &config_changed_count) {
             break;
           }
         }
@@ -451,6 +993,122 @@ mod test {
         .await
     }
 
+    pub async fn check_file_with_config(&self, config_path: impl AsRef<Path>, file_path: impl AsRef<Path>) -> Result<bool> {
+      let (tx, rx) = oneshot::channel::<Result<bool>>();
+
+      self
+        .send_message(
+          EditorMessageBody::CanFormatWithConfig(config_path.as_ref().to_path_buf(), file_path.as_ref().to_path_buf()),
+          MessageResponseChannel::CanFormat(tx),
+          rx,
+          Arc::new(CancellationToken::new()),
+        )
+        .await
+    }
+
+    pub async fn format_text_with_config(&self, config_path: impl AsRef<Path>, file_path: impl AsRef<Path>, file_bytes: Vec<u8>) -> FormatResult {
+      let (tx, rx) = oneshot::channel::<Result<Option<Vec<u8>>>>();
+
+      self
+        .send_message(
+          EditorMessageBody::FormatWithConfig(
+            config_path.as_ref().to_path_buf(),
+            FormatEditorMessageBody {
+              file_path: file_path.as_ref().to_path_buf(),
+              file_bytes,
+              override_config: Vec::new(),
+              range: None,
+            },
+          ),
+          MessageResponseChannel::Format(tx),
+          rx,
+          Arc::new(CancellationToken::new()),
+        )
+        .await
+    }
+
+    pub async fn handshake(&self, features: &[&str]) -> Result<HandshakeMessageBody> {
+      let (tx, rx) = oneshot::channel::<Result<HandshakeMessageBody>>();
+
+      self
+        .send_message(
+          EditorMessageBody::Handshake(HandshakeMessageBody {
+            schema_version: 6,
+            features: features.iter().map(|feature| feature.to_string()).collect(),
+          }),
+          MessageResponseChannel::Handshake(tx),
+          rx,
+          Arc::new(CancellationToken::new()),
+        )
+        .await
+    }
+
+    pub async fn file_associations(&self) -> Result<serde_json::Value> {
+      let (tx, rx) = oneshot::channel::<Result<serde_json::Value>>();
+
+      self
+        .send_message(
+          EditorMessageBody::FileAssociations,
+          MessageResponseChannel::FileAssociations(tx),
+          rx,
+          Arc::new(CancellationToken::new()),
+        )
+        .await
+    }
+
+    pub async fn status(&self) -> Result<serde_json::Value> {
+      let (tx, rx) = oneshot::channel::<Result<serde_json::Value>>();
+
+      self
+        .send_message(
+          EditorMessageBody::Status,
+          MessageResponseChannel::Status(tx),
+          rx,
+          Arc::new(CancellationToken::new()),
+        )
+        .await
+    }
+
+    pub async fn resolved_config(&self, file_path: impl AsRef<Path>) -> Result<serde_json::Value> {
+      let (tx, rx) = oneshot::channel::<Result<serde_json::Value>>();
+
+      self
+        .send_message(
+          EditorMessageBody::ResolvedConfig(file_path.as_ref().to_path_buf()),
+          MessageResponseChannel::ResolvedConfig(tx),
+          rx,
+          Arc::new(CancellationToken::new()),
+        )
+        .await
+    }
+
+    pub async fn format_batch(&self, files: Vec<FormatEditorMessageBody>) -> Result<Vec<(u32, FormatBatchItemResult)>> {
+      let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+      let message_id = self.id_generator.next();
+      self.messages.store(message_id, MessageResponseChannel::FormatBatch(tx));
+      self.writer.send(EditorMessage {
+        id: message_id,
+        body: EditorMessageBody::FormatBatch(files),
+      })?;
+      // the channel is closed once the batch completes
+      let mut items = Vec::new();
+      while let Some(item) = rx.recv().await {
+        items.push(item?);
+      }
+      items.sort_by_key(|(index, _)| *index);
+      Ok(items)
+    }
+
+    pub async fn wait_config_changed(&self) {
+      for _ in 0..100 {
+        if self.config_changed_count.take() > 0 {
+          return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+      }
+      panic!("Timed out waiting for the config changed message.");
+    }
+
     pub async fn exit(&self) -> Result<()> {
       let (tx, rx) = oneshot::channel::<Result<()>>();
 
@@ -497,12 +1155,13 @@ mod test {
     }
   }
 
-  fn handle_stdout_message(message: EditorMessage, messages: &RcIdStore<MessageResponseChannel>) -> Result<()> {
+  fn handle_stdout_message(message: EditorMessage, messages: &RcIdStore<MessageResponseChannel>, config_changed_count: &Cell<usize>) -> Result<()> {
     match message.body {
       EditorMessageBody::Success(message_id) => match messages.take(message_id) {
         Some(MessageResponseChannel::Success(channel)) => {
           let _ignore = channel.send(Ok(()));
         }
+        Some(MessageResponseChannel::FormatBatch(_)) => {} // dropping closes the channel
         Some(_) => unreachable!(),
         None => {}
       },
@@ -518,6 +1177,21 @@ mod test {
           Some(MessageResponseChannel::CanFormat(channel)) => {
             let _ignore = channel.send(Err(err));
           }
+          Some(MessageResponseChannel::FormatBatch(channel)) => {
+            let _ignore = channel.send(Err(err));
+          }
+          Some(MessageResponseChannel::ResolvedConfig(channel)) => {
+            let _ignore = channel.send(Err(err));
+          }
+          Some(MessageResponseChannel::Handshake(channel)) => {
+            let _ignore = channel.send(Err(err));
+          }
+          Some(MessageResponseChannel::FileAssociations(channel)) => {
+            let _ignore = channel.send(Err(err));
+          }
+          Some(MessageResponseChannel::Status(channel)) => {
+            let _ignore = channel.send(Err(err));
+          }
           None => {}
         }
       }
@@ -535,6 +1209,45 @@ mod test {
         Some(_) => unreachable!(),
         None => {}
       },
+      EditorMessageBody::FormatBatchItemResponse(message_id, file_index, result) => match messages.take(message_id) {
+        Some(MessageResponseChannel::FormatBatch(channel)) => {
+          let _ignore = channel.send(Ok((file_index, result)));
+          messages.store(message_id, MessageResponseChannel::FormatBatch(channel));
+        }
+        Some(_) => unreachable!(),
+        None => {}
+      },
+      EditorMessageBody::ResolvedConfigResponse(message_id, data) => match messages.take(message_id) {
+        Some(MessageResponseChannel::ResolvedConfig(channel)) => {
+          let _ignore = channel.send(serde_json::from_slice(&data).map_err(|err| err.into()));
+        }
+        Some(_) => unreachable!(),
+        None => {}
+      },
+      EditorMessageBody::HandshakeResponse(message_id, body) => match messages.take(message_id) {
+        Some(MessageResponseChannel::Handshake(channel)) => {
+          let _ignore = channel.send(Ok(body));
+        }
+        Some(_) => unreachable!(),
+        None => {}
+      },
+      EditorMessageBody::FileAssociationsResponse(message_id, data) => match messages.take(message_id) {
+        Some(MessageResponseChannel::FileAssociations(channel)) => {
+          let _ignore = channel.send(serde_json::from_slice(&data).map_err(|err| err.into()));
+        }
+        Some(_) => unreachable!(),
+        None => {}
+      },
+      EditorMessageBody::StatusResponse(message_id, data) => match messages.take(message_id) {
+        Some(MessageResponseChannel::Status(channel)) => {
+          let _ignore = channel.send(serde_json::from_slice(&data).map_err(|err| err.into()));
+        }
+        Some(_) => unreachable!(),
+        None => {}
+      },
+      EditorMessageBody::ConfigChanged => {
+        config_changed_count.set(config_changed_count.get() + 1);
+      }
       _ => unreachable!(),
     }
 
@@ -572,6 +1285,20 @@ mod test {
         TestEnvironment::new().run_in_runtime(async move {
           let communicator = EditorServiceCommunicator::new(stdin, stdout);
 
+          assert_eq!(
+            communicator.handshake(&["configChangedNotification", "unknownFeature"]).await.unwrap(),
+            HandshakeMessageBody {
+              schema_version: 6,
+              features: vec![
+                "formatBatch".to_string(),
+                "resolvedConfig".to_string(),
+                "configChangedNotification".to_string(),
+                "fileAssociations".to_string(),
+                "configPath".to_string(),
+                "status".to_string()
+              ],
+            }
+          );
           assert_eq!(communicator.check_file(&txt_file_path).await.unwrap(), true);
           assert_eq!(communicator.check_file(&PathBuf::from("/non-existent.txt")).await.unwrap(), true);
           assert_eq!(communicator.check_file(&PathBuf::from("/ignored-dir/some-path.txt")).await.unwrap(), false);
@@ -856,6 +1583,118 @@ mod test {
             "te_test_ng_test"
           );
 
+          // test getting the status
+          assert_eq!(
+            communicator.status().await.unwrap(),
+            serde_json::json!({
+              "cliVersion": environment.cli_version(),
+              "configPath": "/dprint.json",
+              "plugins": [{
+                "name": "test-plugin",
+                "version": "0.2.0",
+                "configKey": "test-plugin"
+              }, {
+                "name": "test-process-plugin",
+                "version": "0.1.0",
+                "configKey": "testProcessPlugin"
+              }],
+              "memoryUsage": 1024,
+              "clients": 1,
+              "inFlight": {
+                "formats": 0,
+                "formatBatches": 0
+              }
+            })
+          );
+
+          // test getting the resolved config
+          assert_eq!(
+            communicator.resolved_config(&txt_file_path).await.unwrap(),
+            serde_json::json!({
+              "plugins": [{
+                "name": "test-plugin",
+                "version": "0.2.0",
+                "configKey": "test-plugin",
+                "config": {
+                  "ending": "formatted",
+                  "lineWidth": 120
+                }
+              }]
+            })
+          );
+          assert_eq!(
+            communicator.resolved_config(&ignored_file_path).await.unwrap(),
+            serde_json::json!({ "plugins": [] })
+          );
+
+          // test batch formatting
+          let batch_file = |file_path: &str, text: &str, override_config: &[u8]| FormatEditorMessageBody {
+            file_path: PathBuf::from(file_path),
+            range: None,
+            override_config: override_config.to_vec(),
+            file_bytes: text.to_string().into_bytes(),
+          };
+          assert_eq!(
+            communicator
+              .format_batch(vec![
+                batch_file("/file.txt", "testing", b""),
+                batch_file("/file.txt_ps", "testing", b""),
+                batch_file("/file.txt", "testing_formatted", b""),
+                batch_file("/file.txt", "should_error", b""),
+                batch_file("/file.txt_ps", "testing", br#"{"ending":"test"}"#),
+                batch_file("/file.txt", "testing", b"{"),
+              ])
+              .await
+              .unwrap(),
+            vec![
+              (0, FormatBatchItemResult::Change(b"testing_formatted".to_vec())),
+              (1, FormatBatchItemResult::Change(b"testing_formatted_process".to_vec())),
+              (2, FormatBatchItemResult::NoChange),
+              (3, FormatBatchItemResult::Error(b"Did error.".to_vec())),
+              (4, FormatBatchItemResult::Change(b"testing_test".to_vec())),
+              (
+                5,
+                FormatBatchItemResult::Error(b"Error deserializing override config. EOF while parsing an object at line 1 column 1".to_vec())
+              ),
+            ]
+          );
+
+          // test using an explicitly provided config file
+          let sub_config_path = PathBuf::from("/sub/dprint.json");
+          environment
+            .write_file(
+              &sub_config_path,
+              r#"{
+                    "includes": ["**/*.txt"],
+                    "test-plugin": {
+                        "ending": "sub_ending"
+                    },
+                    "plugins": ["https://plugins.dprint.dev/test-plugin.wasm"]
+                }"#,
+            )
+            .unwrap();
+          assert_eq!(communicator.check_file_with_config(&sub_config_path, "/sub/file.txt").await.unwrap(), true);
+          assert_eq!(communicator.check_file_with_config(&sub_config_path, "/sub/file.ts").await.unwrap(), false);
+          assert_eq!(
+            bytes_to_string(
+              communicator
+                .format_text_with_config(&sub_config_path, "/sub/file.txt", "testing".to_string().into_bytes())
+                .await
+                .unwrap()
+                .unwrap()
+            ),
+            "testing_sub_ending"
+          );
+          assert_eq!(
+            communicator
+              .format_text_with_config("/non-existent/dprint.json", "/sub/file.txt", "testing".to_string().into_bytes())
+              .await
+              .is_err(),
+            true
+          );
+          // the main config is still used for other messages
+          assert_eq!(communicator.check_file(&ts_file_path).await.unwrap(), true);
+
           // write a new file and make sure the service picks up the changes
           environment
             .write_file(
@@ -870,6 +1709,7 @@ mod test {
             )
             .unwrap();
 
+          communicator.wait_config_changed().await;
           assert_eq!(communicator.check_file(&ts_file_path).await.unwrap(), false); // shouldn't match anymore
           assert_eq!(communicator.check_file(&txt_file_path).await.unwrap(), true); // still ok
           assert_eq!(
@@ -895,6 +1735,54 @@ mod test {
     result.join().unwrap();
   }
 
+  #[test]
+  fn should_not_notify_config_changed_without_feature() {
+    let txt_file_path = PathBuf::from("/file.txt");
+    let environment = TestEnvironmentBuilder::new()
+      .add_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_includes("**/*.txt");
+      })
+      .write_file(&txt_file_path, "")
+      .initialize()
+      .build();
+    let stdin = environment.stdin_writer();
+    let stdout = environment.stdout_reader();
+
+    let result = std::thread::spawn({
+      let environment = environment.clone();
+      move || {
+        TestEnvironment::new().run_in_runtime(async move {
+          let communicator = EditorServiceCommunicator::new(stdin, stdout);
+          // older clients don't know about the config changed message
+          communicator.handshake(&[]).await.unwrap();
+          assert_eq!(communicator.check_file(&txt_file_path).await.unwrap(), true);
+
+          environment
+            .write_file(
+              PathBuf::from("./dprint.json"),
+              r#"{
+                    "includes": ["**/*.ts"],
+                    "plugins": ["https://plugins.dprint.dev/test-plugin.wasm"]
+                }"#,
+            )
+            .unwrap();
+          tokio::time::sleep(super::CONFIG_POLL_INTERVAL * 2).await;
+          assert_eq!(communicator.config_changed_count.get(), 0);
+          // the new config is still picked up on the next message
+          assert_eq!(communicator.check_file(&txt_file_path).await.unwrap(), false);
+
+          communicator.exit().await.unwrap();
+        });
+      }
+    });
+
+    let pid = std::process::id().to_string();
+    run_test_cli(vec!["editor-service", "--parent-pid", &pid], &environment).unwrap();
+
+    result.join().unwrap();
+  }
+
   #[test]
   fn should_format_with_config_associations_for_editor_service() {
     let file_path1 = "/file1.txt";
@@ -944,6 +1832,27 @@ mod test {
         TestEnvironment::new().run_in_runtime(async move {
           let communicator = EditorServiceCommunicator::new(stdin, stdout);
 
+          assert_eq!(
+            communicator.file_associations().await.unwrap(),
+            serde_json::json!({
+              "basePath": "/",
+              "includes": ["**/*"],
+              "excludes": null,
+              "plugins": [{
+                "name": "test-plugin",
+                "configKey": "test-plugin",
+                "fileExtensions": ["txt"],
+                "fileNames": [],
+                "associations": ["**/*.{txt,txt_ps}", "some_file_name", "test-process-plugin-exact-file"]
+              }, {
+                "name": "test-process-plugin",
+                "configKey": "testProcessPlugin",
+                "fileExtensions": ["txt_ps"],
+                "fileNames": ["test-process-plugin-exact-file"],
+                "associations": ["**/*.{txt,txt_ps,other}", "test-process-plugin-exact-file"]
+              }]
+            })
+          );
           assert_eq!(communicator.check_file(&file_path1).await.unwrap(), true);
           assert_eq!(communicator.check_file(&file_path2).await.unwrap(), true);
           assert_eq!(communicator.check_file(&file_path3).await.unwrap(), true);
@@ -1027,6 +1936,60 @@ mod test {
     result.join().unwrap();
   }
 
+  #[cfg(unix)]
+  #[test]
+  fn should_share_editor_service_over_socket() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let socket_path = temp_dir.path().join("dprint.sock");
+    let environment = TestEnvironmentBuilder::new().build();
+
+    let result = std::thread::spawn({
+      let socket_path = socket_path.clone();
+      let environment = environment.clone();
+      move || {
+        TestEnvironment::new().run_in_runtime(async move {
+          let connect = || {
+            for _ in 0..100 {
+              if let Ok(stream) = std::os::unix::net::UnixStream::connect(&socket_path) {
+                return EditorServiceCommunicator::new(Box::new(stream.try_clone().unwrap()), Box::new(stream));
+              }
+              std::thread::sleep(Duration::from_millis(50));
+            }
+            panic!("Timed out connecting to the editor service.");
+          };
+          let expected_status = |clients: usize| {
+            serde_json::json!({
+              "cliVersion": environment.cli_version(),
+              "configPath": null,
+              "plugins": [],
+              "memoryUsage": 1024,
+              "clients": clients,
+              "inFlight": {
+                "formats": 0,
+                "formatBatches": 0
+              }
+            })
+          };
+          let communicator1 = connect();
+          let communicator2 = connect();
+          assert_eq!(communicator1.handshake(&[]).await.unwrap().schema_version, 6);
+          assert_eq!(communicator2.handshake(&[]).await.unwrap().schema_version, 6);
+          assert_eq!(communicator1.status().await.unwrap(), expected_status(2));
+
+          // closing one client keeps the service running for the other
+          communicator1.exit().await.unwrap();
+          assert_eq!(communicator2.status().await.unwrap(), expected_status(1));
+          communicator2.exit().await.unwrap();
+        });
+      }
+    });
+
+    run_test_cli(vec!["editor-service", "--socket", &socket_path.to_string_lossy()], &environment).unwrap();
+
+    result.join().unwrap();
+    assert_eq!(environment.take_stderr_messages(), vec![format!("Listening on {}", socket_path.display())]);
+  }
+
   fn bytes_to_string(bytes: Vec<u8>) -> String {
     String::from_utf8(bytes).unwrap()
   }
@@ -0,0 +1,91 @@
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+
+use anyhow::Result;
+use dprint_core::communication::MessageReader;
+use dprint_core::communication::MessageWriter;
+use dprint_core::communication::SingleThreadMessageWriter;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::environment::Environment;
+use crate::utils::LocalSocketListener;
+
+use super::messages::EditorMessage;
+
+/// Events from the clients connected to the editor service.
+pub enum ConnectionEvent {
+  Connected(u32, SingleThreadMessageWriter<EditorMessage>),
+  Message(u32, EditorMessage),
+  Disconnected(u32),
+}
+
+/// Communicates with a single client over stdin and stdout.
+pub fn start_stdio_transport<TEnvironment: Environment>(environment: &TEnvironment, tx: UnboundedSender<ConnectionEvent>) {
+  let writer = SingleThreadMessageWriter::for_stdout(MessageWriter::new(environment.stdout()));
+  start_connection(0, environment.stdin(), writer, environment, tx);
+}
+
+/// Listens for any number of clients on a unix socket or windows named pipe.
+pub fn start_socket_transport<TEnvironment: Environment>(socket_path: &str, environment: &TEnvironment, tx: UnboundedSender<ConnectionEvent>) -> Result<()> {
+  let mut listener = LocalSocketListener::bind(socket_path)?;
+  log_stderr_info!(environment, "Listening on {}", socket_path);
+  let environment = environment.clone();
+  dprint_core::async_runtime::spawn(async move {
+    let mut next_id = 0;
+    while !tx.is_closed() {
+      let (reader, writer) = match listener.accept().await {
+        Ok(streams) => streams,
+        Err(err) => {
+          log_error!(environment, "Editor service failed accepting a connection: {:#}", err);
+          return;
+        }
+      };
+      start_socket_connection(next_id, reader, writer, &environment, tx.clone());
+      next_id += 1;
+    }
+  });
+  Ok(())
+}
+
+fn start_socket_connection<TEnvironment: Environment>(
+  id: u32,
+  reader: impl Read + Unpin + Send + 'static,
+  writer: impl Write + Unpin + Send + 'static,
+  environment: &TEnvironment,
+  tx: UnboundedSender<ConnectionEvent>,
+) {
+  // unlike with stdout, failing to write only means this client went away
+  let writer = SingleThreadMessageWriter::for_stdin(MessageWriter::new(writer));
+  start_connection(id, reader, writer, environment, tx);
+}
+
+fn start_connection<TEnvironment: Environment>(
+  id: u32,
+  reader: impl Read + Unpin + Send + 'static,
+  writer: SingleThreadMessageWriter<EditorMessage>,
+  environment: &TEnvironment,
+  tx: UnboundedSender<ConnectionEvent>,
+) {
+  if tx.send(ConnectionEvent::Connected(id, writer)).is_err() {
+    return; // service shut down
+  }
+  let environment = environment.clone();
+  dprint_core::async_runtime::spawn_blocking(move || {
+    let mut reader = MessageReader::new(reader);
+    loop {
+      let message = match EditorMessage::read(&mut reader) {
+        Ok(message) => message,
+        Err(err) if matches!(err.kind(), ErrorKind::BrokenPipe | ErrorKind::UnexpectedEof) => break,
+        Err(err) => {
+          log_error!(environment, "Editor service failed reading from client: {:#}", err);
+          break;
+        }
+      };
+      if tx.send(ConnectionEvent::Message(id, message)).is_err() {
+        return; // service shut down
+      }
+    }
+    let _ = tx.send(ConnectionEvent::Disconnected(id));
+  });
+}
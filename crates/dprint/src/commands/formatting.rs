@@ -1,31 +1,56 @@
+use anyhow::bail;
 use anyhow::Result;
 use crossterm::style::Stylize;
 use dprint_core::plugins::HostFormatRequest;
 use dprint_core::plugins::NullCancellationToken;
 use parking_lot::Mutex;
+use serde::Serialize;
+use std::future::Future;
 use std::path::Path;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::SystemTime;
 use thiserror::Error;
 
 use crate::arg_parser::CheckSubCommand;
 use crate::arg_parser::CliArgs;
 use crate::arg_parser::FmtSubCommand;
 use crate::arg_parser::OutputFormatTimesSubCommand;
+use crate::arg_parser::ReadOnlyFilesPolicy;
 use crate::arg_parser::StdInFmtSubCommand;
 use crate::configuration::resolve_config_from_args;
 use crate::environment::Environment;
+use crate::environment::FilePermissions;
 use crate::format::run_parallelized;
 use crate::format::EnsureStableFormat;
+use crate::format::FormatCaches;
+use crate::format::TimedOutFiles;
 use crate::incremental::get_incremental_file;
+use crate::incremental::KnownFormattedCache;
 use crate::patterns::FileMatcher;
 use crate::plugins::PluginResolver;
 use crate::resolution::resolve_plugins_scope;
 use crate::resolution::resolve_plugins_scope_and_paths;
 use crate::resolution::PluginsScope;
+use crate::resolution::PluginsScopeAndPathsCollection;
+use crate::utils::enable_profiling;
+use crate::utils::enable_telemetry;
+use crate::utils::get_bytes_hash;
 use crate::utils::get_difference;
+use crate::utils::get_otlp_requests;
+use crate::utils::get_text_edits;
+use crate::utils::profile_async_span;
+use crate::utils::take_profile_json;
+use crate::utils::take_telemetry;
 use crate::utils::AtomicCounter;
+use crate::utils::FileOutput;
+use crate::utils::LaxSingleProcessFsFlag;
+use crate::utils::OtlpEnvConfig;
+use crate::utils::OtlpRun;
+use crate::utils::ProgressBar;
+use crate::utils::ProgressBarStyle;
+use crate::utils::TextEdit;
 
 pub async fn stdin_fmt<TEnvironment: Environment>(
   cmd: &StdInFmtSubCommand,
@@ -49,6 +74,12 @@ pub async fn stdin_fmt<TEnvironment: Environment>(
       return Ok(());
     }
   }
+  // output the file text as-is when it has a dprint-ignore-file directive
+  let config = plugins_scope.config.as_ref().unwrap();
+  if config.ignore_file_comments.is_file_ignored(Path::new(&cmd.file_name_or_path), &cmd.file_bytes) {
+    environment.log_machine_readable(&cmd.file_bytes);
+    return Ok(());
+  }
   output_stdin_format(PathBuf::from(&cmd.file_name_or_path), &cmd.file_bytes, plugins_scope, environment).await
 }
 
@@ -85,14 +116,23 @@ pub async fn output_format_times<TEnvironment: Environment>(
   let durations: Arc<Mutex<Vec<(PathBuf, u128)>>> = Arc::new(Mutex::new(Vec::new()));
 
   for scope_and_paths in scopes.into_iter() {
-    run_parallelized(scope_and_paths, environment, None, EnsureStableFormat(false), {
-      let durations = durations.clone();
-      move |file_path, _, _, start_instant, _| {
-        let duration = start_instant.elapsed().as_millis();
-        durations.lock().push((file_path, duration));
-        Ok(())
-      }
-    })
+    run_parallelized(
+      scope_and_paths,
+      environment,
+      Default::default(),
+      EnsureStableFormat(false),
+      Default::default(),
+      Default::default(),
+      None,
+      {
+        let durations = durations.clone();
+        move |file_path, _, _, start_instant, _, _| {
+          let duration = start_instant.elapsed().as_millis();
+          durations.lock().push((file_path, duration));
+          Ok(())
+        }
+      },
+    )
     .await?;
   }
 
@@ -124,41 +164,96 @@ pub async fn check<TEnvironment: Environment>(
   environment: &TEnvironment,
   plugin_resolver: &Rc<PluginResolver<TEnvironment>>,
 ) -> Result<()> {
+  let future = with_otlp_export(
+    cmd.otlp_endpoint.as_deref(),
+    "check",
+    environment,
+    run_check(cmd, args, environment, plugin_resolver),
+  );
+  with_profile(cmd.profile.as_deref(), "check", environment, future).await
+}
+
+async fn run_check<TEnvironment: Environment>(
+  cmd: &CheckSubCommand,
+  args: &CliArgs,
+  environment: &TEnvironment,
+  plugin_resolver: &Rc<PluginResolver<TEnvironment>>,
+) -> Result<()> {
+  #[derive(Serialize)]
+  #[serde(rename_all = "camelCase")]
+  struct JsonCheckFile {
+    file_path: String,
+    /// The edits to apply to the file or `None` when it's not utf-8.
+    edits: Option<Vec<TextEdit>>,
+  }
+
   let scopes = resolve_plugins_scope_and_paths(args, &cmd.patterns, environment, plugin_resolver).await?;
   scopes.ensure_valid_for_cli_args(args)?;
   let not_formatted_files_count = Arc::new(AtomicCounter::default());
   let list_different = cmd.list_different;
+  let json_files = cmd.json.then(|| Arc::new(Mutex::new(Vec::new())));
+  let known_formatted_cache = get_known_formatted_cache(cmd.cache_file.as_deref(), environment);
+  let progress = add_files_progress(&scopes, "Checking", "not formatted", environment);
+  let timed_out_files = TimedOutFiles::default();
 
   for scope_and_paths in scopes.into_iter() {
+    let _run_lock = lock_config_base_path(&scope_and_paths.scope, cmd.no_lock, environment).await;
     let incremental_file = scope_and_paths
       .scope
       .config
       .as_ref()
       .and_then(|config| get_incremental_file(cmd.incremental, config, &scope_and_paths.scope, environment))
       .map(Arc::new);
-    run_parallelized(scope_and_paths, environment, incremental_file.clone(), EnsureStableFormat(false), {
-      let not_formatted_files_count = not_formatted_files_count.clone();
-      let incremental_file = incremental_file.clone();
-      move |file_path, file_bytes, formatted_bytes, _, environment| {
-        if formatted_bytes != file_bytes {
-          not_formatted_files_count.inc();
-          if list_different {
-            log_stdout_info!(environment, "{}", file_path.display());
+    let caches = FormatCaches {
+      incremental_file: incremental_file.clone(),
+      known_formatted_cache: known_formatted_cache.clone(),
+    };
+    run_parallelized(
+      scope_and_paths,
+      environment,
+      caches,
+      EnsureStableFormat(false),
+      cmd.slow_format,
+      timed_out_files.clone(),
+      progress.clone(),
+      {
+        let not_formatted_files_count = not_formatted_files_count.clone();
+        let incremental_file = incremental_file.clone();
+        let progress = progress.clone();
+        let json_files = json_files.clone();
+        move |file_path, file_bytes, formatted_bytes, _, _, output| {
+          if formatted_bytes != *file_bytes {
+            not_formatted_files_count.inc();
+            if let Some(progress) = &progress {
+              progress.inc_changed();
+            }
+            if let Some(json_files) = &json_files {
+              let edits = match (std::str::from_utf8(&file_bytes), std::str::from_utf8(&formatted_bytes)) {
+                (Ok(file_text), Ok(formatted_text)) => Some(get_text_edits(file_text, formatted_text)),
+                _ => None,
+              };
+              json_files.lock().push(JsonCheckFile {
+                file_path: file_path.display().to_string(),
+                edits,
+              });
+            } else if list_different {
+              log_stdout_info!(output, "{}", file_path.display());
+            } else {
+              output_difference(&file_path, &file_bytes, &formatted_bytes, output);
+            }
           } else {
-            output_difference(&file_path, &file_bytes, &formatted_bytes, &environment);
-          }
-        } else {
-          // update the incremental cache when the file is already formatted correctly
-          // so that this runs faster next time, but don't update it with the
-          // correctly formatted file because it hasn't undergone a stable
-          // formatting check
-          if let Some(incremental_file) = &incremental_file {
-            incremental_file.update_file(&formatted_bytes);
+            // update the incremental cache when the file is already formatted correctly
+            // so that this runs faster next time, but don't update it with the
+            // correctly formatted file because it hasn't undergone a stable
+            // formatting check
+            if let Some(incremental_file) = &incremental_file {
+              incremental_file.update_file(&formatted_bytes);
+            }
           }
+          Ok(())
         }
-        Ok(())
-      }
-    })
+      },
+    )
     .await?;
 
     if let Some(incremental_file) = &incremental_file {
@@ -166,25 +261,145 @@ pub async fn check<TEnvironment: Environment>(
     }
   }
 
+  if let Some(known_formatted_cache) = &known_formatted_cache {
+    known_formatted_cache.write();
+  }
+
+  if let Some(json_files) = &json_files {
+    let mut json_files = std::mem::take(&mut *json_files.lock());
+    json_files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+    environment.log_machine_readable(serde_json::to_string_pretty(&json_files)?.as_bytes());
+  }
+  timed_out_files.log_summary(environment);
+
   let not_formatted_files_count = not_formatted_files_count.get();
   if not_formatted_files_count == 0 {
     Ok(())
   } else {
     Err(
       CheckError {
-        files_count: if list_different { None } else { Some(not_formatted_files_count) },
+        files_count: if list_different || json_files.is_some() {
+          None
+        } else {
+          Some(not_formatted_files_count)
+        },
       }
       .into(),
     )
   }
 }
 
-fn output_difference(file_path: &Path, file_bytes: &[u8], formatted_bytes: &[u8], environment: &impl Environment) {
+/// Records a profile of the command when a profile path is provided.
+async fn with_profile<TEnvironment: Environment>(
+  profile_path: Option<&str>,
+  command_name: &'static str,
+  environment: &TEnvironment,
+  future: impl Future<Output = Result<()>>,
+) -> Result<()> {
+  let Some(profile_path) = profile_path else {
+    return future.await;
+  };
+  enable_profiling();
+  let result = {
+    let _span = profile_async_span("command", || command_name.to_string());
+    future.await
+  };
+  let file_path = environment.cwd().join(profile_path);
+  let bytes = take_profile_json().unwrap();
+  match environment.write_file_bytes(&file_path, &bytes) {
+    Ok(()) => log_stderr_info!(environment, "Wrote profile to {}", file_path.display()),
+    Err(err) => log_warn!(environment, "Failed writing profile to {}. {:#}", file_path.display(), err),
+  }
+  result
+}
+
+/// Exports telemetry about the run to the OpenTelemetry collector when an endpoint is provided.
+async fn with_otlp_export<TEnvironment: Environment>(
+  endpoint: Option<&str>,
+  command_name: &'static str,
+  environment: &TEnvironment,
+  future: impl Future<Output = Result<()>>,
+) -> Result<()> {
+  let Some(endpoint) = endpoint else {
+    return future.await;
+  };
+  enable_telemetry();
+  let start_time = SystemTime::now();
+  let result = future.await;
+  let cli_version = environment.cli_version();
+  let run = OtlpRun {
+    command_name,
+    cli_version: &cli_version,
+    start_time,
+    end_time: SystemTime::now(),
+    is_success: result.is_ok(),
+  };
+  #[allow(clippy::disallowed_methods)]
+  let env_config = OtlpEnvConfig::from_env_vars(|name| std::env::var(name).ok());
+  let data = take_telemetry().unwrap();
+  // failing to export shouldn't fail the run
+  for (url, body) in get_otlp_requests(endpoint, &run, &data, &env_config.resource_attributes) {
+    if let Err(err) = environment.post_json(&url, &env_config.headers, body).await {
+      log_warn!(environment, "Failed exporting telemetry to {}. {:#}", url, err);
+    }
+  }
+  result
+}
+
+/// Shows the progress of going over the files of all the scopes.
+fn add_files_progress<TEnvironment: Environment>(
+  scopes: &PluginsScopeAndPathsCollection<TEnvironment>,
+  message: &str,
+  changed_label: &'static str,
+  environment: &TEnvironment,
+) -> Option<Arc<ProgressBar>> {
+  let progress_bars = environment.progress_bars()?;
+  let file_count = scopes.iter().map(|s| s.file_paths_by_plugins.all_file_paths().count()).sum();
+  Some(Arc::new(progress_bars.add_progress(
+    message.to_string(),
+    ProgressBarStyle::Files { changed_label },
+    file_count,
+  )))
+}
+
+/// Prevents other dprint processes from formatting or checking the files of the
+/// same configuration at the same time (ex. an editor on save and a git hook),
+/// which could interleave writes to the files or to the incremental file.
+async fn lock_config_base_path<TEnvironment: Environment>(
+  scope: &PluginsScope<TEnvironment>,
+  no_lock: bool,
+  environment: &TEnvironment,
+) -> Option<LaxSingleProcessFsFlag> {
+  if no_lock {
+    return None;
+  }
+  let config = scope.config.as_ref()?;
+  let lock_path = get_config_lock_path(config.base_path.as_ref(), environment);
+  if let Some(locks_dir) = lock_path.parent() {
+    let _ = environment.mk_dir_all(locks_dir);
+  }
+  let long_wait_message = format!("Waiting for another dprint process formatting {}...", config.base_path.display());
+  Some(LaxSingleProcessFsFlag::lock(environment, lock_path, &long_wait_message).await)
+}
+
+fn get_config_lock_path(base_path: &Path, environment: &impl Environment) -> PathBuf {
+  let id = get_bytes_hash(base_path.to_string_lossy().as_bytes());
+  environment.get_cache_dir().join("locks").join(format!(".run-{}.lock", id))
+}
+
+fn get_known_formatted_cache<TEnvironment: Environment>(
+  cache_file: Option<&str>,
+  environment: &TEnvironment,
+) -> Option<Arc<KnownFormattedCache<TEnvironment>>> {
+  cache_file.map(|cache_file| Arc::new(KnownFormattedCache::new(environment.cwd().join(cache_file), environment.clone())))
+}
+
+fn output_difference(file_path: &Path, file_bytes: &[u8], formatted_bytes: &[u8], output: &mut FileOutput) {
   let file_text = match String::from_utf8(file_bytes.to_vec()) {
     Ok(text) => text,
     Err(err) => {
       log_warn!(
-        environment,
+        output,
         "Failed outputting difference for {}. Could not get original text as utf-8. {:#}",
         file_path.display(),
         err
@@ -196,7 +411,7 @@ fn output_difference(file_path: &Path, file_bytes: &[u8], formatted_bytes: &[u8]
     Ok(text) => text,
     Err(err) => {
       log_warn!(
-        environment,
+        output,
         "Failed outputting difference for {}. Coult not get formatted text as utf-8. {:#}",
         file_path.display(),
         err
@@ -205,7 +420,7 @@ fn output_difference(file_path: &Path, file_bytes: &[u8], formatted_bytes: &[u8]
     }
   };
   let difference_text = get_difference(&file_text, &formatted_text);
-  log_stdout_info!(environment, "{} {}:\n{}\n--", "from".bold().red(), file_path.display(), difference_text);
+  log_stdout_info!(output, "{} {}:\n{}\n--", "from".bold().red(), file_path.display(), difference_text);
 }
 
 pub async fn format<TEnvironment: Environment>(
@@ -213,12 +428,32 @@ pub async fn format<TEnvironment: Environment>(
   args: &CliArgs,
   environment: &TEnvironment,
   plugin_resolver: &Rc<PluginResolver<TEnvironment>>,
+) -> Result<()> {
+  let future = with_otlp_export(
+    cmd.otlp_endpoint.as_deref(),
+    "fmt",
+    environment,
+    run_format(cmd, args, environment, plugin_resolver),
+  );
+  with_profile(cmd.profile.as_deref(), "fmt", environment, future).await
+}
+
+async fn run_format<TEnvironment: Environment>(
+  cmd: &FmtSubCommand,
+  args: &CliArgs,
+  environment: &TEnvironment,
+  plugin_resolver: &Rc<PluginResolver<TEnvironment>>,
 ) -> Result<()> {
   let scopes = resolve_plugins_scope_and_paths(args, &cmd.patterns, environment, plugin_resolver).await?;
   scopes.ensure_valid_for_cli_args(args)?;
 
   let formatted_files_count = Arc::new(AtomicCounter::default());
+  let skipped_read_only_files_count = Arc::new(AtomicCounter::default());
+  let known_formatted_cache = get_known_formatted_cache(cmd.cache_file.as_deref(), environment);
+  let progress = add_files_progress(&scopes, "Formatting", "changed", environment);
+  let timed_out_files = TimedOutFiles::default();
   for scope_and_paths in scopes.into_iter() {
+    let _run_lock = lock_config_base_path(&scope_and_paths.scope, cmd.no_lock, environment).await;
     let incremental_file = scope_and_paths
       .scope
       .config
@@ -226,27 +461,64 @@ pub async fn format<TEnvironment: Environment>(
       .and_then(|config| get_incremental_file(cmd.incremental, config, &scope_and_paths.scope, environment))
       .map(Arc::new);
     let output_diff = cmd.diff;
+    let read_only_files = cmd
+      .read_only_files
+      .or_else(|| scope_and_paths.scope.config.as_ref().and_then(|config| config.read_only_files))
+      .unwrap_or_default();
+
+    let caches = FormatCaches {
+      incremental_file: incremental_file.clone(),
+      known_formatted_cache: known_formatted_cache.clone(),
+    };
 
     run_parallelized(
       scope_and_paths,
       environment,
-      incremental_file.clone(),
+      caches,
       EnsureStableFormat(cmd.enable_stable_format),
+      cmd.slow_format,
+      timed_out_files.clone(),
+      progress.clone(),
       {
         let formatted_files_count = formatted_files_count.clone();
+        let skipped_read_only_files_count = skipped_read_only_files_count.clone();
         let incremental_file = incremental_file.clone();
-        move |file_path, file_bytes, formatted_bytes, _, environment| {
-          if let Some(incremental_file) = &incremental_file {
-            incremental_file.update_file(&formatted_bytes);
-          }
+        let progress = progress.clone();
+        move |file_path, file_bytes, formatted_bytes, _, environment, output| {
+          if formatted_bytes != *file_bytes {
+            let read_only_permissions = environment.file_permissions(&file_path).ok().filter(|permissions| permissions.readonly());
+            if read_only_permissions.is_some() {
+              match read_only_files {
+                ReadOnlyFilesPolicy::Error => bail!(
+                  "The file is read-only. Specify `--read-only-files skip` or `--read-only-files make-writable` (or the \"readOnlyFiles\" configuration property) to change how read-only files are handled."
+                ),
+                ReadOnlyFilesPolicy::Skip => {
+                  log_warn!(output, "Skipped formatting read-only file: {}", file_path.display());
+                  skipped_read_only_files_count.inc();
+                  return Ok(());
+                }
+                ReadOnlyFilesPolicy::MakeWritable => {}
+              }
+            }
 
-          if formatted_bytes != file_bytes {
             if output_diff {
-              output_difference(&file_path, &file_bytes, &formatted_bytes, &environment);
+              output_difference(&file_path, &file_bytes, &formatted_bytes, output);
             }
 
+            // the file may be memory mapped, which prevents writing to it on some platforms
+            drop(file_bytes);
             formatted_files_count.inc();
-            environment.write_file_bytes(file_path, &formatted_bytes)?;
+            if let Some(progress) = &progress {
+              progress.inc_changed();
+            }
+            match read_only_permissions {
+              Some(permissions) => replace_read_only_file_bytes(&environment, &file_path, &formatted_bytes, permissions)?,
+              None => environment.replace_file_bytes(&file_path, &formatted_bytes)?,
+            }
+          }
+
+          if let Some(incremental_file) = &incremental_file {
+            incremental_file.update_file(&formatted_bytes);
           }
 
           Ok(())
@@ -260,23 +532,59 @@ pub async fn format<TEnvironment: Environment>(
     }
   }
 
+  if let Some(known_formatted_cache) = &known_formatted_cache {
+    known_formatted_cache.write();
+  }
+
   let formatted_files_count = formatted_files_count.get();
   if formatted_files_count > 0 {
     let suffix = if formatted_files_count == 1 { "file" } else { "files" };
     log_stdout_info!(environment, "Formatted {} {}.", formatted_files_count.to_string().bold(), suffix);
   }
 
+  let skipped_read_only_files_count = skipped_read_only_files_count.get();
+  if skipped_read_only_files_count > 0 {
+    let suffix = if skipped_read_only_files_count == 1 { "file" } else { "files" };
+    log_warn!(
+      environment,
+      "Skipped {} read-only {}.",
+      skipped_read_only_files_count.to_string().bold(),
+      suffix
+    );
+  }
+  timed_out_files.log_summary(environment);
+
   Ok(())
 }
 
+/// Writes to a read-only file by temporarily allowing the owner to write to it.
+fn replace_read_only_file_bytes<TEnvironment: Environment>(
+  environment: &TEnvironment,
+  file_path: &Path,
+  bytes: &[u8],
+  permissions: FilePermissions,
+) -> Result<()> {
+  environment.set_file_permissions(file_path, permissions.with_owner_write())?;
+  let result = environment.replace_file_bytes(file_path, bytes);
+  // restore the permissions even when writing failed
+  let restore_result = environment.set_file_permissions(file_path, permissions);
+  result?;
+  restore_result
+}
+
 #[cfg(test)]
 mod test {
+  use std::time::Duration;
+
   use crossterm::style::Stylize;
   use pretty_assertions::assert_eq;
 
   use crate::environment::Environment;
+  use crate::environment::FilePermissions;
+  use crate::environment::ProcessPluginTrustPolicy;
   use crate::environment::TestEnvironment;
   use crate::environment::TestEnvironmentBuilder;
+  use crate::environment::TestFilePermissions;
   use crate::test_helpers;
   use crate::test_helpers::get_plural_check_text;
   use crate::test_helpers::get_plural_formatted_text;
@@ -291,6 +599,8 @@ mod test {
   use crate::utils::get_difference;
   use crate::utils::TestStdInReader;
 
+  use super::get_config_lock_path;
+
   #[test]
   fn should_output_format_times() {
     let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
@@ -314,6 +624,37 @@ mod test {
     assert_eq!(environment.read_file(&file_path1).unwrap(), "text_formatted");
   }
 
+  #[test]
+  fn should_wait_for_another_process_formatting_the_same_config() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file.txt", "text")
+      .build();
+    let lock_path = get_config_lock_path(environment.cwd().as_ref(), &environment);
+    let lock = environment.try_lock_file(&lock_path).unwrap().unwrap();
+    let release_thread = std::thread::spawn({
+      let environment = environment.clone();
+      move || {
+        std::thread::sleep(Duration::from_millis(1_500));
+        // nothing was formatted while the lock was held
+        assert_eq!(environment.read_file("/file.txt").unwrap(), "text");
+        drop(lock);
+      }
+    });
+    run_test_cli(vec!["fmt"], &environment).unwrap();
+    release_thread.join().unwrap();
+    assert_eq!(environment.take_stderr_messages(), vec!["Waiting for another dprint process formatting /..."]);
+    assert_eq!(environment.take_stdout_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.read_file("/file.txt").unwrap(), "text_formatted");
+
+    // doesn't wait when specifying --no-lock
+    let _lock = environment.try_lock_file(&lock_path).unwrap().unwrap();
+    environment.write_file("/file.txt", "text").unwrap();
+    run_test_cli(vec!["fmt", "--no-lock"], &environment).unwrap();
+    assert!(environment.take_stderr_messages().is_empty());
+    assert_eq!(environment.take_stdout_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.read_file("/file.txt").unwrap(), "text_formatted");
+  }
+
   #[test]
   fn should_format_files() {
     let file_path1 = "/file.txt";
@@ -329,6 +670,80 @@ mod test {
     assert_eq!(environment.read_file(&file_path2).unwrap(), "text2_formatted_process");
   }
 
+  #[test]
+  fn should_export_otlp_telemetry() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
+      .write_file("/file.txt", "text")
+      .write_file("/file.txt_ps", "text2")
+      .build();
+    run_test_cli(vec!["fmt", "/file.*", "--otlp-endpoint", "http://localhost:4318/"], &environment).unwrap();
+    assert_eq!(environment.take_stdout_messages(), vec![get_plural_formatted_text(2)]);
+    let posted_json = environment.take_posted_json();
+    assert_eq!(
+      posted_json.iter().map(|(url, _)| url.as_str()).collect::<Vec<_>>(),
+      vec!["http://localhost:4318/v1/traces", "http://localhost:4318/v1/metrics"]
+    );
+    let span = &posted_json[0].1["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+    assert_eq!(span["name"], "dprint fmt");
+    assert_eq!(span["status"]["code"], 1);
+    // other tests may record at the same time, so only check the plugins were included
+    let metrics = posted_json[1].1["resourceMetrics"][0]["scopeMetrics"][0]["metrics"].as_array().unwrap();
+    let plugin_count_metric = metrics.iter().find(|m| m["name"] == "dprint.plugin.format.count").unwrap();
+    let plugin_names = plugin_count_metric["sum"]["dataPoints"]
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|data_point| data_point["attributes"][0]["value"]["stringValue"].as_str().unwrap())
+      .collect::<Vec<_>>();
+    assert!(plugin_names.contains(&"test-plugin"));
+    assert!(plugin_names.contains(&"test-process-plugin"));
+  }
+
+  #[test]
+  fn should_skip_and_report_files_exceeding_format_timeout() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
+      .write_file("/file.txt", "text")
+      .write_file("/file.txt_ps", "wait_cancellation")
+      .build();
+    run_test_cli(vec!["fmt", "--format-timeout-seconds", "1", "/file.*"], &environment).unwrap();
+    assert_eq!(environment.take_stdout_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(
+      environment.take_stderr_messages(),
+      vec![
+        "Skipped /file.txt_ps after exceeding the format timeout of 1s.",
+        "Skipped (timeout) 1 file:\n  /file.txt_ps",
+      ]
+    );
+    assert_eq!(environment.read_file("/file.txt").unwrap(), "text_formatted");
+    assert_eq!(environment.read_file("/file.txt_ps").unwrap(), "wait_cancellation");
+  }
+
+  #[test]
+  fn should_format_files_in_other_encodings() {
+    fn utf16_bytes(text: &str) -> Vec<u8> {
+      let mut bytes = vec![0xFF, 0xFE];
+      for code_unit in text.encode_utf16() {
+        bytes.extend(code_unit.to_le_bytes());
+      }
+      bytes
+    }
+
+    let mut builder = TestEnvironmentBuilder::new();
+    builder
+      .add_remote_wasm_plugin()
+      .with_default_config(|config_file| {
+        config_file.add_remote_wasm_plugin().add_config_section("legacyEncoding", r#""windows-1252""#);
+      })
+      .write_file_bytes("/file1.txt", &utf16_bytes("tëxt"))
+      .write_file_bytes("/file2.txt", b"caf\xE9")
+      .initialize();
+    let environment = builder.build();
+    run_test_cli(vec!["fmt", "/file*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_stdout_messages(), vec![get_plural_formatted_text(2)]);
+    assert_eq!(environment.read_file_bytes("/file1.txt").unwrap(), utf16_bytes("tëxt_formatted"));
+    assert_eq!(environment.read_file_bytes("/file2.txt").unwrap(), b"caf\xE9_formatted");
+  }
+
   #[test]
   fn should_format_only_staged_files() {
     let file_path1 = "/file.txt";
@@ -408,6 +823,59 @@ mod test {
     assert_eq!(error_message.to_string(), "Had 1 error formatting.");
   }
 
+  fn get_read_only_files_environment() -> TestEnvironment {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file1.txt", "text1")
+      .write_file("/file2.txt", "text2")
+      .build();
+    let read_only = FilePermissions::Test(TestFilePermissions { readonly: true });
+    environment.set_file_permissions("/file1.txt", read_only).unwrap();
+    environment
+  }
+
+  #[test]
+  fn should_error_formatting_read_only_files_by_default() {
+    let environment = get_read_only_files_environment();
+    let error_message = run_test_cli(vec!["fmt"], &environment).err().unwrap();
+    assert_eq!(
+      environment.take_stderr_messages(),
+      vec![String::from(concat!(
+        "Error formatting /file1.txt. Message: The file is read-only. Specify `--read-only-files skip` or ",
+        "`--read-only-files make-writable` (or the \"readOnlyFiles\" configuration property) to change how read-only files are handled."
+      ))]
+    );
+    assert_eq!(error_message.to_string(), "Had 1 error formatting.");
+    assert_eq!(environment.read_file("/file1.txt").unwrap(), "text1");
+    assert_eq!(environment.read_file("/file2.txt").unwrap(), "text2_formatted");
+  }
+
+  #[test]
+  fn should_skip_formatting_read_only_files() {
+    let environment = get_read_only_files_environment();
+    run_test_cli(vec!["fmt", "--read-only-files", "skip"], &environment).unwrap();
+    assert_eq!(environment.take_stdout_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(
+      environment.take_stderr_messages(),
+      vec![
+        "Skipped formatting read-only file: /file1.txt".to_string(),
+        format!("Skipped {} read-only file.", "1".bold()),
+      ]
+    );
+    assert_eq!(environment.read_file("/file1.txt").unwrap(), "text1");
+    assert_eq!(environment.read_file("/file2.txt").unwrap(), "text2_formatted");
+  }
+
+  #[test]
+  fn should_make_read_only_files_writable() {
+    let environment = get_read_only_files_environment();
+    run_test_cli(vec!["fmt", "--read-only-files", "make-writable"], &environment).unwrap();
+    assert_eq!(environment.take_stdout_messages(), vec![get_plural_formatted_text(2)]);
+    assert_eq!(environment.read_file("/file1.txt").unwrap(), "text1_formatted");
+    assert_eq!(environment.read_file("/file2.txt").unwrap(), "text2_formatted");
+    // restores the permissions
+    assert!(environment.file_permissions("/file1.txt").unwrap().readonly());
+  }
+
   #[test]
   fn should_handle_process_plugin_erroring() {
     let environment = TestEnvironmentBuilder::with_initialized_remote_process_plugin()
@@ -429,13 +897,21 @@ mod test {
       .build();
     let error_message = run_test_cli(vec!["fmt", "**.{txt,txt_ps}"], &environment).err().unwrap();
     let logged_errors = environment.take_stderr_messages();
-    assert_eq!(logged_errors.len(), 1);
+    assert_eq!(logged_errors.len(), 2);
     let expected_start_text = concat!(
       "Critical error formatting /file1.txt. Cannot continue. ",
       "Message: Originally panicked in test-plugin, then failed reinitialize. ",
       "This may be a bug in the plugin, the dprint cli is out of date, or the plugin is out of date.",
     );
     assert_eq!(&logged_errors[0][..expected_start_text.len()], expected_start_text);
+    let report_path = format!("/cache/crash_reports/crash-123456-{}.md", std::process::id());
+    assert_eq!(
+      logged_errors[1],
+      format!("A crash report was written to {}. Please include it when reporting this issue.", report_path)
+    );
+    let report_text = environment.read_file(&report_path).unwrap();
+    assert!(report_text.starts_with("# dprint crash report\n\nCritical error formatting /file1.txt: Originally panicked in test-plugin"));
+    assert!(report_text.contains("- test-plugin 0.1.0\n"));
     assert_eq!(error_message.to_string(), "Had 1 error formatting.");
     // should still format with the other plugin
     assert_eq!(environment.read_file("/file2.txt_ps").unwrap(), "test_formatted_process");
@@ -1714,6 +2190,35 @@ mod test {
     environment.clear_logs();
   }
 
+  #[test]
+  fn should_skip_known_formatted_files_with_cache_file() {
+    let file_path1 = "/file1.txt";
+    let known_formatted_msg = "Known formatted: /file1.txt";
+    let environment = TestEnvironmentBuilder::with_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin();
+      })
+      .write_file(file_path1, "text1_formatted")
+      .initialize()
+      .build();
+
+    run_test_cli(vec!["check", "--incremental=false", "--cache-file", "/cache/dprint.json"], &environment).unwrap();
+    assert!(environment.path_exists("/cache/dprint.json"));
+    environment.clear_logs();
+    run_test_cli(
+      vec!["check", "--incremental=false", "--cache-file", "/cache/dprint.json", "--log-level=debug"],
+      &environment,
+    )
+    .unwrap();
+    assert!(environment.take_stderr_messages().iter().any(|msg| msg.contains(known_formatted_msg)));
+
+    // changed content is checked again
+    environment.write_file(file_path1, "text1").unwrap();
+    let err = run_test_cli(vec!["check", "--incremental=false", "--cache-file", "/cache/dprint.json"], &environment).unwrap_err();
+    err.assert_exit_code(20);
+    environment.clear_logs();
+  }
+
   #[test]
   fn should_format_without_incremental_when_specified() {
     let file_path1 = "/subdir/file1.txt";
@@ -1847,6 +2352,46 @@ mod test {
     assert_eq!(logged_messages, vec!["/file1.txt", "/file2.txt",]);
   }
 
+  #[test]
+  fn should_output_largest_files_first_for_check() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file1.txt", "const t=4;")
+      .write_file("/file2.txt", "const t=5;")
+      .write_file("/file3.txt", "const t=6; const u=7;")
+      .build();
+
+    let err = run_test_cli(vec!["check", "--list-different", "/file1.txt", "/file2.txt", "/file3.txt"], &environment).unwrap_err();
+    err.assert_exit_code(20);
+    // output is released in the order the files started formatting
+    assert_eq!(environment.take_stdout_messages(), vec!["/file3.txt", "/file1.txt", "/file2.txt"]);
+  }
+
+  #[test]
+  fn should_output_json_when_files_need_formatting_for_check() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file1.txt", "const t=4;")
+      .write_file("/file2.txt", "const t=5;_formatted")
+      .write_file("/file3.txt", "const t=6;")
+      .build();
+
+    let err = run_test_cli(vec!["check", "--json", "/file1.txt", "/file2.txt", "/file3.txt"], &environment).unwrap_err();
+    err.assert_exit_code(20);
+    assert_eq!(err.to_string(), "");
+    let stdout = environment.take_stdout_messages();
+    assert_eq!(stdout.len(), 1);
+    let value: serde_json::Value = serde_json::from_str(&stdout[0]).unwrap();
+    assert_eq!(
+      value,
+      serde_json::json!([{
+        "filePath": "/file1.txt",
+        "edits": [{ "start": 10, "end": 10, "newText": "_formatted" }],
+      }, {
+        "filePath": "/file3.txt",
+        "edits": [{ "start": 10, "end": 10, "newText": "_formatted" }],
+      }])
+    );
+  }
+
   #[test]
   fn should_handle_bom() {
     let file_path = "/file.txt";
@@ -1858,6 +2403,54 @@ mod test {
     assert_eq!(environment.read_file(&file_path).unwrap(), "\u{FEFF}text_formatted");
   }
 
+  #[test]
+  fn should_handle_bom_policy() {
+    for (policy, expected_text1, expected_text2) in [
+      ("preserve", "\u{FEFF}text_formatted", "text_formatted"),
+      ("remove", "text_formatted", "text_formatted"),
+      ("add", "\u{FEFF}text_formatted", "\u{FEFF}text_formatted"),
+    ] {
+      let mut builder = TestEnvironmentBuilder::new();
+      builder
+        .add_remote_wasm_plugin()
+        .with_default_config(|config_file| {
+          config_file.add_remote_wasm_plugin().add_config_section("bom", &format!("\"{}\"", policy));
+        })
+        .write_file("/file1.txt", "\u{FEFF}text")
+        .write_file("/file2.txt", "text")
+        .initialize();
+      let environment = builder.build();
+      run_test_cli(vec!["fmt", "/file*.txt"], &environment).unwrap();
+      assert_eq!(environment.take_stdout_messages(), vec![get_plural_formatted_text(2)]);
+      assert_eq!(environment.read_file("/file1.txt").unwrap(), expected_text1);
+      assert_eq!(environment.read_file("/file2.txt").unwrap(), expected_text2);
+    }
+  }
+
+  #[test]
+  fn should_skip_files_with_ignore_file_directive() {
+    let mut builder = TestEnvironmentBuilder::new();
+    builder
+      .add_remote_wasm_plugin()
+      .with_default_config(|config_file| {
+        config_file
+          .add_remote_wasm_plugin()
+          .add_config_section("ignoreFileComments", r#"{ "txt": "//" }"#);
+      })
+      .write_file("/file1.txt", "// dprint-ignore-file\ntext")
+      .write_file("/file2.txt", "text")
+      .initialize();
+    let environment = builder.build();
+    run_test_cli(vec!["fmt", "/file*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_stdout_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.read_file("/file1.txt").unwrap(), "// dprint-ignore-file\ntext");
+    assert_eq!(environment.read_file("/file2.txt").unwrap(), "text_formatted");
+
+    let test_std_in = TestStdInReader::from("// dprint-ignore-file\ntext");
+    run_test_cli_with_stdin(vec!["fmt", "--stdin", "file.txt"], &environment, test_std_in).unwrap();
+    assert_eq!(environment.take_stdout_messages(), vec!["// dprint-ignore-file\ntext"]);
+  }
+
   #[test]
   fn should_format_for_stdin_fmt_with_file_name() {
     // it should not output anything when downloading plugins
@@ -2129,6 +2722,80 @@ mod test {
     );
   }
 
+  #[test]
+  fn should_prompt_to_trust_new_process_plugin() {
+    let environment = TestEnvironmentBuilder::with_remote_process_plugin()
+      .with_default_config(|c| {
+        c.add_remote_process_plugin();
+      })
+      .write_file("/test.txt_ps", "text")
+      .build();
+    let checksum = TestProcessPluginFile::default().checksum();
+    environment.set_process_plugin_trust_policy(ProcessPluginTrustPolicy::Prompt);
+    environment.set_confirm_results(vec![Ok(Some(false))]);
+    let err = run_test_cli(vec!["fmt", "*.*"], &environment).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      "Error resolving plugin https://plugins.dprint.dev/test-process.json: The process plugin https://plugins.dprint.dev/test-process.json was not trusted."
+    );
+    assert_eq!(
+      environment.take_stderr_messages(),
+      vec![
+        format!(
+          concat!(
+            "The configuration uses a new process plugin, which is a native executable that ",
+            "runs with the same permissions as dprint.\n  Url: https://plugins.dprint.dev/test-process.json\n  Checksum: {}"
+          ),
+          checksum
+        ),
+        "Do you trust it? N".to_string(),
+      ]
+    );
+
+    // remembers the decision
+    let err = run_test_cli(vec!["fmt", "*.*"], &environment).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      format!(
+        "Error resolving plugin https://plugins.dprint.dev/test-process.json: The process plugin https://plugins.dprint.dev/test-process.json (checksum {}) was previously not trusted. Provide --yes to trust it.",
+        checksum
+      )
+    );
+    assert!(environment.take_stderr_messages().is_empty());
+
+    run_test_cli(vec!["fmt", "*.*", "--yes"], &environment).unwrap();
+    assert_eq!(environment.read_file("/test.txt_ps").unwrap(), "text_formatted_process");
+    assert_eq!(
+      environment.read_file(environment.get_cache_dir().join("process-plugin-trust.json")).unwrap(),
+      format!(r#"{{"plugins":{{"https://plugins.dprint.dev/test-process.json@{}":true}}}}"#, checksum)
+    );
+  }
+
+  #[test]
+  fn should_error_for_untrusted_process_plugin_when_cannot_prompt() {
+    let environment = TestEnvironmentBuilder::with_remote_process_plugin()
+      .with_default_config(|c| {
+        c.add_remote_process_plugin();
+      })
+      .write_file("/test.txt_ps", "text")
+      .build();
+    let checksum = TestProcessPluginFile::default().checksum();
+    environment.set_process_plugin_trust_policy(ProcessPluginTrustPolicy::Deny);
+    let err = run_test_cli(vec!["fmt", "*.*"], &environment).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      format!(
+        concat!(
+          "Error resolving plugin https://plugins.dprint.dev/test-process.json: The process plugin ",
+          "https://plugins.dprint.dev/test-process.json (checksum {}) has not been trusted. Run the command in a terminal ",
+          "to be prompted to trust it or provide --yes to trust it without prompting (ex. on CI)."
+        ),
+        checksum
+      )
+    );
+    assert!(!environment.path_exists(environment.get_cache_dir().join("process-plugin-trust.json")));
+  }
+
   #[test]
   fn should_format_many_files() {
     let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin().build();
@@ -8,7 +8,7 @@ use crate::arg_parser::CliArgs;
 use crate::arg_parser::OutputFilePathsSubCommand;
 use crate::environment::Environment;
 use crate::plugins::PluginResolver;
-use crate::resolution::get_plugins_scope_from_args;
+use crate::resolution::get_plugins_config_from_args;
 use crate::resolution::resolve_plugins_scope_and_paths;
 use crate::utils::get_table_text;
 use crate::utils::is_out_of_date;
@@ -28,12 +28,18 @@ pub async fn output_help<TEnvironment: Environment>(
   // log the cli's help first
   log_stdout_info!(environment, help_text);
 
-  // now check for the plugins
-  let scope_result = get_plugins_scope_from_args(args, environment, plugin_resolver).await;
-  match scope_result {
-    Ok(scope) => {
-      if !scope.plugins.is_empty() {
-        let table_text = get_table_text(scope.plugins.values().map(|plugin| (plugin.name(), plugin.info().help_url.as_str())).collect());
+  // now check for the plugins (only the plugin info is necessary, so don't initialize them)
+  let plugins_config_result = get_plugins_config_from_args(args, environment, plugin_resolver).await;
+  match plugins_config_result {
+    Ok(plugins_config) => {
+      if !plugins_config.plugins.is_empty() {
+        let table_text = get_table_text(
+          plugins_config
+            .plugins
+            .iter()
+            .map(|(_, plugin)| (plugin.info().name.as_str(), plugin.info().help_url.as_str()))
+            .collect(),
+        );
         log_stdout_info!(environment, "\nPLUGINS HELP:");
         log_stdout_info!(
           environment,
@@ -72,8 +78,8 @@ pub async fn output_license<TEnvironment: Environment>(
   log_stdout_info!(environment, std::str::from_utf8(include_bytes!("../../LICENSE"))?);
 
   // now check for the plugins
-  for plugin in get_plugins_scope_from_args(args, environment, plugin_resolver).await?.plugins.values() {
-    log_stdout_info!(environment, "\n==== {} LICENSE ====", plugin.name().to_uppercase());
+  for (_, plugin) in get_plugins_config_from_args(args, environment, plugin_resolver).await?.plugins {
+    log_stdout_info!(environment, "\n==== {} LICENSE ====", plugin.info().name.to_uppercase());
     let initialized_plugin = plugin.initialize().await?;
     log_stdout_info!(environment, &initialized_plugin.license_text().await?);
   }
@@ -0,0 +1,76 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+
+use tower_lsp::jsonrpc::Request;
+use tower_lsp::jsonrpc::Response;
+use tower_service::Service;
+
+/// Wraps the language server's service in order to add the capabilities
+/// that aren't in lsp-types yet to the response of the `initialize` request.
+pub struct CapabilitiesService<S> {
+  inner: S,
+}
+
+impl<S> CapabilitiesService<S> {
+  pub fn new(inner: S) -> Self {
+    Self { inner }
+  }
+}
+
+impl<S> Service<Request> for CapabilitiesService<S>
+where
+  S: Service<Request, Response = Option<Response>>,
+  S::Future: Send + 'static,
+{
+  type Response = Option<Response>;
+  type Error = S::Error;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+  fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.inner.poll_ready(cx)
+  }
+
+  fn call(&mut self, request: Request) -> Self::Future {
+    let is_initialize = request.method() == "initialize";
+    let future = self.inner.call(request);
+    Box::pin(async move {
+      let response = future.await?;
+      Ok(match response {
+        Some(response) if is_initialize => {
+          let (id, result) = response.into_parts();
+          Some(Response::from_parts(
+            id,
+            result.map(|mut value| {
+              add_unsupported_capabilities(&mut value);
+              value
+            }),
+          ))
+        }
+        response => response,
+      })
+    })
+  }
+}
+
+/// Adds the capabilities that can't be expressed with lsp-types to the
+/// serialized `InitializeResult`.
+pub fn add_unsupported_capabilities(initialize_result: &mut serde_json::Value) {
+  let Some(capabilities) = initialize_result.get_mut("capabilities") else {
+    return;
+  };
+  // LSP 3.18 support for textDocument/rangesFormatting when the
+  // range formatting provider isn't registered dynamically
+  if let Some(provider) = capabilities.get_mut("documentRangeFormattingProvider") {
+    match provider {
+      serde_json::Value::Bool(true) => {
+        *provider = serde_json::json!({ "rangesSupport": true });
+      }
+      serde_json::Value::Object(options) => {
+        options.insert("rangesSupport".to_string(), serde_json::Value::Bool(true));
+      }
+      _ => {}
+    }
+  }
+}
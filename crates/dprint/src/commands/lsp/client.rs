@@ -1,12 +1,39 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
+use tower_lsp::lsp_types::notification::Progress;
+use tower_lsp::lsp_types::request::WorkDoneProgressCreate;
+use tower_lsp::lsp_types::Diagnostic;
 use tower_lsp::lsp_types::MessageType;
+use tower_lsp::lsp_types::ProgressParams;
+use tower_lsp::lsp_types::ProgressParamsValue;
+use tower_lsp::lsp_types::ProgressToken;
+use tower_lsp::lsp_types::Registration;
+use tower_lsp::lsp_types::Unregistration;
+use tower_lsp::lsp_types::WorkDoneProgress;
+use tower_lsp::lsp_types::WorkDoneProgressBegin;
+use tower_lsp::lsp_types::WorkDoneProgressCreateParams;
+use tower_lsp::lsp_types::WorkDoneProgressEnd;
 use tower_lsp::Client;
+use url::Url;
 
+use super::status::LspStatus;
+use super::status::StatusNotification;
+
+#[tower_lsp::async_trait]
 pub trait ClientTrait: std::fmt::Debug + Send + Sync {
   fn log(&self, message_type: MessageType, message: String);
+  fn publish_diagnostics(&self, uri: Url, diagnostics: Vec<Diagnostic>);
+  fn register_capability(&self, registrations: Vec<Registration>);
+  fn unregister_capability(&self, unregistrations: Vec<Unregistration>);
+  async fn create_work_done_progress(&self, token: ProgressToken) -> bool;
+  async fn send_progress(&self, token: ProgressToken, progress: WorkDoneProgress);
+  fn send_status(&self, status: LspStatus);
 }
 
+#[tower_lsp::async_trait]
 impl ClientTrait for Client {
   fn log(&self, message_type: MessageType, message: String) {
     let client = self.clone();
@@ -14,21 +41,145 @@ impl ClientTrait for Client {
       client.log_message(message_type, &message).await;
     });
   }
+
+  fn publish_diagnostics(&self, uri: Url, diagnostics: Vec<Diagnostic>) {
+    let client = self.clone();
+    dprint_core::async_runtime::spawn(async move {
+      client.publish_diagnostics(uri, diagnostics, None).await;
+    });
+  }
+
+  fn register_capability(&self, registrations: Vec<Registration>) {
+    let client = self.clone();
+    dprint_core::async_runtime::spawn(async move {
+      if let Err(err) = client.register_capability(registrations).await {
+        client
+          .log_message(MessageType::WARNING, format!("Failed registering capability: {:#}", err))
+          .await;
+      }
+    });
+  }
+
+  fn unregister_capability(&self, unregistrations: Vec<Unregistration>) {
+    let client = self.clone();
+    dprint_core::async_runtime::spawn(async move {
+      if let Err(err) = client.unregister_capability(unregistrations).await {
+        client
+          .log_message(MessageType::WARNING, format!("Failed unregistering capability: {:#}", err))
+          .await;
+      }
+    });
+  }
+
+  async fn create_work_done_progress(&self, token: ProgressToken) -> bool {
+    self
+      .send_request::<WorkDoneProgressCreate>(WorkDoneProgressCreateParams { token })
+      .await
+      .is_ok()
+  }
+
+  async fn send_progress(&self, token: ProgressToken, progress: WorkDoneProgress) {
+    self
+      .send_notification::<Progress>(ProgressParams {
+        token,
+        value: ProgressParamsValue::WorkDone(progress),
+      })
+      .await;
+  }
+
+  fn send_status(&self, status: LspStatus) {
+    let client = self.clone();
+    dprint_core::async_runtime::spawn(async move {
+      client.send_notification::<StatusNotification>(status).await;
+    });
+  }
 }
 
 #[derive(Debug, Clone)]
-pub struct ClientWrapper(Arc<dyn ClientTrait>);
+pub struct ClientWrapper {
+  client: Arc<dyn ClientTrait>,
+  supports_work_done_progress: Arc<AtomicBool>,
+  next_progress_id: Arc<AtomicU32>,
+}
 
 impl ClientWrapper {
   pub fn new(client: Arc<dyn ClientTrait>) -> Self {
-    Self(client)
+    Self {
+      client,
+      supports_work_done_progress: Default::default(),
+      next_progress_id: Default::default(),
+    }
   }
 
   pub fn log_info(&self, message: String) {
     self.log(MessageType::INFO, message);
   }
 
-  fn log(&self, message_type: MessageType, message: String) {
-    self.0.log(message_type, message)
+  pub fn log(&self, message_type: MessageType, message: String) {
+    self.client.log(message_type, message)
+  }
+
+  pub fn publish_diagnostics(&self, uri: Url, diagnostics: Vec<Diagnostic>) {
+    self.client.publish_diagnostics(uri, diagnostics)
+  }
+
+  pub fn register_capability(&self, registrations: Vec<Registration>) {
+    self.client.register_capability(registrations)
+  }
+
+  pub fn unregister_capability(&self, unregistrations: Vec<Unregistration>) {
+    self.client.unregister_capability(unregistrations)
+  }
+
+  pub fn send_status(&self, status: LspStatus) {
+    self.client.send_status(status)
+  }
+
+  pub fn set_supports_work_done_progress(&self, value: bool) {
+    self.supports_work_done_progress.store(value, Ordering::Relaxed);
+  }
+
+  /// Starts reporting progress to the client. Returns `None` when
+  /// the client doesn't support server initiated progress.
+  pub async fn start_progress(&self, title: String, message: Option<String>) -> Option<ClientProgress> {
+    if !self.supports_work_done_progress.load(Ordering::Relaxed) {
+      return None;
+    }
+    let id = self.next_progress_id.fetch_add(1, Ordering::Relaxed);
+    let token = ProgressToken::String(format!("dprint/progress/{}", id));
+    if !self.client.create_work_done_progress(token.clone()).await {
+      return None;
+    }
+    self
+      .client
+      .send_progress(
+        token.clone(),
+        WorkDoneProgress::Begin(WorkDoneProgressBegin {
+          title,
+          cancellable: Some(false),
+          message,
+          percentage: None,
+        }),
+      )
+      .await;
+    Some(ClientProgress {
+      client: self.client.clone(),
+      token,
+    })
+  }
+}
+
+/// Progress being reported to the client.
+pub struct ClientProgress {
+  client: Arc<dyn ClientTrait>,
+  token: ProgressToken,
+}
+
+impl ClientProgress {
+  pub async fn finish(self) {
+    self
+      .client
+      .send_progress(self.token, WorkDoneProgress::End(WorkDoneProgressEnd { message: None }))
+      .await;
   }
 }
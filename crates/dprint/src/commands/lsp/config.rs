@@ -1,43 +1,234 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::Path;
+use std::path::PathBuf;
 use std::rc::Rc;
 
 use anyhow::Result;
 
 use crate::configuration::get_default_config_file_in_ancestor_directories;
 use crate::configuration::resolve_config_from_path;
+use crate::configuration::ResolvedConfigPath;
 use crate::environment::CanonicalizedPathBuf;
 use crate::environment::Environment;
 use crate::plugins;
 use crate::resolution::resolve_plugins_scope;
 use crate::resolution::PluginsScope;
+use crate::utils::resolve_url_or_file_path;
 use crate::utils::AsyncMutex;
+use crate::utils::PathSource;
+
+use super::client::ClientWrapper;
 
 type ScopeCell<TEnvironment> = AsyncMutex<Option<Rc<PluginsScope<TEnvironment>>>>;
 
+/// Plugin scope containers for each workspace folder open in the client.
+///
+/// Each workspace folder resolves its own plugins so that a config change
+/// in one folder doesn't restart the plugins of the other folders.
+pub struct LspWorkspaceFolders<TEnvironment: Environment> {
+  environment: TEnvironment,
+  plugin_resolver: Rc<plugins::PluginResolver<TEnvironment>>,
+  /// Container used for files that aren't in any workspace folder.
+  default_container: Rc<LspPluginsScopeContainer<TEnvironment>>,
+  folder_containers: RefCell<Vec<Rc<LspPluginsScopeContainer<TEnvironment>>>>,
+  config_override: RefCell<Option<String>>,
+}
+
+impl<TEnvironment: Environment> LspWorkspaceFolders<TEnvironment> {
+  pub fn new(environment: TEnvironment, plugin_resolver: Rc<plugins::PluginResolver<TEnvironment>>) -> Self {
+    Self {
+      default_container: Rc::new(LspPluginsScopeContainer::new(environment.clone(), plugin_resolver.clone(), None, None)),
+      environment,
+      plugin_resolver,
+      folder_containers: Default::default(),
+      config_override: Default::default(),
+    }
+  }
+
+  pub fn add(&self, folder_path: PathBuf) {
+    let mut folder_containers = self.folder_containers.borrow_mut();
+    if folder_containers.iter().any(|c| c.root_dir() == Some(&folder_path)) {
+      return;
+    }
+    folder_containers.push(Rc::new(LspPluginsScopeContainer::new(
+      self.environment.clone(),
+      Rc::new(self.plugin_resolver.new_sharing_cache()),
+      Some(folder_path),
+      self.config_override.borrow().clone(),
+    )));
+  }
+
+  pub async fn remove(&self, folder_path: &Path) {
+    let removed_containers = {
+      let mut folder_containers = self.folder_containers.borrow_mut();
+      let (removed, kept) = folder_containers.drain(..).partition::<Vec<_>, _>(|c| c.root_dir() == Some(folder_path));
+      *folder_containers = kept;
+      removed
+    };
+    for container in removed_containers {
+      container.shutdown().await;
+    }
+  }
+
+  /// Gets the container of the innermost workspace folder containing the path.
+  pub fn get_for_path(&self, file_path: &Path) -> Rc<LspPluginsScopeContainer<TEnvironment>> {
+    self
+      .folder_containers
+      .borrow()
+      .iter()
+      .filter(|c| c.root_dir().map(|dir| file_path.starts_with(dir)).unwrap_or(false))
+      .max_by_key(|c| c.root_dir().map(|dir| dir.components().count()))
+      .cloned()
+      .unwrap_or_else(|| self.default_container.clone())
+  }
+
+  /// Clears all the resolved plugin scopes so they're resolved
+  /// again on the next request with the provided config override.
+  pub async fn invalidate(&self, config_override: Option<String>) {
+    *self.config_override.borrow_mut() = config_override.clone();
+    for container in self.all_containers() {
+      container.invalidate(config_override.clone()).await;
+    }
+  }
+
+  /// Resolves the plugins for the config file of each workspace folder.
+  pub async fn resolve_folder_roots(&self, client: &ClientWrapper) {
+    let containers = self.folder_containers.borrow().clone();
+    for container in containers {
+      let Some(root_dir) = container.root_dir() else {
+        continue;
+      };
+      if let Err(err) = container.resolve_by_path(root_dir, client).await {
+        log_warn!(self.environment, "Failed resolving plugins for {}: {:#}", root_dir.display(), err);
+      }
+    }
+  }
+
+  /// The plugin scopes that have been resolved so far in all the containers.
+  pub fn resolved_scopes(&self) -> Vec<Rc<PluginsScope<TEnvironment>>> {
+    self.all_containers().iter().flat_map(|container| container.resolved_scopes()).collect()
+  }
+
+  /// Frees the memory the initialized plugins of all the containers hold onto.
+  pub fn release_memory(&self) {
+    for container in self.all_containers() {
+      container.plugin_resolver.release_memory();
+    }
+  }
+
+  /// Clears all the resolved plugin scopes and shuts down the plugins
+  /// so they're started again on the next request.
+  pub async fn restart_plugins(&self) {
+    let config_override = self.config_override.borrow().clone();
+    self.invalidate(config_override).await;
+  }
+
+  /// Clears the resolved plugin scopes that may be affected by
+  /// changes to the provided configuration or ignore files.
+  pub async fn invalidate_for_changed_files(&self, file_paths: &[PathBuf]) {
+    for container in self.all_containers() {
+      container.invalidate_for_changed_files(file_paths).await;
+    }
+  }
+
+  /// Restarts the plugins and deletes the cache directory.
+  pub async fn clear_cache(&self) -> Result<()> {
+    self.restart_plugins().await;
+    self.plugin_resolver.clear_cache().await
+  }
+
+  pub async fn shutdown(&self) {
+    let containers = self.folder_containers.borrow_mut().drain(..).collect::<Vec<_>>();
+    for container in containers {
+      container.shutdown().await;
+    }
+    self.default_container.shutdown().await;
+  }
+
+  fn all_containers(&self) -> Vec<Rc<LspPluginsScopeContainer<TEnvironment>>> {
+    let mut containers = vec![self.default_container.clone()];
+    containers.extend(self.folder_containers.borrow().iter().cloned());
+    containers
+  }
+}
+
 pub struct LspPluginsScopeContainer<TEnvironment: Environment> {
   environment: TEnvironment,
   plugin_resolver: Rc<plugins::PluginResolver<TEnvironment>>,
+  root_dir: Option<PathBuf>,
+  config_override: RefCell<Option<String>>,
   plugins_scope_by_config: RefCell<HashMap<CanonicalizedPathBuf, Rc<ScopeCell<TEnvironment>>>>,
+  resolved_scopes: RefCell<HashMap<CanonicalizedPathBuf, Rc<PluginsScope<TEnvironment>>>>,
 }
 
 impl<TEnvironment: Environment> LspPluginsScopeContainer<TEnvironment> {
-  pub fn new(environment: TEnvironment, plugin_resolver: Rc<plugins::PluginResolver<TEnvironment>>) -> Self {
+  pub fn new(
+    environment: TEnvironment,
+    plugin_resolver: Rc<plugins::PluginResolver<TEnvironment>>,
+    root_dir: Option<PathBuf>,
+    config_override: Option<String>,
+  ) -> Self {
     Self {
       environment,
       plugin_resolver,
+      root_dir,
+      config_override: RefCell::new(config_override),
       plugins_scope_by_config: Default::default(),
+      resolved_scopes: Default::default(),
     }
   }
 
-  pub async fn shutdown(&self) {
+  /// The workspace folder this container is for.
+  pub fn root_dir(&self) -> Option<&Path> {
+    self.root_dir.as_deref()
+  }
+
+  fn clear(&self) {
     self.plugins_scope_by_config.borrow_mut().clear();
+    self.resolved_scopes.borrow_mut().clear();
+  }
+
+  /// The plugin scopes that have been resolved so far.
+  pub fn resolved_scopes(&self) -> Vec<Rc<PluginsScope<TEnvironment>>> {
+    self.resolved_scopes.borrow().values().cloned().collect()
+  }
+
+  /// Clears the resolved plugin scopes, shuts down the plugins, and sets
+  /// the path or url of the config file to use instead of the closest one.
+  pub async fn invalidate(&self, config_override: Option<String>) {
+    *self.config_override.borrow_mut() = config_override;
+    self.shutdown().await;
+  }
+
+  /// Clears the resolved plugin scopes that may be affected by changes to
+  /// the provided files and shuts down this container's plugins if any were.
+  pub async fn invalidate_for_changed_files(&self, file_paths: &[PathBuf]) {
+    if self.clear_for_changed_files(file_paths) {
+      self.plugin_resolver.clear_and_shutdown_initialized().await;
+    }
+  }
+
+  fn clear_for_changed_files(&self, file_paths: &[PathBuf]) -> bool {
+    let is_not_affected = |config_path: &CanonicalizedPathBuf| {
+      !file_paths
+        .iter()
+        .any(|file_path| is_config_affected_by_changed_file(config_path.as_ref(), file_path))
+    };
+    let mut plugins_scope_by_config = self.plugins_scope_by_config.borrow_mut();
+    let previous_len = plugins_scope_by_config.len();
+    plugins_scope_by_config.retain(|config_path, _| is_not_affected(config_path));
+    self.resolved_scopes.borrow_mut().retain(|config_path, _| is_not_affected(config_path));
+    plugins_scope_by_config.len() != previous_len
+  }
+
+  pub async fn shutdown(&self) {
+    self.clear();
     self.plugin_resolver.clear_and_shutdown_initialized().await;
   }
 
-  pub async fn resolve_by_path(&self, dir_path: &Path) -> Result<Option<Rc<PluginsScope<TEnvironment>>>> {
-    let Some(config_path) = get_default_config_file_in_ancestor_directories(&self.environment, dir_path)? else {
+  pub async fn resolve_by_path(&self, dir_path: &Path, client: &ClientWrapper) -> Result<Option<Rc<PluginsScope<TEnvironment>>>> {
+    let Some(config_path) = self.resolve_config_path(dir_path).await? else {
       return Ok(None);
     };
     let cell = {
@@ -57,8 +248,93 @@ impl<TEnvironment: Environment> LspPluginsScopeContainer<TEnvironment> {
       self.plugin_resolver.clear_and_shutdown_initialized().await;
     }
 
-    let new_scope = Rc::new(resolve_plugins_scope(Rc::new(config), &self.environment, &self.plugin_resolver).await?);
+    // this may take a while when the plugins need to be downloaded and compiled
+    let progress = client
+      .start_progress(
+        "Resolving dprint plugins".to_string(),
+        Some(config_path.resolved_path.file_path.display().to_string()),
+      )
+      .await;
+    let result = resolve_plugins_scope(Rc::new(config), &self.environment, &self.plugin_resolver).await;
+    if let Some(progress) = progress {
+      progress.finish().await;
+    }
+    let new_scope = Rc::new(result?);
     let _ = cell.insert(new_scope.clone());
+    self.resolved_scopes.borrow_mut().insert(config_path.resolved_path.file_path, new_scope.clone());
     Ok(Some(new_scope))
   }
+
+  async fn resolve_config_path(&self, dir_path: &Path) -> Result<Option<ResolvedConfigPath>> {
+    let config_override = self.config_override.borrow().clone();
+    let Some(config_override) = config_override else {
+      return get_default_config_file_in_ancestor_directories(&self.environment, dir_path);
+    };
+    let base_path = match &self.root_dir {
+      Some(root_dir) => self.environment.canonicalize(root_dir)?,
+      None => self.environment.cwd(),
+    };
+    let resolved_path = resolve_url_or_file_path(&config_override, &PathSource::new_local(base_path.clone()), &self.environment).await?;
+    Ok(Some(ResolvedConfigPath { resolved_path, base_path }))
+  }
+}
+
+/// Gets if a change to the file may affect the configuration, which is when
+/// it's the config file itself or a file in an ancestor or descendant directory
+/// (ex. a .gitignore file).
+fn is_config_affected_by_changed_file(config_path: &Path, changed_file_path: &Path) -> bool {
+  if config_path == changed_file_path {
+    return true;
+  }
+  match (config_path.parent(), changed_file_path.parent()) {
+    (Some(config_dir), Some(changed_dir)) => config_dir.starts_with(changed_dir) || changed_dir.starts_with(config_dir),
+    _ => false,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::path::PathBuf;
+  use std::rc::Rc;
+
+  use crate::environment::TestEnvironment;
+  use crate::plugins::PluginCache;
+  use crate::plugins::PluginResolver;
+
+  use super::is_config_affected_by_changed_file;
+  use super::LspWorkspaceFolders;
+
+  #[tokio::test]
+  async fn should_get_container_for_innermost_folder() {
+    let environment = TestEnvironment::new();
+    let plugin_resolver = Rc::new(PluginResolver::new(environment.clone(), PluginCache::new(environment.clone())));
+    let folders = LspWorkspaceFolders::new(environment, plugin_resolver);
+    folders.add(PathBuf::from("/a"));
+    folders.add(PathBuf::from("/a/b"));
+    folders.add(PathBuf::from("/a/b")); // ignores duplicates
+    folders.add(PathBuf::from("/c"));
+
+    let get_root_dir = |path: &str| folders.get_for_path(&PathBuf::from(path)).root_dir().map(|p| p.to_path_buf());
+    assert_eq!(get_root_dir("/a/file.txt"), Some(PathBuf::from("/a")));
+    assert_eq!(get_root_dir("/a/b/c/file.txt"), Some(PathBuf::from("/a/b")));
+    assert_eq!(get_root_dir("/a/bc/file.txt"), Some(PathBuf::from("/a")));
+    assert_eq!(get_root_dir("/c/file.txt"), Some(PathBuf::from("/c")));
+    assert_eq!(get_root_dir("/d/file.txt"), None);
+
+    folders.remove(&PathBuf::from("/a/b")).await;
+    assert_eq!(get_root_dir("/a/b/c/file.txt"), Some(PathBuf::from("/a")));
+  }
+
+  #[test]
+  fn should_get_if_config_affected_by_changed_file() {
+    let is_affected =
+      |config_path: &str, changed_file_path: &str| is_config_affected_by_changed_file(&PathBuf::from(config_path), &PathBuf::from(changed_file_path));
+    assert!(is_affected("/a/dprint.json", "/a/dprint.json"));
+    assert!(is_affected("/a/dprint.json", "/a/.gitignore"));
+    assert!(is_affected("/a/dprint.json", "/.gitignore"));
+    assert!(is_affected("/a/dprint.json", "/a/b/.gitignore"));
+    assert!(is_affected("/a/b/dprint.json", "/a/dprint.json"));
+    assert!(!is_affected("/a/dprint.json", "/b/dprint.json"));
+    assert!(!is_affected("/a/b/dprint.json", "/a/c/.gitignore"));
+  }
 }
@@ -30,7 +30,6 @@ impl IndexValid {
 pub struct Document {
   line_index: Option<LineIndex>,
   version: i32,
-  #[allow(dead_code)]
   pub language_id: String,
   pub text: String,
 }
@@ -60,6 +59,14 @@ impl<TEnvironment: Environment> Documents<TEnvironment> {
     );
   }
 
+  pub fn language_id(&self, uri: &Url) -> Option<&str> {
+    self.docs.get(uri).map(|entry| entry.language_id.as_str())
+  }
+
+  pub fn text(&self, uri: &Url) -> Option<&str> {
+    self.docs.get(uri).map(|entry| entry.text.as_str())
+  }
+
   pub fn get_content(&self, uri: &Url) -> Option<(String, Option<LineIndex>)> {
     let Some(entry) = self.docs.get(uri) else {
       log_warn!(self.environment, "Missing document: {}", uri);
@@ -128,4 +135,8 @@ impl<TEnvironment: Environment> Documents<TEnvironment> {
   pub fn closed(&mut self, params: DidCloseTextDocumentParams) {
     self.docs.remove(&params.text_document.uri);
   }
+
+  pub fn uris(&self) -> Vec<Url> {
+    self.docs.keys().cloned().collect()
+  }
 }
@@ -0,0 +1,99 @@
+use std::path::Path;
+use std::time::Duration;
+
+use tower_lsp::lsp_types::MessageType;
+
+use super::settings::LspSettings;
+
+/// When to log how long it took to format a document.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FormatTimingSettings {
+  pub log_format_times: bool,
+  pub slow_format_threshold_ms: Option<u64>,
+}
+
+impl FormatTimingSettings {
+  pub fn from_settings(settings: &LspSettings) -> Self {
+    Self {
+      log_format_times: settings.log_format_times,
+      slow_format_threshold_ms: settings.slow_format_threshold,
+    }
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    self.log_format_times || self.slow_format_threshold_ms.is_some()
+  }
+}
+
+/// Gets the message to log for the time it took the plugins to format a file.
+pub fn get_format_timing_message(
+  settings: &FormatTimingSettings,
+  file_path: &Path,
+  plugin_names: &[String],
+  elapsed: Duration,
+) -> Option<(MessageType, String)> {
+  let elapsed_ms = elapsed.as_millis();
+  let plugins_text = if plugin_names.is_empty() {
+    "no plugins".to_string()
+  } else {
+    plugin_names.join(", ")
+  };
+  match settings.slow_format_threshold_ms {
+    Some(threshold_ms) if elapsed_ms > threshold_ms as u128 => Some((
+      MessageType::WARNING,
+      format!(
+        "Formatting {} took {}ms, which exceeds the slow format threshold of {}ms ({})",
+        file_path.display(),
+        elapsed_ms,
+        threshold_ms,
+        plugins_text,
+      ),
+    )),
+    _ if settings.log_format_times => Some((
+      MessageType::INFO,
+      format!("Formatted {} in {}ms ({})", file_path.display(), elapsed_ms, plugins_text),
+    )),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::path::PathBuf;
+
+  use super::*;
+
+  #[test]
+  fn should_get_format_timing_message() {
+    let file_path = PathBuf::from("file.ts");
+    let plugin_names = vec!["typescript".to_string()];
+    let get_message = |log_format_times: bool, slow_format_threshold_ms: Option<u64>, elapsed_ms: u64| {
+      get_format_timing_message(
+        &FormatTimingSettings {
+          log_format_times,
+          slow_format_threshold_ms,
+        },
+        &file_path,
+        &plugin_names,
+        Duration::from_millis(elapsed_ms),
+      )
+    };
+    assert_eq!(get_message(false, None, 100), None);
+    assert_eq!(
+      get_message(true, None, 100),
+      Some((MessageType::INFO, "Formatted file.ts in 100ms (typescript)".to_string()))
+    );
+    assert_eq!(get_message(false, Some(100), 100), None);
+    assert_eq!(
+      get_message(true, Some(100), 100),
+      Some((MessageType::INFO, "Formatted file.ts in 100ms (typescript)".to_string()))
+    );
+    assert_eq!(
+      get_message(false, Some(100), 101),
+      Some((
+        MessageType::WARNING,
+        "Formatting file.ts took 101ms, which exceeds the slow format threshold of 100ms (typescript)".to_string()
+      ))
+    );
+  }
+}
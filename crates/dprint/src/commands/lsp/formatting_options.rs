@@ -0,0 +1,80 @@
+use dprint_core::configuration::ConfigKeyMap;
+use dprint_core::configuration::ConfigKeyValue;
+use dprint_core::configuration::GlobalConfiguration;
+use tower_lsp::lsp_types::FormattingOptions;
+
+use crate::environment::Environment;
+use crate::resolution::PluginsScope;
+
+/// Gets the config to override the plugins' config with based on the
+/// editor's formatting options. Keys set in the project's config are
+/// not overridden so that the editor formats the same as the CLI.
+///
+/// Note that `trimTrailingWhitespace` and `insertFinalNewline` are not
+/// mapped because there's no equivalent global configuration.
+pub fn get_formatting_options_override_config<TEnvironment: Environment>(options: &FormattingOptions, scope: &PluginsScope<TEnvironment>) -> ConfigKeyMap {
+  let mut override_config = get_override_config(options);
+  for plugin in scope.plugins.values() {
+    remove_configured_keys(&mut override_config, &plugin.format_config.global, &plugin.format_config.plugin);
+  }
+  override_config
+}
+
+fn get_override_config(options: &FormattingOptions) -> ConfigKeyMap {
+  let mut config = ConfigKeyMap::new();
+  if options.tab_size > 0 {
+    config.insert("indentWidth".to_string(), ConfigKeyValue::from_i32(options.tab_size as i32));
+  }
+  config.insert("useTabs".to_string(), ConfigKeyValue::from_bool(!options.insert_spaces));
+  config
+}
+
+fn remove_configured_keys(override_config: &mut ConfigKeyMap, global_config: &GlobalConfiguration, plugin_config: &ConfigKeyMap) {
+  override_config.retain(|key, _| {
+    let is_global_configured = match key.as_str() {
+      "indentWidth" => global_config.indent_width.is_some(),
+      "useTabs" => global_config.use_tabs.is_some(),
+      _ => false,
+    };
+    !is_global_configured && !plugin_config.contains_key(key)
+  });
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_get_override_config() {
+    let options = FormattingOptions {
+      tab_size: 4,
+      insert_spaces: false,
+      ..Default::default()
+    };
+    let mut override_config = get_override_config(&options);
+    assert_eq!(
+      override_config,
+      ConfigKeyMap::from([
+        ("indentWidth".to_string(), ConfigKeyValue::from_i32(4)),
+        ("useTabs".to_string(), ConfigKeyValue::from_bool(true)),
+      ])
+    );
+
+    remove_configured_keys(
+      &mut override_config,
+      &Default::default(),
+      &ConfigKeyMap::from([("useTabs".to_string(), ConfigKeyValue::from_bool(false))]),
+    );
+    assert_eq!(override_config, ConfigKeyMap::from([("indentWidth".to_string(), ConfigKeyValue::from_i32(4))]));
+
+    remove_configured_keys(
+      &mut override_config,
+      &GlobalConfiguration {
+        indent_width: Some(2),
+        ..Default::default()
+      },
+      &ConfigKeyMap::new(),
+    );
+    assert_eq!(override_config, ConfigKeyMap::new());
+  }
+}
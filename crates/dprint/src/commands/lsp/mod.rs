@@ -3,54 +3,99 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
 
 use anyhow::Context;
 use anyhow::Result;
+use dprint_core::async_runtime::future;
 use dprint_core::async_runtime::JoinHandle;
 use dprint_core::plugins::process::start_parent_process_checker_task;
 use dprint_core::plugins::FormatRange;
 use dprint_core::plugins::HostFormatRequest;
 use parking_lot::Mutex;
+use serde::Deserialize;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+use tokio::net::TcpListener;
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
 use tokio::sync::Semaphore;
 use tokio::try_join;
 use tokio_util::sync::CancellationToken;
+use tower_lsp::jsonrpc::Error as LspError;
 use tower_lsp::jsonrpc::Result as LspResult;
+use tower_lsp::lsp_types::Diagnostic;
+use tower_lsp::lsp_types::DiagnosticSeverity;
+use tower_lsp::lsp_types::DidChangeConfigurationParams;
 use tower_lsp::lsp_types::DidChangeTextDocumentParams;
+use tower_lsp::lsp_types::DidChangeWatchedFilesParams;
+use tower_lsp::lsp_types::DidChangeWatchedFilesRegistrationOptions;
+use tower_lsp::lsp_types::DidChangeWorkspaceFoldersParams;
 use tower_lsp::lsp_types::DidCloseTextDocumentParams;
 use tower_lsp::lsp_types::DidOpenTextDocumentParams;
 use tower_lsp::lsp_types::DocumentFormattingParams;
+use tower_lsp::lsp_types::DocumentOnTypeFormattingOptions;
+use tower_lsp::lsp_types::DocumentOnTypeFormattingParams;
 use tower_lsp::lsp_types::DocumentRangeFormattingParams;
+use tower_lsp::lsp_types::ExecuteCommandOptions;
+use tower_lsp::lsp_types::ExecuteCommandParams;
+use tower_lsp::lsp_types::FileSystemWatcher;
+use tower_lsp::lsp_types::FormattingOptions;
+use tower_lsp::lsp_types::GlobPattern;
 use tower_lsp::lsp_types::InitializeParams;
 use tower_lsp::lsp_types::InitializeResult;
 use tower_lsp::lsp_types::InitializedParams;
 use tower_lsp::lsp_types::OneOf;
+use tower_lsp::lsp_types::Position;
+use tower_lsp::lsp_types::Range;
+use tower_lsp::lsp_types::Registration;
 use tower_lsp::lsp_types::ServerCapabilities;
 use tower_lsp::lsp_types::ServerInfo;
+use tower_lsp::lsp_types::TextDocumentIdentifier;
 use tower_lsp::lsp_types::TextDocumentSyncCapability;
 use tower_lsp::lsp_types::TextDocumentSyncKind;
 use tower_lsp::lsp_types::TextDocumentSyncOptions;
 use tower_lsp::lsp_types::TextEdit;
+use tower_lsp::lsp_types::WorkspaceFolder;
+use tower_lsp::lsp_types::WorkspaceFoldersServerCapabilities;
+use tower_lsp::lsp_types::WorkspaceServerCapabilities;
 use tower_lsp::LanguageServer;
 use tower_lsp::LspService;
 use tower_lsp::Server;
 use url::Url;
 
 use crate::arg_parser::CliArgs;
+use crate::arg_parser::LspSubCommand;
 use crate::environment::Environment;
 use crate::plugins::PluginResolver;
 
+use self::capabilities::CapabilitiesService;
 use self::client::ClientWrapper;
-use self::config::LspPluginsScopeContainer;
+use self::config::LspWorkspaceFolders;
 use self::documents::Documents;
+use self::format_timing::get_format_timing_message;
+use self::format_timing::FormatTimingSettings;
+use self::formatting_options::get_formatting_options_override_config;
+use self::selectors::DocumentSelectorRegistration;
+use self::settings::LspSettings;
+use self::status::LspStatus;
+use self::status::StatusNotifier;
 use self::text::get_edits;
 use self::text::LineIndex;
+use self::untitled::get_untitled_file_path;
 
+mod capabilities;
 mod client;
 mod config;
 mod documents;
+mod format_timing;
+mod formatting_options;
+mod selectors;
+mod settings;
+mod status;
 mod text;
+mod untitled;
 
 // tower-lsp will drop the future on cancellation,
 // so use this to cancel the containing token on drop.
@@ -104,6 +149,10 @@ impl PendingTokens {
     }
   }
 
+  pub fn is_empty(&self) -> bool {
+    self.tokens.borrow().is_empty()
+  }
+
   pub fn cancel_all(&mut self) {
     let mut pending_tokens = self.tokens.borrow_mut();
     for (_, token) in pending_tokens.iter() {
@@ -123,16 +172,52 @@ impl PendingTokens {
   }
 }
 
+/// Configuration files and ignore files that affect the resolved plugin scopes.
+const WATCHED_FILES_PATTERN: &str = "**/{dprint.json,dprint.jsonc,.dprint.json,.dprint.jsonc,.gitignore}";
+
+/// How long to wait without any formatting before freeing the memory the plugins
+/// grew to, so the language server doesn't hold onto its peak memory forever.
+const RELEASE_MEMORY_IDLE_DURATION: Duration = Duration::from_secs(60);
+
+const RESTART_PLUGINS_COMMAND: &str = "dprint.restartPlugins";
+const CLEAR_CACHE_COMMAND: &str = "dprint.clearCache";
+
 struct EditorFormatRequest {
   pub file_path: PathBuf,
   pub file_text: String,
   pub maybe_line_index: Option<LineIndex>,
   pub range: FormatRange,
+  /// The editor's formatting options to use when not configured.
+  pub formatting_options: Option<FormattingOptions>,
+  /// When to log how long the plugins took to format the document.
+  pub timing: FormatTimingSettings,
   pub token: Arc<CancellationToken>,
 }
 
+/// Parameters of the `textDocument/rangesFormatting` request, which
+/// was added in LSP 3.18 and isn't in lsp-types yet.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DocumentRangesFormattingParams {
+  text_document: TextDocumentIdentifier,
+  ranges: Vec<Range>,
+  options: FormattingOptions,
+}
+
 enum ChannelMessage {
-  Format(EditorFormatRequest, oneshot::Sender<Result<Option<Vec<TextEdit>>>>),
+  Format(Box<EditorFormatRequest>, oneshot::Sender<Result<Option<Vec<TextEdit>>>>),
+  WorkspaceFoldersChanged {
+    added: Vec<PathBuf>,
+    removed: Vec<PathBuf>,
+  },
+  InvalidateConfig {
+    config_override: Option<String>,
+  },
+  ConfigFilesChanged(Vec<PathBuf>),
+  RestartPlugins(oneshot::Sender<()>),
+  ClearCache(oneshot::Sender<Result<()>>),
+  RegisterDocumentSelectors,
+  GetStatus(oneshot::Sender<LspStatus>),
   Shutdown(oneshot::Sender<()>),
   /// This message is used for testing.
   #[cfg(test)]
@@ -141,7 +226,8 @@ enum ChannelMessage {
 
 async fn handle_format_request<TEnvironment: Environment>(
   mut request: EditorFormatRequest,
-  scope_container: Rc<LspPluginsScopeContainer<TEnvironment>>,
+  workspace_folders: Rc<LspWorkspaceFolders<TEnvironment>>,
+  client: &ClientWrapper,
   environment: &TEnvironment,
 ) -> Result<Option<Vec<TextEdit>>> {
   let Some(parent_dir) = request.file_path.parent() else {
@@ -151,7 +237,8 @@ async fn handle_format_request<TEnvironment: Environment>(
   if request.token.is_cancelled() {
     return Ok(None);
   }
-  let Some(scope) = scope_container.resolve_by_path(parent_dir).await? else {
+  let scope_container = workspace_folders.get_for_path(&request.file_path);
+  let Some(scope) = scope_container.resolve_by_path(parent_dir, client).await? else {
     log_stderr_info!(environment, "Path did not have a dprint config file: {}", request.file_path.display());
     return Ok(None);
   };
@@ -169,16 +256,32 @@ async fn handle_format_request<TEnvironment: Environment>(
     return Ok(None);
   }
 
-  let Some(result) = scope
+  let override_config = match &request.formatting_options {
+    Some(options) => get_formatting_options_override_config(options, &scope),
+    None => Default::default(),
+  };
+  let timing_info = request.timing.is_enabled().then(|| {
+    (
+      request.file_path.clone(),
+      scope.plugin_name_maps.get_plugin_names_from_file_path(&request.file_path),
+    )
+  });
+  let start_instant = Instant::now();
+  let result = scope
     .format(HostFormatRequest {
       file_path: request.file_path,
       file_bytes: request.file_text.as_bytes().to_vec(),
       range: request.range,
-      override_config: Default::default(),
+      override_config,
       token: request.token,
     })
-    .await?
-  else {
+    .await;
+  if let Some((file_path, plugin_names)) = timing_info {
+    if let Some((message_type, message)) = get_format_timing_message(&request.timing, &file_path, &plugin_names, start_instant.elapsed()) {
+      client.log(message_type, message);
+    }
+  }
+  let Some(result) = result? else {
     return Ok(None);
   };
   dprint_core::async_runtime::spawn_blocking(move || {
@@ -193,20 +296,24 @@ pub async fn run_language_server<TEnvironment: Environment>(
   _args: &CliArgs,
   environment: &TEnvironment,
   plugin_resolver: &Rc<PluginResolver<TEnvironment>>,
+  cmd: &LspSubCommand,
 ) -> anyhow::Result<()> {
-  let stdin = tokio::io::stdin();
-  let stdout = tokio::io::stdout();
+  let (reader, writer) = connect_transport(cmd, environment).await?;
   let (tx, rx) = mpsc::unbounded_channel();
 
-  let recv_task = start_message_handler(environment, plugin_resolver, rx);
+  let mut lsp_client = None;
+  let (service, socket) = LspService::build(|client| {
+    let client = ClientWrapper::new(Arc::new(client));
+    lsp_client = Some(client.clone());
+    Backend::new(client, environment.clone(), tx)
+  })
+  .custom_method("textDocument/rangesFormatting", Backend::ranges_formatting)
+  .custom_method("dprint/status", Backend::status)
+  .finish();
+  let recv_task = start_message_handler(environment, plugin_resolver, lsp_client.unwrap(), rx);
 
-  let environment = environment.clone();
   let lsp_task = dprint_core::async_runtime::spawn(async move {
-    let (service, socket) = LspService::new(|client| {
-      let client = ClientWrapper::new(Arc::new(client));
-      Backend::new(client.clone(), environment.clone(), tx)
-    });
-    Server::new(stdin, stdout, socket).serve(service).await;
+    Server::new(reader, writer, socket).serve(CapabilitiesService::new(service)).await;
   });
 
   try_join!(recv_task, lsp_task)?;
@@ -214,9 +321,42 @@ pub async fn run_language_server<TEnvironment: Environment>(
   Ok(())
 }
 
+type LspTransport = (Box<dyn AsyncRead + Unpin>, Box<dyn AsyncWrite + Unpin>);
+
+async fn connect_transport(cmd: &LspSubCommand, environment: &impl Environment) -> Result<LspTransport> {
+  if let Some(port) = cmd.port {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+      .await
+      .with_context(|| format!("Failed listening on port {}.", port))?;
+    log_stderr_info!(environment, "Listening on 127.0.0.1:{}", port);
+    let (stream, _) = listener.accept().await?;
+    let (reader, writer) = stream.into_split();
+    Ok((Box::new(reader), Box::new(writer)))
+  } else if let Some(pipe) = &cmd.pipe {
+    connect_pipe(pipe).await.with_context(|| format!("Failed connecting to pipe {}.", pipe))
+  } else {
+    Ok((Box::new(tokio::io::stdin()), Box::new(tokio::io::stdout())))
+  }
+}
+
+#[cfg(unix)]
+async fn connect_pipe(pipe: &str) -> Result<LspTransport> {
+  let stream = tokio::net::UnixStream::connect(pipe).await?;
+  let (reader, writer) = stream.into_split();
+  Ok((Box::new(reader), Box::new(writer)))
+}
+
+#[cfg(windows)]
+async fn connect_pipe(pipe: &str) -> Result<LspTransport> {
+  let client = tokio::net::windows::named_pipe::ClientOptions::new().open(pipe)?;
+  let (reader, writer) = tokio::io::split(client);
+  Ok((Box::new(reader), Box::new(writer)))
+}
+
 fn start_message_handler<TEnvironment: Environment>(
   environment: &TEnvironment,
   plugin_resolver: &Rc<PluginResolver<TEnvironment>>,
+  client: ClientWrapper,
   mut rx: mpsc::UnboundedReceiver<ChannelMessage>,
 ) -> JoinHandle<()> {
   // tower_lsp requires Backend to implement Send and Sync, but
@@ -225,41 +365,186 @@ fn start_message_handler<TEnvironment: Environment>(
   let max_cores = environment.max_threads();
   let concurrency_limiter = Rc::new(Semaphore::new(std::cmp::max(1, max_cores - 1)));
   let environment = environment.clone();
-  let scope_container = Rc::new(LspPluginsScopeContainer::new(environment.clone(), plugin_resolver.clone()));
+  let workspace_folders = Rc::new(LspWorkspaceFolders::new(environment.clone(), plugin_resolver.clone()));
+  let document_selectors: Rc<RefCell<Option<DocumentSelectorRegistration>>> = Default::default();
+  let status_notifier = Rc::new(StatusNotifier::new(client.clone()));
+  let queue_resolve_folder_roots = {
+    let workspace_folders = workspace_folders.clone();
+    let document_selectors = document_selectors.clone();
+    let status_notifier = status_notifier.clone();
+    let client = client.clone();
+    move || {
+      if document_selectors.borrow().is_none() {
+        // only the document selectors need the plugins up front, otherwise
+        // they're resolved lazily when a document is first formatted
+        status_notifier.notify(&workspace_folders);
+        return;
+      }
+      let workspace_folders = workspace_folders.clone();
+      let document_selectors = document_selectors.clone();
+      let status_notifier = status_notifier.clone();
+      let client = client.clone();
+      status_notifier.start_resolving(&workspace_folders);
+      dprint_core::async_runtime::spawn(async move {
+        workspace_folders.resolve_folder_roots(&client).await;
+        if let Some(document_selectors) = document_selectors.borrow().as_ref() {
+          document_selectors.update(&workspace_folders);
+        }
+        status_notifier.finish_resolving(&workspace_folders);
+      });
+    }
+  };
   dprint_core::async_runtime::spawn(async move {
     let mut pending_tokens = PendingTokens::default();
-    while let Some(message) = rx.recv().await {
+    let mut is_memory_released = true;
+    loop {
+      let message = if is_memory_released {
+        rx.recv().await
+      } else {
+        match tokio::time::timeout(RELEASE_MEMORY_IDLE_DURATION, rx.recv()).await {
+          Ok(message) => message,
+          Err(_) => {
+            if pending_tokens.is_empty() {
+              log_debug!(environment, "Releasing plugin memory after being idle.");
+              workspace_folders.release_memory();
+              is_memory_released = true;
+            }
+            continue;
+          }
+        }
+      };
+      let Some(message) = message else {
+        break;
+      };
       match message {
         ChannelMessage::Format(request, sender) => {
+          is_memory_released = false;
           let token_guard = pending_tokens.insert(request.token.clone());
           let concurrency_limiter = concurrency_limiter.clone();
-          let scope_container = scope_container.clone();
+          let workspace_folders = workspace_folders.clone();
+          let client = client.clone();
           let environment = environment.clone();
+          let document_selectors = document_selectors.clone();
+          let status_notifier = status_notifier.clone();
           dprint_core::async_runtime::spawn(async move {
             let _permit = concurrency_limiter.acquire().await;
-            let result = handle_format_request(request, scope_container, &environment).await;
+            let result = handle_format_request(*request, workspace_folders.clone(), &client, &environment).await;
             let _ = sender.send(result);
+            // the format may have resolved the plugins of another config file
+            if let Some(document_selectors) = document_selectors.borrow().as_ref() {
+              document_selectors.update(&workspace_folders);
+            }
+            status_notifier.notify(&workspace_folders);
             drop(token_guard); // remove the token from the pending tokens
           });
         }
+        ChannelMessage::WorkspaceFoldersChanged { added, removed } => {
+          for folder_path in removed {
+            workspace_folders.remove(&folder_path).await;
+          }
+          for folder_path in added {
+            workspace_folders.add(folder_path);
+          }
+          queue_resolve_folder_roots();
+        }
+        ChannelMessage::InvalidateConfig { config_override } => {
+          workspace_folders.invalidate(config_override).await;
+          queue_resolve_folder_roots();
+        }
+        ChannelMessage::ConfigFilesChanged(file_paths) => {
+          let file_paths = file_paths
+            .into_iter()
+            .map(|file_path| canonicalize_changed_file_path(&environment, file_path))
+            .collect::<Vec<_>>();
+          workspace_folders.invalidate_for_changed_files(&file_paths).await;
+          queue_resolve_folder_roots();
+        }
+        ChannelMessage::RestartPlugins(sender) => {
+          workspace_folders.restart_plugins().await;
+          let _ = sender.send(());
+          queue_resolve_folder_roots();
+        }
+        ChannelMessage::ClearCache(sender) => {
+          let _ = sender.send(workspace_folders.clear_cache().await);
+          queue_resolve_folder_roots();
+        }
+        ChannelMessage::RegisterDocumentSelectors => {
+          document_selectors.replace(Some(DocumentSelectorRegistration::new(client.clone())));
+          queue_resolve_folder_roots();
+        }
+        ChannelMessage::GetStatus(sender) => {
+          let _ = sender.send(status_notifier.get_status(&workspace_folders));
+        }
         ChannelMessage::Shutdown(sender) => {
           pending_tokens.cancel_all();
-          scope_container.shutdown().await;
+          workspace_folders.shutdown().await;
           let _ = sender.send(());
           break; // exit
         }
         #[cfg(test)]
         ChannelMessage::HasPending(sender) => {
-          let is_empty = pending_tokens.tokens.borrow().is_empty();
-          let _ = sender.send(!is_empty);
+          let _ = sender.send(!pending_tokens.is_empty());
         }
       }
     }
   })
 }
 
+/// Canonicalizes the directory of the file since the file itself may have been deleted.
+fn canonicalize_changed_file_path(environment: &impl Environment, file_path: PathBuf) -> PathBuf {
+  match (file_path.parent(), file_path.file_name()) {
+    (Some(dir_path), Some(file_name)) => match environment.canonicalize(dir_path) {
+      Ok(dir_path) => dir_path.join(file_name),
+      Err(_) => file_path,
+    },
+    _ => file_path,
+  }
+}
+
+async fn send_format_request(sender: &mpsc::UnboundedSender<ChannelMessage>, request: EditorFormatRequest) -> Result<Option<Vec<TextEdit>>> {
+  let (tx, rx) = oneshot::channel();
+  sender.send(ChannelMessage::Format(Box::new(request), tx))?;
+  rx.await?
+}
+
+/// Merges the edits from formatting several ranges of the same text.
+///
+/// Each range is formatted independently, so an edit that overlaps
+/// one from a previous range is dropped rather than applied twice.
+fn merge_range_edits(edits_by_range: Vec<Vec<TextEdit>>) -> Vec<TextEdit> {
+  let mut edits: Vec<TextEdit> = Vec::new();
+  for edit in edits_by_range.into_iter().flatten() {
+    let overlaps = edits
+      .iter()
+      .any(|e| e.range == edit.range || (e.range.start < edit.range.end && edit.range.start < e.range.end));
+    if !overlaps {
+      edits.push(edit);
+    }
+  }
+  edits.sort_by_key(|e| e.range.start);
+  edits
+}
+
+fn get_unformatted_diagnostics(edits: &[TextEdit]) -> Vec<Diagnostic> {
+  match edits.first() {
+    Some(edit) => vec![Diagnostic {
+      range: edit.range,
+      severity: Some(DiagnosticSeverity::WARNING),
+      source: Some("dprint".to_string()),
+      message: "File is not formatted.".to_string(),
+      ..Default::default()
+    }],
+    None => Vec::new(),
+  }
+}
+
 struct State<TEnvironment: Environment> {
   documents: Documents<TEnvironment>,
+  settings: LspSettings,
+  supports_watched_files_registration: bool,
+  supports_formatting_registration: bool,
+  diagnostic_tokens: HashMap<Url, Arc<CancellationToken>>,
+  workspace_folders: Vec<PathBuf>,
 }
 
 struct Backend<TEnvironment: Environment> {
@@ -277,13 +562,18 @@ impl<TEnvironment: Environment> Backend<TEnvironment> {
       sender,
       state: Mutex::new(State {
         documents: Documents::new(environment),
+        settings: Default::default(),
+        supports_watched_files_registration: false,
+        supports_formatting_registration: false,
+        diagnostic_tokens: Default::default(),
+        workspace_folders: Default::default(),
       }),
     }
   }
 
   async fn send_format_request(&self, uri: &Url, request: EditorFormatRequest) -> LspResult<Option<Vec<TextEdit>>> {
     let mut drop_token = DropToken::new(request.token.clone());
-    let result = self.send_format_request_inner(request).await;
+    let result = send_format_request(&self.sender, request).await;
     drop_token.completed();
     match result {
       Ok(value) => Ok(value),
@@ -294,10 +584,198 @@ impl<TEnvironment: Environment> Backend<TEnvironment> {
     }
   }
 
-  async fn send_format_request_inner(&self, request: EditorFormatRequest) -> Result<Option<Vec<TextEdit>>> {
+  fn send_workspace_folders_changed(&self, added: Vec<WorkspaceFolder>, removed: Vec<WorkspaceFolder>) {
+    let to_paths = |folders: Vec<WorkspaceFolder>| folders.iter().filter_map(|f| url_to_file_path(&f.uri)).collect::<Vec<_>>();
+    let added = to_paths(added);
+    let removed = to_paths(removed);
+    {
+      let mut state = self.state.lock();
+      state.workspace_folders.retain(|folder| !removed.contains(folder));
+      state.workspace_folders.extend(added.iter().cloned());
+    }
+    let _ = self.sender.send(ChannelMessage::WorkspaceFoldersChanged { added, removed });
+  }
+
+  /// Gets the file path to format the document as. Untitled documents are
+  /// formatted as a file in the root directory based on their language.
+  fn get_file_path(&self, uri: &Url) -> Option<PathBuf> {
+    if uri.scheme() != "untitled" {
+      return url_to_file_path(uri);
+    }
+    let state = self.state.lock();
+    let language_id = state.documents.language_id(uri)?;
+    let root_dir = match state.workspace_folders.first() {
+      Some(folder) => folder.clone(),
+      None => self.environment.cwd().into_path_buf(),
+    };
+    let file_path = get_untitled_file_path(&root_dir, language_id, &state.settings.language_extensions);
+    if file_path.is_none() {
+      log_debug!(self.environment, "No extension for language '{}' of untitled document: {}", language_id, uri);
+    }
+    file_path
+  }
+
+  fn update_settings(&self, settings: LspSettings) {
+    let config_override = settings.config.clone();
+    let uris_to_clear = {
+      let mut state = self.state.lock();
+      state.settings = settings;
+      if state.settings.diagnostics {
+        Vec::new()
+      } else {
+        state
+          .diagnostic_tokens
+          .drain()
+          .map(|(uri, token)| {
+            token.cancel();
+            uri
+          })
+          .collect::<Vec<_>>()
+      }
+    };
+    // send this before checking the documents so they're checked with the new config
+    let _ = self.sender.send(ChannelMessage::InvalidateConfig { config_override });
+    self.queue_check_all_formatted();
+    for uri in uris_to_clear {
+      self.client.publish_diagnostics(uri, Vec::new());
+    }
+  }
+
+  fn get_formatting_options(&self, options: FormattingOptions) -> Option<FormattingOptions> {
+    self.state.lock().settings.editor_formatting_options.then_some(options)
+  }
+
+  fn get_format_timing(&self) -> FormatTimingSettings {
+    FormatTimingSettings::from_settings(&self.state.lock().settings)
+  }
+
+  fn register_watched_files(&self) {
+    let options = DidChangeWatchedFilesRegistrationOptions {
+      watchers: vec![FileSystemWatcher {
+        glob_pattern: GlobPattern::String(WATCHED_FILES_PATTERN.to_string()),
+        kind: None,
+      }],
+    };
+    self.client.register_capability(vec![Registration {
+      id: "dprint-watched-files".to_string(),
+      method: "workspace/didChangeWatchedFiles".to_string(),
+      register_options: serde_json::to_value(options).ok(),
+    }]);
+  }
+
+  fn queue_check_all_formatted(&self) {
+    let uris = {
+      let state = self.state.lock();
+      if !state.settings.diagnostics {
+        return;
+      }
+      state.documents.uris()
+    };
+    for uri in uris {
+      self.queue_check_formatted(&uri);
+    }
+  }
+
+  /// Checks if the document is formatted in the background and publishes
+  /// a diagnostic when it's not. Does nothing unless enabled in the settings.
+  fn queue_check_formatted(&self, uri: &Url) {
+    let Some(file_path) = self.get_file_path(uri) else {
+      return;
+    };
+    let token = Arc::new(CancellationToken::new());
+    let (file_text, maybe_line_index) = {
+      let mut state = self.state.lock();
+      if !state.settings.diagnostics {
+        return;
+      }
+      let Some(content) = state.documents.get_content(uri) else {
+        return;
+      };
+      if let Some(previous_token) = state.diagnostic_tokens.insert(uri.clone(), token.clone()) {
+        previous_token.cancel();
+      }
+      content
+    };
+    let sender = self.sender.clone();
+    let client = self.client.clone();
+    let environment = self.environment.clone();
+    let uri = uri.clone();
+    dprint_core::async_runtime::spawn(async move {
+      let result = send_format_request(
+        &sender,
+        EditorFormatRequest {
+          file_path,
+          file_text,
+          range: None,
+          maybe_line_index,
+          formatting_options: None,
+          timing: Default::default(),
+          token: token.clone(),
+        },
+      )
+      .await;
+      if token.is_cancelled() {
+        return;
+      }
+      let diagnostics = match result {
+        Ok(Some(edits)) => get_unformatted_diagnostics(&edits),
+        Ok(None) => Vec::new(),
+        Err(err) => {
+          // this will commonly happen while the user is typing
+          log_debug!(environment, "Failed checking if '{}' was formatted: {:#}", uri, err);
+          Vec::new()
+        }
+      };
+      client.publish_diagnostics(uri, diagnostics);
+    });
+  }
+
+  async fn ranges_formatting(&self, params: DocumentRangesFormattingParams) -> LspResult<Option<Vec<TextEdit>>> {
+    let uri = &params.text_document.uri;
+    let Some(file_path) = self.get_file_path(uri) else {
+      return Ok(None);
+    };
+    let formatting_options = self.get_formatting_options(params.options);
+    let timing = self.get_format_timing();
+    // get all the ranges from the same snapshot of the document
+    let requests = {
+      let mut state = self.state.lock();
+      let mut requests = Vec::with_capacity(params.ranges.len());
+      for range in params.ranges {
+        let Some((file_text, range, line_index)) = state.documents.get_content_with_range(uri, range) else {
+          return Ok(None);
+        };
+        requests.push(EditorFormatRequest {
+          file_path: file_path.clone(),
+          file_text,
+          range,
+          maybe_line_index: Some(line_index),
+          formatting_options: formatting_options.clone(),
+          timing,
+          token: Arc::new(CancellationToken::new()),
+        });
+      }
+      requests
+    };
+    let results = future::join_all(requests.into_iter().map(|request| self.send_format_request(uri, request))).await;
+    let mut edits_by_range = Vec::with_capacity(results.len());
+    for result in results {
+      if let Some(edits) = result? {
+        edits_by_range.push(edits);
+      }
+    }
+    if edits_by_range.is_empty() {
+      Ok(None)
+    } else {
+      Ok(Some(merge_range_edits(edits_by_range)))
+    }
+  }
+
+  /// Handles the custom `dprint/status` request.
+  async fn status(&self) -> LspResult<LspStatus> {
     let (sender, receiver) = oneshot::channel();
-    self.sender.send(ChannelMessage::Format(request, sender))?;
-    receiver.await?
+    self.sender.send(ChannelMessage::GetStatus(sender)).map_err(|_| LspError::internal_error())?;
+    receiver.await.map_err(|_| LspError::internal_error())
   }
 
   /// This is used in the test code to ensure there are no pending requests.
@@ -319,6 +797,40 @@ impl<TEnvironment: Environment> LanguageServer for Backend<TEnvironment> {
       start_parent_process_checker_task(parent_id);
     }
 
+    let workspace_folders = match params.workspace_folders {
+      Some(folders) => folders,
+      None => params
+        .root_uri
+        .map(|uri| vec![WorkspaceFolder { name: uri.to_string(), uri }])
+        .unwrap_or_default(),
+    };
+    self.send_workspace_folders_changed(workspace_folders, Vec::new());
+
+    self
+      .client
+      .set_supports_work_done_progress(params.capabilities.window.as_ref().and_then(|w| w.work_done_progress).unwrap_or(false));
+    let supports_formatting_registration = params
+      .capabilities
+      .text_document
+      .as_ref()
+      .map(|c| c.formatting.and_then(|c| c.dynamic_registration).unwrap_or(false) && c.range_formatting.and_then(|c| c.dynamic_registration).unwrap_or(false))
+      .unwrap_or(false);
+    {
+      let mut state = self.state.lock();
+      state.supports_watched_files_registration = params
+        .capabilities
+        .workspace
+        .and_then(|w| w.did_change_watched_files)
+        .and_then(|c| c.dynamic_registration)
+        .unwrap_or(false);
+      state.supports_formatting_registration = supports_formatting_registration;
+    }
+
+    match LspSettings::from_value(params.initialization_options) {
+      Ok(settings) => self.update_settings(settings),
+      Err(err) => log_warn!(self.environment, "Failed deserializing initialization options: {:#}", err),
+    }
+
     Ok(InitializeResult {
       server_info: Some(ServerInfo {
         name: "dprint".to_string(),
@@ -333,8 +845,24 @@ impl<TEnvironment: Environment> LanguageServer for Backend<TEnvironment> {
           will_save: None,
           will_save_wait_until: None,
         })),
-        document_formatting_provider: Some(OneOf::Left(true)),
-        document_range_formatting_provider: Some(OneOf::Left(true)),
+        // registered dynamically with a document selector once the plugins are resolved
+        document_formatting_provider: (!supports_formatting_registration).then_some(OneOf::Left(true)),
+        document_range_formatting_provider: (!supports_formatting_registration).then_some(OneOf::Left(true)),
+        document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+          first_trigger_character: "}".to_string(),
+          more_trigger_character: Some(vec![";".to_string(), "\n".to_string()]),
+        }),
+        execute_command_provider: Some(ExecuteCommandOptions {
+          commands: vec![RESTART_PLUGINS_COMMAND.to_string(), CLEAR_CACHE_COMMAND.to_string()],
+          work_done_progress_options: Default::default(),
+        }),
+        workspace: Some(WorkspaceServerCapabilities {
+          workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+            supported: Some(true),
+            change_notifications: Some(OneOf::Left(true)),
+          }),
+          file_operations: None,
+        }),
         ..ServerCapabilities::default()
       },
     })
@@ -348,22 +876,68 @@ impl<TEnvironment: Environment> LanguageServer for Backend<TEnvironment> {
       self.environment.cpu_arch()
     ));
     self.client.log_info("Server ready.".to_string());
+    let (supports_watched_files_registration, supports_formatting_registration) = {
+      let state = self.state.lock();
+      (state.supports_watched_files_registration, state.supports_formatting_registration)
+    };
+    if supports_watched_files_registration {
+      self.register_watched_files();
+    }
+    if supports_formatting_registration {
+      let _ = self.sender.send(ChannelMessage::RegisterDocumentSelectors);
+    }
   }
 
   async fn did_open(&self, params: DidOpenTextDocumentParams) {
+    let uri = params.text_document.uri.clone();
     self.state.lock().documents.open(params.text_document);
+    self.queue_check_formatted(&uri);
   }
 
   async fn did_change(&self, params: DidChangeTextDocumentParams) {
+    let uri = params.text_document.uri.clone();
     self.state.lock().documents.changed(params);
+    self.queue_check_formatted(&uri);
   }
 
   async fn did_close(&self, params: DidCloseTextDocumentParams) {
-    self.state.lock().documents.closed(params);
+    let uri = params.text_document.uri.clone();
+    let diagnostics_enabled = {
+      let mut state = self.state.lock();
+      state.documents.closed(params);
+      if let Some(token) = state.diagnostic_tokens.remove(&uri) {
+        token.cancel();
+      }
+      state.settings.diagnostics
+    };
+    if diagnostics_enabled {
+      self.client.publish_diagnostics(uri, Vec::new());
+    }
+  }
+
+  async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+    match LspSettings::from_change_configuration_value(params.settings) {
+      Ok(settings) => self.update_settings(settings),
+      Err(err) => log_warn!(self.environment, "Failed deserializing configuration settings: {:#}", err),
+    }
+  }
+
+  async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+    let file_paths = params.changes.iter().filter_map(|c| url_to_file_path(&c.uri)).collect::<Vec<_>>();
+    if file_paths.is_empty() {
+      return;
+    }
+    // send this before checking the documents so they're checked with the new config
+    let _ = self.sender.send(ChannelMessage::ConfigFilesChanged(file_paths));
+    self.queue_check_all_formatted();
+  }
+
+  async fn did_change_workspace_folders(&self, params: DidChangeWorkspaceFoldersParams) {
+    self.send_workspace_folders_changed(params.event.added, params.event.removed);
   }
 
   async fn formatting(&self, params: DocumentFormattingParams) -> LspResult<Option<Vec<TextEdit>>> {
-    let Some(file_path) = url_to_file_path(&params.text_document.uri) else {
+    let Some(file_path) = self.get_file_path(&params.text_document.uri) else {
       return Ok(None);
     };
     let Some((file_text, maybe_line_index)) = self.state.lock().documents.get_content(&params.text_document.uri) else {
@@ -377,6 +951,8 @@ impl<TEnvironment: Environment> LanguageServer for Backend<TEnvironment> {
           file_text,
           range: None,
           maybe_line_index,
+          formatting_options: self.get_formatting_options(params.options),
+          timing: self.get_format_timing(),
           token: Arc::new(CancellationToken::new()),
         },
       )
@@ -384,7 +960,7 @@ impl<TEnvironment: Environment> LanguageServer for Backend<TEnvironment> {
   }
 
   async fn range_formatting(&self, params: DocumentRangeFormattingParams) -> LspResult<Option<Vec<TextEdit>>> {
-    let Some(file_path) = url_to_file_path(&params.text_document.uri) else {
+    let Some(file_path) = self.get_file_path(&params.text_document.uri) else {
       return Ok(None);
     };
     let Some((file_text, range, line_index)) = self.state.lock().documents.get_content_with_range(&params.text_document.uri, params.range) else {
@@ -398,12 +974,69 @@ impl<TEnvironment: Environment> LanguageServer for Backend<TEnvironment> {
           file_text,
           range,
           maybe_line_index: Some(line_index),
+          formatting_options: self.get_formatting_options(params.options),
+          timing: self.get_format_timing(),
           token: Arc::new(CancellationToken::new()),
         },
       )
       .await
   }
 
+  async fn on_type_formatting(&self, params: DocumentOnTypeFormattingParams) -> LspResult<Option<Vec<TextEdit>>> {
+    let uri = &params.text_document_position.text_document.uri;
+    let Some(file_path) = self.get_file_path(uri) else {
+      return Ok(None);
+    };
+    let content = {
+      let mut state = self.state.lock();
+      let file_text = state.documents.text(uri).unwrap_or_default();
+      let range = get_on_type_format_range(file_text, params.text_document_position.position, &params.ch);
+      state.documents.get_content_with_range(uri, range)
+    };
+    let Some((file_text, range, line_index)) = content else {
+      return Ok(None);
+    };
+    self
+      .send_format_request(
+        uri,
+        EditorFormatRequest {
+          file_path,
+          file_text,
+          range,
+          maybe_line_index: Some(line_index),
+          formatting_options: self.get_formatting_options(params.options),
+          timing: self.get_format_timing(),
+          token: Arc::new(CancellationToken::new()),
+        },
+      )
+      .await
+  }
+
+  async fn execute_command(&self, params: ExecuteCommandParams) -> LspResult<Option<serde_json::Value>> {
+    match params.command.as_str() {
+      RESTART_PLUGINS_COMMAND => {
+        let (sender, receiver) = oneshot::channel();
+        if self.sender.send(ChannelMessage::RestartPlugins(sender)).is_ok() {
+          let _ = receiver.await;
+        }
+        self.client.log_info("Restarted plugins.".to_string());
+      }
+      CLEAR_CACHE_COMMAND => {
+        let (sender, receiver) = oneshot::channel();
+        if self.sender.send(ChannelMessage::ClearCache(sender)).is_ok() {
+          if let Ok(Err(err)) = receiver.await {
+            log_error!(self.environment, "Failed clearing cache: {:#}", err);
+            return Err(LspError::internal_error());
+          }
+        }
+        self.client.log_info("Cleared cache.".to_string());
+      }
+      command => return Err(LspError::invalid_params(format!("Unknown command: {}", command))),
+    }
+    self.queue_check_all_formatted();
+    Ok(None)
+  }
+
   async fn shutdown(&self) -> LspResult<()> {
     let (sender, receiver) = oneshot::channel();
     if self.sender.send(ChannelMessage::Shutdown(sender)).is_ok() {
@@ -413,6 +1046,46 @@ impl<TEnvironment: Environment> LanguageServer for Backend<TEnvironment> {
   }
 }
 
+/// Gets the range to format when the user types a trigger character.
+///
+/// For a newline this is the line that was just completed along with the
+/// new line's indentation, for a closing brace it's the block from the line of
+/// the matching opening brace, otherwise it's the current line up to the position.
+fn get_on_type_format_range(file_text: &str, position: Position, ch: &str) -> Range {
+  let start_line = match ch {
+    "\n" => position.line.saturating_sub(1),
+    "}" => get_open_brace_line(file_text, position).unwrap_or(position.line),
+    _ => position.line,
+  };
+  Range::new(Position::new(start_line, 0), position)
+}
+
+/// Gets the line of the opening brace matching the closing brace before the position.
+fn get_open_brace_line(file_text: &str, position: Position) -> Option<u32> {
+  let line_start = file_text.split('\n').take(position.line as usize).map(|line| line.len() + 1).sum::<usize>();
+  let line_text = file_text.get(line_start..)?.split('\n').next()?;
+  let mut utf16_col = 0;
+  let line_end = line_text
+    .char_indices()
+    .find(|(_, c)| {
+      utf16_col += c.len_utf16() as u32;
+      utf16_col > position.character
+    })
+    .map(|(index, _)| index)
+    .unwrap_or(line_text.len());
+  let text_before = file_text[..line_start + line_end].strip_suffix('}')?;
+  let mut depth = 0;
+  for (index, c) in text_before.char_indices().rev() {
+    match c {
+      '}' => depth += 1,
+      '{' if depth == 0 => return Some(text_before[..index].matches('\n').count() as u32),
+      '{' => depth -= 1,
+      _ => {}
+    }
+  }
+  None
+}
+
 /// Attempts to convert a specifier to a file path. By default, uses the Url
 /// crate's `to_file_path()` method, but falls back to try and resolve unix-style
 /// paths on Windows.
@@ -451,19 +1124,20 @@ pub fn url_to_file_path(specifier: &Url) -> Option<PathBuf> {
 mod test {
   use std::time::Duration;
 
-  use dprint_core::async_runtime::future;
   use tower_lsp::lsp_types::MessageType;
-  use tower_lsp::lsp_types::Position;
-  use tower_lsp::lsp_types::Range;
+  use tower_lsp::lsp_types::ProgressToken;
   use tower_lsp::lsp_types::TextDocumentContentChangeEvent;
   use tower_lsp::lsp_types::TextDocumentIdentifier;
   use tower_lsp::lsp_types::TextDocumentItem;
+  use tower_lsp::lsp_types::Unregistration;
   use tower_lsp::lsp_types::VersionedTextDocumentIdentifier;
+  use tower_lsp::lsp_types::WorkDoneProgress;
 
   use crate::environment::TestConfigFileBuilder;
   use crate::environment::TestEnvironment;
   use crate::environment::TestEnvironmentBuilder;
   use crate::plugins::PluginCache;
+  use crate::resolution::PluginsScope;
 
   use super::client::ClientTrait;
   use super::*;
@@ -901,18 +1575,293 @@ mod test {
     });
   }
 
+  #[test]
+  fn should_publish_diagnostics_for_unformatted_documents() {
+    let environment = TestEnvironmentBuilder::new()
+      .add_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin();
+      })
+      .initialize()
+      .build();
+
+    environment.clone().run_in_runtime(async move {
+      let (backend, recv_task, test_client) = setup_backend(environment.clone());
+      let run_test_task = dprint_core::async_runtime::spawn({
+        let test_client = test_client.clone();
+        async move {
+          backend
+            .initialize(InitializeParams {
+              initialization_options: Some(serde_json::json!({ "diagnostics": true })),
+              ..Default::default()
+            })
+            .await
+            .unwrap();
+
+          let file_uri = Url::parse("file:///file.txt").unwrap();
+          backend
+            .did_open(DidOpenTextDocumentParams {
+              text_document: TextDocumentItem {
+                uri: file_uri.clone(),
+                language_id: "txt".to_string(),
+                version: 0,
+                text: "testing".to_string(),
+              },
+            })
+            .await;
+          assert_eq!(
+            test_client.wait_diagnostics().await,
+            vec![(
+              file_uri.clone(),
+              vec![Diagnostic {
+                range: Range::new(Position::new(0, 7), Position::new(0, 7)),
+                severity: Some(DiagnosticSeverity::WARNING),
+                source: Some("dprint".to_string()),
+                message: "File is not formatted.".to_string(),
+                ..Default::default()
+              }]
+            )]
+          );
+
+          // now formatted, so the diagnostic should be cleared
+          backend
+            .did_change(DidChangeTextDocumentParams {
+              text_document: VersionedTextDocumentIdentifier {
+                uri: file_uri.clone(),
+                version: 1,
+              },
+              content_changes: vec![TextDocumentContentChangeEvent {
+                range: Some(Range::new(Position::new(0, 7), Position::new(0, 7))),
+                range_length: None,
+                text: "_formatted".to_string(),
+              }],
+            })
+            .await;
+          assert_eq!(test_client.wait_diagnostics().await, vec![(file_uri.clone(), vec![])]);
+
+          backend
+            .did_close(DidCloseTextDocumentParams {
+              text_document: TextDocumentIdentifier { uri: file_uri.clone() },
+            })
+            .await;
+          assert_eq!(test_client.wait_diagnostics().await, vec![(file_uri, vec![])]);
+
+          backend.shutdown().await.unwrap();
+        }
+      });
+
+      try_join!(recv_task, run_test_task).unwrap();
+    });
+  }
+
+  #[test]
+  fn should_execute_commands() {
+    let environment = TestEnvironment::new();
+    environment.write_file(environment.get_cache_dir().join("file.txt"), "").unwrap();
+
+    environment.clone().run_in_runtime(async move {
+      let (backend, recv_task, test_client) = setup_backend(environment.clone());
+      let run_test_task = dprint_core::async_runtime::spawn({
+        let environment = environment.clone();
+        let test_client = test_client.clone();
+        async move {
+          let execute_command = |command: &str| {
+            backend.execute_command(ExecuteCommandParams {
+              command: command.to_string(),
+              arguments: Vec::new(),
+              work_done_progress_params: Default::default(),
+            })
+          };
+          assert_eq!(execute_command(RESTART_PLUGINS_COMMAND).await.unwrap(), None);
+          assert_eq!(test_client.take_messages(), vec![(MessageType::INFO, "Restarted plugins.".to_string())]);
+
+          assert_eq!(execute_command(CLEAR_CACHE_COMMAND).await.unwrap(), None);
+          assert_eq!(test_client.take_messages(), vec![(MessageType::INFO, "Cleared cache.".to_string())]);
+          assert!(!environment.path_exists(environment.get_cache_dir().join("file.txt")));
+
+          assert!(execute_command("dprint.unknown").await.is_err());
+
+          backend.shutdown().await.unwrap();
+        }
+      });
+
+      try_join!(recv_task, run_test_task).unwrap();
+    });
+  }
+
+  #[test]
+  fn should_get_status() {
+    let environment = TestEnvironment::new();
+
+    environment.clone().run_in_runtime(async move {
+      let (backend, recv_task, test_client) = setup_backend(environment.clone());
+      let run_test_task = dprint_core::async_runtime::spawn(async move {
+        backend.initialize(InitializeParams::default()).await.unwrap();
+        let expected = LspStatus {
+          ready: true,
+          configs: Vec::new(),
+        };
+        assert_eq!(test_client.wait_ready_status().await, expected);
+        assert_eq!(backend.status().await.unwrap(), expected);
+        backend.shutdown().await.unwrap();
+      });
+
+      try_join!(recv_task, run_test_task).unwrap();
+    });
+  }
+
+  #[test]
+  fn should_resolve_each_workspace_folder_separately() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file("/a/dprint.json", r#"{ "includes": ["**/*.ts"], "plugins": [] }"#)
+      .unwrap();
+    environment
+      .write_file("/b/dprint.json", r#"{ "includes": ["**/*.md"], "plugins": [] }"#)
+      .unwrap();
+
+    environment.clone().run_in_runtime(async move {
+      let plugin_resolver = Rc::new(PluginResolver::new(environment.clone(), PluginCache::new(environment.clone())));
+      let folders = LspWorkspaceFolders::new(environment.clone(), plugin_resolver);
+      folders.add(PathBuf::from("/a"));
+      folders.add(PathBuf::from("/b"));
+      let client = ClientWrapper::new(Arc::new(TestClient::default()));
+      let resolve = |dir_path: &'static str| {
+        let container = folders.get_for_path(&PathBuf::from(dir_path).join("file.txt"));
+        let client = client.clone();
+        async move { container.resolve_by_path(&PathBuf::from(dir_path), &client).await.unwrap().unwrap() }
+      };
+      let get_includes = |scope: &Rc<PluginsScope<TestEnvironment>>| scope.config.as_ref().unwrap().includes.clone().unwrap();
+
+      let scope_a = resolve("/a/sub").await;
+      let scope_b = resolve("/b").await;
+      assert_eq!(get_includes(&scope_a), vec!["**/*.ts"]);
+      assert_eq!(get_includes(&scope_b), vec!["**/*.md"]);
+
+      // only the folder of the changed config is resolved again
+      environment
+        .write_file("/a/dprint.json", r#"{ "includes": ["**/*.js"], "plugins": [] }"#)
+        .unwrap();
+      folders.invalidate_for_changed_files(&[PathBuf::from("/a/dprint.json")]).await;
+      assert_eq!(get_includes(&resolve("/a").await), vec!["**/*.js"]);
+      assert!(Rc::ptr_eq(&resolve("/b/sub").await, &scope_b));
+
+      folders.shutdown().await;
+    });
+  }
+
+  #[test]
+  fn should_advertise_ranges_support() {
+    let environment = TestEnvironment::new();
+
+    environment.clone().run_in_runtime(async move {
+      let (backend, recv_task, _test_client) = setup_backend(environment.clone());
+      let run_test_task = dprint_core::async_runtime::spawn(async move {
+        let get_range_formatting_provider = |result: InitializeResult| {
+          let mut value = serde_json::to_value(result).unwrap();
+          super::capabilities::add_unsupported_capabilities(&mut value);
+          value["capabilities"].get("documentRangeFormattingProvider").cloned()
+        };
+
+        // without dynamic registration, it's in the static capabilities
+        let result = backend.initialize(InitializeParams::default()).await.unwrap();
+        assert_eq!(get_range_formatting_provider(result), Some(serde_json::json!({ "rangesSupport": true })));
+
+        // otherwise it's registered with the document selector
+        let params: InitializeParams = serde_json::from_value(serde_json::json!({
+          "capabilities": {
+            "textDocument": {
+              "formatting": { "dynamicRegistration": true },
+              "rangeFormatting": { "dynamicRegistration": true }
+            }
+          }
+        }))
+        .unwrap();
+        let result = backend.initialize(params).await.unwrap();
+        assert_eq!(get_range_formatting_provider(result), None);
+        backend.shutdown().await.unwrap();
+      });
+
+      try_join!(recv_task, run_test_task).unwrap();
+    });
+  }
+
+  #[test]
+  fn should_merge_range_edits() {
+    let edit = |start: u32, end: u32, text: &str| TextEdit {
+      range: Range::new(Position::new(0, start), Position::new(0, end)),
+      new_text: text.to_string(),
+    };
+    assert_eq!(
+      merge_range_edits(vec![
+        vec![edit(10, 12, "b")],
+        vec![edit(0, 2, "a"), edit(11, 13, "c"), edit(10, 12, "b")],
+        vec![edit(5, 5, "d"), edit(5, 5, "d")],
+      ]),
+      vec![edit(0, 2, "a"), edit(5, 5, "d"), edit(10, 12, "b")]
+    );
+  }
+
+  #[test]
+  fn should_get_on_type_format_range() {
+    let text = "a;\nb;\nc; }\n";
+    assert_eq!(
+      get_on_type_format_range(text, Position::new(2, 4), "}"),
+      Range::new(Position::new(2, 0), Position::new(2, 4))
+    );
+    assert_eq!(
+      get_on_type_format_range(text, Position::new(2, 2), ";"),
+      Range::new(Position::new(2, 0), Position::new(2, 2))
+    );
+    assert_eq!(
+      get_on_type_format_range(text, Position::new(2, 4), "\n"),
+      Range::new(Position::new(1, 0), Position::new(2, 4))
+    );
+    assert_eq!(
+      get_on_type_format_range(text, Position::new(0, 0), "\n"),
+      Range::new(Position::new(0, 0), Position::new(0, 0))
+    );
+  }
+
+  #[test]
+  fn should_get_on_type_format_range_of_multi_line_block() {
+    // the whole block is reindented when typing its closing brace
+    let text = "fn a() {\n  if b {\n      c();\n    d({ e: 1 });\n  }\n      }";
+    assert_eq!(
+      get_on_type_format_range(text, Position::new(4, 3), "}"),
+      Range::new(Position::new(1, 0), Position::new(4, 3))
+    );
+    assert_eq!(
+      get_on_type_format_range(text, Position::new(5, 7), "}"),
+      Range::new(Position::new(0, 0), Position::new(5, 7))
+    );
+    // utf-16 columns
+    assert_eq!(
+      get_on_type_format_range("{\n'é😀' }", Position::new(1, 7), "}"),
+      Range::new(Position::new(0, 0), Position::new(1, 7))
+    );
+    // no matching brace
+    assert_eq!(
+      get_on_type_format_range("a\nb }", Position::new(1, 3), "}"),
+      Range::new(Position::new(1, 0), Position::new(1, 3))
+    );
+  }
+
   fn setup_backend(environment: TestEnvironment) -> (Backend<TestEnvironment>, JoinHandle<()>, Arc<TestClient>) {
     let plugin_cache = PluginCache::new(environment.clone());
     let plugin_resolver = Rc::new(PluginResolver::new(environment.clone(), plugin_cache));
     let (tx, rx) = mpsc::unbounded_channel();
-    let recv_task = start_message_handler(&environment, &plugin_resolver, rx);
     let test_client = Arc::new(TestClient::default());
-    (Backend::new(ClientWrapper::new(test_client.clone()), environment, tx), recv_task, test_client)
+    let client = ClientWrapper::new(test_client.clone());
+    let recv_task = start_message_handler(&environment, &plugin_resolver, client.clone(), rx);
+    (Backend::new(client, environment, tx), recv_task, test_client)
   }
 
   #[derive(Debug, Default)]
   struct TestClient {
     logged_messages: Mutex<Vec<(MessageType, String)>>,
+    diagnostics: Mutex<Vec<(Url, Vec<Diagnostic>)>>,
+    statuses: Mutex<Vec<LspStatus>>,
   }
 
   impl Drop for TestClient {
@@ -933,11 +1882,52 @@ mod test {
     pub fn take_messages(&self) -> Vec<(MessageType, String)> {
       self.logged_messages.lock().drain(..).collect()
     }
+
+    pub async fn wait_diagnostics(&self) -> Vec<(Url, Vec<Diagnostic>)> {
+      for _ in 0..200 {
+        let diagnostics = self.diagnostics.lock().drain(..).collect::<Vec<_>>();
+        if !diagnostics.is_empty() {
+          return diagnostics;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+      }
+      panic!("Timed out waiting for diagnostics.");
+    }
+
+    pub async fn wait_ready_status(&self) -> LspStatus {
+      for _ in 0..200 {
+        let statuses = self.statuses.lock().drain(..).collect::<Vec<_>>();
+        if let Some(status) = statuses.into_iter().rfind(|s| s.ready) {
+          return status;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+      }
+      panic!("Timed out waiting for the ready status.");
+    }
   }
 
+  #[tower_lsp::async_trait]
   impl ClientTrait for TestClient {
     fn log(&self, message_type: MessageType, message: String) {
       self.logged_messages.lock().push((message_type, message));
     }
+
+    fn publish_diagnostics(&self, uri: Url, diagnostics: Vec<Diagnostic>) {
+      self.diagnostics.lock().push((uri, diagnostics));
+    }
+
+    fn register_capability(&self, _registrations: Vec<Registration>) {}
+
+    fn unregister_capability(&self, _unregistrations: Vec<Unregistration>) {}
+
+    async fn create_work_done_progress(&self, _token: ProgressToken) -> bool {
+      false
+    }
+
+    async fn send_progress(&self, _token: ProgressToken, _progress: WorkDoneProgress) {}
+
+    fn send_status(&self, status: LspStatus) {
+      self.statuses.lock().push(status);
+    }
   }
 }
@@ -0,0 +1,154 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use tower_lsp::lsp_types::DocumentFilter;
+use tower_lsp::lsp_types::Registration;
+use tower_lsp::lsp_types::TextDocumentRegistrationOptions;
+use tower_lsp::lsp_types::Unregistration;
+
+use crate::environment::Environment;
+use crate::resolution::PluginsScope;
+
+use super::client::ClientWrapper;
+use super::config::LspWorkspaceFolders;
+
+const REGISTERED_METHODS: [&str; 2] = ["textDocument/formatting", "textDocument/rangeFormatting"];
+
+/// Dynamically registers the formatting capabilities with a document selector
+/// that matches the files the resolved plugins format.
+pub struct DocumentSelectorRegistration {
+  client: ClientWrapper,
+  next_id: Cell<usize>,
+  registered: RefCell<Option<(usize, Vec<DocumentFilter>)>>,
+}
+
+impl DocumentSelectorRegistration {
+  pub fn new(client: ClientWrapper) -> Self {
+    Self {
+      client,
+      next_id: Default::default(),
+      registered: Default::default(),
+    }
+  }
+
+  /// Re-registers the formatting capabilities if the document selector changed.
+  pub fn update<TEnvironment: Environment>(&self, workspace_folders: &LspWorkspaceFolders<TEnvironment>) {
+    let filters = get_document_filters(&workspace_folders.resolved_scopes());
+    let mut registered = self.registered.borrow_mut();
+    if registered.as_ref().map(|(_, registered_filters)| registered_filters) == Some(&filters) {
+      return;
+    }
+    if let Some((previous_id, _)) = registered.take() {
+      self.client.unregister_capability(
+        REGISTERED_METHODS
+          .iter()
+          .map(|method| Unregistration {
+            id: get_registration_id(method, previous_id),
+            method: method.to_string(),
+          })
+          .collect(),
+      );
+    }
+    let id = self.next_id.get();
+    self.next_id.set(id + 1);
+    self.client.register_capability(
+      REGISTERED_METHODS
+        .iter()
+        .map(|method| Registration {
+          id: get_registration_id(method, id),
+          method: method.to_string(),
+          register_options: get_register_options(method, &filters),
+        })
+        .collect(),
+    );
+    *registered = Some((id, filters));
+  }
+}
+
+fn get_register_options(method: &str, filters: &[DocumentFilter]) -> Option<serde_json::Value> {
+  let mut options = serde_json::to_value(TextDocumentRegistrationOptions {
+    document_selector: Some(filters.to_vec()),
+  })
+  .ok()?;
+  if method == "textDocument/rangeFormatting" {
+    // LSP 3.18 support for textDocument/rangesFormatting, which isn't in lsp-types yet
+    options["rangesSupport"] = serde_json::Value::Bool(true);
+  }
+  Some(options)
+}
+
+fn get_registration_id(method: &str, id: usize) -> String {
+  format!("dprint/{}/{}", method, id)
+}
+
+/// Gets the document filters for the files the plugins in the scopes format.
+///
+/// When nothing is resolved yet, this matches all files so that the first
+/// format request can cause the plugins to be resolved.
+fn get_document_filters<TEnvironment: Environment>(scopes: &[Rc<PluginsScope<TEnvironment>>]) -> Vec<DocumentFilter> {
+  let mut patterns = BTreeSet::new();
+  for plugin in scopes.iter().flat_map(|scope| scope.plugins.values()) {
+    for extension in &plugin.file_matching.file_extensions {
+      patterns.insert(format!("**/*.{}", extension));
+    }
+    for file_name in &plugin.file_matching.file_names {
+      patterns.insert(format!("**/{}", file_name));
+    }
+    for association in plugin.associations.iter().flatten() {
+      // negated associations only narrow what's matched
+      if !association.starts_with('!') {
+        patterns.insert(get_association_pattern(association));
+      }
+    }
+  }
+
+  let mut filters = if patterns.is_empty() {
+    vec![DocumentFilter {
+      language: None,
+      scheme: Some("file".to_string()),
+      pattern: None,
+    }]
+  } else {
+    patterns
+      .into_iter()
+      .map(|pattern| DocumentFilter {
+        language: None,
+        scheme: Some("file".to_string()),
+        pattern: Some(pattern),
+      })
+      .collect()
+  };
+  // untitled documents are formatted based on their language
+  filters.push(DocumentFilter {
+    language: None,
+    scheme: Some("untitled".to_string()),
+    pattern: None,
+  });
+  filters
+}
+
+/// Associations are relative to the config file, so loosen them to
+/// match in any directory as the selector must be an absolute glob.
+fn get_association_pattern(association: &str) -> String {
+  let association = association.strip_prefix("./").unwrap_or(association);
+  if association.starts_with("**/") {
+    association.to_string()
+  } else {
+    format!("**/{}", association.trim_start_matches('/'))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_get_association_pattern() {
+    assert_eq!(get_association_pattern("**/*.txt"), "**/*.txt");
+    assert_eq!(get_association_pattern("*.txt"), "**/*.txt");
+    assert_eq!(get_association_pattern("./src/*.txt"), "**/src/*.txt");
+    assert_eq!(get_association_pattern("/src/*.txt"), "**/src/*.txt");
+  }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+/// Settings provided by the client in the `initializationOptions`
+/// or via a `workspace/didChangeConfiguration` notification.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LspSettings {
+  /// Publish a diagnostic for open documents that would change when formatted.
+  pub diagnostics: bool,
+  /// Path or url to a configuration file to use instead of searching
+  /// the ancestor directories. Relative paths are resolved relative to
+  /// the workspace folder.
+  pub config: Option<String>,
+  /// Use the editor's formatting options (ex. tab size) for the
+  /// indentation settings that aren't in the configuration file.
+  pub editor_formatting_options: bool,
+  /// Extensions to format untitled documents with by language identifier
+  /// (ex. `{ "typescript": "ts" }`), which take precedence over the defaults.
+  pub language_extensions: HashMap<String, String>,
+  /// Log how long each format request took.
+  pub log_format_times: bool,
+  /// Log a warning when formatting a document takes longer
+  /// than this many milliseconds.
+  pub slow_format_threshold: Option<u64>,
+}
+
+impl LspSettings {
+  pub fn from_value(value: Option<serde_json::Value>) -> Result<Self, serde_json::Error> {
+    match value {
+      Some(value) if !value.is_null() => serde_json::from_value(value),
+      _ => Ok(Default::default()),
+    }
+  }
+
+  /// Deserializes the settings from a `workspace/didChangeConfiguration`
+  /// notification, which clients commonly nest in a "dprint" section.
+  pub fn from_change_configuration_value(mut value: serde_json::Value) -> Result<Self, serde_json::Error> {
+    match value.get_mut("dprint") {
+      Some(section) => Self::from_value(Some(section.take())),
+      None => Self::from_value(Some(value)),
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_deserialize_settings() {
+    assert_eq!(LspSettings::from_value(None).unwrap(), LspSettings::default());
+    assert_eq!(LspSettings::from_value(Some(serde_json::Value::Null)).unwrap(), LspSettings::default());
+    assert_eq!(
+      LspSettings::from_value(Some(serde_json::json!({ "diagnostics": true, "unknown": 1 }))).unwrap(),
+      LspSettings {
+        diagnostics: true,
+        ..Default::default()
+      }
+    );
+    assert_eq!(
+      LspSettings::from_value(Some(serde_json::json!({ "languageExtensions": { "typescript": "mts" } }))).unwrap(),
+      LspSettings {
+        language_extensions: HashMap::from([("typescript".to_string(), "mts".to_string())]),
+        ..Default::default()
+      }
+    );
+    assert_eq!(
+      LspSettings::from_value(Some(serde_json::json!({ "logFormatTimes": true, "slowFormatThreshold": 500 }))).unwrap(),
+      LspSettings {
+        log_format_times: true,
+        slow_format_threshold: Some(500),
+        ..Default::default()
+      }
+    );
+    assert!(!LspSettings::from_value(None).unwrap().editor_formatting_options);
+    assert!(
+      LspSettings::from_value(Some(serde_json::json!({ "editorFormattingOptions": true })))
+        .unwrap()
+        .editor_formatting_options
+    );
+    assert!(LspSettings::from_value(Some(serde_json::json!({ "diagnostics": 5 }))).is_err());
+  }
+
+  #[test]
+  fn should_deserialize_change_configuration_settings() {
+    let expected = LspSettings {
+      config: Some("./configs/dprint.json".to_string()),
+      ..Default::default()
+    };
+    assert_eq!(
+      LspSettings::from_change_configuration_value(serde_json::json!({ "dprint": { "config": "./configs/dprint.json" } })).unwrap(),
+      expected
+    );
+    assert_eq!(
+      LspSettings::from_change_configuration_value(serde_json::json!({ "config": "./configs/dprint.json" })).unwrap(),
+      expected
+    );
+    assert_eq!(
+      LspSettings::from_change_configuration_value(serde_json::json!({ "dprint": null })).unwrap(),
+      LspSettings::default()
+    );
+  }
+}
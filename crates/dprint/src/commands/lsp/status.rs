@@ -0,0 +1,118 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Deserialize;
+use serde::Serialize;
+use tower_lsp::lsp_types::notification::Notification;
+
+use crate::environment::Environment;
+use crate::resolution::PluginsScope;
+
+use super::client::ClientWrapper;
+use super::config::LspWorkspaceFolders;
+
+/// Notification sent to the client when the status changes.
+pub enum StatusNotification {}
+
+impl Notification for StatusNotification {
+  type Params = LspStatus;
+  const METHOD: &'static str = "dprint/status";
+}
+
+/// Status of the language server for displaying in the editor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspStatus {
+  /// If the plugins of the workspace folders are done resolving.
+  pub ready: bool,
+  pub configs: Vec<LspConfigStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspConfigStatus {
+  /// Path or url of the configuration file.
+  pub config_path: Option<String>,
+  pub plugins: Vec<LspPluginStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspPluginStatus {
+  pub name: String,
+  pub version: String,
+  pub config_key: String,
+}
+
+/// Tracks when plugins are being resolved and notifies
+/// the client when the status changes.
+pub struct StatusNotifier {
+  client: ClientWrapper,
+  resolving_count: Cell<usize>,
+  last_status: RefCell<Option<LspStatus>>,
+}
+
+impl StatusNotifier {
+  pub fn new(client: ClientWrapper) -> Self {
+    Self {
+      client,
+      resolving_count: Default::default(),
+      last_status: Default::default(),
+    }
+  }
+
+  pub fn start_resolving<TEnvironment: Environment>(&self, workspace_folders: &LspWorkspaceFolders<TEnvironment>) {
+    self.resolving_count.set(self.resolving_count.get() + 1);
+    self.notify(workspace_folders);
+  }
+
+  pub fn finish_resolving<TEnvironment: Environment>(&self, workspace_folders: &LspWorkspaceFolders<TEnvironment>) {
+    self.resolving_count.set(self.resolving_count.get().saturating_sub(1));
+    self.notify(workspace_folders);
+  }
+
+  pub fn get_status<TEnvironment: Environment>(&self, workspace_folders: &LspWorkspaceFolders<TEnvironment>) -> LspStatus {
+    get_status(self.resolving_count.get() == 0, &workspace_folders.resolved_scopes())
+  }
+
+  /// Sends the status to the client if it changed since it was last sent.
+  pub fn notify<TEnvironment: Environment>(&self, workspace_folders: &LspWorkspaceFolders<TEnvironment>) {
+    let status = self.get_status(workspace_folders);
+    let mut last_status = self.last_status.borrow_mut();
+    if last_status.as_ref() == Some(&status) {
+      return;
+    }
+    *last_status = Some(status.clone());
+    self.client.send_status(status);
+  }
+}
+
+fn get_status<TEnvironment: Environment>(ready: bool, scopes: &[Rc<PluginsScope<TEnvironment>>]) -> LspStatus {
+  let mut configs = scopes
+    .iter()
+    .map(|scope| {
+      let mut plugins = scope
+        .plugins
+        .values()
+        .map(|plugin| {
+          let info = plugin.info();
+          LspPluginStatus {
+            name: info.name.clone(),
+            version: info.version.clone(),
+            config_key: info.config_key.clone(),
+          }
+        })
+        .collect::<Vec<_>>();
+      plugins.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.version.cmp(&b.version)));
+      LspConfigStatus {
+        config_path: scope.config.as_ref().map(|config| config.resolved_path.source.display()),
+        plugins,
+      }
+    })
+    .collect::<Vec<_>>();
+  // keep the order stable so the status only changes when the configs do
+  configs.sort_by(|a, b| a.config_path.cmp(&b.config_path));
+  configs.dedup_by(|a, b| a.config_path.is_some() && a.config_path == b.config_path);
+  LspStatus { ready, configs }
+}
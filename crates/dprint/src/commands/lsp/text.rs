@@ -5,14 +5,14 @@
 
 use anyhow::bail;
 use anyhow::Result;
-use dissimilar::diff;
-use dissimilar::Chunk;
 use std::collections::HashMap;
 use text_size::TextRange;
 use text_size::TextSize;
 use tower_lsp::lsp_types as lsp;
 use tower_lsp::lsp_types::TextEdit;
 
+use crate::utils::get_text_edits;
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct Utf16Char {
   pub start: TextSize,
@@ -140,61 +140,24 @@ impl LineIndex {
 /// Compare two strings and return a vector of text edit records which are
 /// supported by the Language Server Protocol.
 pub fn get_edits(a: &str, b: &str, line_index: &LineIndex) -> Vec<TextEdit> {
-  if a == b {
-    return vec![];
-  }
-  // Heuristic to detect things like minified files. `diff()` is expensive.
-  if b.chars().filter(|c| *c == '\n').count() > line_index.utf8_offsets.len() * 3 {
-    return vec![TextEdit {
-      range: lsp::Range {
-        start: lsp::Position::new(0, 0),
-        end: line_index.position_utf16(TextSize::from(a.len() as u32)),
-      },
-      new_text: b.to_string(),
-    }];
-  }
-  let chunks = diff(a, b);
-  let mut text_edits = Vec::<TextEdit>::new();
-  let mut iter = chunks.iter().peekable();
+  let mut a_byte_pos = 0;
   let mut a_pos = TextSize::from(0);
-  loop {
-    let chunk = iter.next();
-    match chunk {
-      None => break,
-      Some(Chunk::Equal(e)) => {
-        a_pos += TextSize::from(e.encode_utf16().count() as u32);
-      }
-      Some(Chunk::Delete(d)) => {
-        let start = line_index.position_utf16(a_pos);
-        a_pos += TextSize::from(d.encode_utf16().count() as u32);
-        let end = line_index.position_utf16(a_pos);
-        let range = lsp::Range { start, end };
-        match iter.peek() {
-          Some(Chunk::Insert(i)) => {
-            iter.next();
-            text_edits.push(TextEdit {
-              range,
-              new_text: i.to_string(),
-            });
-          }
-          _ => text_edits.push(TextEdit {
-            range,
-            new_text: "".to_string(),
-          }),
-        }
+  let mut utf16_pos = |byte_pos: usize| {
+    a_pos += TextSize::from(a[a_byte_pos..byte_pos].encode_utf16().count() as u32);
+    a_byte_pos = byte_pos;
+    line_index.position_utf16(a_pos)
+  };
+  get_text_edits(a, b)
+    .into_iter()
+    .map(|edit| {
+      let start = utf16_pos(edit.start);
+      let end = utf16_pos(edit.end);
+      TextEdit {
+        range: lsp::Range { start, end },
+        new_text: edit.new_text,
       }
-      Some(Chunk::Insert(i)) => {
-        let pos = line_index.position_utf16(a_pos);
-        let range = lsp::Range { start: pos, end: pos };
-        text_edits.push(TextEdit {
-          range,
-          new_text: i.to_string(),
-        });
-      }
-    }
-  }
-
-  text_edits
+    })
+    .collect()
 }
 
 fn partition_point<T, P>(slice: &[T], mut predicate: P) -> usize
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Extensions for common language identifiers that clients send for
+/// documents that haven't been saved to the file system yet.
+const DEFAULT_LANGUAGE_EXTENSIONS: [(&str, &str); 23] = [
+  ("astro", "astro"),
+  ("css", "css"),
+  ("go", "go"),
+  ("graphql", "graphql"),
+  ("html", "html"),
+  ("javascript", "js"),
+  ("javascriptreact", "jsx"),
+  ("json", "json"),
+  ("jsonc", "jsonc"),
+  ("less", "less"),
+  ("markdown", "md"),
+  ("python", "py"),
+  ("rust", "rs"),
+  ("sass", "sass"),
+  ("scss", "scss"),
+  ("sql", "sql"),
+  ("svelte", "svelte"),
+  ("toml", "toml"),
+  ("typescript", "ts"),
+  ("typescriptreact", "tsx"),
+  ("vue", "vue"),
+  ("xml", "xml"),
+  ("yaml", "yml"),
+];
+
+/// Gets the file path to format an untitled document as, which is a file
+/// in the root directory with the extension of the document's language.
+///
+/// The provided language extensions take precedence over the defaults.
+pub fn get_untitled_file_path(root_dir: &Path, language_id: &str, language_extensions: &HashMap<String, String>) -> Option<PathBuf> {
+  let extension = match language_extensions.get(language_id) {
+    Some(extension) => extension.as_str(),
+    None => DEFAULT_LANGUAGE_EXTENSIONS
+      .iter()
+      .find(|(id, _)| *id == language_id)
+      .map(|(_, extension)| *extension)?,
+  };
+  let extension = extension.trim_start_matches('.');
+  if extension.is_empty() {
+    return None;
+  }
+  Some(root_dir.join(format!("untitled.{}", extension)))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_get_untitled_file_path() {
+    let root_dir = PathBuf::from("/project");
+    let language_extensions = HashMap::from([("typescript".to_string(), "mts".to_string()), ("custom".to_string(), ".cst".to_string())]);
+    assert_eq!(
+      get_untitled_file_path(&root_dir, "typescript", &HashMap::new()),
+      Some(root_dir.join("untitled.ts"))
+    );
+    assert_eq!(
+      get_untitled_file_path(&root_dir, "typescript", &language_extensions),
+      Some(root_dir.join("untitled.mts"))
+    );
+    assert_eq!(
+      get_untitled_file_path(&root_dir, "custom", &language_extensions),
+      Some(root_dir.join("untitled.cst"))
+    );
+    assert_eq!(get_untitled_file_path(&root_dir, "unknown", &language_extensions), None);
+  }
+}
@@ -1,6 +1,12 @@
+use std::collections::HashSet;
+
+use dprint_core::configuration::diagnostic_codes;
+use dprint_core::configuration::get_global_option_names;
 use dprint_core::configuration::ConfigKeyMap;
 use dprint_core::configuration::ConfigurationDiagnostic;
 use dprint_core::configuration::GlobalConfiguration;
+use dprint_core::configuration::GLOBAL_OPTIONS;
+use dprint_core::plugins::PluginInfo;
 
 use super::ConfigMap;
 use super::ConfigMapValue;
@@ -10,6 +16,15 @@ pub enum GlobalConfigDiagnostic {
   Other(ConfigurationDiagnostic),
 }
 
+impl GlobalConfigDiagnostic {
+  pub fn diagnostic(&self) -> &ConfigurationDiagnostic {
+    match self {
+      GlobalConfigDiagnostic::UnknownProperty(diagnostic) => diagnostic,
+      GlobalConfigDiagnostic::Other(diagnostic) => diagnostic,
+    }
+  }
+}
+
 impl std::fmt::Display for GlobalConfigDiagnostic {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
@@ -35,7 +50,7 @@ pub fn get_global_config(mut config_map: ConfigMap) -> GlobalConfigurationResult
   let global_config_result = dprint_core::configuration::resolve_global_config(&mut global_config);
   diagnostics.extend(global_config_result.diagnostics.into_iter().map(GlobalConfigDiagnostic::Other));
 
-  let unknown_property_diagnostics = dprint_core::configuration::get_unknown_property_diagnostics(global_config);
+  let unknown_property_diagnostics = dprint_core::configuration::get_unknown_property_diagnostics_with_known_keys(global_config, &get_global_option_names());
   diagnostics.extend(unknown_property_diagnostics.into_iter().map(GlobalConfigDiagnostic::UnknownProperty));
 
   return GlobalConfigurationResult {
@@ -51,10 +66,9 @@ pub fn get_global_config(mut config_map: ConfigMap) -> GlobalConfigurationResult
       if let ConfigMapValue::KeyValue(value) = value {
         global_config.insert(key, value);
       } else {
-        diagnostics.push(GlobalConfigDiagnostic::UnknownProperty(ConfigurationDiagnostic {
-          property_name: key,
-          message: "Unexpected non-string, boolean, or int property".to_string(),
-        }));
+        diagnostics.push(GlobalConfigDiagnostic::UnknownProperty(
+          ConfigurationDiagnostic::error(key, "Unexpected non-string, boolean, or int property").with_code(diagnostic_codes::UNKNOWN_PROPERTY),
+        ));
       }
     }
 
@@ -62,6 +76,34 @@ pub fn get_global_config(mut config_map: ConfigMap) -> GlobalConfigurationResult
   }
 }
 
+/// Gets a warning for each global option in the configuration that none of the
+/// plugins use. Plugins that don't declare which global options they use are
+/// assumed to use all of them.
+pub fn get_unused_global_option_diagnostics<'a>(config_map: &ConfigMap, plugins: impl Iterator<Item = &'a PluginInfo>) -> Vec<GlobalConfigDiagnostic> {
+  let mut used_options = HashSet::new();
+  let mut has_plugins = false;
+  for plugin in plugins {
+    match &plugin.global_options {
+      Some(global_options) => used_options.extend(global_options.iter().map(|name| name.as_str())),
+      None => return Vec::new(),
+    }
+    has_plugins = true;
+  }
+  if !has_plugins {
+    return Vec::new();
+  }
+
+  GLOBAL_OPTIONS
+    .iter()
+    .filter(|option| config_map.contains_key(option.name) && !used_options.contains(option.name))
+    .map(|option| {
+      GlobalConfigDiagnostic::Other(
+        ConfigurationDiagnostic::warning(option.name, "The global option is not used by any of the plugins.").with_code(diagnostic_codes::UNUSED_GLOBAL_OPTION),
+      )
+    })
+    .collect()
+}
+
 #[cfg(test)]
 mod tests {
   use dprint_core::configuration::NewLineKind;
@@ -144,6 +186,22 @@ mod tests {
     );
   }
 
+  #[test]
+  fn should_suggest_similar_global_property() {
+    let mut config_map = ConfigMap::new();
+    config_map.insert(String::from("lineWdth"), ConfigMapValue::from_i32(80));
+    assert_result(
+      config_map,
+      GlobalConfiguration {
+        line_width: None,
+        use_tabs: None,
+        indent_width: None,
+        new_line_kind: None,
+      },
+      &["Unknown property in configuration. Did you mean 'lineWidth'? (lineWdth)"],
+    );
+  }
+
   #[test]
   fn should_ignore_schema_property() {
     let mut config_map = ConfigMap::new();
@@ -169,4 +227,37 @@ mod tests {
       diagnostics.into_iter().map(|d| d.to_string()).collect::<Vec<_>>()
     );
   }
+  #[test]
+  fn should_warn_for_global_options_no_plugin_uses() {
+    let mut config_map = ConfigMap::new();
+    config_map.insert(String::from("lineWidth"), ConfigMapValue::from_i32(80));
+    config_map.insert(String::from("useTabs"), ConfigMapValue::from_bool(true));
+    config_map.insert(String::from("newLineKind"), ConfigMapValue::from_str("lf"));
+    let plugin_a = create_plugin_info(Some(vec!["lineWidth"]));
+    let plugin_b = create_plugin_info(Some(vec!["newLineKind", "indentWidth"]));
+    let diagnostics = get_unused_global_option_diagnostics(&config_map, [&plugin_a, &plugin_b].into_iter());
+    assert_eq!(
+      diagnostics.iter().map(|d| d.diagnostic().clone()).collect::<Vec<_>>(),
+      vec![
+        ConfigurationDiagnostic::warning("useTabs", "The global option is not used by any of the plugins.").with_code(diagnostic_codes::UNUSED_GLOBAL_OPTION)
+      ]
+    );
+
+    // assumes a plugin uses all the global options when it doesn't say
+    let plugin_c = create_plugin_info(None);
+    assert!(get_unused_global_option_diagnostics(&config_map, [&plugin_a, &plugin_c].into_iter()).is_empty());
+    assert!(get_unused_global_option_diagnostics(&config_map, [].into_iter()).is_empty());
+  }
+
+  fn create_plugin_info(global_options: Option<Vec<&str>>) -> PluginInfo {
+    PluginInfo {
+      name: "test-plugin".to_string(),
+      version: "0.1.0".to_string(),
+      config_key: "test".to_string(),
+      help_url: "https://dprint.dev/plugins/test".to_string(),
+      config_schema_url: "".to_string(),
+      update_url: None,
+      global_options: global_options.map(|options| options.into_iter().map(String::from).collect()),
+    }
+  }
 }
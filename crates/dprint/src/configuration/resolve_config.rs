@@ -6,9 +6,11 @@ use crossterm::style::Stylize;
 use dprint_core::async_runtime::FutureExt;
 use dprint_core::async_runtime::LocalBoxFuture;
 use dprint_core::configuration::ConfigKeyValue;
+use encoding_rs::Encoding;
 use thiserror::Error;
 
 use crate::arg_parser::CliArgs;
+use crate::arg_parser::ReadOnlyFilesPolicy;
 use crate::configuration::deserialize_config;
 use crate::configuration::ConfigMap;
 use crate::configuration::ConfigMapValue;
@@ -16,7 +18,10 @@ use crate::environment::CanonicalizedPathBuf;
 use crate::environment::Environment;
 use crate::plugins::parse_plugin_source_reference;
 use crate::plugins::PluginSourceReference;
+use crate::utils::get_legacy_encoding_for_label;
 use crate::utils::resolve_url_or_file_path;
+use crate::utils::BomPolicy;
+use crate::utils::IgnoreFileComments;
 use crate::utils::PathSource;
 use crate::utils::PluginKind;
 use crate::utils::ResolvedPath;
@@ -33,6 +38,18 @@ pub struct ResolvedConfig {
   pub excludes: Option<Vec<String>>,
   pub plugins: Vec<PluginSourceReference>,
   pub incremental: Option<bool>,
+  /// Seconds after which a warning is logged for a file that's still being formatted.
+  pub slow_format_warning_secs: Option<u32>,
+  /// Seconds after which formatting a file is cancelled.
+  pub format_timeout_secs: Option<u32>,
+  /// How to handle formatting files that are read-only.
+  pub read_only_files: Option<ReadOnlyFilesPolicy>,
+  /// Encoding of files that aren't UTF-8 or UTF-16.
+  pub legacy_encoding: Option<&'static Encoding>,
+  /// How to handle the byte order mark of UTF-8 files.
+  pub bom: Option<BomPolicy>,
+  /// Comment syntax used to find `dprint-ignore-file` directives.
+  pub ignore_file_comments: IgnoreFileComments,
   pub config_map: ConfigMap,
 }
 
@@ -61,6 +78,12 @@ pub async fn resolve_config_from_args<TEnvironment: Environment>(args: &CliArgs,
           excludes: None,
           includes: None,
           incremental: None,
+          slow_format_warning_secs: None,
+          format_timeout_secs: None,
+          read_only_files: None,
+          legacy_encoding: None,
+          bom: None,
+          ignore_file_comments: Default::default(),
           plugins: Vec::new(),
         }
       } else {
@@ -136,6 +159,13 @@ pub async fn resolve_config_from_path<TEnvironment: Environment>(
   let excludes = take_array_from_config_map(&mut config_map, "excludes")?;
 
   let incremental = take_bool_from_config_map(&mut config_map, "incremental")?;
+  let slow_format_warning_secs = take_positive_number_from_config_map(&mut config_map, "slowFormatWarningSeconds")?;
+  let format_timeout_secs = take_positive_number_from_config_map(&mut config_map, "formatTimeoutSeconds")?;
+  let read_only_files = take_read_only_files_from_config_map(&mut config_map)?;
+  let legacy_encoding = take_legacy_encoding_from_config_map(&mut config_map)?;
+  let bom = take_bom_from_config_map(&mut config_map)?;
+  let mut ignore_file_comments = IgnoreFileComments::default();
+  take_ignore_file_comments_from_config_map(&mut config_map, &mut ignore_file_comments)?;
   config_map.shift_remove("projectType"); // this was an old config property that's no longer used
   let extends = take_extends(&mut config_map)?;
   let resolved_config = ResolvedConfig {
@@ -146,6 +176,12 @@ pub async fn resolve_config_from_path<TEnvironment: Environment>(
     excludes,
     plugins,
     incremental,
+    slow_format_warning_secs,
+    format_timeout_secs,
+    read_only_files,
+    legacy_encoding,
+    bom,
+    ignore_file_comments,
   };
 
   // resolve extends
@@ -212,6 +248,19 @@ async fn handle_config_file<TEnvironment: Environment>(
     }
   }
 
+  // use the timeouts from extended config when not specified
+  let slow_format_warning_secs = take_positive_number_from_config_map(&mut new_config_map, "slowFormatWarningSeconds")?;
+  resolved_config.slow_format_warning_secs = resolved_config.slow_format_warning_secs.or(slow_format_warning_secs);
+  let format_timeout_secs = take_positive_number_from_config_map(&mut new_config_map, "formatTimeoutSeconds")?;
+  resolved_config.format_timeout_secs = resolved_config.format_timeout_secs.or(format_timeout_secs);
+  let read_only_files = take_read_only_files_from_config_map(&mut new_config_map)?;
+  resolved_config.read_only_files = resolved_config.read_only_files.or(read_only_files);
+  let legacy_encoding = take_legacy_encoding_from_config_map(&mut new_config_map)?;
+  resolved_config.legacy_encoding = resolved_config.legacy_encoding.or(legacy_encoding);
+  let bom = take_bom_from_config_map(&mut new_config_map)?;
+  resolved_config.bom = resolved_config.bom.or(bom);
+  take_ignore_file_comments_from_config_map(&mut new_config_map, &mut resolved_config.ignore_file_comments)?;
+
   // Also remove any non-wasm plugins, but only for remote configurations.
   // The assumption here is that the user won't be malicious to themselves.
   let plugins = take_plugins_array_from_config_map(&mut new_config_map, &resolved_path.source.parent(), environment)?;
@@ -445,6 +494,81 @@ fn take_bool_from_config_map(config_map: &mut ConfigMap, property_name: &str) ->
   }
 }
 
+fn take_positive_number_from_config_map(config_map: &mut ConfigMap, property_name: &str) -> Result<Option<u32>> {
+  if let Some(value) = config_map.shift_remove(property_name) {
+    match value {
+      ConfigMapValue::KeyValue(ConfigKeyValue::Number(value)) if value > 0 => Ok(Some(value as u32)),
+      _ => bail!("Expected positive number in '{}' property.", property_name),
+    }
+  } else {
+    Ok(None)
+  }
+}
+
+fn take_read_only_files_from_config_map(config_map: &mut ConfigMap) -> Result<Option<ReadOnlyFilesPolicy>> {
+  if let Some(value) = config_map.shift_remove("readOnlyFiles") {
+    match value {
+      ConfigMapValue::KeyValue(ConfigKeyValue::String(value)) => match value.as_str() {
+        "error" => Ok(Some(ReadOnlyFilesPolicy::Error)),
+        "skip" => Ok(Some(ReadOnlyFilesPolicy::Skip)),
+        "makeWritable" => Ok(Some(ReadOnlyFilesPolicy::MakeWritable)),
+        _ => bail!("Expected \"error\", \"skip\", or \"makeWritable\" in 'readOnlyFiles' property."),
+      },
+      _ => bail!("Expected string in 'readOnlyFiles' property."),
+    }
+  } else {
+    Ok(None)
+  }
+}
+
+fn take_legacy_encoding_from_config_map(config_map: &mut ConfigMap) -> Result<Option<&'static Encoding>> {
+  match config_map.shift_remove("legacyEncoding") {
+    Some(ConfigMapValue::KeyValue(ConfigKeyValue::String(label))) => Ok(Some(get_legacy_encoding_for_label(&label)?)),
+    Some(_) => bail!("Expected string in 'legacyEncoding' property."),
+    None => Ok(None),
+  }
+}
+
+fn take_bom_from_config_map(config_map: &mut ConfigMap) -> Result<Option<BomPolicy>> {
+  if let Some(value) = config_map.shift_remove("bom") {
+    match value {
+      ConfigMapValue::KeyValue(ConfigKeyValue::String(value)) => match value.as_str() {
+        "preserve" => Ok(Some(BomPolicy::Preserve)),
+        "remove" => Ok(Some(BomPolicy::Remove)),
+        "add" => Ok(Some(BomPolicy::Add)),
+        _ => bail!("Expected \"preserve\", \"remove\", or \"add\" in 'bom' property."),
+      },
+      _ => bail!("Expected string in 'bom' property."),
+    }
+  } else {
+    Ok(None)
+  }
+}
+
+fn take_ignore_file_comments_from_config_map(config_map: &mut ConfigMap, ignore_file_comments: &mut IgnoreFileComments) -> Result<()> {
+  match config_map.shift_remove("ignoreFileComments") {
+    Some(ConfigMapValue::PluginConfig(obj)) => {
+      for (extension, value) in obj.properties {
+        let comment_starts = match value {
+          ConfigKeyValue::String(comment_start) => vec![comment_start],
+          ConfigKeyValue::Array(values) => values
+            .into_iter()
+            .map(|value| match value {
+              ConfigKeyValue::String(comment_start) => Ok(comment_start),
+              _ => bail!("Expected string or array of strings for '{}' in 'ignoreFileComments' property.", extension),
+            })
+            .collect::<Result<Vec<_>>>()?,
+          _ => bail!("Expected string or array of strings for '{}' in 'ignoreFileComments' property.", extension),
+        };
+        ignore_file_comments.set_if_absent(&extension, comment_starts);
+      }
+      Ok(())
+    }
+    Some(_) => bail!("Expected object in 'ignoreFileComments' property."),
+    None => Ok(()),
+  }
+}
+
 fn filter_non_wasm_plugins(plugins: Vec<PluginSourceReference>, environment: &impl Environment) -> Vec<PluginSourceReference> {
   if plugins.iter().any(|plugin| plugin.plugin_kind() != Some(PluginKind::Wasm)) {
     log_warn!(environment, &get_warn_non_wasm_plugins_message());
@@ -1482,6 +1606,241 @@ mod tests {
     });
   }
 
+  #[test]
+  fn should_handle_slow_format_properties() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        PathBuf::from("/test.json"),
+        r#"{
+            "extends": "./base.json",
+            "slowFormatWarningSeconds": 5,
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+    environment
+      .write_file(
+        PathBuf::from("/base.json"),
+        r#"{
+            "slowFormatWarningSeconds": 1,
+            "formatTimeoutSeconds": 60,
+        }"#,
+      )
+      .unwrap();
+
+    environment.clone().run_in_runtime(async move {
+      let result = get_result("/test.json", &environment).await.unwrap();
+      assert_eq!(environment.take_stdout_messages().len(), 0);
+      assert_eq!(result.slow_format_warning_secs, Some(5));
+      assert_eq!(result.format_timeout_secs, Some(60));
+      assert!(result.config_map.is_empty());
+    });
+  }
+
+  #[test]
+  fn should_error_for_non_positive_format_timeout() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        PathBuf::from("/test.json"),
+        r#"{
+            "formatTimeoutSeconds": 0,
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    environment.clone().run_in_runtime(async move {
+      let err = get_result("/test.json", &environment).await.err().unwrap();
+      assert_eq!(err.to_string(), "Expected positive number in 'formatTimeoutSeconds' property.");
+    });
+  }
+
+  #[test]
+  fn should_handle_read_only_files_property() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        PathBuf::from("/test.json"),
+        r#"{
+            "extends": "./base.json",
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+    environment
+      .write_file(
+        PathBuf::from("/base.json"),
+        r#"{
+            "readOnlyFiles": "makeWritable",
+        }"#,
+      )
+      .unwrap();
+
+    environment.clone().run_in_runtime(async move {
+      let result = get_result("/test.json", &environment).await.unwrap();
+      assert_eq!(result.read_only_files, Some(ReadOnlyFilesPolicy::MakeWritable));
+      assert!(result.config_map.is_empty());
+    });
+  }
+
+  #[test]
+  fn should_error_for_invalid_read_only_files_property() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        PathBuf::from("/test.json"),
+        r#"{
+            "readOnlyFiles": "overwrite",
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    environment.clone().run_in_runtime(async move {
+      let err = get_result("/test.json", &environment).await.err().unwrap();
+      assert_eq!(
+        err.to_string(),
+        "Expected \"error\", \"skip\", or \"makeWritable\" in 'readOnlyFiles' property."
+      );
+    });
+  }
+
+  #[test]
+  fn should_handle_legacy_encoding_property() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        PathBuf::from("/test.json"),
+        r#"{
+            "extends": "./base.json",
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+    environment
+      .write_file(
+        PathBuf::from("/base.json"),
+        r#"{
+            "legacyEncoding": "windows-1252",
+        }"#,
+      )
+      .unwrap();
+
+    environment.clone().run_in_runtime(async move {
+      let result = get_result("/test.json", &environment).await.unwrap();
+      assert_eq!(result.legacy_encoding, Some(encoding_rs::WINDOWS_1252));
+      assert!(result.config_map.is_empty());
+    });
+  }
+
+  #[test]
+  fn should_error_for_unsupported_legacy_encoding_property() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        PathBuf::from("/test.json"),
+        r#"{
+            "legacyEncoding": "utf-16",
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    environment.clone().run_in_runtime(async move {
+      let err = get_result("/test.json", &environment).await.err().unwrap();
+      assert_eq!(
+        err.to_string(),
+        "Unsupported encoding 'utf-16'. Specify a legacy encoding such as \"windows-1252\" or \"shift_jis\"."
+      );
+    });
+  }
+
+  #[test]
+  fn should_handle_bom_property() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        PathBuf::from("/test.json"),
+        r#"{
+            "extends": "./base.json",
+            "bom": "remove",
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+    environment
+      .write_file(
+        PathBuf::from("/base.json"),
+        r#"{
+            "bom": "add",
+        }"#,
+      )
+      .unwrap();
+
+    environment.clone().run_in_runtime(async move {
+      let result = get_result("/test.json", &environment).await.unwrap();
+      assert_eq!(result.bom, Some(BomPolicy::Remove));
+      assert!(result.config_map.is_empty());
+    });
+  }
+
+  #[test]
+  fn should_handle_ignore_file_comments_property() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        PathBuf::from("/test.json"),
+        r##"{
+            "extends": "./base.json",
+            "ignoreFileComments": { "txt": "#", ".ini": [";", "#"] },
+            "plugins": ["./testing/asdf.wasm"],
+        }"##,
+      )
+      .unwrap();
+    environment
+      .write_file(
+        PathBuf::from("/base.json"),
+        r#"{
+            "ignoreFileComments": { "txt": "//", "sql": "--" },
+        }"#,
+      )
+      .unwrap();
+
+    environment.clone().run_in_runtime(async move {
+      let result = get_result("/test.json", &environment).await.unwrap();
+      let mut expected = IgnoreFileComments::default();
+      expected.set_if_absent("txt", vec!["#".to_string()]);
+      expected.set_if_absent("ini", vec![";".to_string(), "#".to_string()]);
+      expected.set_if_absent("sql", vec!["--".to_string()]);
+      assert_eq!(result.ignore_file_comments, expected);
+      assert!(result.config_map.is_empty());
+    });
+  }
+
+  #[test]
+  fn should_error_for_invalid_ignore_file_comments_property() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        PathBuf::from("/test.json"),
+        r#"{
+            "ignoreFileComments": { "txt": 5 },
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    environment.clone().run_in_runtime(async move {
+      let err = get_result("/test.json", &environment).await.err().unwrap();
+      assert_eq!(
+        err.to_string(),
+        "Expected string or array of strings for 'txt' in 'ignoreFileComments' property."
+      );
+    });
+  }
+
   #[test]
   fn should_ignore_non_wasm_plugins_in_remote_config() {
     let environment = TestEnvironment::new();
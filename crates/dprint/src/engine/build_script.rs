@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use dprint_core::async_runtime::future;
+
+use super::Engine;
+use super::EngineOptions;
+use crate::environment::Environment;
+use crate::environment::ProcessPluginTrustPolicy;
+use crate::environment::RealEnvironment;
+use crate::environment::RealEnvironmentOptions;
+use crate::utils::LogLevel;
+
+/// Options for [`format_generated_files`].
+#[derive(Debug, Default, Clone)]
+pub struct BuildScriptOptions {
+  /// Path or url of the configuration file, which is otherwise
+  /// discovered in the current working directory or its ancestors.
+  pub config: Option<String>,
+  /// Plugins to use instead of the ones in the configuration file.
+  pub plugins: Vec<String>,
+  /// Errors instead of downloading anything, so only plugins that are
+  /// local files (ex. vendored in the repo) or already cached are used.
+  pub offline: bool,
+  /// Runs process plugins (native executables) that haven't been trusted
+  /// yet instead of erroring, since a build script can't prompt for them.
+  pub trust_process_plugins: bool,
+}
+
+/// Formats generated files (ex. in a build script) without reading them from
+/// or writing them to disk, returning the formatted text of each file.
+///
+/// This blocks the current thread. Files that no plugin formats are returned
+/// unchanged and the includes and excludes of the configuration aren't applied.
+pub fn format_generated_files(options: BuildScriptOptions, files: HashMap<PathBuf, String>) -> Result<HashMap<PathBuf, String>> {
+  let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build()?;
+  let environment = RealEnvironment::new(RealEnvironmentOptions {
+    log_level: LogLevel::Warn,
+    is_stdout_machine_readable: false,
+    offline: options.offline,
+    process_plugin_trust: match options.trust_process_plugins {
+      true => ProcessPluginTrustPolicy::Trust,
+      false => ProcessPluginTrustPolicy::Deny,
+    },
+  })?;
+  runtime.block_on(async move {
+    let engine = Engine::new(
+      environment,
+      EngineOptions {
+        config: options.config,
+        plugins: options.plugins,
+      },
+    )
+    .await?;
+    let result = format_files_in_memory(&engine, files).await;
+    engine.shutdown().await;
+    result
+  })
+}
+
+/// Formats the files concurrently with the engine, returning the formatted text of each file.
+///
+/// Relative paths are resolved from the current working directory when
+/// finding the plugins, but the returned map uses the provided paths.
+pub async fn format_files_in_memory<TEnvironment: Environment>(
+  engine: &Engine<TEnvironment>,
+  files: HashMap<PathBuf, String>,
+) -> Result<HashMap<PathBuf, String>> {
+  let cwd = engine.environment().cwd();
+  let futures = files.into_iter().map(|(file_path, file_text)| {
+    let resolved_path = cwd.join(&file_path);
+    async move {
+      let formatted_text = match engine.format_file(&resolved_path, file_text.clone().into_bytes()).await {
+        Ok(Some(bytes)) => String::from_utf8(bytes).with_context(|| format!("Formatted text was not valid UTF-8 for {}", file_path.display()))?,
+        Ok(None) => file_text,
+        Err(err) => return Err(err.context(format!("Failed formatting {}", file_path.display()))),
+      };
+      Ok((file_path, formatted_text))
+    }
+  });
+  future::join_all(futures).await.into_iter().collect()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironmentBuilder;
+
+  #[test]
+  fn should_format_files_in_memory() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|_| {})
+      .build();
+    environment.clone().run_in_runtime(async move {
+      let engine = Engine::new(environment.clone(), Default::default()).await.unwrap();
+      let files = HashMap::from([
+        (PathBuf::from("src/generated.txt"), "text".to_string()),
+        (PathBuf::from("/formatted.txt"), "text_formatted".to_string()),
+        (PathBuf::from("src/generated.md"), "text".to_string()),
+      ]);
+      let formatted = format_files_in_memory(&engine, files).await.unwrap();
+      engine.shutdown().await;
+      assert_eq!(
+        formatted,
+        HashMap::from([
+          (PathBuf::from("src/generated.txt"), "text_formatted".to_string()),
+          (PathBuf::from("/formatted.txt"), "text_formatted".to_string()),
+          (PathBuf::from("src/generated.md"), "text".to_string()),
+        ])
+      );
+      // nothing is written to disk
+      assert!(!environment.path_exists("/src/generated.txt"));
+    });
+  }
+}
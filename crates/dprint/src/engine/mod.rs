@@ -0,0 +1,159 @@
+//! Formats files with the plugins of a configuration file without running the CLI.
+//!
+//! This is enabled for tools that want to embed dprint (ex. IDE backends and code
+//! generators) and published in the `dprint-engine` crate. The engine is not `Send`,
+//! so it must be used within a current thread tokio runtime.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use anyhow::Result;
+use dprint_core::plugins::HostFormatRequest;
+use dprint_core::plugins::NullCancellationToken;
+
+use crate::arg_parser::CliArgs;
+use crate::configuration::resolve_config_from_args;
+use crate::plugins::PluginCache;
+use crate::plugins::PluginResolver;
+use crate::resolution::resolve_plugins_scope;
+use crate::resolution::PluginsScope;
+
+#[cfg(feature = "build-script")]
+mod build_script;
+
+pub use crate::environment::Environment;
+pub use crate::environment::OverlayEnvironment;
+pub use crate::environment::ProcessPluginTrustPolicy;
+pub use crate::environment::RealEnvironment;
+pub use crate::environment::RealEnvironmentOptions;
+pub use crate::utils::LogLevel;
+#[cfg(feature = "build-script")]
+pub use build_script::*;
+
+/// Options for creating an [`Engine`].
+#[derive(Debug, Default, Clone)]
+pub struct EngineOptions {
+  /// Path or url of the configuration file, which is otherwise
+  /// discovered in the current working directory or its ancestors.
+  pub config: Option<String>,
+  /// Plugins to use instead of the ones in the configuration file.
+  pub plugins: Vec<String>,
+}
+
+/// Formats files with the plugins of a configuration file.
+pub struct Engine<TEnvironment: Environment> {
+  environment: TEnvironment,
+  plugin_resolver: Rc<PluginResolver<TEnvironment>>,
+  scope: Rc<PluginsScope<TEnvironment>>,
+}
+
+impl<TEnvironment: Environment> Engine<TEnvironment> {
+  /// Resolves the configuration and its plugins.
+  pub async fn new(environment: TEnvironment, options: EngineOptions) -> Result<Self> {
+    let plugin_cache = PluginCache::new(environment.clone());
+    let plugin_resolver = Rc::new(PluginResolver::new(environment.clone(), plugin_cache));
+    let args = CliArgs::for_config(options.config, options.plugins);
+    let config = Rc::new(resolve_config_from_args(&args, &environment).await?);
+    let scope = resolve_plugins_scope(config, &environment, &plugin_resolver).await?;
+    scope.ensure_plugins_found()?;
+    scope.ensure_no_global_config_diagnostics()?;
+    Ok(Engine {
+      environment,
+      plugin_resolver,
+      scope: Rc::new(scope),
+    })
+  }
+
+  pub fn environment(&self) -> &TEnvironment {
+    &self.environment
+  }
+
+  /// Gets the path of the resolved configuration file.
+  pub fn config_file_path(&self) -> Option<PathBuf> {
+    let config = self.scope.config.as_ref()?;
+    Some(config.resolved_path.file_path.clone().into_path_buf())
+  }
+
+  /// Gets if the file is matched by the configuration's includes and excludes
+  /// and there's a plugin that formats it.
+  pub fn can_format(&self, file_path: impl AsRef<Path>) -> bool {
+    let file_path = file_path.as_ref();
+    let file_path = self
+      .environment
+      .canonicalize(file_path)
+      .map(|p| p.into_path_buf())
+      .unwrap_or_else(|_| file_path.to_path_buf());
+    self.scope.can_format_for_editor(&file_path)
+  }
+
+  /// Formats the file's bytes with the plugins associated with its path,
+  /// returning `None` when the file didn't change or no plugin formats it.
+  ///
+  /// The file doesn't need to exist and the includes and excludes of the
+  /// configuration aren't applied, so use `can_format` to check those.
+  pub async fn format_file(&self, file_path: impl AsRef<Path>, file_bytes: Vec<u8>) -> Result<Option<Vec<u8>>> {
+    self
+      .scope
+      .format(HostFormatRequest {
+        file_path: file_path.as_ref().to_path_buf(),
+        file_bytes,
+        range: None,
+        override_config: Default::default(),
+        token: Arc::new(NullCancellationToken),
+      })
+      .await
+  }
+
+  /// Shuts down the plugins, which should be done before dropping the engine
+  /// so that process plugins exit gracefully.
+  pub async fn shutdown(self) {
+    drop(self.scope);
+    self.plugin_resolver.clear_and_shutdown_initialized().await;
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironment;
+  use crate::environment::TestEnvironmentBuilder;
+
+  #[test]
+  fn should_format_files() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file.txt", "text")
+      .write_file("/ignored/file.txt", "text")
+      .with_default_config(|config| {
+        config.add_excludes("./ignored");
+      })
+      .build();
+    environment.clone().run_in_runtime(async move {
+      let engine = Engine::new(environment.clone(), Default::default()).await.unwrap();
+      assert_eq!(engine.config_file_path(), Some(PathBuf::from("/dprint.json")));
+      assert!(engine.can_format("/file.txt"));
+      assert!(engine.can_format("/unsaved.txt"));
+      assert!(!engine.can_format("/ignored/file.txt"));
+      assert!(!engine.can_format("/file.md"));
+      assert_eq!(
+        engine.format_file("/unsaved.txt", b"text".to_vec()).await.unwrap(),
+        Some(b"text_formatted".to_vec())
+      );
+      assert_eq!(engine.format_file("/unsaved.txt", b"text_formatted".to_vec()).await.unwrap(), None);
+      assert_eq!(engine.format_file("/file.md", b"text".to_vec()).await.unwrap(), None);
+      engine.shutdown().await;
+      // the file on disk is not changed
+      assert_eq!(environment.read_file("/file.txt").unwrap(), "text");
+    });
+  }
+
+  #[test]
+  fn should_error_when_no_config_file() {
+    let environment = TestEnvironment::new();
+    environment.clone().run_in_runtime(async move {
+      let err = Engine::new(environment, Default::default()).await.err().unwrap();
+      assert!(err.to_string().starts_with("No config file found at /dprint.json."), "{}", err);
+    });
+  }
+}
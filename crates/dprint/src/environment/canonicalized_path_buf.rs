@@ -12,12 +12,22 @@ pub struct CanonicalizedPathBuf {
 
 impl CanonicalizedPathBuf {
   pub(super) fn new(path: PathBuf) -> Self {
+    let path = match normalize_verbatim_path(&path) {
+      Cow::Borrowed(_) => path,
+      Cow::Owned(path) => path,
+    };
     CanonicalizedPathBuf { path }
   }
 
   #[cfg(test)]
   pub fn new_for_testing(path: impl AsRef<Path>) -> CanonicalizedPathBuf {
-    assert!(path.as_ref().starts_with("/") || path.as_ref().starts_with("C:\\") || path.as_ref().starts_with("V:\\") || path.as_ref().starts_with("\\?\\UNC"));
+    assert!(
+      path.as_ref().starts_with("/")
+        || path.as_ref().starts_with("C:\\")
+        || path.as_ref().starts_with("V:\\")
+        || path.as_ref().starts_with("\\?\\UNC")
+        || path.as_ref().to_string_lossy().starts_with("\\\\?\\")
+    );
     CanonicalizedPathBuf::new(path.as_ref().to_path_buf())
   }
 
@@ -64,3 +74,82 @@ impl AsRef<Path> for CanonicalizedPathBuf {
     self.path.as_path()
   }
 }
+
+/// Converts an extended-length path (ex. `\\?\C:\dir` or `\\?\UNC\server\share\dir`)
+/// on Windows to its regular form so it displays and compares equal to other paths.
+///
+/// The standard library adds the prefix back when doing file IO with a path that's
+/// too long, so the only paths left as-is are the ones that can't be represented
+/// without it (ex. a file named `con` or one that ends with a period).
+pub fn normalize_verbatim_path(path: &Path) -> Cow<Path> {
+  if cfg!(windows) {
+    if let Some(text) = path.to_str().and_then(simplify_verbatim_path_text) {
+      return Cow::Owned(PathBuf::from(text));
+    }
+  }
+  Cow::Borrowed(path)
+}
+
+fn simplify_verbatim_path_text(text: &str) -> Option<String> {
+  let (prefix, rest) = if let Some(rest) = text.strip_prefix("\\\\?\\UNC\\") {
+    ("\\\\", rest)
+  } else if let Some(rest) = text.strip_prefix("\\\\?\\") {
+    let mut chars = rest.chars();
+    let is_disk = matches!((chars.next(), chars.next(), chars.next()), (Some(c), Some(':'), None | Some('\\')) if c.is_ascii_alphabetic());
+    if !is_disk {
+      return None;
+    }
+    ("", rest)
+  } else {
+    return None;
+  };
+  // skip the drive for disk paths
+  let skip_count = if prefix.is_empty() { 1 } else { 0 };
+  if rest.split('\\').skip(skip_count).filter(|c| !c.is_empty()).all(is_valid_non_verbatim_component) {
+    Some(format!("{}{}", prefix, rest))
+  } else {
+    None
+  }
+}
+
+fn is_valid_non_verbatim_component(component: &str) -> bool {
+  const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7",
+    "LPT8", "LPT9",
+  ];
+  // also handles `.` and `..` components
+  if component.ends_with('.') || component.ends_with(' ') {
+    return false;
+  }
+  if component.chars().any(|c| c < ' ' || matches!(c, '<' | '>' | ':' | '"' | '/' | '|' | '?' | '*')) {
+    return false;
+  }
+  let stem = component.split('.').next().unwrap().trim_end();
+  !RESERVED_NAMES.iter().any(|name| name.eq_ignore_ascii_case(stem))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_simplify_verbatim_paths() {
+    assert_eq!(simplify_verbatim_path_text("\\\\?\\C:\\dev\\project"), Some("C:\\dev\\project".to_string()));
+    assert_eq!(simplify_verbatim_path_text("\\\\?\\C:\\"), Some("C:\\".to_string()));
+    assert_eq!(
+      simplify_verbatim_path_text("\\\\?\\UNC\\server\\share\\dir\\file.ts"),
+      Some("\\\\server\\share\\dir\\file.ts".to_string())
+    );
+    let long_path = format!("\\\\?\\C:\\{}", vec!["node_modules"; 40].join("\\"));
+    assert_eq!(simplify_verbatim_path_text(&long_path).unwrap(), long_path[4..]);
+    // not verbatim
+    assert_eq!(simplify_verbatim_path_text("C:\\dev"), None);
+    assert_eq!(simplify_verbatim_path_text("/home/dev"), None);
+    // can't be represented without the prefix
+    assert_eq!(simplify_verbatim_path_text("\\\\?\\C:\\dev\\con.txt"), None);
+    assert_eq!(simplify_verbatim_path_text("\\\\?\\C:\\dev\\file."), None);
+    assert_eq!(simplify_verbatim_path_text("\\\\?\\C:\\dev\\file "), None);
+    assert_eq!(simplify_verbatim_path_text("\\\\?\\C:\\dev\\..\\file"), None);
+    assert_eq!(simplify_verbatim_path_text("\\\\?\\Volume{b75e2c83-0000-0000-0000-602f00000000}\\dev"), None);
+  }
+}
@@ -0,0 +1,150 @@
+/// Gets the number of CPUs the process is limited to by a cgroup v1 or v2
+/// CPU quota (ex. a container started with `--cpus`).
+#[cfg(target_os = "linux")]
+pub fn get_cgroup_cpu_limit() -> Option<usize> {
+  #[allow(clippy::disallowed_methods)]
+  resolve_cgroup_cpu_limit(&|path| std::fs::read_to_string(path).ok())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn get_cgroup_cpu_limit() -> Option<usize> {
+  None
+}
+
+const CGROUP_V1_CPU_MOUNTS: [&str; 2] = ["/sys/fs/cgroup/cpu,cpuacct", "/sys/fs/cgroup/cpu"];
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn resolve_cgroup_cpu_limit(read_file: &impl Fn(&str) -> Option<String>) -> Option<usize> {
+  let cgroups_text = read_file("/proc/self/cgroup")?;
+  let mut limit = None;
+  for line in cgroups_text.lines() {
+    // ex. `0::/user.slice` for v2 or `4:cpu,cpuacct:/docker/abc` for v1
+    let mut parts = line.splitn(3, ':');
+    let (Some(_), Some(controllers), Some(cgroup_path)) = (parts.next(), parts.next(), parts.next()) else {
+      continue;
+    };
+    let line_limit = if controllers.is_empty() {
+      min_of_ancestors(cgroup_path, |dir_path| {
+        parse_cgroup_v2_cpu_max(&read_file(&format!("/sys/fs/cgroup{}/cpu.max", dir_path))?)
+      })
+    } else if controllers.split(',').any(|controller| controller == "cpu") {
+      min_of_ancestors(cgroup_path, |dir_path| {
+        CGROUP_V1_CPU_MOUNTS.iter().find_map(|mount_path| {
+          let quota = read_file(&format!("{}{}/cpu.cfs_quota_us", mount_path, dir_path))?;
+          let period = read_file(&format!("{}{}/cpu.cfs_period_us", mount_path, dir_path))?;
+          get_quota_cpu_count(quota.trim().parse().ok()?, period.trim().parse().ok()?)
+        })
+      })
+    } else {
+      None
+    };
+    limit = min_limit(limit, line_limit);
+  }
+  limit
+}
+
+/// Gets the lowest limit of the cgroup and its ancestors since a limit
+/// may be on a parent, which is also the case when the cgroup path isn't
+/// visible in a container (ex. the container's cgroup is mounted as the root).
+fn min_of_ancestors(cgroup_path: &str, get_limit: impl Fn(&str) -> Option<usize>) -> Option<usize> {
+  let mut dir_path = cgroup_path.trim_end_matches('/');
+  let mut limit = None;
+  loop {
+    limit = min_limit(limit, get_limit(dir_path));
+    match dir_path.rfind('/') {
+      Some(index) => dir_path = &dir_path[..index],
+      None => return limit,
+    }
+  }
+}
+
+/// Parses the text of a cgroup v2 `cpu.max` file (ex. `150000 100000` or `max 100000`).
+fn parse_cgroup_v2_cpu_max(text: &str) -> Option<usize> {
+  let mut parts = text.split_whitespace();
+  let quota = parts.next()?.parse().ok()?; // "max" when unlimited
+  let period = parts.next().unwrap_or("100000").parse().ok()?;
+  get_quota_cpu_count(quota, period)
+}
+
+/// Gets the CPU count of the quota (-1 is unlimited in v1), rounding
+/// down so a fractional CPU isn't oversubscribed.
+fn get_quota_cpu_count(quota: i64, period: i64) -> Option<usize> {
+  if quota <= 0 || period <= 0 {
+    None
+  } else {
+    Some(std::cmp::max(1, (quota / period) as usize))
+  }
+}
+
+fn min_limit(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+  match (a, b) {
+    (Some(a), Some(b)) => Some(std::cmp::min(a, b)),
+    (a, b) => a.or(b),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::collections::HashMap;
+
+  use super::*;
+
+  fn resolve(files: &[(&str, &str)]) -> Option<usize> {
+    let files = files.iter().map(|(path, text)| (path.to_string(), text.to_string())).collect::<HashMap<_, _>>();
+    resolve_cgroup_cpu_limit(&|path| files.get(path).cloned())
+  }
+
+  #[test]
+  fn should_get_cgroup_v2_limit() {
+    assert_eq!(
+      resolve(&[("/proc/self/cgroup", "0::/\n"), ("/sys/fs/cgroup/cpu.max", "200000 100000\n")]),
+      Some(2)
+    );
+    assert_eq!(resolve(&[("/proc/self/cgroup", "0::/\n"), ("/sys/fs/cgroup/cpu.max", "max 100000\n")]), None);
+    // rounds down, but never below one
+    assert_eq!(
+      resolve(&[("/proc/self/cgroup", "0::/\n"), ("/sys/fs/cgroup/cpu.max", "250000 100000\n")]),
+      Some(2)
+    );
+    assert_eq!(
+      resolve(&[("/proc/self/cgroup", "0::/\n"), ("/sys/fs/cgroup/cpu.max", "50000 100000\n")]),
+      Some(1)
+    );
+    // uses the lowest limit of an ancestor
+    assert_eq!(
+      resolve(&[
+        ("/proc/self/cgroup", "0::/kubepods/pod1/container\n"),
+        ("/sys/fs/cgroup/kubepods/pod1/container/cpu.max", "max 100000\n"),
+        ("/sys/fs/cgroup/kubepods/pod1/cpu.max", "300000 100000\n"),
+        ("/sys/fs/cgroup/kubepods/cpu.max", "800000 100000\n"),
+      ]),
+      Some(3)
+    );
+  }
+
+  #[test]
+  fn should_get_cgroup_v1_limit() {
+    assert_eq!(
+      resolve(&[
+        ("/proc/self/cgroup", "5:memory:/docker/abc\n4:cpu,cpuacct:/docker/abc\n"),
+        ("/sys/fs/cgroup/cpu,cpuacct/cpu.cfs_quota_us", "400000\n"),
+        ("/sys/fs/cgroup/cpu,cpuacct/cpu.cfs_period_us", "100000\n"),
+      ]),
+      Some(4)
+    );
+    assert_eq!(
+      resolve(&[
+        ("/proc/self/cgroup", "4:cpu:/docker/abc\n"),
+        ("/sys/fs/cgroup/cpu/docker/abc/cpu.cfs_quota_us", "-1\n"),
+        ("/sys/fs/cgroup/cpu/docker/abc/cpu.cfs_period_us", "100000\n"),
+      ]),
+      None
+    );
+  }
+
+  #[test]
+  fn should_not_get_limit_without_cgroups() {
+    assert_eq!(resolve(&[]), None);
+    assert_eq!(resolve(&[("/proc/self/cgroup", "0::/\n")]), None);
+  }
+}
@@ -10,10 +10,12 @@ use std::sync::Arc;
 use dprint_core::async_runtime::async_trait;
 
 use crate::plugins::CompilationResult;
+use crate::utils::verify_sha256_checksum;
 use crate::utils::LogLevel;
 use crate::utils::ProgressBars;
 
 use super::CanonicalizedPathBuf;
+use super::FileBytes;
 
 #[derive(Debug)]
 pub enum DirEntry {
@@ -21,10 +23,26 @@ pub enum DirEntry {
   File { name: std::ffi::OsString, path: PathBuf },
 }
 
+/// An exclusive lock on a file that's released when dropped.
+pub struct FileLock(Option<Box<dyn FnOnce() + Send>>);
+
+impl FileLock {
+  pub fn new(release: impl FnOnce() + Send + 'static) -> Self {
+    FileLock(Some(Box::new(release)))
+  }
+}
+
+impl Drop for FileLock {
+  fn drop(&mut self) {
+    if let Some(release) = self.0.take() {
+      release();
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
 pub enum FilePermissions {
   Std(std::fs::Permissions),
-  #[allow(dead_code)]
   Test(TestFilePermissions),
 }
 
@@ -35,6 +53,25 @@ impl FilePermissions {
       FilePermissions::Test(p) => p.readonly,
     }
   }
+
+  /// Gets these permissions with the owner allowed to write to the file.
+  pub fn with_owner_write(&self) -> FilePermissions {
+    match self {
+      FilePermissions::Std(p) => {
+        let mut p = p.clone();
+        #[cfg(unix)]
+        {
+          use std::os::unix::fs::PermissionsExt;
+          p.set_mode(p.mode() | 0o200);
+        }
+        #[cfg(not(unix))]
+        #[allow(clippy::permissions_set_readonly_false)]
+        p.set_readonly(false);
+        FilePermissions::Std(p)
+      }
+      FilePermissions::Test(_) => FilePermissions::Test(TestFilePermissions { readonly: false }),
+    }
+  }
 }
 
 #[derive(Default, Debug, Clone)]
@@ -42,6 +79,25 @@ pub struct TestFilePermissions {
   pub readonly: bool,
 }
 
+/// Errors when network access is disabled (ex. `--no-network`) instead of downloading the url.
+pub fn ensure_network_allowed(is_network_disabled: bool, url: &str) -> Result<()> {
+  if is_network_disabled {
+    bail!("Cannot download {} because network access is disabled.", url);
+  }
+  Ok(())
+}
+
+/// How to handle a process plugin that the user hasn't trusted yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessPluginTrustPolicy {
+  /// Prompts the user to trust the plugin.
+  Prompt,
+  /// Trusts the plugin without prompting (ex. `--yes`).
+  Trust,
+  /// Errors because the user can't be prompted (ex. on CI).
+  Deny,
+}
+
 #[async_trait(?Send)]
 pub trait UrlDownloader {
   async fn download_file(&self, url: &str) -> Result<Option<Vec<u8>>>;
@@ -51,30 +107,46 @@ pub trait UrlDownloader {
       None => bail!("Error downloading {} - 404 Not Found", url),
     }
   }
+  /// Downloads a file and verifies it has the provided sha256 checksum,
+  /// erroring with a `ChecksumMismatchError` when it doesn't.
+  async fn download_file_with_checksum(&self, url: &str, checksum: &str) -> Result<Option<Vec<u8>>> {
+    let result = self.download_file(url).await?;
+    if let Some(bytes) = &result {
+      verify_sha256_checksum(bytes, checksum)?;
+    }
+    Ok(result)
+  }
+  /// Sends the JSON body to the url in a POST request (ex. to export telemetry).
+  async fn post_json(&self, url: &str, headers: &[(String, String)], body: Vec<u8>) -> Result<()>;
 }
 
 #[async_trait]
 pub trait Environment: Clone + Send + Sync + UrlDownloader + 'static {
   fn is_real(&self) -> bool;
-  fn get_staged_files(&self) -> Result<Vec<PathBuf>>;
+  /// Gets the git staged files relative to the provided directory.
+  fn get_staged_files(&self, dir: &Path) -> Result<Vec<PathBuf>>;
   fn read_file(&self, file_path: impl AsRef<Path>) -> Result<String>;
   fn read_file_bytes(&self, file_path: impl AsRef<Path>) -> Result<Vec<u8>>;
+  /// Reads the bytes of a file that will only be read from, which may
+  /// memory map the file when it's large instead of copying it into memory.
+  fn read_file_bytes_mapped(&self, file_path: impl AsRef<Path>) -> Result<FileBytes> {
+    Ok(FileBytes::Owned(self.read_file_bytes(file_path)?))
+  }
   fn write_file(&self, file_path: impl AsRef<Path>, file_text: &str) -> Result<()> {
     self.write_file_bytes(file_path, file_text.as_bytes())
   }
   fn write_file_bytes(&self, file_path: impl AsRef<Path>, bytes: &[u8]) -> Result<()>;
   /// An atomic write, which will write to a temporary file and then rename it to the destination.
   fn atomic_write_file_bytes(&self, file_path: impl AsRef<Path>, bytes: &[u8]) -> Result<()> {
-    // lifted from https://github.com/denoland/deno/blob/0f4051a37ad23377091043206e64126003caa480/cli/util/fs.rs#L29
-    let rand: String = (0..4).fold(String::new(), |mut output, _| {
-      let _ = write!(output, "{:02x}", rand::random::<u8>());
-      output
-    });
-    let extension = format!("{rand}.tmp");
-    let tmp_file = file_path.as_ref().with_extension(extension);
+    let tmp_file = get_atomic_write_temp_file_path(file_path.as_ref());
     self.write_file_bytes(&tmp_file, bytes)?;
     self.rename(tmp_file, file_path)
   }
+  /// Replaces the contents of a file such that readers never observe a
+  /// partially written file, preserving its metadata where possible.
+  fn replace_file_bytes(&self, file_path: impl AsRef<Path>, bytes: &[u8]) -> Result<()> {
+    self.atomic_write_file_bytes(file_path, bytes)
+  }
   fn rename(&self, path_from: impl AsRef<Path>, path_to: impl AsRef<Path>) -> Result<()>;
   fn remove_file(&self, file_path: impl AsRef<Path>) -> Result<()>;
   fn remove_dir_all(&self, dir_path: impl AsRef<Path>) -> Result<()>;
@@ -83,8 +155,13 @@ pub trait Environment: Clone + Send + Sync + UrlDownloader + 'static {
   fn canonicalize(&self, path: impl AsRef<Path>) -> Result<CanonicalizedPathBuf>;
   fn is_absolute_path(&self, path: impl AsRef<Path>) -> bool;
   fn file_permissions(&self, path: impl AsRef<Path>) -> Result<FilePermissions>;
+  /// Gets the size of the file in bytes.
+  fn file_size(&self, path: impl AsRef<Path>) -> Result<u64>;
   fn set_file_permissions(&self, path: impl AsRef<Path>, permissions: FilePermissions) -> Result<()>;
   fn mk_dir_all(&self, path: impl AsRef<Path>) -> Result<()>;
+  /// Attempts to exclusively lock the file, creating it if it doesn't exist.
+  /// Returns `None` when the lock is held elsewhere (ex. by another process).
+  fn try_lock_file(&self, file_path: &Path) -> std::io::Result<Option<FileLock>>;
   fn cwd(&self) -> CanonicalizedPathBuf;
   fn current_exe(&self) -> Result<PathBuf>;
   /// Don't ever call this directly in the code. That's why this has this weird name.
@@ -118,11 +195,14 @@ pub trait Environment: Clone + Send + Sync + UrlDownloader + 'static {
   fn get_multi_selection(&self, prompt_message: &str, item_indent_width: u16, items: &[(bool, String)]) -> Result<Vec<usize>>;
   fn confirm(&self, prompt_message: &str, default_value: bool) -> Result<bool>;
   fn is_ci(&self) -> bool;
+  fn process_plugin_trust_policy(&self) -> ProcessPluginTrustPolicy;
   fn log_level(&self) -> LogLevel;
   fn compile_wasm(&self, wasm_bytes: &[u8]) -> Result<CompilationResult>;
   fn wasm_cache_key(&self) -> String;
   /// Returns the current CPU usage as a value from 0-100.
   async fn cpu_usage(&self) -> u8;
+  /// Returns the memory used by the current process in bytes.
+  fn memory_usage(&self) -> Option<u64>;
   fn stdout(&self) -> Box<dyn Write + Send>;
   fn stdin(&self) -> Box<dyn Read + Send>;
   fn progress_bars(&self) -> Option<&Arc<ProgressBars>> {
@@ -134,6 +214,17 @@ pub trait Environment: Clone + Send + Sync + UrlDownloader + 'static {
   fn remove_system_path(&self, directory_path: &str) -> Result<()>;
 }
 
+/// Gets a path to a temporary file in the same directory as the provided file path.
+pub fn get_atomic_write_temp_file_path(file_path: &Path) -> PathBuf {
+  // lifted from https://github.com/denoland/deno/blob/0f4051a37ad23377091043206e64126003caa480/cli/util/fs.rs#L29
+  let rand: String = (0..4).fold(String::new(), |mut output, _| {
+    let _ = write!(output, "{:02x}", rand::random::<u8>());
+    output
+  });
+  let extension = format!("{rand}.tmp");
+  file_path.with_extension(extension)
+}
+
 // use a macro here so the expression provided is only evaluated when in debug mode
 macro_rules! log_debug {
   ($logger:expr, $($arg:tt)*) => {
@@ -205,3 +296,17 @@ macro_rules! log_all {
     $logger.__log_stderr__(&format!($($arg)*));
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_error_when_network_disabled() {
+    assert!(ensure_network_allowed(false, "https://plugins.dprint.dev/cli.json").is_ok());
+    assert_eq!(
+      ensure_network_allowed(true, "https://plugins.dprint.dev/cli.json").err().unwrap().to_string(),
+      "Cannot download https://plugins.dprint.dev/cli.json because network access is disabled."
+    );
+  }
+}
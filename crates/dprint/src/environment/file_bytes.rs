@@ -0,0 +1,33 @@
+use std::ops::Deref;
+
+/// Bytes of a file that may either be owned or memory mapped.
+pub enum FileBytes {
+  Owned(Vec<u8>),
+  Mapped(memmap2::Mmap),
+}
+
+impl FileBytes {
+  pub fn is_mapped(&self) -> bool {
+    matches!(self, FileBytes::Mapped(_))
+  }
+}
+
+impl Deref for FileBytes {
+  type Target = [u8];
+
+  fn deref(&self) -> &[u8] {
+    match self {
+      FileBytes::Owned(bytes) => bytes,
+      FileBytes::Mapped(mmap) => mmap,
+    }
+  }
+}
+
+impl std::fmt::Debug for FileBytes {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("FileBytes")
+      .field("len", &self.len())
+      .field("mapped", &self.is_mapped())
+      .finish()
+  }
+}
@@ -1,18 +1,23 @@
 mod canonicalized_path_buf;
+mod cgroup;
 #[allow(clippy::module_inception)]
 #[macro_use]
 mod environment;
+mod file_bytes;
+mod overlay_environment;
 mod real_environment;
-#[cfg(test)]
+#[cfg(any(test, feature = "test-harness"))]
 mod test_environment;
-#[cfg(test)]
+#[cfg(any(test, feature = "test-harness"))]
 mod test_environment_builder;
 
 pub use canonicalized_path_buf::*;
 pub use environment::*;
+pub use file_bytes::*;
+pub use overlay_environment::*;
 pub use real_environment::*;
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test-harness"))]
 pub use test_environment::*;
-#[cfg(test)]
+#[cfg(any(test, feature = "test-harness"))]
 pub use test_environment_builder::*;
@@ -0,0 +1,479 @@
+use anyhow::Result;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use dprint_core::async_runtime::async_trait;
+
+use super::CanonicalizedPathBuf;
+use super::DirEntry;
+use super::Environment;
+use super::FileBytes;
+use super::FileLock;
+use super::FilePermissions;
+use super::ProcessPluginTrustPolicy;
+use super::TestFilePermissions;
+use super::UrlDownloader;
+use crate::plugins::CompilationResult;
+use crate::utils::LogLevel;
+use crate::utils::ProgressBars;
+
+/// An environment that overlays in-memory file contents over the file system
+/// of another environment (ex. unsaved editor buffers over the real file system).
+///
+/// Reads of overlaid files return the in-memory contents and writes to them only
+/// update the overlay. Everything else, such as reading configuration files, is
+/// delegated to the inner environment. Overlaid file paths should be absolute and
+/// canonicalized.
+#[derive(Clone)]
+pub struct OverlayEnvironment<TEnvironment: Environment> {
+  inner: TEnvironment,
+  files: Arc<Mutex<HashMap<PathBuf, Vec<u8>>>>,
+}
+
+impl<TEnvironment: Environment> OverlayEnvironment<TEnvironment> {
+  pub fn new(inner: TEnvironment) -> Self {
+    Self {
+      inner,
+      files: Default::default(),
+    }
+  }
+
+  pub fn inner(&self) -> &TEnvironment {
+    &self.inner
+  }
+
+  /// Sets the in-memory contents of a file.
+  pub fn overlay_file(&self, file_path: impl AsRef<Path>, bytes: impl Into<Vec<u8>>) {
+    self.files.lock().insert(file_path.as_ref().to_path_buf(), bytes.into());
+  }
+
+  /// Gets the in-memory contents of a file if it's overlaid.
+  pub fn overlaid_file_bytes(&self, file_path: impl AsRef<Path>) -> Option<Vec<u8>> {
+    self.files.lock().get(file_path.as_ref()).cloned()
+  }
+
+  /// Removes the in-memory contents of a file, returning them if the file was overlaid.
+  pub fn remove_overlay(&self, file_path: impl AsRef<Path>) -> Option<Vec<u8>> {
+    self.files.lock().remove(file_path.as_ref())
+  }
+
+  fn is_overlaid_file(&self, file_path: &Path) -> bool {
+    self.files.lock().contains_key(file_path)
+  }
+
+  /// Gets if the path is an overlaid file or a directory containing one.
+  fn is_overlaid_path(&self, path: &Path) -> bool {
+    self.files.lock().keys().any(|key| key.starts_with(path))
+  }
+
+  /// Updates the overlaid file, returning false when the file isn't overlaid.
+  fn try_write_overlaid_file(&self, file_path: &Path, bytes: &[u8]) -> bool {
+    match self.files.lock().get_mut(file_path) {
+      Some(contents) => {
+        log_debug!(self, "Writing overlaid file: {}", file_path.display());
+        *contents = bytes.to_vec();
+        true
+      }
+      None => false,
+    }
+  }
+}
+
+#[async_trait(?Send)]
+impl<TEnvironment: Environment> UrlDownloader for OverlayEnvironment<TEnvironment> {
+  async fn download_file(&self, url: &str) -> Result<Option<Vec<u8>>> {
+    self.inner.download_file(url).await
+  }
+
+  async fn download_file_with_checksum(&self, url: &str, checksum: &str) -> Result<Option<Vec<u8>>> {
+    self.inner.download_file_with_checksum(url, checksum).await
+  }
+
+  async fn post_json(&self, url: &str, headers: &[(String, String)], body: Vec<u8>) -> Result<()> {
+    self.inner.post_json(url, headers, body).await
+  }
+}
+
+#[async_trait]
+impl<TEnvironment: Environment> Environment for OverlayEnvironment<TEnvironment> {
+  fn is_real(&self) -> bool {
+    self.inner.is_real()
+  }
+
+  fn get_staged_files(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+    self.inner.get_staged_files(dir)
+  }
+
+  fn read_file(&self, file_path: impl AsRef<Path>) -> Result<String> {
+    Ok(String::from_utf8(self.read_file_bytes(file_path)?)?)
+  }
+
+  fn read_file_bytes(&self, file_path: impl AsRef<Path>) -> Result<Vec<u8>> {
+    match self.overlaid_file_bytes(&file_path) {
+      Some(bytes) => {
+        log_debug!(self, "Reading overlaid file: {}", file_path.as_ref().display());
+        Ok(bytes)
+      }
+      None => self.inner.read_file_bytes(file_path),
+    }
+  }
+
+  fn read_file_bytes_mapped(&self, file_path: impl AsRef<Path>) -> Result<FileBytes> {
+    if self.is_overlaid_file(file_path.as_ref()) {
+      Ok(FileBytes::Owned(self.read_file_bytes(file_path)?))
+    } else {
+      self.inner.read_file_bytes_mapped(file_path)
+    }
+  }
+
+  fn write_file_bytes(&self, file_path: impl AsRef<Path>, bytes: &[u8]) -> Result<()> {
+    if self.try_write_overlaid_file(file_path.as_ref(), bytes) {
+      Ok(())
+    } else {
+      self.inner.write_file_bytes(file_path, bytes)
+    }
+  }
+
+  fn atomic_write_file_bytes(&self, file_path: impl AsRef<Path>, bytes: &[u8]) -> Result<()> {
+    if self.try_write_overlaid_file(file_path.as_ref(), bytes) {
+      Ok(())
+    } else {
+      self.inner.atomic_write_file_bytes(file_path, bytes)
+    }
+  }
+
+  fn replace_file_bytes(&self, file_path: impl AsRef<Path>, bytes: &[u8]) -> Result<()> {
+    if self.try_write_overlaid_file(file_path.as_ref(), bytes) {
+      Ok(())
+    } else {
+      self.inner.replace_file_bytes(file_path, bytes)
+    }
+  }
+
+  fn rename(&self, path_from: impl AsRef<Path>, path_to: impl AsRef<Path>) -> Result<()> {
+    let mut files = self.files.lock();
+    match files.remove(path_from.as_ref()) {
+      Some(bytes) => {
+        files.insert(path_to.as_ref().to_path_buf(), bytes);
+        Ok(())
+      }
+      None => {
+        // otherwise the overlay would hide the renamed file
+        files.remove(path_to.as_ref());
+        drop(files);
+        self.inner.rename(path_from, path_to)
+      }
+    }
+  }
+
+  fn remove_file(&self, file_path: impl AsRef<Path>) -> Result<()> {
+    if self.remove_overlay(&file_path).is_some() {
+      Ok(())
+    } else {
+      self.inner.remove_file(file_path)
+    }
+  }
+
+  fn remove_dir_all(&self, dir_path: impl AsRef<Path>) -> Result<()> {
+    self.files.lock().retain(|key, _| !key.starts_with(dir_path.as_ref()));
+    self.inner.remove_dir_all(dir_path)
+  }
+
+  fn dir_info(&self, dir_path: impl AsRef<Path>) -> std::io::Result<Vec<DirEntry>> {
+    let dir_path = dir_path.as_ref();
+    let mut entries = match self.inner.dir_info(dir_path) {
+      Ok(entries) => entries,
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound && self.is_overlaid_path(dir_path) => Vec::new(),
+      Err(err) => return Err(err),
+    };
+    let mut found_paths = entries
+      .iter()
+      .map(|entry| match entry {
+        DirEntry::Directory(path) => path.clone(),
+        DirEntry::File { path, .. } => path.clone(),
+      })
+      .collect::<HashSet<_>>();
+
+    let files = self.files.lock();
+    for key in files.keys() {
+      let Ok(relative_path) = key.strip_prefix(dir_path) else {
+        continue;
+      };
+      let mut components = relative_path.components();
+      let Some(first_component) = components.next() else {
+        continue;
+      };
+      let path = dir_path.join(first_component);
+      if !found_paths.insert(path.clone()) {
+        continue;
+      }
+      if components.next().is_some() {
+        entries.push(DirEntry::Directory(path));
+      } else {
+        entries.push(DirEntry::File {
+          name: first_component.as_os_str().to_os_string(),
+          path,
+        });
+      }
+    }
+
+    Ok(entries)
+  }
+
+  fn path_exists(&self, file_path: impl AsRef<Path>) -> bool {
+    self.is_overlaid_path(file_path.as_ref()) || self.inner.path_exists(file_path)
+  }
+
+  fn canonicalize(&self, path: impl AsRef<Path>) -> Result<CanonicalizedPathBuf> {
+    match self.inner.canonicalize(&path) {
+      Ok(path) => Ok(path),
+      // the path only exists in memory
+      Err(_) if self.is_overlaid_path(path.as_ref()) => Ok(CanonicalizedPathBuf::new(path.as_ref().to_path_buf())),
+      Err(err) => Err(err),
+    }
+  }
+
+  fn is_absolute_path(&self, path: impl AsRef<Path>) -> bool {
+    self.inner.is_absolute_path(path)
+  }
+
+  fn file_permissions(&self, path: impl AsRef<Path>) -> Result<FilePermissions> {
+    if self.is_overlaid_file(path.as_ref()) && !self.inner.path_exists(&path) {
+      Ok(FilePermissions::Test(TestFilePermissions::default()))
+    } else {
+      self.inner.file_permissions(path)
+    }
+  }
+
+  fn file_size(&self, path: impl AsRef<Path>) -> Result<u64> {
+    match self.files.lock().get(path.as_ref()) {
+      Some(bytes) => Ok(bytes.len() as u64),
+      None => self.inner.file_size(path),
+    }
+  }
+
+  fn set_file_permissions(&self, path: impl AsRef<Path>, permissions: FilePermissions) -> Result<()> {
+    if self.is_overlaid_file(path.as_ref()) && !self.inner.path_exists(&path) {
+      Ok(())
+    } else {
+      self.inner.set_file_permissions(path, permissions)
+    }
+  }
+
+  fn mk_dir_all(&self, path: impl AsRef<Path>) -> Result<()> {
+    self.inner.mk_dir_all(path)
+  }
+
+  fn try_lock_file(&self, file_path: &Path) -> std::io::Result<Option<FileLock>> {
+    self.inner.try_lock_file(file_path)
+  }
+
+  fn cwd(&self) -> CanonicalizedPathBuf {
+    self.inner.cwd()
+  }
+
+  fn current_exe(&self) -> Result<PathBuf> {
+    self.inner.current_exe()
+  }
+
+  fn __log__(&self, text: &str) {
+    self.inner.__log__(text)
+  }
+
+  fn __log_stderr__(&self, text: &str) {
+    self.inner.__log_stderr__(text)
+  }
+
+  fn log_stderr_with_context(&self, text: &str, context_name: &str) {
+    self.inner.log_stderr_with_context(text, context_name)
+  }
+
+  fn log_machine_readable(&self, bytes: &[u8]) {
+    self.inner.log_machine_readable(bytes)
+  }
+
+  fn log_action_with_progress<TResult: Send + Sync, TCreate: FnOnce(Box<dyn Fn(usize)>) -> TResult + Send + Sync>(
+    &self,
+    message: &str,
+    action: TCreate,
+    total_size: usize,
+  ) -> TResult {
+    self.inner.log_action_with_progress(message, action, total_size)
+  }
+
+  fn get_cache_dir(&self) -> CanonicalizedPathBuf {
+    self.inner.get_cache_dir()
+  }
+
+  fn cpu_arch(&self) -> String {
+    self.inner.cpu_arch()
+  }
+
+  fn os(&self) -> String {
+    self.inner.os()
+  }
+
+  fn max_threads(&self) -> usize {
+    self.inner.max_threads()
+  }
+
+  fn cli_version(&self) -> String {
+    self.inner.cli_version()
+  }
+
+  fn get_time_secs(&self) -> u64 {
+    self.inner.get_time_secs()
+  }
+
+  fn get_selection(&self, prompt_message: &str, item_indent_width: u16, items: &[String]) -> Result<usize> {
+    self.inner.get_selection(prompt_message, item_indent_width, items)
+  }
+
+  fn get_multi_selection(&self, prompt_message: &str, item_indent_width: u16, items: &[(bool, String)]) -> Result<Vec<usize>> {
+    self.inner.get_multi_selection(prompt_message, item_indent_width, items)
+  }
+
+  fn confirm(&self, prompt_message: &str, default_value: bool) -> Result<bool> {
+    self.inner.confirm(prompt_message, default_value)
+  }
+
+  fn is_ci(&self) -> bool {
+    self.inner.is_ci()
+  }
+
+  fn process_plugin_trust_policy(&self) -> ProcessPluginTrustPolicy {
+    self.inner.process_plugin_trust_policy()
+  }
+
+  #[inline]
+  fn log_level(&self) -> LogLevel {
+    self.inner.log_level()
+  }
+
+  fn compile_wasm(&self, wasm_bytes: &[u8]) -> Result<CompilationResult> {
+    self.inner.compile_wasm(wasm_bytes)
+  }
+
+  fn wasm_cache_key(&self) -> String {
+    self.inner.wasm_cache_key()
+  }
+
+  async fn cpu_usage(&self) -> u8 {
+    self.inner.cpu_usage().await
+  }
+
+  fn memory_usage(&self) -> Option<u64> {
+    self.inner.memory_usage()
+  }
+
+  fn stdout(&self) -> Box<dyn Write + Send> {
+    self.inner.stdout()
+  }
+
+  fn stdin(&self) -> Box<dyn Read + Send> {
+    self.inner.stdin()
+  }
+
+  fn progress_bars(&self) -> Option<&Arc<ProgressBars>> {
+    self.inner.progress_bars()
+  }
+
+  #[cfg(windows)]
+  fn ensure_system_path(&self, directory_path: &str) -> Result<()> {
+    self.inner.ensure_system_path(directory_path)
+  }
+
+  #[cfg(windows)]
+  fn remove_system_path(&self, directory_path: &str) -> Result<()> {
+    self.inner.remove_system_path(directory_path)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::path::PathBuf;
+
+  use super::*;
+  use crate::environment::TestEnvironment;
+
+  fn get_environment() -> OverlayEnvironment<TestEnvironment> {
+    let inner = TestEnvironment::new();
+    inner.write_file("/dprint.json", "{}").unwrap();
+    inner.write_file("/src/file.ts", "disk").unwrap();
+    inner.write_file("/src/other.ts", "disk").unwrap();
+    let environment = OverlayEnvironment::new(inner);
+    environment.overlay_file("/src/file.ts", "memory");
+    environment.overlay_file("/src/new.ts", "new");
+    environment.overlay_file("/src/sub/nested.ts", "nested");
+    environment
+  }
+
+  #[test]
+  fn should_read_overlaid_files() {
+    let environment = get_environment();
+    assert_eq!(environment.read_file("/src/file.ts").unwrap(), "memory");
+    assert_eq!(environment.read_file("/src/new.ts").unwrap(), "new");
+    assert_eq!(environment.read_file("/src/other.ts").unwrap(), "disk");
+    assert_eq!(&*environment.read_file_bytes_mapped("/src/file.ts").unwrap(), b"memory");
+    assert_eq!(environment.file_size("/src/new.ts").unwrap(), 3);
+    assert!(environment.path_exists("/src/new.ts"));
+    assert!(environment.path_exists("/src/sub"));
+    assert!(!environment.path_exists("/src/other"));
+    assert_eq!(
+      environment.canonicalize("/src/sub/nested.ts").unwrap(),
+      CanonicalizedPathBuf::new_for_testing("/src/sub/nested.ts")
+    );
+  }
+
+  #[test]
+  fn should_only_write_overlaid_files_to_overlay() {
+    let environment = get_environment();
+    environment.write_file("/src/file.ts", "formatted").unwrap();
+    environment.replace_file_bytes("/src/new.ts", b"formatted new").unwrap();
+    environment.write_file("/src/other.ts", "formatted other").unwrap();
+    assert_eq!(environment.overlaid_file_bytes("/src/file.ts").unwrap(), b"formatted");
+    assert_eq!(environment.overlaid_file_bytes("/src/new.ts").unwrap(), b"formatted new");
+    assert_eq!(environment.inner().read_file("/src/file.ts").unwrap(), "disk");
+    assert!(!environment.inner().path_exists("/src/new.ts"));
+    assert_eq!(environment.inner().read_file("/src/other.ts").unwrap(), "formatted other");
+
+    environment.remove_file("/src/file.ts").unwrap();
+    assert_eq!(environment.read_file("/src/file.ts").unwrap(), "disk");
+    environment.rename("/src/new.ts", "/src/renamed.ts").unwrap();
+    assert_eq!(environment.remove_overlay("/src/renamed.ts").unwrap(), b"formatted new");
+    assert!(!environment.path_exists("/src/new.ts"));
+  }
+
+  #[test]
+  fn should_include_overlaid_files_in_dir_info() {
+    let environment = get_environment();
+    let mut entries = environment
+      .dir_info("/src")
+      .unwrap()
+      .into_iter()
+      .map(|entry| match entry {
+        DirEntry::Directory(path) => (path, true),
+        DirEntry::File { path, .. } => (path, false),
+      })
+      .collect::<Vec<_>>();
+    entries.sort();
+    assert_eq!(
+      entries,
+      vec![
+        (PathBuf::from("/src/file.ts"), false),
+        (PathBuf::from("/src/new.ts"), false),
+        (PathBuf::from("/src/other.ts"), false),
+        (PathBuf::from("/src/sub"), true),
+      ]
+    );
+    let entries = environment.dir_info("/src/sub").unwrap();
+    assert_eq!(entries.len(), 1);
+    assert!(matches!(&entries[0], DirEntry::File { name, .. } if name == "nested.ts"));
+  }
+}
@@ -1,11 +1,15 @@
 use anyhow::bail;
 use anyhow::Context;
 use anyhow::Result;
+use crossterm::tty::IsTty;
 use once_cell::sync::Lazy;
 use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
+use std::borrow::Cow;
 use std::fs;
 use std::hash::Hash;
+use std::io::Read;
+use std::io::Write;
 use std::num::NonZeroUsize;
 use std::path::Path;
 use std::path::PathBuf;
@@ -16,10 +20,16 @@ use sysinfo::System;
 
 use dprint_core::async_runtime::async_trait;
 
+use super::cgroup::get_cgroup_cpu_limit;
+use super::ensure_network_allowed;
+use super::get_atomic_write_temp_file_path;
 use super::CanonicalizedPathBuf;
 use super::DirEntry;
 use super::Environment;
+use super::FileBytes;
+use super::FileLock;
 use super::FilePermissions;
+use super::ProcessPluginTrustPolicy;
 use super::UrlDownloader;
 use crate::plugins::CompilationResult;
 use crate::utils::log_action_with_progress;
@@ -31,6 +41,7 @@ use crate::utils::LogLevel;
 use crate::utils::Logger;
 use crate::utils::LoggerOptions;
 use crate::utils::ProgressBars;
+use crate::utils::ProgressBarsOptions;
 use crate::utils::RealUrlDownloader;
 
 // cache the cwd because it's much faster than looking it up each time
@@ -39,6 +50,11 @@ static CACHED_CWD: OnceCell<CanonicalizedPathBuf> = OnceCell::new();
 pub struct RealEnvironmentOptions {
   pub log_level: LogLevel,
   pub is_stdout_machine_readable: bool,
+  /// Errors instead of downloading anything, so only local and
+  /// already cached plugins and configuration files may be used.
+  pub offline: bool,
+  /// How to handle process plugins the user hasn't trusted yet.
+  pub process_plugin_trust: ProcessPluginTrustPolicy,
 }
 
 #[derive(Clone)]
@@ -47,6 +63,8 @@ pub struct RealEnvironment {
   url_downloader: Arc<RealUrlDownloader>,
   logger: Arc<Logger>,
   system: Arc<Mutex<System>>,
+  offline: bool,
+  process_plugin_trust: ProcessPluginTrustPolicy,
 }
 
 impl RealEnvironment {
@@ -56,13 +74,19 @@ impl RealEnvironment {
       is_stdout_machine_readable: options.is_stdout_machine_readable,
       log_level: options.log_level,
     }));
-    let progress_bars = ProgressBars::new(&logger).map(Arc::new);
+    let progress_bars = ProgressBars::new(&logger, is_ci(), get_progress_bars_options(&logger)).map(Arc::new);
     let url_downloader = Arc::new(RealUrlDownloader::new(progress_bars.clone(), logger.clone())?);
     let environment = RealEnvironment {
       url_downloader,
       logger,
       progress_bars,
       system: Default::default(),
+      offline: options.offline,
+      process_plugin_trust: match options.process_plugin_trust {
+        // there's nobody to answer a prompt on CI or when not run in a terminal
+        ProcessPluginTrustPolicy::Prompt if is_ci() || !can_prompt() => ProcessPluginTrustPolicy::Deny,
+        policy => policy,
+      },
     };
 
     // ensure the cache directory is created
@@ -82,6 +106,8 @@ impl RealEnvironment {
     let env = RealEnvironment::new(RealEnvironmentOptions {
       log_level: LogLevel::Info,
       is_stdout_machine_readable: false,
+      offline: false,
+      process_plugin_trust: ProcessPluginTrustPolicy::Trust,
     })
     .unwrap();
 
@@ -92,12 +118,33 @@ impl RealEnvironment {
 #[async_trait(?Send)]
 impl UrlDownloader for RealEnvironment {
   async fn download_file(&self, url: &str) -> Result<Option<Vec<u8>>> {
+    ensure_network_allowed(self.offline, url)?;
     log_debug!(self, "Downloading url: {}", url);
 
     let downloader = self.url_downloader.clone();
     let url = url.to_string();
     dprint_core::async_runtime::spawn_blocking(move || downloader.download(&url)).await?
   }
+
+  async fn download_file_with_checksum(&self, url: &str, checksum: &str) -> Result<Option<Vec<u8>>> {
+    ensure_network_allowed(self.offline, url)?;
+    log_debug!(self, "Downloading url: {}", url);
+
+    let downloader = self.url_downloader.clone();
+    let url = url.to_string();
+    let checksum = checksum.to_string();
+    dprint_core::async_runtime::spawn_blocking(move || downloader.download_with_checksum(&url, Some(&checksum))).await?
+  }
+
+  async fn post_json(&self, url: &str, headers: &[(String, String)], body: Vec<u8>) -> Result<()> {
+    ensure_network_allowed(self.offline, url)?;
+    log_debug!(self, "Posting to url: {}", url);
+
+    let downloader = self.url_downloader.clone();
+    let url = url.to_string();
+    let headers = headers.to_vec();
+    dprint_core::async_runtime::spawn_blocking(move || downloader.post_json(&url, &headers, &body)).await?
+  }
 }
 
 #[async_trait]
@@ -119,8 +166,17 @@ impl Environment for RealEnvironment {
     }
   }
 
-  fn get_staged_files(&self) -> Result<Vec<PathBuf>> {
+  fn read_file_bytes_mapped(&self, file_path: impl AsRef<Path>) -> Result<FileBytes> {
+    log_debug!(self, "Reading file: {}", file_path.as_ref().display());
+    match read_file_bytes_mapped(file_path.as_ref()) {
+      Ok(bytes) => Ok(bytes),
+      Err(err) => bail!("Error reading file {}: {:#}", file_path.as_ref().display(), err),
+    }
+  }
+
+  fn get_staged_files(&self, dir: &Path) -> Result<Vec<PathBuf>> {
     let output = Command::new("git")
+      .current_dir(dir)
       .arg("diff")
       .arg("--name-only")
       .arg("--relative")
@@ -140,6 +196,14 @@ impl Environment for RealEnvironment {
     }
   }
 
+  fn replace_file_bytes(&self, file_path: impl AsRef<Path>, bytes: &[u8]) -> Result<()> {
+    log_debug!(self, "Replacing file: {}", file_path.as_ref().display());
+    match replace_file_bytes(file_path.as_ref(), bytes) {
+      Ok(_) => Ok(()),
+      Err(err) => bail!("Error writing file {}: {:#}", file_path.as_ref().display(), err),
+    }
+  }
+
   fn rename(&self, path_from: impl AsRef<Path>, path_to: impl AsRef<Path>) -> Result<()> {
     log_debug!(self, "Renaming {} -> {}", path_from.as_ref().display(), path_to.as_ref().display());
     #[allow(clippy::disallowed_methods)]
@@ -211,6 +275,12 @@ impl Environment for RealEnvironment {
     ))
   }
 
+  fn file_size(&self, path: impl AsRef<Path>) -> Result<u64> {
+    #[allow(clippy::disallowed_methods)]
+    let metadata = fs::metadata(&path).with_context(|| format!("Error getting file size for: {}", path.as_ref().display()))?;
+    Ok(metadata.len())
+  }
+
   fn set_file_permissions(&self, path: impl AsRef<Path>, permissions: FilePermissions) -> Result<()> {
     let permissions = match permissions {
       FilePermissions::Std(p) => p,
@@ -230,6 +300,22 @@ impl Environment for RealEnvironment {
     }
   }
 
+  fn try_lock_file(&self, file_path: &Path) -> std::io::Result<Option<FileLock>> {
+    use fs3::FileExt;
+    #[allow(clippy::disallowed_methods)]
+    let file = fs::OpenOptions::new().read(true).write(true).truncate(true).create(true).open(file_path)?;
+    if file.try_lock_exclusive().is_err() {
+      return Ok(None);
+    }
+    let environment = self.clone();
+    let file_path = file_path.to_path_buf();
+    Ok(Some(FileLock::new(move || {
+      if let Err(err) = file.unlock() {
+        log_debug!(environment, "Failed releasing lock for {}. {:#}", file_path.display(), err);
+      }
+    })))
+  }
+
   fn cwd(&self) -> CanonicalizedPathBuf {
     CACHED_CWD
       .get_or_init(|| {
@@ -286,7 +372,11 @@ impl Environment for RealEnvironment {
 
   fn max_threads(&self) -> usize {
     #[allow(clippy::disallowed_methods)]
-    resolve_max_threads(std::env::var("DPRINT_MAX_THREADS").ok(), std::thread::available_parallelism().ok())
+    resolve_max_threads(
+      std::env::var("DPRINT_MAX_THREADS").ok(),
+      std::thread::available_parallelism().ok(),
+      get_cgroup_cpu_limit(),
+    )
   }
 
   fn cli_version(&self) -> String {
@@ -316,13 +406,11 @@ impl Environment for RealEnvironment {
   }
 
   fn is_ci(&self) -> bool {
-    match std::env::var_os("CI") {
-      Some(value) => {
-        let value = value.to_string_lossy();
-        matches!(value.as_ref(), "true" | "1")
-      }
-      None => false,
-    }
+    is_ci()
+  }
+
+  fn process_plugin_trust_policy(&self) -> ProcessPluginTrustPolicy {
+    self.process_plugin_trust
   }
 
   #[inline]
@@ -383,6 +471,13 @@ impl Environment for RealEnvironment {
     .unwrap_or(0)
   }
 
+  fn memory_usage(&self) -> Option<u64> {
+    let pid = sysinfo::get_current_pid().ok()?;
+    let mut system = self.system.lock();
+    system.refresh_process(pid);
+    system.process(pid).map(|process| process.memory())
+  }
+
   fn stdout(&self) -> Box<dyn std::io::Write + Send> {
     Box::new(std::io::stdout())
   }
@@ -438,7 +533,7 @@ impl Environment for RealEnvironment {
   }
 }
 
-fn resolve_max_threads(env_var: Option<String>, available_parallelism: Option<NonZeroUsize>) -> usize {
+fn resolve_max_threads(env_var: Option<String>, available_parallelism: Option<NonZeroUsize>, cgroup_cpu_limit: Option<usize>) -> usize {
   fn maybe_specified_threads(env_var: Option<String>) -> Option<usize> {
     let value = env_var?.parse::<usize>().ok()?;
     if value > 0 {
@@ -448,7 +543,11 @@ fn resolve_max_threads(env_var: Option<String>, available_parallelism: Option<No
     }
   }
 
-  let maybe_actual_count = available_parallelism.map(|p| p.get());
+  // std may fall back to the host's cores when it can't read the cgroup quota
+  let maybe_actual_count = match (available_parallelism.map(|p| p.get()), cgroup_cpu_limit) {
+    (Some(count), Some(limit)) => Some(std::cmp::min(count, limit)),
+    (count, limit) => count.or(limit),
+  };
   match maybe_specified_threads(env_var) {
     Some(specified_count) => match maybe_actual_count {
       Some(actual_count) if specified_count > actual_count => actual_count,
@@ -458,6 +557,86 @@ fn resolve_max_threads(env_var: Option<String>, available_parallelism: Option<No
   }
 }
 
+/// Files at or above this size are memory mapped when read for formatting.
+const MMAP_FILE_SIZE_THRESHOLD: u64 = 1024 * 1024;
+
+fn read_file_bytes_mapped(file_path: &Path) -> std::io::Result<FileBytes> {
+  read_file_bytes_mapped_with_threshold(file_path, MMAP_FILE_SIZE_THRESHOLD)
+}
+
+fn read_file_bytes_mapped_with_threshold(file_path: &Path, threshold: u64) -> std::io::Result<FileBytes> {
+  let mut file = fs::File::open(file_path)?;
+  let len = file.metadata()?.len();
+  if len > 0 && len >= threshold {
+    // SAFETY: Another process modifying the file while it's mapped is undefined
+    // behaviour. This is accepted similarly to other tools (ex. ripgrep) because the
+    // mapping is only held while the file is being formatted.
+    if let Ok(mmap) = unsafe { memmap2::Mmap::map(&file) } {
+      return Ok(FileBytes::Mapped(mmap));
+    }
+    // otherwise fall back to reading the file (ex. the file system doesn't support mapping)
+  }
+  let mut bytes = Vec::with_capacity(len as usize);
+  file.read_to_end(&mut bytes)?;
+  Ok(FileBytes::Owned(bytes))
+}
+
+#[allow(clippy::disallowed_methods)]
+fn replace_file_bytes(file_path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+  // write to the target of a symlink instead of replacing the symlink
+  let file_path = match fs::symlink_metadata(file_path) {
+    Ok(metadata) if metadata.file_type().is_symlink() => Cow::Owned(fs::canonicalize(file_path)?),
+    _ => Cow::Borrowed(file_path),
+  };
+  let metadata = match fs::metadata(&file_path) {
+    Ok(metadata) => metadata,
+    Err(_) => return fs::write(&file_path, bytes),
+  };
+  if !can_replace_by_rename(&metadata) {
+    return fs::write(&file_path, bytes);
+  }
+
+  let tmp_file_path = get_atomic_write_temp_file_path(&file_path);
+  let result = write_replacement_file(&tmp_file_path, bytes, &metadata).and_then(|_| fs::rename(&tmp_file_path, &file_path));
+  if result.is_err() {
+    // ex. the directory isn't writable or the file is in use on Windows
+    let _ = fs::remove_file(&tmp_file_path);
+    fs::write(&file_path, bytes)
+  } else {
+    Ok(())
+  }
+}
+
+fn can_replace_by_rename(metadata: &fs::Metadata) -> bool {
+  // keep erroring for readonly files
+  if metadata.permissions().readonly() {
+    return false;
+  }
+  // renaming would break the hard link
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::MetadataExt;
+    if metadata.nlink() > 1 {
+      return false;
+    }
+  }
+  true
+}
+
+fn write_replacement_file(file_path: &Path, bytes: &[u8], original_metadata: &fs::Metadata) -> std::io::Result<()> {
+  let mut file = fs::OpenOptions::new().write(true).create_new(true).open(file_path)?;
+  file.write_all(bytes)?;
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::MetadataExt;
+    // only privileged users may change the owner, so this is best effort
+    let _ = std::os::unix::fs::fchown(&file, Some(original_metadata.uid()), Some(original_metadata.gid()));
+  }
+  file.set_permissions(original_metadata.permissions())?;
+  // ensure the contents are on disk before the rename replaces the original file
+  file.sync_all()
+}
+
 fn canonicalize_path(path: impl AsRef<Path>) -> Result<CanonicalizedPathBuf> {
   // use this to avoid //?//C:/etc... like paths on windows (UNC)
   match dunce::canonicalize(path.as_ref()) {
@@ -476,6 +655,39 @@ static CACHE_DIR: Lazy<Result<CanonicalizedPathBuf>> = Lazy::new(|| {
   canonicalize_path(cache_dir)
 });
 
+fn get_progress_bars_options(logger: &Logger) -> ProgressBarsOptions {
+  #[allow(clippy::disallowed_methods)]
+  let mut options = match std::env::var("DPRINT_PROGRESS_BARS") {
+    Ok(value) => match ProgressBarsOptions::parse(&value) {
+      Ok(options) => options,
+      Err(err) => {
+        log_warn!(logger, "Ignoring DPRINT_PROGRESS_BARS environment variable. {:#}", err);
+        ProgressBarsOptions::default()
+      }
+    },
+    Err(_) => ProgressBarsOptions::default(),
+  };
+  // https://no-color.org
+  if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+    options.colors = false;
+  }
+  options
+}
+
+fn is_ci() -> bool {
+  match std::env::var_os("CI") {
+    Some(value) => {
+      let value = value.to_string_lossy();
+      matches!(value.as_ref(), "true" | "1")
+    }
+    None => false,
+  }
+}
+
+fn can_prompt() -> bool {
+  std::io::stdin().is_tty() && std::io::stderr().is_tty()
+}
+
 fn get_cache_dir_internal(get_env_var: impl Fn(&str) -> Option<String>) -> Result<PathBuf> {
   if let Some(dir_path) = get_env_var(CACHE_DIR_ENV_VAR_NAME) {
     if !dir_path.trim().is_empty() {
@@ -496,6 +708,7 @@ fn get_cache_dir_internal(get_env_var: impl Fn(&str) -> Option<String>) -> Resul
 }
 
 #[cfg(test)]
+#[allow(clippy::disallowed_methods)]
 mod test {
   use super::*;
 
@@ -522,14 +735,80 @@ mod test {
     );
   }
 
+  #[test]
+  fn should_read_file_bytes_mapped() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file_path = temp_dir.path().join("file.txt");
+    std::fs::write(&file_path, "text").unwrap();
+
+    let bytes = read_file_bytes_mapped_with_threshold(&file_path, 4).unwrap();
+    assert!(bytes.is_mapped());
+    assert_eq!(&*bytes, b"text");
+    drop(bytes);
+
+    let bytes = read_file_bytes_mapped_with_threshold(&file_path, 5).unwrap();
+    assert!(!bytes.is_mapped());
+    assert_eq!(&*bytes, b"text");
+
+    std::fs::write(&file_path, "").unwrap();
+    let bytes = read_file_bytes_mapped_with_threshold(&file_path, 0).unwrap();
+    assert!(!bytes.is_mapped());
+    assert!(bytes.is_empty());
+  }
+
+  #[test]
+  fn should_replace_file_bytes() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file_path = temp_dir.path().join("file.txt");
+    fs::write(&file_path, "text").unwrap();
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      fs::set_permissions(&file_path, fs::Permissions::from_mode(0o640)).unwrap();
+    }
+
+    replace_file_bytes(&file_path, b"new text").unwrap();
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "new text");
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      assert_eq!(fs::metadata(&file_path).unwrap().permissions().mode() & 0o777, 0o640);
+    }
+    // the temp file should not remain
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn should_replace_file_bytes_of_links() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file_path = temp_dir.path().join("file.txt");
+    let hard_link_path = temp_dir.path().join("hard_link.txt");
+    let symlink_path = temp_dir.path().join("symlink.txt");
+    fs::write(&file_path, "text").unwrap();
+    fs::hard_link(&file_path, &hard_link_path).unwrap();
+    std::os::unix::fs::symlink(&file_path, &symlink_path).unwrap();
+
+    replace_file_bytes(&hard_link_path, b"1").unwrap();
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "1");
+    replace_file_bytes(&symlink_path, b"2").unwrap();
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "2");
+    assert!(fs::symlink_metadata(&symlink_path).unwrap().file_type().is_symlink());
+  }
+
   #[test]
   fn should_resolve_num_threads() {
-    assert_eq!(resolve_max_threads(None, None), 4);
-    assert_eq!(resolve_max_threads(None, NonZeroUsize::new(1)), 1);
-    assert_eq!(resolve_max_threads(None, NonZeroUsize::new(4)), 4);
-    assert_eq!(resolve_max_threads(Some("2".to_string()), NonZeroUsize::new(4)), 2);
-    assert_eq!(resolve_max_threads(Some("0".to_string()), NonZeroUsize::new(4)), 4);
-    assert_eq!(resolve_max_threads(Some("5".to_string()), NonZeroUsize::new(4)), 4);
-    assert_eq!(resolve_max_threads(Some("4".to_string()), NonZeroUsize::new(4)), 4);
+    assert_eq!(resolve_max_threads(None, None, None), 4);
+    assert_eq!(resolve_max_threads(None, NonZeroUsize::new(1), None), 1);
+    assert_eq!(resolve_max_threads(None, NonZeroUsize::new(4), None), 4);
+    assert_eq!(resolve_max_threads(Some("2".to_string()), NonZeroUsize::new(4), None), 2);
+    assert_eq!(resolve_max_threads(Some("0".to_string()), NonZeroUsize::new(4), None), 4);
+    assert_eq!(resolve_max_threads(Some("5".to_string()), NonZeroUsize::new(4), None), 4);
+    assert_eq!(resolve_max_threads(Some("4".to_string()), NonZeroUsize::new(4), None), 4);
+    // cgroup cpu quota
+    assert_eq!(resolve_max_threads(None, NonZeroUsize::new(8), Some(2)), 2);
+    assert_eq!(resolve_max_threads(None, NonZeroUsize::new(2), Some(8)), 2);
+    assert_eq!(resolve_max_threads(None, None, Some(2)), 2);
+    assert_eq!(resolve_max_threads(Some("4".to_string()), NonZeroUsize::new(8), Some(2)), 2);
   }
 }
@@ -19,7 +19,9 @@ use dprint_core::async_runtime::async_trait;
 use super::CanonicalizedPathBuf;
 use super::DirEntry;
 use super::Environment;
+use super::FileLock;
 use super::FilePermissions;
+use super::ProcessPluginTrustPolicy;
 use super::UrlDownloader;
 use crate::plugins::CompilationResult;
 use crate::utils::get_bytes_hash;
@@ -100,11 +102,13 @@ pub struct TestEnvironment {
   log_level: Arc<Mutex<LogLevel>>,
   cwd: Arc<Mutex<String>>,
   files: Arc<Mutex<HashMap<PathBuf, Vec<u8>>>>,
+  locked_files: Arc<Mutex<HashSet<PathBuf>>>,
   staged_files: Arc<Mutex<Vec<PathBuf>>>,
   file_permissions: Arc<Mutex<HashMap<PathBuf, FilePermissions>>>,
   stdout_messages: Arc<Mutex<Vec<String>>>,
   stderr_messages: Arc<Mutex<Vec<String>>>,
   remote_files: Arc<Mutex<HashMap<String, Result<Vec<u8>>>>>,
+  posted_json: Arc<Mutex<Vec<(String, serde_json::Value)>>>,
   deleted_directories: Arc<Mutex<Vec<PathBuf>>>,
   selection_result: Arc<Mutex<usize>>,
   multi_selection_result: Arc<Mutex<Option<Vec<usize>>>>,
@@ -118,6 +122,13 @@ pub struct TestEnvironment {
   cpu_arch: Arc<Mutex<String>>,
   max_threads_count: Arc<Mutex<usize>>,
   current_exe_path: Arc<Mutex<PathBuf>>,
+  process_plugin_trust_policy: Arc<Mutex<ProcessPluginTrustPolicy>>,
+}
+
+impl Default for TestEnvironment {
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
 impl TestEnvironment {
@@ -126,11 +137,13 @@ impl TestEnvironment {
       log_level: Arc::new(Mutex::new(LogLevel::Info)),
       cwd: Arc::new(Mutex::new(String::from("/"))),
       files: Default::default(),
+      locked_files: Default::default(),
       staged_files: Default::default(),
       file_permissions: Default::default(),
       stdout_messages: Default::default(),
       stderr_messages: Default::default(),
       remote_files: Default::default(),
+      posted_json: Default::default(),
       deleted_directories: Default::default(),
       selection_result: Arc::new(Mutex::new(0)),
       multi_selection_result: Arc::new(Mutex::new(None)),
@@ -150,6 +163,7 @@ impl TestEnvironment {
       cpu_arch: Arc::new(Mutex::new("x86_64".to_string())),
       max_threads_count: Arc::new(Mutex::new(std::thread::available_parallelism().map(|p| p.get()).unwrap_or(4))),
       current_exe_path: Arc::new(Mutex::new(PathBuf::from("/dprint"))),
+      process_plugin_trust_policy: Arc::new(Mutex::new(ProcessPluginTrustPolicy::Trust)),
     }
   }
 
@@ -187,6 +201,11 @@ impl TestEnvironment {
     }
   }
 
+  /// Takes the url and body of the JSON posted with `post_json`.
+  pub fn take_posted_json(&self) -> Vec<(String, serde_json::Value)> {
+    std::mem::take(&mut *self.posted_json.lock())
+  }
+
   pub fn is_dir_deleted(&self, path: impl AsRef<Path>) -> bool {
     self.deleted_directories.lock().contains(&path.as_ref().to_path_buf())
   }
@@ -248,9 +267,13 @@ impl TestEnvironment {
     *self.max_threads_count.lock() = value;
   }
 
+  pub fn set_process_plugin_trust_policy(&self, policy: ProcessPluginTrustPolicy) {
+    *self.process_plugin_trust_policy.lock() = policy;
+  }
+
   /// Remember to drop the plugins collection manually if using this with one.
   pub fn run_in_runtime<T>(&self, future: impl Future<Output = T>) -> T {
-    let rt = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+    let rt = tokio::runtime::Builder::new_current_thread().enable_time().enable_io().build().unwrap();
     rt.block_on(future)
   }
 
@@ -291,6 +314,11 @@ impl UrlDownloader for TestEnvironment {
   async fn download_file(&self, url: &str) -> Result<Option<Vec<u8>>> {
     self.get_remote_file(url)
   }
+
+  async fn post_json(&self, url: &str, _headers: &[(String, String)], body: Vec<u8>) -> Result<()> {
+    self.posted_json.lock().push((url.to_string(), serde_json::from_slice(&body)?));
+    Ok(())
+  }
 }
 
 #[async_trait]
@@ -299,7 +327,7 @@ impl Environment for TestEnvironment {
     false
   }
 
-  fn get_staged_files(&self) -> Result<Vec<PathBuf>> {
+  fn get_staged_files(&self, _dir: &Path) -> Result<Vec<PathBuf>> {
     Ok(self.staged_files.lock().clone())
   }
 
@@ -429,6 +457,14 @@ impl Environment for TestEnvironment {
     }
   }
 
+  fn file_size(&self, path: impl AsRef<Path>) -> Result<u64> {
+    let path = self.clean_path(path);
+    match self.files.lock().get(&path) {
+      Some(bytes) => Ok(bytes.len() as u64),
+      None => bail!("File not found."),
+    }
+  }
+
   fn set_file_permissions(&self, path: impl AsRef<Path>, permissions: FilePermissions) -> Result<()> {
     let path = self.clean_path(path);
     self.file_permissions.lock().insert(path, permissions);
@@ -439,6 +475,17 @@ impl Environment for TestEnvironment {
     Ok(())
   }
 
+  fn try_lock_file(&self, file_path: &Path) -> std::io::Result<Option<FileLock>> {
+    let file_path = self.clean_path(file_path);
+    if !self.locked_files.lock().insert(file_path.clone()) {
+      return Ok(None);
+    }
+    let locked_files = self.locked_files.clone();
+    Ok(Some(FileLock::new(move || {
+      locked_files.lock().remove(&file_path);
+    })))
+  }
+
   fn cwd(&self) -> CanonicalizedPathBuf {
     let cwd = self.cwd.lock();
     self.canonicalize(cwd.to_owned()).unwrap()
@@ -532,6 +579,10 @@ impl Environment for TestEnvironment {
     false
   }
 
+  fn process_plugin_trust_policy(&self) -> ProcessPluginTrustPolicy {
+    *self.process_plugin_trust_policy.lock()
+  }
+
   fn log_level(&self) -> LogLevel {
     *self.log_level.lock()
   }
@@ -566,6 +617,10 @@ impl Environment for TestEnvironment {
     20
   }
 
+  fn memory_usage(&self) -> Option<u64> {
+    Some(1024)
+  }
+
   fn stdout(&self) -> Box<dyn Write + Send> {
     Box::new(self.std_out_pipe.lock().0.take().unwrap())
   }
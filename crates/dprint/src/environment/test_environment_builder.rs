@@ -2,16 +2,23 @@ use indexmap::IndexMap;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::path::Path;
+#[cfg(test)]
 use std::path::PathBuf;
 
 use super::Environment;
 use super::TestEnvironment;
+use crate::test_harness::run_test_cli;
+#[cfg(test)]
 use crate::test_helpers;
+#[cfg(test)]
 use crate::test_helpers::TestProcessPluginFile;
+#[cfg(test)]
 use crate::test_helpers::WASM_PLUGIN_0_1_0_BYTES;
+#[cfg(test)]
 use crate::utils::get_sha256_checksum;
 
 pub struct TestConfigFileBuilder {
+  #[cfg_attr(not(test), allow(dead_code))]
   environment: TestEnvironment,
   incremental: Option<bool>,
   includes: Option<Vec<String>>,
@@ -64,6 +71,48 @@ impl TestConfigFileBuilder {
     self
   }
 
+  pub fn add_config_section(&mut self, name: &str, text: &str) -> &mut Self {
+    self.sections.insert(name.to_string(), text.to_string());
+    self
+  }
+
+  pub fn ensure_plugins_section(&mut self) -> &mut Self {
+    if self.plugins.is_none() {
+      self.plugins = Some(Vec::new());
+    }
+    self
+  }
+
+  pub fn clear_plugins(&mut self) -> &mut Self {
+    self.plugins = None;
+    self
+  }
+
+  pub fn add_plugin(&mut self, plugin: &str) -> &mut Self {
+    let mut plugins = self.plugins.take().unwrap_or_else(Vec::new);
+    plugins.push(plugin.to_string());
+    self.plugins = Some(plugins);
+    self
+  }
+
+  pub fn add_includes(&mut self, includes_item: &str) -> &mut Self {
+    let mut includes = self.includes.take().unwrap_or_else(Vec::new);
+    includes.push(includes_item.to_string());
+    self.includes = Some(includes);
+    self
+  }
+
+  pub fn add_excludes(&mut self, excludes_item: &str) -> &mut Self {
+    let mut excludes = self.excludes.take().unwrap_or_else(Vec::new);
+    excludes.push(excludes_item.to_string());
+    self.excludes = Some(excludes);
+    self
+  }
+}
+
+/// Helpers for the test plugins in this repo.
+#[cfg(test)]
+impl TestConfigFileBuilder {
   pub fn add_local_wasm_plugin(&mut self) -> &mut Self {
     self.add_plugin("/plugins/test-plugin.wasm")
   }
@@ -88,11 +137,6 @@ impl TestConfigFileBuilder {
     ))
   }
 
-  pub fn add_config_section(&mut self, name: &str, text: &str) -> &mut Self {
-    self.sections.insert(name.to_string(), text.to_string());
-    self
-  }
-
   pub fn add_remote_process_plugin(&mut self) -> &mut Self {
     // get the process plugin file and check its checksum
     let remote_file_text = self
@@ -112,39 +156,6 @@ impl TestConfigFileBuilder {
       self.add_plugin(&format!("{}@{}", url, checksum))
     }
   }
-
-  pub fn ensure_plugins_section(&mut self) -> &mut Self {
-    if self.plugins.is_none() {
-      self.plugins = Some(Vec::new());
-    }
-    self
-  }
-
-  pub fn clear_plugins(&mut self) -> &mut Self {
-    self.plugins = None;
-    self
-  }
-
-  pub fn add_plugin(&mut self, plugin: &str) -> &mut Self {
-    let mut plugins = self.plugins.take().unwrap_or_else(Vec::new);
-    plugins.push(plugin.to_string());
-    self.plugins = Some(plugins);
-    self
-  }
-
-  pub fn add_includes(&mut self, includes_item: &str) -> &mut Self {
-    let mut includes = self.includes.take().unwrap_or_else(Vec::new);
-    includes.push(includes_item.to_string());
-    self.includes = Some(includes);
-    self
-  }
-
-  pub fn add_excludes(&mut self, excludes_item: &str) -> &mut Self {
-    let mut excludes = self.excludes.take().unwrap_or_else(Vec::new);
-    excludes.push(excludes_item.to_string());
-    self.excludes = Some(excludes);
-    self
-  }
 }
 
 #[derive(Default)]
@@ -198,6 +209,12 @@ pub struct TestEnvironmentBuilder {
   info_file: Option<TestInfoFileBuilder>,
 }
 
+impl Default for TestEnvironmentBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 impl TestEnvironmentBuilder {
   pub fn new() -> Self {
     Self {
@@ -207,54 +224,8 @@ impl TestEnvironmentBuilder {
     }
   }
 
-  pub fn with_remote_wasm_plugin() -> TestEnvironmentBuilder {
-    let mut builder = TestEnvironmentBuilder::new();
-    builder.add_remote_wasm_plugin();
-    builder
-  }
-
-  pub fn with_initialized_remote_wasm_plugin() -> TestEnvironmentBuilder {
-    let mut builder = TestEnvironmentBuilder::new();
-    builder
-      .add_remote_wasm_plugin()
-      .with_default_config(|config_file| {
-        config_file.add_remote_wasm_plugin();
-      })
-      .initialize();
-    builder
-  }
-
-  pub fn with_remote_process_plugin() -> TestEnvironmentBuilder {
-    let mut builder = TestEnvironmentBuilder::new();
-    builder.add_remote_process_plugin();
-    builder
-  }
-
-  pub fn with_initialized_remote_process_plugin() -> TestEnvironmentBuilder {
-    let mut builder = TestEnvironmentBuilder::new();
-    builder
-      .add_remote_process_plugin()
-      .with_default_config(|config_file| {
-        config_file.add_remote_process_plugin();
-      })
-      .initialize();
-    builder
-  }
-
-  pub fn with_initialized_remote_wasm_and_process_plugin() -> TestEnvironmentBuilder {
-    let mut builder = TestEnvironmentBuilder::new();
-    builder
-      .add_remote_process_plugin()
-      .add_remote_wasm_plugin()
-      .with_default_config(|config_file| {
-        config_file.add_remote_wasm_plugin().add_remote_process_plugin();
-      })
-      .initialize();
-    builder
-  }
-
   pub fn initialize(&mut self) -> &mut Self {
-    test_helpers::run_test_cli(vec!["license"], &self.environment).unwrap(); // cause initialization
+    run_test_cli(vec!["license"], &self.environment).unwrap(); // cause initialization
     self.environment.clear_logs();
     self
   }
@@ -263,23 +234,6 @@ impl TestEnvironmentBuilder {
     self.environment.clone()
   }
 
-  pub fn add_remote_wasm_plugin(&mut self) -> &mut Self {
-    self.add_remote_wasm_plugin_at_url("https://plugins.dprint.dev/test-plugin.wasm");
-    self
-  }
-
-  pub fn add_remote_wasm_0_1_0_plugin(&mut self) -> &mut Self {
-    self
-      .environment
-      .add_remote_file("https://plugins.dprint.dev/test-plugin-0.1.0.wasm", test_helpers::WASM_PLUGIN_0_1_0_BYTES);
-    self
-  }
-
-  pub fn add_remote_wasm_plugin_at_url(&mut self, url: &str) -> &mut Self {
-    self.environment.add_remote_file(url, test_helpers::WASM_PLUGIN_BYTES);
-    self
-  }
-
   pub fn with_default_config(&mut self, func: impl FnMut(&mut TestConfigFileBuilder)) -> &mut Self {
     self.with_local_config("/dprint.json", func)
   }
@@ -330,10 +284,87 @@ impl TestEnvironmentBuilder {
     self
   }
 
+  pub fn add_remote_file_bytes(&mut self, path: &str, bytes: Vec<u8>) -> &mut Self {
+    self.environment.add_remote_file_bytes(path, bytes);
+    self
+  }
+
+  pub fn write_file_bytes(&mut self, file_path: impl AsRef<Path>, bytes: &[u8]) -> &mut Self {
+    self.environment.write_file_bytes(file_path, bytes).unwrap();
+    self
+  }
+
   pub fn set_cwd(&mut self, dir_path: &str) -> &mut Self {
     self.environment.set_cwd(dir_path);
     self
   }
+}
+
+/// Helpers for the test plugins in this repo.
+#[cfg(test)]
+impl TestEnvironmentBuilder {
+  pub fn with_remote_wasm_plugin() -> TestEnvironmentBuilder {
+    let mut builder = TestEnvironmentBuilder::new();
+    builder.add_remote_wasm_plugin();
+    builder
+  }
+
+  pub fn with_initialized_remote_wasm_plugin() -> TestEnvironmentBuilder {
+    let mut builder = TestEnvironmentBuilder::new();
+    builder
+      .add_remote_wasm_plugin()
+      .with_default_config(|config_file| {
+        config_file.add_remote_wasm_plugin();
+      })
+      .initialize();
+    builder
+  }
+
+  pub fn with_remote_process_plugin() -> TestEnvironmentBuilder {
+    let mut builder = TestEnvironmentBuilder::new();
+    builder.add_remote_process_plugin();
+    builder
+  }
+
+  pub fn with_initialized_remote_process_plugin() -> TestEnvironmentBuilder {
+    let mut builder = TestEnvironmentBuilder::new();
+    builder
+      .add_remote_process_plugin()
+      .with_default_config(|config_file| {
+        config_file.add_remote_process_plugin();
+      })
+      .initialize();
+    builder
+  }
+
+  pub fn with_initialized_remote_wasm_and_process_plugin() -> TestEnvironmentBuilder {
+    let mut builder = TestEnvironmentBuilder::new();
+    builder
+      .add_remote_process_plugin()
+      .add_remote_wasm_plugin()
+      .with_default_config(|config_file| {
+        config_file.add_remote_wasm_plugin().add_remote_process_plugin();
+      })
+      .initialize();
+    builder
+  }
+
+  pub fn add_remote_wasm_plugin(&mut self) -> &mut Self {
+    self.add_remote_wasm_plugin_at_url("https://plugins.dprint.dev/test-plugin.wasm");
+    self
+  }
+
+  pub fn add_remote_wasm_0_1_0_plugin(&mut self) -> &mut Self {
+    self
+      .environment
+      .add_remote_file("https://plugins.dprint.dev/test-plugin-0.1.0.wasm", test_helpers::WASM_PLUGIN_0_1_0_BYTES);
+    self
+  }
+
+  pub fn add_remote_wasm_plugin_at_url(&mut self, url: &str) -> &mut Self {
+    self.environment.add_remote_file(url, test_helpers::WASM_PLUGIN_BYTES);
+    self
+  }
 
   pub fn add_local_wasm_plugin(&mut self) -> &mut Self {
     self
@@ -3,7 +3,7 @@ use anyhow::Result;
 use dprint_core::async_runtime::future;
 use dprint_core::configuration::ConfigKeyMap;
 use dprint_core::plugins::CriticalFormatError;
-use dprint_core::plugins::NullCancellationToken;
+use parking_lot::Mutex;
 use std::borrow::Cow;
 use std::path::PathBuf;
 use std::rc::Rc;
@@ -12,39 +12,141 @@ use std::time::Duration;
 use std::time::Instant;
 use tokio_util::sync::CancellationToken;
 
+use crate::arg_parser::SlowFormatArgs;
+use crate::configuration::ResolvedConfig;
+use crate::crash_report::get_crash_report_written_message;
+use crate::crash_report::write_crash_report;
 use crate::environment::Environment;
+use crate::environment::FileBytes;
 use crate::incremental::IncrementalFile;
+use crate::incremental::KnownFormattedCache;
 use crate::resolution::GetPluginResult;
 use crate::resolution::InitializedPluginWithConfig;
 use crate::resolution::InitializedPluginWithConfigFormatRequest;
 use crate::resolution::PluginWithConfig;
 use crate::resolution::PluginsScope;
 use crate::resolution::PluginsScopeAndPaths;
+use crate::utils::profile_async_span;
+use crate::utils::profile_span;
+use crate::utils::record_cache_lookup;
+use crate::utils::record_file_result;
+use crate::utils::record_plugin_format;
+use crate::utils::BomPolicy;
+use crate::utils::CacheKind;
 use crate::utils::ErrorCountLogger;
+use crate::utils::FastInsecureHasher;
+use crate::utils::FileEncoding;
+use crate::utils::FileOutput;
+use crate::utils::FileResult;
+use crate::utils::OrderedOutput;
+use crate::utils::ProfileSpan;
+use crate::utils::ProgressBar;
 use crate::utils::Semaphore;
 
 struct TaskWork {
   semaphore: Rc<Semaphore>,
   plugins: Vec<Rc<PluginWithConfig>>,
-  file_paths: Vec<PathBuf>,
+  /// File paths along with their index in the dispatch order of the scope.
+  file_paths: Vec<(usize, PathBuf)>,
+}
+
+/// Caches used to skip formatting files that are known to be formatted.
+pub struct FormatCaches<TEnvironment: Environment> {
+  pub incremental_file: Option<Arc<IncrementalFile<TEnvironment>>>,
+  pub known_formatted_cache: Option<Arc<KnownFormattedCache<TEnvironment>>>,
+}
+
+impl<TEnvironment: Environment> Default for FormatCaches<TEnvironment> {
+  fn default() -> Self {
+    FormatCaches {
+      incremental_file: None,
+      known_formatted_cache: None,
+    }
+  }
+}
+
+impl<TEnvironment: Environment> Clone for FormatCaches<TEnvironment> {
+  fn clone(&self) -> Self {
+    FormatCaches {
+      incremental_file: self.incremental_file.clone(),
+      known_formatted_cache: self.known_formatted_cache.clone(),
+    }
+  }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub struct EnsureStableFormat(pub bool);
 
+/// Files that were skipped because formatting them exceeded the format timeout.
+#[derive(Default, Clone)]
+pub struct TimedOutFiles(Arc<Mutex<Vec<PathBuf>>>);
+
+impl TimedOutFiles {
+  fn add(&self, file_path: PathBuf) {
+    self.0.lock().push(file_path);
+  }
+
+  /// Outputs the "skipped (timeout)" section of the summary when any files timed out.
+  pub fn log_summary<TEnvironment: Environment>(&self, environment: &TEnvironment) {
+    let mut file_paths = self.0.lock().clone();
+    if file_paths.is_empty() {
+      return;
+    }
+    file_paths.sort();
+    let mut text = format!(
+      "Skipped (timeout) {} {}:",
+      file_paths.len(),
+      if file_paths.len() == 1 { "file" } else { "files" }
+    );
+    for file_path in &file_paths {
+      text.push_str(&format!("\n  {}", file_path.display()));
+    }
+    log_warn!(environment, "{}", text);
+  }
+}
+
+const DEFAULT_SLOW_FORMAT_WARNING_SECS: u32 = 10;
+
+/// When to warn about and cancel formatting a slow file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct SlowFormatOptions {
+  warning_after: Duration,
+  timeout: Option<Duration>,
+}
+
+impl SlowFormatOptions {
+  /// Resolves the options giving precedence to the CLI args over the configuration file.
+  fn resolve(args: SlowFormatArgs, config: Option<&ResolvedConfig>) -> Self {
+    let warning_secs = args
+      .warning_secs
+      .or_else(|| config.and_then(|c| c.slow_format_warning_secs))
+      .unwrap_or(DEFAULT_SLOW_FORMAT_WARNING_SECS);
+    let timeout_secs = args.timeout_secs.or_else(|| config.and_then(|c| c.format_timeout_secs));
+    SlowFormatOptions {
+      warning_after: Duration::from_secs(warning_secs as u64),
+      timeout: timeout_secs.map(|secs| Duration::from_secs(secs as u64)),
+    }
+  }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn run_parallelized<F, TEnvironment: Environment>(
   scope_and_paths: PluginsScopeAndPaths<TEnvironment>,
   environment: &TEnvironment,
-  incremental_file: Option<Arc<IncrementalFile<TEnvironment>>>,
+  caches: FormatCaches<TEnvironment>,
   ensure_stable_format: EnsureStableFormat,
+  slow_format_args: SlowFormatArgs,
+  timed_out_files: TimedOutFiles,
+  progress: Option<Arc<ProgressBar>>,
   f: F,
 ) -> Result<()>
 where
-  F: Fn(PathBuf, Vec<u8>, Vec<u8>, Instant, TEnvironment) -> Result<()> + 'static + Clone + Send + Sync,
+  F: Fn(PathBuf, FileBytes, Vec<u8>, Instant, TEnvironment, &mut FileOutput) -> Result<()> + 'static + Clone + Send + Sync,
 {
   if let Some(config) = &scope_and_paths.scope.config {
     log_debug!(environment, "Running for config: {}", config.resolved_path.file_path.display());
   }
+  let slow_format = SlowFormatOptions::resolve(slow_format_args, scope_and_paths.scope.config.as_deref());
 
   let max_threads = environment.max_threads();
   let number_process_plugins = scope_and_paths.scope.process_plugin_count();
@@ -53,15 +155,23 @@ where
   log_debug!(environment, "Max threads: {}\nThread count: {}", max_threads, number_threads,);
 
   let error_logger = ErrorCountLogger::from_environment(environment);
+  let ordered_output = OrderedOutput::new(environment.clone());
 
   let scope = Rc::new(scope_and_paths.scope);
   let mut file_paths_by_plugins = scope_and_paths.file_paths_by_plugins.into_vec();
   // favour giving semaphore permits to ones with more items at the start
   file_paths_by_plugins.sort_by_key(|(_, file_paths)| 0i32 - file_paths.len() as i32);
   let collection_count = file_paths_by_plugins.len();
+  let mut file_paths_by_task = dprint_core::async_runtime::spawn_blocking({
+    let environment = environment.clone();
+    let file_paths_by_task = file_paths_by_plugins.iter_mut().map(|(_, file_paths)| std::mem::take(file_paths)).collect();
+    move || get_indexed_file_paths_largest_first(&environment, file_paths_by_task)
+  })
+  .await
+  .unwrap();
   let mut semaphores = Vec::with_capacity(collection_count);
   let mut task_works = Vec::with_capacity(collection_count);
-  for (i, (plugin_names, file_paths)) in file_paths_by_plugins.into_iter().enumerate() {
+  for (i, (plugin_names, _)) in file_paths_by_plugins.into_iter().enumerate() {
     let plugins = plugin_names.names().map(|plugin_name| scope.get_plugin(plugin_name)).collect();
     let additional_thread = i < number_threads % collection_count;
     let permits = number_threads / collection_count + if additional_thread { 1 } else { 0 };
@@ -70,7 +180,7 @@ where
     task_works.push(TaskWork {
       semaphore,
       plugins,
-      file_paths,
+      file_paths: std::mem::take(&mut file_paths_by_task[i]),
     });
   }
 
@@ -87,13 +197,24 @@ where
   let handles = task_works.into_iter().enumerate().map(|(index, task_work)| {
     dprint_core::async_runtime::spawn({
       let error_logger = error_logger.clone();
+      let ordered_output = ordered_output.clone();
       let environment = environment.clone();
-      let incremental_file = incremental_file.clone();
+      let caches = caches.clone();
+      let progress = progress.clone();
+      let timed_out_files = timed_out_files.clone();
       let f = f.clone();
       let semaphores = semaphores.clone();
       let scope = scope.clone();
       async move {
         let _semaphore_permits = SemaphorePermitReleaser { index, semaphores };
+        let plugins_hash = {
+          let mut hasher = FastInsecureHasher::default();
+          for plugin in &task_work.plugins {
+            plugin.incremental_hash(&mut hasher);
+          }
+          scope.file_options_hash(&mut hasher);
+          hasher.finish()
+        };
         // resolve the plugins
         let mut plugins = Vec::with_capacity(task_work.plugins.len());
         for plugin in task_work.plugins {
@@ -114,19 +235,24 @@ where
         }
 
         let plugins = Rc::new(plugins);
+        let plugin_names = Rc::new(plugins.iter().map(|plugin| plugin.info().name.as_str()).collect::<Vec<_>>().join(", "));
         let mut format_handles = Vec::with_capacity(task_work.file_paths.len());
-        for file_path in task_work.file_paths.into_iter() {
+        for (file_index, file_path) in task_work.file_paths.into_iter() {
           let permit = match task_work.semaphore.acquire().await {
             Ok(permit) => permit,
             Err(_) => return, // semaphore was closed, so stop working
           };
           let semaphore = task_work.semaphore.clone();
           let environment = environment.clone();
-          let incremental_file = incremental_file.clone();
+          let caches = caches.clone();
           let f = f.clone();
           let plugins = plugins.clone();
           let error_logger = error_logger.clone();
+          let ordered_output = ordered_output.clone();
+          let progress = progress.clone();
+          let timed_out_files = timed_out_files.clone();
           let scope = scope.clone();
+          let plugin_names = plugin_names.clone();
           format_handles.push(dprint_core::async_runtime::spawn(async move {
             let long_format_token = CancellationToken::new();
             dprint_core::async_runtime::spawn({
@@ -134,30 +260,99 @@ where
               let environment = environment.clone();
               let file_path = file_path.clone();
               async move {
-                tokio::select! {
-                  _ = long_format_token.cancelled() => {
-                    // exit
-                  }
-                  _ = tokio::time::sleep(Duration::from_secs(10)) => {
-                    log_warn!(environment, "WARNING: Formatting is slow for {}", file_path.display());
+                let start_instant = Instant::now();
+                loop {
+                  tokio::select! {
+                    _ = long_format_token.cancelled() => {
+                      break;
+                    }
+                    _ = tokio::time::sleep(slow_format.warning_after) => {
+                      log_warn!(
+                        environment,
+                        "WARNING: Formatting is slow for {} with {} ({}s elapsed)",
+                        file_path.display(),
+                        plugin_names,
+                        start_instant.elapsed().as_secs()
+                      );
+                    }
                   }
                 }
               }
             });
-            let result = run_for_file_path(environment, incremental_file, scope, plugins, file_path.clone(), ensure_stable_format, f).await;
+            let log_level = environment.log_level();
+            let format_token = Arc::new(CancellationToken::new());
+            let file_span = profile_async_span("format", || file_path.display().to_string());
+            let mut format_future = Box::pin(run_for_file_path(
+              environment.clone(),
+              caches,
+              scope,
+              plugins,
+              plugins_hash,
+              file_path.clone(),
+              ensure_stable_format,
+              format_token.clone(),
+              &file_span,
+              f,
+            ));
+            // none when the format timed out
+            let result = match slow_format.timeout {
+              Some(timeout) => {
+                tokio::select! {
+                  result = &mut format_future => Some(result),
+                  _ = tokio::time::sleep(timeout) => {
+                    format_token.cancel();
+                    None
+                  }
+                }
+              }
+              None => Some(format_future.as_mut().await),
+            };
             long_format_token.cancel();
-            if let Err(err) = result {
-              if let Some(err) = err.downcast_ref::<CriticalFormatError>() {
-                error_logger.log_error(&format!(
-                  "Critical error formatting {}. Cannot continue. Message: {:#}",
+            let result_timed_out = result.is_none();
+            let output = match result {
+              Some(Ok(output)) => output,
+              None => {
+                let mut output = FileOutput::new(log_level);
+                log_warn!(
+                  output,
+                  "Skipped {} after exceeding the format timeout of {}s.",
                   file_path.display(),
-                  err
-                ));
-                semaphore.close(); // stop formatting
-              } else {
-                error_logger.log_error(&format!("Error formatting {}. Message: {:#}", file_path.display(), err));
+                  slow_format.timeout.unwrap().as_secs()
+                );
+                record_file_result(FileResult::TimedOut);
+                timed_out_files.add(file_path);
+                output
               }
+              Some(Err(err)) => {
+                let mut output = FileOutput::new(log_level);
+                if let Some(err) = err.downcast_ref::<CriticalFormatError>() {
+                  log_error!(output, "Critical error formatting {}. Cannot continue. Message: {:#}", file_path.display(), err);
+                  let reason = format!("Critical error formatting {}: {:#}", file_path.display(), err);
+                  match write_crash_report(&environment, &reason, None) {
+                    Ok(report_path) => log_error!(output, "{}", get_crash_report_written_message(&report_path)),
+                    Err(err) => log_warn!(output, "Failed writing crash report. {:#}", err),
+                  }
+                  semaphore.close(); // stop formatting
+                } else {
+                  log_error!(output, "Error formatting {}. Message: {:#}", file_path.display(), err);
+                }
+                record_file_result(FileResult::Error);
+                error_logger.add_error_count(1);
+                output
+              }
+            };
+            ordered_output.release(file_index, output);
+            if let Some(progress) = &progress {
+              progress.inc_position();
             }
+            if result_timed_out {
+              // the plugins may keep using the cpu until they notice the cancellation, so hold
+              // the permit while waiting for them, but abandon the plugin instances that don't
+              // stop in time so they aren't used again
+              let _ = tokio::time::timeout(slow_format.timeout.unwrap(), &mut format_future).await;
+            }
+            drop(format_future);
+            drop(file_span);
             // drop the semaphore permit when we're all done
             drop(permit);
           }));
@@ -167,6 +362,8 @@ where
     })
   });
   future::join_all(handles).await;
+  // output anything that was waiting on a file that didn't get formatted
+  ordered_output.flush();
 
   cpu_task_token.cancel();
 
@@ -178,49 +375,148 @@ where
   };
 
   #[inline]
+  #[allow(clippy::too_many_arguments)]
   async fn run_for_file_path<F, TEnvironment: Environment>(
     environment: TEnvironment,
-    incremental_file: Option<Arc<IncrementalFile<TEnvironment>>>,
+    caches: FormatCaches<TEnvironment>,
     scope: Rc<PluginsScope<TEnvironment>>,
     plugins: Rc<Vec<InitializedPluginWithConfig>>,
+    plugins_hash: u64,
     file_path: PathBuf,
     ensure_stable_format: EnsureStableFormat,
+    token: Arc<dyn dprint_core::plugins::CancellationToken>,
+    file_span: &ProfileSpan,
     f: F,
-  ) -> Result<()>
+  ) -> Result<FileOutput>
   where
-    F: Fn(PathBuf, Vec<u8>, Vec<u8>, Instant, TEnvironment) -> Result<()> + 'static + Clone + Send + Sync,
+    F: Fn(PathBuf, FileBytes, Vec<u8>, Instant, TEnvironment, &mut FileOutput) -> Result<()> + 'static + Clone + Send + Sync,
   {
+    let environment_log_level = environment.log_level();
+    let legacy_encoding = scope.config.as_ref().and_then(|config| config.legacy_encoding);
+    let ignore_file_comments = scope.config.as_ref().map(|config| config.ignore_file_comments.clone()).unwrap_or_default();
     // it's a big perf improvement to do this work on a blocking thread
-    let result = dprint_core::async_runtime::spawn_blocking(move || {
-      let file_text = environment.read_file_bytes(&file_path)?;
-
-      if let Some(incremental_file) = &incremental_file {
-        if incremental_file.is_file_known_formatted(&file_text) {
-          log_debug!(environment, "No change: {}", file_path.display());
-          return Ok::<_, anyhow::Error>(None);
+    let result = dprint_core::async_runtime::spawn_blocking({
+      let caches = caches.clone();
+      move || {
+        let file_text = {
+          let _span = profile_span("io", || format!("Read {}", file_path.display()));
+          environment.read_file_bytes_mapped(&file_path)?
+        };
+
+        if let Some(incremental_file) = &caches.incremental_file {
+          let is_known_formatted = incremental_file.is_file_known_formatted(&file_text);
+          record_cache_lookup(CacheKind::Incremental, is_known_formatted);
+          if is_known_formatted {
+            log_debug!(environment, "No change: {}", file_path.display());
+            if let Some(known_formatted_cache) = &caches.known_formatted_cache {
+              known_formatted_cache.mark_seen(&file_path);
+            }
+            record_file_result(FileResult::Cached);
+            return Ok::<_, anyhow::Error>(None);
+          }
+        }
+        if let Some(known_formatted_cache) = &caches.known_formatted_cache {
+          let is_known_formatted = known_formatted_cache.is_known_formatted(&file_path, &file_text, plugins_hash);
+          record_cache_lookup(CacheKind::CacheFile, is_known_formatted);
+          if is_known_formatted {
+            log_debug!(environment, "Known formatted: {}", file_path.display());
+            record_file_result(FileResult::Cached);
+            return Ok(None);
+          }
         }
+        // plugins are provided the text of files that aren't utf-8 as utf-8
+        let decoded_text = match FileEncoding::detect(&file_text, legacy_encoding) {
+          Some(encoding) => {
+            log_debug!(environment, "Transcoding {} from {}", file_path.display(), encoding.name());
+            Some((encoding, encoding.decode(&file_text)?))
+          }
+          None => None,
+        };
+        let text = match &decoded_text {
+          Some((_, decoded_text)) => decoded_text.as_bytes(),
+          None => &file_text,
+        };
+        if ignore_file_comments.is_file_ignored(&file_path, text) {
+          log_debug!(environment, "Ignored (dprint-ignore-file): {}", file_path.display());
+          record_file_result(FileResult::Ignored);
+          return Ok(None);
+        }
+        Ok(Some((file_path, file_text, decoded_text, environment)))
       }
-      Ok(Some((file_path, file_text, environment)))
     })
     .await
     .unwrap()?;
 
-    let Some((file_path, file_text, environment)) = result else {
-      return Ok(());
+    let Some((file_path, file_text, decoded_text, environment)) = result else {
+      return Ok(FileOutput::new(environment_log_level));
+    };
+    let original_text = match &decoded_text {
+      Some((_, decoded_text)) => decoded_text.as_bytes(),
+      None => &file_text,
+    };
+    // when configured, the cli handles the bom of utf-8 files instead of the plugins
+    let bom_policy = match &decoded_text {
+      Some(_) => None,
+      None => scope.config.as_ref().and_then(|config| config.bom),
+    };
+    let (text_to_format, had_bom) = match bom_policy {
+      Some(_) => BomPolicy::strip(original_text),
+      None => (original_text, false),
     };
 
-    let (start_instant, formatted_text) =
-      run_single_pass_for_file_path(environment.clone(), scope.clone(), plugins.clone(), file_path.clone(), &file_text).await?;
-
-    let formatted_text = if ensure_stable_format.0 && formatted_text != file_text {
-      get_stabilized_format_text(environment.clone(), scope, plugins, file_path.clone(), formatted_text).await?
+    let (start_instant, formatted_text) = run_single_pass_for_file_path(
+      environment.clone(),
+      scope.clone(),
+      plugins.clone(),
+      file_path.clone(),
+      text_to_format,
+      token.clone(),
+      file_span,
+    )
+    .await?;
+
+    let formatted_text = if ensure_stable_format.0 && formatted_text != text_to_format {
+      let _span = file_span.child(|| "Ensure stable format".to_string());
+      get_stabilized_format_text(environment.clone(), scope, plugins, file_path.clone(), formatted_text, token.clone(), file_span).await?
     } else {
       formatted_text
     };
+    if token.is_cancelled() {
+      // finished after the format timeout, which already reported the file as skipped
+      return Ok(FileOutput::new(environment_log_level));
+    }
+    let formatted_text = match bom_policy {
+      Some(bom_policy) => bom_policy.apply(formatted_text, had_bom),
+      None => formatted_text,
+    };
 
-    dprint_core::async_runtime::spawn_blocking(move || f(file_path, file_text, formatted_text, start_instant, environment)).await??;
+    record_file_result(if formatted_text == original_text {
+      FileResult::Unchanged
+    } else {
+      FileResult::Changed
+    });
 
-    Ok(())
+    // write the file back in its original encoding
+    let formatted_text = match &decoded_text {
+      Some((_, decoded_text)) if formatted_text == decoded_text.as_bytes() => file_text.to_vec(),
+      Some((encoding, _)) => encoding.encode(&String::from_utf8(formatted_text)?)?,
+      None => formatted_text,
+    };
+
+    dprint_core::async_runtime::spawn_blocking(move || {
+      // only cache text that was already formatted because the formatted
+      // text may not have undergone a stable formatting check
+      if let Some(known_formatted_cache) = &caches.known_formatted_cache {
+        if formatted_text == *file_text {
+          known_formatted_cache.add(&file_path, &file_text, plugins_hash);
+        }
+      }
+      let mut output = FileOutput::new(environment.log_level());
+      let _span = profile_span("io", || format!("Output {}", file_path.display()));
+      f(file_path, file_text, formatted_text, start_instant, environment, &mut output)?;
+      Ok::<_, anyhow::Error>(output)
+    })
+    .await?
   }
 
   async fn get_stabilized_format_text<TEnvironment: Environment>(
@@ -229,11 +525,23 @@ where
     plugins: Rc<Vec<InitializedPluginWithConfig>>,
     file_path: PathBuf,
     mut formatted_text: Vec<u8>,
+    token: Arc<dyn dprint_core::plugins::CancellationToken>,
+    file_span: &ProfileSpan,
   ) -> Result<Vec<u8>> {
     log_debug!(environment, "Ensuring stable format: {}", file_path.display());
     let mut count = 0;
     loop {
-      match run_single_pass_for_file_path(environment.clone(), scope.clone(), plugins.clone(), file_path.clone(), &formatted_text).await {
+      match run_single_pass_for_file_path(
+        environment.clone(),
+        scope.clone(),
+        plugins.clone(),
+        file_path.clone(),
+        &formatted_text,
+        token.clone(),
+        file_span,
+      )
+      .await
+      {
         Ok((_, next_pass_text)) => {
           if next_pass_text == formatted_text {
             return Ok(formatted_text);
@@ -272,6 +580,8 @@ where
     plugins: Rc<Vec<InitializedPluginWithConfig>>,
     file_path: PathBuf,
     file_text: &[u8],
+    token: Arc<dyn dprint_core::plugins::CancellationToken>,
+    file_span: &ProfileSpan,
   ) -> Result<(Instant, Vec<u8>)> {
     let start_instant = Instant::now();
     let original_text = file_text;
@@ -279,6 +589,7 @@ where
     let plugins_len = plugins.len();
     for (i, plugin) in plugins.iter().enumerate() {
       let start_instant = Instant::now();
+      let plugin_span = file_span.child(|| plugin.info().name.to_string());
       let format_text_result = plugin
         .format_text(InitializedPluginWithConfigFormatRequest {
           file_path: file_path.to_path_buf(),
@@ -286,9 +597,11 @@ where
           range: None,
           override_config: ConfigKeyMap::new(),
           on_host_format: scope.create_host_format_callback(),
-          token: Arc::new(NullCancellationToken),
+          token: token.clone(),
         })
         .await;
+      drop(plugin_span);
+      record_plugin_format(&plugin.info().name, &plugin.info().version, start_instant.elapsed());
       log_debug!(
         environment,
         "Formatted file: {} in {}ms{}",
@@ -326,6 +639,32 @@ where
   }
 }
 
+/// Orders the file paths of the scope so the largest files, which are likely the slowest
+/// to format, start first, then by path for files of the same size. Returns the file paths
+/// of each task along with their index in that order.
+///
+/// The output is released in this same order, which is deterministic for a set of files
+/// and means a large file doesn't hold back the output of the files dispatched after it.
+fn get_indexed_file_paths_largest_first<TEnvironment: Environment>(
+  environment: &TEnvironment,
+  file_paths_by_task: Vec<Vec<PathBuf>>,
+) -> Vec<Vec<(usize, PathBuf)>> {
+  let task_count = file_paths_by_task.len();
+  let mut file_paths = Vec::new();
+  for (task_index, task_file_paths) in file_paths_by_task.into_iter().enumerate() {
+    file_paths.extend(task_file_paths.into_iter().map(|file_path| {
+      let file_size = environment.file_size(&file_path).unwrap_or(0);
+      (std::cmp::Reverse(file_size), file_path, task_index)
+    }));
+  }
+  file_paths.sort_unstable();
+  let mut indexed_file_paths_by_task = vec![Vec::new(); task_count];
+  for (file_index, (_, file_path, task_index)) in file_paths.into_iter().enumerate() {
+    indexed_file_paths_by_task[task_index].push((file_index, file_path));
+  }
+  indexed_file_paths_by_task
+}
+
 fn target_cpu_decrease_bound(number_threads: usize) -> u8 {
   if number_threads < 3 {
     100 // never decrease
@@ -480,6 +819,7 @@ mod test {
   use std::rc::Rc;
 
   use super::*;
+  use crate::environment::TestEnvironment;
   use crate::utils::Semaphore;
 
   #[test]
@@ -508,6 +848,28 @@ mod test {
     }
   }
 
+  #[test]
+  fn should_index_largest_files_first() {
+    let environment = TestEnvironment::new();
+    environment.write_file("/a.txt", "1").unwrap();
+    environment.write_file("/b.txt", "123").unwrap();
+    environment.write_file("/c.txt", "12").unwrap();
+    environment.write_file("/d.txt", "123").unwrap();
+    environment.write_file("/z.txt", "123456789").unwrap();
+    let file_paths_by_task = vec![
+      vec![PathBuf::from("/z.txt"), PathBuf::from("/c.txt"), PathBuf::from("/a.txt")],
+      vec![PathBuf::from("/e.txt"), PathBuf::from("/d.txt"), PathBuf::from("/b.txt")],
+    ];
+    let file_paths_by_task = get_indexed_file_paths_largest_first(&environment, file_paths_by_task);
+    assert_eq!(
+      file_paths_by_task,
+      vec![
+        vec![(0, PathBuf::from("/z.txt")), (3, PathBuf::from("/c.txt")), (4, PathBuf::from("/a.txt"))],
+        vec![(1, PathBuf::from("/b.txt")), (2, PathBuf::from("/d.txt")), (5, PathBuf::from("/e.txt"))],
+      ]
+    );
+  }
+
   #[tokio::test]
   async fn test_throttle_cpu() {
     let semaphore1 = Rc::new(Semaphore::new(1));
@@ -1,25 +1,64 @@
+use anyhow::Result;
 use parking_lot::Mutex;
 use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashSet;
 use std::path::Path;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 
 use crate::environment::CanonicalizedPathBuf;
 use crate::environment::Environment;
 use crate::utils::get_bytes_hash;
 
+/// Number of runs to keep the cache hit statistics of.
+const MAX_RUN_STATS: usize = 10;
+
+/// The state of the plugins that the formatted files are cached for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncrementalState {
+  pub plugins_hash: u64,
+  pub plugins: Vec<IncrementalPluginState>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncrementalPluginState {
+  pub name: String,
+  pub version: String,
+  pub config_hash: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncrementalRunStats {
+  pub time_secs: u64,
+  pub hits: usize,
+  pub misses: usize,
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct IncrementalFileData {
   plugins_hash: u64,
   file_hashes: HashSet<u64>,
+  #[serde(default)]
+  plugins: Vec<IncrementalPluginState>,
+  #[serde(default)]
+  runs: Vec<IncrementalRunStats>,
+  /// Why the formatted files were last discarded.
+  #[serde(default)]
+  reset_reason: Option<String>,
 }
 
 impl IncrementalFileData {
-  pub fn new(plugins_hash: u64) -> IncrementalFileData {
+  pub fn new(state: &IncrementalState) -> IncrementalFileData {
     IncrementalFileData {
-      plugins_hash,
+      plugins_hash: state.plugins_hash,
       file_hashes: Default::default(),
+      plugins: state.plugins.clone(),
+      runs: Default::default(),
+      reset_reason: None,
     }
   }
 }
@@ -28,26 +67,35 @@ pub struct IncrementalFile<TEnvironment: Environment> {
   file_path: CanonicalizedPathBuf,
   read_data: IncrementalFileData,
   write_data: Mutex<IncrementalFileData>,
+  hits: AtomicUsize,
+  misses: AtomicUsize,
   environment: TEnvironment,
 }
 
 impl<TEnvironment: Environment> IncrementalFile<TEnvironment> {
-  pub fn new(file_path: CanonicalizedPathBuf, plugins_hash: u64, environment: TEnvironment) -> Self {
+  pub fn new(file_path: CanonicalizedPathBuf, state: IncrementalState, environment: TEnvironment) -> Self {
     let read_data = read_incremental(&file_path, &environment);
+    let mut write_data = IncrementalFileData::new(&state);
     let read_data = if let Some(read_data) = read_data {
-      if read_data.plugins_hash == plugins_hash {
+      write_data.runs = read_data.runs.clone();
+      if read_data.plugins_hash == state.plugins_hash {
+        write_data.reset_reason = read_data.reset_reason.clone();
         read_data
       } else {
-        log_debug!(environment, "Plugins changed. Creating new incremental file.");
-        IncrementalFileData::new(plugins_hash)
+        let reasons = get_stale_reasons(&read_data.plugins, &state.plugins);
+        log_debug!(environment, "Plugins changed. Creating new incremental file. {}", reasons.join(" "));
+        write_data.reset_reason = Some(reasons.join(" "));
+        IncrementalFileData::new(&state)
       }
     } else {
-      IncrementalFileData::new(plugins_hash)
+      IncrementalFileData::new(&state)
     };
     IncrementalFile {
       file_path,
       read_data,
-      write_data: Mutex::new(IncrementalFileData::new(plugins_hash)),
+      write_data: Mutex::new(write_data),
+      hits: Default::default(),
+      misses: Default::default(),
       environment,
     }
   }
@@ -56,10 +104,12 @@ impl<TEnvironment: Environment> IncrementalFile<TEnvironment> {
   pub fn is_file_known_formatted(&self, file_text: &[u8]) -> bool {
     let hash = get_bytes_hash(file_text);
     if self.read_data.file_hashes.contains(&hash) {
+      self.hits.fetch_add(1, Ordering::Relaxed);
       // the file is the same, so save it in the write data
       self.add_to_write_data(hash);
       true
     } else {
+      self.misses.fetch_add(1, Ordering::Relaxed);
       false
     }
   }
@@ -75,11 +125,89 @@ impl<TEnvironment: Environment> IncrementalFile<TEnvironment> {
   }
 
   pub fn write(&self) {
-    let write_data = self.write_data.lock();
+    let mut write_data = self.write_data.lock();
+    write_data.runs.push(IncrementalRunStats {
+      time_secs: self.environment.get_time_secs(),
+      hits: self.hits.load(Ordering::Relaxed),
+      misses: self.misses.load(Ordering::Relaxed),
+    });
+    if write_data.runs.len() > MAX_RUN_STATS {
+      let excess_count = write_data.runs.len() - MAX_RUN_STATS;
+      write_data.runs.drain(..excess_count);
+    }
     write_incremental(&self.file_path, &write_data, &self.environment);
   }
 }
 
+/// Information about an incremental file for `dprint cache incremental status`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncrementalFileStatus {
+  pub tracked_file_count: usize,
+  pub runs: Vec<IncrementalRunStats>,
+  pub reset_reason: Option<String>,
+  /// Why the formatted files will be discarded on the next run, if they will be.
+  pub stale_reasons: Vec<String>,
+}
+
+pub fn get_incremental_file_status(file_path: impl AsRef<Path>, state: &IncrementalState, environment: &impl Environment) -> Option<IncrementalFileStatus> {
+  let data = read_incremental(file_path, environment)?;
+  let stale_reasons = if data.plugins_hash == state.plugins_hash {
+    Vec::new()
+  } else {
+    get_stale_reasons(&data.plugins, &state.plugins)
+  };
+  Some(IncrementalFileStatus {
+    tracked_file_count: data.file_hashes.len(),
+    runs: data.runs,
+    reset_reason: data.reset_reason,
+    stale_reasons,
+  })
+}
+
+/// Removes the texts from the formatted files, returning how many were removed.
+pub fn invalidate_incremental_file_texts<'a>(
+  file_path: impl AsRef<Path>,
+  file_texts: impl Iterator<Item = &'a [u8]>,
+  environment: &impl Environment,
+) -> Result<usize> {
+  let Some(mut data) = read_incremental(&file_path, environment) else {
+    return Ok(0);
+  };
+  let removed_count = file_texts.filter(|file_text| data.file_hashes.remove(&get_bytes_hash(file_text))).count();
+  if removed_count > 0 {
+    let json_text = serde_json::to_string(&data)?;
+    environment.atomic_write_file_bytes(&file_path, json_text.as_bytes())?;
+  }
+  Ok(removed_count)
+}
+
+/// Gets why the formatted files of the previous plugins aren't valid for the current plugins.
+fn get_stale_reasons(previous: &[IncrementalPluginState], current: &[IncrementalPluginState]) -> Vec<String> {
+  let mut reasons = Vec::new();
+  for plugin in current {
+    match previous.iter().find(|p| p.name == plugin.name) {
+      None => reasons.push(format!("Plugin {} was added.", plugin.name)),
+      Some(previous_plugin) if previous_plugin.version != plugin.version => reasons.push(format!(
+        "Plugin {} was updated from {} to {}.",
+        plugin.name, previous_plugin.version, plugin.version
+      )),
+      Some(previous_plugin) if previous_plugin.config_hash != plugin.config_hash => {
+        reasons.push(format!("Configuration of plugin {} changed.", plugin.name));
+      }
+      Some(_) => {}
+    }
+  }
+  for plugin in previous {
+    if !current.iter().any(|p| p.name == plugin.name) {
+      reasons.push(format!("Plugin {} was removed.", plugin.name));
+    }
+  }
+  if reasons.is_empty() {
+    reasons.push("Configuration changed.".to_string());
+  }
+  reasons
+}
+
 fn read_incremental(file_path: impl AsRef<Path>, environment: &impl Environment) -> Option<IncrementalFileData> {
   let file_text = match environment.read_file(&file_path) {
     Ok(file_text) => file_text,
@@ -112,3 +240,37 @@ fn write_incremental(file_path: impl AsRef<Path>, file_data: &IncrementalFileDat
     log_warn!(environment, "Error saving incremental file {}: {}", file_path.as_ref().display(), err);
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn plugin_state(name: &str, version: &str, config_hash: u64) -> IncrementalPluginState {
+    IncrementalPluginState {
+      name: name.to_string(),
+      version: version.to_string(),
+      config_hash,
+    }
+  }
+
+  #[test]
+  fn should_get_stale_reasons() {
+    assert_eq!(
+      get_stale_reasons(
+        &[plugin_state("a", "0.1.0", 1), plugin_state("b", "0.1.0", 1), plugin_state("c", "0.1.0", 1)],
+        &[plugin_state("a", "0.2.0", 2), plugin_state("b", "0.1.0", 2), plugin_state("d", "0.1.0", 1)],
+      ),
+      vec![
+        "Plugin a was updated from 0.1.0 to 0.2.0.",
+        "Configuration of plugin b changed.",
+        "Plugin d was added.",
+        "Plugin c was removed.",
+      ]
+    );
+    // ex. the global configuration or an incremental file from an older version
+    assert_eq!(
+      get_stale_reasons(&[plugin_state("a", "0.1.0", 1)], &[plugin_state("a", "0.1.0", 1)]),
+      vec!["Configuration changed."]
+    );
+  }
+}
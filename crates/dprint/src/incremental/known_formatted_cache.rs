@@ -0,0 +1,192 @@
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::hash::Hasher;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::environment::Environment;
+use crate::utils::get_bytes_hash;
+use crate::utils::FastInsecureHasher;
+
+const CACHE_VERSION: u32 = 2;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KnownFormattedCacheData {
+  version: u32,
+  /// Hash of the known formatted content keyed by the hash of the file path.
+  entries: HashMap<u64, u64>,
+}
+
+impl Default for KnownFormattedCacheData {
+  fn default() -> Self {
+    KnownFormattedCacheData {
+      version: CACHE_VERSION,
+      entries: Default::default(),
+    }
+  }
+}
+
+/// Cache of files known to be formatted, keyed by the file path relative to
+/// the cache file along with the hash of the content and the hash of the
+/// plugins and configuration that format it.
+///
+/// Unlike the incremental file, this is stored at a user provided path and shared
+/// by every configuration file, so it can be persisted between machines (ex. CI runs).
+///
+/// Entries of files that weren't seen in a run are dropped when it's written.
+pub struct KnownFormattedCache<TEnvironment: Environment> {
+  file_path: PathBuf,
+  data: Mutex<KnownFormattedCacheData>,
+  /// Path keys of the files seen in this run.
+  seen_path_keys: Mutex<HashSet<u64>>,
+  environment: TEnvironment,
+}
+
+impl<TEnvironment: Environment> KnownFormattedCache<TEnvironment> {
+  pub fn new(file_path: PathBuf, environment: TEnvironment) -> Self {
+    let data = read_cache(&file_path, &environment).unwrap_or_default();
+    KnownFormattedCache {
+      file_path,
+      data: Mutex::new(data),
+      seen_path_keys: Default::default(),
+      environment,
+    }
+  }
+
+  /// If the file text is known to be formatted by plugins with the provided hash.
+  pub fn is_known_formatted(&self, file_path: &Path, file_text: &[u8], plugins_hash: u64) -> bool {
+    let (path_key, value) = self.get_entry(file_path, file_text, plugins_hash);
+    self.seen_path_keys.lock().insert(path_key);
+    self.data.lock().entries.get(&path_key) == Some(&value)
+  }
+
+  pub fn add(&self, file_path: &Path, file_text: &[u8], plugins_hash: u64) {
+    let (path_key, value) = self.get_entry(file_path, file_text, plugins_hash);
+    self.seen_path_keys.lock().insert(path_key);
+    self.data.lock().entries.insert(path_key, value);
+  }
+
+  /// Keeps the entry of a file that was skipped without checking this cache
+  /// (ex. it was unchanged according to the incremental file).
+  pub fn mark_seen(&self, file_path: &Path) {
+    let path_key = self.get_path_key(file_path);
+    self.seen_path_keys.lock().insert(path_key);
+  }
+
+  pub fn write(&self) {
+    let mut data = self.data.lock().clone();
+    let seen_path_keys = self.seen_path_keys.lock();
+    data.entries.retain(|path_key, _| seen_path_keys.contains(path_key));
+    write_cache(&self.file_path, &data, &self.environment);
+  }
+
+  fn get_entry(&self, file_path: &Path, file_text: &[u8], plugins_hash: u64) -> (u64, u64) {
+    let mut hasher = FastInsecureHasher::default();
+    hasher.write_u64(get_bytes_hash(file_text));
+    hasher.write_u64(plugins_hash);
+    (self.get_path_key(file_path), hasher.finish())
+  }
+
+  fn get_path_key(&self, file_path: &Path) -> u64 {
+    // relative so that the cache can be shared between machines
+    let file_path = self.file_path.parent().and_then(|dir| file_path.strip_prefix(dir).ok()).unwrap_or(file_path);
+    let mut hasher = FastInsecureHasher::default();
+    for component in file_path.components() {
+      hasher.write(component.as_os_str().as_encoded_bytes());
+      hasher.write_u8(0);
+    }
+    hasher.finish()
+  }
+}
+
+fn read_cache(file_path: &Path, environment: &impl Environment) -> Option<KnownFormattedCacheData> {
+  let file_text = match environment.read_file(file_path) {
+    Ok(file_text) => file_text,
+    Err(err) => {
+      if environment.path_exists(file_path) {
+        log_warn!(environment, "Error reading cache file {}: {}", file_path.display(), err);
+      }
+      return None;
+    }
+  };
+
+  match serde_json::from_str::<KnownFormattedCacheData>(&file_text) {
+    Ok(data) if data.version == CACHE_VERSION => Some(data),
+    Ok(_) => {
+      log_debug!(environment, "Cache file version changed. Creating new cache file.");
+      None
+    }
+    Err(err) => {
+      log_warn!(environment, "Error deserializing cache file {}: {}", file_path.display(), err);
+      None
+    }
+  }
+}
+
+fn write_cache(file_path: &Path, data: &KnownFormattedCacheData, environment: &impl Environment) {
+  let json_text = match serde_json::to_string(data) {
+    Ok(json_text) => json_text,
+    Err(err) => {
+      log_warn!(environment, "Error serializing cache file {}: {}", file_path.display(), err);
+      return;
+    }
+  };
+  if let Some(parent) = file_path.parent() {
+    let _ = environment.mk_dir_all(parent);
+  }
+  if let Err(err) = environment.atomic_write_file_bytes(file_path, json_text.as_bytes()) {
+    log_warn!(environment, "Error saving cache file {}: {}", file_path.display(), err);
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironment;
+
+  #[test]
+  fn should_persist_known_formatted_entries() {
+    let environment = TestEnvironment::new();
+    let file_path = PathBuf::from("/project/dprint-cache.json");
+    let file1 = Path::new("/project/file1.txt");
+    let file2 = Path::new("/project/sub/file2.txt");
+    let cache = KnownFormattedCache::new(file_path.clone(), environment.clone());
+    assert!(!cache.is_known_formatted(file1, b"text", 1));
+    cache.add(file1, b"text", 1);
+    cache.add(file2, b"other", 1);
+    cache.write();
+
+    let cache = KnownFormattedCache::new(file_path.clone(), environment.clone());
+    assert!(cache.is_known_formatted(file1, b"text", 1));
+    // different plugins or config
+    assert!(!cache.is_known_formatted(file1, b"text", 2));
+    assert!(!cache.is_known_formatted(file1, b"changed", 1));
+    // same text in a different file
+    assert!(!cache.is_known_formatted(Path::new("/project/file1.md"), b"text", 1));
+    assert!(!cache.is_known_formatted(Path::new("/project/sub/file1.txt"), b"text", 1));
+    cache.add(file1, b"changed", 1);
+    cache.mark_seen(file2);
+    cache.write();
+
+    // entries of files skipped in the last run are kept
+    let cache = KnownFormattedCache::new(file_path.clone(), environment.clone());
+    assert!(!cache.is_known_formatted(file1, b"text", 1));
+    assert!(cache.is_known_formatted(file1, b"changed", 1));
+    cache.write();
+
+    // while entries of files not seen in the last run are dropped
+    let cache = KnownFormattedCache::new(file_path, environment.clone());
+    assert!(cache.is_known_formatted(file1, b"changed", 1));
+    assert!(!cache.is_known_formatted(file2, b"other", 1));
+
+    // the paths are relative to the cache file
+    let cache_text = environment.read_file("/project/dprint-cache.json").unwrap();
+    environment.write_file("/moved/dprint-cache.json", &cache_text).unwrap();
+    let cache = KnownFormattedCache::new(PathBuf::from("/moved/dprint-cache.json"), environment);
+    assert!(cache.is_known_formatted(Path::new("/moved/file1.txt"), b"changed", 1));
+  }
+}
@@ -1,11 +1,20 @@
 mod incremental_file;
+mod known_formatted_cache;
 
+pub use incremental_file::get_incremental_file_status;
+pub use incremental_file::invalidate_incremental_file_texts;
 pub use incremental_file::IncrementalFile;
+pub use incremental_file::IncrementalPluginState;
+pub use incremental_file::IncrementalRunStats;
+pub use incremental_file::IncrementalState;
+pub use known_formatted_cache::KnownFormattedCache;
 
 use crate::configuration::ResolvedConfig;
+use crate::environment::CanonicalizedPathBuf;
 use crate::environment::Environment;
 use crate::resolution::PluginsScope;
 use crate::utils::get_bytes_hash;
+use crate::utils::FastInsecureHasher;
 
 pub fn get_incremental_file<TEnvironment: Environment>(
   incremental_cli_arg: Option<bool>,
@@ -19,13 +28,36 @@ pub fn get_incremental_file<TEnvironment: Environment>(
     }
   }
 
-  // the incremental file is stored in the cache with a key based on the root directory
-  let incremental_dir = environment.get_cache_dir().join_panic_relative("incremental");
-  if environment.mk_dir_all(&incremental_dir).is_err() {
+  let file_path = get_incremental_file_path(config, environment);
+  if environment.mk_dir_all(file_path.parent().unwrap()).is_err() {
     return None;
   }
 
-  let base_path = config.base_path.clone();
-  let file_path = incremental_dir.join_panic_relative(get_bytes_hash(base_path.to_string_lossy().as_bytes()).to_string());
-  Some(IncrementalFile::new(file_path, scope.plugins_hash(), environment.clone()))
+  Some(IncrementalFile::new(file_path, get_incremental_state(scope), environment.clone()))
+}
+
+/// Gets the path of the incremental file, which is stored in the cache
+/// with a key based on the root directory.
+pub fn get_incremental_file_path(config: &ResolvedConfig, environment: &impl Environment) -> CanonicalizedPathBuf {
+  let incremental_dir = environment.get_cache_dir().join_panic_relative("incremental");
+  incremental_dir.join_panic_relative(get_bytes_hash(config.base_path.to_string_lossy().as_bytes()).to_string())
+}
+
+pub fn get_incremental_state<TEnvironment: Environment>(scope: &PluginsScope<TEnvironment>) -> IncrementalState {
+  IncrementalState {
+    plugins_hash: scope.plugins_hash(),
+    plugins: scope
+      .plugins
+      .values()
+      .map(|plugin| {
+        let mut hasher = FastInsecureHasher::default();
+        plugin.incremental_hash(&mut hasher);
+        IncrementalPluginState {
+          name: plugin.info().name.clone(),
+          version: plugin.info().version.clone(),
+          config_hash: hasher.finish(),
+        }
+      })
+      .collect(),
+  }
 }
@@ -0,0 +1,111 @@
+#![deny(clippy::print_stderr)]
+#![deny(clippy::print_stdout)]
+#![deny(clippy::unused_async)]
+
+#[macro_use]
+mod environment;
+
+use std::rc::Rc;
+
+use arg_parser::CliArgs;
+use arg_parser::SubCommand;
+use utils::RealStdInReader;
+
+mod arg_parser;
+mod commands;
+mod configuration;
+mod crash_report;
+pub mod engine;
+mod format;
+mod incremental;
+mod paths;
+mod patterns;
+mod plugins;
+mod resolution;
+mod run_cli;
+mod utils;
+
+#[cfg(any(test, feature = "test-harness"))]
+pub mod test_harness;
+#[cfg(test)]
+mod test_helpers;
+
+pub use crash_report::setup_crash_report_panic_hook;
+pub use environment::Environment;
+pub use environment::OverlayEnvironment;
+pub use environment::ProcessPluginTrustPolicy;
+pub use environment::RealEnvironment;
+pub use environment::RealEnvironmentOptions;
+pub use run_cli::AppError;
+pub use utils::LogLevel;
+
+/// Runs the CLI with the provided arguments, where the first argument is the executable.
+pub async fn run(mut args: Vec<String>) -> Result<(), (AppError, LogLevel)> {
+  // the environment variable is provided as the flag so it's also sent to the daemon
+  if is_no_network_env_var_set() && !args.iter().any(|arg| arg == "--no-network") {
+    args.insert(1.min(args.len()), "--no-network".to_string());
+  }
+  if is_trust_plugins_env_var_set() && !args.iter().any(|arg| arg == "--yes" || arg == "-y") {
+    args.insert(1.min(args.len()), "--yes".to_string());
+  }
+  let raw_args = args.clone();
+  let args = arg_parser::parse_args(args, RealStdInReader).map_err(|err| (err.into(), LogLevel::Info))?;
+
+  let environment = RealEnvironment::new(RealEnvironmentOptions {
+    log_level: args.log_level,
+    is_stdout_machine_readable: args.is_stdout_machine_readable(),
+    offline: args.no_network,
+    process_plugin_trust: get_process_plugin_trust_policy(&args),
+  })
+  .map_err(|err| (err.into(), args.log_level))?;
+  crash_report::init_crash_report(&environment, &raw_args);
+  if let Some(daemon_args) = args.sub_command.daemon() {
+    match commands::run_in_daemon(raw_args, &args, daemon_args, &environment) {
+      Ok(Some(0)) => return Ok(()),
+      Ok(Some(exit_code)) => {
+        // the daemon already output the error
+        let err = AppError {
+          inner: anyhow::anyhow!(""),
+          exit_code,
+        };
+        return Err((err, args.log_level));
+      }
+      Ok(None) => {} // run in this process
+      Err(err) => return Err((err.into(), args.log_level)),
+    }
+  }
+
+  let plugin_cache = plugins::PluginCache::new(environment.clone());
+  let plugin_resolver = Rc::new(plugins::PluginResolver::new(environment.clone(), plugin_cache));
+
+  let result = run_cli::run_cli(&args, &environment, &plugin_resolver).await;
+  plugin_resolver.clear_and_shutdown_initialized().await;
+  result.map_err(|err| (err.into(), args.log_level))
+}
+
+fn is_no_network_env_var_set() -> bool {
+  match std::env::var_os("DPRINT_NO_NETWORK") {
+    Some(value) => matches!(value.to_string_lossy().as_ref(), "true" | "1"),
+    None => false,
+  }
+}
+
+fn is_trust_plugins_env_var_set() -> bool {
+  match std::env::var_os("DPRINT_TRUST_PLUGINS") {
+    Some(value) => matches!(value.to_string_lossy().as_ref(), "true" | "1"),
+    None => false,
+  }
+}
+
+fn get_process_plugin_trust_policy(args: &CliArgs) -> ProcessPluginTrustPolicy {
+  if args.trust_process_plugins {
+    return ProcessPluginTrustPolicy::Trust;
+  }
+  match args.sub_command {
+    // these are run by editors or use stdin, so the user can't be prompted
+    SubCommand::StdInFmt(_) | SubCommand::EditorInfo | SubCommand::EditorService(_) | SubCommand::Lsp(_) | SubCommand::Daemon(_) => {
+      ProcessPluginTrustPolicy::Deny
+    }
+    _ => ProcessPluginTrustPolicy::Prompt,
+  }
+}
@@ -1,39 +1,18 @@
 #![deny(clippy::print_stderr)]
 #![deny(clippy::print_stdout)]
-#![deny(clippy::unused_async)]
 
-#[macro_use]
-mod environment;
-
-use anyhow::Result;
+use dprint::setup_crash_report_panic_hook;
+use dprint::LogLevel;
 use dprint_core::plugins::process::setup_exit_process_panic_hook;
-use environment::RealEnvironment;
-use environment::RealEnvironmentOptions;
-use run_cli::AppError;
-use std::rc::Rc;
-use utils::LogLevel;
-use utils::RealStdInReader;
-
-mod arg_parser;
-mod commands;
-mod configuration;
-mod format;
-mod incremental;
-mod paths;
-mod patterns;
-mod plugins;
-mod resolution;
-mod run_cli;
-mod utils;
-
-#[cfg(test)]
-mod test_helpers;
 
 fn main() {
+  // this must be before the exit process hook so it runs before exiting
+  setup_crash_report_panic_hook();
   setup_exit_process_panic_hook();
-  let rt = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+  // io is enabled for the lsp's tcp and pipe transports
+  let rt = tokio::runtime::Builder::new_current_thread().enable_time().enable_io().build().unwrap();
   rt.block_on(async move {
-    match run().await {
+    match dprint::run(std::env::args().collect()).await {
       Ok(_) => {}
       Err((err, log_level)) => {
         if log_level != LogLevel::Silent {
@@ -48,19 +27,3 @@ fn main() {
     }
   });
 }
-
-async fn run() -> Result<(), (AppError, LogLevel)> {
-  let args = arg_parser::parse_args(std::env::args().collect(), RealStdInReader).map_err(|err| (err.into(), LogLevel::Info))?;
-
-  let environment = RealEnvironment::new(RealEnvironmentOptions {
-    log_level: args.log_level,
-    is_stdout_machine_readable: args.is_stdout_machine_readable(),
-  })
-  .map_err(|err| (err.into(), args.log_level))?;
-  let plugin_cache = plugins::PluginCache::new(environment.clone());
-  let plugin_resolver = Rc::new(plugins::PluginResolver::new(environment.clone(), plugin_cache));
-
-  let result = run_cli::run_cli(&args, &environment, &plugin_resolver).await;
-  plugin_resolver.clear_and_shutdown_initialized().await;
-  result.map_err(|err| (err.into(), args.log_level))
-}
@@ -94,7 +94,7 @@ pub async fn get_and_resolve_file_paths<'a>(
   let mut file_patterns = get_all_file_patterns(config, args, &cwd);
 
   if args.only_staged {
-    let staged_files = environment.get_staged_files().context("Failed running git staged.")?;
+    let staged_files = environment.get_staged_files(cwd.as_ref()).context("Failed running git staged.")?;
     file_patterns.arg_includes = Some(GlobPattern::new_vec(
       staged_files.into_iter().map(|path| path.to_string_lossy().into_owned()).collect(),
       cwd.clone(),
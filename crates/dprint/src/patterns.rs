@@ -12,6 +12,7 @@ use crate::utils::ExcludeMatchDetail;
 use crate::utils::GitIgnoreTree;
 use crate::utils::GlobMatcher;
 use crate::utils::GlobMatcherOptions;
+use crate::utils::GlobMatcherSet;
 use crate::utils::GlobMatchesDetail;
 use crate::utils::GlobPattern;
 use crate::utils::GlobPatterns;
@@ -88,19 +89,16 @@ impl<TEnvironment: Environment> FileMatcher<TEnvironment> {
   }
 }
 
-pub fn get_patterns_as_glob_matcher(patterns: &[String], config_base_path: &CanonicalizedPathBuf) -> Result<GlobMatcher> {
-  let patterns = process_config_patterns(patterns);
-  let (includes, excludes) = patterns.into_iter().partition(|p| !is_negated_glob(p));
-  GlobMatcher::new(
-    GlobPatterns {
-      arg_includes: None,
-      config_includes: Some(GlobPattern::new_vec(includes, config_base_path.clone())),
-      arg_excludes: None,
-      config_excludes: excludes
-        .into_iter()
-        .map(|relative_pattern| GlobPattern::new(relative_pattern, config_base_path.clone()).invert())
-        .collect(),
-    },
+/// Compiles each list of patterns into a single matcher so that a path
+/// is matched against all the lists at once.
+pub fn get_pattern_lists_as_glob_matcher_set<'a>(
+  pattern_lists: impl Iterator<Item = &'a [String]>,
+  config_base_path: &CanonicalizedPathBuf,
+) -> Result<GlobMatcherSet> {
+  GlobMatcherSet::new(
+    pattern_lists
+      .map(|patterns| get_patterns_as_glob_patterns(patterns, config_base_path))
+      .collect(),
     &GlobMatcherOptions {
       case_sensitive: !cfg!(windows),
       base_dir: config_base_path.clone(),
@@ -108,6 +106,20 @@ pub fn get_patterns_as_glob_matcher(patterns: &[String], config_base_path: &Cano
   )
 }
 
+fn get_patterns_as_glob_patterns(patterns: &[String], config_base_path: &CanonicalizedPathBuf) -> GlobPatterns {
+  let patterns = process_config_patterns(patterns);
+  let (includes, excludes) = patterns.into_iter().partition(|p| !is_negated_glob(p));
+  GlobPatterns {
+    arg_includes: None,
+    config_includes: Some(GlobPattern::new_vec(includes, config_base_path.clone())),
+    arg_excludes: None,
+    config_excludes: excludes
+      .into_iter()
+      .map(|relative_pattern| GlobPattern::new(relative_pattern, config_base_path.clone()).invert())
+      .collect(),
+  }
+}
+
 pub fn get_all_file_patterns(config: &ResolvedConfig, args: &FilePatternArgs, cwd: &CanonicalizedPathBuf) -> GlobPatterns {
   GlobPatterns {
     config_includes: get_config_includes_file_patterns(config, args, cwd),
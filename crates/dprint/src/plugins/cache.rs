@@ -1,13 +1,13 @@
 use anyhow::bail;
 use anyhow::Result;
-use dprint_core::async_runtime::FutureExt;
-use dprint_core::async_runtime::LocalBoxFuture;
+use once_cell::sync::OnceCell;
 use parking_lot::RwLock;
 use std::path::PathBuf;
 
 use dprint_core::plugins::PluginInfo;
 
 use super::cache_fs_locks::CacheFsLockPool;
+use super::ensure_process_plugin_trusted;
 use super::implementations::cleanup_plugin;
 use super::implementations::get_file_path_from_plugin_info;
 use super::implementations::setup_plugin;
@@ -17,9 +17,11 @@ use super::PluginCacheManifest;
 use super::PluginCacheManifestItem;
 use crate::environment::Environment;
 use crate::plugins::PluginSourceReference;
+use crate::utils::fetch_file_or_url_bytes;
+use crate::utils::fetch_file_or_url_bytes_with_checksum;
 use crate::utils::get_bytes_hash;
 use crate::utils::get_sha256_checksum;
-use crate::utils::verify_sha256_checksum;
+use crate::utils::ChecksumMismatchError;
 use crate::utils::PathSource;
 use crate::utils::PluginKind;
 
@@ -46,6 +48,13 @@ where
     }
   }
 
+  /// Deletes the cache directory and reloads the now empty manifest.
+  pub fn clear(&self) -> Result<()> {
+    self.environment.remove_dir_all(self.environment.get_cache_dir())?;
+    self.manifest.reload_from_disk();
+    Ok(())
+  }
+
   pub async fn forget_and_recreate(&self, source_reference: &PluginSourceReference) -> Result<PluginCacheItem> {
     let _setup_guard = self.fs_locks.lock(&source_reference.path_source).await;
     self.forget(source_reference).await?;
@@ -67,10 +76,10 @@ where
 
   pub async fn get_plugin_cache_item(&self, source_reference: &PluginSourceReference) -> Result<PluginCacheItem> {
     match &source_reference.path_source {
-      PathSource::Remote(_) => self.get_plugin(source_reference, false, download_url).await,
+      PathSource::Remote(_) => self.get_plugin(source_reference, false).await,
       PathSource::Local(_) => {
         if let Some(manifest_item) = self.manifest.get(&source_reference.path_source)? {
-          let file_bytes = get_file_bytes(source_reference.path_source.clone(), self.environment.clone()).await?;
+          let file_bytes = fetch_file_or_url_bytes(&source_reference.path_source, &self.environment).await?;
           let file_hash = get_bytes_hash(&file_bytes);
           let cache_file_hash = match &manifest_item.file_hash {
             Some(file_hash) => *file_hash,
@@ -87,17 +96,12 @@ where
           }
         }
 
-        self.get_plugin(source_reference, true, get_file_bytes).await
+        self.get_plugin(source_reference, true).await
       }
     }
   }
 
-  async fn get_plugin(
-    &self,
-    source_reference: &PluginSourceReference,
-    include_file_hash: bool,
-    read_bytes: impl Fn(PathSource, TEnvironment) -> LocalBoxFuture<'static, Result<Vec<u8>>>,
-  ) -> Result<PluginCacheItem> {
+  async fn get_plugin(&self, source_reference: &PluginSourceReference, include_file_hash: bool) -> Result<PluginCacheItem> {
     if let Some(item) = self.get_plugin_cache_item_from_cache(&source_reference.path_source)? {
       return Ok(item);
     }
@@ -111,27 +115,35 @@ where
       return Ok(item);
     }
 
-    // get bytes
-    let file_bytes = read_bytes(source_reference.path_source.clone(), self.environment.clone()).await?;
+    // get bytes, checking the checksum only if provided (not required for Wasm plugins)
+    let path_source = &source_reference.path_source;
+    let file_bytes = match &source_reference.checksum {
+      Some(checksum) => match fetch_file_or_url_bytes_with_checksum(path_source, checksum, &self.environment).await {
+        Ok(file_bytes) => file_bytes,
+        Err(err) => match err.downcast_ref::<ChecksumMismatchError>() {
+          Some(err) => bail!(
+            "Invalid checksum specified in configuration file. Check the plugin's release notes for what the expected checksum is.\n\n{:#}",
+            err
+          ),
+          None => return Err(err),
+        },
+      },
+      None => fetch_file_or_url_bytes(path_source, &self.environment).await?,
+    };
 
-    // check checksum only if provided (not required for Wasm plugins)
-    if let Some(checksum) = &source_reference.checksum {
-      if let Err(err) = verify_sha256_checksum(&file_bytes, checksum) {
+    if source_reference.plugin_kind() != Some(PluginKind::Wasm) {
+      let Some(checksum) = &source_reference.checksum else {
         bail!(
-          "Invalid checksum specified in configuration file. Check the plugin's release notes for what the expected checksum is.\n\n{:#}",
-          err
+          concat!(
+            "The plugin must have a checksum specified for security reasons ",
+            "since it is not a Wasm plugin. Check the plugin's release notes for what ",
+            "the checksum is or if you trust the source, you may specify: {}@{}"
+          ),
+          source_reference.path_source.display(),
+          get_sha256_checksum(&file_bytes),
         );
-      }
-    } else if source_reference.plugin_kind() != Some(PluginKind::Wasm) {
-      bail!(
-        concat!(
-          "The plugin must have a checksum specified for security reasons ",
-          "since it is not a Wasm plugin. Check the plugin's release notes for what ",
-          "the checksum is or if you trust the source, you may specify: {}@{}"
-        ),
-        source_reference.path_source.display(),
-        get_sha256_checksum(&file_bytes),
-      );
+      };
+      ensure_process_plugin_trusted(source_reference, checksum, &self.environment)?;
     }
 
     let file_hash = if include_file_hash { Some(get_bytes_hash(&file_bytes)) } else { None };
@@ -164,22 +176,25 @@ where
 
 struct ConcurrentPluginCacheManifest<TEnvironment: Environment> {
   environment: TEnvironment,
-  manifest: RwLock<PluginCacheManifest>,
+  /// Lazily read so commands that never resolve plugins don't read it.
+  manifest: OnceCell<RwLock<PluginCacheManifest>>,
 }
 
 impl<TEnvironment: Environment> ConcurrentPluginCacheManifest<TEnvironment> {
   pub fn new(environment: TEnvironment) -> Self {
-    let manifest = RwLock::new(read_manifest(&environment));
-    Self { environment, manifest }
+    Self {
+      environment,
+      manifest: OnceCell::new(),
+    }
   }
 
   pub fn get(&self, path_source: &PathSource) -> Result<Option<PluginCacheManifestItem>> {
     let cache_key = self.get_cache_key(path_source)?;
-    Ok(self.manifest.read().get_item(&cache_key).map(|x| x.to_owned()))
+    Ok(self.manifest().read().get_item(&cache_key).map(|x| x.to_owned()))
   }
 
   pub fn add(&self, path_source: &PathSource, cache_item: PluginCacheManifestItem) -> Result<()> {
-    let mut manifest = self.manifest.write();
+    let mut manifest = self.manifest().write();
     manifest.add_item(self.get_cache_key(path_source)?, cache_item);
     write_manifest(&manifest, &self.environment)?;
     Ok(())
@@ -187,20 +202,28 @@ impl<TEnvironment: Environment> ConcurrentPluginCacheManifest<TEnvironment> {
 
   pub fn remove(&self, path_source: &PathSource) -> Result<Option<PluginCacheManifestItem>> {
     let cache_key = self.get_cache_key(path_source)?;
-    let mut manifest = self.manifest.write();
+    let mut manifest = self.manifest().write();
     let cache_item = manifest.remove_item(&cache_key);
     write_manifest(&manifest, &self.environment)?;
     Ok(cache_item)
   }
 
   pub fn reload_from_disk(&self) {
+    let Some(manifest) = self.manifest.get() else {
+      // not read yet, so it will be read on first use
+      return;
+    };
     // ensure the lock is held while reading from the file system
     // in order to prevent another thread writing to the file system
     // at the same time
-    let mut manifest = self.manifest.write();
+    let mut manifest = manifest.write();
     *manifest = read_manifest(&self.environment);
   }
 
+  fn manifest(&self) -> &RwLock<PluginCacheManifest> {
+    self.manifest.get_or_init(|| RwLock::new(read_manifest(&self.environment)))
+  }
+
   fn get_cache_key(&self, path_source: &PathSource) -> Result<String> {
     Ok(match path_source {
       PathSource::Remote(remote_source) => format!("remote:{}", remote_source.url.as_str()),
@@ -212,14 +235,6 @@ impl<TEnvironment: Environment> ConcurrentPluginCacheManifest<TEnvironment> {
   }
 }
 
-fn download_url<TEnvironment: Environment>(path_source: PathSource, environment: TEnvironment) -> LocalBoxFuture<'static, Result<Vec<u8>>> {
-  async move { environment.download_file_err_404(path_source.unwrap_remote().url.as_str()).await }.boxed_local()
-}
-
-fn get_file_bytes<TEnvironment: Environment>(path_source: PathSource, environment: TEnvironment) -> LocalBoxFuture<'static, Result<Vec<u8>>> {
-  async move { environment.read_file_bytes(path_source.unwrap_local().path) }.boxed_local()
-}
-
 #[cfg(test)]
 mod test {
   use super::*;
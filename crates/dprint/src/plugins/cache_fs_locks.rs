@@ -8,21 +8,21 @@ use crate::utils::get_bytes_hash;
 use crate::utils::LaxSingleProcessFsFlag;
 use crate::utils::PathSource;
 
-struct CacheFsLockGuardInner<TEnvironment: Environment> {
+struct CacheFsLockGuardInner {
   id: u64,
   locks: Rc<RefCell<HashSet<u64>>>,
   // keep this alive for the duration of the guard
-  _fs_flag: LaxSingleProcessFsFlag<TEnvironment>,
+  _fs_flag: LaxSingleProcessFsFlag,
 }
 
-impl<TEnvironment: Environment> Drop for CacheFsLockGuardInner<TEnvironment> {
+impl Drop for CacheFsLockGuardInner {
   fn drop(&mut self) {
     // allow this process to set the lock again
     self.locks.borrow_mut().remove(&self.id);
   }
 }
 
-pub struct CacheFsLockGuard<TEnvironment: Environment>(Option<CacheFsLockGuardInner<TEnvironment>>);
+pub struct CacheFsLockGuard(#[cfg_attr(not(test), allow(dead_code))] Option<CacheFsLockGuardInner>);
 
 /// Re-entrant LaxSingleProcessFsFlag at a path source. This attempts to
 /// prevent multiple processes from modifying the cache at the same time.
@@ -50,7 +50,7 @@ impl<TEnvironment: Environment> CacheFsLockPool<TEnvironment> {
   /// Objects a file system lock for the provided path source. Locks are re-entrant
   /// for the current process, but WARNING that they currently don't handle the guards
   /// being dropped out of order. For the current code consuming this, it's ok.
-  pub async fn lock(&self, path_source: &PathSource) -> CacheFsLockGuard<TEnvironment> {
+  pub async fn lock(&self, path_source: &PathSource) -> CacheFsLockGuard {
     let id = get_bytes_hash(path_source.display().as_bytes());
     // ensure this process only sets the lock once for this id
     if self.locks.borrow_mut().insert(id) {
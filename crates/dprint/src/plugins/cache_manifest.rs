@@ -191,6 +191,7 @@ mod test {
           help_url: "help url".to_string(),
           config_schema_url: "schema url".to_string(),
           update_url: None,
+          global_options: None,
         },
       },
     );
@@ -206,6 +207,7 @@ mod test {
           help_url: "help url 2".to_string(),
           config_schema_url: "schema url 2".to_string(),
           update_url: None,
+          global_options: None,
         },
       },
     );
@@ -221,6 +223,7 @@ mod test {
           help_url: "cargo help url".to_string(),
           config_schema_url: "cargo schema url".to_string(),
           update_url: Some("cargo update url".to_string()),
+          global_options: None,
         },
       },
     );
@@ -323,6 +326,7 @@ mod test {
           help_url: "help url".to_string(),
           config_schema_url: "schema url".to_string(),
           update_url: Some("update url".to_string()),
+          global_options: None,
         },
       },
     );
@@ -338,6 +342,7 @@ mod test {
           help_url: "help url 2".to_string(),
           config_schema_url: "schema url 2".to_string(),
           update_url: None,
+          global_options: None,
         },
       },
     );
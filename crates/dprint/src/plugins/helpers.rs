@@ -1,6 +1,8 @@
 use std::sync::Arc;
 
 use anyhow::Result;
+use dprint_core::configuration::ConfigurationDiagnostic;
+use dprint_core::configuration::ConfigurationDiagnosticSeverity;
 use thiserror::Error;
 
 use super::FormatConfig;
@@ -23,8 +25,11 @@ pub async fn output_plugin_config_diagnostics<TEnvironment: Environment>(
   let mut diagnostic_count = 0;
 
   for diagnostic in plugin.config_diagnostics(format_config).await? {
-    log_warn!(environment, "[{}]: {}", plugin_name, diagnostic);
-    diagnostic_count += 1;
+    log_warn!(environment, "[{}]: {}", plugin_name, display_config_diagnostic(&diagnostic));
+    // only errors prevent the plugin from being used
+    if diagnostic.severity.is_error() {
+      diagnostic_count += 1;
+    }
   }
 
   if diagnostic_count > 0 {
@@ -36,3 +41,50 @@ pub async fn output_plugin_config_diagnostics<TEnvironment: Environment>(
     Ok(Ok(()))
   }
 }
+
+/// Gets the text to display to the user for a configuration diagnostic.
+pub fn display_config_diagnostic(diagnostic: &ConfigurationDiagnostic) -> String {
+  match diagnostic.severity {
+    ConfigurationDiagnosticSeverity::Error => diagnostic.to_string(),
+    ConfigurationDiagnosticSeverity::Warning => format!("Warning: {}", diagnostic),
+    ConfigurationDiagnosticSeverity::Deprecation => format!("Deprecated: {}", diagnostic),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use dprint_core::plugins::FormatConfigId;
+
+  use super::*;
+  use crate::environment::TestEnvironment;
+  use crate::plugins::Plugin;
+  use crate::plugins::TestPlugin;
+
+  #[test]
+  fn should_only_count_error_config_diagnostics() {
+    let environment = TestEnvironment::new();
+    let plugin = TestPlugin::new("test-plugin", "test", vec!["txt"], vec![]).with_config_diagnostics(vec![
+      ConfigurationDiagnostic::deprecation("oldProp", "The configuration key was renamed to 'newProp'"),
+      ConfigurationDiagnostic::warning("prop", "Consider something else."),
+    ]);
+    let format_config = Arc::new(FormatConfig {
+      id: FormatConfigId::from_raw(1),
+      plugin: Default::default(),
+      global: Default::default(),
+    });
+    environment.clone().run_in_runtime(async move {
+      let instance = plugin.initialize().await.unwrap();
+      let result = output_plugin_config_diagnostics("test-plugin", &*instance, format_config.clone(), &environment)
+        .await
+        .unwrap();
+      assert!(result.is_ok());
+      assert_eq!(
+        environment.take_stderr_messages(),
+        vec![
+          "[test-plugin]: Deprecated: The configuration key was renamed to 'newProp' (oldProp)",
+          "[test-plugin]: Warning: Consider something else. (prop)",
+        ]
+      );
+    });
+  }
+}
@@ -100,6 +100,7 @@ impl<TEnvironment: Environment> InitializedProcessPluginCommunicator<TEnvironmen
         override_config: request.override_config,
         on_host_format: request.on_host_format,
         token: request.token,
+        deadline: None,
       })
       .await
     {
@@ -139,13 +140,16 @@ async fn create_new_communicator<TEnvironment: Environment>(restart_info: &Proce
   // ensure it's initialized each time
   let plugin_name = restart_info.plugin_name.to_string();
   let environment = restart_info.environment.clone();
+  let max_in_flight_formats = environment.max_threads();
   let communicator = ProcessPluginCommunicator::new(&restart_info.executable_file_path, move |error_message| {
     // consider messages from process plugins as warnings
     if environment.log_level().is_warn() {
       environment.log_stderr_with_context(&error_message, &plugin_name);
     }
   })
-  .await?;
+  .await?
+  // don't flood slow plugins with more requests than there are threads to format with
+  .with_max_in_flight_formats(max_in_flight_formats);
   Ok(communicator)
 }
 
@@ -154,10 +158,13 @@ mod test {
   use std::rc::Rc;
   use std::sync::Arc;
   use std::time::Duration;
+  use std::time::Instant;
 
   use dprint_core::async_runtime::future;
   use dprint_core::async_runtime::FutureExt;
   use dprint_core::configuration::ConfigKeyMap;
+  use dprint_core::plugins::process::HostFormatCallback;
+  use dprint_core::plugins::process::RequestTimeoutError;
   use dprint_core::plugins::NullCancellationToken;
   use tokio_util::sync::CancellationToken;
 
@@ -303,4 +310,120 @@ mod test {
       }
     })
   }
+
+  #[test]
+  fn should_handle_deadline() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_process_plugin().build();
+    environment.run_in_runtime({
+      let environment = environment.clone();
+      async move {
+        let communicator = InitializedProcessPluginCommunicator::new_test_plugin_communicator(environment.clone()).await;
+        let format_config = FormatConfig {
+          id: FormatConfigId::from_raw(1),
+          plugin: Default::default(),
+          global: Default::default(),
+        };
+        let inner = communicator.get_inner_ensure_config(&format_config).await.unwrap();
+
+        // start up a format that will wait for cancellation, which the deadline should cause
+        let result = inner
+          .format_text(ProcessPluginCommunicatorFormatRequest {
+            file_path: PathBuf::from("test.txt"),
+            // special text that makes it wait for cancellation
+            file_bytes: "wait_cancellation".to_string().into_bytes(),
+            range: None,
+            config_id: format_config.id,
+            override_config: Default::default(),
+            on_host_format: Rc::new(|_| future::ready(Ok(None)).boxed_local()),
+            token: Arc::new(NullCancellationToken),
+            deadline: Some(std::time::Instant::now() + Duration::from_millis(100)),
+          })
+          .await;
+
+        let err = result.err().unwrap();
+        assert!(err.is::<RequestTimeoutError>(), "{:#}", err);
+
+        // the plugin should still be usable afterwards
+        assert!(inner.is_process_alive().await);
+
+        communicator.shutdown().await;
+      }
+    })
+  }
+
+  #[test]
+  fn should_queue_formats_beyond_max_in_flight() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_process_plugin().build();
+    environment.set_max_threads(1);
+    environment.run_in_runtime({
+      let environment = environment.clone();
+      async move {
+        let communicator = InitializedProcessPluginCommunicator::new_test_plugin_communicator(environment.clone()).await;
+        let format_config = FormatConfig {
+          id: FormatConfigId::from_raw(1),
+          plugin: {
+            let mut config = ConfigKeyMap::new();
+            config.insert("ending".to_string(), "custom".to_string().into());
+            config
+          },
+          global: Default::default(),
+        };
+        let inner = communicator.get_inner_ensure_config(&format_config).await.unwrap();
+        let create_request = |text: &str, on_host_format: HostFormatCallback, deadline: Option<Instant>| ProcessPluginCommunicatorFormatRequest {
+          file_path: PathBuf::from("test.txt"),
+          file_bytes: text.to_string().into_bytes(),
+          range: None,
+          config_id: format_config.id,
+          override_config: Default::default(),
+          on_host_format,
+          token: Arc::new(NullCancellationToken),
+          deadline,
+        };
+        let no_host_format: HostFormatCallback = Rc::new(|_| future::ready(Ok(None)).boxed_local());
+
+        // the second request should wait for the first to time out before being sent
+        let start = Instant::now();
+        let (first, second) = future::join(
+          inner.format_text(create_request(
+            "wait_cancellation",
+            no_host_format.clone(),
+            Some(start + Duration::from_millis(200)),
+          )),
+          inner.format_text(create_request("text", no_host_format.clone(), None)),
+        )
+        .await;
+        assert!(first.err().unwrap().is::<RequestTimeoutError>());
+        assert_eq!(String::from_utf8(second.unwrap().unwrap()).unwrap(), "text_custom");
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        // host formatting with the same plugin should not deadlock
+        let on_host_format: HostFormatCallback = Rc::new({
+          let inner = inner.clone();
+          let config_id = format_config.id;
+          move |request| {
+            let inner = inner.clone();
+            async move {
+              inner
+                .format_text(ProcessPluginCommunicatorFormatRequest {
+                  file_path: request.file_path,
+                  file_bytes: request.file_bytes,
+                  range: request.range,
+                  config_id,
+                  override_config: request.override_config,
+                  on_host_format: Rc::new(|_| future::ready(Ok(None)).boxed_local()),
+                  token: request.token,
+                  deadline: None,
+                })
+                .await
+            }
+            .boxed_local()
+          }
+        });
+        let result = inner.format_text(create_request("plugin: text", on_host_format, None)).await;
+        assert_eq!(String::from_utf8(result.unwrap().unwrap()).unwrap(), "plugin: text_custom_custom");
+
+        communicator.shutdown().await;
+      }
+    })
+  }
 }
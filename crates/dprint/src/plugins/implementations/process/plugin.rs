@@ -147,4 +147,8 @@ impl<TEnvironment: Environment> InitializedPlugin for InitializedProcessPlugin<T
   async fn shutdown(&self) -> () {
     self.communicator.shutdown().await
   }
+
+  fn release_memory(&self) {
+    // the memory is held by the plugin's process
+  }
 }
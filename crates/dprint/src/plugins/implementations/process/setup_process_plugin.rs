@@ -11,10 +11,11 @@ use std::str;
 
 use crate::environment::Environment;
 use crate::plugins::implementations::SetupPluginResult;
-use crate::utils::extract_zip;
-use crate::utils::fetch_file_or_url_bytes;
+use crate::utils::extract_archive;
+use crate::utils::fetch_file_or_url_bytes_with_checksum;
 use crate::utils::resolve_url_or_file_path_to_path_source;
-use crate::utils::verify_sha256_checksum;
+use crate::utils::ArchiveKind;
+use crate::utils::ChecksumMismatchError;
 use crate::utils::PathSource;
 
 pub fn get_file_path_from_plugin_info(plugin_info: &PluginInfo, environment: &impl Environment) -> PathBuf {
@@ -71,11 +72,19 @@ pub async fn setup_process_plugin<TEnvironment: Environment>(
     environment.mk_dir_all(plugin_cache_dir_path)?;
     let plugin_executable_file_path = get_plugin_executable_file_path(plugin_cache_dir_path, &plugin_name);
 
-    extract_zip(&format!("Extracting zip for {}", plugin_name), zip_bytes, plugin_cache_dir_path, environment)?;
+    let archive_kind = ArchiveKind::detect(zip_bytes)?;
+    extract_archive(
+      &format!("Extracting {} for {}", archive_kind, plugin_name),
+      archive_kind,
+      zip_bytes,
+      plugin_cache_dir_path,
+      environment,
+    )?;
 
     if !environment.path_exists(&plugin_executable_file_path) {
       bail!(
-        "Plugin zip file did not contain required executable at: {}",
+        "Plugin {} file did not contain required executable at: {}",
+        archive_kind,
         plugin_executable_file_path.display()
       );
     }
@@ -156,17 +165,20 @@ async fn get_plugin_zip_bytes<TEnvironment: Environment>(
   let plugin_file = deserialize_file(plugin_file_bytes)?;
   let plugin_path = get_os_path(&plugin_file, environment)?;
   let plugin_zip_path = resolve_url_or_file_path_to_path_source(&plugin_path.reference, &url_or_file_path.parent(), environment)?;
-  let plugin_zip_bytes = fetch_file_or_url_bytes(&plugin_zip_path, environment).await?;
-  if let Err(err) = verify_sha256_checksum(&plugin_zip_bytes, &plugin_path.checksum) {
-    bail!(
-      concat!(
-        "Invalid checksum found within process plugin's manifest file for '{}'. This is likely a ",
-        "bug in the process plugin. Please report it.\n\n{:#}",
+  let plugin_zip_bytes = match fetch_file_or_url_bytes_with_checksum(&plugin_zip_path, &plugin_path.checksum, environment).await {
+    Ok(bytes) => bytes,
+    Err(err) => match err.downcast_ref::<ChecksumMismatchError>() {
+      Some(err) => bail!(
+        concat!(
+          "Invalid checksum found within process plugin's manifest file for '{}'. This is likely a ",
+          "bug in the process plugin. Please report it.\n\n{:#}",
+        ),
+        plugin_path.reference,
+        err,
       ),
-      plugin_path.reference,
-      err,
-    )
-  }
+      None => return Err(err),
+    },
+  };
 
   Ok(ProcessPluginZipBytes {
     name: plugin_file.name,
@@ -164,6 +164,8 @@ impl<TEnvironment: Environment> InitializedWasmPlugin<TEnvironment> {
       Ok(instance) => instance,
       Err(err) => return Err(CriticalFormatError(err).into()),
     };
+    // when this is dropped before completing (ex. the format timed out), the instance
+    // is dropped instead of being released so that it's never used again
     let result = action(plugin.sender.clone()).await;
     match result {
       Ok(result) => {
@@ -257,8 +259,10 @@ impl<TEnvironment: Environment> InitializedWasmPlugin<TEnvironment> {
     let (tx, rx) = std::sync::mpsc::channel::<WasmPluginMessage>();
     let (initialize_tx, initialize_rx) = tokio::sync::oneshot::channel::<Result<(), anyhow::Error>>();
 
-    // spawn the wasm instance on a dedicated thread to reduce issues
-    dprint_core::async_runtime::spawn_blocking({
+    // spawn the wasm instance on a dedicated thread to reduce issues, which isn't a
+    // blocking task of the runtime so that an instance that never finishes formatting
+    // (ex. abandoned after the format timeout) doesn't prevent the process from exiting
+    std::thread::Builder::new().name(format!("{} instance", self.name)).spawn({
       let load_instance = self.load_instance.clone();
       let module = self.module.clone();
       move || {
@@ -327,7 +331,7 @@ impl<TEnvironment: Environment> InitializedWasmPlugin<TEnvironment> {
           }
         }
       }
-    });
+    })?;
 
     // wait for initialization
     initialize_rx.await??;
@@ -448,4 +452,73 @@ impl<TEnvironment: Environment> InitializedPlugin for InitializedWasmPlugin<TEnv
   async fn shutdown(&self) {
     // do nothing
   }
+
+  fn release_memory(&self) {
+    // The memory of a wasm instance never shrinks, so drop the idle instances
+    // to free the memory they grew to. They'll be recreated when necessary.
+    let instances = std::mem::take(&mut *self.pending_instances.borrow_mut());
+    if !instances.is_empty() {
+      log_debug!(self.environment, "Released {} idle instances of {}", instances.len(), self.name);
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::time::Duration;
+
+  use dprint_core::plugins::FormatConfigId;
+  use dprint_core::plugins::NullCancellationToken;
+
+  use super::*;
+  use crate::environment::TestEnvironment;
+
+  #[test]
+  fn should_abandon_instance_that_never_finishes_formatting() {
+    // a plugin that ignores cancellation and never finishes formatting
+    let wat = r#"(module
+      (memory (export "memory") 1)
+      (func (export "dprint_plugin_version_4") (result i32) i32.const 4)
+      (func (export "get_shared_bytes_ptr") (result i32) i32.const 0)
+      (func (export "clear_shared_bytes") (param i32) (result i32) i32.const 0)
+      (func (export "register_config") (param i32))
+      (func (export "set_file_path"))
+      (func (export "format") (param i32) (result i32)
+        (loop $spin (br $spin))
+        i32.const 0)
+    )"#;
+    let environment = TestEnvironment::new();
+    // this would hang when dropping the runtime if the instance's thread was waited on
+    environment.clone().run_in_runtime(async move {
+      let module = WasmModuleCreator::default().create_from_wasm_bytes(wat.as_bytes()).unwrap();
+      let plugin = InitializedWasmPlugin::new(
+        "spin-plugin".to_string(),
+        module,
+        Arc::new({
+          let environment = environment.clone();
+          move |store, module, host_format_sender| {
+            let (import_object, env) = create_pools_import_object(environment.clone(), "spin-plugin", module.version(), store, host_format_sender);
+            load_instance(store, module, env, &import_object)
+          }
+        }),
+        environment.clone(),
+      );
+      let format = plugin.format_text(InitializedPluginFormatRequest {
+        file_path: PathBuf::from("/file.txt"),
+        file_text: b"text".to_vec(),
+        range: None,
+        config: Arc::new(FormatConfig {
+          id: FormatConfigId::from_raw(1),
+          plugin: Default::default(),
+          global: Default::default(),
+        }),
+        override_config: Default::default(),
+        on_host_format: Rc::new(|_| async { Ok(None) }.boxed_local()),
+        token: Arc::new(NullCancellationToken),
+      });
+      assert!(tokio::time::timeout(Duration::from_millis(100), format).await.is_err());
+      // the timed out instance isn't used again
+      assert!(plugin.pending_instances.borrow().is_empty());
+    });
+  }
 }
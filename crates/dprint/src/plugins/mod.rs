@@ -5,6 +5,7 @@ mod helpers;
 mod implementations;
 mod name_resolution;
 mod plugin;
+mod process_plugin_trust;
 mod repo;
 mod resolver;
 mod types;
@@ -13,6 +14,7 @@ pub use cache::*;
 use cache_manifest::*;
 pub use helpers::*;
 pub use plugin::*;
+use process_plugin_trust::*;
 pub use repo::*;
 pub use resolver::*;
 pub use types::*;
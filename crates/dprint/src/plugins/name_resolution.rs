@@ -1,29 +1,31 @@
 use anyhow::Result;
 use std::collections::HashMap;
 use std::path::Path;
-use std::rc::Rc;
 
 use crate::environment::CanonicalizedPathBuf;
-use crate::patterns::get_patterns_as_glob_matcher;
+use crate::patterns::get_pattern_lists_as_glob_matcher_set;
 use crate::resolution::PluginWithConfig;
 use crate::utils::get_lowercase_file_extension;
 use crate::utils::get_lowercase_file_name;
-use crate::utils::GlobMatcher;
+use crate::utils::GlobMatcherSet;
 use crate::utils::GlobMatchesDetail;
 
 #[derive(Default)]
 pub struct PluginNameResolutionMaps {
   extension_to_plugin_names_map: HashMap<String, Vec<String>>,
   file_name_to_plugin_names_map: HashMap<String, Vec<String>>,
-  /// Associations matchers ordered by precedence.
-  association_matchers: Vec<(String, Rc<GlobMatcher>)>,
-  /// Associations matchers in a map.
-  association_matchers_map: HashMap<String, Rc<GlobMatcher>>,
+  /// Names of the plugins with associations ordered by precedence.
+  association_plugin_names: Vec<String>,
+  /// Index of each plugin in the associations matcher.
+  association_indexes: HashMap<String, usize>,
+  /// Matches the associations of all the plugins at once.
+  associations_matcher: Option<GlobMatcherSet>,
 }
 
 impl PluginNameResolutionMaps {
   pub fn from_plugins<'a>(plugins: impl Iterator<Item = &'a PluginWithConfig>, config_base_path: &CanonicalizedPathBuf) -> Result<Self> {
     let mut plugin_name_maps = PluginNameResolutionMaps::default();
+    let mut associations = Vec::new();
     for plugin in plugins {
       let plugin_name = plugin.name();
 
@@ -42,20 +44,27 @@ impl PluginNameResolutionMaps {
           .push(plugin_name.to_string());
       }
 
-      if let Some(matcher) = get_plugin_association_glob_matcher(plugin, config_base_path)? {
-        let matcher = Rc::new(matcher);
-        plugin_name_maps.association_matchers.push((plugin_name.to_string(), matcher.clone()));
-        plugin_name_maps.association_matchers_map.insert(plugin_name.to_string(), matcher);
+      if let Some(plugin_associations) = plugin.associations.as_ref() {
+        plugin_name_maps.association_indexes.insert(plugin_name.to_string(), associations.len());
+        plugin_name_maps.association_plugin_names.push(plugin_name.to_string());
+        associations.push(plugin_associations.as_slice());
       }
     }
+    if !associations.is_empty() {
+      plugin_name_maps.associations_matcher = Some(get_pattern_lists_as_glob_matcher_set(associations.into_iter(), config_base_path)?);
+    }
     Ok(plugin_name_maps)
   }
 
   pub fn get_plugin_names_from_file_path(&self, file_path: &Path) -> Vec<String> {
+    let association_matches = match &self.associations_matcher {
+      Some(matcher) => matcher.matches_detail(file_path),
+      None => Vec::new(),
+    };
     let mut plugin_names = Vec::new();
 
-    for (plugin_name, matcher) in self.association_matchers.iter() {
-      if matcher.matches(file_path) {
+    for (plugin_name, detail) in self.association_plugin_names.iter().zip(&association_matches) {
+      if matches!(detail, GlobMatchesDetail::Matched | GlobMatchesDetail::MatchedOptedOutExclude) {
         plugin_names.push(plugin_name.to_owned());
       }
     }
@@ -67,7 +76,7 @@ impl PluginNameResolutionMaps {
     if let Some(file_name) = get_lowercase_file_name(file_path) {
       if let Some(plugin_names) = self.file_name_to_plugin_names_map.get(&file_name) {
         for plugin_name in plugin_names {
-          if self.is_not_associations_excluded(plugin_name, file_path) {
+          if self.is_not_associations_excluded(plugin_name, &association_matches) {
             return vec![plugin_name.clone()];
           }
         }
@@ -77,7 +86,7 @@ impl PluginNameResolutionMaps {
     if let Some(ext) = get_lowercase_file_extension(file_path) {
       if let Some(plugin_names) = self.extension_to_plugin_names_map.get(&ext) {
         for plugin_name in plugin_names {
-          if self.is_not_associations_excluded(plugin_name, file_path) {
+          if self.is_not_associations_excluded(plugin_name, &association_matches) {
             return vec![plugin_name.clone()];
           }
         }
@@ -87,19 +96,10 @@ impl PluginNameResolutionMaps {
     plugin_names
   }
 
-  fn is_not_associations_excluded(&self, plugin_name: &str, file_path: &Path) -> bool {
-    if let Some(matcher) = self.association_matchers_map.get(plugin_name) {
-      matcher.has_only_excludes() && matcher.matches_detail(file_path) == GlobMatchesDetail::NotMatched
-    } else {
-      true
+  fn is_not_associations_excluded(&self, plugin_name: &str, association_matches: &[GlobMatchesDetail]) -> bool {
+    match (self.association_indexes.get(plugin_name), &self.associations_matcher) {
+      (Some(index), Some(matcher)) => matcher.has_only_excludes(*index) && association_matches[*index] == GlobMatchesDetail::NotMatched,
+      _ => true,
     }
   }
 }
-
-fn get_plugin_association_glob_matcher(plugin: &PluginWithConfig, config_base_path: &CanonicalizedPathBuf) -> Result<Option<GlobMatcher>> {
-  Ok(if let Some(associations) = plugin.associations.as_ref() {
-    Some(get_patterns_as_glob_matcher(associations, config_base_path)?)
-  } else {
-    None
-  })
-}
@@ -60,6 +60,8 @@ pub trait InitializedPlugin {
   async fn format_text(&self, format_request: InitializedPluginFormatRequest) -> FormatResult;
   /// Shuts down the plugin. This is used for process plugins.
   async fn shutdown(&self) -> ();
+  /// Frees any memory held onto between formats, which is used when idle.
+  fn release_memory(&self);
 }
 
 #[cfg(test)]
@@ -79,13 +81,22 @@ impl TestPlugin {
         help_url: "https://dprint.dev/plugins/test".to_string(),
         config_schema_url: "https://plugins.dprint.dev/schemas/test.json".to_string(),
         update_url: None,
+        global_options: None,
+      },
+      initialized_test_plugin: InitializedTestPlugin {
+        file_matching_info: FileMatchingInfo {
+          file_extensions: file_extensions.into_iter().map(String::from).collect(),
+          file_names: file_names.into_iter().map(String::from).collect(),
+        },
+        config_diagnostics: Vec::new(),
       },
-      initialized_test_plugin: InitializedTestPlugin(FileMatchingInfo {
-        file_extensions: file_extensions.into_iter().map(String::from).collect(),
-        file_names: file_names.into_iter().map(String::from).collect(),
-      }),
     }
   }
+
+  pub fn with_config_diagnostics(mut self, diagnostics: Vec<ConfigurationDiagnostic>) -> Self {
+    self.initialized_test_plugin.config_diagnostics = diagnostics;
+    self
+  }
 }
 
 #[cfg(test)]
@@ -107,7 +118,10 @@ impl Plugin for TestPlugin {
 
 #[cfg(test)]
 #[derive(Clone)]
-pub struct InitializedTestPlugin(FileMatchingInfo);
+pub struct InitializedTestPlugin {
+  file_matching_info: FileMatchingInfo,
+  config_diagnostics: Vec<ConfigurationDiagnostic>,
+}
 
 #[cfg(test)]
 #[async_trait(?Send)]
@@ -121,11 +135,11 @@ impl InitializedPlugin for InitializedTestPlugin {
   }
 
   async fn file_matching_info(&self, _config: Arc<FormatConfig>) -> Result<FileMatchingInfo> {
-    Ok(self.0.clone())
+    Ok(self.file_matching_info.clone())
   }
 
   async fn config_diagnostics(&self, _config: Arc<FormatConfig>) -> Result<Vec<ConfigurationDiagnostic>> {
-    Ok(vec![])
+    Ok(self.config_diagnostics.clone())
   }
 
   async fn check_config_updates(&self, _message: CheckConfigUpdatesMessage) -> Result<Vec<ConfigChange>> {
@@ -139,4 +153,8 @@ impl InitializedPlugin for InitializedTestPlugin {
   async fn shutdown(&self) -> () {
     // do nothing
   }
+
+  fn release_memory(&self) {
+    // do nothing
+  }
 }
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::bail;
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::environment::Environment;
+use crate::environment::ProcessPluginTrustPolicy;
+use crate::plugins::PluginSourceReference;
+
+/// The decisions of the user about which process plugins to trust.
+///
+/// Process plugins are native executables, so unlike Wasm plugins they
+/// aren't sandboxed and the user is asked before running a new one.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct ProcessPluginTrustStore {
+  /// Whether the plugin was trusted keyed by `<url>@<checksum>`.
+  plugins: HashMap<String, bool>,
+}
+
+/// Ensures the user trusts the process plugin before it's set up,
+/// prompting for it based on the environment's trust policy.
+pub fn ensure_process_plugin_trusted(source_reference: &PluginSourceReference, checksum: &str, environment: &impl Environment) -> Result<()> {
+  let source = source_reference.display();
+  let key = format!("{}@{}", source, checksum);
+  let mut store = read_store(environment);
+  let policy = environment.process_plugin_trust_policy();
+  match store.plugins.get(&key) {
+    Some(true) => return Ok(()),
+    Some(false) if policy != ProcessPluginTrustPolicy::Trust => {
+      bail!(
+        "The process plugin {} (checksum {}) was previously not trusted. Provide --yes to trust it.",
+        source,
+        checksum
+      );
+    }
+    _ => {}
+  }
+
+  let trusted = match policy {
+    ProcessPluginTrustPolicy::Trust => true,
+    ProcessPluginTrustPolicy::Deny => bail!(
+      concat!(
+        "The process plugin {} (checksum {}) has not been trusted. Run the command in a terminal ",
+        "to be prompted to trust it or provide --yes to trust it without prompting (ex. on CI)."
+      ),
+      source,
+      checksum
+    ),
+    ProcessPluginTrustPolicy::Prompt => {
+      log_all!(
+        environment,
+        concat!(
+          "The configuration uses a new process plugin, which is a native executable that ",
+          "runs with the same permissions as dprint.\n  Url: {}\n  Checksum: {}"
+        ),
+        source,
+        checksum
+      );
+      environment.confirm("Do you trust it?", false)?
+    }
+  };
+
+  store.plugins.insert(key, trusted);
+  if let Err(err) = write_store(&store, environment) {
+    log_warn!(environment, "Error saving process plugin trust decision: {:#}", err);
+  }
+  if !trusted {
+    bail!("The process plugin {} was not trusted.", source);
+  }
+  Ok(())
+}
+
+fn read_store(environment: &impl Environment) -> ProcessPluginTrustStore {
+  let file_path = get_store_file_path(environment);
+  let Ok(text) = environment.read_file(&file_path) else {
+    return ProcessPluginTrustStore::default();
+  };
+  match serde_json::from_str(&text) {
+    Ok(store) => store,
+    Err(err) => {
+      log_debug!(environment, "Ignoring process plugin trust store due to deserialization error: {:#}", err);
+      ProcessPluginTrustStore::default()
+    }
+  }
+}
+
+fn write_store(store: &ProcessPluginTrustStore, environment: &impl Environment) -> Result<()> {
+  let serialized_store = serde_json::to_string(store)?;
+  environment.atomic_write_file_bytes(get_store_file_path(environment), serialized_store.as_bytes())
+}
+
+fn get_store_file_path(environment: &impl Environment) -> PathBuf {
+  environment.get_cache_dir().join("process-plugin-trust.json")
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironment;
+  use crate::utils::PathSource;
+
+  #[test]
+  fn should_record_trust_decisions() {
+    let environment = TestEnvironment::new();
+    let source_reference = PluginSourceReference {
+      path_source: PathSource::new_remote_from_str("https://plugins.dprint.dev/test-process.json"),
+      checksum: Some("abc".to_string()),
+    };
+    environment.set_process_plugin_trust_policy(ProcessPluginTrustPolicy::Deny);
+    let err = ensure_process_plugin_trusted(&source_reference, "abc", &environment).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      concat!(
+        "The process plugin https://plugins.dprint.dev/test-process.json (checksum abc) has not been trusted. Run the command in a terminal ",
+        "to be prompted to trust it or provide --yes to trust it without prompting (ex. on CI)."
+      )
+    );
+
+    environment.set_process_plugin_trust_policy(ProcessPluginTrustPolicy::Prompt);
+    environment.set_confirm_results(vec![Ok(Some(false))]);
+    let err = ensure_process_plugin_trusted(&source_reference, "abc", &environment).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      "The process plugin https://plugins.dprint.dev/test-process.json was not trusted."
+    );
+    assert_eq!(
+      environment.take_stderr_messages(),
+      vec![
+        concat!(
+          "The configuration uses a new process plugin, which is a native executable that runs with the same ",
+          "permissions as dprint.\n  Url: https://plugins.dprint.dev/test-process.json\n  Checksum: abc"
+        ),
+        "Do you trust it? N",
+      ]
+    );
+    // doesn't prompt again
+    let err = ensure_process_plugin_trusted(&source_reference, "abc", &environment).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      "The process plugin https://plugins.dprint.dev/test-process.json (checksum abc) was previously not trusted. Provide --yes to trust it."
+    );
+
+    // a new checksum is a different plugin
+    environment.set_confirm_results(vec![Ok(Some(true))]);
+    ensure_process_plugin_trusted(&source_reference, "def", &environment).unwrap();
+    assert_eq!(environment.take_stderr_messages().len(), 2);
+    ensure_process_plugin_trusted(&source_reference, "def", &environment).unwrap();
+    assert!(environment.take_stderr_messages().is_empty());
+
+    environment.set_process_plugin_trust_policy(ProcessPluginTrustPolicy::Trust);
+    ensure_process_plugin_trusted(&source_reference, "abc", &environment).unwrap();
+    environment.set_process_plugin_trust_policy(ProcessPluginTrustPolicy::Deny);
+    ensure_process_plugin_trusted(&source_reference, "abc", &environment).unwrap();
+    assert_eq!(
+      read_store(&environment).plugins,
+      HashMap::from([
+        ("https://plugins.dprint.dev/test-process.json@abc".to_string(), true),
+        ("https://plugins.dprint.dev/test-process.json@def".to_string(), true),
+      ])
+    );
+  }
+}
@@ -2,6 +2,7 @@ use anyhow::bail;
 use anyhow::Context;
 use anyhow::Result;
 use dprint_core::async_runtime::future;
+use dprint_core::async_runtime::FutureExt;
 use dprint_core::communication::IdGenerator;
 use dprint_core::plugins::FormatConfigId;
 use dprint_core::plugins::PluginInfo;
@@ -16,6 +17,7 @@ use crate::environment::Environment;
 use crate::plugins::Plugin;
 use crate::plugins::PluginCache;
 use crate::plugins::PluginSourceReference;
+use crate::utils::profile_async_span;
 use crate::utils::AsyncCell;
 
 pub struct PluginWrapper {
@@ -40,7 +42,17 @@ impl PluginWrapper {
   }
 
   pub async fn initialize(&self) -> Result<Rc<dyn InitializedPlugin>> {
-    self.initialized_plugin.get_or_try_init(|| self.plugin.initialize()).await.cloned()
+    self
+      .initialized_plugin
+      .get_or_try_init(|| {
+        async {
+          let _span = profile_async_span("plugin", || format!("Initialize {}", self.info().name));
+          self.plugin.initialize().await
+        }
+        .boxed_local()
+      })
+      .await
+      .cloned()
   }
 
   pub async fn shutdown(&self) {
@@ -48,11 +60,17 @@ impl PluginWrapper {
       plugin.shutdown().await;
     }
   }
+
+  pub fn release_memory(&self) {
+    if let Some(plugin) = self.initialized_plugin.get() {
+      plugin.release_memory();
+    }
+  }
 }
 
 pub struct PluginResolver<TEnvironment: Environment> {
   environment: TEnvironment,
-  plugin_cache: PluginCache<TEnvironment>,
+  plugin_cache: Rc<PluginCache<TEnvironment>>,
   memory_cache: RefCell<HashMap<PluginSourceReference, Rc<tokio::sync::OnceCell<Rc<PluginWrapper>>>>>,
   wasm_module_creator: WasmModuleCreator,
   next_config_id: IdGenerator,
@@ -62,7 +80,18 @@ impl<TEnvironment: Environment> PluginResolver<TEnvironment> {
   pub fn new(environment: TEnvironment, plugin_cache: PluginCache<TEnvironment>) -> Self {
     PluginResolver {
       environment,
-      plugin_cache,
+      plugin_cache: Rc::new(plugin_cache),
+      memory_cache: Default::default(),
+      wasm_module_creator: Default::default(),
+      next_config_id: Default::default(),
+    }
+  }
+
+  /// Creates a resolver with its own initialized plugins that shares this resolver's cache.
+  pub fn new_sharing_cache(&self) -> Self {
+    PluginResolver {
+      environment: self.environment.clone(),
+      plugin_cache: self.plugin_cache.clone(),
       memory_cache: Default::default(),
       wasm_module_creator: Default::default(),
       next_config_id: Default::default(),
@@ -75,6 +104,20 @@ impl<TEnvironment: Environment> PluginResolver<TEnvironment> {
     future::join_all(futures).await;
   }
 
+  /// Frees the memory the initialized plugins hold onto between formats.
+  pub fn release_memory(&self) {
+    let plugins = self.memory_cache.borrow().values().filter_map(|p| p.get()).cloned().collect::<Vec<_>>();
+    for plugin in plugins {
+      plugin.release_memory();
+    }
+  }
+
+  /// Shuts down the initialized plugins and deletes the cache directory.
+  pub async fn clear_cache(&self) -> Result<()> {
+    self.clear_and_shutdown_initialized().await;
+    self.plugin_cache.clear()
+  }
+
   pub fn next_config_id(&self) -> FormatConfigId {
     // + 1 because 0 is reserved for uninitialized
     FormatConfigId::from_raw(self.next_config_id.next() + 1)
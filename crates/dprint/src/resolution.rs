@@ -11,6 +11,8 @@ use anyhow::Result;
 use dprint_core::async_runtime::FutureExt;
 use dprint_core::async_runtime::LocalBoxFuture;
 use dprint_core::configuration::ConfigKeyMap;
+use dprint_core::configuration::ConfigurationDiagnostic;
+use dprint_core::configuration::GlobalConfiguration;
 use dprint_core::plugins::process::HostFormatCallback;
 use dprint_core::plugins::CancellationToken;
 use dprint_core::plugins::CheckConfigUpdatesMessage;
@@ -28,12 +30,15 @@ use crate::arg_parser::CliArgs;
 use crate::arg_parser::FilePatternArgs;
 use crate::configuration::get_global_config;
 use crate::configuration::get_plugin_config_map;
+use crate::configuration::get_unused_global_option_diagnostics;
 use crate::configuration::resolve_config_from_args;
 use crate::configuration::resolve_config_from_path;
 use crate::configuration::GlobalConfigDiagnostic;
+use crate::configuration::RawPluginConfig;
 use crate::configuration::ResolveConfigError;
 use crate::configuration::ResolvedConfig;
 use crate::configuration::ResolvedConfigPath;
+use crate::crash_report::record_crash_report_config;
 use crate::environment::CanonicalizedPathBuf;
 use crate::environment::Environment;
 use crate::paths::get_and_resolve_file_paths;
@@ -41,6 +46,7 @@ use crate::paths::get_file_paths_by_plugins;
 use crate::paths::FilesPathsByPlugins;
 use crate::paths::NoFilesFoundError;
 use crate::patterns::FileMatcher;
+use crate::plugins::display_config_diagnostic;
 use crate::plugins::output_plugin_config_diagnostics;
 use crate::plugins::FormatConfig;
 use crate::plugins::InitializedPlugin;
@@ -49,6 +55,7 @@ use crate::plugins::OutputPluginConfigDiagnosticsError;
 use crate::plugins::PluginNameResolutionMaps;
 use crate::plugins::PluginResolver;
 use crate::plugins::PluginWrapper;
+use crate::utils::profile_async_span;
 use crate::utils::FastInsecureHasher;
 use crate::utils::ResolvedPath;
 
@@ -165,12 +172,8 @@ impl InitializedPluginWithConfig {
     self.instance.resolved_config(self.plugin.format_config.clone()).await
   }
 
-  pub async fn file_matching_info(&self) -> Result<FileMatchingInfo> {
-    self.instance.file_matching_info(self.plugin.format_config.clone()).await
-  }
-
-  pub async fn license_text(&self) -> Result<String> {
-    self.instance.license_text().await
+  pub async fn config_diagnostics(&self) -> Result<Vec<ConfigurationDiagnostic>> {
+    self.instance.config_diagnostics(self.plugin.format_config.clone()).await
   }
 
   pub async fn output_config_diagnostics<TEnvironment: Environment>(
@@ -217,6 +220,7 @@ impl<TEnvironment: Environment> PluginsScope<TEnvironment> {
     global_config_diagnostics: Vec<GlobalConfigDiagnostic>,
   ) -> Result<Self> {
     let plugin_name_maps = PluginNameResolutionMaps::from_plugins(plugins.iter().map(|p| p.as_ref()), &config.base_path)?;
+    record_crash_report_config(&config, &plugins);
 
     Ok(PluginsScope {
       environment,
@@ -228,6 +232,21 @@ impl<TEnvironment: Environment> PluginsScope<TEnvironment> {
     })
   }
 
+  fn empty(environment: TEnvironment) -> Self {
+    PluginsScope {
+      environment,
+      config: None,
+      plugin_name_maps: Default::default(),
+      plugins: Default::default(),
+      global_config_diagnostics: Default::default(),
+      cached_editor_file_matcher: Default::default(),
+    }
+  }
+
+  pub fn global_config_diagnostics(&self) -> &[GlobalConfigDiagnostic] {
+    &self.global_config_diagnostics
+  }
+
   pub fn ensure_valid_for_cli_args(&self, cli_args: &CliArgs) -> Result<()> {
     self.ensure_no_global_config_diagnostics()?;
     self.ensure_plugins_found()?;
@@ -236,6 +255,11 @@ impl<TEnvironment: Environment> PluginsScope<TEnvironment> {
     // to only specific plugins.
     if cli_args.plugins.is_empty() {
       self.ensure_no_unknown_config_property_diagnostics()?;
+      for diagnostic in self.global_config_diagnostics.iter().map(|d| d.diagnostic()) {
+        if !diagnostic.severity.is_error() {
+          log_warn!(self.environment, "{}", display_config_diagnostic(diagnostic));
+        }
+      }
     }
     Ok(())
   }
@@ -249,18 +273,7 @@ impl<TEnvironment: Environment> PluginsScope<TEnvironment> {
   }
 
   pub fn ensure_no_global_config_diagnostics(&self) -> Result<(), ResolveConfigError> {
-    if self.global_config_diagnostics.is_empty() {
-      return Ok(());
-    }
-    let diagnostics = self
-      .global_config_diagnostics
-      .iter()
-      .filter_map(|d| match d {
-        GlobalConfigDiagnostic::UnknownProperty(_) => None,
-        GlobalConfigDiagnostic::Other(d) => Some(d.to_string()),
-      })
-      .collect::<Vec<_>>();
-    self.error_for_diagnostics(&diagnostics)
+    ensure_no_global_config_diagnostics(&self.global_config_diagnostics, self.config.as_deref())
   }
 
   pub fn ensure_no_unknown_config_property_diagnostics(&self) -> Result<(), ResolveConfigError> {
@@ -271,29 +284,11 @@ impl<TEnvironment: Environment> PluginsScope<TEnvironment> {
       .global_config_diagnostics
       .iter()
       .filter_map(|d| match d {
-        GlobalConfigDiagnostic::UnknownProperty(d) => Some(d.to_string()),
+        GlobalConfigDiagnostic::UnknownProperty(d) => d.severity.is_error().then(|| d.to_string()),
         GlobalConfigDiagnostic::Other(_) => None,
       })
       .collect::<Vec<_>>();
-    self.error_for_diagnostics(&diagnostics)
-  }
-
-  fn error_for_diagnostics(&self, diagnostics: &[String]) -> Result<(), ResolveConfigError> {
-    if diagnostics.is_empty() {
-      return Ok(());
-    }
-    let diagnostics_len = diagnostics.len();
-    let mut output_text = String::new();
-    for diagnostic in diagnostics {
-      output_text.push_str("* ");
-      output_text.push_str(diagnostic);
-      output_text.push('\n');
-    }
-    output_text.push_str(&format!("\nHad {} config diagnostic(s)", diagnostics_len));
-    if let Some(config) = &self.config {
-      output_text.push_str(&format!(" in {}", config.resolved_path.source));
-    }
-    Err(ResolveConfigError::Other(anyhow::anyhow!("{}", output_text)))
+    error_for_diagnostics(&diagnostics, self.config.as_deref())
   }
 
   pub fn process_plugin_count(&self) -> usize {
@@ -313,9 +308,19 @@ impl<TEnvironment: Environment> PluginsScope<TEnvironment> {
     for plugin in self.plugins.values() {
       plugin.incremental_hash(&mut hasher);
     }
+    self.file_options_hash(&mut hasher);
     hasher.finish()
   }
 
+  /// Hashes the configuration that changes the output of the CLI
+  /// outside the plugins so it invalidates the caches.
+  pub fn file_options_hash(&self, hasher: &mut impl Hasher) {
+    use std::hash::Hash;
+    if let Some(config) = &self.config {
+      config.bom.hash(hasher);
+    }
+  }
+
   pub fn create_host_format_callback(self: &Rc<Self>) -> HostFormatCallback {
     let scope = self.clone();
     Rc::new(move |host_request| scope.format(host_request))
@@ -448,6 +453,7 @@ pub async fn resolve_plugins_scope_and_paths<TEnvironment: Environment>(
   environment: &TEnvironment,
   plugin_resolver: &Rc<PluginResolver<TEnvironment>>,
 ) -> Result<PluginsScopeAndPathsCollection<TEnvironment>> {
+  let _span = profile_async_span("resolution", || "Resolve plugins and file paths".to_string());
   let resolver = PluginsAndPathsResolver {
     args,
     patterns,
@@ -523,23 +529,39 @@ impl<'a, TEnvironment: Environment> PluginsAndPathsResolver<'a, TEnvironment> {
   }
 }
 
-pub async fn get_plugins_scope_from_args<TEnvironment: Environment>(
-  args: &CliArgs,
-  environment: &TEnvironment,
-  plugin_resolver: &Rc<PluginResolver<TEnvironment>>,
-) -> Result<PluginsScope<TEnvironment>, ResolvePluginsError> {
-  match resolve_config_from_args(args, environment).await {
-    Ok(config) => resolve_plugins_scope(Rc::new(config), environment, plugin_resolver).await,
-    // ignore
-    Err(_) => Ok(PluginsScope {
-      environment: environment.clone(),
-      config: None,
-      plugin_name_maps: Default::default(),
-      plugins: Default::default(),
-      global_config_diagnostics: Default::default(),
-      cached_editor_file_matcher: Default::default(),
-    }),
+fn ensure_no_global_config_diagnostics(
+  global_config_diagnostics: &[GlobalConfigDiagnostic],
+  config: Option<&ResolvedConfig>,
+) -> Result<(), ResolveConfigError> {
+  if global_config_diagnostics.is_empty() {
+    return Ok(());
+  }
+  let diagnostics = global_config_diagnostics
+    .iter()
+    .filter_map(|d| match d {
+      GlobalConfigDiagnostic::UnknownProperty(_) => None,
+      GlobalConfigDiagnostic::Other(d) => d.severity.is_error().then(|| d.to_string()),
+    })
+    .collect::<Vec<_>>();
+  error_for_diagnostics(&diagnostics, config)
+}
+
+fn error_for_diagnostics(diagnostics: &[String], config: Option<&ResolvedConfig>) -> Result<(), ResolveConfigError> {
+  if diagnostics.is_empty() {
+    return Ok(());
   }
+  let diagnostics_len = diagnostics.len();
+  let mut output_text = String::new();
+  for diagnostic in diagnostics {
+    output_text.push_str("* ");
+    output_text.push_str(diagnostic);
+    output_text.push('\n');
+  }
+  output_text.push_str(&format!("\nHad {} config diagnostic(s)", diagnostics_len));
+  if let Some(config) = config {
+    output_text.push_str(&format!(" in {}", config.resolved_path.source));
+  }
+  Err(ResolveConfigError::Other(anyhow::anyhow!("{}", output_text)))
 }
 
 #[derive(Debug, Error)]
@@ -555,6 +577,113 @@ pub async fn resolve_plugins_scope<TEnvironment: Environment>(
   environment: &TEnvironment,
   plugin_resolver: &Rc<PluginResolver<TEnvironment>>,
 ) -> Result<PluginsScope<TEnvironment>, ResolvePluginsError> {
+  resolve_plugins_config(config, environment, plugin_resolver)
+    .await?
+    .initialize(plugin_resolver)
+    .await
+}
+
+/// Plugins along with their resolved configuration that have not been initialized.
+///
+/// This is useful for commands that only need the plugin information
+/// and don't want to pay the cost of initializing each plugin.
+pub struct ResolvedPluginsConfig<TEnvironment: Environment> {
+  environment: TEnvironment,
+  pub config: Option<Rc<ResolvedConfig>>,
+  pub plugins: Vec<(RawPluginConfig, Rc<PluginWrapper>)>,
+  global_config: GlobalConfiguration,
+  global_config_diagnostics: Vec<GlobalConfigDiagnostic>,
+}
+
+impl<TEnvironment: Environment> ResolvedPluginsConfig<TEnvironment> {
+  pub fn ensure_no_global_config_diagnostics(&self) -> Result<(), ResolveConfigError> {
+    ensure_no_global_config_diagnostics(&self.global_config_diagnostics, self.config.as_deref())
+  }
+
+  /// Gets a hash of everything that could change the information
+  /// provided by the plugins once initialized.
+  pub fn plugins_hash(&self, hasher: &mut impl Hasher) {
+    use std::hash::Hash;
+    for (plugin_config, plugin) in &self.plugins {
+      let info = plugin.info();
+      hasher.write(info.name.as_bytes());
+      hasher.write(info.version.as_bytes());
+      hasher.write(info.config_key.as_bytes());
+      hasher.write(info.help_url.as_bytes());
+      hasher.write(info.config_schema_url.as_bytes());
+      // serialize the config keys in order to prevent the hash from changing
+      let sorted_config = plugin_config.properties.iter().collect::<BTreeMap<_, _>>();
+      for (key, value) in sorted_config {
+        hasher.write(key.as_bytes());
+        value.hash(hasher);
+      }
+      if let Some(associations) = &plugin_config.associations {
+        for association in associations {
+          hasher.write(association.as_bytes());
+        }
+      }
+    }
+    self.global_config.hash(hasher);
+  }
+
+  pub async fn initialize(self, plugin_resolver: &Rc<PluginResolver<TEnvironment>>) -> Result<PluginsScope<TEnvironment>, ResolvePluginsError> {
+    let Some(config) = self.config else {
+      return Ok(PluginsScope::empty(self.environment));
+    };
+    let plugins = self.plugins.into_iter().map(|(plugin_config, plugin)| {
+      let global_config = self.global_config.clone();
+      let next_config_id = plugin_resolver.next_config_id();
+      async move {
+        let instance = plugin.initialize().await?;
+        let format_config = Arc::new(FormatConfig {
+          id: next_config_id,
+          global: global_config,
+          plugin: plugin_config.properties,
+        });
+        let file_matching_info = instance.file_matching_info(format_config.clone()).await?;
+        Ok::<_, anyhow::Error>(Rc::new(PluginWithConfig::new(
+          plugin,
+          plugin_config.associations,
+          format_config,
+          file_matching_info,
+        )))
+      }
+      .boxed_local()
+    });
+    let plugin_results = dprint_core::async_runtime::future::join_all(plugins).await;
+    let mut plugins = Vec::with_capacity(plugin_results.len());
+    for result in plugin_results {
+      plugins.push(result?);
+    }
+
+    Ok(PluginsScope::new(self.environment, plugins, config, self.global_config_diagnostics)?)
+  }
+}
+
+/// Resolves the plugins and their configuration without initializing them.
+pub async fn get_plugins_config_from_args<TEnvironment: Environment>(
+  args: &CliArgs,
+  environment: &TEnvironment,
+  plugin_resolver: &Rc<PluginResolver<TEnvironment>>,
+) -> Result<ResolvedPluginsConfig<TEnvironment>, ResolvePluginsError> {
+  match resolve_config_from_args(args, environment).await {
+    Ok(config) => resolve_plugins_config(Rc::new(config), environment, plugin_resolver).await,
+    // ignore
+    Err(_) => Ok(ResolvedPluginsConfig {
+      environment: environment.clone(),
+      config: None,
+      plugins: Default::default(),
+      global_config: Default::default(),
+      global_config_diagnostics: Default::default(),
+    }),
+  }
+}
+
+pub async fn resolve_plugins_config<TEnvironment: Environment>(
+  config: Rc<ResolvedConfig>,
+  environment: &TEnvironment,
+  plugin_resolver: &Rc<PluginResolver<TEnvironment>>,
+) -> Result<ResolvedPluginsConfig<TEnvironment>, ResolvePluginsError> {
   // resolve the plugins
   let plugins = plugin_resolver.resolve_plugins(config.plugins.clone()).await?;
   let mut config_map = config.config_map.clone();
@@ -566,35 +695,15 @@ pub async fn resolve_plugins_scope<TEnvironment: Environment>(
   }
 
   // now get global config
-  let global_config_result = get_global_config(config_map);
-  let global_config = global_config_result.config;
-
-  // create the scope
-  let plugins = plugins_with_config.into_iter().map(|(plugin_config, plugin)| {
-    let global_config = global_config.clone();
-    let next_config_id = plugin_resolver.next_config_id();
-    async move {
-      let instance = plugin.initialize().await?;
-      let format_config = Arc::new(FormatConfig {
-        id: next_config_id,
-        global: global_config,
-        plugin: plugin_config.properties,
-      });
-      let file_matching_info = instance.file_matching_info(format_config.clone()).await?;
-      Ok::<_, anyhow::Error>(Rc::new(PluginWithConfig::new(
-        plugin,
-        plugin_config.associations,
-        format_config,
-        file_matching_info,
-      )))
-    }
-    .boxed_local()
-  });
-  let plugin_results = dprint_core::async_runtime::future::join_all(plugins).await;
-  let mut plugins = Vec::with_capacity(plugin_results.len());
-  for result in plugin_results {
-    plugins.push(result?);
-  }
-
-  Ok(PluginsScope::new(environment.clone(), plugins, config, global_config_result.diagnostics)?)
+  let unused_global_option_diagnostics = get_unused_global_option_diagnostics(&config_map, plugins_with_config.iter().map(|(_, plugin)| plugin.info()));
+  let mut global_config_result = get_global_config(config_map);
+  global_config_result.diagnostics.extend(unused_global_option_diagnostics);
+
+  Ok(ResolvedPluginsConfig {
+    environment: environment.clone(),
+    config: Some(config),
+    plugins: plugins_with_config,
+    global_config: global_config_result.config,
+    global_config_diagnostics: global_config_result.diagnostics,
+  })
 }
@@ -9,6 +9,7 @@ use crate::environment::Environment;
 use crate::paths::NoFilesFoundError;
 use crate::plugins::PluginResolver;
 
+use crate::arg_parser::CacheSubCommand;
 use crate::arg_parser::CliArgs;
 use crate::arg_parser::ConfigSubCommand;
 use crate::arg_parser::SubCommand;
@@ -113,12 +114,18 @@ pub async fn run_cli<TEnvironment: Environment>(args: &CliArgs, environment: &TE
     SubCommand::License => commands::output_license(args, environment, plugin_resolver).await,
     SubCommand::EditorInfo => commands::output_editor_info(args, environment, plugin_resolver).await,
     SubCommand::EditorService(cmd) => commands::run_editor_service(args, environment, plugin_resolver, cmd).await,
-    SubCommand::Lsp => commands::run_language_server(args, environment, plugin_resolver).await,
+    SubCommand::Daemon(cmd) => commands::run_daemon(environment, cmd).await,
+    SubCommand::Lsp(cmd) => commands::run_language_server(args, environment, plugin_resolver, cmd).await,
     SubCommand::ClearCache => commands::clear_cache(environment),
+    SubCommand::Cache(cmd) => match cmd {
+      CacheSubCommand::IncrementalStatus => commands::output_incremental_status(args, environment, plugin_resolver).await,
+      CacheSubCommand::IncrementalInvalidate(file_paths) => commands::invalidate_incremental_cache(file_paths, args, environment).await,
+    },
     SubCommand::Config(cmd) => match cmd {
       ConfigSubCommand::Init => commands::init_config_file(environment, &args.config).await,
       ConfigSubCommand::Add(plugin_name_or_url) => commands::add_plugin_config_file(args, plugin_name_or_url.as_ref(), environment, plugin_resolver).await,
       ConfigSubCommand::Update { yes } => commands::update_plugins_config_file(args, environment, plugin_resolver, *yes).await,
+      ConfigSubCommand::Diagnostics { json } => commands::output_config_diagnostics(args, environment, plugin_resolver, *json).await,
     },
     SubCommand::Version => commands::output_version(environment),
     SubCommand::StdInFmt(cmd) => commands::stdin_fmt(cmd, args, environment, plugin_resolver).await,
@@ -0,0 +1,101 @@
+//! Helpers for testing the CLI against an in-memory file system.
+//!
+//! These are enabled by the `test-harness` feature and published in
+//! the `dprint-test-harness` crate for plugin and editor extension authors.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use anyhow::Result;
+use thiserror::Error;
+
+use crate::arg_parser::parse_args;
+use crate::environment::ProcessPluginTrustPolicy;
+use crate::plugins::PluginCache;
+use crate::plugins::PluginResolver;
+use crate::run_cli::run_cli;
+use crate::AppError;
+
+pub use crate::environment::Environment;
+pub use crate::environment::TestConfigFileBuilder;
+pub use crate::environment::TestEnvironment;
+pub use crate::environment::TestEnvironmentBuilder;
+pub use crate::environment::TestInfoFileBuilder;
+pub use crate::environment::TestInfoFilePlugin;
+pub use crate::environment::UrlDownloader;
+pub use crate::utils::TestStdInReader;
+
+#[derive(Debug, Error)]
+#[error("{inner:#}")]
+pub struct TestAppError {
+  asserted_exit_code: RefCell<bool>,
+  inner: AppError,
+}
+
+impl TestAppError {
+  pub fn exit_code(&self) -> i32 {
+    self.inner.exit_code
+  }
+
+  #[track_caller]
+  pub fn assert_exit_code(&self, exit_code: i32) {
+    self.asserted_exit_code.replace(true);
+    assert_eq!(self.inner.exit_code, exit_code);
+  }
+}
+
+impl From<AppError> for TestAppError {
+  fn from(inner: AppError) -> Self {
+    Self {
+      asserted_exit_code: Default::default(),
+      inner,
+    }
+  }
+}
+
+impl From<anyhow::Error> for TestAppError {
+  fn from(inner: anyhow::Error) -> Self {
+    Self {
+      asserted_exit_code: Default::default(),
+      inner: inner.into(),
+    }
+  }
+}
+
+impl Drop for TestAppError {
+  fn drop(&mut self) {
+    if std::thread::panicking() || self.inner.exit_code <= 1 {
+      return;
+    }
+    if !*self.asserted_exit_code.borrow() {
+      panic!("Exit code must be asserted. Was: {}", self.inner.exit_code);
+    }
+  }
+}
+
+/// Runs the CLI with the provided arguments (excluding the executable) against the test environment.
+pub fn run_test_cli(args: Vec<&str>, environment: &TestEnvironment) -> Result<(), TestAppError> {
+  run_test_cli_with_stdin(args, environment, TestStdInReader::default())
+}
+
+pub fn run_test_cli_with_stdin(args: Vec<&str>, environment: &TestEnvironment, stdin_reader: TestStdInReader) -> Result<(), TestAppError> {
+  let mut args: Vec<String> = args.into_iter().map(String::from).collect();
+  args.insert(0, String::from(""));
+  let plugin_cache = PluginCache::new(environment.clone());
+  let plugin_resolver = Rc::new(PluginResolver::new(environment.clone(), plugin_cache));
+  let args = parse_args(args, stdin_reader).map_err(Into::<AppError>::into)?;
+  environment.set_stdout_machine_readable(args.is_stdout_machine_readable());
+  environment.set_log_level(args.log_level);
+  if args.trust_process_plugins {
+    environment.set_process_plugin_trust_policy(ProcessPluginTrustPolicy::Trust);
+  }
+
+  environment.run_in_runtime({
+    let environment = environment.clone();
+    async move {
+      let result = run_cli(&args, &environment, &plugin_resolver).await;
+      plugin_resolver.clear_and_shutdown_initialized().await;
+      Ok(result?)
+    }
+  })
+}
@@ -1,20 +1,12 @@
-use std::cell::RefCell;
 use std::io::Write;
 use std::path::PathBuf;
-use std::rc::Rc;
 
-use anyhow::Result;
 use crossterm::style::Stylize;
 use once_cell::sync::Lazy;
-use thiserror::Error;
 
-use crate::arg_parser::parse_args;
-use crate::environment::TestEnvironment;
-use crate::plugins::PluginCache;
-use crate::plugins::PluginResolver;
-use crate::run_cli::run_cli;
-use crate::utils::TestStdInReader;
-use crate::AppError;
+pub use crate::test_harness::run_test_cli;
+pub use crate::test_harness::run_test_cli_with_stdin;
+pub use crate::test_harness::TestAppError;
 
 // macro lifted from Deno's codebase
 #[macro_export]
@@ -68,73 +60,6 @@ pub static PROCESS_PLUGIN_ZIP_BYTES: Lazy<Vec<u8>> = Lazy::new(|| {
 });
 pub static PROCESS_PLUGIN_ZIP_CHECKSUM: Lazy<String> = Lazy::new(|| crate::utils::get_sha256_checksum(&PROCESS_PLUGIN_ZIP_BYTES));
 
-#[derive(Debug, Error)]
-#[error("{inner:#}")]
-pub struct TestAppError {
-  asserted_exit_code: RefCell<bool>,
-  inner: AppError,
-}
-
-impl TestAppError {
-  #[track_caller]
-  pub fn assert_exit_code(&self, exit_code: i32) {
-    self.asserted_exit_code.replace(true);
-    assert_eq!(self.inner.exit_code, exit_code);
-  }
-}
-
-impl From<AppError> for TestAppError {
-  fn from(inner: AppError) -> Self {
-    Self {
-      asserted_exit_code: Default::default(),
-      inner,
-    }
-  }
-}
-
-impl From<anyhow::Error> for TestAppError {
-  fn from(inner: anyhow::Error) -> Self {
-    Self {
-      asserted_exit_code: Default::default(),
-      inner: inner.into(),
-    }
-  }
-}
-
-impl Drop for TestAppError {
-  fn drop(&mut self) {
-    if std::thread::panicking() || self.inner.exit_code <= 1 {
-      return;
-    }
-    if !self.asserted_exit_code.borrow().clone() {
-      panic!("Exit code must be asserted. Was: {}", self.inner.exit_code);
-    }
-  }
-}
-
-pub fn run_test_cli(args: Vec<&str>, environment: &TestEnvironment) -> Result<(), TestAppError> {
-  run_test_cli_with_stdin(args, environment, TestStdInReader::default())
-}
-
-pub fn run_test_cli_with_stdin(args: Vec<&str>, environment: &TestEnvironment, stdin_reader: TestStdInReader) -> Result<(), TestAppError> {
-  let mut args: Vec<String> = args.into_iter().map(String::from).collect();
-  args.insert(0, String::from(""));
-  let plugin_cache = PluginCache::new(environment.clone());
-  let plugin_resolver = Rc::new(PluginResolver::new(environment.clone(), plugin_cache));
-  let args = parse_args(args, stdin_reader).map_err(|err| Into::<AppError>::into(err))?;
-  environment.set_stdout_machine_readable(args.is_stdout_machine_readable());
-  environment.set_log_level(args.log_level);
-
-  environment.run_in_runtime({
-    let environment = environment.clone();
-    async move {
-      let result = run_cli(&args, &environment, &plugin_resolver).await;
-      plugin_resolver.clear_and_shutdown_initialized().await;
-      Ok(result?)
-    }
-  })
-}
-
 pub fn get_test_wasm_plugin_checksum() -> String {
   crate::utils::get_sha256_checksum(WASM_PLUGIN_BYTES)
 }
@@ -264,9 +189,11 @@ SUBCOMMANDS:
   output-resolved-config  Prints the resolved configuration for the plugins based on the args and configuration.
   output-format-times     Prints the amount of time it takes to format each file. Use this for debugging.
   clear-cache             Deletes the plugin cache directory.
+  cache                   Functionality related to the incremental formatting cache.
   upgrade                 Upgrades the dprint executable.
   completions             Generate shell completions script for dprint
   license                 Outputs the software license.
+  daemon                  Starts a daemon that keeps plugins loaded between `fmt` and `check` commands run with `--daemon`.
   lsp                     Starts up a language server for formatting files.
 
 More details at `dprint help <SUBCOMMAND>`
@@ -275,18 +202,24 @@ OPTIONS:
   -c, --config <config>          Path or url to JSON configuration file. Defaults to dprint.json(c) or .dprint.json(c) in current or ancestor directory when not provided.
       --plugins <urls/files>...  List of urls or file paths of plugins to use. This overrides what is specified in the config file.
   -L, --log-level <log-level>    Set log level [default: info] [possible values: debug, info, warn, error, silent]
+      --no-network               Errors instead of accessing the network (ex. downloading plugins).
+  -y, --yes                      Trusts new process plugins and upgrades them without prompting (ex. on CI).
 
 ENVIRONMENT VARIABLES:
-  DPRINT_CACHE_DIR     Directory to store the dprint cache. Note that this
-                       directory may be periodically deleted by the CLI.
-  DPRINT_MAX_THREADS   Limit the number of threads dprint uses for
-                       formatting (ex. DPRINT_MAX_THREADS=4).
-  DPRINT_CERT          Load certificate authority from PEM encoded file.
-  DPRINT_TLS_CA_STORE  Comma-separated list of order dependent certificate stores.
-                       Possible values: "mozilla" and "system".
-                       Defaults to "mozilla,system".
-  HTTPS_PROXY          Proxy to use when downloading plugins or configuration
-                       files (set HTTP_PROXY for HTTP).
+  DPRINT_CACHE_DIR      Directory to store the dprint cache. Note that this
+                        directory may be periodically deleted by the CLI.
+  DPRINT_MAX_THREADS    Limit the number of threads dprint uses for
+                        formatting (ex. DPRINT_MAX_THREADS=4).
+  DPRINT_CERT           Load certificate authority from PEM encoded file.
+  DPRINT_TLS_CA_STORE   Comma-separated list of order dependent certificate stores.
+                        Possible values: "mozilla" and "system".
+                        Defaults to "mozilla,system".
+  DPRINT_NO_NETWORK     Set to 1 to error instead of accessing the network
+                        (same as --no-network).
+  DPRINT_TRUST_PLUGINS  Set to 1 to trust new process plugins without prompting
+                        (same as --yes).
+  HTTPS_PROXY           Proxy to use when downloading plugins or configuration
+                        files (set HTTP_PROXY for HTTP).
 
 GETTING STARTED:
   1. Navigate to the root directory of a code repository.
@@ -43,6 +43,10 @@ impl<TInner: UrlDownloader> UrlDownloader for CachedDownloader<TInner> {
     );
     result
   }
+
+  async fn post_json(&self, url: &str, headers: &[(String, String)], body: Vec<u8>) -> Result<()> {
+    self.inner.post_json(url, headers, body).await
+  }
 }
 
 #[cfg(test)]
@@ -1,24 +1,49 @@
-use anyhow::bail;
-use anyhow::Result;
+use sha2::Digest;
+use sha2::Sha256;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("The checksum did not match the expected checksum.\n\nActual: {actual}\nExpected: {expected}")]
+pub struct ChecksumMismatchError {
+  pub actual: String,
+  pub expected: String,
+}
 
 pub fn get_sha256_checksum(bytes: &[u8]) -> String {
-  use sha2::Digest;
-  use sha2::Sha256;
-  let mut hasher = Sha256::new();
-  hasher.update(bytes);
-  format!("{:x}", hasher.finalize())
+  let mut checksum = StreamingChecksum::default();
+  checksum.update(bytes);
+  checksum.finish()
+}
+
+pub fn verify_sha256_checksum(bytes: &[u8], checksum: &str) -> Result<(), ChecksumMismatchError> {
+  let mut streaming_checksum = StreamingChecksum::default();
+  streaming_checksum.update(bytes);
+  streaming_checksum.verify(checksum)
 }
 
-pub fn verify_sha256_checksum(bytes: &[u8], checksum: &str) -> Result<()> {
-  let bytes_checksum = get_sha256_checksum(bytes);
-  if bytes_checksum != checksum {
-    bail!(
-      "The checksum did not match the expected checksum.\n\nActual: {}\nExpected: {}",
-      bytes_checksum,
-      checksum
-    )
-  } else {
-    Ok(())
+/// Computes a sha256 checksum of bytes as they're received.
+#[derive(Default, Clone)]
+pub struct StreamingChecksum(Sha256);
+
+impl StreamingChecksum {
+  pub fn update(&mut self, bytes: &[u8]) {
+    self.0.update(bytes);
+  }
+
+  pub fn finish(self) -> String {
+    format!("{:x}", self.0.finalize())
+  }
+
+  pub fn verify(self, checksum: &str) -> Result<(), ChecksumMismatchError> {
+    let actual = self.finish();
+    if actual != checksum {
+      Err(ChecksumMismatchError {
+        actual,
+        expected: checksum.to_string(),
+      })
+    } else {
+      Ok(())
+    }
   }
 }
 
@@ -49,6 +74,18 @@ pub fn parse_checksum_path_or_url(text: &str) -> ChecksumPathOrUrl {
 mod test {
   use super::*;
 
+  #[test]
+  pub fn streaming_checksum_matches_checksum() {
+    let mut checksum = StreamingChecksum::default();
+    checksum.update(b"hello ");
+    checksum.update(b"world");
+    assert_eq!(checksum.finish(), get_sha256_checksum(b"hello world"));
+    assert!(verify_sha256_checksum(b"hello world", &get_sha256_checksum(b"hello world")).is_ok());
+    let err = verify_sha256_checksum(b"hello", "abc").unwrap_err();
+    assert_eq!(err.expected, "abc");
+    assert_eq!(err.actual, get_sha256_checksum(b"hello"));
+  }
+
   #[test]
   pub fn parses_checksum_path_or_url() {
     assert_eq!(
@@ -0,0 +1,425 @@
+use std::io::prelude::*;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::bail;
+use anyhow::Result;
+
+use crate::environment::Environment;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+  Zip,
+  TarGz,
+  TarZst,
+}
+
+impl ArchiveKind {
+  /// Detects the kind of archive from its leading bytes.
+  pub fn detect(bytes: &[u8]) -> Result<Self> {
+    if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+      Ok(ArchiveKind::Zip)
+    } else if bytes.starts_with(&[0x1f, 0x8b]) {
+      Ok(ArchiveKind::TarGz)
+    } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+      Ok(ArchiveKind::TarZst)
+    } else {
+      bail!("Unsupported archive format. Expected a .zip, .tar.gz, or .tar.zst file.")
+    }
+  }
+}
+
+impl std::fmt::Display for ArchiveKind {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ArchiveKind::Zip => write!(f, "zip"),
+      ArchiveKind::TarGz => write!(f, "tar.gz"),
+      ArchiveKind::TarZst => write!(f, "tar.zst"),
+    }
+  }
+}
+
+pub fn extract_archive(message: &str, kind: ArchiveKind, archive_bytes: &[u8], dir_path: &Path, environment: &impl Environment) -> Result<()> {
+  match kind {
+    ArchiveKind::Zip => extract_zip(message, archive_bytes, dir_path, environment),
+    ArchiveKind::TarGz => {
+      let mut tar_bytes = Vec::new();
+      flate2::read::MultiGzDecoder::new(archive_bytes).read_to_end(&mut tar_bytes)?;
+      extract_tar(message, &tar_bytes, dir_path, environment)
+    }
+    ArchiveKind::TarZst => {
+      let tar_bytes = zstd::decode_all(archive_bytes)?;
+      extract_tar(message, &tar_bytes, dir_path, environment)
+    }
+  }
+}
+
+pub fn extract_zip(message: &str, zip_bytes: &[u8], dir_path: &Path, environment: &impl Environment) -> Result<()> {
+  // adapted from https://github.com/mvdnes/zip-rs/blob/master/examples/extract.rs
+  let reader = std::io::Cursor::new(&zip_bytes);
+  let mut zip = zip::ZipArchive::new(reader)?;
+  let length = zip.len();
+
+  log_debug!(environment, "Extracting zip file to directory: {}", dir_path.display());
+
+  environment.log_action_with_progress(
+    message,
+    move |update_size| -> Result<()> {
+      // todo: consider parallelizing this
+      for i in 0..zip.len() {
+        update_size(i);
+        let mut file = zip.by_index(i).unwrap();
+        if let Some(file_name) = file.enclosed_name() {
+          let file_path = dir_path.join(file_name);
+
+          if !file.is_dir() {
+            if let Some(parent_dir_path) = file_path.parent() {
+              environment.mk_dir_all(parent_dir_path)?;
+            }
+            let mut file_bytes = Vec::with_capacity(file.size() as usize);
+            file.read_to_end(&mut file_bytes)?;
+            environment.write_file_bytes(&file_path, &file_bytes)?;
+          } else {
+            environment.mk_dir_all(&file_path)?;
+          }
+
+          if let Some(mode) = file.unix_mode() {
+            set_unix_mode(&file_path, mode, environment)?;
+          }
+        } else {
+          log_warn!(environment, "Ignoring path in zip because it was not enclosed: {}", file.name());
+        }
+      }
+
+      Ok(())
+    },
+    length,
+  )?;
+
+  Ok(())
+}
+
+fn extract_tar(message: &str, tar_bytes: &[u8], dir_path: &Path, environment: &impl Environment) -> Result<()> {
+  let entries = parse_tar(tar_bytes)?;
+  let length = entries.len();
+
+  log_debug!(environment, "Extracting tar file to directory: {}", dir_path.display());
+
+  environment.log_action_with_progress(
+    message,
+    move |update_size| -> Result<()> {
+      for (i, entry) in entries.iter().enumerate() {
+        update_size(i);
+        let Some(file_name) = get_enclosed_path(&entry.path) else {
+          log_warn!(environment, "Ignoring path in tar because it was not enclosed: {}", entry.path);
+          continue;
+        };
+        let file_path = dir_path.join(file_name);
+        match entry.kind {
+          TarEntryKind::File => {
+            if let Some(parent_dir_path) = file_path.parent() {
+              environment.mk_dir_all(parent_dir_path)?;
+            }
+            environment.write_file_bytes(&file_path, entry.data)?;
+          }
+          TarEntryKind::Directory => {
+            environment.mk_dir_all(&file_path)?;
+          }
+          TarEntryKind::Other => {
+            log_warn!(environment, "Ignoring path in tar because it was not a file or directory: {}", entry.path);
+            continue;
+          }
+        }
+
+        if let Some(mode) = entry.mode {
+          set_unix_mode(&file_path, mode, environment)?;
+        }
+      }
+
+      Ok(())
+    },
+    length,
+  )?;
+
+  Ok(())
+}
+
+fn set_unix_mode(file_path: &Path, mode: u32, environment: &impl Environment) -> Result<()> {
+  #[cfg(unix)]
+  if environment.is_real() {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[allow(clippy::disallowed_methods)]
+    fs::set_permissions(file_path, fs::Permissions::from_mode(mode))
+      .map_err(|err| anyhow::anyhow!("Error setting permissions to {} for file {}: {:#}", mode, file_path.display(), err))?
+  }
+  #[cfg(not(unix))]
+  let _ = (file_path, mode, environment);
+  Ok(())
+}
+
+/// Gets the path when it doesn't escape the directory it's extracted to.
+fn get_enclosed_path(path: &str) -> Option<PathBuf> {
+  let mut result = PathBuf::new();
+  for component in Path::new(path).components() {
+    match component {
+      Component::Normal(name) => result.push(name),
+      Component::CurDir => {}
+      Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+    }
+  }
+  if result.as_os_str().is_empty() {
+    None
+  } else {
+    Some(result)
+  }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum TarEntryKind {
+  File,
+  Directory,
+  /// Links and special files, which aren't extracted.
+  Other,
+}
+
+#[derive(Debug)]
+struct TarEntry<'a> {
+  path: String,
+  kind: TarEntryKind,
+  mode: Option<u32>,
+  data: &'a [u8],
+}
+
+const TAR_BLOCK_SIZE: usize = 512;
+
+/// Parses the entries of a ustar, GNU, or pax tar file.
+fn parse_tar(bytes: &[u8]) -> Result<Vec<TarEntry<'_>>> {
+  let mut entries = Vec::new();
+  let mut offset = 0;
+  let mut next_path = None;
+  while offset + TAR_BLOCK_SIZE <= bytes.len() {
+    let header = &bytes[offset..offset + TAR_BLOCK_SIZE];
+    if header.iter().all(|b| *b == 0) {
+      break; // end of archive
+    }
+    verify_tar_header_checksum(header)?;
+    let size = parse_tar_number(&header[124..136])? as usize;
+    let data_start = offset + TAR_BLOCK_SIZE;
+    let Some(data) = data_start.checked_add(size).and_then(|data_end| bytes.get(data_start..data_end)) else {
+      bail!("Unexpected end of tar file.");
+    };
+    offset = data_start + size.div_ceil(TAR_BLOCK_SIZE) * TAR_BLOCK_SIZE;
+
+    let kind = match header[156] {
+      // gnu long name for the next entry
+      b'L' => {
+        next_path = Some(parse_tar_string(data));
+        continue;
+      }
+      // pax extended header for the next entry
+      b'x' => {
+        if let Some(path) = parse_pax_path(data)? {
+          next_path = Some(path);
+        }
+        continue;
+      }
+      // pax global header
+      b'g' => continue,
+      b'0' | b'\0' | b'7' => TarEntryKind::File,
+      b'5' => TarEntryKind::Directory,
+      _ => TarEntryKind::Other,
+    };
+    let path = match next_path.take() {
+      Some(path) => path,
+      None => {
+        let name = parse_tar_string(&header[0..100]);
+        let prefix = if &header[257..262] == b"ustar" {
+          parse_tar_string(&header[345..500])
+        } else {
+          String::new()
+        };
+        if prefix.is_empty() {
+          name
+        } else {
+          format!("{}/{}", prefix, name)
+        }
+      }
+    };
+    entries.push(TarEntry {
+      path,
+      kind,
+      mode: parse_tar_number(&header[100..108]).ok().map(|mode| mode as u32),
+      data,
+    });
+  }
+  Ok(entries)
+}
+
+fn verify_tar_header_checksum(header: &[u8]) -> Result<()> {
+  let expected = parse_tar_number(&header[148..156])?;
+  // the checksum is calculated with the checksum field as spaces
+  let actual = header
+    .iter()
+    .enumerate()
+    .map(|(i, b)| if (148..156).contains(&i) { b' ' as u64 } else { *b as u64 })
+    .sum::<u64>();
+  if actual != expected {
+    bail!("Invalid tar header checksum.");
+  }
+  Ok(())
+}
+
+fn parse_tar_string(bytes: &[u8]) -> String {
+  let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+  String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn parse_tar_number(bytes: &[u8]) -> Result<u64> {
+  // gnu base-256 encoding for large numbers
+  if bytes.first().is_some_and(|b| b & 0x80 != 0) {
+    let mut value = (bytes[0] & 0x7f) as u64;
+    for b in &bytes[1..] {
+      value = value.checked_mul(256).ok_or_else(|| anyhow::anyhow!("Number in tar header was too large."))? + *b as u64;
+    }
+    return Ok(value);
+  }
+  let text = std::str::from_utf8(bytes)?.trim_matches(|c| c == '\0' || c == ' ');
+  if text.is_empty() {
+    return Ok(0);
+  }
+  match u64::from_str_radix(text, 8) {
+    Ok(value) => Ok(value),
+    Err(err) => bail!("Invalid number in tar header '{}': {:#}", text, err),
+  }
+}
+
+/// Gets the path from pax extended header records (ex. `30 path=some/long/path\n`).
+fn parse_pax_path(data: &[u8]) -> Result<Option<String>> {
+  let mut remaining = data;
+  let mut path = None;
+  while !remaining.is_empty() {
+    let Some(space_index) = remaining.iter().position(|b| *b == b' ') else {
+      bail!("Invalid pax extended header.");
+    };
+    let len = std::str::from_utf8(&remaining[..space_index])?.parse::<usize>()?;
+    if len <= space_index + 1 || len > remaining.len() {
+      bail!("Invalid pax extended header.");
+    }
+    let record = &remaining[space_index + 1..len - 1]; // exclude trailing newline
+    if let Some(value) = record.strip_prefix(b"path=") {
+      path = Some(String::from_utf8_lossy(value).into_owned());
+    }
+    remaining = &remaining[len..];
+  }
+  Ok(path)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironment;
+
+  fn tar_header(path: &str, type_flag: u8, size: usize) -> Vec<u8> {
+    let mut header = vec![0; TAR_BLOCK_SIZE];
+    header[..path.len()].copy_from_slice(path.as_bytes());
+    header[100..107].copy_from_slice(b"0000755");
+    header[124..135].copy_from_slice(format!("{:011o}", size).as_bytes());
+    header[156] = type_flag;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[148..156].copy_from_slice(b"        ");
+    let checksum = header.iter().map(|b| *b as u32).sum::<u32>();
+    header[148..155].copy_from_slice(format!("{:06o}\0", checksum).as_bytes());
+    header
+  }
+
+  fn tar_entry(tar: &mut Vec<u8>, path: &str, type_flag: u8, data: &[u8]) {
+    tar.extend(tar_header(path, type_flag, data.len()));
+    tar.extend(data);
+    tar.resize(tar.len().div_ceil(TAR_BLOCK_SIZE) * TAR_BLOCK_SIZE, 0);
+  }
+
+  fn create_tar() -> Vec<u8> {
+    let mut tar = Vec::new();
+    tar_entry(&mut tar, "dir/", b'5', b"");
+    tar_entry(&mut tar, "dir/plugin", b'0', b"executable");
+    let long_path = format!("dir/{}.txt", "a".repeat(120));
+    tar_entry(&mut tar, "././@LongLink", b'L', format!("{}\0", long_path).as_bytes());
+    tar_entry(&mut tar, "dir/aaaa", b'0', b"long");
+    tar_entry(&mut tar, "pax", b'x', b"20 path=dir/pax.txt\n");
+    tar_entry(&mut tar, "dir/short", b'0', b"pax");
+    tar_entry(&mut tar, "dir/link", b'2', b"");
+    tar_entry(&mut tar, "../escape.txt", b'0', b"escape");
+    tar.extend(vec![0; TAR_BLOCK_SIZE * 2]);
+    tar
+  }
+
+  fn assert_extracted(environment: &TestEnvironment) {
+    assert_eq!(environment.read_file("/output/dir/plugin").unwrap(), "executable");
+    assert_eq!(environment.read_file(format!("/output/dir/{}.txt", "a".repeat(120))).unwrap(), "long");
+    assert_eq!(environment.read_file("/output/dir/pax.txt").unwrap(), "pax");
+    assert!(!environment.path_exists("/output/dir/link"));
+    assert!(!environment.path_exists("/escape.txt"));
+    assert_eq!(
+      environment.take_stderr_messages(),
+      vec![
+        "Extracting".to_string(),
+        "Ignoring path in tar because it was not a file or directory: dir/link".to_string(),
+        "Ignoring path in tar because it was not enclosed: ../escape.txt".to_string(),
+      ]
+    );
+  }
+
+  #[test]
+  fn should_extract_tar_gz() {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&create_tar()).unwrap();
+    let bytes = encoder.finish().unwrap();
+    assert_eq!(ArchiveKind::detect(&bytes).unwrap(), ArchiveKind::TarGz);
+
+    let environment = TestEnvironment::new();
+    extract_archive("Extracting", ArchiveKind::TarGz, &bytes, Path::new("/output"), &environment).unwrap();
+    assert_extracted(&environment);
+  }
+
+  #[test]
+  fn should_extract_tar_zst() {
+    let bytes = zstd::encode_all(create_tar().as_slice(), 0).unwrap();
+    assert_eq!(ArchiveKind::detect(&bytes).unwrap(), ArchiveKind::TarZst);
+
+    let environment = TestEnvironment::new();
+    extract_archive("Extracting", ArchiveKind::TarZst, &bytes, Path::new("/output"), &environment).unwrap();
+    assert_extracted(&environment);
+  }
+
+  #[test]
+  fn should_detect_archive_kind() {
+    assert_eq!(ArchiveKind::detect(b"PK\x03\x04rest").unwrap(), ArchiveKind::Zip);
+    assert_eq!(
+      ArchiveKind::detect(b"other").unwrap_err().to_string(),
+      "Unsupported archive format. Expected a .zip, .tar.gz, or .tar.zst file."
+    );
+  }
+
+  #[test]
+  fn should_error_for_invalid_tar() {
+    let mut tar = create_tar();
+    tar[0] = b'x';
+    assert_eq!(parse_tar(&tar).unwrap_err().to_string(), "Invalid tar header checksum.");
+    let tar = create_tar();
+    assert_eq!(
+      parse_tar(&tar[..TAR_BLOCK_SIZE * 2 + 5]).unwrap_err().to_string(),
+      "Unexpected end of tar file."
+    );
+  }
+
+  #[test]
+  fn should_parse_tar_numbers() {
+    assert_eq!(parse_tar_number(b"0000755\0").unwrap(), 0o755);
+    assert_eq!(parse_tar_number(b"   12 \0").unwrap(), 0o12);
+    assert_eq!(parse_tar_number(b"\0\0\0").unwrap(), 0);
+    assert_eq!(parse_tar_number(&[0x80, 0, 0, 1, 0]).unwrap(), 256);
+  }
+}
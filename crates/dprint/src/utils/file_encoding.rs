@@ -0,0 +1,240 @@
+use anyhow::bail;
+use anyhow::Result;
+use encoding_rs::Encoding;
+use encoding_rs::UTF_16BE;
+use encoding_rs::UTF_16LE;
+
+/// Number of bytes to look at when detecting UTF-16 without a BOM.
+const UTF16_DETECTION_SAMPLE_LEN: usize = 1024;
+const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+
+/// How to handle the byte order mark of UTF-8 files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BomPolicy {
+  Preserve,
+  Remove,
+  Add,
+}
+
+impl BomPolicy {
+  /// Removes the UTF-8 BOM so plugins don't handle it, returning if the text had one.
+  pub fn strip(text: &[u8]) -> (&[u8], bool) {
+    match text.strip_prefix(&UTF8_BOM) {
+      Some(text) => (text, true),
+      None => (text, false),
+    }
+  }
+
+  /// Adds the UTF-8 BOM back to the formatted text when the file should have one.
+  pub fn apply(&self, formatted_text: Vec<u8>, had_bom: bool) -> Vec<u8> {
+    match self {
+      BomPolicy::Add => {}
+      BomPolicy::Preserve if had_bom => {}
+      BomPolicy::Preserve | BomPolicy::Remove => return formatted_text,
+    }
+    let mut text = Vec::with_capacity(UTF8_BOM.len() + formatted_text.len());
+    text.extend(UTF8_BOM);
+    text.extend(formatted_text);
+    text
+  }
+}
+
+/// The encoding of a file that isn't UTF-8, which is transcoded to
+/// UTF-8 for the plugins and back to the encoding when writing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileEncoding {
+  Utf16 {
+    big_endian: bool,
+    has_bom: bool,
+  },
+  /// The encoding of the "legacyEncoding" configuration property (ex. windows-1252).
+  Legacy(&'static Encoding),
+}
+
+impl FileEncoding {
+  /// Detects the encoding of the file's bytes, returning `None` for UTF-8.
+  ///
+  /// The legacy encoding is only used for files that aren't valid UTF-8 or UTF-16.
+  pub fn detect(bytes: &[u8], legacy_encoding: Option<&'static Encoding>) -> Option<FileEncoding> {
+    if bytes.starts_with(&[0xFF, 0xFE]) {
+      Some(FileEncoding::Utf16 {
+        big_endian: false,
+        has_bom: true,
+      })
+    } else if bytes.starts_with(&[0xFE, 0xFF]) {
+      Some(FileEncoding::Utf16 {
+        big_endian: true,
+        has_bom: true,
+      })
+    } else if let Some(big_endian) = detect_utf16_without_bom(bytes) {
+      Some(FileEncoding::Utf16 { big_endian, has_bom: false })
+    } else {
+      match legacy_encoding {
+        Some(encoding) if std::str::from_utf8(bytes).is_err() => Some(FileEncoding::Legacy(encoding)),
+        _ => None,
+      }
+    }
+  }
+
+  pub fn name(&self) -> &'static str {
+    self.encoding().name()
+  }
+
+  /// Decodes the bytes (without the BOM) to UTF-8 text.
+  pub fn decode(&self, bytes: &[u8]) -> Result<String> {
+    let bytes = match self {
+      FileEncoding::Utf16 { has_bom: true, .. } => &bytes[2..],
+      _ => bytes,
+    };
+    match self.encoding().decode_without_bom_handling_and_without_replacement(bytes) {
+      Some(text) => Ok(text.into_owned()),
+      None => bail!("Could not decode the file as {}.", self.name()),
+    }
+  }
+
+  /// Encodes the text in this encoding, adding back the BOM when the file had one.
+  pub fn encode(&self, text: &str) -> Result<Vec<u8>> {
+    match self {
+      FileEncoding::Utf16 { big_endian, has_bom } => {
+        let to_bytes = if *big_endian { u16::to_be_bytes } else { u16::to_le_bytes };
+        let mut bytes = Vec::with_capacity(text.len() * 2 + 2);
+        if *has_bom {
+          bytes.extend(to_bytes(0xFEFF));
+        }
+        for code_unit in text.encode_utf16() {
+          bytes.extend(to_bytes(code_unit));
+        }
+        Ok(bytes)
+      }
+      FileEncoding::Legacy(encoding) => {
+        let (bytes, _, had_unmappable_chars) = encoding.encode(text);
+        if had_unmappable_chars {
+          bail!("The formatted text has characters that can't be encoded as {}.", encoding.name());
+        }
+        Ok(bytes.into_owned())
+      }
+    }
+  }
+
+  fn encoding(&self) -> &'static Encoding {
+    match self {
+      FileEncoding::Utf16 { big_endian: true, .. } => UTF_16BE,
+      FileEncoding::Utf16 { big_endian: false, .. } => UTF_16LE,
+      FileEncoding::Legacy(encoding) => encoding,
+    }
+  }
+}
+
+/// Gets the encoding for the label of the "legacyEncoding" configuration property.
+pub fn get_legacy_encoding_for_label(label: &str) -> Result<&'static Encoding> {
+  match Encoding::for_label(label.as_bytes()) {
+    // UTF-8 and UTF-16 are already handled and can't be written by encoding_rs
+    Some(encoding) if encoding.output_encoding() == encoding && encoding != encoding_rs::UTF_8 => Ok(encoding),
+    _ => bail!(
+      "Unsupported encoding '{}'. Specify a legacy encoding such as \"windows-1252\" or \"shift_jis\".",
+      label
+    ),
+  }
+}
+
+/// Detects UTF-16 without a BOM by the zero bytes of ASCII
+/// characters, returning if it's big endian.
+fn detect_utf16_without_bom(bytes: &[u8]) -> Option<bool> {
+  if bytes.len() < 2 || bytes.len() % 2 == 1 {
+    return None;
+  }
+  let sample = &bytes[..std::cmp::min(bytes.len(), UTF16_DETECTION_SAMPLE_LEN)];
+  let code_unit_count = sample.len() / 2;
+  let even_zero_count = sample.iter().step_by(2).filter(|b| **b == 0).count();
+  let odd_zero_count = sample.iter().skip(1).step_by(2).filter(|b| **b == 0).count();
+  // most characters of text files are ascii, which has a zero high byte
+  if odd_zero_count * 2 > code_unit_count && even_zero_count == 0 {
+    Some(false)
+  } else if even_zero_count * 2 > code_unit_count && odd_zero_count == 0 {
+    Some(true)
+  } else {
+    None
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use encoding_rs::SHIFT_JIS;
+  use encoding_rs::WINDOWS_1252;
+
+  use super::*;
+
+  fn utf16_bytes(text: &str, big_endian: bool, has_bom: bool) -> Vec<u8> {
+    FileEncoding::Utf16 { big_endian, has_bom }.encode(text).unwrap()
+  }
+
+  #[test]
+  fn should_detect_utf16() {
+    for big_endian in [false, true] {
+      for has_bom in [false, true] {
+        let bytes = utf16_bytes("const a = \"ü\";\n", big_endian, has_bom);
+        let encoding = FileEncoding::detect(&bytes, None).unwrap();
+        assert_eq!(encoding, FileEncoding::Utf16 { big_endian, has_bom });
+        assert_eq!(encoding.decode(&bytes).unwrap(), "const a = \"ü\";\n");
+        assert_eq!(encoding.encode("const a = \"ü\";\n").unwrap(), bytes);
+      }
+    }
+  }
+
+  #[test]
+  fn should_not_detect_utf8() {
+    assert_eq!(FileEncoding::detect(b"", None), None);
+    assert_eq!(FileEncoding::detect(b"text\n", None), None);
+    assert_eq!(FileEncoding::detect("\u{FEFF}ü\n".as_bytes(), Some(WINDOWS_1252)), None);
+  }
+
+  #[test]
+  fn should_use_legacy_encoding_for_invalid_utf8() {
+    // "café" in windows-1252
+    let bytes = b"caf\xE9\n";
+    assert_eq!(FileEncoding::detect(bytes, None), None);
+    let encoding = FileEncoding::detect(bytes, Some(WINDOWS_1252)).unwrap();
+    assert_eq!(encoding, FileEncoding::Legacy(WINDOWS_1252));
+    assert_eq!(encoding.decode(bytes).unwrap(), "café\n");
+    assert_eq!(encoding.encode("café;\n").unwrap(), b"caf\xE9;\n");
+    assert_eq!(
+      encoding.encode("日本").err().unwrap().to_string(),
+      "The formatted text has characters that can't be encoded as windows-1252."
+    );
+  }
+
+  #[test]
+  fn should_error_decoding_invalid_text() {
+    let encoding = FileEncoding::Legacy(SHIFT_JIS);
+    assert_eq!(encoding.decode(b"\x81").err().unwrap().to_string(), "Could not decode the file as Shift_JIS.");
+  }
+
+  #[test]
+  fn should_apply_bom_policy() {
+    assert_eq!(BomPolicy::strip("\u{FEFF}text".as_bytes()), (b"text".as_slice(), true));
+    assert_eq!(BomPolicy::strip(b"text"), (b"text".as_slice(), false));
+    for (policy, had_bom, expected) in [
+      (BomPolicy::Preserve, true, "\u{FEFF}text"),
+      (BomPolicy::Preserve, false, "text"),
+      (BomPolicy::Remove, true, "text"),
+      (BomPolicy::Add, false, "\u{FEFF}text"),
+    ] {
+      assert_eq!(policy.apply(b"text".to_vec(), had_bom), expected.as_bytes());
+    }
+  }
+
+  #[test]
+  fn should_get_legacy_encoding_for_label() {
+    assert_eq!(get_legacy_encoding_for_label("latin1").unwrap(), WINDOWS_1252);
+    assert_eq!(get_legacy_encoding_for_label("shift_jis").unwrap(), SHIFT_JIS);
+    for label in ["utf-8", "utf-16le", "unknown"] {
+      assert_eq!(
+        get_legacy_encoding_for_label(label).err().unwrap().to_string(),
+        format!(
+          "Unsupported encoding '{}'. Specify a legacy encoding such as \"windows-1252\" or \"shift_jis\".",
+          label
+        )
+      );
+    }
+  }
+}
@@ -1,9 +1,66 @@
 use std::time::Duration;
 
 use crossterm::style::Stylize;
+use dissimilar::Chunk;
+use serde::Serialize;
 use similar::ChangeTag;
 use similar::TextDiffConfig;
 
+/// A replacement of a range in the old text.
+///
+/// The offsets are UTF-8 byte offsets into the old text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextEdit {
+  pub start: usize,
+  pub end: usize,
+  pub new_text: String,
+}
+
+/// Gets the minimal edits that transform the old text into the new text.
+///
+/// The edits are in order and don't overlap.
+pub fn get_text_edits(old_text: &str, new_text: &str) -> Vec<TextEdit> {
+  if old_text == new_text {
+    return Vec::new();
+  }
+  // heuristic to detect things like minified files because diffing is expensive
+  if new_text.matches('\n').count() > (old_text.matches('\n').count() + 2) * 3 {
+    return vec![TextEdit {
+      start: 0,
+      end: old_text.len(),
+      new_text: new_text.to_string(),
+    }];
+  }
+
+  let chunks = dissimilar::diff(old_text, new_text);
+  let mut edits = Vec::new();
+  let mut chunks = chunks.into_iter().peekable();
+  let mut old_pos = 0;
+  while let Some(chunk) = chunks.next() {
+    match chunk {
+      Chunk::Equal(text) => {
+        old_pos += text.len();
+      }
+      Chunk::Delete(text) => {
+        let start = old_pos;
+        old_pos += text.len();
+        let new_text = match chunks.next_if(|c| matches!(c, Chunk::Insert(_))) {
+          Some(Chunk::Insert(text)) => text.to_string(),
+          _ => String::new(),
+        };
+        edits.push(TextEdit { start, end: old_pos, new_text });
+      }
+      Chunk::Insert(text) => edits.push(TextEdit {
+        start: old_pos,
+        end: old_pos,
+        new_text: text.to_string(),
+      }),
+    }
+  }
+  edits
+}
+
 /// Gets a string showing the difference between two strings.
 pub fn get_difference(old_text: &str, new_text: &str) -> String {
   debug_assert!(old_text != new_text);
@@ -119,6 +176,66 @@ mod test {
   use super::*;
   use pretty_assertions::assert_eq;
 
+  fn apply_text_edits(text: &str, edits: &[TextEdit]) -> String {
+    let mut result = String::new();
+    let mut last_end = 0;
+    for edit in edits {
+      result.push_str(&text[last_end..edit.start]);
+      result.push_str(&edit.new_text);
+      last_end = edit.end;
+    }
+    result.push_str(&text[last_end..]);
+    result
+  }
+
+  #[test]
+  fn should_get_text_edits() {
+    assert_eq!(get_text_edits("test", "test"), vec![]);
+    let edits = get_text_edits("const t = 'ä';\nlet a", "const t = \"ä\";\nlet a;\n");
+    assert_eq!(
+      edits,
+      vec![
+        TextEdit {
+          start: 10,
+          end: 14,
+          new_text: "\"ä\"".to_string(),
+        },
+        TextEdit {
+          start: 21,
+          end: 21,
+          new_text: ";\n".to_string(),
+        },
+      ]
+    );
+    assert_eq!(serde_json::to_string(&edits[0]).unwrap(), r#"{"start":10,"end":14,"newText":"\"ä\""}"#);
+  }
+
+  #[test]
+  fn should_get_text_edits_that_apply() {
+    let cases = [
+      ("class Test\n{\n\n}", "class Test {\n}\n"),
+      ("a👍b\r\nc", "a😃b\nc\n"),
+      ("", "new"),
+      ("old", ""),
+    ];
+    for (old_text, new_text) in cases {
+      let edits = get_text_edits(old_text, new_text);
+      assert_eq!(apply_text_edits(old_text, &edits), new_text);
+    }
+  }
+
+  #[test]
+  fn should_replace_all_text_for_minified_text() {
+    assert_eq!(
+      get_text_edits("a;b;", "a;\nb;\n\n\n\n\n\n\n"),
+      vec![TextEdit {
+        start: 0,
+        end: 4,
+        new_text: "a;\nb;\n\n\n\n\n\n\n".to_string(),
+      }]
+    );
+  }
+
   #[test]
   fn should_get_when_differs_by_line_endings() {
     assert_eq!(get_difference("test\r\n", "test\n"), " | Text differed by line endings.");
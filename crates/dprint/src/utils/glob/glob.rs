@@ -60,13 +60,17 @@ pub fn glob(environment: &impl Environment, opts: GlobOptions) -> Result<GlobOut
     },
   )?;
 
-  let shared_state = Arc::new(SharedState::new(opts.start_dir.clone()));
+  // one thread is used for the glob matching
+  let read_dir_thread_count = std::cmp::max(1, environment.max_threads().saturating_sub(1));
+  let shared_state = Arc::new(SharedState::new(opts.start_dir.clone(), read_dir_thread_count));
 
   // This is a performance improvement to attempt to reduce the time of globbing down
   // to the speed of `fs::read_dir` calls. Essentially, run all the `fs::read_dir` calls
-  // on a new thread and do the glob matching on the other thread.
-  let read_dir_runner = ReadDirRunner::new(opts.start_dir, environment.clone(), shared_state.clone());
-  dprint_core::async_runtime::spawn_blocking(move || read_dir_runner.run());
+  // on other threads in parallel and do the glob matching on the current thread.
+  for _ in 0..read_dir_thread_count {
+    let read_dir_runner = ReadDirRunner::new(opts.start_dir.clone(), environment.clone(), shared_state.clone());
+    dprint_core::async_runtime::spawn_blocking(move || read_dir_runner.run());
+  }
 
   // run the glob matching on the current thread (the two threads will communicate with each other)
   let mut glob_matching_processor = GlobMatchingProcessor::new(shared_state, glob_matcher, git_ignore_tree);
@@ -111,7 +115,7 @@ impl<TEnvironment: Environment> ReadDirRunner<TEnvironment> {
     while let Some(pending_dirs) = self.get_next_pending_dirs() {
       let mut pending_count = 0;
       let mut all_entries = Vec::new();
-      for current_dir in pending_dirs.into_iter().flatten() {
+      for current_dir in pending_dirs {
         let info_result = self.environment.dir_info(&current_dir);
         let entries = match info_result {
           Ok(entries) => {
@@ -169,27 +173,31 @@ impl<TEnvironment: Environment> ReadDirRunner<TEnvironment> {
           pending_count = 0;
         }
       }
-      if !all_entries.is_empty() {
-        self.push_entries(all_entries);
-      }
+      self.complete_pending_dirs(all_entries);
     }
   }
 
-  fn get_next_pending_dirs(&self) -> Option<Vec<Vec<PathBuf>>> {
+  fn get_next_pending_dirs(&self) -> Option<Vec<PathBuf>> {
     let (ref lock, ref cvar) = &self.shared_state.inner;
     let mut state = lock.lock();
     loop {
+      if state.read_dir_error.is_some() {
+        return None;
+      }
       if !state.pending_dirs.is_empty() {
-        state.read_dir_thread_state = ReadDirThreadState::Processing;
-        cvar.notify_one();
-        return Some(std::mem::take(&mut state.pending_dirs));
+        // split the work up between the read dir threads
+        let take_count = state.pending_dirs.len().div_ceil(self.shared_state.read_dir_thread_count);
+        let split_index = state.pending_dirs.len() - take_count;
+        let pending_dirs = state.pending_dirs.split_off(split_index);
+        state.active_read_dir_threads += 1;
+        return Some(pending_dirs);
       }
-      state.read_dir_thread_state = ReadDirThreadState::Waiting;
-      cvar.notify_one();
-      if matches!(state.processing_thread_state, ProcessingThreadState::Waiting) && state.pending_entries.is_empty() {
+      if state.active_read_dir_threads == 0 && matches!(state.processing_thread_state, ProcessingThreadState::Waiting) && state.pending_entries.is_empty() {
+        // wake up the other threads so they also exit
+        cvar.notify_all();
         return None;
       } else {
-        // wait to be notified by the other thread
+        // wait to be notified by another thread
         cvar.wait(&mut state);
       }
     }
@@ -198,15 +206,26 @@ impl<TEnvironment: Environment> ReadDirRunner<TEnvironment> {
   fn set_glob_error(&self, error: Error) {
     let (ref lock, ref cvar) = &self.shared_state.inner;
     let mut state = lock.lock();
-    state.read_dir_thread_state = ReadDirThreadState::Error(error);
-    cvar.notify_one();
+    state.active_read_dir_threads -= 1;
+    state.read_dir_error = Some(error);
+    cvar.notify_all();
   }
 
   fn push_entries(&self, entries: Vec<DirEntries>) {
     let (ref lock, ref cvar) = &self.shared_state.inner;
     let mut state = lock.lock();
     state.pending_entries.push(entries);
-    cvar.notify_one();
+    cvar.notify_all();
+  }
+
+  fn complete_pending_dirs(&self, entries: Vec<DirEntries>) {
+    let (ref lock, ref cvar) = &self.shared_state.inner;
+    let mut state = lock.lock();
+    if !entries.is_empty() {
+      state.pending_entries.push(entries);
+    }
+    state.active_read_dir_threads -= 1;
+    cvar.notify_all();
   }
 }
 
@@ -293,8 +312,8 @@ impl<TEnvironment: Environment> GlobMatchingProcessor<TEnvironment> {
   fn push_pending_dirs(&self, pending_dirs: Vec<PathBuf>) {
     let (ref lock, ref cvar) = &self.shared_state.inner;
     let mut state = lock.lock();
-    state.pending_dirs.push(pending_dirs);
-    cvar.notify_one();
+    state.pending_dirs.extend(pending_dirs);
+    cvar.notify_all();
   }
 
   fn get_next_entries(&self) -> Result<Option<Vec<Vec<DirEntries>>>> {
@@ -307,63 +326,54 @@ impl<TEnvironment: Environment> GlobMatchingProcessor<TEnvironment> {
       }
       if !matches!(state.processing_thread_state, ProcessingThreadState::Waiting) {
         state.processing_thread_state = ProcessingThreadState::Waiting;
-        cvar.notify_one();
+        cvar.notify_all();
       }
-      match &state.read_dir_thread_state {
-        ReadDirThreadState::Waiting => {
-          if state.pending_dirs.is_empty() {
-            return Ok(None);
-          } else {
-            // wait to be notified by the other thread
-            cvar.wait(&mut state);
-          }
-        }
-        ReadDirThreadState::Error(err) => {
-          return Err(anyhow!("{:#}", err));
-        }
-        ReadDirThreadState::Processing => {
-          // wait to be notified by the other thread
-          cvar.wait(&mut state);
-        }
+      if let Some(err) = &state.read_dir_error {
+        return Err(anyhow!("{:#}", err));
+      }
+      if state.active_read_dir_threads == 0 && state.pending_dirs.is_empty() {
+        return Ok(None);
+      } else {
+        // wait to be notified by the read dir threads
+        cvar.wait(&mut state);
       }
     }
   }
 }
 
-enum ReadDirThreadState {
-  Processing,
-  Waiting,
-  Error(Error),
-}
-
 enum ProcessingThreadState {
   Processing,
   Waiting,
 }
 
 struct SharedStateInternal {
-  pending_dirs: Vec<Vec<PathBuf>>,
+  pending_dirs: Vec<PathBuf>,
   pending_entries: Vec<Vec<DirEntries>>,
-  read_dir_thread_state: ReadDirThreadState,
+  /// Number of read dir threads currently reading directories.
+  active_read_dir_threads: usize,
+  read_dir_error: Option<Error>,
   processing_thread_state: ProcessingThreadState,
 }
 
 struct SharedState {
   inner: (Mutex<SharedStateInternal>, Condvar),
+  read_dir_thread_count: usize,
 }
 
 impl SharedState {
-  pub fn new(initial_dir: PathBuf) -> Self {
+  pub fn new(initial_dir: PathBuf, read_dir_thread_count: usize) -> Self {
     SharedState {
       inner: (
         Mutex::new(SharedStateInternal {
           processing_thread_state: ProcessingThreadState::Waiting,
-          read_dir_thread_state: ReadDirThreadState::Processing,
-          pending_dirs: vec![vec![initial_dir]],
+          active_read_dir_threads: 0,
+          read_dir_error: None,
+          pending_dirs: vec![initial_dir],
           pending_entries: Vec::new(),
         }),
         Condvar::new(),
       ),
+      read_dir_thread_count,
     }
   }
 }
@@ -426,6 +436,36 @@ mod test {
     assert_eq!(result, expected_matches);
   }
 
+  #[tokio::test]
+  async fn should_glob_with_single_thread() {
+    let environment = TestEnvironmentBuilder::new()
+      .write_file("/a.txt", "")
+      .write_file("/dir/b.txt", "")
+      .write_file("/dir/sub/c.txt", "")
+      .write_file("/dir/sub/d.ps", "")
+      .build();
+    environment.set_max_threads(1);
+    let root_dir = environment.canonicalize("/").unwrap();
+    let result = glob(
+      &environment,
+      GlobOptions {
+        start_dir: PathBuf::from("/"),
+        file_patterns: GlobPatterns {
+          arg_includes: None,
+          config_includes: Some(vec![GlobPattern::new("**/*.txt".to_string(), root_dir)]),
+          arg_excludes: None,
+          config_excludes: Vec::new(),
+        },
+        pattern_base: CanonicalizedPathBuf::new_for_testing("/"),
+      },
+    )
+    .unwrap();
+
+    let mut result = result.file_paths.into_iter().map(|r| r.to_string_lossy().to_string()).collect::<Vec<_>>();
+    result.sort();
+    assert_eq!(result, vec!["/a.txt", "/dir/b.txt", "/dir/sub/c.txt"]);
+  }
+
   #[tokio::test]
   async fn should_handle_dir_info_erroring() {
     let environment = TestEnvironmentBuilder::new().build();
@@ -8,6 +8,7 @@ use ignore::overrides::Override;
 use ignore::overrides::OverrideBuilder;
 use ignore::Match;
 
+use crate::environment::normalize_verbatim_path;
 use crate::environment::CanonicalizedPathBuf;
 
 use super::GlobPattern;
@@ -101,6 +102,7 @@ impl GlobMatcher {
   }
 
   /// Gets if the matcher only has excludes patterns.
+  #[cfg(test)]
   pub fn has_only_excludes(&self) -> bool {
     (self.config_include_matcher.as_ref().map(|m| m.is_empty()).unwrap_or(true) && self.arg_include_matcher.as_ref().map(|m| m.is_empty()).unwrap_or(true))
       && (!self.config_exclude_matcher.is_empty() || !self.arg_exclude_matcher.as_ref().map(|m| m.is_empty()).unwrap_or(true))
@@ -114,28 +116,8 @@ impl GlobMatcher {
   }
 
   pub fn matches_detail(&self, path: impl AsRef<Path>) -> GlobMatchesDetail {
-    let path = path.as_ref();
-    let path = if path.is_absolute() && path.starts_with(&self.base_dir) {
-      if let Ok(prefix) = path.strip_prefix(&self.base_dir) {
-        Cow::Borrowed(prefix)
-      } else {
-        // this is a very strange state that we want to know more about,
-        // so just always log directly to stderr in this scenario and maybe
-        // eventually remove this code.
-        #[allow(clippy::print_stderr)]
-        {
-          eprintln!(
-            "WARNING: Path prefix error for {} and {}. Please report this error in issue #540.",
-            &self.base_dir.display(),
-            path.display()
-          );
-        }
-        return GlobMatchesDetail::NotMatched;
-      }
-    } else if !path.is_absolute() {
-      Cow::Owned(self.base_dir.join(path))
-    } else {
-      Cow::Borrowed(path)
+    let Some(path) = get_match_path(&self.base_dir, path.as_ref()) else {
+      return GlobMatchesDetail::NotMatched;
     };
 
     let matched_result = match self.check_exclude(&path, false) {
@@ -193,6 +175,37 @@ impl GlobMatcher {
   }
 }
 
+/// Gets the path to use for matching against patterns relative to the base directory.
+pub(super) fn get_match_path<'a>(base_dir: &CanonicalizedPathBuf, path: &'a Path) -> Option<Cow<'a, Path>> {
+  let path = match normalize_verbatim_path(path) {
+    Cow::Borrowed(path) => path,
+    // compare in the same form as the base directory
+    Cow::Owned(path) => return get_match_path(base_dir, &path).map(|p| Cow::Owned(p.into_owned())),
+  };
+  if path.is_absolute() && path.starts_with(base_dir) {
+    if let Ok(prefix) = path.strip_prefix(base_dir) {
+      Some(Cow::Borrowed(prefix))
+    } else {
+      // this is a very strange state that we want to know more about,
+      // so just always log directly to stderr in this scenario and maybe
+      // eventually remove this code.
+      #[allow(clippy::print_stderr)]
+      {
+        eprintln!(
+          "WARNING: Path prefix error for {} and {}. Please report this error in issue #540.",
+          base_dir.display(),
+          path.display()
+        );
+      }
+      None
+    }
+  } else if !path.is_absolute() {
+    Some(Cow::Owned(base_dir.join(path)))
+  } else {
+    Some(Cow::Borrowed(path))
+  }
+}
+
 fn build_override(patterns: &[GlobPattern], opts: &GlobMatcherOptions, base_dir: &CanonicalizedPathBuf) -> Result<Override> {
   let mut builder = OverrideBuilder::new(base_dir);
   let builder = builder.case_insensitive(!opts.case_sensitive)?;
@@ -217,7 +230,7 @@ fn build_gitignore(patterns: &[GlobPattern], opts: &GlobMatcherOptions, base_dir
   Ok(builder.build()?)
 }
 
-fn normalize_pattern(pattern: &GlobPattern) -> Cow<str> {
+pub(super) fn normalize_pattern(pattern: &GlobPattern) -> Cow<str> {
   // change patterns that start with ./ to be at the "root" of the globbing
   if pattern.relative_pattern.starts_with("!./") {
     Cow::Owned(format!("!/{}", &pattern.relative_pattern[3..]))
@@ -334,4 +347,28 @@ mod test {
     assert!(glob_matcher.matches("\\?\\UNC\\wsl$\\Ubuntu\\home\\david\\dir\\other.ts"));
     assert!(!glob_matcher.matches("\\?\\UNC\\wsl$\\Ubuntu\\home\\david\\no-match.ts"));
   }
+
+  #[cfg(target_os = "windows")]
+  #[test]
+  fn works_extended_length_paths() {
+    let cwd = CanonicalizedPathBuf::new_for_testing("\\\\?\\UNC\\server\\share\\project");
+    assert_eq!(cwd.display().to_string(), "\\\\server\\share\\project");
+    let glob_matcher = GlobMatcher::new(
+      GlobPatterns {
+        arg_includes: None,
+        config_includes: Some(vec![GlobPattern::new("**/*.ts".to_string(), cwd.clone())]),
+        arg_excludes: None,
+        config_excludes: vec![GlobPattern::new("no-match.ts".to_string(), cwd.clone())],
+      },
+      &GlobMatcherOptions {
+        case_sensitive: true,
+        base_dir: cwd,
+      },
+    )
+    .unwrap();
+    let deep_dir = vec!["node_modules"; 30].join("\\");
+    assert!(glob_matcher.matches(format!("\\\\server\\share\\project\\{}\\match.ts", deep_dir)));
+    assert!(glob_matcher.matches(format!("\\\\?\\UNC\\server\\share\\project\\{}\\match.ts", deep_dir)));
+    assert!(!glob_matcher.matches("\\\\?\\UNC\\server\\share\\project\\no-match.ts"));
+  }
 }
@@ -0,0 +1,416 @@
+use std::path::Path;
+
+use anyhow::Result;
+use globset::Candidate;
+use globset::GlobBuilder;
+use globset::GlobSet;
+use globset::GlobSetBuilder;
+
+use crate::environment::CanonicalizedPathBuf;
+
+use super::glob_matcher::get_match_path;
+use super::glob_matcher::normalize_pattern;
+use super::ExcludeMatchDetail;
+use super::GlobMatcherOptions;
+use super::GlobMatchesDetail;
+use super::GlobPattern;
+use super::GlobPatterns;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum PatternKind {
+  ConfigInclude = 0,
+  ArgInclude = 1,
+  ConfigExclude = 2,
+  ArgExclude = 3,
+}
+
+struct PatternEntry {
+  matcher_index: usize,
+  kind: PatternKind,
+  is_whitelist: bool,
+  is_only_dir: bool,
+}
+
+struct MatcherInfo {
+  has_config_includes: bool,
+  has_arg_includes: bool,
+  has_only_excludes: bool,
+}
+
+/// Matches a path against the patterns of many matchers in a single pass.
+///
+/// This gives the same results as a `GlobMatcher` per `GlobPatterns`, but the
+/// cost of matching a path does not grow with the number of matchers. All the
+/// patterns are made relative to the base directory of the provided options.
+pub struct GlobMatcherSet {
+  base_dir: CanonicalizedPathBuf,
+  set: GlobSet,
+  entries: Vec<PatternEntry>,
+  matchers: Vec<MatcherInfo>,
+}
+
+impl GlobMatcherSet {
+  pub fn new(patterns: Vec<GlobPatterns>, opts: &GlobMatcherOptions) -> Result<GlobMatcherSet> {
+    let base_dir = opts.base_dir.clone();
+    let mut builder = GlobSetBuilder::new();
+    let mut entries = Vec::new();
+    let mut matchers = Vec::with_capacity(patterns.len());
+
+    for (matcher_index, patterns) in patterns.into_iter().enumerate() {
+      let mut add_patterns = |patterns: Vec<GlobPattern>, kind: PatternKind| -> Result<usize> {
+        let mut count = 0;
+        for pattern in patterns.into_iter().filter_map(|p| p.into_new_base(base_dir.clone())) {
+          let Some(line) = parse_gitignore_line(&normalize_pattern(&pattern)) else {
+            continue;
+          };
+          builder.add(
+            GlobBuilder::new(&line.glob)
+              .literal_separator(true)
+              .case_insensitive(!opts.case_sensitive)
+              .backslash_escape(true)
+              .build()?,
+          );
+          entries.push(PatternEntry {
+            matcher_index,
+            kind,
+            is_whitelist: line.is_whitelist,
+            is_only_dir: line.is_only_dir,
+          });
+          count += 1;
+        }
+        Ok(count)
+      };
+
+      let has_config_includes = patterns.config_includes.is_some();
+      let has_arg_includes = patterns.arg_includes.is_some();
+      let config_include_count = add_patterns(patterns.config_includes.unwrap_or_default(), PatternKind::ConfigInclude)?;
+      let arg_include_count = add_patterns(patterns.arg_includes.unwrap_or_default(), PatternKind::ArgInclude)?;
+      let exclude_count =
+        add_patterns(patterns.config_excludes, PatternKind::ConfigExclude)? + add_patterns(patterns.arg_excludes.unwrap_or_default(), PatternKind::ArgExclude)?;
+      matchers.push(MatcherInfo {
+        has_config_includes,
+        has_arg_includes,
+        has_only_excludes: config_include_count == 0 && arg_include_count == 0 && exclude_count > 0,
+      });
+    }
+
+    Ok(GlobMatcherSet {
+      base_dir,
+      set: builder.build()?,
+      entries,
+      matchers,
+    })
+  }
+
+  #[cfg(test)]
+  pub fn len(&self) -> usize {
+    self.matchers.len()
+  }
+
+  /// Gets if the matcher at the provided index only has excludes patterns.
+  pub fn has_only_excludes(&self, index: usize) -> bool {
+    self.matchers[index].has_only_excludes
+  }
+
+  /// Gets the match detail of each matcher for the provided path.
+  pub fn matches_detail(&self, path: impl AsRef<Path>) -> Vec<GlobMatchesDetail> {
+    let Some(path) = get_match_path(&self.base_dir, path.as_ref()) else {
+      return vec![GlobMatchesDetail::NotMatched; self.matchers.len()];
+    };
+    let path = strip_base_dir(self.base_dir.as_ref(), &path);
+
+    // the last matching pattern of each kind wins, same as in a gitignore file
+    let mut last_matches = vec![[None::<bool>; 4]; self.matchers.len()];
+    for index in self.set.matches_candidate(&Candidate::new(path)) {
+      let entry = &self.entries[index];
+      if !entry.is_only_dir {
+        last_matches[entry.matcher_index][entry.kind as usize] = Some(entry.is_whitelist);
+      }
+    }
+
+    self
+      .matchers
+      .iter()
+      .zip(last_matches)
+      .map(|(matcher, last_matches)| {
+        let mut exclude_result = ExcludeMatchDetail::NotExcluded;
+        for kind in [PatternKind::ConfigExclude, PatternKind::ArgExclude] {
+          match last_matches[kind as usize] {
+            Some(true) => exclude_result = ExcludeMatchDetail::OptedOutExclude,
+            Some(false) => exclude_result = ExcludeMatchDetail::Excluded,
+            None => {}
+          }
+        }
+        let matched_result = match exclude_result {
+          ExcludeMatchDetail::Excluded => return GlobMatchesDetail::Excluded,
+          ExcludeMatchDetail::OptedOutExclude => GlobMatchesDetail::MatchedOptedOutExclude,
+          ExcludeMatchDetail::NotExcluded => GlobMatchesDetail::Matched,
+        };
+        // includes are overrides, so a non-negated pattern needs to be the last match
+        let is_included = |has_includes: bool, kind: PatternKind| !has_includes || last_matches[kind as usize] == Some(false);
+        if is_included(matcher.has_arg_includes, PatternKind::ArgInclude) && is_included(matcher.has_config_includes, PatternKind::ConfigInclude) {
+          matched_result
+        } else {
+          GlobMatchesDetail::NotMatched
+        }
+      })
+      .collect()
+  }
+}
+
+struct GitignoreLine {
+  glob: String,
+  is_whitelist: bool,
+  is_only_dir: bool,
+}
+
+/// Parses a line the same way as `ignore::gitignore::GitignoreBuilder::add_line`.
+fn parse_gitignore_line(mut line: &str) -> Option<GitignoreLine> {
+  if line.starts_with('#') {
+    return None;
+  }
+  if !line.ends_with("\\ ") {
+    line = line.trim_end();
+  }
+  if line.is_empty() {
+    return None;
+  }
+  let mut is_whitelist = false;
+  let mut is_only_dir = false;
+  let mut is_absolute = false;
+  if line.starts_with("\\!") || line.starts_with("\\#") {
+    line = &line[1..];
+    is_absolute = line.starts_with('/');
+  } else {
+    if let Some(value) = line.strip_prefix('!') {
+      is_whitelist = true;
+      line = value;
+    }
+    if let Some(value) = line.strip_prefix('/') {
+      // only match at the start of the path
+      line = value;
+      is_absolute = true;
+    }
+  }
+  if let Some(value) = line.strip_suffix('/') {
+    is_only_dir = true;
+    line = value.strip_suffix('\\').unwrap_or(value);
+  }
+  let mut glob = line.to_string();
+  if !is_absolute && !line.contains('/') && !glob.starts_with("**/") && glob != "**" {
+    glob = format!("**/{}", glob);
+  }
+  if glob.ends_with("/**") {
+    glob.push_str("/*");
+  }
+  Some(GitignoreLine {
+    glob,
+    is_whitelist,
+    is_only_dir,
+  })
+}
+
+/// Strips the base directory the same way as the `ignore` crate's matchers.
+fn strip_base_dir<'a>(base_dir: &Path, path: &'a Path) -> &'a Path {
+  let mut path = strip_path_prefix(Path::new("./"), path).unwrap_or(path);
+  let is_file_name = path.parent().map(|p| p.as_os_str().is_empty()).unwrap_or(false);
+  if base_dir != Path::new(".") && !is_file_name {
+    if let Some(stripped) = strip_path_prefix(base_dir, path) {
+      path = strip_path_prefix(Path::new("/"), stripped).unwrap_or(stripped);
+    }
+  }
+  path
+}
+
+#[cfg(unix)]
+fn strip_path_prefix<'a>(prefix: &Path, path: &'a Path) -> Option<&'a Path> {
+  use std::ffi::OsStr;
+  use std::os::unix::ffi::OsStrExt;
+
+  let bytes = path.as_os_str().as_bytes();
+  bytes
+    .strip_prefix(prefix.as_os_str().as_bytes())
+    .map(|remaining| Path::new(OsStr::from_bytes(remaining)))
+}
+
+#[cfg(not(unix))]
+fn strip_path_prefix<'a>(prefix: &Path, path: &'a Path) -> Option<&'a Path> {
+  path.strip_prefix(prefix).ok()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::utils::GlobMatcher;
+
+  fn get_patterns(includes: Option<&[&str]>, excludes: &[&str], base_dir: &CanonicalizedPathBuf) -> GlobPatterns {
+    GlobPatterns {
+      arg_includes: None,
+      config_includes: includes.map(|includes| GlobPattern::new_vec(includes.iter().map(|p| p.to_string()).collect(), base_dir.clone())),
+      arg_excludes: None,
+      config_excludes: GlobPattern::new_vec(excludes.iter().map(|p| p.to_string()).collect(), base_dir.clone()),
+    }
+  }
+
+  #[test]
+  fn should_match_same_as_glob_matchers() {
+    let base_dir = CanonicalizedPathBuf::new_for_testing("/testing/dir");
+    let opts = GlobMatcherOptions {
+      case_sensitive: true,
+      base_dir: base_dir.clone(),
+    };
+    let patterns: Vec<(Option<&[&str]>, &[&str])> = vec![
+      (Some(&["*.ts"]), &["no-match.ts"]),
+      (
+        Some(&["src/**/*.json", "./root.md", "/abs/*.txt"]),
+        &["src/ignored/**", "!src/ignored/keep.json"],
+      ),
+      (None, &["*.js", "dir/"]),
+      (Some(&[]), &[]),
+      (Some(&["**/*.{ts,js}", "!sub/*.ts"]), &[]),
+      (Some(&["a/**", "*.TS"]), &["\\#hash.ts", "a/b/**"]),
+    ];
+    let matchers = patterns
+      .iter()
+      .map(|(includes, excludes)| GlobMatcher::new(get_patterns(*includes, excludes, &base_dir), &opts).unwrap())
+      .collect::<Vec<_>>();
+    let matcher_set = GlobMatcherSet::new(
+      patterns
+        .iter()
+        .map(|(includes, excludes)| get_patterns(*includes, excludes, &base_dir))
+        .collect(),
+      &opts,
+    )
+    .unwrap();
+    assert_eq!(matcher_set.len(), matchers.len());
+
+    let paths = [
+      "/testing/dir/match.ts",
+      "/testing/dir/no-match.ts",
+      "/testing/dir/sub/file.ts",
+      "/testing/dir/sub/file.js",
+      "/testing/dir/src/data.json",
+      "/testing/dir/src/nested/data.json",
+      "/testing/dir/src/ignored/data.json",
+      "/testing/dir/src/ignored/keep.json",
+      "/testing/dir/root.md",
+      "/testing/dir/other/root.md",
+      "/testing/dir/abs/file.txt",
+      "/testing/dir/dir/file.txt",
+      "/testing/dir/a/b/c.ts",
+      "/testing/dir/a/c.ts",
+      "/testing/dir/UPPER.TS",
+      "/testing/dir/#hash.ts",
+      "/testing/dirother/file.ts",
+      "/some/other/dir/file.ts",
+      "relative/file.ts",
+      "file.js",
+    ];
+    for path in paths {
+      let expected = matchers.iter().map(|m| m.matches_detail(path)).collect::<Vec<_>>();
+      assert_eq!(matcher_set.matches_detail(path), expected, "Path: {}", path);
+    }
+    for (index, matcher) in matchers.iter().enumerate() {
+      assert_eq!(matcher_set.has_only_excludes(index), matcher.has_only_excludes(), "Index: {}", index);
+    }
+  }
+
+  #[test]
+  fn should_parse_lines_same_as_gitignore() {
+    let base_dir = Path::new("/testing/dir");
+    let lines = [
+      "# comment",
+      "",
+      "   ",
+      "file.ts",
+      "file.ts   ",
+      "file\\ ",
+      "*.ts",
+      "?.ts",
+      "[ab].ts",
+      "[!ab].ts",
+      "{a,b}.ts",
+      "sub/file.ts",
+      "sub/*.ts",
+      "/file.ts",
+      "/sub/file.ts",
+      "./file.ts",
+      "dir/",
+      "/dir/",
+      "sub/dir/",
+      "dir\\/",
+      "!file.ts",
+      "!/sub/file.ts",
+      "!dir/",
+      "\\!file.ts",
+      "\\#file.ts",
+      "\\#/file.ts",
+      "**",
+      "**/file.ts",
+      "**/sub/file.ts",
+      "sub/**",
+      "sub/**/file.ts",
+      "/sub/**",
+      "sub/**/",
+      "**/dir/**",
+    ];
+    let paths = [
+      "/testing/dir/file.ts",
+      "/testing/dir/a.ts",
+      "/testing/dir/c.ts",
+      "/testing/dir/file ",
+      "/testing/dir/dir",
+      "/testing/dir/!file.ts",
+      "/testing/dir/#file.ts",
+      "/testing/dir/sub/file.ts",
+      "/testing/dir/sub/a.ts",
+      "/testing/dir/sub/dir",
+      "/testing/dir/sub/nested/file.ts",
+      "/testing/dir/other/sub/file.ts",
+      "/testing/dir/other/dir",
+      "/testing/dir/dir/file.ts",
+      "/testing/dirother/file.ts",
+      "/other/file.ts",
+      "./file.ts",
+      "./sub/file.ts",
+      "sub/file.ts",
+      "file.ts",
+      "dir",
+    ];
+    for case_sensitive in [true, false] {
+      for line in lines {
+        let mut builder = ignore::gitignore::GitignoreBuilder::new(base_dir);
+        builder.case_insensitive(!case_sensitive).unwrap();
+        builder.add_line(None, line).unwrap();
+        let gitignore = builder.build().unwrap();
+        let parsed_line = parse_gitignore_line(line);
+        let glob = parsed_line.as_ref().map(|parsed_line| {
+          GlobBuilder::new(&parsed_line.glob)
+            .literal_separator(true)
+            .case_insensitive(!case_sensitive)
+            .backslash_escape(true)
+            .build()
+            .unwrap()
+            .compile_matcher()
+        });
+        for path in paths {
+          for is_dir in [false, true] {
+            let path = Path::new(path);
+            let expected = match gitignore.matched(path, is_dir) {
+              ignore::Match::None => None,
+              ignore::Match::Ignore(_) => Some(false),
+              ignore::Match::Whitelist(_) => Some(true),
+            };
+            let actual = match (&parsed_line, &glob) {
+              (Some(parsed_line), Some(glob)) if (is_dir || !parsed_line.is_only_dir) && glob.is_match(strip_base_dir(base_dir, path)) => {
+                Some(parsed_line.is_whitelist)
+              }
+              _ => None,
+            };
+            assert_eq!(actual, expected, "Line: {:?}, Path: {}, Is dir: {}", line, path.display(), is_dir);
+          }
+        }
+      }
+    }
+  }
+}
@@ -1,10 +1,12 @@
 #[allow(clippy::module_inception)]
 mod glob;
 mod glob_matcher;
+mod glob_matcher_set;
 mod glob_pattern;
 mod glob_utils;
 
 pub use glob::*;
 pub use glob_matcher::*;
+pub use glob_matcher_set::*;
 pub use glob_pattern::*;
 pub use glob_utils::*;
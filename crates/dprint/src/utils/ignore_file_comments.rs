@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::get_lowercase_file_extension;
+
+const IGNORE_FILE_DIRECTIVE: &[u8] = b"dprint-ignore-file";
+/// Number of lines at the start of a file to search for the directive.
+const IGNORE_FILE_DIRECTIVE_LINE_COUNT: usize = 5;
+
+/// Comment syntax by file extension used to find a `dprint-ignore-file`
+/// directive, which skips formatting the file regardless of the plugin.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IgnoreFileComments {
+  /// Comment starts from the "ignoreFileComments" configuration property
+  /// that replace the defaults of an extension.
+  overrides: HashMap<String, Vec<String>>,
+}
+
+impl IgnoreFileComments {
+  /// Sets the comment starts of an extension unless a configuration
+  /// file that extends this one already set them.
+  pub fn set_if_absent(&mut self, extension: &str, comment_starts: Vec<String>) {
+    let extension = extension.trim_start_matches('.').to_lowercase();
+    self.overrides.entry(extension).or_insert(comment_starts);
+  }
+
+  pub fn is_file_ignored(&self, file_path: &Path, file_text: &[u8]) -> bool {
+    let Some(extension) = get_lowercase_file_extension(file_path) else {
+      return false;
+    };
+    match self.overrides.get(&extension) {
+      Some(comment_starts) => has_ignore_file_directive(file_text, comment_starts),
+      None => has_ignore_file_directive(file_text, get_default_comment_starts(&extension)),
+    }
+  }
+}
+
+fn get_default_comment_starts(extension: &str) -> &'static [&'static str] {
+  match extension {
+    "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" | "json" | "jsonc" | "json5" | "scss" | "less" | "c" | "h" | "cc" | "cpp" | "hpp" | "cs"
+    | "go" | "java" | "kt" | "kts" | "rs" | "swift" | "dart" | "proto" | "zig" => &["//", "/*"],
+    "css" => &["/*"],
+    "py" | "rb" | "sh" | "bash" | "zsh" | "ps1" | "yml" | "yaml" | "toml" | "graphql" | "gql" | "r" | "pl" | "nix" | "dockerfile" => &["#"],
+    "md" | "markdown" | "html" | "htm" | "xml" | "svg" | "vue" | "svelte" | "astro" => &["<!--"],
+    "sql" | "lua" | "hs" => &["--", "/*"],
+    _ => &[],
+  }
+}
+
+fn has_ignore_file_directive(file_text: &[u8], comment_starts: &[impl AsRef<str>]) -> bool {
+  if comment_starts.is_empty() {
+    return false;
+  }
+  let file_text = file_text.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(file_text);
+  file_text.split(|b| *b == b'\n').take(IGNORE_FILE_DIRECTIVE_LINE_COUNT).any(|line| {
+    let line = line.trim_ascii_start();
+    comment_starts.iter().any(|comment_start| {
+      let Some(comment_text) = line.strip_prefix(comment_start.as_ref().as_bytes()) else {
+        return false;
+      };
+      match comment_text.trim_ascii_start().strip_prefix(IGNORE_FILE_DIRECTIVE) {
+        // ensure it's not a longer word like "dprint-ignore-file-foo"
+        Some(rest) => !rest.first().is_some_and(|b| b.is_ascii_alphanumeric() || *b == b'-' || *b == b'_'),
+        None => false,
+      }
+    })
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_find_directive_with_default_comments() {
+    let comments = IgnoreFileComments::default();
+    assert!(comments.is_file_ignored(Path::new("/file.ts"), b"// dprint-ignore-file\nconst a = 5;"));
+    assert!(comments.is_file_ignored(Path::new("/file.TS"), b"/* dprint-ignore-file */\nconst a = 5;"));
+    assert!(comments.is_file_ignored(Path::new("/file.ts"), "\u{FEFF}//dprint-ignore-file".as_bytes()));
+    assert!(comments.is_file_ignored(Path::new("/file.py"), b"#!/usr/bin/env python\n  # dprint-ignore-file: generated\n"));
+    assert!(comments.is_file_ignored(Path::new("/file.md"), b"<!-- dprint-ignore-file -->\n# Title"));
+    assert!(comments.is_file_ignored(Path::new("/file.sql"), b"-- dprint-ignore-file\r\nselect 1;"));
+    // not a comment of the file's syntax
+    assert!(!comments.is_file_ignored(Path::new("/file.py"), b"// dprint-ignore-file\n"));
+    assert!(!comments.is_file_ignored(Path::new("/file.ts"), b"const a = \"// dprint-ignore-file\";\n"));
+    assert!(!comments.is_file_ignored(Path::new("/file.ts"), b"// dprint-ignore-file-next\n"));
+    assert!(!comments.is_file_ignored(Path::new("/file.txt"), b"// dprint-ignore-file\n"));
+    assert!(!comments.is_file_ignored(Path::new("/file"), b"# dprint-ignore-file\n"));
+  }
+
+  #[test]
+  fn should_only_search_first_lines() {
+    let comments = IgnoreFileComments::default();
+    let text = format!("{}// dprint-ignore-file\n", "\n".repeat(IGNORE_FILE_DIRECTIVE_LINE_COUNT - 1));
+    assert!(comments.is_file_ignored(Path::new("/file.ts"), text.as_bytes()));
+    let text = format!("\n{}", text);
+    assert!(!comments.is_file_ignored(Path::new("/file.ts"), text.as_bytes()));
+  }
+
+  #[test]
+  fn should_use_configured_comments() {
+    let mut comments = IgnoreFileComments::default();
+    comments.set_if_absent(".TXT", vec![";".to_string()]);
+    comments.set_if_absent("txt", vec!["#".to_string()]);
+    comments.set_if_absent("ts", Vec::new());
+    assert!(comments.is_file_ignored(Path::new("/file.txt"), b"; dprint-ignore-file\n"));
+    assert!(!comments.is_file_ignored(Path::new("/file.txt"), b"# dprint-ignore-file\n"));
+    assert!(!comments.is_file_ignored(Path::new("/file.ts"), b"// dprint-ignore-file\n"));
+  }
+}
@@ -8,6 +8,7 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use crate::environment::Environment;
+use crate::environment::FileLock;
 
 /// A file system based flag that will attempt to synchronize multiple
 /// processes so they go one after the other. In scenarios where
@@ -17,97 +18,94 @@ use crate::environment::Environment;
 /// This should only be used in places where it's ideal for multiple
 /// processes to not update something on the file system at the same time,
 /// but it's not that big of a deal.
-pub struct LaxSingleProcessFsFlag<TEnvironment: Environment>(Option<LaxSingleProcessFsFlagInner<TEnvironment>>);
-
-impl<TEnvironment: Environment> LaxSingleProcessFsFlag<TEnvironment> {
-  pub async fn lock(environment: &TEnvironment, file_path: PathBuf, long_wait_message: &str) -> Self {
-    // don't bother making this work for a test environment
-    if !environment.is_real() {
-      return Self(None);
-    }
+pub struct LaxSingleProcessFsFlag {
+  _inner: Option<LaxSingleProcessFsFlagInner>,
+}
 
+impl LaxSingleProcessFsFlag {
+  pub async fn lock(environment: &impl Environment, file_path: PathBuf, long_wait_message: &str) -> Self {
     log_debug!(environment, "Acquiring file lock at {}", file_path.display());
-    use fs3::FileExt;
     let last_updated_path = file_path.with_extension("lock.poll");
     let start_instant = std::time::Instant::now();
-    let open_result = std::fs::OpenOptions::new().read(true).write(true).truncate(true).create(true).open(&file_path);
+    let mut has_shown_long_wait = false;
+    let mut pb_update_guard = None;
+    let mut error_count = 0;
+    let poll_file_update_ms = 100;
+    while error_count < 10 {
+      match environment.try_lock_file(&file_path) {
+        Ok(Some(file_lock)) => {
+          log_debug!(environment, "Acquired file lock at {}", file_path.display());
+          let token = Arc::new(tokio_util::sync::CancellationToken::new());
 
-    match open_result {
-      Ok(fs_file) => {
-        let mut pb_update_guard = None;
-        let mut error_count = 0;
-        while error_count < 10 {
-          let lock_result = fs_file.try_lock_exclusive();
-          let poll_file_update_ms = 100;
-          match lock_result {
-            Ok(_) => {
-              log_debug!(environment, "Acquired file lock at {}", file_path.display());
-              #[allow(clippy::disallowed_methods)]
-              let _ignore = std::fs::write(&last_updated_path, "");
-              let token = Arc::new(tokio_util::sync::CancellationToken::new());
+          // don't bother with the poll file for a test environment
+          if environment.is_real() {
+            #[allow(clippy::disallowed_methods)]
+            let _ignore = std::fs::write(&last_updated_path, "");
 
-              // Spawn a blocking task that will continually update a file
-              // signalling the lock is alive. This is a fail safe for when
-              // a file lock is never released. For example, on some operating
-              // systems, if a process does not release the lock (say it's
-              // killed), then the OS may release it at an indeterminate time
-              //
-              // This uses a blocking task because we use a single threaded
-              // runtime and this is time sensitive so we don't want it to update
-              // at the whims of of whatever is occurring on the runtime thread.
-              dprint_core::async_runtime::spawn_blocking({
-                let token = token.clone();
-                let last_updated_path = last_updated_path.clone();
-                move || {
-                  let mut i = 0;
-                  while !token.is_cancelled() {
-                    i += 1;
-                    #[allow(clippy::disallowed_methods)]
-                    let _ignore = std::fs::write(&last_updated_path, i.to_string());
-                    std::thread::sleep(Duration::from_millis(poll_file_update_ms));
-                  }
+            // Spawn a blocking task that will continually update a file
+            // signalling the lock is alive. This is a fail safe for when
+            // a file lock is never released. For example, on some operating
+            // systems, if a process does not release the lock (say it's
+            // killed), then the OS may release it at an indeterminate time
+            //
+            // This uses a blocking task because we use a single threaded
+            // runtime and this is time sensitive so we don't want it to update
+            // at the whims of of whatever is occurring on the runtime thread.
+            dprint_core::async_runtime::spawn_blocking({
+              let token = token.clone();
+              let last_updated_path = last_updated_path.clone();
+              move || {
+                let mut i = 0;
+                while !token.is_cancelled() {
+                  i += 1;
+                  #[allow(clippy::disallowed_methods)]
+                  let _ignore = std::fs::write(&last_updated_path, i.to_string());
+                  std::thread::sleep(Duration::from_millis(poll_file_update_ms));
                 }
-              });
+              }
+            });
+          }
 
-              return Self(Some(LaxSingleProcessFsFlagInner {
-                file_path,
-                fs_file,
-                finished_token: token,
-                environment: environment.clone(),
-              }));
+          return Self {
+            _inner: Some(LaxSingleProcessFsFlagInner {
+              _file_lock: file_lock,
+              finished_token: token,
+            }),
+          };
+        }
+        Ok(None) => {
+          // show a message if it's been a while
+          if !has_shown_long_wait && start_instant.elapsed().as_millis() > 1_000 {
+            has_shown_long_wait = true;
+            match environment.progress_bars() {
+              Some(pb) => pb_update_guard = Some(pb.add_progress(long_wait_message.to_string(), crate::utils::ProgressBarStyle::Action, 1)),
+              None => log_stderr_info!(environment, "{}", long_wait_message),
             }
-            Err(_) => {
-              // show a message if it's been a while
-              if pb_update_guard.is_none() && start_instant.elapsed().as_millis() > 1_000 {
-                pb_update_guard = environment
-                  .progress_bars()
-                  .map(|pb| pb.add_progress(long_wait_message.to_string(), crate::utils::ProgressBarStyle::Action, 1));
-              }
+          }
+
+          // sleep for a little bit
+          tokio::time::sleep(Duration::from_millis(20)).await;
 
-              // sleep for a little bit
-              tokio::time::sleep(Duration::from_millis(20)).await;
+          if !environment.is_real() {
+            continue;
+          }
 
-              // Poll the last updated path to check if it's stopped updating,
-              // which is an indication that the file lock is claimed, but
-              // was never properly released.
-              #[allow(clippy::disallowed_methods)]
-              match std::fs::metadata(&last_updated_path).and_then(|p| p.modified()) {
-                Ok(last_updated_time) => {
-                  let current_time = std::time::SystemTime::now();
-                  match current_time.duration_since(last_updated_time) {
-                    Ok(duration) => {
-                      if duration.as_millis() > (poll_file_update_ms * 2) as u128 {
-                        // the other process hasn't updated this file in a long time
-                        // so maybe it was killed and the operating system hasn't
-                        // released the file lock yet
-                        return Self(None);
-                      } else {
-                        error_count = 0; // reset
-                      }
-                    }
-                    Err(_) => {
-                      error_count += 1;
-                    }
+          // Poll the last updated path to check if it's stopped updating,
+          // which is an indication that the file lock is claimed, but
+          // was never properly released.
+          #[allow(clippy::disallowed_methods)]
+          match std::fs::metadata(&last_updated_path).and_then(|p| p.modified()) {
+            Ok(last_updated_time) => {
+              let current_time = std::time::SystemTime::now();
+              match current_time.duration_since(last_updated_time) {
+                Ok(duration) => {
+                  if duration.as_millis() > (poll_file_update_ms * 2) as u128 {
+                    // the other process hasn't updated this file in a long time
+                    // so maybe it was killed and the operating system hasn't
+                    // released the file lock yet
+                    return Self { _inner: None };
+                  } else {
+                    error_count = 0; // reset
                   }
                 }
                 Err(_) => {
@@ -115,36 +113,33 @@ impl<TEnvironment: Environment> LaxSingleProcessFsFlag<TEnvironment> {
                 }
               }
             }
+            Err(_) => {
+              error_count += 1;
+            }
           }
         }
-
-        drop(pb_update_guard); // explicit for clarity
-        Self(None)
-      }
-      Err(err) => {
-        log_debug!(environment, "Failed to open file lock at {}. {:#}", file_path.display(), err);
-        Self(None) // let the process through
+        Err(err) => {
+          log_debug!(environment, "Failed to open file lock at {}. {:#}", file_path.display(), err);
+          return Self { _inner: None }; // let the process through
+        }
       }
     }
+
+    drop(pb_update_guard); // explicit for clarity
+    Self { _inner: None }
   }
 }
 
-struct LaxSingleProcessFsFlagInner<TEnvironment: Environment> {
-  file_path: PathBuf,
-  fs_file: std::fs::File,
+struct LaxSingleProcessFsFlagInner {
+  // released after the poll thread is stopped
+  _file_lock: FileLock,
   finished_token: Arc<tokio_util::sync::CancellationToken>,
-  environment: TEnvironment,
 }
 
-impl<TEnvironment: Environment> Drop for LaxSingleProcessFsFlagInner<TEnvironment> {
+impl Drop for LaxSingleProcessFsFlagInner {
   fn drop(&mut self) {
-    use fs3::FileExt;
     // kill the poll thread
     self.finished_token.cancel();
-    // release the file lock
-    if let Err(err) = self.fs_file.unlock() {
-      log_debug!(self.environment, "Failed releasing lock for {}. {:#}", self.file_path.display(), err);
-    }
   }
 }
 
@@ -159,9 +154,34 @@ mod test {
   use tokio::sync::Notify;
 
   use crate::environment::RealEnvironment;
+  use crate::environment::TestEnvironment;
 
   use super::*;
 
+  #[test]
+  fn should_wait_for_lock_in_test_environment() {
+    let environment = TestEnvironment::new();
+    environment.clone().run_in_runtime(async move {
+      let lock_path = PathBuf::from("/cache/file.lock");
+      let order = Arc::new(Mutex::new(Vec::new()));
+      let flag = LaxSingleProcessFsFlag::lock(&environment, lock_path.clone(), "Waiting...").await;
+      let task = dprint_core::async_runtime::spawn({
+        let environment = environment.clone();
+        let order = order.clone();
+        async move {
+          let _flag = LaxSingleProcessFsFlag::lock(&environment, lock_path, "Waiting...").await;
+          order.lock().push("second");
+        }
+      });
+      tokio::time::sleep(Duration::from_millis(1_200)).await;
+      assert_eq!(environment.take_stderr_messages(), vec!["Waiting..."]);
+      order.lock().push("first");
+      drop(flag);
+      task.await.unwrap();
+      assert_eq!(*order.lock(), vec!["first", "second"]);
+    });
+  }
+
   #[test]
   fn lax_fs_lock() {
     RealEnvironment::run_test_with_real_env(|env| {
@@ -0,0 +1,123 @@
+use std::io::Read;
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::environment::Environment;
+
+/// The blocking reader and writer of a unix socket or windows named pipe connection.
+pub type LocalSocketStreams = (Box<dyn Read + Send>, Box<dyn Write + Send>);
+
+/// Listens for connections on a unix socket.
+#[cfg(unix)]
+pub struct LocalSocketListener {
+  listener: tokio::net::UnixListener,
+}
+
+#[cfg(unix)]
+impl LocalSocketListener {
+  pub fn bind(socket_path: &str) -> Result<Self> {
+    Ok(Self {
+      listener: tokio::net::UnixListener::bind(socket_path)?,
+    })
+  }
+
+  /// Accepts the next connection. The streams are blocking so they
+  /// should be read from and written to on blocking threads.
+  pub async fn accept(&mut self) -> std::io::Result<LocalSocketStreams> {
+    let (stream, _) = self.listener.accept().await?;
+    let stream = stream.into_std()?;
+    stream.set_nonblocking(false)?;
+    let reader = stream.try_clone()?;
+    Ok((Box::new(reader), Box::new(UnixStreamWriter(stream))))
+  }
+}
+
+/// Shuts down the socket once the connection's writer is dropped so
+/// that the client and the thread reading from the socket finish.
+#[cfg(unix)]
+struct UnixStreamWriter(std::os::unix::net::UnixStream);
+
+#[cfg(unix)]
+impl Write for UnixStreamWriter {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.0.write(buf)
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    self.0.flush()
+  }
+}
+
+#[cfg(unix)]
+impl Drop for UnixStreamWriter {
+  fn drop(&mut self) {
+    let _ = self.0.shutdown(std::net::Shutdown::Both);
+  }
+}
+
+/// Listens for connections on a windows named pipe.
+#[cfg(windows)]
+pub struct LocalSocketListener {
+  socket_path: String,
+  server: tokio::net::windows::named_pipe::NamedPipeServer,
+}
+
+#[cfg(windows)]
+impl LocalSocketListener {
+  pub fn bind(socket_path: &str) -> Result<Self> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    Ok(Self {
+      socket_path: socket_path.to_string(),
+      server: ServerOptions::new().first_pipe_instance(true).create(socket_path)?,
+    })
+  }
+
+  /// Accepts the next connection. The streams are blocking so they
+  /// should be read from and written to on blocking threads.
+  pub async fn accept(&mut self) -> std::io::Result<LocalSocketStreams> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+    use tokio_util::io::SyncIoBridge;
+
+    self.server.connect().await?;
+    // create the next instance before handing off this one so a client can always connect
+    let next_server = ServerOptions::new().create(&self.socket_path)?;
+    let client = std::mem::replace(&mut self.server, next_server);
+    // bridge to the synchronous traits
+    let (reader, writer) = tokio::io::split(client);
+    Ok((Box::new(SyncIoBridge::new(reader)), Box::new(SyncIoBridge::new(writer))))
+  }
+}
+
+/// Connects to a unix socket, blocking the current thread.
+#[cfg(unix)]
+pub fn connect_local_socket(socket_path: &str) -> std::io::Result<LocalSocketStreams> {
+  let stream = std::os::unix::net::UnixStream::connect(socket_path)?;
+  let reader = stream.try_clone()?;
+  Ok((Box::new(reader), Box::new(stream)))
+}
+
+/// Connects to a windows named pipe, blocking the current thread.
+#[cfg(windows)]
+pub fn connect_local_socket(socket_path: &str) -> std::io::Result<LocalSocketStreams> {
+  #[allow(clippy::disallowed_methods)]
+  let pipe = std::fs::OpenOptions::new().read(true).write(true).open(socket_path)?;
+  let reader = pipe.try_clone()?;
+  Ok((Box::new(reader), Box::new(pipe)))
+}
+
+/// Cleans up after a listener once it's no longer used.
+#[cfg(unix)]
+pub async fn cleanup_local_socket<TEnvironment: Environment>(socket_path: &str, environment: &TEnvironment) {
+  // the socket file would otherwise prevent the next listener from binding
+  let _ = environment.remove_file(socket_path);
+}
+
+/// Cleans up after a listener once it's no longer used.
+#[cfg(windows)]
+pub async fn cleanup_local_socket<TEnvironment: Environment>(_socket_path: &str, _environment: &TEnvironment) {
+  // the named pipes are written to through the runtime, so keep it
+  // running for a bit to allow the last responses to be sent
+  tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+}
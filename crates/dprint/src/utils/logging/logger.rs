@@ -148,6 +148,7 @@ impl Logger {
   }
 
   pub fn log_stderr_with_context(&self, text: &str, context_name: &str) {
+    crate::crash_report::record_crash_report_log_line(text);
     let mut state = self.output_lock.lock();
     self.inner_log(&mut state, false, text, context_name);
   }
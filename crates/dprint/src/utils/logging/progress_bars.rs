@@ -1,3 +1,5 @@
+use anyhow::bail;
+use anyhow::Result;
 use crossterm::style::Stylize;
 use crossterm::tty::IsTty;
 use parking_lot::Mutex;
@@ -20,6 +22,78 @@ use super::LoggerTextItem;
 pub enum ProgressBarStyle {
   Download,
   Action,
+  /// Progress of going over files, where some number of the files are "changed".
+  Files {
+    changed_label: &'static str,
+  },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ProgressBarsMode {
+  /// Redraws the progress bars in place.
+  Interactive,
+  /// Periodically outputs plain text lines, which is better for captured logs (ex. on CI).
+  Plain,
+}
+
+/// How long to wait before showing the progress of files in order
+/// to prevent flickering when formatting is quick.
+const FILES_PROGRESS_DELAY: Duration = Duration::from_secs(1);
+/// How often to output a line of progress in plain mode.
+const PLAIN_PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+/// Terminals narrower than this won't show the bar itself.
+const MIN_BAR_TERMINAL_WIDTH: u16 = 30;
+
+/// Options for how progress is displayed.
+///
+/// These are configured via a comma separated `DPRINT_PROGRESS_BARS` environment
+/// variable (ex. `DPRINT_PROGRESS_BARS=ascii,no-color,refresh-ms=500`) because
+/// they depend on the terminal rather than the project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgressBarsOptions {
+  /// Whether to show any progress.
+  pub enabled: bool,
+  /// Always output plain text lines instead of redrawing the bars in place.
+  pub force_plain: bool,
+  /// Replace any non-ASCII characters in the output.
+  pub ascii_only: bool,
+  pub colors: bool,
+  pub refresh_interval: Duration,
+}
+
+impl Default for ProgressBarsOptions {
+  fn default() -> Self {
+    ProgressBarsOptions {
+      enabled: true,
+      force_plain: false,
+      ascii_only: false,
+      colors: true,
+      refresh_interval: Duration::from_millis(120),
+    }
+  }
+}
+
+impl ProgressBarsOptions {
+  /// Parses the value of the `DPRINT_PROGRESS_BARS` environment variable.
+  pub fn parse(value: &str) -> Result<Self> {
+    let mut options = ProgressBarsOptions::default();
+    for item in value.split(',').map(|item| item.trim()).filter(|item| !item.is_empty()) {
+      match item {
+        "off" => options.enabled = false,
+        "plain" => options.force_plain = true,
+        "ascii" => options.ascii_only = true,
+        "no-color" => options.colors = false,
+        _ => match item.strip_prefix("refresh-ms=").map(|value| value.parse::<u64>()) {
+          Some(Ok(ms)) if ms > 0 => options.refresh_interval = Duration::from_millis(ms),
+          _ => bail!(
+            "Unknown progress bars option '{}'. Expected a comma separated list of: off, plain, ascii, no-color, refresh-ms=<milliseconds>",
+            item
+          ),
+        },
+      }
+    }
+    Ok(options)
+  }
 }
 
 pub struct ProgressBar {
@@ -27,6 +101,7 @@ pub struct ProgressBar {
   logger: Arc<Logger>,
   state: Arc<Mutex<InternalState>>,
   pos: Arc<RwLock<usize>>,
+  changed: Arc<RwLock<usize>>,
 }
 
 impl Drop for ProgressBar {
@@ -41,6 +116,14 @@ impl ProgressBar {
     *pos = new_pos;
   }
 
+  pub fn inc_position(&self) {
+    *self.pos.write() += 1;
+  }
+
+  pub fn inc_changed(&self) {
+    *self.changed.write() += 1;
+  }
+
   pub fn finish(&self) {
     let mut internal_state = self.state.lock();
 
@@ -48,7 +131,9 @@ impl ProgressBar {
       internal_state.progress_bars.remove(index);
 
       if internal_state.progress_bars.is_empty() {
-        self.logger.remove_refresh_item(LoggerRefreshItemKind::ProgressBars);
+        if internal_state.mode == ProgressBarsMode::Interactive {
+          self.logger.remove_refresh_item(LoggerRefreshItemKind::ProgressBars);
+        }
         internal_state.drawer_id += 1;
       }
     }
@@ -63,13 +148,36 @@ pub struct ProgressBars {
 struct ProgressBarState {
   id: usize,
   start_time: SystemTime,
+  /// When a line was last output in plain mode.
+  last_plain_output_time: SystemTime,
   message: String,
   size: usize,
   style: ProgressBarStyle,
   pos: Arc<RwLock<usize>>,
+  changed: Arc<RwLock<usize>>,
+}
+
+impl ProgressBarState {
+  fn elapsed(&self) -> Duration {
+    self.start_time.elapsed().unwrap_or_default()
+  }
+
+  fn plain_text(&self) -> String {
+    let pos = *self.pos.read();
+    let total = std::cmp::max(pos, self.size);
+    let progress_text = match self.style {
+      ProgressBarStyle::Download => format!(": {}/{}", get_bytes_text(pos, total), get_bytes_text(total, total)),
+      ProgressBarStyle::Action if total > 1 => format!(": {}%", pos * 100 / total),
+      ProgressBarStyle::Action => String::new(),
+      ProgressBarStyle::Files { changed_label } => format!(": {}", get_files_text(pos, total, *self.changed.read(), changed_label)),
+    };
+    format!("{}{} {}", self.message, progress_text, get_elapsed_text(self.elapsed()))
+  }
 }
 
 struct InternalState {
+  mode: ProgressBarsMode,
+  options: ProgressBarsOptions,
   // this ensures only one draw thread is running
   drawer_id: usize,
   progress_bar_counter: usize,
@@ -77,44 +185,68 @@ struct InternalState {
 }
 
 impl ProgressBars {
-  /// Checks if progress bars are supported
+  /// Checks if progress bars that redraw in place are supported.
   pub fn are_supported() -> bool {
     std::io::stderr().is_tty() && get_terminal_size().is_some()
   }
 
-  /// Creates a new ProgressBars or returns None when not supported.
-  pub fn new(logger: &Arc<Logger>) -> Option<Self> {
-    if ProgressBars::are_supported() {
-      Some(ProgressBars {
-        logger: logger.clone(),
-        state: Arc::new(Mutex::new(InternalState {
-          drawer_id: 0,
-          progress_bar_counter: 0,
-          progress_bars: Vec::new(),
-        })),
-      })
+  /// Creates a new ProgressBars or returns None when nothing would be output.
+  ///
+  /// Progress is output as periodic plain text lines on CI or when
+  /// stderr is not a terminal instead of redrawing it in place.
+  pub fn new(logger: &Arc<Logger>, is_ci: bool, options: ProgressBarsOptions) -> Option<Self> {
+    let mode = if !options.enabled {
+      return None;
+    } else if !is_ci && !options.force_plain && ProgressBars::are_supported() {
+      ProgressBarsMode::Interactive
+    } else if logger.log_level().is_info() {
+      ProgressBarsMode::Plain
     } else {
-      None
-    }
+      return None;
+    };
+    Some(ProgressBars {
+      logger: logger.clone(),
+      state: Arc::new(Mutex::new(InternalState {
+        mode,
+        options,
+        drawer_id: 0,
+        progress_bar_counter: 0,
+        progress_bars: Vec::new(),
+      })),
+    })
   }
 
   pub fn add_progress(&self, message: String, style: ProgressBarStyle, total_size: usize) -> ProgressBar {
     let mut internal_state = self.state.lock();
     let id = internal_state.progress_bar_counter;
     let pos = Arc::new(RwLock::new(0));
+    let changed = Arc::new(RwLock::new(0));
+    let message = if internal_state.options.ascii_only {
+      to_ascii_text(&message)
+    } else {
+      message
+    };
+    if internal_state.mode == ProgressBarsMode::Plain && !matches!(style, ProgressBarStyle::Files { .. }) {
+      // output the message right away so it's known what's happening
+      self.logger.__log_stderr__(&message);
+    }
+    let start_time = SystemTime::now();
     let pb_state = ProgressBarState {
       id,
-      start_time: SystemTime::now(),
+      start_time,
+      last_plain_output_time: start_time,
       message,
       size: total_size,
       style,
       pos: pos.clone(),
+      changed: changed.clone(),
     };
     let pb = ProgressBar {
       id,
       logger: self.logger.clone(),
       state: self.state.clone(),
       pos,
+      changed,
     };
     internal_state.progress_bars.push(pb_state);
     internal_state.progress_bar_counter += 1;
@@ -129,69 +261,111 @@ impl ProgressBars {
   fn start_draw_thread(&self, internal_state: &mut InternalState) {
     internal_state.drawer_id += 1;
     let drawer_id = internal_state.drawer_id;
+    let mode = internal_state.mode;
+    let options = internal_state.options.clone();
     let internal_state = self.state.clone();
     let logger = self.logger.clone();
     dprint_core::async_runtime::spawn_blocking(move || {
+      let mut has_drawn = false;
       loop {
         {
-          let internal_state = internal_state.lock();
+          let mut internal_state = internal_state.lock();
           // exit if not the current draw thread or there are no more progress bars
           if internal_state.drawer_id != drawer_id || internal_state.progress_bars.is_empty() {
             break;
           }
 
-          let terminal_width = get_terminal_size().unwrap().cols;
-          let mut text = String::new();
-          for (i, progress_bar) in internal_state.progress_bars.iter().enumerate() {
-            if i > 0 {
-              text.push('\n');
+          match mode {
+            ProgressBarsMode::Interactive => {
+              let terminal_width = get_terminal_size().map(|size| size.cols).unwrap_or(80);
+              let mut text = String::new();
+              let progress_bars = internal_state
+                .progress_bars
+                .iter()
+                .filter(|pb| !matches!(pb.style, ProgressBarStyle::Files { .. }) || pb.elapsed() >= FILES_PROGRESS_DELAY);
+              for (i, progress_bar) in progress_bars.enumerate() {
+                if i > 0 {
+                  text.push('\n');
+                }
+                text.push_str(&progress_bar.message);
+                text.push('\n');
+                text.push_str(&get_progress_bar_text(
+                  terminal_width,
+                  *progress_bar.pos.read(),
+                  progress_bar.size,
+                  *progress_bar.changed.read(),
+                  progress_bar.style,
+                  progress_bar.elapsed(),
+                  options.colors,
+                ));
+              }
+
+              if !text.is_empty() {
+                logger.set_refresh_item(LoggerRefreshItemKind::ProgressBars, vec![LoggerTextItem::Text(text)]);
+                has_drawn = true;
+              } else if has_drawn {
+                logger.remove_refresh_item(LoggerRefreshItemKind::ProgressBars);
+                has_drawn = false;
+              }
+            }
+            ProgressBarsMode::Plain => {
+              for progress_bar in internal_state.progress_bars.iter_mut() {
+                if progress_bar.last_plain_output_time.elapsed().unwrap_or_default() >= PLAIN_PROGRESS_INTERVAL {
+                  logger.__log_stderr__(&progress_bar.plain_text());
+                  progress_bar.last_plain_output_time = SystemTime::now();
+                }
+              }
             }
-            text.push_str(&progress_bar.message);
-            text.push('\n');
-            text.push_str(&get_progress_bar_text(
-              terminal_width,
-              *progress_bar.pos.read(),
-              progress_bar.size,
-              progress_bar.style,
-              progress_bar.start_time.elapsed().unwrap(),
-            ));
           }
-
-          logger.set_refresh_item(LoggerRefreshItemKind::ProgressBars, vec![LoggerTextItem::Text(text)]);
         }
 
-        std::thread::sleep(Duration::from_millis(120));
+        std::thread::sleep(options.refresh_interval);
       }
     });
   }
 }
 
-fn get_progress_bar_text(terminal_width: u16, pos: usize, total: usize, pb_style: ProgressBarStyle, duration: Duration) -> String {
+fn get_progress_bar_text(
+  terminal_width: u16,
+  pos: usize,
+  total: usize,
+  changed: usize,
+  pb_style: ProgressBarStyle,
+  duration: Duration,
+  colors: bool,
+) -> String {
   let total = std::cmp::max(pos, total); // increase the total when pos > total
-  let bytes_text = if pb_style == ProgressBarStyle::Download {
-    format!(" {}/{}", get_bytes_text(pos, total), get_bytes_text(total, total))
-  } else {
-    String::new()
+  let bytes_text = match pb_style {
+    ProgressBarStyle::Download => format!(" {}/{}", get_bytes_text(pos, total), get_bytes_text(total, total)),
+    ProgressBarStyle::Files { changed_label } => format!(" {}", get_files_text(pos, total, changed, changed_label)),
+    ProgressBarStyle::Action => String::new(),
   };
 
   let elapsed_text = get_elapsed_text(duration);
   let mut text = String::new();
   text.push_str(&elapsed_text);
-  // get progress bar
-  let percent = pos as f32 / total as f32;
   // don't include the bytes text in this because a string going from X.XXMB to XX.XXMB should not adjust the progress bar
-  let total_bars = (std::cmp::min(50, terminal_width - 15) as usize) - elapsed_text.len() - 1 - 2;
-  let completed_bars = (total_bars as f32 * percent).floor() as usize;
-  text.push_str(" [");
-  if completed_bars != total_bars {
-    if completed_bars > 0 {
-      text.push_str(&format!("{}", format!("{}{}", "#".repeat(completed_bars - 1), ">").cyan()))
+  let total_bars = (std::cmp::min(50, terminal_width.saturating_sub(15)) as usize).saturating_sub(elapsed_text.len() + 1 + 2);
+  if terminal_width >= MIN_BAR_TERMINAL_WIDTH && total_bars > 0 {
+    // get progress bar
+    let percent = if total == 0 { 1.0 } else { pos as f32 / total as f32 };
+    let completed_bars = (total_bars as f32 * percent).floor() as usize;
+    text.push_str(" [");
+    if completed_bars != total_bars {
+      if completed_bars > 0 {
+        text.push_str(&colorize(format!("{}{}", "#".repeat(completed_bars - 1), ">"), colors, |t| {
+          t.cyan().to_string()
+        }));
+      }
+      text.push_str(&colorize("-".repeat(total_bars - completed_bars), colors, |t| t.blue().to_string()));
+    } else {
+      text.push_str(&colorize("#".repeat(completed_bars), colors, |t| t.cyan().to_string()));
     }
-    text.push_str(&format!("{}", "-".repeat(total_bars - completed_bars).blue()))
-  } else {
-    text.push_str(&format!("{}", "#".repeat(completed_bars).cyan()))
+    text.push(']');
+  } else if pb_style == ProgressBarStyle::Action {
+    // show something since there's no bar
+    text.push_str(&format!(" {}%", (pos * 100).checked_div(total).unwrap_or(100)));
   }
-  text.push(']');
 
   // bytes text
   text.push_str(&bytes_text);
@@ -199,6 +373,18 @@ fn get_progress_bar_text(terminal_width: u16, pos: usize, total: usize, pb_style
   text
 }
 
+fn colorize(text: String, colors: bool, style: impl FnOnce(String) -> String) -> String {
+  if colors {
+    style(text)
+  } else {
+    text
+  }
+}
+
+fn to_ascii_text(text: &str) -> String {
+  text.chars().map(|c| if c.is_ascii() { c } else { '?' }).collect()
+}
+
 fn get_bytes_text(byte_count: usize, total_bytes: usize) -> String {
   let bytes_to_kb = 1_000;
   let bytes_to_mb = 1_000_000;
@@ -215,6 +401,10 @@ fn get_bytes_text(byte_count: usize, total_bytes: usize) -> String {
   }
 }
 
+fn get_files_text(pos: usize, total: usize, changed: usize, changed_label: &str) -> String {
+  format!("{}/{} files, {} {}", pos, total, changed, changed_label)
+}
+
 fn get_elapsed_text(elapsed: Duration) -> String {
   let elapsed_secs = elapsed.as_secs();
   let seconds = elapsed_secs % 60;
@@ -241,6 +431,101 @@ mod test {
     assert_eq!(get_bytes_text(9_524_102, 10_000_000), "9.52MB");
   }
 
+  #[test]
+  fn should_parse_options() {
+    assert_eq!(ProgressBarsOptions::parse("").unwrap(), ProgressBarsOptions::default());
+    assert_eq!(
+      ProgressBarsOptions::parse("ascii, no-color,plain,refresh-ms=500").unwrap(),
+      ProgressBarsOptions {
+        enabled: true,
+        force_plain: true,
+        ascii_only: true,
+        colors: false,
+        refresh_interval: Duration::from_millis(500),
+      }
+    );
+    assert!(!ProgressBarsOptions::parse("off").unwrap().enabled);
+    assert!(ProgressBarsOptions::parse("refresh-ms=0").is_err());
+    assert!(ProgressBarsOptions::parse("refresh-ms=fast").is_err());
+    assert_eq!(
+      ProgressBarsOptions::parse("unicode").err().unwrap().to_string(),
+      "Unknown progress bars option 'unicode'. Expected a comma separated list of: off, plain, ascii, no-color, refresh-ms=<milliseconds>"
+    );
+  }
+
+  #[test]
+  fn should_get_progress_bar_text() {
+    let duration = Duration::from_secs(5);
+    assert_eq!(
+      get_progress_bar_text(80, 5, 10, 0, ProgressBarStyle::Action, duration, false),
+      "[00:05] [###################>--------------------]"
+    );
+    assert_eq!(
+      get_progress_bar_text(80, 10, 10, 0, ProgressBarStyle::Action, duration, false),
+      "[00:05] [########################################]"
+    );
+    assert_eq!(
+      get_progress_bar_text(80, 3, 4, 1, ProgressBarStyle::Files { changed_label: "changed" }, duration, false),
+      "[00:05] [#############################>----------] 3/4 files, 1 changed"
+    );
+    // should not show the bar for narrow terminals
+    assert_eq!(get_progress_bar_text(20, 5, 10, 0, ProgressBarStyle::Action, duration, false), "[00:05] 50%");
+    assert_eq!(get_progress_bar_text(0, 5, 10, 0, ProgressBarStyle::Action, duration, false), "[00:05] 50%");
+    assert_eq!(
+      get_progress_bar_text(10, 1_000, 2_000, 0, ProgressBarStyle::Download, duration, false),
+      "[00:05] 1.00KB/2.00KB"
+    );
+  }
+
+  #[test]
+  fn should_get_ascii_text() {
+    assert_eq!(
+      to_ascii_text("Compiling https://example.com/plügin.wasm"),
+      "Compiling https://example.com/pl?gin.wasm"
+    );
+  }
+
+  #[test]
+  fn should_get_plain_text() {
+    fn state(style: ProgressBarStyle, message: &str, pos: usize, size: usize, changed: usize) -> ProgressBarState {
+      let start_time = SystemTime::now() - Duration::from_secs(65);
+      ProgressBarState {
+        id: 0,
+        start_time,
+        last_plain_output_time: start_time,
+        message: message.to_string(),
+        size,
+        style,
+        pos: Arc::new(RwLock::new(pos)),
+        changed: Arc::new(RwLock::new(changed)),
+      }
+    }
+
+    assert_eq!(
+      state(ProgressBarStyle::Files { changed_label: "changed" }, "Formatting", 1200, 5000, 3).plain_text(),
+      "Formatting: 1200/5000 files, 3 changed [01:05]"
+    );
+    assert_eq!(
+      state(
+        ProgressBarStyle::Download,
+        "Downloading https://plugins.dprint.dev/test.wasm",
+        1_200_000,
+        5_000_000,
+        0
+      )
+      .plain_text(),
+      "Downloading https://plugins.dprint.dev/test.wasm: 1.20MB/5.00MB [01:05]"
+    );
+    assert_eq!(
+      state(ProgressBarStyle::Action, "Extracting zip", 2, 5, 0).plain_text(),
+      "Extracting zip: 40% [01:05]"
+    );
+    assert_eq!(
+      state(ProgressBarStyle::Action, "Compiling test.wasm", 0, 1, 0).plain_text(),
+      "Compiling test.wasm [01:05]"
+    );
+  }
+
   #[test]
   fn should_get_elapsed_text() {
     assert_eq!(get_elapsed_text(Duration::from_secs(1)), "[00:01]");
@@ -2,21 +2,27 @@ mod cached_downloader;
 mod certs;
 mod checksums;
 mod error_count_logger;
-mod extract_zip;
+mod extract_archive;
+mod file_encoding;
 mod file_path_utils;
 mod get_bytes_hash;
 mod get_difference;
 mod gitignore;
 mod glob;
+mod ignore_file_comments;
 mod lax_single_process_fs_flag;
+mod local_socket;
 mod logging;
+mod ordered_output;
 mod path_source;
 mod pretty_print_json_text;
 mod process;
+mod profiler;
 mod resolve_url_or_file_path;
 mod stdin_reader;
 mod sync;
 mod table_text;
+mod telemetry;
 mod terminal;
 mod unsync;
 mod update_checker;
@@ -26,21 +32,27 @@ pub use self::url::*;
 pub use cached_downloader::*;
 pub use checksums::*;
 pub use error_count_logger::*;
-pub use extract_zip::*;
+pub use extract_archive::*;
+pub use file_encoding::*;
 pub use file_path_utils::*;
 pub use get_bytes_hash::*;
 pub use get_difference::*;
 pub use gitignore::*;
 pub use glob::*;
+pub use ignore_file_comments::*;
 pub use lax_single_process_fs_flag::*;
+pub use local_socket::*;
 pub use logging::*;
+pub use ordered_output::*;
 pub use path_source::*;
 pub use pretty_print_json_text::*;
 pub use process::*;
+pub use profiler::*;
 pub use resolve_url_or_file_path::*;
 pub use stdin_reader::*;
 pub use sync::*;
 pub use table_text::*;
+pub use telemetry::*;
 pub use terminal::*;
 pub use unsync::*;
 pub use update_checker::*;
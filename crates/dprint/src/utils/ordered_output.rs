@@ -0,0 +1,143 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::environment::Environment;
+use crate::utils::LogLevel;
+
+enum OutputMessage {
+  Stdout(String),
+  Stderr(String),
+}
+
+/// Buffered output of a single file.
+///
+/// This implements the methods used by the logging macros so
+/// it may be logged to the same way as an environment.
+pub struct FileOutput {
+  log_level: LogLevel,
+  messages: Vec<OutputMessage>,
+}
+
+impl FileOutput {
+  pub fn new(log_level: LogLevel) -> Self {
+    FileOutput {
+      log_level,
+      messages: Vec::new(),
+    }
+  }
+
+  pub fn log_level(&self) -> LogLevel {
+    self.log_level
+  }
+
+  /// Don't ever call this directly in the code. Use the logging macros instead.
+  pub fn __log__(&mut self, text: &str) {
+    self.messages.push(OutputMessage::Stdout(text.to_string()));
+  }
+
+  /// Don't ever call this directly in the code. Use the logging macros instead.
+  pub fn __log_stderr__(&mut self, text: &str) {
+    self.messages.push(OutputMessage::Stderr(text.to_string()));
+  }
+}
+
+struct OrderedOutputState {
+  next_index: usize,
+  pending: HashMap<usize, FileOutput>,
+}
+
+/// Releases the output of files in order even though they may complete out of order.
+#[derive(Clone)]
+pub struct OrderedOutput<TEnvironment: Environment> {
+  environment: TEnvironment,
+  state: Rc<RefCell<OrderedOutputState>>,
+}
+
+impl<TEnvironment: Environment> OrderedOutput<TEnvironment> {
+  pub fn new(environment: TEnvironment) -> Self {
+    OrderedOutput {
+      environment,
+      state: Rc::new(RefCell::new(OrderedOutputState {
+        next_index: 0,
+        pending: HashMap::new(),
+      })),
+    }
+  }
+
+  /// Marks the file at the provided index as done, outputting its
+  /// output along with any following outputs that were waiting on it.
+  pub fn release(&self, index: usize, output: FileOutput) {
+    let mut state = self.state.borrow_mut();
+    state.pending.insert(index, output);
+    loop {
+      let next_index = state.next_index;
+      let Some(output) = state.pending.remove(&next_index) else {
+        break;
+      };
+      self.write(output);
+      state.next_index += 1;
+    }
+  }
+
+  /// Outputs everything that's pending regardless of whether a
+  /// previous file was released (ex. formatting was stopped).
+  pub fn flush(&self) {
+    let mut state = self.state.borrow_mut();
+    let mut indexes = state.pending.keys().copied().collect::<Vec<_>>();
+    indexes.sort_unstable();
+    for index in indexes {
+      let output = state.pending.remove(&index).unwrap();
+      self.write(output);
+    }
+  }
+
+  fn write(&self, output: FileOutput) {
+    for message in output.messages {
+      match message {
+        OutputMessage::Stdout(text) => self.environment.__log__(&text),
+        OutputMessage::Stderr(text) => self.environment.__log_stderr__(&text),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironment;
+
+  fn file_output(environment: &TestEnvironment, text: &str) -> FileOutput {
+    let mut output = FileOutput::new(environment.log_level());
+    log_stdout_info!(output, text);
+    log_error!(output, "error {}", text);
+    output
+  }
+
+  #[test]
+  fn should_release_output_in_order() {
+    let environment = TestEnvironment::new();
+    let ordered_output = OrderedOutput::new(environment.clone());
+    ordered_output.release(1, file_output(&environment, "1"));
+    ordered_output.release(3, file_output(&environment, "3"));
+    assert!(environment.take_stdout_messages().is_empty());
+    ordered_output.release(0, file_output(&environment, "0"));
+    assert_eq!(environment.take_stdout_messages(), vec!["0", "1"]);
+    assert_eq!(environment.take_stderr_messages(), vec!["error 0", "error 1"]);
+    ordered_output.release(2, file_output(&environment, "2"));
+    assert_eq!(environment.take_stdout_messages(), vec!["2", "3"]);
+    assert_eq!(environment.take_stderr_messages(), vec!["error 2", "error 3"]);
+  }
+
+  #[test]
+  fn should_flush_pending_output() {
+    let environment = TestEnvironment::new();
+    let ordered_output = OrderedOutput::new(environment.clone());
+    ordered_output.release(4, file_output(&environment, "4"));
+    ordered_output.release(2, file_output(&environment, "2"));
+    assert!(environment.take_stdout_messages().is_empty());
+    ordered_output.flush();
+    assert_eq!(environment.take_stdout_messages(), vec!["2", "4"]);
+    assert_eq!(environment.take_stderr_messages(), vec!["error 2", "error 4"]);
+  }
+}
@@ -56,22 +56,6 @@ impl PathSource {
     }
   }
 
-  pub fn unwrap_local(&self) -> LocalPathSource {
-    if let PathSource::Local(local_path_source) = self {
-      local_path_source.clone()
-    } else {
-      panic!("Attempted to unwrap a path source as local that was not local.");
-    }
-  }
-
-  pub fn unwrap_remote(&self) -> RemotePathSource {
-    if let PathSource::Remote(remote_path_source) = self {
-      remote_path_source.clone()
-    } else {
-      panic!("Attempted to unwrap a path source as remote that was not remote.");
-    }
-  }
-
   pub fn display(&self) -> String {
     match self {
       PathSource::Local(local) => local.path.display().to_string(),
@@ -0,0 +1,271 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Instant;
+
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::Serialize;
+
+static PROFILER: OnceCell<Profiler> = OnceCell::new();
+
+/// Enables recording spans to the global profiler for the rest of the process.
+pub fn enable_profiling() {
+  PROFILER.get_or_init(Profiler::new);
+}
+
+/// Gets the recorded profile when profiling is enabled.
+pub fn take_profile_json() -> Option<Vec<u8>> {
+  PROFILER.get().map(|p| p.take_json())
+}
+
+/// Starts a span that ends when dropped. Use this for work that
+/// stays on the current thread (ex. inside `spawn_blocking`).
+pub fn profile_span(category: &'static str, name: impl FnOnce() -> String) -> ProfileSpan {
+  match PROFILER.get() {
+    Some(profiler) => profiler.span(category, name()),
+    None => ProfileSpan::disabled(),
+  }
+}
+
+/// Starts a span for async work that may interleave with other work on the
+/// same thread. Use `ProfileSpan::child` for spans nested within it.
+pub fn profile_async_span(category: &'static str, name: impl FnOnce() -> String) -> ProfileSpan {
+  match PROFILER.get() {
+    Some(profiler) => profiler.async_span(category, name()),
+    None => ProfileSpan::disabled(),
+  }
+}
+
+#[derive(Serialize)]
+struct TraceEvent {
+  name: Cow<'static, str>,
+  #[serde(skip_serializing_if = "str::is_empty")]
+  cat: &'static str,
+  ph: &'static str,
+  /// Microseconds since the profiler started.
+  ts: f64,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  dur: Option<f64>,
+  pid: u32,
+  tid: u64,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  id: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  args: Option<serde_json::Value>,
+}
+
+#[derive(Default)]
+struct ProfilerState {
+  events: Vec<TraceEvent>,
+  named_threads: HashSet<u64>,
+}
+
+struct ProfilerInner {
+  start: Instant,
+  next_async_id: AtomicU64,
+  state: Mutex<ProfilerState>,
+}
+
+/// Records spans in the Chrome trace event format, which can be
+/// viewed in chrome://tracing, Perfetto, or speedscope.
+#[derive(Clone)]
+pub struct Profiler(Arc<ProfilerInner>);
+
+impl Profiler {
+  pub fn new() -> Self {
+    Profiler(Arc::new(ProfilerInner {
+      start: Instant::now(),
+      next_async_id: AtomicU64::new(1),
+      state: Default::default(),
+    }))
+  }
+
+  pub fn span(&self, category: &'static str, name: String) -> ProfileSpan {
+    ProfileSpan(Some(ActiveSpan {
+      profiler: self.clone(),
+      category,
+      name,
+      start: Instant::now(),
+      async_id: None,
+    }))
+  }
+
+  pub fn async_span(&self, category: &'static str, name: String) -> ProfileSpan {
+    let id = self.0.next_async_id.fetch_add(1, Ordering::Relaxed);
+    self.start_async_span(category, name, id)
+  }
+
+  fn start_async_span(&self, category: &'static str, name: String, id: u64) -> ProfileSpan {
+    let start = Instant::now();
+    self.record(TraceEvent {
+      name: Cow::Owned(name.clone()),
+      cat: category,
+      ph: "b",
+      ts: self.micros_since_start(start),
+      dur: None,
+      pid: std::process::id(),
+      tid: current_thread_id(),
+      id: Some(id),
+      args: None,
+    });
+    ProfileSpan(Some(ActiveSpan {
+      profiler: self.clone(),
+      category,
+      name,
+      start,
+      async_id: Some(id),
+    }))
+  }
+
+  /// Takes the recorded events as JSON.
+  pub fn take_json(&self) -> Vec<u8> {
+    let events = std::mem::take(&mut self.0.state.lock().events);
+    serde_json::to_vec(&serde_json::json!({
+      "traceEvents": events,
+      "displayTimeUnit": "ms",
+    }))
+    .unwrap()
+  }
+
+  fn record(&self, event: TraceEvent) {
+    let mut state = self.0.state.lock();
+    if state.named_threads.insert(event.tid) {
+      if let Some(thread_name) = std::thread::current().name() {
+        state.events.push(TraceEvent {
+          name: Cow::Borrowed("thread_name"),
+          cat: "",
+          ph: "M",
+          ts: 0.0,
+          dur: None,
+          pid: event.pid,
+          tid: event.tid,
+          id: None,
+          args: Some(serde_json::json!({ "name": thread_name })),
+        });
+      }
+    }
+    state.events.push(event);
+  }
+
+  fn micros_since_start(&self, instant: Instant) -> f64 {
+    instant.saturating_duration_since(self.0.start).as_secs_f64() * 1_000_000.0
+  }
+}
+
+struct ActiveSpan {
+  profiler: Profiler,
+  category: &'static str,
+  name: String,
+  start: Instant,
+  async_id: Option<u64>,
+}
+
+/// A span of work that's recorded when dropped.
+pub struct ProfileSpan(Option<ActiveSpan>);
+
+impl ProfileSpan {
+  fn disabled() -> Self {
+    ProfileSpan(None)
+  }
+
+  /// Starts a span nested within this async span.
+  pub fn child(&self, name: impl FnOnce() -> String) -> ProfileSpan {
+    match &self.0 {
+      Some(span) => match span.async_id {
+        Some(id) => span.profiler.start_async_span(span.category, name(), id),
+        None => span.profiler.span(span.category, name()),
+      },
+      None => ProfileSpan::disabled(),
+    }
+  }
+}
+
+impl Drop for ProfileSpan {
+  fn drop(&mut self) {
+    let Some(span) = self.0.take() else {
+      return;
+    };
+    let profiler = &span.profiler;
+    let end = Instant::now();
+    let event = match span.async_id {
+      Some(id) => TraceEvent {
+        name: Cow::Owned(span.name),
+        cat: span.category,
+        ph: "e",
+        ts: profiler.micros_since_start(end),
+        dur: None,
+        pid: std::process::id(),
+        tid: current_thread_id(),
+        id: Some(id),
+        args: None,
+      },
+      None => TraceEvent {
+        name: Cow::Owned(span.name),
+        cat: span.category,
+        ph: "X",
+        ts: profiler.micros_since_start(span.start),
+        dur: Some(end.duration_since(span.start).as_secs_f64() * 1_000_000.0),
+        pid: std::process::id(),
+        tid: current_thread_id(),
+        id: None,
+        args: None,
+      },
+    };
+    profiler.record(event);
+  }
+}
+
+fn current_thread_id() -> u64 {
+  static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+  thread_local! {
+    static THREAD_ID: u64 = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+  }
+  THREAD_ID.with(|id| *id)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_record_spans() {
+    let profiler = Profiler::new();
+    {
+      let _span = profiler.span("io", "read".to_string());
+    }
+    {
+      let file_span = profiler.async_span("format", "file.txt".to_string());
+      let _plugin_span = file_span.child(|| "plugin".to_string());
+    }
+    let value: serde_json::Value = serde_json::from_slice(&profiler.take_json()).unwrap();
+    let events = value["traceEvents"]
+      .as_array()
+      .unwrap()
+      .iter()
+      .filter(|e| e["ph"] != "M")
+      .map(|e| (e["name"].as_str().unwrap(), e["ph"].as_str().unwrap(), e["id"].as_u64()))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      events,
+      vec![
+        ("read", "X", None),
+        ("file.txt", "b", Some(1)),
+        ("plugin", "b", Some(1)),
+        ("plugin", "e", Some(1)),
+        ("file.txt", "e", Some(1)),
+      ]
+    );
+    // should have taken the events
+    let value: serde_json::Value = serde_json::from_slice(&profiler.take_json()).unwrap();
+    assert!(value["traceEvents"].as_array().unwrap().is_empty());
+  }
+
+  #[test]
+  fn disabled_span_should_not_evaluate_name() {
+    let span = ProfileSpan::disabled();
+    let _child = span.child(|| unreachable!());
+  }
+}
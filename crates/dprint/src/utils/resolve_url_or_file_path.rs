@@ -3,6 +3,7 @@ use anyhow::Result;
 use url::Url;
 
 use super::get_bytes_hash;
+use super::verify_sha256_checksum;
 use super::PathSource;
 use crate::environment::CanonicalizedPathBuf;
 use crate::environment::Environment;
@@ -83,6 +84,24 @@ pub async fn fetch_file_or_url_bytes(url_or_file_path: &PathSource, environment:
   }
 }
 
+/// Fetches the bytes and verifies their sha256 checksum, erroring with
+/// a `ChecksumMismatchError` when it doesn't match.
+///
+/// Remote files are verified as they're downloaded.
+pub async fn fetch_file_or_url_bytes_with_checksum(url_or_file_path: &PathSource, checksum: &str, environment: &impl Environment) -> Result<Vec<u8>> {
+  match url_or_file_path {
+    PathSource::Remote(path_source) => match environment.download_file_with_checksum(path_source.url.as_str(), checksum).await? {
+      Some(bytes) => Ok(bytes),
+      None => bail!("Error downloading {} - 404 Not Found", path_source.url.as_str()),
+    },
+    PathSource::Local(path_source) => {
+      let bytes = environment.read_file_bytes(&path_source.path)?;
+      verify_sha256_checksum(&bytes, checksum)?;
+      Ok(bytes)
+    }
+  }
+}
+
 pub fn resolve_url_or_file_path_to_path_source(url_or_file_path: &str, base: &PathSource, environment: &impl Environment) -> Result<PathSource> {
   if let Some(url) = try_parse_url(url_or_file_path) {
     if url.cannot_be_a_base() {
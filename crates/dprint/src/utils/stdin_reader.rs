@@ -2,7 +2,7 @@ use anyhow::Result;
 use std::io::Read;
 use std::io::{self};
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test-harness"))]
 pub use tests::TestStdInReader;
 
 pub trait StdInReader: Clone + Send + Sync {
@@ -20,7 +20,7 @@ impl StdInReader for RealStdInReader {
   }
 }
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test-harness"))]
 mod tests {
   use super::*;
   use parking_lot::Mutex;
@@ -0,0 +1,426 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use indexmap::IndexMap;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde_json::json;
+use serde_json::Value;
+
+static TELEMETRY: OnceCell<Mutex<TelemetryData>> = OnceCell::new();
+
+/// Enables recording telemetry for the rest of the process,
+/// clearing anything that was previously recorded.
+pub fn enable_telemetry() {
+  *TELEMETRY.get_or_init(Default::default).lock() = Default::default();
+}
+
+/// Takes the recorded telemetry when it's enabled.
+pub fn take_telemetry() -> Option<TelemetryData> {
+  TELEMETRY.get().map(|telemetry| std::mem::take(&mut *telemetry.lock()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileResult {
+  Unchanged,
+  Changed,
+  /// Skipped because a cache said it was formatted.
+  Cached,
+  /// Skipped because formatting exceeded the format timeout.
+  TimedOut,
+  /// Skipped because of a `dprint-ignore-file` directive.
+  Ignored,
+  Error,
+}
+
+impl FileResult {
+  fn as_str(&self) -> &'static str {
+    match self {
+      FileResult::Unchanged => "unchanged",
+      FileResult::Changed => "changed",
+      FileResult::Cached => "cached",
+      FileResult::TimedOut => "timed_out",
+      FileResult::Ignored => "ignored",
+      FileResult::Error => "error",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheKind {
+  Incremental,
+  CacheFile,
+}
+
+impl CacheKind {
+  fn as_str(&self) -> &'static str {
+    match self {
+      CacheKind::Incremental => "incremental",
+      CacheKind::CacheFile => "cache_file",
+    }
+  }
+}
+
+pub fn record_file_result(result: FileResult) {
+  with_telemetry(|data| *data.file_results.entry(result).or_default() += 1);
+}
+
+pub fn record_cache_lookup(kind: CacheKind, is_hit: bool) {
+  with_telemetry(|data| *data.cache_lookups.entry((kind, is_hit)).or_default() += 1);
+}
+
+pub fn record_plugin_format(plugin_name: &str, plugin_version: &str, duration: Duration) {
+  with_telemetry(|data| {
+    let stats = data.plugin_formats.entry((plugin_name.to_string(), plugin_version.to_string())).or_default();
+    stats.count += 1;
+    stats.duration += duration;
+  });
+}
+
+fn with_telemetry(action: impl FnOnce(&mut TelemetryData)) {
+  if let Some(telemetry) = TELEMETRY.get() {
+    action(&mut telemetry.lock());
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct TelemetryData {
+  file_results: IndexMap<FileResult, u64>,
+  cache_lookups: IndexMap<(CacheKind, bool), u64>,
+  plugin_formats: IndexMap<(String, String), PluginFormatStats>,
+}
+
+#[derive(Debug, Default)]
+struct PluginFormatStats {
+  count: u64,
+  duration: Duration,
+}
+
+/// The run of a command to export the telemetry of.
+pub struct OtlpRun<'a> {
+  pub command_name: &'a str,
+  pub cli_version: &'a str,
+  pub start_time: SystemTime,
+  pub end_time: SystemTime,
+  pub is_success: bool,
+}
+
+/// Configuration for exporting that's provided in the standard OpenTelemetry environment variables.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct OtlpEnvConfig {
+  /// Headers to send (ex. for authentication) from `OTEL_EXPORTER_OTLP_HEADERS`.
+  pub headers: Vec<(String, String)>,
+  /// Attributes describing the run (ex. the CI job) from `OTEL_RESOURCE_ATTRIBUTES`.
+  pub resource_attributes: Vec<(String, String)>,
+}
+
+impl OtlpEnvConfig {
+  pub fn from_env_vars(read_env_var: impl Fn(&str) -> Option<String>) -> Self {
+    Self {
+      headers: parse_key_value_list(read_env_var("OTEL_EXPORTER_OTLP_HEADERS").as_deref()),
+      resource_attributes: parse_key_value_list(read_env_var("OTEL_RESOURCE_ATTRIBUTES").as_deref()),
+    }
+  }
+}
+
+/// Parses a list in the format `key1=value1,key2=value2`.
+fn parse_key_value_list(text: Option<&str>) -> Vec<(String, String)> {
+  let Some(text) = text else {
+    return Vec::new();
+  };
+  text
+    .split(',')
+    .filter_map(|item| item.split_once('='))
+    .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+    .filter(|(key, _)| !key.is_empty())
+    .collect()
+}
+
+/// Gets the urls and JSON bodies of the requests that export the run
+/// and its telemetry as a trace and metrics to an OTLP/HTTP endpoint.
+pub fn get_otlp_requests(endpoint: &str, run: &OtlpRun, data: &TelemetryData, resource_attributes: &[(String, String)]) -> Vec<(String, Vec<u8>)> {
+  let endpoint = endpoint.trim_end_matches('/');
+  let mut attributes = vec![string_attribute("service.name", "dprint"), string_attribute("service.version", run.cli_version)];
+  attributes.extend(resource_attributes.iter().map(|(key, value)| string_attribute(key, value)));
+  let resource = json!({ "attributes": attributes });
+  let scope = json!({ "name": "dprint", "version": run.cli_version });
+
+  let traces = json!({
+    "resourceSpans": [{
+      "resource": resource,
+      "scopeSpans": [{
+        "scope": scope,
+        "spans": [get_command_span(run, data)],
+      }],
+    }],
+  });
+  let metrics = json!({
+    "resourceMetrics": [{
+      "resource": resource,
+      "scopeMetrics": [{
+        "scope": scope,
+        "metrics": get_metrics(run, data),
+      }],
+    }],
+  });
+  vec![
+    (format!("{}/v1/traces", endpoint), serde_json::to_vec(&traces).unwrap()),
+    (format!("{}/v1/metrics", endpoint), serde_json::to_vec(&metrics).unwrap()),
+  ]
+}
+
+fn get_command_span(run: &OtlpRun, data: &TelemetryData) -> Value {
+  let mut attributes = vec![string_attribute("dprint.command", run.command_name)];
+  for result in [
+    FileResult::Unchanged,
+    FileResult::Changed,
+    FileResult::Cached,
+    FileResult::TimedOut,
+    FileResult::Ignored,
+    FileResult::Error,
+  ] {
+    let count = data.file_results.get(&result).copied().unwrap_or(0);
+    attributes.push(int_attribute(&format!("dprint.files.{}", result.as_str()), count));
+  }
+  json!({
+    "traceId": random_hex_id(16),
+    "spanId": random_hex_id(8),
+    "name": format!("dprint {}", run.command_name),
+    // internal
+    "kind": 1,
+    "startTimeUnixNano": unix_nanos(run.start_time),
+    "endTimeUnixNano": unix_nanos(run.end_time),
+    "attributes": attributes,
+    // ok or error
+    "status": { "code": if run.is_success { 1 } else { 2 } },
+  })
+}
+
+fn get_metrics(run: &OtlpRun, data: &TelemetryData) -> Vec<Value> {
+  let start_time = unix_nanos(run.start_time);
+  let end_time = unix_nanos(run.end_time);
+  let data_point = |attributes: Vec<Value>, value: (&str, Value)| {
+    let mut data_point = json!({
+      "attributes": attributes,
+      "startTimeUnixNano": start_time,
+      "timeUnixNano": end_time,
+    });
+    data_point[value.0] = value.1;
+    data_point
+  };
+  // delta because each run is a separate process
+  let sum = |name: &str, unit: &str, data_points: Vec<Value>| {
+    json!({
+      "name": name,
+      "unit": unit,
+      "sum": { "aggregationTemporality": 1, "isMonotonic": true, "dataPoints": data_points },
+    })
+  };
+
+  let mut metrics = vec![json!({
+    "name": "dprint.command.duration",
+    "unit": "ms",
+    "gauge": {
+      "dataPoints": [data_point(
+        vec![string_attribute("dprint.command", run.command_name)],
+        ("asDouble", json!(duration_ms(run.end_time.duration_since(run.start_time).unwrap_or_default()))),
+      )],
+    },
+  })];
+  if !data.file_results.is_empty() {
+    metrics.push(sum(
+      "dprint.files",
+      "{file}",
+      data
+        .file_results
+        .iter()
+        .map(|(result, count)| {
+          data_point(
+            vec![string_attribute("dprint.file.result", result.as_str())],
+            ("asInt", json!(count.to_string())),
+          )
+        })
+        .collect(),
+    ));
+  }
+  if !data.cache_lookups.is_empty() {
+    metrics.push(sum(
+      "dprint.cache.lookups",
+      "{lookup}",
+      data
+        .cache_lookups
+        .iter()
+        .map(|((kind, is_hit), count)| {
+          data_point(
+            vec![
+              string_attribute("dprint.cache.kind", kind.as_str()),
+              bool_attribute("dprint.cache.hit", *is_hit),
+            ],
+            ("asInt", json!(count.to_string())),
+          )
+        })
+        .collect(),
+    ));
+  }
+  if !data.plugin_formats.is_empty() {
+    let plugin_attributes = |name: &str, version: &str| vec![string_attribute("dprint.plugin.name", name), string_attribute("dprint.plugin.version", version)];
+    metrics.push(sum(
+      "dprint.plugin.format.count",
+      "{format}",
+      data
+        .plugin_formats
+        .iter()
+        .map(|((name, version), stats)| data_point(plugin_attributes(name, version), ("asInt", json!(stats.count.to_string()))))
+        .collect(),
+    ));
+    metrics.push(sum(
+      "dprint.plugin.format.duration",
+      "ms",
+      data
+        .plugin_formats
+        .iter()
+        .map(|((name, version), stats)| data_point(plugin_attributes(name, version), ("asDouble", json!(duration_ms(stats.duration)))))
+        .collect(),
+    ));
+  }
+  metrics
+}
+
+fn string_attribute(key: &str, value: &str) -> Value {
+  json!({ "key": key, "value": { "stringValue": value } })
+}
+
+fn int_attribute(key: &str, value: u64) -> Value {
+  json!({ "key": key, "value": { "intValue": value.to_string() } })
+}
+
+fn bool_attribute(key: &str, value: bool) -> Value {
+  json!({ "key": key, "value": { "boolValue": value } })
+}
+
+fn unix_nanos(time: SystemTime) -> String {
+  time.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_nanos().to_string()
+}
+
+fn duration_ms(duration: Duration) -> f64 {
+  duration.as_secs_f64() * 1000.0
+}
+
+/// Gets a random lowercase hex id (ex. a trace id) of the provided number of bytes.
+fn random_hex_id(byte_count: usize) -> String {
+  let mut text = String::with_capacity(byte_count * 2 + 16);
+  while text.len() < byte_count * 2 {
+    // each RandomState has different random keys
+    text.push_str(&format!("{:016x}", RandomState::new().hash_one(text.len())));
+  }
+  text.truncate(byte_count * 2);
+  text
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_parse_env_config() {
+    let config = OtlpEnvConfig::from_env_vars(|name| match name {
+      "OTEL_EXPORTER_OTLP_HEADERS" => Some("Authorization=Bearer 123, x-other = value".to_string()),
+      "OTEL_RESOURCE_ATTRIBUTES" => Some("ci.job.id=456,invalid".to_string()),
+      _ => None,
+    });
+    assert_eq!(
+      config,
+      OtlpEnvConfig {
+        headers: vec![
+          ("Authorization".to_string(), "Bearer 123".to_string()),
+          ("x-other".to_string(), "value".to_string()),
+        ],
+        resource_attributes: vec![("ci.job.id".to_string(), "456".to_string())],
+      }
+    );
+    assert_eq!(OtlpEnvConfig::from_env_vars(|_| None), OtlpEnvConfig::default());
+  }
+
+  #[test]
+  fn should_get_otlp_requests() {
+    let mut data = TelemetryData::default();
+    data.file_results.insert(FileResult::Changed, 2);
+    data.cache_lookups.insert((CacheKind::Incremental, true), 3);
+    data.plugin_formats.insert(
+      ("test-plugin".to_string(), "0.1.0".to_string()),
+      PluginFormatStats {
+        count: 2,
+        duration: Duration::from_millis(5),
+      },
+    );
+    let start_time = SystemTime::UNIX_EPOCH + Duration::from_secs(10);
+    let run = OtlpRun {
+      command_name: "fmt",
+      cli_version: "0.1.0",
+      start_time,
+      end_time: start_time + Duration::from_millis(20),
+      is_success: true,
+    };
+    let requests = get_otlp_requests("http://localhost:4318/", &run, &data, &[("ci.job.id".to_string(), "1".to_string())]);
+    let requests = requests
+      .into_iter()
+      .map(|(url, body)| (url, serde_json::from_slice::<Value>(&body).unwrap()))
+      .collect::<Vec<_>>();
+
+    assert_eq!(requests[0].0, "http://localhost:4318/v1/traces");
+    let resource_spans = &requests[0].1["resourceSpans"][0];
+    assert_eq!(
+      resource_spans["resource"]["attributes"],
+      json!([
+        { "key": "service.name", "value": { "stringValue": "dprint" } },
+        { "key": "service.version", "value": { "stringValue": "0.1.0" } },
+        { "key": "ci.job.id", "value": { "stringValue": "1" } },
+      ])
+    );
+    let span = &resource_spans["scopeSpans"][0]["spans"][0];
+    assert_eq!(span["name"], "dprint fmt");
+    assert_eq!(span["traceId"].as_str().unwrap().len(), 32);
+    assert_eq!(span["spanId"].as_str().unwrap().len(), 16);
+    assert_eq!(span["startTimeUnixNano"], "10000000000");
+    assert_eq!(span["endTimeUnixNano"], "10020000000");
+    assert_eq!(span["status"]["code"], 1);
+    assert_eq!(span["attributes"][2], json!({ "key": "dprint.files.changed", "value": { "intValue": "2" } }));
+
+    assert_eq!(requests[1].0, "http://localhost:4318/v1/metrics");
+    let metrics = requests[1].1["resourceMetrics"][0]["scopeMetrics"][0]["metrics"].as_array().unwrap();
+    assert_eq!(
+      metrics.iter().map(|m| m["name"].as_str().unwrap()).collect::<Vec<_>>(),
+      vec![
+        "dprint.command.duration",
+        "dprint.files",
+        "dprint.cache.lookups",
+        "dprint.plugin.format.count",
+        "dprint.plugin.format.duration",
+      ]
+    );
+    assert_eq!(metrics[0]["gauge"]["dataPoints"][0]["asDouble"], 20.0);
+    assert_eq!(
+      metrics[2]["sum"]["dataPoints"][0],
+      json!({
+        "attributes": [
+          { "key": "dprint.cache.kind", "value": { "stringValue": "incremental" } },
+          { "key": "dprint.cache.hit", "value": { "boolValue": true } },
+        ],
+        "startTimeUnixNano": "10000000000",
+        "timeUnixNano": "10020000000",
+        "asInt": "3",
+      })
+    );
+    assert_eq!(metrics[4]["sum"]["dataPoints"][0]["asDouble"], 5.0);
+  }
+
+  #[test]
+  fn should_get_random_hex_ids() {
+    let id = random_hex_id(16);
+    assert_eq!(id.len(), 32);
+    assert!(id.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_ne!(id, random_hex_id(16));
+  }
+}
@@ -6,6 +6,7 @@ use anyhow::Result;
 use once_cell::sync::OnceCell;
 
 use super::certs::get_root_cert_store;
+use super::checksums::StreamingChecksum;
 use super::logging::ProgressBarStyle;
 use super::logging::ProgressBars;
 use super::Logger;
@@ -30,6 +31,30 @@ impl RealUrlDownloader {
   }
 
   pub fn download(&self, url: &str) -> Result<Option<Vec<u8>>> {
+    self.download_with_checksum(url, None)
+  }
+
+  /// Downloads the url while verifying the provided sha256 checksum
+  /// as the bytes are received.
+  pub fn download_with_checksum(&self, url: &str, checksum: Option<&str>) -> Result<Option<Vec<u8>>> {
+    let agent = self.get_agent(url)?;
+    self.download_with_retries(url, checksum, agent)
+  }
+
+  /// Sends the JSON body to the url in a POST request.
+  pub fn post_json(&self, url: &str, headers: &[(String, String)], body: &[u8]) -> Result<()> {
+    let agent = self.get_agent(url)?;
+    let mut request = agent.post(url).set("Content-Type", "application/json");
+    for (name, value) in headers {
+      request = request.set(name, value);
+    }
+    match request.send_bytes(body) {
+      Ok(_) => Ok(()),
+      Err(err) => bail!("Error sending to {} - Error: {:#}", url, err),
+    }
+  }
+
+  fn get_agent(&self, url: &str) -> Result<&ureq::Agent> {
     let lowercase_url = url.to_lowercase();
     let (agent, kind) = if lowercase_url.starts_with("https://") {
       (&self.https_agent, AgentKind::Https)
@@ -39,15 +64,34 @@ impl RealUrlDownloader {
       bail!("Not implemented url scheme: {}", url);
     };
     // this is expensive, but we're already in a blocking task here
-    let agent = agent.get_or_try_init(|| build_agent(kind, &self.logger))?;
-    self.download_with_retries(url, agent)
+    agent.get_or_try_init(|| build_agent(kind, &self.logger))
   }
 
-  fn download_with_retries(&self, url: &str, agent: &ureq::Agent) -> Result<Option<Vec<u8>>> {
+  fn download_with_retries(&self, url: &str, checksum: Option<&str>, agent: &ureq::Agent) -> Result<Option<Vec<u8>>> {
+    let mut download = PartialDownload::default();
     let mut last_error = None;
     for retry_count in 0..(MAX_RETRIES + 1) {
-      match inner_download(url, retry_count, agent, self.progress_bars.as_deref()) {
-        Ok(result) => return Ok(result),
+      if download.can_resume() {
+        log_debug!(self.logger, "Resuming download of {} at byte {}", url, download.bytes.len());
+      }
+      match inner_download(url, retry_count, checksum.is_some(), agent, &mut download, self.progress_bars.as_deref()) {
+        Ok(false) => return Ok(None),
+        Ok(true) => {
+          let was_resumed = download.was_resumed;
+          let download = std::mem::take(&mut download);
+          let Some(checksum) = checksum else {
+            return Ok(Some(download.bytes));
+          };
+          match download.checksum.verify(checksum) {
+            Ok(()) => return Ok(Some(download.bytes)),
+            // the server might not have served the same file when resuming, so start over
+            Err(err) if was_resumed && retry_count < MAX_RETRIES => {
+              log_debug!(self.logger, "Checksum mismatch after resuming download of {}. Downloading again.", url);
+              last_error = Some(err.into());
+            }
+            Err(err) => return Err(err.into()),
+          }
+        }
         Err(err) => {
           if retry_count < MAX_RETRIES {
             log_debug!(self.logger, "Error downloading {} ({}/{}): {:#}", url, retry_count, MAX_RETRIES, err);
@@ -60,47 +104,121 @@ impl RealUrlDownloader {
   }
 }
 
-fn inner_download(url: &str, retry_count: u8, agent: &ureq::Agent, progress_bars: Option<&ProgressBars>) -> Result<Option<Vec<u8>>> {
-  let resp = match agent.get(url).call() {
+/// The bytes of a download received so far, which are kept
+/// between retries so an interrupted download may be resumed.
+#[derive(Default)]
+struct PartialDownload {
+  bytes: Vec<u8>,
+  checksum: StreamingChecksum,
+  accepts_ranges: bool,
+  was_resumed: bool,
+}
+
+impl PartialDownload {
+  fn can_resume(&self) -> bool {
+    self.accepts_ranges && !self.bytes.is_empty()
+  }
+
+  fn extend(&mut self, bytes: &[u8]) {
+    self.checksum.update(bytes);
+    self.bytes.extend(bytes);
+  }
+}
+
+/// Downloads the url into the partial download, returning `false` when not found.
+fn inner_download(
+  url: &str,
+  retry_count: u8,
+  is_verifying: bool,
+  agent: &ureq::Agent,
+  download: &mut PartialDownload,
+  progress_bars: Option<&ProgressBars>,
+) -> Result<bool> {
+  let is_resuming = download.can_resume();
+  let mut request = agent.get(url);
+  if is_resuming {
+    request = request.set("Range", &format!("bytes={}-", download.bytes.len()));
+  }
+  let resp = match request.call() {
     Ok(resp) => resp,
     Err(ureq::Error::Status(404, _)) => {
-      return Ok(None);
+      return Ok(false);
     }
     Err(err) => {
+      if is_resuming {
+        // start over on the next retry
+        *download = PartialDownload::default();
+      }
       bail!("Error downloading {} - Error: {:#}", url, err)
     }
   };
 
-  let total_size = resp.header("Content-Length").and_then(|s| s.parse::<usize>().ok()).unwrap_or(0);
+  let content_range_start = resp.header("Content-Range").and_then(parse_content_range_start);
+  if is_resuming && resp.status() == 206 && content_range_start == Some(download.bytes.len()) {
+    download.was_resumed = true;
+  } else {
+    // the server sent the whole file
+    *download = PartialDownload::default();
+  }
+  download.accepts_ranges = resp.header("Accept-Ranges") == Some("bytes") || resp.status() == 206;
+
+  let remaining_size = resp.header("Content-Length").and_then(|s| s.parse::<usize>().ok()).unwrap_or(0);
   let mut reader = resp.into_reader();
-  match read_response(url, retry_count, &mut reader, total_size, progress_bars) {
-    Ok(result) => Ok(Some(result)),
+  let mut message = format!("{} {}", if is_verifying { "Downloading and verifying" } else { "Downloading" }, url);
+  if retry_count > 0 {
+    let action = if download.was_resumed { "Resumed" } else { "Retry" };
+    message.push_str(&format!(" ({} {}/{})", action, retry_count, MAX_RETRIES));
+  }
+  match read_response(message, &mut reader, remaining_size, download, progress_bars) {
+    Ok(()) => Ok(true),
     Err(err) => bail!("Error downloading {} - {:#}", url, err),
   }
 }
 
-fn read_response(url: &str, retry_count: u8, reader: &mut impl Read, total_size: usize, progress_bars: Option<&ProgressBars>) -> Result<Vec<u8>> {
-  let mut final_bytes = Vec::with_capacity(total_size);
-  if let Some(progress_bars) = &progress_bars {
-    let mut buf: [u8; 512] = [0; 512]; // ensure progress bars update often
-    let mut message = format!("Downloading {}", url);
-    if retry_count > 0 {
-      message.push_str(&format!(" (Retry {}/{})", retry_count, MAX_RETRIES))
-    }
-    let pb = progress_bars.add_progress(message, ProgressBarStyle::Download, total_size);
-    loop {
-      let bytes_read = reader.read(&mut buf)?;
-      if bytes_read == 0 {
-        break;
+fn read_response(
+  message: String,
+  reader: &mut impl Read,
+  remaining_size: usize,
+  download: &mut PartialDownload,
+  progress_bars: Option<&ProgressBars>,
+) -> Result<()> {
+  download.bytes.reserve(remaining_size);
+  let mut buf: [u8; 512] = [0; 512]; // ensure progress bars update often
+  let pb = progress_bars.map(|progress_bars| {
+    let pb = progress_bars.add_progress(message, ProgressBarStyle::Download, download.bytes.len() + remaining_size);
+    pb.set_position(download.bytes.len());
+    pb
+  });
+  loop {
+    let bytes_read = match reader.read(&mut buf) {
+      Ok(bytes_read) => bytes_read,
+      Err(err) => {
+        if let Some(pb) = &pb {
+          pb.finish();
+        }
+        return Err(err.into());
       }
-      final_bytes.extend(&buf[..bytes_read]);
-      pb.set_position(final_bytes.len());
+    };
+    if bytes_read == 0 {
+      break;
+    }
+    download.extend(&buf[..bytes_read]);
+    if let Some(pb) = &pb {
+      // these bytes have been hashed at this point
+      pb.set_position(download.bytes.len());
     }
+  }
+  if let Some(pb) = &pb {
     pb.finish();
-  } else {
-    reader.read_to_end(&mut final_bytes)?;
   }
-  Ok(final_bytes)
+  Ok(())
+}
+
+/// Gets the start of a `Content-Range` header value (ex. `bytes 100-999/1000`).
+fn parse_content_range_start(value: &str) -> Option<usize> {
+  let range = value.strip_prefix("bytes ")?;
+  let (start, _) = range.split_once('-')?;
+  start.trim().parse().ok()
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
@@ -140,3 +258,42 @@ fn read_proxy_env_var(env_var_name: &str) -> Option<String> {
     .ok()
     .or_else(|| std::env::var(env_var_name.to_lowercase()).ok())
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::utils::get_sha256_checksum;
+
+  #[test]
+  fn should_parse_content_range_start() {
+    assert_eq!(parse_content_range_start("bytes 100-999/1000"), Some(100));
+    assert_eq!(parse_content_range_start("bytes 0-0/*"), Some(0));
+    assert_eq!(parse_content_range_start("bytes */1000"), None);
+    assert_eq!(parse_content_range_start("items 1-2/3"), None);
+  }
+
+  #[test]
+  fn should_keep_received_bytes_when_interrupted() {
+    struct InterruptedReader(Option<&'static [u8]>);
+
+    impl Read for InterruptedReader {
+      fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.0.take() {
+          Some(bytes) => {
+            buf[..bytes.len()].copy_from_slice(bytes);
+            Ok(bytes.len())
+          }
+          None => Err(std::io::ErrorKind::ConnectionReset.into()),
+        }
+      }
+    }
+
+    let mut download = PartialDownload::default();
+    let result = read_response(String::new(), &mut InterruptedReader(Some(b"hello ")), 11, &mut download, None);
+    assert!(result.is_err());
+    assert_eq!(download.bytes, b"hello ");
+    read_response(String::new(), &mut b"world".as_slice(), 5, &mut download, None).unwrap();
+    assert_eq!(download.bytes, b"hello world");
+    assert!(download.checksum.verify(&get_sha256_checksum(b"hello world")).is_ok());
+  }
+}
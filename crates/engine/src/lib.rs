@@ -0,0 +1,28 @@
+//! Embeds dprint's configuration resolution, plugin resolution, and formatting
+//! so that tools may format files without running the CLI.
+//!
+//! ```ignore
+//! use dprint_engine::*;
+//!
+//! let environment = RealEnvironment::new(RealEnvironmentOptions {
+//!   log_level: LogLevel::Warn,
+//!   is_stdout_machine_readable: false,
+//!   offline: false,
+//!   // there's no user to prompt when embedded, so error for
+//!   // process plugins that haven't been trusted yet
+//!   process_plugin_trust: ProcessPluginTrustPolicy::Deny,
+//! })?;
+//! let engine = Engine::new(environment, EngineOptions::default()).await?;
+//! if engine.can_format(&file_path) {
+//!   if let Some(formatted_bytes) = engine.format_file(&file_path, file_bytes).await? {
+//!     // ...
+//!   }
+//! }
+//! engine.shutdown().await;
+//! ```
+//!
+//! With the `build-script` feature, `format_generated_files` formats generated
+//! files in memory (ex. from a `build.rs` file) and optionally only uses local or
+//! already cached plugins.
+
+pub use dprint::engine::*;
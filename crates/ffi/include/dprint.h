@@ -0,0 +1,66 @@
+#ifndef DPRINT_H
+#define DPRINT_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define DPRINT_FORMAT_UNCHANGED 0
+#define DPRINT_FORMAT_CHANGED 1
+#define DPRINT_FORMAT_ERROR -1
+
+/* An engine is not thread safe, so calls using the same engine must not be made concurrently. */
+typedef struct DprintEngine DprintEngine;
+
+/*
+ * Creates an engine for the configuration file at the provided path or url, which
+ * is discovered from the current working directory when NULL.
+ *
+ * Process plugins (native executables) that haven't been trusted yet cause an
+ * error unless `trust_process_plugins` is true.
+ *
+ * Returns NULL on error and sets `error` to a message that must be freed with
+ * `dprint_string_free`.
+ */
+DprintEngine *dprint_engine_new(const char *config, bool trust_process_plugins, char **error);
+
+/* Gets if the file is matched by the configuration and there's a plugin that formats it. */
+bool dprint_engine_can_format(const DprintEngine *engine, const char *file_path);
+
+/*
+ * Formats the bytes of a file.
+ *
+ * Returns DPRINT_FORMAT_CHANGED and sets `result` and `result_len` to the formatted
+ * bytes, which must be freed with `dprint_bytes_free`, when the file was formatted.
+ * Returns DPRINT_FORMAT_UNCHANGED when the file didn't change or no plugin formats it.
+ * Returns DPRINT_FORMAT_ERROR and sets `error` to a message that must be freed with
+ * `dprint_string_free` on error.
+ */
+int32_t dprint_engine_format(
+  const DprintEngine *engine,
+  const char *file_path,
+  const uint8_t *file_bytes,
+  size_t file_bytes_len,
+  uint8_t **result,
+  size_t *result_len,
+  char **error
+);
+
+/* Shuts down the plugins and frees the engine. */
+void dprint_engine_free(DprintEngine *engine);
+
+/* Frees bytes returned by `dprint_engine_format`. */
+void dprint_bytes_free(uint8_t *bytes, size_t len);
+
+/* Frees an error message. */
+void dprint_string_free(char *text);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
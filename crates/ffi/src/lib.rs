@@ -0,0 +1,206 @@
+//! C API over the dprint engine for integrations that can't use the
+//! CLI or its editor service. See `include/dprint.h` for the declarations.
+//!
+//! An engine is not thread safe, so calls using the same engine must not
+//! be made concurrently.
+
+use std::ffi::c_char;
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::path::Path;
+use std::ptr;
+
+use anyhow::Context;
+use anyhow::Result;
+use dprint_engine::Engine;
+use dprint_engine::EngineOptions;
+use dprint_engine::LogLevel;
+use dprint_engine::ProcessPluginTrustPolicy;
+use dprint_engine::RealEnvironment;
+use dprint_engine::RealEnvironmentOptions;
+
+pub const DPRINT_FORMAT_UNCHANGED: i32 = 0;
+pub const DPRINT_FORMAT_CHANGED: i32 = 1;
+pub const DPRINT_FORMAT_ERROR: i32 = -1;
+
+pub struct DprintEngine {
+  runtime: tokio::runtime::Runtime,
+  engine: Engine<RealEnvironment>,
+}
+
+/// Creates an engine for the configuration file at the provided path or url,
+/// which is discovered from the current working directory when null.
+///
+/// Process plugins (native executables) that haven't been trusted yet
+/// cause an error unless `trust_process_plugins` is true.
+///
+/// Returns null on error and sets `error` to a message that must be freed
+/// with `dprint_string_free`.
+///
+/// # Safety
+///
+/// `config` must be null or a valid nul-terminated string and `error` must
+/// be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn dprint_engine_new(config: *const c_char, trust_process_plugins: bool, error: *mut *mut c_char) -> *mut DprintEngine {
+  let result = (|| {
+    let config = match config.is_null() {
+      true => None,
+      false => Some(read_str(config, "config")?.to_string()),
+    };
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build()?;
+    let environment = RealEnvironment::new(RealEnvironmentOptions {
+      log_level: LogLevel::Warn,
+      is_stdout_machine_readable: false,
+      offline: false,
+      process_plugin_trust: match trust_process_plugins {
+        true => ProcessPluginTrustPolicy::Trust,
+        false => ProcessPluginTrustPolicy::Deny,
+      },
+    })?;
+    let engine = runtime.block_on(Engine::new(environment, EngineOptions { config, ..Default::default() }))?;
+    Ok(DprintEngine { runtime, engine })
+  })();
+  match result {
+    Ok(engine) => Box::into_raw(Box::new(engine)),
+    Err(err) => {
+      set_error(error, err);
+      ptr::null_mut()
+    }
+  }
+}
+
+/// Gets if the file is matched by the configuration and there's a plugin that formats it.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine and `file_path` a valid nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn dprint_engine_can_format(engine: *const DprintEngine, file_path: *const c_char) -> bool {
+  let engine = &*engine;
+  match read_str(file_path, "file_path") {
+    Ok(file_path) => engine.engine.can_format(file_path),
+    Err(_) => false,
+  }
+}
+
+/// Formats the bytes of a file.
+///
+/// Returns `DPRINT_FORMAT_CHANGED` and sets `result` and `result_len` to the
+/// formatted bytes, which must be freed with `dprint_bytes_free`, when the file
+/// was formatted. Returns `DPRINT_FORMAT_UNCHANGED` when the file didn't change
+/// or no plugin formats it. Returns `DPRINT_FORMAT_ERROR` and sets `error` to a
+/// message that must be freed with `dprint_string_free` on error.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine, `file_path` a valid nul-terminated string,
+/// `file_bytes` valid for reads of `file_bytes_len` bytes (or null when the length
+/// is zero), `result` and `result_len` valid for writes, and `error` null or valid
+/// for writes.
+#[no_mangle]
+pub unsafe extern "C" fn dprint_engine_format(
+  engine: *const DprintEngine,
+  file_path: *const c_char,
+  file_bytes: *const u8,
+  file_bytes_len: usize,
+  result: *mut *mut u8,
+  result_len: *mut usize,
+  error: *mut *mut c_char,
+) -> i32 {
+  let engine = &*engine;
+  let format_result = read_str(file_path, "file_path").and_then(|file_path| {
+    let file_bytes = match file_bytes_len {
+      0 => Vec::new(),
+      _ => std::slice::from_raw_parts(file_bytes, file_bytes_len).to_vec(),
+    };
+    engine.runtime.block_on(engine.engine.format_file(Path::new(file_path), file_bytes))
+  });
+  match format_result {
+    Ok(Some(bytes)) => {
+      let bytes = Box::into_raw(bytes.into_boxed_slice());
+      *result_len = bytes.len();
+      *result = bytes as *mut u8;
+      DPRINT_FORMAT_CHANGED
+    }
+    Ok(None) => DPRINT_FORMAT_UNCHANGED,
+    Err(err) => {
+      set_error(error, err);
+      DPRINT_FORMAT_ERROR
+    }
+  }
+}
+
+/// Shuts down the plugins and frees the engine.
+///
+/// # Safety
+///
+/// `engine` must be null or a valid engine, which must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn dprint_engine_free(engine: *mut DprintEngine) {
+  if engine.is_null() {
+    return;
+  }
+  let DprintEngine { runtime, engine } = *Box::from_raw(engine);
+  runtime.block_on(engine.shutdown());
+}
+
+/// Frees bytes returned by `dprint_engine_format`.
+///
+/// # Safety
+///
+/// `bytes` must be null or bytes returned by `dprint_engine_format` along with their length.
+#[no_mangle]
+pub unsafe extern "C" fn dprint_bytes_free(bytes: *mut u8, len: usize) {
+  if !bytes.is_null() {
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(bytes, len)));
+  }
+}
+
+/// Frees an error message.
+///
+/// # Safety
+///
+/// `text` must be null or a string returned by this library.
+#[no_mangle]
+pub unsafe extern "C" fn dprint_string_free(text: *mut c_char) {
+  if !text.is_null() {
+    drop(CString::from_raw(text));
+  }
+}
+
+unsafe fn read_str<'a>(text: *const c_char, name: &str) -> Result<&'a str> {
+  if text.is_null() {
+    anyhow::bail!("Expected {} to not be null.", name);
+  }
+  CStr::from_ptr(text).to_str().with_context(|| format!("Expected {} to be valid UTF-8.", name))
+}
+
+unsafe fn set_error(error: *mut *mut c_char, err: anyhow::Error) {
+  if error.is_null() {
+    return;
+  }
+  // nul characters would truncate the message, so replace them
+  let message = format!("{:#}", err).replace('\0', " ");
+  *error = CString::new(message).unwrap().into_raw();
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_error_for_missing_config() {
+    let config = CString::new("/non-existent/dprint.json").unwrap();
+    let mut error = ptr::null_mut();
+    unsafe {
+      let engine = dprint_engine_new(config.as_ptr(), false, &mut error);
+      assert!(engine.is_null());
+      assert!(!error.is_null());
+      let message = CStr::from_ptr(error).to_str().unwrap().to_string();
+      dprint_string_free(error);
+      assert!(message.contains("/non-existent/dprint.json"), "{}", message);
+      dprint_engine_free(engine);
+    }
+  }
+}
@@ -0,0 +1,46 @@
+export interface FormatterOptions {
+  /**
+   * Path or url of the configuration file, which is otherwise
+   * discovered in the current working directory or its ancestors.
+   */
+  config?: string;
+  /** Plugins to use instead of the ones in the configuration file. */
+  plugins?: string[];
+  /**
+   * Runs process plugins (native executables) that haven't been trusted
+   * yet instead of erroring. Defaults to false.
+   */
+  trustProcessPlugins?: boolean;
+}
+
+/** Resolves the configuration and its plugins, then creates a formatter for them. */
+export function createFormatter(options?: FormatterOptions): Promise<Formatter>;
+
+/** Formats files with the plugins of a configuration file. */
+export class Formatter {
+  private constructor();
+  /**
+   * Gets if the file is matched by the configuration's includes and excludes
+   * and there's a plugin that formats it.
+   */
+  canFormat(filePath: string): Promise<boolean>;
+  /**
+   * Formats the text with the plugins associated with the file path, returning
+   * the text as-is when it didn't change or no plugin formats it.
+   *
+   * The includes and excludes of the configuration aren't applied, so use
+   * `canFormat` to check those.
+   */
+  formatText(filePath: string, fileText: string): Promise<string>;
+  /**
+   * Formats the file on the file system when it's matched by the configuration,
+   * returning if it changed.
+   */
+  formatFile(filePath: string): Promise<boolean>;
+  /**
+   * Shuts down the plugins. The formatter can't be used afterwards.
+   *
+   * This also happens when the formatter is garbage collected.
+   */
+  shutdown(): Promise<void>;
+}
@@ -0,0 +1,27 @@
+// @ts-check
+"use strict";
+
+const path = require("path");
+
+const fileName = `dprint-node.${getPlatformSuffix()}.node`;
+module.exports = require(path.join(__dirname, fileName));
+
+function getPlatformSuffix() {
+  const arch = process.arch;
+  switch (process.platform) {
+    case "win32":
+      return `win32-${arch}-msvc`;
+    case "darwin":
+      return `darwin-${arch}`;
+    case "linux":
+      return `linux-${arch}-${isMusl() ? "musl" : "gnu"}`;
+    default:
+      throw new Error(`Unsupported platform: ${process.platform} ${arch}`);
+  }
+}
+
+function isMusl() {
+  // @ts-ignore
+  const report = typeof process.report?.getReport === "function" ? process.report.getReport() : undefined;
+  return report != null && report.header != null && report.header.glibcVersionRuntime == null;
+}
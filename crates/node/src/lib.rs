@@ -0,0 +1,238 @@
+//! Node.js bindings over the dprint engine so that tools like editor extensions
+//! and build tools can format without spawning a process per invocation.
+//!
+//! The engine is not `Send`, so each formatter owns a thread with a current
+//! thread runtime that the engine lives on and requests are sent to it. The
+//! resolved plugins stay initialized on that thread for the formatter's lifetime.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use dprint_engine::Engine;
+use dprint_engine::EngineOptions;
+use dprint_engine::Environment;
+use dprint_engine::LogLevel;
+use dprint_engine::ProcessPluginTrustPolicy;
+use dprint_engine::RealEnvironment;
+use dprint_engine::RealEnvironmentOptions;
+use napi_derive::napi;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct FormatterOptions {
+  /// Path or url of the configuration file, which is otherwise
+  /// discovered in the current working directory or its ancestors.
+  pub config: Option<String>,
+  /// Plugins to use instead of the ones in the configuration file.
+  pub plugins: Option<Vec<String>>,
+  /// Runs process plugins (native executables) that haven't been trusted
+  /// yet instead of erroring. Defaults to false.
+  pub trust_process_plugins: Option<bool>,
+}
+
+enum Request {
+  CanFormat {
+    file_path: PathBuf,
+    sender: oneshot::Sender<bool>,
+  },
+  FormatText {
+    file_path: PathBuf,
+    file_text: String,
+    sender: oneshot::Sender<Result<String>>,
+  },
+  FormatFile {
+    file_path: PathBuf,
+    sender: oneshot::Sender<Result<bool>>,
+  },
+  Shutdown {
+    sender: oneshot::Sender<()>,
+  },
+}
+
+/// Resolves the configuration and its plugins, then creates a formatter for them.
+#[napi]
+pub async fn create_formatter(options: Option<FormatterOptions>) -> napi::Result<Formatter> {
+  let options = options.unwrap_or_default();
+  let process_plugin_trust = match options.trust_process_plugins.unwrap_or(false) {
+    true => ProcessPluginTrustPolicy::Trust,
+    false => ProcessPluginTrustPolicy::Deny,
+  };
+  let options = EngineOptions {
+    config: options.config,
+    plugins: options.plugins.unwrap_or_default(),
+  };
+  let (sender, receiver) = mpsc::unbounded_channel();
+  let (init_sender, init_receiver) = oneshot::channel();
+  std::thread::spawn(move || run_engine_thread(options, process_plugin_trust, receiver, init_sender));
+  match init_receiver.await {
+    Ok(Ok(())) => Ok(Formatter { sender }),
+    Ok(Err(err)) => Err(to_napi_error(err)),
+    Err(_) => Err(napi::Error::from_reason("The formatter's thread exited unexpectedly.")),
+  }
+}
+
+/// Formats files with the plugins of a configuration file.
+#[napi]
+pub struct Formatter {
+  sender: mpsc::UnboundedSender<Request>,
+}
+
+#[napi]
+impl Formatter {
+  /// Gets if the file is matched by the configuration's includes and excludes
+  /// and there's a plugin that formats it.
+  #[napi]
+  pub async fn can_format(&self, file_path: String) -> napi::Result<bool> {
+    let (sender, receiver) = oneshot::channel();
+    self.send(Request::CanFormat {
+      file_path: PathBuf::from(file_path),
+      sender,
+    })?;
+    receive(receiver).await
+  }
+
+  /// Formats the text with the plugins associated with the file path, returning
+  /// the text as-is when it didn't change or no plugin formats it.
+  ///
+  /// The includes and excludes of the configuration aren't applied, so use
+  /// `canFormat` to check those.
+  #[napi]
+  pub async fn format_text(&self, file_path: String, file_text: String) -> napi::Result<String> {
+    let (sender, receiver) = oneshot::channel();
+    self.send(Request::FormatText {
+      file_path: PathBuf::from(file_path),
+      file_text,
+      sender,
+    })?;
+    receive(receiver).await?.map_err(to_napi_error)
+  }
+
+  /// Formats the file on the file system when it's matched by the configuration,
+  /// returning if it changed.
+  #[napi]
+  pub async fn format_file(&self, file_path: String) -> napi::Result<bool> {
+    let (sender, receiver) = oneshot::channel();
+    self.send(Request::FormatFile {
+      file_path: PathBuf::from(file_path),
+      sender,
+    })?;
+    receive(receiver).await?.map_err(to_napi_error)
+  }
+
+  /// Shuts down the plugins. The formatter can't be used afterwards.
+  ///
+  /// This also happens when the formatter is garbage collected.
+  #[napi]
+  pub async fn shutdown(&self) -> napi::Result<()> {
+    let (sender, receiver) = oneshot::channel();
+    if self.sender.send(Request::Shutdown { sender }).is_err() {
+      // already shut down
+      return Ok(());
+    }
+    let _ = receiver.await;
+    Ok(())
+  }
+
+  fn send(&self, request: Request) -> napi::Result<()> {
+    self.sender.send(request).map_err(|_| shut_down_error())
+  }
+}
+
+fn run_engine_thread(
+  options: EngineOptions,
+  process_plugin_trust: ProcessPluginTrustPolicy,
+  mut receiver: mpsc::UnboundedReceiver<Request>,
+  init_sender: oneshot::Sender<Result<()>>,
+) {
+  let runtime = match tokio::runtime::Builder::new_current_thread().enable_time().build() {
+    Ok(runtime) => runtime,
+    Err(err) => {
+      let _ = init_sender.send(Err(err.into()));
+      return;
+    }
+  };
+  runtime.block_on(async move {
+    let engine = match create_engine(options, process_plugin_trust).await {
+      Ok(engine) => {
+        let _ = init_sender.send(Ok(()));
+        engine
+      }
+      Err(err) => {
+        let _ = init_sender.send(Err(err));
+        return;
+      }
+    };
+
+    let mut shutdown_sender = None;
+    while let Some(request) = receiver.recv().await {
+      match request {
+        Request::CanFormat { file_path, sender } => {
+          let _ = sender.send(engine.can_format(file_path));
+        }
+        Request::FormatText { file_path, file_text, sender } => {
+          let _ = sender.send(format_text(&engine, file_path, file_text).await);
+        }
+        Request::FormatFile { file_path, sender } => {
+          let _ = sender.send(format_file(&engine, file_path).await);
+        }
+        Request::Shutdown { sender } => {
+          shutdown_sender = Some(sender);
+          break;
+        }
+      }
+    }
+
+    // fail any queued requests by dropping their senders
+    drop(receiver);
+    engine.shutdown().await;
+    if let Some(sender) = shutdown_sender {
+      let _ = sender.send(());
+    }
+  });
+}
+
+async fn create_engine(options: EngineOptions, process_plugin_trust: ProcessPluginTrustPolicy) -> Result<Engine<RealEnvironment>> {
+  let environment = RealEnvironment::new(RealEnvironmentOptions {
+    log_level: LogLevel::Warn,
+    is_stdout_machine_readable: false,
+    offline: false,
+    process_plugin_trust,
+  })?;
+  Engine::new(environment, options).await
+}
+
+async fn format_text(engine: &Engine<RealEnvironment>, file_path: PathBuf, file_text: String) -> Result<String> {
+  match engine.format_file(&file_path, file_text.clone().into_bytes()).await? {
+    Some(bytes) => Ok(String::from_utf8(bytes)?),
+    None => Ok(file_text),
+  }
+}
+
+async fn format_file(engine: &Engine<RealEnvironment>, file_path: PathBuf) -> Result<bool> {
+  if !engine.can_format(&file_path) {
+    return Ok(false);
+  }
+  let environment = engine.environment();
+  let file_bytes = environment.read_file_bytes(&file_path)?;
+  match engine.format_file(&file_path, file_bytes).await? {
+    Some(bytes) => {
+      environment.atomic_write_file_bytes(&file_path, &bytes)?;
+      Ok(true)
+    }
+    None => Ok(false),
+  }
+}
+
+async fn receive<T>(receiver: oneshot::Receiver<T>) -> napi::Result<T> {
+  receiver.await.map_err(|_| shut_down_error())
+}
+
+fn shut_down_error() -> napi::Error {
+  napi::Error::from_reason("The formatter was shut down.")
+}
+
+fn to_napi_error(err: anyhow::Error) -> napi::Error {
+  napi::Error::from_reason(format!("{:#}", err))
+}
@@ -0,0 +1,49 @@
+import os
+from types import TracebackType
+from typing import List, Optional, Type, Union
+
+_Path = Union[str, os.PathLike[str]]
+
+class DprintError(Exception): ...
+
+class Formatter:
+    """Formats files with the plugins of a configuration file."""
+
+    def __init__(
+        self,
+        config: Optional[str] = None,
+        plugins: Optional[List[str]] = None,
+        trust_process_plugins: bool = False,
+    ) -> None:
+        """Resolves the configuration and its plugins.
+
+        The configuration file is discovered in the current working directory
+        or its ancestors when a path or url isn't provided. Process plugins
+        (native executables) that haven't been trusted yet raise an error
+        unless `trust_process_plugins` is true.
+        """
+    @property
+    def config_file_path(self) -> Optional[str]:
+        """The path of the resolved configuration file."""
+    def can_format(self, file_path: _Path) -> bool:
+        """Gets if the file is matched by the configuration's includes and excludes
+        and there's a plugin that formats it."""
+    def format_text(self, file_path: _Path, file_text: str) -> str:
+        """Formats the text with the plugins associated with the file path, returning
+        the text as-is when it didn't change or no plugin formats it.
+
+        The includes and excludes of the configuration aren't applied, so use
+        `can_format` to check those.
+        """
+    def format_file(self, file_path: _Path) -> bool:
+        """Formats the file on the file system when it's matched by the configuration,
+        returning if it changed."""
+    def close(self) -> None:
+        """Shuts down the plugins. The formatter can't be used afterwards."""
+    def __enter__(self) -> "Formatter": ...
+    def __exit__(
+        self,
+        exc_type: Optional[Type[BaseException]],
+        exc_value: Optional[BaseException],
+        traceback: Optional[TracebackType],
+    ) -> None: ...
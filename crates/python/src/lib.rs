@@ -0,0 +1,141 @@
+//! Python bindings over the dprint engine for pipelines that would otherwise
+//! run the CLI in a subprocess. See `dprint.pyi` for the Python API.
+//!
+//! The engine is not `Send`, so a formatter may only be used on the thread
+//! that created it.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use dprint_engine::Engine;
+use dprint_engine::EngineOptions;
+use dprint_engine::Environment;
+use dprint_engine::LogLevel;
+use dprint_engine::ProcessPluginTrustPolicy;
+use dprint_engine::RealEnvironment;
+use dprint_engine::RealEnvironmentOptions;
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::PyAny;
+
+create_exception!(dprint, DprintError, PyException);
+
+struct FormatterInner {
+  runtime: tokio::runtime::Runtime,
+  engine: Engine<RealEnvironment>,
+}
+
+/// Formats files with the plugins of a configuration file.
+#[pyclass(unsendable, module = "dprint")]
+struct Formatter {
+  inner: Option<FormatterInner>,
+}
+
+#[pymethods]
+impl Formatter {
+  #[new]
+  #[pyo3(signature = (config=None, plugins=None, trust_process_plugins=false))]
+  fn new(config: Option<String>, plugins: Option<Vec<String>>, trust_process_plugins: bool) -> PyResult<Self> {
+    let process_plugin_trust = match trust_process_plugins {
+      true => ProcessPluginTrustPolicy::Trust,
+      false => ProcessPluginTrustPolicy::Deny,
+    };
+    let inner = create_inner(
+      EngineOptions {
+        config,
+        plugins: plugins.unwrap_or_default(),
+      },
+      process_plugin_trust,
+    )
+    .map_err(to_py_err)?;
+    Ok(Formatter { inner: Some(inner) })
+  }
+
+  #[getter]
+  fn config_file_path(&self) -> PyResult<Option<PathBuf>> {
+    Ok(self.inner()?.engine.config_file_path())
+  }
+
+  fn can_format(&self, file_path: PathBuf) -> PyResult<bool> {
+    Ok(self.inner()?.engine.can_format(file_path))
+  }
+
+  fn format_text(&self, file_path: PathBuf, file_text: String) -> PyResult<String> {
+    let inner = self.inner()?;
+    let result = inner.runtime.block_on(inner.engine.format_file(file_path, file_text.clone().into_bytes()));
+    match result.map_err(to_py_err)? {
+      Some(bytes) => String::from_utf8(bytes).map_err(|err| to_py_err(err.into())),
+      None => Ok(file_text),
+    }
+  }
+
+  fn format_file(&self, file_path: PathBuf) -> PyResult<bool> {
+    let inner = self.inner()?;
+    inner.runtime.block_on(format_file(&inner.engine, file_path)).map_err(to_py_err)
+  }
+
+  fn close(&mut self) {
+    if let Some(FormatterInner { runtime, engine }) = self.inner.take() {
+      runtime.block_on(engine.shutdown());
+    }
+  }
+
+  fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+    slf
+  }
+
+  fn __exit__(&mut self, _exc_type: &Bound<'_, PyAny>, _exc_value: &Bound<'_, PyAny>, _traceback: &Bound<'_, PyAny>) {
+    self.close();
+  }
+}
+
+impl Formatter {
+  fn inner(&self) -> PyResult<&FormatterInner> {
+    self.inner.as_ref().ok_or_else(|| DprintError::new_err("The formatter was closed."))
+  }
+}
+
+impl Drop for Formatter {
+  fn drop(&mut self) {
+    self.close();
+  }
+}
+
+fn create_inner(options: EngineOptions, process_plugin_trust: ProcessPluginTrustPolicy) -> Result<FormatterInner> {
+  let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build()?;
+  let environment = RealEnvironment::new(RealEnvironmentOptions {
+    log_level: LogLevel::Warn,
+    is_stdout_machine_readable: false,
+    offline: false,
+    process_plugin_trust,
+  })?;
+  let engine = runtime.block_on(Engine::new(environment, options))?;
+  Ok(FormatterInner { runtime, engine })
+}
+
+async fn format_file(engine: &Engine<RealEnvironment>, file_path: PathBuf) -> Result<bool> {
+  if !engine.can_format(&file_path) {
+    return Ok(false);
+  }
+  let environment = engine.environment();
+  let file_bytes = environment.read_file_bytes(&file_path)?;
+  match engine.format_file(&file_path, file_bytes).await? {
+    Some(bytes) => {
+      environment.atomic_write_file_bytes(&file_path, &bytes)?;
+      Ok(true)
+    }
+    None => Ok(false),
+  }
+}
+
+fn to_py_err(err: anyhow::Error) -> PyErr {
+  DprintError::new_err(format!("{:#}", err))
+}
+
+#[pymodule]
+fn dprint(m: &Bound<'_, PyModule>) -> PyResult<()> {
+  m.add_class::<Formatter>()?;
+  m.add("DprintError", m.py().get_type_bound::<DprintError>())?;
+  Ok(())
+}
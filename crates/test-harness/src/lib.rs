@@ -0,0 +1,31 @@
+//! Runs the dprint CLI against an in-memory file system.
+//!
+//! ```ignore
+//! use dprint_test_harness::*;
+//!
+//! let environment = TestEnvironmentBuilder::new()
+//!   .add_remote_file_bytes("https://plugins.dprint.dev/my-plugin.wasm", MY_PLUGIN_BYTES.to_vec())
+//!   .with_default_config(|config| {
+//!     config.add_plugin("https://plugins.dprint.dev/my-plugin.wasm");
+//!   })
+//!   .write_file("/file.txt", "text")
+//!   .build();
+//! run_test_cli(vec!["fmt"], &environment).unwrap();
+//! assert_eq!(environment.read_file("/file.txt").unwrap(), "formatted text");
+//! ```
+
+pub use dprint::test_harness::*;
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_run_cli() {
+    let environment = TestEnvironmentBuilder::new().write_file("/file.txt", "text").build();
+    let err = run_test_cli(vec!["output-file-paths"], &environment).unwrap_err();
+    err.assert_exit_code(11);
+    assert_eq!(environment.read_file("/file.txt").unwrap(), "text");
+    environment.clear_logs();
+  }
+}
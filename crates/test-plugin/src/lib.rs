@@ -49,10 +49,7 @@ impl SyncPluginHandler<Configuration> for TestWasmPlugin {
         |value, _index, diagnostics| match value {
           ConfigKeyValue::String(value) => Some(value),
           _ => {
-            diagnostics.push(ConfigurationDiagnostic {
-              property_name: key.to_string(),
-              message: "Expected only string values.".to_string(),
-            });
+            diagnostics.push(ConfigurationDiagnostic::error(key, "Expected only string values."));
             None
           }
         },
@@ -85,6 +82,7 @@ impl SyncPluginHandler<Configuration> for TestWasmPlugin {
       help_url: "https://dprint.dev/plugins/test".to_string(),
       config_schema_url: "https://plugins.dprint.dev/test/schema.json".to_string(),
       update_url: Some("https://plugins.dprint.dev/dprint/test-plugin/latest.json".to_string()),
+      global_options: None,
     }
   }
 
@@ -72,6 +72,7 @@ impl AsyncPluginHandler for TestProcessPluginHandler {
       help_url: "https://dprint.dev/plugins/test-process".to_string(),
       config_schema_url: "".to_string(),
       update_url: Some("https://plugins.dprint.dev/dprint/test-process-plugin/latest.json".to_string()),
+      global_options: None,
     }
   }
 
@@ -87,10 +88,7 @@ impl AsyncPluginHandler for TestProcessPluginHandler {
         |value, _index, diagnostics| match value {
           ConfigKeyValue::String(value) => Some(value),
           _ => {
-            diagnostics.push(ConfigurationDiagnostic {
-              property_name: key.to_string(),
-              message: "Expected only string values.".to_string(),
-            });
+            diagnostics.push(ConfigurationDiagnostic::error(key, "Expected only string values."));
             None
           }
         },
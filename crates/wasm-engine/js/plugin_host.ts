@@ -0,0 +1,174 @@
+/**
+ * Host shim that instantiates dprint Wasm plugins (schema version 4) with the
+ * browser's WebAssembly API so they can be added to the engine.
+ */
+
+export interface FormatRequest {
+  filePath: string;
+  fileText: string;
+  overrideConfig: Record<string, unknown>;
+}
+
+/** Formats embedded code for a plugin, returning `undefined` when it didn't change. */
+export type HostFormat = (filePath: string, fileText: string, overrideConfig: Record<string, unknown>) => string | undefined;
+
+export interface Plugin {
+  getPluginInfo(): object;
+  setConfig(globalConfig: object, pluginConfig: object): void;
+  getConfigDiagnostics(): object[];
+  getFileMatchingInfo(): object;
+  formatText(request: FormatRequest): string | undefined;
+}
+
+interface PluginExports {
+  memory: WebAssembly.Memory;
+  dprint_plugin_version_4(): number;
+  get_shared_bytes_ptr(): number;
+  clear_shared_bytes(capacity: number): number;
+  get_plugin_info(): number;
+  register_config(configId: number): void;
+  get_config_diagnostics(configId: number): number;
+  get_config_file_matching(configId: number): number;
+  set_file_path(): void;
+  set_override_config(): void;
+  format(configId: number): number;
+  get_formatted_text(): number;
+  get_error_text(): number;
+}
+
+const encoder = new TextEncoder();
+const decoder = new TextDecoder();
+
+/**
+ * Instantiates a plugin. Provide the engine's `hostFormat` so the plugin
+ * can format embedded code with the other plugins.
+ */
+export async function createPlugin(wasmBytes: BufferSource, hostFormat: HostFormat): Promise<Plugin> {
+  let exports: PluginExports | undefined;
+  let sharedBytes = new Uint8Array(0);
+  let formattedText = "";
+  let errorText = "";
+
+  const getExports = () => exports!;
+  const memoryBytes = () => new Uint8Array(getExports().memory.buffer);
+  const readString = (ptr: number, len: number) => decoder.decode(memoryBytes().slice(ptr, ptr + len));
+
+  const imports: WebAssembly.Imports = {
+    env: {
+      fd_write(fd: number, iovsPtr: number, iovsLen: number, nwrittenPtr: number) {
+        if (fd !== 1 && fd !== 2) {
+          return 1;
+        }
+        const view = new DataView(getExports().memory.buffer);
+        let text = "";
+        let totalWritten = 0;
+        for (let i = 0; i < iovsLen; i++) {
+          const bufPtr = view.getUint32(iovsPtr + i * 8, true);
+          const bufLen = view.getUint32(iovsPtr + i * 8 + 4, true);
+          text += readString(bufPtr, bufLen);
+          totalWritten += bufLen;
+        }
+        console.error(text);
+        view.setUint32(nwrittenPtr, totalWritten, true);
+        return 0;
+      },
+    },
+    dprint: {
+      host_write_buffer(ptr: number) {
+        memoryBytes().set(sharedBytes, ptr);
+      },
+      host_format(
+        filePathPtr: number,
+        filePathLen: number,
+        _rangeStart: number,
+        _rangeEnd: number,
+        overrideConfigPtr: number,
+        overrideConfigLen: number,
+        fileBytesPtr: number,
+        fileBytesLen: number,
+      ) {
+        const filePath = readString(filePathPtr, filePathLen);
+        const overrideConfig = overrideConfigLen === 0 ? {} : JSON.parse(readString(overrideConfigPtr, overrideConfigLen));
+        const fileText = readString(fileBytesPtr, fileBytesLen);
+        try {
+          const result = hostFormat(filePath, fileText, overrideConfig);
+          if (result == null) {
+            return 0; // no change
+          }
+          formattedText = result;
+          return 1; // change
+        } catch (err: any) {
+          errorText = err?.message ?? String(err);
+          return 2; // error
+        }
+      },
+      host_get_formatted_text() {
+        sharedBytes = encoder.encode(formattedText);
+        formattedText = "";
+        return sharedBytes.length;
+      },
+      host_get_error_text() {
+        sharedBytes = encoder.encode(errorText);
+        errorText = "";
+        return sharedBytes.length;
+      },
+      host_has_cancelled() {
+        return 0;
+      },
+    },
+  };
+
+  const { instance } = await WebAssembly.instantiate(wasmBytes, imports);
+  exports = instance.exports as unknown as PluginExports;
+  if (typeof exports.dprint_plugin_version_4 !== "function") {
+    throw new Error("Only plugins with schema version 4 are supported.");
+  }
+
+  let configId = 0;
+
+  function sendString(text: string) {
+    const bytes = encoder.encode(text);
+    const ptr = getExports().clear_shared_bytes(bytes.length);
+    memoryBytes().set(bytes, ptr);
+  }
+
+  function receiveString(len: number) {
+    return readString(getExports().get_shared_bytes_ptr(), len);
+  }
+
+  return {
+    getPluginInfo() {
+      return JSON.parse(receiveString(getExports().get_plugin_info()));
+    },
+    setConfig(globalConfig, pluginConfig) {
+      configId++;
+      sendString(JSON.stringify({ plugin: pluginConfig, global: globalConfig }));
+      getExports().register_config(configId);
+    },
+    getConfigDiagnostics() {
+      return JSON.parse(receiveString(getExports().get_config_diagnostics(configId)));
+    },
+    getFileMatchingInfo() {
+      return JSON.parse(receiveString(getExports().get_config_file_matching(configId)));
+    },
+    formatText(request) {
+      if (Object.keys(request.overrideConfig).length > 0) {
+        sendString(JSON.stringify(request.overrideConfig));
+        getExports().set_override_config();
+      }
+      sendString(request.filePath);
+      getExports().set_file_path();
+      sendString(request.fileText);
+      switch (getExports().format(configId)) {
+        case 0:
+          return undefined;
+        case 1:
+          return receiveString(getExports().get_formatted_text());
+        case 2:
+          throw new Error(receiveString(getExports().get_error_text()));
+        default:
+          throw new Error("Unexpected response from plugin.");
+      }
+    },
+  };
+}
@@ -0,0 +1,325 @@
+use std::path::Path;
+
+use anyhow::bail;
+use anyhow::Result;
+use dprint_core::configuration::get_unknown_property_diagnostics;
+use dprint_core::configuration::resolve_global_config;
+use dprint_core::configuration::ConfigKeyMap;
+use dprint_core::configuration::ConfigKeyValue;
+use dprint_core::configuration::ConfigurationDiagnostic;
+use dprint_core::configuration::GlobalConfiguration;
+use dprint_core::plugins::FileMatchingInfo;
+use dprint_core::plugins::PluginInfo;
+use serde::Serialize;
+
+/// Configuration properties only used by the CLI, which are ignored.
+const CLI_PROPERTY_NAMES: &[&str] = &["$schema", "excludes", "extends", "includes", "incremental", "plugins"];
+
+/// A plugin instantiated by the host.
+///
+/// In the browser, this is a Wasm plugin instantiated by the JavaScript
+/// shim, which sends the plugin's host format requests to the engine.
+pub trait Plugin {
+  fn info(&self) -> Result<PluginInfo>;
+  fn set_config(&self, global_config: &GlobalConfiguration, plugin_config: &ConfigKeyMap) -> Result<()>;
+  fn config_diagnostics(&self) -> Result<Vec<ConfigurationDiagnostic>>;
+  fn file_matching_info(&self) -> Result<FileMatchingInfo>;
+  /// Formats the text, returning `None` when it didn't change.
+  fn format_text(&self, request: &FormatRequest) -> Result<Option<String>>;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FormatRequest<'a> {
+  pub file_path: &'a str,
+  pub file_text: &'a str,
+  pub override_config: &'a ConfigKeyMap,
+}
+
+/// A configuration diagnostic along with the plugin it's for, which
+/// is `None` for diagnostics about the global configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineDiagnostic {
+  pub plugin_name: Option<String>,
+  #[serde(flatten)]
+  pub diagnostic: ConfigurationDiagnostic,
+}
+
+struct EnginePlugin<TPlugin: Plugin> {
+  info: PluginInfo,
+  file_matching: FileMatchingInfo,
+  has_config_errors: bool,
+  plugin: TPlugin,
+}
+
+/// Formats text with plugins based on a configuration object, without
+/// any file system or network access.
+pub struct Engine<TPlugin: Plugin> {
+  config: ConfigKeyMap,
+  plugins: Vec<EnginePlugin<TPlugin>>,
+}
+
+impl<TPlugin: Plugin> Default for Engine<TPlugin> {
+  fn default() -> Self {
+    Self {
+      config: Default::default(),
+      plugins: Default::default(),
+    }
+  }
+}
+
+impl<TPlugin: Plugin> Engine<TPlugin> {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds a plugin, which takes precedence over the plugins added after it
+  /// when they format the same files.
+  pub fn add_plugin(&mut self, plugin: TPlugin) -> Result<Vec<EngineDiagnostic>> {
+    let info = plugin.info()?;
+    if self.plugins.iter().any(|p| p.info.config_key == info.config_key) {
+      bail!("A plugin with the configuration key '{}' was already added.", info.config_key);
+    }
+    self.plugins.push(EnginePlugin {
+      info,
+      file_matching: FileMatchingInfo {
+        file_extensions: Vec::new(),
+        file_names: Vec::new(),
+      },
+      has_config_errors: false,
+      plugin,
+    });
+    self.configure_plugins()
+  }
+
+  pub fn plugins_info(&self) -> Vec<&PluginInfo> {
+    self.plugins.iter().map(|p| &p.info).collect()
+  }
+
+  /// Sets the configuration (the object of a configuration file) and
+  /// returns its diagnostics.
+  pub fn set_config(&mut self, config: ConfigKeyMap) -> Result<Vec<EngineDiagnostic>> {
+    self.config = config;
+    self.configure_plugins()
+  }
+
+  /// Gets if a plugin formats the file.
+  pub fn can_format(&self, file_path: &str) -> bool {
+    self.get_plugin(file_path).is_some()
+  }
+
+  /// Formats the text with the plugin associated with the file path,
+  /// returning `None` when it didn't change or no plugin formats it.
+  pub fn format_text(&self, file_path: &str, file_text: &str) -> Result<Option<String>> {
+    self.host_format(&FormatRequest {
+      file_path,
+      file_text,
+      override_config: &Default::default(),
+    })
+  }
+
+  /// Formats the text of a plugin's host format request (ex. a code block in a markdown file).
+  pub fn host_format(&self, request: &FormatRequest) -> Result<Option<String>> {
+    let Some(plugin) = self.get_plugin(request.file_path) else {
+      return Ok(None);
+    };
+    if plugin.has_config_errors {
+      bail!("Cannot format with {} until its configuration diagnostics are fixed.", plugin.info.name);
+    }
+    let result = plugin.plugin.format_text(request)?;
+    // treat formatting to the same text as no change
+    Ok(result.filter(|text| text != request.file_text))
+  }
+
+  fn configure_plugins(&mut self) -> Result<Vec<EngineDiagnostic>> {
+    let mut config = self.config.clone();
+    let mut diagnostics = Vec::new();
+    for name in CLI_PROPERTY_NAMES {
+      config.shift_remove(*name);
+    }
+    let plugin_configs = self
+      .plugins
+      .iter()
+      .map(|plugin| match config.shift_remove(&plugin.info.config_key) {
+        Some(ConfigKeyValue::Object(plugin_config)) => plugin_config,
+        Some(_) => {
+          diagnostics.push(EngineDiagnostic {
+            plugin_name: None,
+            diagnostic: ConfigurationDiagnostic::error(&plugin.info.config_key, "Expected an object."),
+          });
+          ConfigKeyMap::new()
+        }
+        None => ConfigKeyMap::new(),
+      })
+      .collect::<Vec<_>>();
+    let global_config = resolve_global_config(&mut config);
+    diagnostics.extend(
+      global_config
+        .diagnostics
+        .into_iter()
+        .chain(get_unknown_property_diagnostics(config))
+        .map(|diagnostic| EngineDiagnostic { plugin_name: None, diagnostic }),
+    );
+
+    for (plugin, plugin_config) in self.plugins.iter_mut().zip(plugin_configs) {
+      plugin.plugin.set_config(&global_config.config, &plugin_config)?;
+      let plugin_diagnostics = plugin.plugin.config_diagnostics()?;
+      plugin.has_config_errors = plugin_diagnostics.iter().any(|d| d.severity.is_error());
+      plugin.file_matching = plugin.plugin.file_matching_info()?;
+      diagnostics.extend(plugin_diagnostics.into_iter().map(|diagnostic| EngineDiagnostic {
+        plugin_name: Some(plugin.info.name.clone()),
+        diagnostic,
+      }));
+    }
+    Ok(diagnostics)
+  }
+
+  fn get_plugin(&self, file_path: &str) -> Option<&EnginePlugin<TPlugin>> {
+    let file_path = Path::new(file_path);
+    if let Some(file_name) = file_path.file_name().map(|name| name.to_string_lossy().to_lowercase()) {
+      let plugin = self
+        .plugins
+        .iter()
+        .find(|plugin| plugin.file_matching.file_names.iter().any(|name| name.to_lowercase() == file_name));
+      if plugin.is_some() {
+        return plugin;
+      }
+    }
+    let ext = file_path.extension()?.to_string_lossy().to_lowercase();
+    self
+      .plugins
+      .iter()
+      .find(|plugin| plugin.file_matching.file_extensions.iter().any(|e| e.to_lowercase() == ext))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::cell::RefCell;
+
+  use dprint_core::configuration::get_value;
+
+  use super::*;
+
+  struct TestPlugin {
+    config_key: &'static str,
+    extension: &'static str,
+    config: RefCell<(GlobalConfiguration, ConfigKeyMap)>,
+  }
+
+  impl TestPlugin {
+    fn new(config_key: &'static str, extension: &'static str) -> Self {
+      Self {
+        config_key,
+        extension,
+        config: Default::default(),
+      }
+    }
+  }
+
+  impl Plugin for TestPlugin {
+    fn info(&self) -> Result<PluginInfo> {
+      Ok(PluginInfo {
+        name: format!("{}-plugin", self.config_key),
+        version: "0.1.0".to_string(),
+        config_key: self.config_key.to_string(),
+        help_url: String::new(),
+        config_schema_url: String::new(),
+        update_url: None,
+        global_options: None,
+      })
+    }
+
+    fn set_config(&self, global_config: &GlobalConfiguration, plugin_config: &ConfigKeyMap) -> Result<()> {
+      *self.config.borrow_mut() = (global_config.clone(), plugin_config.clone());
+      Ok(())
+    }
+
+    fn config_diagnostics(&self) -> Result<Vec<ConfigurationDiagnostic>> {
+      let mut config = self.config.borrow().1.clone();
+      let mut diagnostics = Vec::new();
+      get_value(&mut config, "suffix", String::new(), &mut diagnostics);
+      diagnostics.extend(get_unknown_property_diagnostics(config));
+      Ok(diagnostics)
+    }
+
+    fn file_matching_info(&self) -> Result<FileMatchingInfo> {
+      Ok(FileMatchingInfo {
+        file_extensions: vec![self.extension.to_string()],
+        file_names: Vec::new(),
+      })
+    }
+
+    fn format_text(&self, request: &FormatRequest) -> Result<Option<String>> {
+      let (global_config, plugin_config) = &*self.config.borrow();
+      let suffix = request
+        .override_config
+        .get("suffix")
+        .or_else(|| plugin_config.get("suffix"))
+        .and_then(|value| value.as_string().cloned())
+        .unwrap_or_default();
+      let line_width = global_config.line_width.unwrap_or(120);
+      Ok(Some(format!("{}{}_{}", request.file_text, suffix, line_width)))
+    }
+  }
+
+  fn config(json: &str) -> ConfigKeyMap {
+    serde_json::from_str(json).unwrap()
+  }
+
+  #[test]
+  fn should_format_with_plugin_for_file() {
+    let mut engine = Engine::new();
+    assert!(engine.add_plugin(TestPlugin::new("a", "a")).unwrap().is_empty());
+    assert!(engine.add_plugin(TestPlugin::new("b", "b")).unwrap().is_empty());
+    let diagnostics = engine
+      .set_config(config(
+        r#"{ "lineWidth": 80, "plugins": [], "a": { "suffix": "_a" }, "b": { "suffix": "_b" } }"#,
+      ))
+      .unwrap();
+    assert!(diagnostics.is_empty());
+    assert!(engine.can_format("/file.A"));
+    assert!(!engine.can_format("/file.c"));
+    assert_eq!(engine.format_text("/file.a", "text").unwrap(), Some("text_a_80".to_string()));
+    assert_eq!(engine.format_text("/file.b", "text").unwrap(), Some("text_b_80".to_string()));
+    assert_eq!(engine.format_text("/file.c", "text").unwrap(), None);
+    assert_eq!(
+      engine
+        .host_format(&FormatRequest {
+          file_path: "/file.a",
+          file_text: "text",
+          override_config: &config(r#"{ "suffix": "_override" }"#),
+        })
+        .unwrap(),
+      Some("text_override_80".to_string())
+    );
+  }
+
+  #[test]
+  fn should_surface_config_diagnostics() {
+    let mut engine = Engine::new();
+    engine.add_plugin(TestPlugin::new("a", "a")).unwrap();
+    let diagnostics = engine
+      .set_config(config(r#"{ "lineWidth": "wide", "unknown": 1, "a": { "other": 5 } }"#))
+      .unwrap();
+    let diagnostics = diagnostics.into_iter().map(|d| (d.plugin_name, d.diagnostic.property_name)).collect::<Vec<_>>();
+    assert_eq!(
+      diagnostics,
+      vec![
+        (None, "lineWidth".to_string()),
+        (None, "unknown".to_string()),
+        (Some("a-plugin".to_string()), "other".to_string()),
+      ]
+    );
+    let err = engine.format_text("/file.a", "text").err().unwrap();
+    assert_eq!(err.to_string(), "Cannot format with a-plugin until its configuration diagnostics are fixed.");
+  }
+
+  #[test]
+  fn should_error_adding_plugin_with_same_config_key() {
+    let mut engine = Engine::new();
+    engine.add_plugin(TestPlugin::new("a", "a")).unwrap();
+    let err = engine.add_plugin(TestPlugin::new("a", "b")).err().unwrap();
+    assert_eq!(err.to_string(), "A plugin with the configuration key 'a' was already added.");
+  }
+}
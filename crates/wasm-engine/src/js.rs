@@ -0,0 +1,168 @@
+use anyhow::anyhow;
+use anyhow::Result;
+use dprint_core::configuration::ConfigKeyMap;
+use dprint_core::configuration::ConfigurationDiagnostic;
+use dprint_core::configuration::GlobalConfiguration;
+use dprint_core::plugins::FileMatchingInfo;
+use dprint_core::plugins::PluginInfo;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::Engine;
+use crate::FormatRequest;
+use crate::Plugin;
+
+#[wasm_bindgen(typescript_custom_section)]
+const PLUGIN_TYPE: &'static str = r#"
+/** A plugin instantiated by `createPlugin` in the host shim. */
+export interface Plugin {
+  getPluginInfo(): object;
+  setConfig(globalConfig: object, pluginConfig: object): void;
+  getConfigDiagnostics(): object[];
+  getFileMatchingInfo(): object;
+  formatText(request: { filePath: string; fileText: string; overrideConfig: object }): string | undefined;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+  #[wasm_bindgen(typescript_type = "Plugin")]
+  pub type JsPlugin;
+
+  #[wasm_bindgen(method, catch, js_name = getPluginInfo)]
+  fn get_plugin_info(this: &JsPlugin) -> Result<JsValue, JsValue>;
+
+  #[wasm_bindgen(method, catch, js_name = setConfig)]
+  fn set_config(this: &JsPlugin, global_config: JsValue, plugin_config: JsValue) -> Result<(), JsValue>;
+
+  #[wasm_bindgen(method, catch, js_name = getConfigDiagnostics)]
+  fn get_config_diagnostics(this: &JsPlugin) -> Result<JsValue, JsValue>;
+
+  #[wasm_bindgen(method, catch, js_name = getFileMatchingInfo)]
+  fn get_file_matching_info(this: &JsPlugin) -> Result<JsValue, JsValue>;
+
+  #[wasm_bindgen(method, catch, js_name = formatText)]
+  fn format_text(this: &JsPlugin, request: JsValue) -> Result<Option<String>, JsValue>;
+}
+
+impl Plugin for JsPlugin {
+  fn info(&self) -> Result<PluginInfo> {
+    from_js(self.get_plugin_info().map_err(js_err)?)
+  }
+
+  fn set_config(&self, global_config: &GlobalConfiguration, plugin_config: &ConfigKeyMap) -> Result<()> {
+    JsPlugin::set_config(self, to_js(global_config)?, to_js(plugin_config)?).map_err(js_err)
+  }
+
+  fn config_diagnostics(&self) -> Result<Vec<ConfigurationDiagnostic>> {
+    from_js(self.get_config_diagnostics().map_err(js_err)?)
+  }
+
+  fn file_matching_info(&self) -> Result<FileMatchingInfo> {
+    from_js(self.get_file_matching_info().map_err(js_err)?)
+  }
+
+  fn format_text(&self, request: &FormatRequest) -> Result<Option<String>> {
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct JsFormatRequest<'a> {
+      file_path: &'a str,
+      file_text: &'a str,
+      override_config: &'a ConfigKeyMap,
+    }
+
+    let request = to_js(&JsFormatRequest {
+      file_path: request.file_path,
+      file_text: request.file_text,
+      override_config: request.override_config,
+    })?;
+    JsPlugin::format_text(self, request).map_err(js_err)
+  }
+}
+
+/// Formats text with plugins in the browser.
+#[wasm_bindgen(js_name = Engine)]
+pub struct WasmEngine {
+  inner: Engine<JsPlugin>,
+}
+
+#[wasm_bindgen(js_class = Engine)]
+impl WasmEngine {
+  #[wasm_bindgen(constructor)]
+  #[allow(clippy::new_without_default)]
+  pub fn new() -> WasmEngine {
+    WasmEngine { inner: Engine::new() }
+  }
+
+  /// Adds a plugin and returns the configuration diagnostics.
+  #[wasm_bindgen(js_name = addPlugin)]
+  pub fn add_plugin(&mut self, plugin: JsPlugin) -> Result<JsValue, JsError> {
+    let diagnostics = self.inner.add_plugin(plugin).map_err(to_js_error)?;
+    to_js(&diagnostics).map_err(to_js_error)
+  }
+
+  #[wasm_bindgen(js_name = getPluginsInfo)]
+  pub fn plugins_info(&self) -> Result<JsValue, JsError> {
+    to_js(&self.inner.plugins_info()).map_err(to_js_error)
+  }
+
+  /// Sets the configuration (the object of a configuration file) and
+  /// returns its diagnostics.
+  #[wasm_bindgen(js_name = setConfig)]
+  pub fn set_config(&mut self, config: JsValue) -> Result<JsValue, JsError> {
+    let config = from_js(config).map_err(to_js_error)?;
+    let diagnostics = self.inner.set_config(config).map_err(to_js_error)?;
+    to_js(&diagnostics).map_err(to_js_error)
+  }
+
+  #[wasm_bindgen(js_name = canFormat)]
+  pub fn can_format(&self, file_path: &str) -> bool {
+    self.inner.can_format(file_path)
+  }
+
+  /// Formats the text, returning `undefined` when it didn't change
+  /// or no plugin formats it.
+  #[wasm_bindgen(js_name = formatText)]
+  pub fn format_text(&self, file_path: &str, file_text: &str) -> Result<Option<String>, JsError> {
+    self.inner.format_text(file_path, file_text).map_err(to_js_error)
+  }
+
+  /// Formats the text of a plugin's host format request. This should be
+  /// provided to `createPlugin` so plugins can format embedded code.
+  #[wasm_bindgen(js_name = hostFormat)]
+  pub fn host_format(&self, file_path: &str, file_text: &str, override_config: JsValue) -> Result<Option<String>, JsError> {
+    let override_config: ConfigKeyMap = match override_config.is_undefined() || override_config.is_null() {
+      true => Default::default(),
+      false => from_js(override_config).map_err(to_js_error)?,
+    };
+    self
+      .inner
+      .host_format(&FormatRequest {
+        file_path,
+        file_text,
+        override_config: &override_config,
+      })
+      .map_err(to_js_error)
+  }
+}
+
+fn to_js<T: Serialize + ?Sized>(value: &T) -> Result<JsValue> {
+  value
+    .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+    .map_err(|err| anyhow!("{}", err))
+}
+
+fn from_js<T: serde::de::DeserializeOwned>(value: JsValue) -> Result<T> {
+  serde_wasm_bindgen::from_value(value).map_err(|err| anyhow!("{}", err))
+}
+
+fn js_err(value: JsValue) -> anyhow::Error {
+  match value.dyn_ref::<js_sys::Error>() {
+    Some(err) => anyhow!("{}", String::from(err.message())),
+    None => anyhow!("{:?}", value),
+  }
+}
+
+fn to_js_error(err: anyhow::Error) -> JsError {
+  JsError::new(&format!("{:#}", err))
+}
@@ -0,0 +1,12 @@
+//! Formats with dprint's Wasm plugins in the browser (ex. in the playground).
+//!
+//! The plugins are instantiated with the JavaScript host shim in
+//! `js/plugin_host.ts` and added to an [`Engine`], which resolves the
+//! configuration for each plugin and routes format requests (including
+//! the host format requests of plugins) to the plugin for the file.
+
+mod engine;
+#[cfg(target_arch = "wasm32")]
+mod js;
+
+pub use engine::*;